@@ -10,11 +10,29 @@ fn main() {
                     target_os = "windows"
                 ),
                 // HACK: The 3ds is marked as a unix like despite not being one
-                not(target_os = "horizon")
+                not(target_os = "horizon"),
+                feature = "frontend"
             )
         },
         nintendo_3ds: {
-            target_os = "horizon"
+            all(target_os = "horizon", feature = "frontend")
+        },
+        // Hardware accelerated backends the build asked for, which only desktops have
+        vulkan: {
+            all(desktop, feature = "vulkan")
+        },
+        wgpu: {
+            all(desktop, feature = "wgpu")
+        },
+        // Extensions the hot loops are dispatched to. Calling into them is unsafe, which the core build can't have
+        simd_x86: {
+            all(
+                any(target_arch = "x86", target_arch = "x86_64"),
+                not(feature = "core-only")
+            )
+        },
+        simd_aarch64: {
+            all(target_arch = "aarch64", not(feature = "core-only"))
         },
         // Mere speculative at this moment considering the rust port to the psp has not hit std support yet
         sony_psp: {
@@ -0,0 +1,155 @@
+//! Writes that leave either the old or the new file behind, never half of one, even if the process dies midway
+//!
+//! The new contents go to a temporary file next to the target which is synced and then renamed over it
+
+use std::{
+    fmt::Display,
+    fs::{create_dir_all, remove_file, rename, File},
+    io::{BufWriter, Write},
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+static NEXT_TEMPORARY: AtomicU64 = AtomicU64::new(0);
+
+/// Where the previous contents of a file written with [write_atomically_with_backup] are kept
+pub fn backup_path(path: &Path) -> PathBuf {
+    with_suffix(path, ".bak")
+}
+
+/// Replaces the file with whatever the callback writes, the parent directory is created if needed
+pub fn write_atomically<E: From<std::io::Error>>(
+    path: &Path,
+    write: impl FnOnce(&mut BufWriter<File>) -> Result<(), E>,
+) -> Result<(), E> {
+    replace(path, false, write)
+}
+
+/// Like [write_atomically], with the file that was replaced kept around for [read_with_backup]
+pub fn write_atomically_with_backup<E: From<std::io::Error>>(
+    path: &Path,
+    write: impl FnOnce(&mut BufWriter<File>) -> Result<(), E>,
+) -> Result<(), E> {
+    replace(path, true, write)
+}
+
+/// Falls back to the backup if the file is missing or the callback can't make sense of it
+pub fn read_with_backup<T, E: Display>(
+    path: &Path,
+    read: impl Fn(&Path) -> Result<T, E>,
+) -> Result<T, E> {
+    let error = match read(path) {
+        Ok(value) => return Ok(value),
+        Err(error) => error,
+    };
+
+    let backup = backup_path(path);
+    if !backup.is_file() {
+        return Err(error);
+    }
+
+    match read(&backup) {
+        Ok(value) => {
+            tracing::warn!(
+                "Could not read {} ({}), recovered the previous version from {}",
+                path.display(),
+                error,
+                backup.display()
+            );
+
+            Ok(value)
+        }
+        Err(_) => Err(error),
+    }
+}
+
+fn replace<E: From<std::io::Error>>(
+    path: &Path,
+    keep_backup: bool,
+    write: impl FnOnce(&mut BufWriter<File>) -> Result<(), E>,
+) -> Result<(), E> {
+    if let Some(parent) = path.parent() {
+        create_dir_all(parent)?;
+    }
+
+    // Unique so writes of the same file from different threads don't trample each other before the rename
+    let temporary = with_suffix(
+        path,
+        &format!(".{}.tmp", NEXT_TEMPORARY.fetch_add(1, Ordering::Relaxed)),
+    );
+    let result: Result<(), E> = (|| {
+        let mut file = BufWriter::new(File::create(&temporary)?);
+        write(&mut file)?;
+        file.flush()?;
+        file.get_ref().sync_all()?;
+
+        Ok(())
+    })();
+    if let Err(error) = result {
+        let _ = remove_file(&temporary);
+        return Err(error);
+    }
+
+    // Between these the file is only at the backup, which reading falls back to
+    if keep_backup && path.is_file() {
+        rename(path, backup_path(path))?;
+    }
+    rename(&temporary, path)?;
+
+    // The renames themselves are only durable once the directory is synced, which only unix can do
+    #[cfg(unix)]
+    if let Some(parent) = path.parent() {
+        let _ = File::open(parent).and_then(|directory| directory.sync_all());
+    }
+
+    Ok(())
+}
+
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut path = path.as_os_str().to_owned();
+    path.push(suffix);
+
+    path.into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::{read_to_string, write};
+
+    #[test]
+    fn corrupted_files_recover_from_the_backup() {
+        let directory =
+            std::env::temp_dir().join(format!("multiemu_atomic_write_{}", std::process::id()));
+        let path = directory.join("config.ron");
+        let read = |path: &Path| {
+            let contents = read_to_string(path)?;
+            contents
+                .parse::<u32>()
+                .map_err(|error| std::io::Error::new(std::io::ErrorKind::InvalidData, error))
+        };
+
+        for value in ["1", "2"] {
+            write_atomically_with_backup::<std::io::Error>(&path, |file| {
+                file.write_all(value.as_bytes())
+            })
+            .unwrap();
+        }
+        assert_eq!(read_with_backup(&path, read).unwrap(), 2);
+        assert_eq!(std::fs::read_dir(&directory).unwrap().count(), 2);
+
+        // Like a crash halfway through a plain write
+        write(&path, "").unwrap();
+        assert_eq!(read_with_backup(&path, read).unwrap(), 1);
+
+        // A failed write leaves the file alone
+        assert!(write_atomically::<std::io::Error>(&path, |file| {
+            file.write_all(b"3")?;
+            Err(std::io::ErrorKind::Other.into())
+        })
+        .is_err());
+        assert_eq!(read_to_string(&path).unwrap(), "");
+
+        std::fs::remove_dir_all(directory).unwrap();
+    }
+}
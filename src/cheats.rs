@@ -0,0 +1,258 @@
+#[cfg(feature = "frontend")]
+use crate::{
+    atomic_write::{read_with_backup, write_atomically_with_backup},
+    rom::RomId,
+};
+use crate::{
+    component::memory::ReadPatch,
+    rom::{GameSystem, NintendoSystem},
+};
+#[cfg(feature = "frontend")]
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "frontend")]
+use std::{collections::HashMap, error::Error, fs::File, io::BufReader, path::Path};
+use thiserror::Error;
+
+/// Letters of NES Game Genie codes, by the nibble they stand for
+const NES_GAME_GENIE_LETTERS: &str = "APZLGITYEOXUKSVN";
+
+#[cfg(feature = "frontend")]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct Cheat {
+    pub name: String,
+    /// Any number of codes joined by `+`, in any format the system of the game takes
+    pub code: String,
+    pub enabled: bool,
+}
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum CheatError {
+    #[error("{0} is not a code this system takes")]
+    Unrecognized(String),
+}
+
+/// Cheats of every game, kept next to the ROM database
+#[cfg(feature = "frontend")]
+#[derive(Default, Clone, Debug, PartialEq, Eq)]
+pub struct CheatDatabase {
+    pub cheats: HashMap<RomId, Vec<Cheat>>,
+}
+
+#[cfg(feature = "frontend")]
+impl CheatDatabase {
+    /// A missing database is a empty one
+    pub fn load(path: &Path) -> Result<Self, Box<dyn Error>> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let cheats = read_with_backup(path, |path| {
+            Ok::<_, Box<dyn Error>>(rmp_serde::from_read(BufReader::new(File::open(path)?))?)
+        })?;
+
+        Ok(Self { cheats })
+    }
+
+    pub fn store(&self, path: &Path) -> Result<(), Box<dyn Error>> {
+        write_atomically_with_backup(path, |file| {
+            rmp_serde::encode::write_named(file, &self.cheats)?;
+
+            Ok(())
+        })
+    }
+}
+
+/// What the enabled cheats patch, along with the codes that made no sense for the system
+#[cfg(feature = "frontend")]
+pub fn read_patches(cheats: &[Cheat], system: GameSystem) -> (Vec<ReadPatch>, Vec<CheatError>) {
+    let mut read_patches = Vec::new();
+    let mut errors = Vec::new();
+
+    for cheat in cheats.iter().filter(|cheat| cheat.enabled) {
+        match decode(&cheat.code, system) {
+            Ok(patches) => read_patches.extend(patches),
+            Err(error) => errors.push(error),
+        }
+    }
+
+    (read_patches, errors)
+}
+
+/// Turns codes joined by `+` into what they patch
+///
+/// Raw patches of `address:value` or `address:value:compare` in hex work everywhere, on top of the formats of the
+/// cheat devices for the system
+pub fn decode(code: &str, system: GameSystem) -> Result<Vec<ReadPatch>, CheatError> {
+    code.split('+')
+        .map(str::trim)
+        .filter(|code| !code.is_empty())
+        .map(|code| {
+            decode_raw(code)
+                .or_else(|| match system {
+                    GameSystem::Nintendo(NintendoSystem::NintendoEntertainmentSystem) => {
+                        decode_nes_game_genie(code)
+                    }
+                    GameSystem::Nintendo(
+                        NintendoSystem::GameBoy | NintendoSystem::GameBoyColor,
+                    ) => decode_game_boy_game_genie(code)
+                        .or_else(|| decode_game_boy_game_shark(code)),
+                    _ => None,
+                })
+                .ok_or_else(|| CheatError::Unrecognized(code.to_string()))
+        })
+        .collect()
+}
+
+fn decode_raw(code: &str) -> Option<ReadPatch> {
+    let mut parts = code.split(':');
+    let address = usize::from_str_radix(parts.next()?, 16).ok()?;
+    let value = u8::from_str_radix(parts.next()?, 16).ok()?;
+    let compare = parts
+        .next()
+        .map(|compare| u8::from_str_radix(compare, 16))
+        .transpose()
+        .ok()?;
+
+    if parts.next().is_some() {
+        return None;
+    }
+
+    Some(ReadPatch {
+        address,
+        value,
+        compare,
+    })
+}
+
+/// Six letters patch a address of the cartridge, eight also compare, so only the right bank is patched
+fn decode_nes_game_genie(code: &str) -> Option<ReadPatch> {
+    let n: Vec<u16> = code
+        .chars()
+        .map(|letter| {
+            NES_GAME_GENIE_LETTERS
+                .find(letter.to_ascii_uppercase())
+                .map(|nibble| nibble as u16)
+        })
+        .collect::<Option<_>>()?;
+
+    if n.len() != 6 && n.len() != 8 {
+        return None;
+    }
+
+    let address = 0x8000
+        + (((n[3] & 7) << 12)
+            | ((n[5] & 7) << 8)
+            | ((n[4] & 8) << 8)
+            | ((n[2] & 7) << 4)
+            | ((n[1] & 8) << 4)
+            | (n[4] & 7)
+            | (n[3] & 8));
+    let low_bits = ((n[1] & 7) << 4) | ((n[0] & 8) << 4) | (n[0] & 7);
+
+    let (value, compare) = if n.len() == 6 {
+        (low_bits | (n[5] & 8), None)
+    } else {
+        (
+            low_bits | (n[7] & 8),
+            Some(((n[7] & 7) << 4) | ((n[6] & 8) << 4) | (n[6] & 7) | (n[5] & 8)),
+        )
+    };
+
+    Some(ReadPatch {
+        address: address as usize,
+        value: value as u8,
+        compare: compare.map(|compare| compare as u8),
+    })
+}
+
+/// `ABC-DEF` or `ABC-DEF-GHI` in hex, AB is the value, FCDE the address with the top nibble inverted and GI the
+/// scrambled compare value
+fn decode_game_boy_game_genie(code: &str) -> Option<ReadPatch> {
+    let digits: Vec<u8> = code
+        .split('-')
+        .flat_map(str::chars)
+        .map(|digit| digit.to_digit(16).map(|digit| digit as u8))
+        .collect::<Option<_>>()?;
+    let groups = code.split('-').count();
+
+    if !matches!((digits.len(), groups), (6, 2) | (9, 3)) {
+        return None;
+    }
+
+    let address = (((digits[5] ^ 0xf) as usize) << 12)
+        | ((digits[2] as usize) << 8)
+        | ((digits[3] as usize) << 4)
+        | digits[4] as usize;
+    let compare =
+        (digits.len() == 9).then(|| ((digits[6] << 4) | digits[8]).rotate_right(2) ^ 0xba);
+
+    Some(ReadPatch {
+        address,
+        value: (digits[0] << 4) | digits[1],
+        compare,
+    })
+}
+
+/// `01VVAAAA` with the address little endian. Other types pick a RAM bank, which needs more than a read patch
+fn decode_game_boy_game_shark(code: &str) -> Option<ReadPatch> {
+    if code.len() != 8 || !code.starts_with("01") {
+        return None;
+    }
+
+    let value = u8::from_str_radix(code.get(2..4)?, 16).ok()?;
+    let address = u16::from_str_radix(code.get(4..8)?, 16).ok()?.swap_bytes();
+
+    Some(ReadPatch {
+        address: address as usize,
+        value,
+        compare: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NES: GameSystem = GameSystem::Nintendo(NintendoSystem::NintendoEntertainmentSystem);
+    const GAME_BOY: GameSystem = GameSystem::Nintendo(NintendoSystem::GameBoy);
+
+    #[test]
+    fn decodes_codes() {
+        assert_eq!(
+            decode("SXIOPO", NES),
+            Ok(vec![ReadPatch {
+                address: 0x91d9,
+                value: 0xad,
+                compare: None,
+            }])
+        );
+        assert_eq!(
+            decode("01FFE1C0 + C000:05:04", GAME_BOY),
+            Ok(vec![
+                ReadPatch {
+                    address: 0xc0e1,
+                    value: 0xff,
+                    compare: None,
+                },
+                ReadPatch {
+                    address: 0xc000,
+                    value: 0x05,
+                    compare: Some(0x04),
+                }
+            ])
+        );
+        assert_eq!(
+            decode("SXIOPO", GAME_BOY),
+            Err(CheatError::Unrecognized("SXIOPO".to_string()))
+        );
+    }
+
+    #[test]
+    fn game_boy_game_genie() {
+        // Writing 0x3c over 0x39 at 0x4a17
+        let patch = decode_game_boy_game_genie("3CA-17B-04E").unwrap();
+        assert_eq!(patch.address, 0x4a17);
+        assert_eq!(patch.value, 0x3c);
+        assert_eq!(patch.compare, Some(0x39));
+    }
+}
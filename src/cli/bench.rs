@@ -0,0 +1,251 @@
+use crate::{
+    component::{
+        definitions::misc::plain_memory::{PlainMemory, PlainMemoryConfig},
+        memory::MemoryTranslationTable,
+        FromConfig,
+    },
+    config::GlobalConfig,
+    env::{IMPORTED_ROM_DIRECTORY, ROM_DATABASE_PATH},
+    machine::{
+        definitions::construct_machine,
+        executor::{single::SingleThreadedExecutor, Executor},
+    },
+    rom::{GameSystem, RomId, RomManager},
+    runtime::{desktop::display::software::SoftwareState, SoftwareRendering},
+    task::Task,
+};
+use num::ToPrimitive;
+use std::{
+    hint::black_box,
+    ops::{Deref, Range},
+    sync::{Arc, Mutex, RwLock},
+    time::{Duration, Instant},
+};
+
+/// A frame is a 60Hz host frame, so numbers are comparable across systems
+const FRAME_TIME: Duration = Duration::from_micros(16_667);
+/// How many components the memory benchmark maps, machines with a lot of small mappings end up towards the top
+const MEMORY_BENCH_REGION_COUNTS: [usize; 5] = [1, 4, 16, 64, 256];
+/// Size of each mapping in the memory benchmark
+const MEMORY_BENCH_REGION_SIZE: usize = 0x100;
+
+#[derive(Debug, Default)]
+struct TaskTimings {
+    batches: u64,
+    ticks: u64,
+    real_time: Duration,
+}
+
+/// Wraps a task to time every batch it runs
+struct TimedTask {
+    task: Box<dyn Task>,
+    timings: Arc<Mutex<TaskTimings>>,
+}
+
+impl Task for TimedTask {
+    fn tick(&mut self, batch_size: u32, memory_translation_table: &MemoryTranslationTable) {
+        let start = Instant::now();
+        self.task.tick(batch_size, memory_translation_table);
+        let elapsed = start.elapsed();
+
+        let mut timings = self.timings.lock().unwrap();
+        timings.batches += 1;
+        timings.ticks += batch_size as u64;
+        timings.real_time += elapsed;
+    }
+
+    fn save(&mut self) -> rmpv::Value {
+        self.task.save()
+    }
+
+    fn load(&mut self, state: rmpv::Value) {
+        self.task.load(state)
+    }
+
+    fn set_breakpoints_enabled(&mut self, enabled: bool) {
+        self.task.set_breakpoints_enabled(enabled)
+    }
+}
+
+/// Runs a game headlessly as fast as possible for a number of frames and prints how fast it went
+pub fn run(
+    user_specified_roms: Vec<RomId>,
+    forced_system: Option<GameSystem>,
+    frames: u32,
+    global_config: Arc<RwLock<GlobalConfig>>,
+) {
+    let mut rom_manager = RomManager::default();
+
+    rom_manager
+        .load_rom_info(ROM_DATABASE_PATH.deref())
+        .unwrap();
+    rom_manager
+        .load_rom_paths(IMPORTED_ROM_DIRECTORY.deref())
+        .unwrap();
+
+    for rom_id in &user_specified_roms {
+        if !rom_manager.rom_paths.contains_key(rom_id) {
+            tracing::error!("ROM {} not found", rom_id);
+            return;
+        }
+    }
+
+    let rom_manager = Arc::new(rom_manager);
+    let game_system = forced_system
+        .unwrap_or_else(|| rom_manager.rom_information[&user_specified_roms[0]].system);
+
+    let mut rendering_state = SoftwareState::headless(global_config);
+    let setup_start = Instant::now();
+    let machine =
+        match construct_machine::<SoftwareRendering>(game_system, rom_manager, user_specified_roms)
+        {
+            Ok(machine) => machine,
+            Err(error) => {
+                tracing::error!("{}", error);
+                return;
+            }
+        };
+    machine.initialize_displays(&mut rendering_state);
+    let setup_time = setup_start.elapsed();
+
+    let mut task_timings = Vec::new();
+    let tasks = machine
+        .tasks
+        .into_iter()
+        .map(|(name, tick_rate, task)| {
+            let timings = Arc::new(Mutex::new(TaskTimings::default()));
+            task_timings.push((name, tick_rate, timings.clone()));
+
+            (
+                name,
+                tick_rate,
+                Box::new(TimedTask { task, timings }) as Box<dyn Task>,
+            )
+        })
+        .collect();
+    let mut executor = SingleThreadedExecutor::new(tasks, machine.memory_translation_table);
+
+    let mut slowest_frame = Duration::ZERO;
+    let start = Instant::now();
+    for _ in 0..frames {
+        let frame_start = Instant::now();
+        executor.run_unthrottled(FRAME_TIME);
+        slowest_frame = slowest_frame.max(frame_start.elapsed());
+    }
+    let real_time = start.elapsed();
+    let machine_time = FRAME_TIME * frames;
+
+    println!("MultiEMU v{} benchmark", env!("CARGO_PKG_VERSION"));
+    println!("System: {}", game_system);
+    println!("Machine setup: {:?}", setup_time);
+    println!(
+        "Ran {} frames ({:?} of machine time) in {:?}",
+        frames, machine_time, real_time
+    );
+    println!(
+        "Speed: {:.1}% of real time, {:.1} frames per second, slowest frame {:?}",
+        machine_time.as_secs_f64() / real_time.as_secs_f64() * 100.0,
+        frames as f64 / real_time.as_secs_f64(),
+        slowest_frame
+    );
+    println!();
+    println!(
+        "{:<24} {:>12} {:>12} {:>12} {:>14} {:>8} {:>10}",
+        "Task", "Rate (Hz)", "Ticks", "Batches", "Time", "Share", "ns/tick"
+    );
+
+    for (name, tick_rate, timings) in task_timings {
+        let timings = timings.lock().unwrap();
+
+        println!(
+            "{:<24} {:>12.0} {:>12} {:>12} {:>14?} {:>7.1}% {:>10.1}",
+            name,
+            tick_rate.to_f64().unwrap(),
+            timings.ticks,
+            timings.batches,
+            timings.real_time,
+            timings.real_time.as_secs_f64() / real_time.as_secs_f64() * 100.0,
+            timings.real_time.as_nanos() as f64 / timings.ticks.max(1) as f64
+        );
+    }
+}
+
+/// Times reads through the memory translation table with more and more components mapped, next to the linear scan
+/// lookups used to be, to show the cost of a access stays flat as machines get more mappings
+pub fn run_memory(accesses: u32) {
+    println!("MultiEMU v{} memory benchmark", env!("CARGO_PKG_VERSION"));
+    println!("{} random single byte reads per row", accesses);
+    println!();
+    println!(
+        "{:>8} {:>16} {:>16} {:>16}",
+        "Regions", "Lookup ns", "Linear scan ns", "Read ns"
+    );
+
+    let rom_manager = Arc::new(RomManager::default());
+
+    for region_count in MEMORY_BENCH_REGION_COUNTS {
+        let mut memory_translation_table = MemoryTranslationTable::default();
+        let ranges: Vec<Range<usize>> = (0..region_count)
+            .map(|index| index * MEMORY_BENCH_REGION_SIZE..(index + 1) * MEMORY_BENCH_REGION_SIZE)
+            .collect();
+
+        for range in &ranges {
+            memory_translation_table.insert(
+                range.clone(),
+                Arc::new(Mutex::new(PlainMemory::from_config(
+                    rom_manager.clone(),
+                    PlainMemoryConfig {
+                        assigned_range: range.clone(),
+                        ..Default::default()
+                    },
+                ))),
+            );
+        }
+
+        // Same addresses for every measurement so only the lookup differs
+        let address_space = region_count * MEMORY_BENCH_REGION_SIZE;
+        let mut state = 0x2545_f491_4f6c_dd1d_u64;
+        let addresses: Vec<usize> = (0..accesses)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                state as usize % address_space
+            })
+            .collect();
+
+        let start = Instant::now();
+        for &address in &addresses {
+            black_box(memory_translation_table.get(black_box(address)));
+        }
+        let lookup_time = start.elapsed();
+
+        let start = Instant::now();
+        for &address in &addresses {
+            black_box(
+                ranges
+                    .iter()
+                    .position(|range| range.contains(&black_box(address))),
+            );
+        }
+        let linear_time = start.elapsed();
+
+        let mut buffer = [0];
+        let start = Instant::now();
+        for &address in &addresses {
+            memory_translation_table
+                .read(black_box(address), &mut buffer)
+                .unwrap();
+        }
+        let read_time = start.elapsed();
+
+        let per_access = |time: Duration| time.as_nanos() as f64 / accesses.max(1) as f64;
+        println!(
+            "{:>8} {:>16.1} {:>16.1} {:>16.1}",
+            region_count,
+            per_access(lookup_time),
+            per_access(linear_time),
+            per_access(read_time)
+        );
+    }
+}
@@ -0,0 +1,101 @@
+use super::{print_json, CliStatus, RomReport};
+use crate::{
+    env::ROM_DATABASE_PATH,
+    rom::{analysis::analyze_rom_file, import::hash_rom, sidecar::identify_rom, RomManager},
+};
+use serde::Serialize;
+use std::{ops::Deref, path::PathBuf};
+use walkdir::WalkDir;
+
+#[derive(Serialize, Debug)]
+struct Identification {
+    #[serde(flatten)]
+    rom: RomReport,
+    /// Found in the database rather than guessed from the file
+    known: bool,
+}
+
+#[derive(Serialize, Debug, Default)]
+struct IdentifyOutput {
+    identified: Vec<Identification>,
+    unidentified: Vec<PathBuf>,
+}
+
+pub fn run(paths: Vec<PathBuf>, json: bool) -> CliStatus {
+    let mut rom_manager = RomManager::default();
+    // Guessing still works without one
+    if let Err(error) = rom_manager.load_rom_info(ROM_DATABASE_PATH.deref()) {
+        tracing::warn!(
+            "Cannot load ROM database, every ROM will be guessed: {}",
+            error
+        );
+    }
+
+    let mut output = IdentifyOutput::default();
+
+    for path in paths {
+        for entry in WalkDir::new(path).into_iter().flatten() {
+            if entry.file_type().is_dir() {
+                continue;
+            }
+
+            let rom_id = hash_rom(entry.path());
+            let Some(mut info) =
+                rom_id.and_then(|rom_id| identify_rom(entry.path(), rom_id, &rom_manager))
+            else {
+                output.unidentified.push(entry.into_path());
+                continue;
+            };
+
+            // Only imported ROMs have their analysis in the database
+            if info.analysis.is_none() {
+                info.analysis = analyze_rom_file(entry.path(), info.system);
+            }
+
+            output.identified.push(Identification {
+                known: rom_manager.rom_information.contains_key(&info.hash),
+                rom: RomReport::new(entry.into_path(), &info),
+            });
+        }
+    }
+
+    if json {
+        print_json(&output);
+    } else {
+        for identification in &output.identified {
+            let rom = &identification.rom;
+
+            println!(
+                "{}: {} for the {} with hash {}{}",
+                rom.path.display(),
+                rom.name.as_deref().unwrap_or("Unnamed ROM"),
+                rom.system,
+                rom.hash,
+                if identification.known {
+                    ""
+                } else {
+                    " (guessed)"
+                }
+            );
+
+            for problem in &rom.problems {
+                println!("    {}", problem);
+            }
+        }
+
+        for path in &output.unidentified {
+            println!("{}: unidentified", path.display());
+        }
+    }
+
+    if output.unidentified.is_empty()
+        && output
+            .identified
+            .iter()
+            .all(|identification| identification.rom.problems.is_empty())
+    {
+        CliStatus::Success
+    } else {
+        CliStatus::ProblemsFound
+    }
+}
@@ -1,6 +1,6 @@
 use crate::{
     env::ROM_DATABASE_PATH,
-    rom::{GameSystem, RomId, RomInfo, RomManager},
+    rom::{GameSystem, RomId, RomInfo, RomManager, RomRegion},
 };
 use serde::Deserialize;
 use serde_with::serde_as;
@@ -77,13 +77,21 @@ pub fn run(files: Vec<PathBuf>) {
         );
 
         for game in data_file.machine.into_iter() {
+            let region = game
+                .rom
+                .region
+                .as_deref()
+                .and_then(RomRegion::from_nointro)
+                .or_else(|| RomRegion::from_nointro_name(&game.name));
+
             rom_manager.rom_information.insert(
                 game.rom.hash,
                 RomInfo {
                     name: Some(game.name),
                     hash: game.rom.hash,
                     system: data_file.header.name,
-                    region: None,
+                    region,
+                    analysis: None,
                 },
             );
         }
@@ -1,32 +1,60 @@
+use super::{print_json, report_failure, CliStatus, RomReport};
 use crate::{
     env::ROM_DATABASE_PATH,
-    rom::{GameSystem, RomId, RomInfo, RomManager},
+    rom::{
+        analysis::analyze_rom_file,
+        import::{hash_rom, store_rom, ImportPolicy},
+        GameSystem, RomInfo, RomManager,
+    },
 };
-use sha1::{Digest, Sha1};
-use std::{fs, ops::Deref, path::PathBuf};
+use std::{ops::Deref, path::PathBuf};
 
-pub fn run(file: PathBuf, system: GameSystem, name: String) {
+pub fn run(
+    file: PathBuf,
+    system: GameSystem,
+    name: String,
+    policy: ImportPolicy,
+    json: bool,
+) -> CliStatus {
     let mut rom_manager = RomManager::default();
     let _ = rom_manager.load_rom_info(ROM_DATABASE_PATH.deref());
 
-    let mut hasher = Sha1::default();
-    let mut rom = fs::File::open(&file).unwrap();
-    std::io::copy(&mut rom, &mut hasher).unwrap();
-    let hash = RomId::new(hasher.finalize().into());
+    let Some(hash) = hash_rom(&file) else {
+        return report_failure(json, format!("Could not read ROM at {}", file.display()));
+    };
+    // Before storing, as moving takes the file away
+    let analysis = analyze_rom_file(&file, system);
+    if let Err(error) = store_rom(&file, hash, policy) {
+        return report_failure(
+            json,
+            format!("Could not import {}: {}", file.display(), error),
+        );
+    }
 
     tracing::info!("Imported ROM {} with hash {}", name, hash);
 
-    rom_manager.rom_information.insert(
+    let info = RomInfo {
+        name: Some(name),
+        system,
         hash,
-        RomInfo {
-            name: Some(name),
-            system,
-            hash,
-            region: None,
-        },
-    );
-
-    rom_manager
-        .store_rom_info(ROM_DATABASE_PATH.deref())
-        .unwrap();
+        region: None,
+        analysis,
+    };
+    rom_manager.rom_information.insert(hash, info.clone());
+
+    if let Err(error) = rom_manager.store_rom_info(ROM_DATABASE_PATH.deref()) {
+        return report_failure(json, format!("Cannot store ROM database: {}", error));
+    }
+
+    let report = RomReport::new(file, &info);
+
+    if json {
+        print_json(&report);
+    }
+
+    if report.problems.is_empty() {
+        CliStatus::Success
+    } else {
+        CliStatus::ProblemsFound
+    }
 }
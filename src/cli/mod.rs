@@ -1,19 +1,99 @@
 use crate::{
     config::GlobalConfig,
-    rom::{GameSystem, RomId},
+    rom::{import::ImportPolicy, GameSystem, RomId, RomInfo, RomRegion},
+    runtime::backend_benchmark::run_backend_benchmark,
 };
 use clap::{Parser, Subcommand, ValueEnum};
+use serde::Serialize;
+use serde_with::{serde_as, DisplayFromStr};
 use std::{
+    fmt::Display,
     path::PathBuf,
+    process::ExitCode,
     sync::{Arc, RwLock},
 };
 
+pub mod bench;
+pub mod identify_roms;
 pub mod import_known_roms;
 pub mod import_native_database;
 pub mod import_nointro_database;
 pub mod import_rom_manually;
 pub mod run_external_rom;
 pub mod run_rom;
+pub mod self_test;
+pub mod verify_roms;
+
+/// How an action went, so scripts can tell outcomes apart without reading the output
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CliStatus {
+    Success,
+    /// The action could not run at all, like when the ROM database can't be read
+    Failure,
+    /// The action ran but some files were unrecognized, corrupt or not imported
+    ProblemsFound,
+}
+
+impl From<CliStatus> for ExitCode {
+    fn from(status: CliStatus) -> Self {
+        match status {
+            CliStatus::Success => ExitCode::SUCCESS,
+            CliStatus::Failure => ExitCode::from(1),
+            // 2 is what clap exits with on bad arguments
+            CliStatus::ProblemsFound => ExitCode::from(3),
+        }
+    }
+}
+
+/// Prints the result of an action to stdout for scripts
+pub fn print_json(value: &impl Serialize) {
+    println!("{}", serde_json::to_string_pretty(value).unwrap());
+}
+
+/// A ROM as the JSON output describes it
+#[serde_as]
+#[derive(Serialize, Debug)]
+pub struct RomReport {
+    pub path: PathBuf,
+    #[serde_as(as = "DisplayFromStr")]
+    pub hash: RomId,
+    pub name: Option<String>,
+    pub system: GameSystem,
+    pub region: Option<RomRegion>,
+    /// What the analysis found that may keep it from booting
+    pub problems: Vec<String>,
+}
+
+impl RomReport {
+    pub fn new(path: PathBuf, info: &RomInfo) -> Self {
+        Self {
+            path,
+            hash: info.hash,
+            name: info.name.clone(),
+            system: info.system,
+            region: info.region,
+            problems: info
+                .analysis
+                .iter()
+                .flat_map(|analysis| &analysis.problems)
+                .map(ToString::to_string)
+                .collect(),
+        }
+    }
+}
+
+/// Reports why an action could not run, on stdout as JSON or on stderr for people
+pub fn report_failure(json: bool, error: impl Display) -> CliStatus {
+    tracing::error!("{}", error);
+
+    if json {
+        print_json(&serde_json::json!({ "error": error.to_string() }));
+    } else {
+        eprintln!("{}", error);
+    }
+
+    CliStatus::Failure
+}
 
 #[derive(ValueEnum, Clone, Debug)]
 pub enum DatabaseType {
@@ -35,37 +115,94 @@ pub enum CliAction {
         path: Vec<PathBuf>,
     },
     ImportRomManually {
+        /// Defaults to the one in the config
+        #[clap(short, long)]
+        policy: Option<ImportPolicy>,
+        /// Print the result as JSON on stdout
+        #[clap(long)]
+        json: bool,
         system: GameSystem,
         name: String,
         path: PathBuf,
     },
     ImportKnownRoms {
+        /// Defaults to the one in the config
+        #[clap(short, long)]
+        policy: Option<ImportPolicy>,
+        /// Print what was imported and skipped as JSON on stdout
+        #[clap(long)]
+        json: bool,
+        #[arg(required=true, num_args=1..)]
+        path: Vec<PathBuf>,
+    },
+    /// Picks the fastest rendering backend for this machine
+    BenchmarkBackends,
+    /// Runs a game headlessly as fast as possible and reports how fast each task ran
+    Bench {
         #[clap(short, long)]
-        symlink: bool,
+        force_system: Option<GameSystem>,
+        /// 60Hz frames of machine time to run for
+        #[clap(long, default_value_t = 600)]
+        frames: u32,
+        #[arg(required=true, num_args=1..)]
+        rom: Vec<RomId>,
+    },
+    /// Times memory accesses through machines with more and more components mapped
+    BenchMemory {
+        #[clap(long, default_value_t = 10_000_000)]
+        accesses: u32,
+    },
+    /// Runs a quick suite of diagnostics and prints a report to attach to bug reports
+    SelfTest,
+    /// Identifies ROM files without importing them, from the database or by guessing
+    Identify {
+        /// Print the identifications as JSON on stdout
+        #[clap(long)]
+        json: bool,
         #[arg(required=true, num_args=1..)]
         path: Vec<PathBuf>,
     },
+    /// Checks the imported ROMs still match their hashes and are known to the database
     VerifyRoms {
+        /// Delete imported ROMs the database doesn't know
         #[clap(short, long)]
         unknown_discard: bool,
+        /// Delete imported ROMs whose contents don't match their hash
         #[clap(short, long)]
         incorrect_discard: bool,
+        /// Print the findings as JSON on stdout
+        #[clap(long)]
+        json: bool,
     },
     Run {
         #[clap(short, long)]
         force_system: Option<GameSystem>,
+        /// Record the audio output to a WAV file
+        #[clap(long)]
+        record_audio: Option<PathBuf>,
+        /// Let gdb or another remote debugger attach on this localhost port
+        #[clap(long)]
+        gdb_port: Option<u16>,
         #[arg(required=true, num_args=1..)]
         rom: Vec<RomId>,
     },
     RunExternal {
         #[clap(short, long)]
         force_system: Option<GameSystem>,
+        /// Record the audio output to a WAV file
+        #[clap(long)]
+        record_audio: Option<PathBuf>,
+        /// Let gdb or another remote debugger attach on this localhost port
+        #[clap(long)]
+        gdb_port: Option<u16>,
         #[arg(required=true, num_args=1..)]
         rom: Vec<PathBuf>,
     },
 }
 
-pub fn handle_cli(cli_action: CliAction, global_config: Arc<RwLock<GlobalConfig>>) {
+pub fn handle_cli(cli_action: CliAction, global_config: Arc<RwLock<GlobalConfig>>) -> CliStatus {
+    let default_import_policy = global_config.read().unwrap().import_policy;
+
     match cli_action {
         CliAction::ImportDatabase {
             database_type: DatabaseType::Native,
@@ -79,34 +216,80 @@ pub fn handle_cli(cli_action: CliAction, global_config: Arc<RwLock<GlobalConfig>
         } => {
             import_nointro_database::run(path);
         }
-        CliAction::Run { rom, force_system } => {
+        CliAction::Run {
+            rom,
+            force_system,
+            record_audio,
+            gdb_port,
+        } => {
             if force_system.is_some() {
                 tracing::warn!(
                     "Forcing a system is not recommended as it can cause mysterious problems"
                 );
             }
 
-            run_rom::run(rom, global_config);
+            run_rom::run(rom, record_audio, gdb_port, global_config);
         }
-        CliAction::RunExternal { rom, force_system } => {
+        CliAction::RunExternal {
+            rom,
+            force_system,
+            record_audio,
+            gdb_port,
+        } => {
             if force_system.is_some() {
                 tracing::warn!(
                     "Forcing a system is not recommended as it can cause mysterious problems"
                 );
             }
 
-            run_external_rom::run(rom, force_system, global_config);
+            run_external_rom::run(rom, force_system, record_audio, gdb_port, global_config);
         }
 
-        CliAction::ImportRomManually { path, system, name } => {
-            import_rom_manually::run(path, system, name);
+        CliAction::ImportRomManually {
+            path,
+            system,
+            name,
+            policy,
+            json,
+        } => {
+            return import_rom_manually::run(
+                path,
+                system,
+                name,
+                policy.unwrap_or(default_import_policy),
+                json,
+            );
+        }
+        CliAction::ImportKnownRoms { path, policy, json } => {
+            return import_known_roms::run(path, policy.unwrap_or(default_import_policy), json);
+        }
+        CliAction::BenchmarkBackends => {
+            run_backend_benchmark(&global_config);
+        }
+        CliAction::Bench {
+            rom,
+            force_system,
+            frames,
+        } => {
+            bench::run(rom, force_system, frames, global_config);
         }
-        CliAction::ImportKnownRoms { path, symlink } => {
-            import_known_roms::run(path, symlink);
+        CliAction::BenchMemory { accesses } => {
+            bench::run_memory(accesses);
+        }
+        CliAction::SelfTest => {
+            self_test::run(global_config);
+        }
+        CliAction::Identify { path, json } => {
+            return identify_roms::run(path, json);
         }
         CliAction::VerifyRoms {
             unknown_discard,
             incorrect_discard,
-        } => todo!(),
+            json,
+        } => {
+            return verify_roms::run(unknown_discard, incorrect_discard, json);
+        }
     }
+
+    CliStatus::Success
 }
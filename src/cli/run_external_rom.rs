@@ -1,10 +1,8 @@
 use crate::{
     config::GlobalConfig,
     env::{IMPORTED_ROM_DIRECTORY, ROM_DATABASE_PATH},
-    rom::{guess_rom::guess_rom, GameSystem, RomId, RomInfo, RomManager},
-    runtime::{
-        desktop::display::vulkan::VulkanRendering, launch_gui, InitialGuiState, SoftwareRendering,
-    },
+    rom::{sidecar::identify_external_rom, GameSystem, RomId, RomInfo, RomManager},
+    runtime::{desktop::launch_gui_with_selected_backend, InitialGuiState},
 };
 use sha1::{Digest, Sha1};
 use std::{
@@ -17,6 +15,8 @@ use std::{
 pub fn run(
     roms: Vec<PathBuf>,
     force_system: Option<GameSystem>,
+    audio_capture: Option<PathBuf>,
+    gdb_port: Option<u16>,
     global_config: Arc<RwLock<GlobalConfig>>,
 ) {
     for rom in &roms {
@@ -54,6 +54,7 @@ pub fn run(
                     hash,
                     system: forced_game_system,
                     region: None,
+                    analysis: None,
                 },
             );
             user_specified_roms.push(hash);
@@ -62,31 +63,24 @@ pub fn run(
         game_system = Some(forced_game_system);
     } else {
         for rom_path in &roms {
-            let Some((guessed_game_system, rom_id)) = guess_rom(rom_path, &rom_manager) else {
+            let Some(rom_info) = identify_external_rom(rom_path, &rom_manager) else {
                 panic!("Failed to guess system for {}", rom_path.display());
             };
 
             if let Some(game_system) = game_system {
-                if guessed_game_system != game_system {
+                if rom_info.system != game_system {
                     panic!(
                         "ROM has confusing system specification: expected {} but got {}",
-                        game_system, guessed_game_system
+                        game_system, rom_info.system
                     );
                 }
             } else {
-                game_system = Some(guessed_game_system);
+                game_system = Some(rom_info.system);
             }
 
+            let rom_id = rom_info.hash;
             rom_manager.rom_paths.insert(rom_id, rom_path.clone());
-            rom_manager.rom_information.insert(
-                rom_id,
-                RomInfo {
-                    name: None,
-                    hash: rom_id,
-                    system: guessed_game_system,
-                    region: None,
-                },
-            );
+            rom_manager.rom_information.insert(rom_id, rom_info);
             user_specified_roms.push(rom_id);
         }
     }
@@ -94,23 +88,14 @@ pub fn run(
     let rom_manager = Arc::new(rom_manager);
     let game_system = game_system.expect("Failed to guess game system");
 
-    if global_config.read().unwrap().hardware_acceleration {
-        launch_gui::<VulkanRendering>(
-            rom_manager,
-            InitialGuiState::OpenGame {
-                user_specified_roms,
-                game_system,
-            },
-            global_config,
-        );
-    } else {
-        launch_gui::<SoftwareRendering>(
-            rom_manager,
-            InitialGuiState::OpenGame {
-                user_specified_roms,
-                game_system,
-            },
-            global_config,
-        );
-    }
+    launch_gui_with_selected_backend(
+        rom_manager,
+        InitialGuiState::OpenGame {
+            user_specified_roms,
+            game_system,
+            audio_capture,
+            gdb_port,
+        },
+        global_config,
+    );
 }
@@ -2,17 +2,21 @@ use crate::{
     config::GlobalConfig,
     env::{IMPORTED_ROM_DIRECTORY, ROM_DATABASE_PATH},
     rom::{RomId, RomManager},
-    runtime::{
-        desktop::display::vulkan::VulkanRendering, launch_gui, InitialGuiState, SoftwareRendering,
-    },
+    runtime::{desktop::launch_gui_with_selected_backend, InitialGuiState},
 };
 use std::{
     fs::create_dir_all,
     ops::Deref,
+    path::PathBuf,
     sync::{Arc, RwLock},
 };
 
-pub fn run(user_specified_roms: Vec<RomId>, global_config: Arc<RwLock<GlobalConfig>>) {
+pub fn run(
+    user_specified_roms: Vec<RomId>,
+    audio_capture: Option<PathBuf>,
+    gdb_port: Option<u16>,
+    global_config: Arc<RwLock<GlobalConfig>>,
+) {
     let mut rom_manager = RomManager::default();
 
     create_dir_all(IMPORTED_ROM_DIRECTORY.deref()).unwrap();
@@ -34,23 +38,14 @@ pub fn run(user_specified_roms: Vec<RomId>, global_config: Arc<RwLock<GlobalConf
     let rom_manager = Arc::new(rom_manager);
     let game_system = rom_manager.rom_information[&user_specified_roms[0]].system;
 
-    if global_config.read().unwrap().hardware_acceleration {
-        launch_gui::<VulkanRendering>(
-            rom_manager,
-            InitialGuiState::OpenGame {
-                user_specified_roms,
-                game_system,
-            },
-            global_config,
-        );
-    } else {
-        launch_gui::<SoftwareRendering>(
-            rom_manager,
-            InitialGuiState::OpenGame {
-                user_specified_roms,
-                game_system,
-            },
-            global_config,
-        );
-    }
+    launch_gui_with_selected_backend(
+        rom_manager,
+        InitialGuiState::OpenGame {
+            user_specified_roms,
+            game_system,
+            audio_capture,
+            gdb_port,
+        },
+        global_config,
+    );
 }
@@ -0,0 +1,408 @@
+#[cfg(vulkan)]
+use crate::runtime::desktop::display::vulkan;
+#[cfg(wgpu)]
+use crate::runtime::desktop::display::wgpu;
+#[cfg(feature = "chip8")]
+use crate::{
+    component::{
+        definitions::chip8::{
+            audio::{Chip8Audio, Chip8AudioConfig},
+            display::{Chip8Display, Chip8DisplayConfig},
+            processor::{Chip8Processor, Chip8ProcessorConfig},
+            timer::Chip8Timer,
+            Chip8Kind, CHIP8_DEMO, CHIP8_DEMO_GLYPHS, CHIP8_FONT,
+        },
+        definitions::misc::plain_memory::PlainMemoryInitialContents,
+        display::DisplayComponent,
+        schedulable::SchedulableComponent,
+        Component,
+    },
+    machine::QueryableComponents,
+    runtime::SoftwareRendering,
+    task::{
+        generic::GenericTask,
+        processor::{ProcessorTask, ProcessorTaskConfig},
+        InitializeableTask,
+    },
+};
+use crate::{
+    component::{
+        definitions::misc::plain_memory::{PlainMemory, PlainMemoryConfig},
+        memory::MemoryTranslationTable,
+        FromConfig,
+    },
+    config::GlobalConfig,
+    machine::executor::{single::SingleThreadedExecutor, Executor},
+    rom::RomManager,
+    runtime::simd,
+    task::Task,
+};
+use cpal::traits::{DeviceTrait, HostTrait};
+use num::{rational::Ratio, ToPrimitive};
+#[cfg(feature = "chip8")]
+use palette::Srgba;
+use std::{
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex, RwLock,
+    },
+    time::{Duration, Instant},
+};
+
+/// How long the scheduler simulation and the demo run for
+const RUN_TIME: Duration = Duration::from_millis(250);
+const FRAME_TIME: Duration = Duration::from_micros(16_667);
+/// Frames to render offscreen for backends that can't run the demo headlessly
+const OFFSCREEN_FRAMES: u32 = 10;
+/// Task rates for the scheduler simulation, picked so they don't evenly divide each other
+const SIMULATED_RATES: [(&str, u32); 3] = [("slow", 60), ("medium", 700), ("fast", 44100)];
+
+type CheckResult = Result<String, String>;
+type Check = fn() -> CheckResult;
+
+/// Counts how many ticks it was given, for checking the scheduler in isolation
+struct CountingTask(Arc<AtomicU64>);
+
+impl Task for CountingTask {
+    fn tick(&mut self, batch_size: u32, _memory_translation_table: &MemoryTranslationTable) {
+        self.0.fetch_add(batch_size as u64, Ordering::Relaxed);
+    }
+
+    fn save(&mut self) -> rmpv::Value {
+        rmpv::Value::Nil
+    }
+
+    fn load(&mut self, _state: rmpv::Value) {}
+}
+
+/// Runs a quick suite of checks in process and prints a report meant to be attached to bug reports
+pub fn run(global_config: Arc<RwLock<GlobalConfig>>) {
+    // What the build left out isn't checked
+    let checks: &[(&str, Check)] = &[
+        ("Scheduler simulation", check_scheduler),
+        ("Memory translation table", check_memory_translation_table),
+        #[cfg(feature = "chip8")]
+        ("Chip8 demo (software)", check_chip8_demo_software),
+        #[cfg(vulkan)]
+        ("Offscreen rendering (vulkan)", check_vulkan),
+        #[cfg(wgpu)]
+        ("Offscreen rendering (wgpu)", check_wgpu),
+        ("Audio devices", check_audio),
+    ];
+
+    println!("MultiEMU v{} self test", env!("CARGO_PKG_VERSION"));
+    println!(
+        "Platform: {} {}",
+        std::env::consts::OS,
+        std::env::consts::ARCH
+    );
+    println!("SIMD: {}", simd::describe());
+    {
+        let global_config = global_config.read().unwrap();
+        println!(
+            "Rendering backend: {} (selected {})",
+            global_config.rendering_backend,
+            global_config.selected_rendering_backend()
+        );
+        println!("Backend benchmark: {:?}", global_config.backend_benchmark);
+    }
+    println!();
+
+    let mut failures = 0;
+    for (name, check) in checks {
+        tracing::info!("Running self test check: {}", name);
+
+        let start = Instant::now();
+        // A panic is a failure like any other, and the rest of the report is still useful
+        let result = std::panic::catch_unwind(check)
+            .unwrap_or_else(|_| Err("Panicked, see the log for details".to_string()));
+        let elapsed = start.elapsed();
+
+        match result {
+            Ok(details) => println!("[PASS] {} ({:?}): {}", name, elapsed, details),
+            Err(details) => {
+                failures += 1;
+                tracing::error!("Self test check {} failed: {}", name, details);
+                println!("[FAIL] {} ({:?}): {}", name, elapsed, details);
+            }
+        }
+    }
+
+    println!();
+    println!(
+        "{} of {} checks passed",
+        checks.len() - failures,
+        checks.len()
+    );
+}
+
+/// Runs tasks at awkward rates and checks they got their share of ticks relative to each other
+fn check_scheduler() -> CheckResult {
+    let counters: Vec<_> = SIMULATED_RATES
+        .iter()
+        .map(|_| Arc::new(AtomicU64::new(0)))
+        .collect();
+
+    let mut executor = SingleThreadedExecutor::new(
+        SIMULATED_RATES
+            .iter()
+            .zip(&counters)
+            .map(|((name, rate), counter)| {
+                (
+                    *name,
+                    Ratio::from_integer(*rate),
+                    Box::new(CountingTask(counter.clone())) as Box<dyn Task>,
+                )
+            })
+            .collect(),
+        Arc::default(),
+    );
+
+    let start = Instant::now();
+    while start.elapsed() < RUN_TIME {
+        executor.run(FRAME_TIME);
+    }
+
+    let report = executor.schedule_report();
+    let (_, reference_rate) = SIMULATED_RATES[0];
+    let reference_ticks = counters[0].load(Ordering::Relaxed);
+    if reference_ticks == 0 {
+        return Err(format!(
+            "The {} Hz task never ran in {:?}",
+            reference_rate, RUN_TIME
+        ));
+    }
+
+    let mut details = Vec::new();
+    for (((name, rate), counter), task) in SIMULATED_RATES.iter().zip(&counters).zip(&report.tasks)
+    {
+        let ticks = counter.load(Ordering::Relaxed);
+        let expected = reference_ticks as f64 * *rate as f64 / reference_rate as f64;
+        // The tasks are at most a batch out of step with each other
+        let tolerance = expected * 0.05 + *rate as f64 / reference_rate as f64;
+
+        if (ticks as f64 - expected).abs() > tolerance {
+            return Err(format!(
+                "Task {} got {} ticks where {:.0} were expected",
+                name, ticks, expected
+            ));
+        }
+
+        let effective_rate = 1.0 / (task.period as f64 * report.tick_real_time.as_secs_f64());
+        details.push(format!(
+            "{} {} Hz ran at {:.2} Hz",
+            name,
+            task.tick_rate.to_f64().unwrap(),
+            effective_rate
+        ));
+    }
+
+    Ok(details.join(", "))
+}
+
+/// Round trips every access size through a table split across two components, including across the seam
+fn check_memory_translation_table() -> CheckResult {
+    let rom_manager = Arc::new(RomManager::default());
+    let mut memory_translation_table = MemoryTranslationTable::default();
+
+    for assigned_range in [0x000..0x100, 0x100..0x200] {
+        let memory = PlainMemory::from_config(
+            rom_manager.clone(),
+            PlainMemoryConfig {
+                assigned_range: assigned_range.clone(),
+                ..Default::default()
+            },
+        );
+
+        memory_translation_table.insert(assigned_range, Arc::new(Mutex::new(memory)));
+    }
+
+    let mut accesses = 0;
+    for size in [1, 2, 4, 8] {
+        for address in [0x000, 0x0f8, 0x100 - size / 2, 0x100, 0x200 - size] {
+            let pattern: Vec<u8> = (0..size)
+                .map(|index| (address + index) as u8 ^ 0xa5)
+                .collect();
+            let mut buffer = vec![0; size];
+
+            memory_translation_table
+                .write(address, &pattern)
+                .map_err(|error| format!("Writing {} bytes at 0x{:x}: {}", size, address, error))?;
+            memory_translation_table
+                .read(address, &mut buffer)
+                .map_err(|error| format!("Reading {} bytes at 0x{:x}: {}", size, address, error))?;
+
+            if buffer != pattern {
+                return Err(format!(
+                    "Wrote {:x?} at 0x{:x} but read back {:x?}",
+                    pattern, address, buffer
+                ));
+            }
+
+            accesses += 1;
+        }
+    }
+
+    if memory_translation_table.read(0x200, &mut [0]).is_ok() {
+        return Err("Reading unmapped memory succeeded".to_string());
+    }
+
+    Ok(format!("{} round trips", accesses))
+}
+
+/// Runs the embedded demo on a chip8 put together by hand, as building a machine needs a window
+#[cfg(feature = "chip8")]
+fn check_chip8_demo_software() -> CheckResult {
+    let rom_manager = Arc::new(RomManager::default());
+
+    let processor = Arc::new(Mutex::new(Chip8Processor::from_config(
+        rom_manager.clone(),
+        Chip8ProcessorConfig {
+            frequency: Ratio::from_integer(700),
+            kind: Chip8Kind::Chip8,
+        },
+    )));
+    let display = Arc::new(Mutex::new(Chip8Display::from_config(
+        rom_manager.clone(),
+        Chip8DisplayConfig {
+            kind: Chip8Kind::Chip8,
+            quirk_sprite_wrapping: false,
+        },
+    )));
+    let timer = Arc::new(Mutex::new(Chip8Timer::from_config(rom_manager.clone(), ())));
+    let audio = Arc::new(Mutex::new(Chip8Audio::from_config(
+        rom_manager.clone(),
+        Chip8AudioConfig::default(),
+    )));
+
+    DisplayComponent::<SoftwareRendering>::initialize_display(&mut *display.lock().unwrap(), ());
+
+    let mut queryable_components = QueryableComponents::default();
+    queryable_components.insert("processor", processor.clone());
+    queryable_components.insert("display", display.clone());
+    queryable_components.insert("timer", timer.clone());
+    queryable_components.insert("audio", audio.clone());
+    processor
+        .lock()
+        .unwrap()
+        .query_components(&queryable_components);
+
+    let mut memory_translation_table = MemoryTranslationTable::default();
+    for (assigned_range, contents, offset) in [
+        (0x000..0x200, bytemuck::cast_slice(&CHIP8_FONT), 0x000),
+        (0x200..0x1000, CHIP8_DEMO.as_slice(), 0x200),
+    ] {
+        let memory = PlainMemory::from_config(
+            rom_manager.clone(),
+            PlainMemoryConfig {
+                max_word_size: 2,
+                assigned_range: assigned_range.clone(),
+                initial_contents: PlainMemoryInitialContents::Array {
+                    value: contents,
+                    offset,
+                },
+                ..Default::default()
+            },
+        );
+
+        memory_translation_table.insert(assigned_range, Arc::new(Mutex::new(memory)));
+    }
+
+    let tasks: Vec<(&'static str, Ratio<u32>, Box<dyn Task>)> = vec![
+        (
+            "processor",
+            processor.lock().unwrap().tick_rate(),
+            Box::new(ProcessorTask::new(
+                processor.clone(),
+                ProcessorTaskConfig {
+                    initial_program_pointer: 0x200,
+                },
+            )),
+        ),
+        (
+            "display",
+            Ratio::from_integer(60),
+            Box::new(GenericTask::new(display.clone(), ())),
+        ),
+        (
+            "timer",
+            Ratio::from_integer(60),
+            Box::new(GenericTask::new(timer, ())),
+        ),
+        (
+            "audio",
+            Ratio::from_integer(60),
+            Box::new(GenericTask::new(audio, ())),
+        ),
+    ];
+    let mut executor = SingleThreadedExecutor::new(tasks, Arc::new(memory_translation_table));
+
+    let start = Instant::now();
+    while start.elapsed() < RUN_TIME {
+        executor.run(FRAME_TIME);
+    }
+
+    let expected_pixels: u32 = CHIP8_FONT[..CHIP8_DEMO_GLYPHS]
+        .iter()
+        .flatten()
+        .map(|row| row.count_ones())
+        .sum();
+
+    let display = display.lock().unwrap();
+    let lit_pixels = DisplayComponent::<SoftwareRendering>::display_data(&*display)
+        .to_pixels()
+        .iter()
+        .filter(|pixel| **pixel != Srgba::new(0, 0, 0, 0xff))
+        .count() as u32;
+
+    if lit_pixels != expected_pixels {
+        return Err(format!(
+            "{} pixels were lit after {:?} where {} were expected",
+            lit_pixels, RUN_TIME, expected_pixels
+        ));
+    }
+
+    Ok(format!("{} pixels lit as expected", lit_pixels))
+}
+
+/// The vulkan display needs a window to run a machine, so this only checks the device can render offscreen
+#[cfg(vulkan)]
+fn check_vulkan() -> CheckResult {
+    vulkan::benchmark::benchmark(OFFSCREEN_FRAMES)
+        .map(|frame_time| format!("{:?} per frame", frame_time))
+        .ok_or_else(|| "No usable vulkan device".to_string())
+}
+
+#[cfg(wgpu)]
+fn check_wgpu() -> CheckResult {
+    wgpu::benchmark::benchmark(OFFSCREEN_FRAMES)
+        .map(|frame_time| format!("{:?} per frame", frame_time))
+        .ok_or_else(|| "No usable wgpu adapter".to_string())
+}
+
+fn check_audio() -> CheckResult {
+    let host = cpal::default_host();
+
+    let device = host
+        .default_output_device()
+        .ok_or_else(|| format!("{} has no default output device", host.id().name()))?;
+    let config = device
+        .default_output_config()
+        .map_err(|error| format!("Could not query the default output config: {}", error))?;
+    let device_count = host
+        .output_devices()
+        .map(|devices| devices.count())
+        .unwrap_or_default();
+
+    Ok(format!(
+        "{} output device(s) on {}, default is {} at {} Hz with {} channel(s) of {}",
+        device_count,
+        host.id().name(),
+        device
+            .name()
+            .unwrap_or_else(|_| "an unnamed device".to_string()),
+        config.sample_rate().0,
+        config.channels(),
+        config.sample_format()
+    ))
+}
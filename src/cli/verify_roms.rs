@@ -0,0 +1,103 @@
+use super::{print_json, report_failure, CliStatus};
+use crate::{
+    env::{IMPORTED_ROM_DIRECTORY, ROM_DATABASE_PATH},
+    rom::{RomId, RomManager},
+};
+use serde::Serialize;
+use serde_with::{serde_as, DisplayFromStr};
+use std::{fs, ops::Deref, path::PathBuf};
+
+#[serde_as]
+#[derive(Serialize, Debug)]
+struct StoredRom {
+    path: PathBuf,
+    /// What the contents actually hash to
+    #[serde_as(as = "DisplayFromStr")]
+    hash: RomId,
+}
+
+#[derive(Serialize, Debug, Default)]
+struct VerifyOutput {
+    verified: usize,
+    /// Contents that don't match the hash they are stored under, usually corruption
+    incorrect: Vec<StoredRom>,
+    /// Not in the database
+    unknown: Vec<StoredRom>,
+    discarded: usize,
+}
+
+pub fn run(unknown_discard: bool, incorrect_discard: bool, json: bool) -> CliStatus {
+    let mut rom_manager = RomManager::default();
+    // Without the database every ROM would be unknown, and discarding them all is not what anyone wants
+    if let Err(error) = rom_manager.load_rom_info(ROM_DATABASE_PATH.deref()) {
+        return report_failure(json, format!("Cannot load ROM database: {}", error));
+    }
+
+    let incorrect_roms = match rom_manager.load_rom_paths_verified(IMPORTED_ROM_DIRECTORY.deref()) {
+        Ok(incorrect_roms) => incorrect_roms,
+        Err(error) => {
+            return report_failure(json, format!("Cannot verify the imported ROMs: {}", error))
+        }
+    };
+
+    let mut output = VerifyOutput {
+        incorrect: incorrect_roms
+            .into_iter()
+            .map(|(hash, path)| StoredRom { path, hash })
+            .collect(),
+        ..Default::default()
+    };
+
+    for (hash, path) in rom_manager.rom_paths {
+        if rom_manager.rom_information.contains_key(&hash) {
+            output.verified += 1;
+        } else {
+            output.unknown.push(StoredRom { path, hash });
+        }
+    }
+
+    let to_discard = output
+        .incorrect
+        .iter()
+        .filter(|_| incorrect_discard)
+        .chain(output.unknown.iter().filter(|_| unknown_discard));
+    for rom in to_discard {
+        match fs::remove_file(&rom.path) {
+            Ok(()) => {
+                tracing::info!("Discarded {}", rom.path.display());
+                output.discarded += 1;
+            }
+            Err(error) => tracing::error!("Could not discard {}: {}", rom.path.display(), error),
+        }
+    }
+
+    if json {
+        print_json(&output);
+    } else {
+        for rom in &output.incorrect {
+            println!(
+                "{}: contents hash to {}, probably corrupt",
+                rom.path.display(),
+                rom.hash
+            );
+        }
+
+        for rom in &output.unknown {
+            println!("{}: not in the database", rom.path.display());
+        }
+
+        println!(
+            "{} verified, {} incorrect, {} unknown, {} discarded",
+            output.verified,
+            output.incorrect.len(),
+            output.unknown.len(),
+            output.discarded
+        );
+    }
+
+    if output.incorrect.is_empty() && output.unknown.is_empty() {
+        CliStatus::Success
+    } else {
+        CliStatus::ProblemsFound
+    }
+}
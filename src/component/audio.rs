@@ -1,22 +1,77 @@
 use std::sync::{Arc, Mutex};
 
 use super::schedulable::SchedulableComponent;
-use num::rational::Ratio;
-use ringbuffer::AllocRingBuffer;
+use num::{rational::Ratio, ToPrimitive};
+use ringbuffer::{AllocRingBuffer, RingBuffer};
 
-pub struct AudioContext {
-    pub host_sample_rate: Ratio<u32>,
-    pub channels: Mutex<Vec<AllocRingBuffer<i16>>>,
+/// A single point in time of stereo audio, left then right
+pub type SampleFrame = [i16; 2];
+
+/// Frames a audio component produced at its native rate that the runtime has not played yet
+///
+/// Cloning shares the same buffer, the component keeps one and hands the other to the runtime
+#[derive(Clone)]
+pub struct AudioBuffer {
+    frames: Arc<Mutex<AllocRingBuffer<SampleFrame>>>,
 }
 
-impl AudioContext {
-    pub fn new(host_sample_rate: Ratio<u32>) -> Arc<Self> {
-        Arc::new(Self {
-            host_sample_rate,
-            channels: Mutex::new(Vec::new()),
-        })
+impl AudioBuffer {
+    /// Holds about a second of audio, if nobody drains it the oldest frames are dropped
+    pub fn new(sample_rate: Ratio<u32>) -> Self {
+        let capacity = sample_rate.ceil().to_usize().unwrap().max(1);
+
+        Self {
+            frames: Arc::new(Mutex::new(AllocRingBuffer::new(capacity))),
+        }
+    }
+
+    #[cfg(all(test, feature = "frontend"))]
+    pub fn push(&self, frame: SampleFrame) {
+        self.frames.lock().unwrap().push(frame);
+    }
+
+    pub fn extend(&self, frames: impl IntoIterator<Item = SampleFrame>) {
+        self.frames.lock().unwrap().extend(frames);
+    }
+
+    /// Oldest first
+    pub fn pop(&self) -> Option<SampleFrame> {
+        self.frames.lock().unwrap().dequeue()
+    }
+
+    /// Throws away everything but the newest frames, for when playback fell too far behind
+    #[cfg(feature = "frontend")]
+    pub fn truncate_front(&self, keep: usize) {
+        let mut frames = self.frames.lock().unwrap();
+
+        while frames.len() > keep {
+            frames.dequeue();
+        }
+    }
+
+    #[cfg(any(feature = "frontend", test))]
+    pub fn len(&self) -> usize {
+        self.frames.lock().unwrap().len()
     }
 }
 
 // It doesn't really make sense to have a piece of audio hardware thats not on the schedule
-pub trait AudioComponent: SchedulableComponent {}
+pub trait AudioComponent: SchedulableComponent {
+    /// Names of the individual channels (APU/PSG voices) this component produces, for debugging
+    #[cfg(feature = "frontend")]
+    fn audio_channels(&self) -> &'static [&'static str];
+
+    /// Disabled channels must output silence
+    #[cfg(feature = "frontend")]
+    fn set_audio_channel_enabled(&mut self, channel: usize, enabled: bool);
+
+    #[cfg(feature = "frontend")]
+    fn is_audio_channel_enabled(&self, channel: usize) -> bool;
+
+    /// Rate the frames in [AudioComponent::audio_buffer] are produced at, the runtime resamples them to the host
+    #[cfg(feature = "frontend")]
+    fn sample_rate(&self) -> Ratio<u32>;
+
+    /// Shared with the runtime, which takes frames out of it as they are played
+    fn audio_buffer(&self) -> AudioBuffer;
+}
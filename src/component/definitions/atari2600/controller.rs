@@ -1,26 +1,55 @@
 use crate::{
-    component::{input::InputComponent, Component},
+    component::{input::InputComponent, Component, ComponentVersion, FromConfig, SemanticVersion},
     input::{gamepad::GamepadInput, EmulatedGamepad, Input},
+    machine::peripheral::PeripheralDevice,
+    rom::RomManager,
 };
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
+
+pub const JOYSTICK_INPUTS: &[Input] = &[
+    // Joystick
+    Input::Gamepad(GamepadInput::LeftStickUp),
+    Input::Gamepad(GamepadInput::LeftStickDown),
+    Input::Gamepad(GamepadInput::LeftStickLeft),
+    Input::Gamepad(GamepadInput::LeftStickRight),
+    // A button
+    Input::Gamepad(GamepadInput::FPadDown),
+];
 
 pub struct Atari2600Controller {
     assigned_controller: Option<Arc<EmulatedGamepad>>,
 }
 
+impl Atari2600Controller {
+    /// For plugging into a controller port
+    pub fn peripheral(rom_manager: Arc<RomManager>, _port: &'static str) -> PeripheralDevice {
+        PeripheralDevice {
+            input: Some(Arc::new(Mutex::new(Self::from_config(rom_manager, ())))),
+            ..Default::default()
+        }
+    }
+}
+
 impl Component for Atari2600Controller {}
 
+impl ComponentVersion for Atari2600Controller {
+    const NAME: &'static str = "atari2600_controller";
+    const VERSION: SemanticVersion = SemanticVersion::new(1, 0, 0);
+}
+
+impl FromConfig for Atari2600Controller {
+    type Config = ();
+
+    fn from_config(_rom_manager: Arc<RomManager>, _config: Self::Config) -> Self {
+        Self {
+            assigned_controller: None,
+        }
+    }
+}
+
 impl InputComponent for Atari2600Controller {
     fn registered_inputs(&self) -> &'static [Input] {
-        &[
-            // Joystick
-            Input::Gamepad(GamepadInput::LeftStickUp),
-            Input::Gamepad(GamepadInput::LeftStickDown),
-            Input::Gamepad(GamepadInput::LeftStickLeft),
-            Input::Gamepad(GamepadInput::LeftStickRight),
-            // A button
-            Input::Gamepad(GamepadInput::FPadDown),
-        ]
+        JOYSTICK_INPUTS
     }
 
     fn assign_controller(&mut self, controller: Arc<EmulatedGamepad>) {
@@ -1,37 +1,197 @@
 use std::sync::Arc;
 
+use super::timer::Chip8TimerHandle;
 use crate::{
     component::{
-        audio::AudioComponent, memory::MemoryTranslationTable, schedulable::SchedulableComponent,
-        Component, FromConfig,
+        audio::{AudioBuffer, AudioComponent},
+        memory::MemoryTranslationTable,
+        schedulable::SchedulableComponent,
+        snapshot::SnapshotableComponent,
+        Component, ComponentVersion, FromConfig, SemanticVersion,
     },
+    machine::event_bus::EventBus,
     rom::RomManager,
 };
 use num::rational::Ratio;
+use serde::Serialize;
+
+const SAMPLE_RATE: u32 = 44100;
+/// Same as the delay timer
+const TICK_RATE: u32 = 60;
+const TONE_AMPLITUDE: i16 = i16::MAX / 4;
+
+/// Where the sound timer is published on the event bus
+pub const SOUND_TIMER_TOPIC: &str = "chip8_sound_timer";
+
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct Chip8AudioConfig {
+    /// Pitch of the beep in Hz. Every interpreter picked its own, the default is close to the COSMAC VIP
+    pub tone_frequency: u32,
+}
+
+impl Default for Chip8AudioConfig {
+    fn default() -> Self {
+        Self {
+            tone_frequency: 440,
+        }
+    }
+}
 
 pub struct Chip8Audio {
+    config: Chip8AudioConfig,
     // The CPU will set this according to what the program wants
-    pub sound_timer: u8,
+    sound_timer: Chip8TimerHandle,
+    beeper_enabled: bool,
+    /// How far through a period of the tone the next sample is, in 1/[SAMPLE_RATE]ths of a period. Carried between
+    /// ticks so the wave has no seams
+    phase: u32,
+    audio_buffer: AudioBuffer,
+}
+
+impl Chip8Audio {
+    pub fn handle(&self) -> Chip8TimerHandle {
+        self.sound_timer.clone()
+    }
+
+    /// A tick worth of square wave while the sound timer is running, silence otherwise
+    fn generate_frames(&mut self) {
+        let frames = SAMPLE_RATE / TICK_RATE;
+
+        if !self.beeper_enabled || self.sound_timer.get() == 0 {
+            // Starting the next beep on a edge sounds the same every time
+            self.phase = 0;
+            self.audio_buffer.extend((0..frames).map(|_| [0; 2]));
+            return;
+        }
+
+        self.audio_buffer.extend((0..frames).map(|_| {
+            let sample = if self.phase < SAMPLE_RATE / 2 {
+                TONE_AMPLITUDE
+            } else {
+                -TONE_AMPLITUDE
+            };
+            self.phase = (self.phase + self.config.tone_frequency) % SAMPLE_RATE;
+
+            [sample; 2]
+        }));
+    }
+}
+
+impl Component for Chip8Audio {
+    fn publish_handles(&self, event_bus: &mut EventBus) {
+        event_bus.publish(SOUND_TIMER_TOPIC, self.handle());
+    }
+}
+
+impl SnapshotableComponent for Chip8Audio {
+    fn save_snapshot(&mut self) -> rmpv::Value {
+        rmpv::Value::from(self.sound_timer.get())
+    }
+
+    fn load_snapshot(&mut self, state: rmpv::Value) {
+        self.sound_timer.set(state.as_u64().unwrap() as u8);
+    }
 }
 
-impl Component for Chip8Audio {}
+impl ComponentVersion for Chip8Audio {
+    const NAME: &'static str = "chip8_audio";
+    const VERSION: SemanticVersion = SemanticVersion::new(1, 0, 0);
+}
 
 impl FromConfig for Chip8Audio {
-    type Config = ();
+    type Config = Chip8AudioConfig;
 
-    fn from_config(_rom_manager: Arc<RomManager>, _config: Self::Config) -> Self {
-        Self { sound_timer: 0 }
+    fn from_config(_rom_manager: Arc<RomManager>, config: Self::Config) -> Self {
+        assert!(
+            config.tone_frequency != 0 && config.tone_frequency < SAMPLE_RATE / 2,
+            "Tone frequency must be audible"
+        );
+
+        Self {
+            config,
+            sound_timer: Chip8TimerHandle::default(),
+            beeper_enabled: true,
+            phase: 0,
+            audio_buffer: AudioBuffer::new(Ratio::from_integer(SAMPLE_RATE)),
+        }
     }
 }
 
 impl SchedulableComponent for Chip8Audio {
     fn tick_rate(&self) -> Ratio<u32> {
-        Ratio::new(60, 1)
+        Ratio::from_integer(TICK_RATE)
     }
 
+    // The timer counts down at the same rate as the delay timer, and a beep lasts exactly as many ticks as it was set to
     fn tick(&mut self, _: &MemoryTranslationTable) {
-        self.sound_timer = self.sound_timer.saturating_sub(1);
+        self.generate_frames();
+        self.sound_timer.decrement();
     }
 }
 
-impl AudioComponent for Chip8Audio {}
+impl AudioComponent for Chip8Audio {
+    #[cfg(feature = "frontend")]
+    fn audio_channels(&self) -> &'static [&'static str] {
+        &["Beeper"]
+    }
+
+    #[cfg(feature = "frontend")]
+    fn set_audio_channel_enabled(&mut self, channel: usize, enabled: bool) {
+        if channel == 0 {
+            self.beeper_enabled = enabled;
+        }
+    }
+
+    #[cfg(feature = "frontend")]
+    fn is_audio_channel_enabled(&self, channel: usize) -> bool {
+        channel == 0 && self.beeper_enabled
+    }
+
+    #[cfg(feature = "frontend")]
+    fn sample_rate(&self) -> Ratio<u32> {
+        Ratio::from_integer(SAMPLE_RATE)
+    }
+
+    fn audio_buffer(&self) -> AudioBuffer {
+        self.audio_buffer.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn beeps_while_timer_runs() {
+        let mut audio = Chip8Audio::from_config(
+            Arc::new(RomManager::default()),
+            Chip8AudioConfig {
+                tone_frequency: 441,
+            },
+        );
+        let audio_buffer = audio.audio_buffer();
+        let memory_translation_table = MemoryTranslationTable::default();
+
+        audio.handle().set(2);
+        for _ in 0..3 {
+            audio.tick(&memory_translation_table);
+        }
+        assert_eq!(audio.handle().get(), 0);
+
+        let frames: Vec<_> = std::iter::from_fn(|| audio_buffer.pop()).collect();
+        let frames_per_tick = (SAMPLE_RATE / TICK_RATE) as usize;
+        assert_eq!(frames.len(), frames_per_tick * 3);
+
+        // 441Hz is exactly 100 samples a period, and the wave carries on across the tick boundary
+        let (beep, silence) = frames.split_at(frames_per_tick * 2);
+        for (index, frame) in beep.iter().enumerate() {
+            let expected = if index % 100 < 50 {
+                TONE_AMPLITUDE
+            } else {
+                -TONE_AMPLITUDE
+            };
+            assert_eq!(*frame, [expected; 2], "Frame {}", index);
+        }
+        assert!(silence.iter().all(|frame| *frame == [0; 2]));
+    }
+}
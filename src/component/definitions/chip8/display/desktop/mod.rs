@@ -1 +1,4 @@
+#[cfg(vulkan)]
 pub mod vulkan;
+#[cfg(wgpu)]
+pub mod wgpu;
@@ -1,14 +1,15 @@
 use crate::{
     component::{
         definitions::chip8::display::{Chip8Display, Chip8DisplayImplementation, InternalState},
-        display::DisplayComponent,
+        display::{color::Pixel, DisplayComponent},
+    },
+    runtime::{
+        desktop::display::vulkan::{VulkanRendering, PIXEL_FORMAT},
+        RenderingBackend,
     },
-    runtime::{desktop::display::vulkan::VulkanRendering, RenderingBackend},
 };
-use bitvec::{prelude::Msb0, view::BitView};
-use nalgebra::{DMatrix, DMatrixViewMut, Point2};
-use palette::Srgba;
-use std::{ops::DerefMut, sync::Arc};
+use nalgebra::DMatrix;
+use std::sync::Arc;
 use vulkano::{
     buffer::{Buffer, BufferCreateInfo, BufferUsage, Subbuffer},
     command_buffer::{
@@ -16,68 +17,25 @@ use vulkano::{
         CommandBufferUsage, CopyBufferToImageInfo, PrimaryCommandBufferAbstract,
     },
     device::Queue,
-    format::Format,
     image::{Image, ImageCreateInfo, ImageType, ImageUsage},
     memory::allocator::{AllocationCreateInfo, MemoryTypeFilter},
     sync::GpuFuture,
 };
 
 pub struct VulkanState {
-    pub staging_buffer: Subbuffer<[Srgba<u8>]>,
+    pub staging_buffer: Subbuffer<[Pixel]>,
     pub render_image: Arc<Image>,
     pub queue: Arc<Queue>,
     pub command_buffer_allocator: Arc<StandardCommandBufferAllocator>,
 }
 
 impl Chip8DisplayImplementation for VulkanState {
-    fn draw_sprite(&mut self, position: Point2<u8>, sprite: &[u8]) -> bool {
-        let mut staging_buffer = self.staging_buffer.write().unwrap();
-        let mut staging_buffer = DMatrixViewMut::from_slice(staging_buffer.deref_mut(), 64, 32);
-
-        let mut collided = false;
-
-        for (y, sprite_row) in sprite.view_bits::<Msb0>().chunks(8).enumerate() {
-            for (x, sprite_pixel) in sprite_row.iter().enumerate() {
-                let x = position.x as usize + x;
-                let y = position.y as usize + y;
-
-                if x >= 64 || y >= 32 {
-                    continue;
-                }
-
-                let old_sprite_pixel = staging_buffer[(x, y)] == Srgba::new(255, 255, 255, 255);
-
-                if *sprite_pixel && old_sprite_pixel {
-                    collided = true;
-                }
-
-                staging_buffer[(x, y)] = if *sprite_pixel ^ old_sprite_pixel {
-                    Srgba::new(255, 255, 255, 255)
-                } else {
-                    Srgba::new(0, 0, 0, 255)
-                };
-            }
-        }
-
-        collided
-    }
-
-    fn clear_display(&mut self) {
-        let mut staging_buffer = self.staging_buffer.write().unwrap();
-        staging_buffer.fill(Srgba::new(0, 0, 0, 255));
-    }
-
-    fn get_display_buffer(&mut self) -> DMatrix<Srgba<u8>> {
-        let staging_buffer = self.staging_buffer.read().unwrap();
-        DMatrix::from_vec(64, 32, staging_buffer.to_vec())
-    }
-
-    fn set_screen_buffer(&mut self, buffer: DMatrix<Srgba<u8>>) {
-        let mut staging_buffer = self.staging_buffer.write().unwrap();
-        staging_buffer.copy_from_slice(buffer.as_slice());
-    }
+    fn commit_display(&mut self, screen_buffer: &DMatrix<Pixel>) {
+        self.staging_buffer
+            .write()
+            .unwrap()
+            .copy_from_slice(screen_buffer.as_slice());
 
-    fn commit_display(&mut self) {
         let mut command_buffer = AutoCommandBufferBuilder::primary(
             &self.command_buffer_allocator,
             self.queue.queue_family_index(),
@@ -112,6 +70,8 @@ impl DisplayComponent<VulkanRendering> for Chip8Display {
         &mut self,
         initialization_data: <VulkanRendering as RenderingBackend>::ComponentInitializationData,
     ) {
+        let (width, height) = self.handle.dimensions();
+
         let staging_buffer = Buffer::from_iter(
             initialization_data.memory_allocator.clone(),
             BufferCreateInfo {
@@ -122,7 +82,7 @@ impl DisplayComponent<VulkanRendering> for Chip8Display {
                 memory_type_filter: MemoryTypeFilter::HOST_RANDOM_ACCESS,
                 ..Default::default()
             },
-            vec![Srgba::new(0, 0, 0, 0); 64 * 32],
+            vec![Pixel::new(0, 0, 0, 0); width * height],
         )
         .unwrap();
 
@@ -130,8 +90,8 @@ impl DisplayComponent<VulkanRendering> for Chip8Display {
             initialization_data.memory_allocator.clone(),
             ImageCreateInfo {
                 image_type: ImageType::Dim2d,
-                format: Format::R8G8B8A8_SRGB,
-                extent: [64, 32, 1],
+                format: PIXEL_FORMAT,
+                extent: [width as u32, height as u32, 1],
                 usage: ImageUsage::TRANSFER_SRC | ImageUsage::TRANSFER_DST | ImageUsage::SAMPLED,
                 ..Default::default()
             },
@@ -155,4 +115,13 @@ impl DisplayComponent<VulkanRendering> for Chip8Display {
 
         render_image
     }
+
+    fn skip_frames(&mut self, count: u32) {
+        self.request_frame_skip(count);
+    }
+
+    // The image and the staging buffer belong to the device of the backend
+    fn release_display(&mut self) {
+        self.state = None;
+    }
 }
@@ -0,0 +1,87 @@
+use crate::{
+    component::{
+        definitions::chip8::display::{Chip8Display, Chip8DisplayImplementation, InternalState},
+        display::{color::Pixel, DisplayComponent},
+    },
+    runtime::{
+        desktop::display::wgpu::{WgpuRendering, PIXEL_FORMAT},
+        RenderingBackend,
+    },
+};
+use nalgebra::DMatrix;
+use std::sync::Arc;
+use wgpu::{
+    Extent3d, ImageDataLayout, Queue, Texture, TextureDescriptor, TextureDimension, TextureUsages,
+};
+
+pub struct WgpuState {
+    pub render_texture: Arc<Texture>,
+    pub queue: Arc<Queue>,
+}
+
+impl Chip8DisplayImplementation for WgpuState {
+    fn commit_display(&mut self, screen_buffer: &DMatrix<Pixel>) {
+        // The whole texture is written so there is nothing to clear, it lands before the next submission draws it
+        self.queue.write_texture(
+            self.render_texture.as_image_copy(),
+            bytemuck::cast_slice(screen_buffer.as_slice()),
+            ImageDataLayout {
+                offset: 0,
+                bytes_per_row: Some(4 * self.render_texture.width()),
+                rows_per_image: None,
+            },
+            self.render_texture.size(),
+        );
+    }
+}
+
+impl DisplayComponent<WgpuRendering> for Chip8Display {
+    fn initialize_display(
+        &mut self,
+        initialization_data: <WgpuRendering as RenderingBackend>::ComponentInitializationData,
+    ) {
+        let (width, height) = self.handle.dimensions();
+
+        let render_texture = initialization_data
+            .device
+            .create_texture(&TextureDescriptor {
+                label: Some("chip8 display"),
+                size: Extent3d {
+                    width: width as u32,
+                    height: height as u32,
+                    depth_or_array_layers: 1,
+                },
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: TextureDimension::D2,
+                format: PIXEL_FORMAT,
+                usage: TextureUsages::TEXTURE_BINDING
+                    | TextureUsages::COPY_SRC
+                    | TextureUsages::COPY_DST,
+                view_formats: &[],
+            });
+
+        self.state = Some(InternalState::Wgpu(WgpuState {
+            render_texture: Arc::new(render_texture),
+            queue: initialization_data.queue,
+        }));
+    }
+
+    fn display_data(&self) -> &<WgpuRendering as RenderingBackend>::ComponentDisplayBuffer {
+        let Some(InternalState::Wgpu(WgpuState { render_texture, .. })) = self.state.as_ref()
+        else {
+            panic!("Display has not been initialized");
+        };
+
+        render_texture
+    }
+
+    fn skip_frames(&mut self, count: u32) {
+        self.request_frame_skip(count);
+    }
+
+    // The texture belongs to the device of the backend
+    fn release_display(&mut self) {
+        self.state = None;
+    }
+}
@@ -1,74 +1,199 @@
 use super::Chip8Kind;
 use crate::{
     component::{
-        memory::MemoryTranslationTable, schedulable::SchedulableComponent,
-        snapshot::SnapshotableComponent, Component, FromConfig,
+        display::color::Pixel, memory::MemoryTranslationTable, schedulable::SchedulableComponent,
+        snapshot::SnapshotableComponent, Component, ComponentVersion, FromConfig, SemanticVersion,
     },
     rom::RomManager,
 };
 use nalgebra::{DMatrix, Point2};
 use num::rational::Ratio;
-use palette::Srgba;
 use serde::{Deserialize, Serialize};
-use std::sync::Arc;
+use std::sync::{
+    atomic::{AtomicU64, Ordering},
+    Arc,
+};
 
 #[cfg(desktop)]
 mod desktop;
-#[cfg(desktop)]
+#[cfg(vulkan)]
 use desktop::vulkan::VulkanState;
+#[cfg(wgpu)]
+use desktop::wgpu::WgpuState;
 
 mod software;
 use software::SoftwareState;
 
+pub const SCREEN_WIDTH: usize = 64;
+pub const SCREEN_HEIGHT: usize = 32;
+/// The SuperChip8 doubles both axes
+pub const HIRES_SCREEN_WIDTH: usize = 128;
+pub const HIRES_SCREEN_HEIGHT: usize = 64;
+
+const PIXEL_ON: Pixel = Pixel::new(255, 255, 255, 255);
+const PIXEL_OFF: Pixel = Pixel::new(0, 0, 0, 255);
+
+/// Position of a pixel on the screen, with x being the column. Screen buffers are indexed the same way, so the number
+/// of rows of a screen buffer is its width
+type ScreenPosition = Point2<usize>;
+
+/// One row of the screen, wide enough for the hires screen. Rows are used as a single number with the left most pixel
+/// in the most significant bit of the screen width, so low resolution rows only take up the lower word
+type ScreenRow = [AtomicU64; 2];
+
+fn load_row(row: &ScreenRow) -> u128 {
+    ((row[1].load(Ordering::Relaxed) as u128) << 64) | row[0].load(Ordering::Relaxed) as u128
+}
+
+fn store_row(row: &ScreenRow, value: u128) {
+    row[0].store(value as u64, Ordering::Relaxed);
+    row[1].store((value >> 64) as u64, Ordering::Relaxed);
+}
+
+/// XORs the value into the row, returning what was there before
+fn xor_row(row: &ScreenRow, value: u128) -> u128 {
+    let low = row[0].fetch_xor(value as u64, Ordering::Relaxed);
+    let high = row[1].fetch_xor((value >> 64) as u64, Ordering::Relaxed);
+
+    ((high as u128) << 64) | low as u128
+}
+
+/// The width and height of the screen a kind of Chip8 has
+fn screen_dimensions(kind: Chip8Kind) -> (usize, usize) {
+    match kind {
+        Chip8Kind::SuperChip8 => (HIRES_SCREEN_WIDTH, HIRES_SCREEN_HEIGHT),
+        _ => (SCREEN_WIDTH, SCREEN_HEIGHT),
+    }
+}
+
 #[non_exhaustive]
 enum InternalState {
-    #[cfg(desktop)]
+    #[cfg(vulkan)]
     Vulkan(VulkanState),
+    #[cfg(wgpu)]
+    Wgpu(WgpuState),
     Software(SoftwareState),
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Chip8DisplaySnapshot {
-    screen_buffer: DMatrix<Srgba<u8>>,
+    screen_buffer: DMatrix<Pixel>,
 }
 
-pub struct Chip8Display {
-    config: Chip8DisplayConfig,
-    state: Option<InternalState>,
+/// The screen the processor draws to, one bit per pixel with a [ScreenRow] for each row. It's lock free so drawing
+/// doesn't need the display component, which picks it up on every vblank
+#[derive(Clone, Debug)]
+pub struct Chip8DisplayHandle {
+    kind: Chip8Kind,
+    quirk_sprite_wrapping: bool,
+    width: usize,
+    screen: Arc<[ScreenRow]>,
 }
 
-impl Chip8Display {
-    pub fn draw_sprite(&mut self, position: Point2<u8>, sprite: &[u8]) -> bool {
+impl Chip8DisplayHandle {
+    fn new(config: &Chip8DisplayConfig) -> Self {
+        let (width, height) = screen_dimensions(config.kind);
+
+        Self {
+            kind: config.kind,
+            quirk_sprite_wrapping: config.quirk_sprite_wrapping,
+            width,
+            screen: (0..height).map(|_| Default::default()).collect(),
+        }
+    }
+
+    /// The width and height of the screen in pixels
+    pub fn dimensions(&self) -> (usize, usize) {
+        (self.width, self.screen.len())
+    }
+
+    pub fn draw_sprite(&self, position: Point2<u8>, sprite: &[u8]) -> bool {
         tracing::debug!(
             "Drawing sprite at position {} of dimensions 8x{}",
             position,
             sprite.len()
         );
 
-        let position = match self.config.kind {
-            Chip8Kind::Chip8 | Chip8Kind::Chip48 => Point2::new(position.x % 63, position.y % 31),
-            Chip8Kind::SuperChip8 => todo!(),
+        // The starting position always wraps, only the rest of the sprite is subject to the quirk
+        let position = match self.kind {
+            Chip8Kind::Chip8 | Chip8Kind::Chip48 | Chip8Kind::SuperChip8 => ScreenPosition::new(
+                position.x as usize % self.width,
+                position.y as usize % self.screen.len(),
+            ),
             _ => todo!(),
         };
 
+        blit_sprite(
+            &self.screen,
+            self.width,
+            position,
+            sprite,
+            self.quirk_sprite_wrapping,
+        )
+    }
+
+    pub fn clear_display(&self) {
+        tracing::debug!("Clearing display");
+
+        for row in self.screen.iter() {
+            store_row(row, 0);
+        }
+    }
+
+    fn screen_buffer(&self) -> DMatrix<Pixel> {
+        rasterize_screen(&self.screen, self.width)
+    }
+
+    /// Anything that isn't the on color is considered off
+    fn set_screen_buffer(&self, buffer: &DMatrix<Pixel>) {
+        for (y, row) in self.screen.iter().enumerate() {
+            let value = (0..self.width)
+                .filter(|x| buffer[(*x, y)] == PIXEL_ON)
+                .fold(0, |value, x| value | pixel_mask(x, self.width));
+
+            store_row(row, value);
+        }
+    }
+}
+
+pub struct Chip8Display {
+    handle: Chip8DisplayHandle,
+    state: Option<InternalState>,
+    /// Vblanks left that won't be committed
+    frames_to_skip: u32,
+    /// If the last vblank was skipped
+    skipped_last_frame: bool,
+}
+
+impl Chip8Display {
+    pub fn handle(&self) -> Chip8DisplayHandle {
+        self.handle.clone()
+    }
+
+    fn commit_display(&mut self) {
+        let screen_buffer = self.handle.screen_buffer();
+
         match &mut self.state {
-            #[cfg(desktop)]
-            Some(InternalState::Vulkan(vulkan_state)) => vulkan_state.draw_sprite(position, sprite),
+            #[cfg(vulkan)]
+            Some(InternalState::Vulkan(vulkan_state)) => {
+                vulkan_state.commit_display(&screen_buffer);
+            }
+            #[cfg(wgpu)]
+            Some(InternalState::Wgpu(wgpu_state)) => {
+                wgpu_state.commit_display(&screen_buffer);
+            }
             Some(InternalState::Software(software_state)) => {
-                software_state.draw_sprite(position, sprite)
+                software_state.commit_display(&screen_buffer);
             }
             _ => panic!("Internal state not initialized"),
         }
     }
 
-    pub fn clear_display(&mut self) {
-        tracing::debug!("Clearing display");
-
-        match &mut self.state {
-            #[cfg(desktop)]
-            Some(InternalState::Vulkan(vulkan_state)) => vulkan_state.clear_display(),
-            Some(InternalState::Software(software_state)) => software_state.clear_display(),
-            _ => panic!("Internal state not initialized"),
+    #[cfg(feature = "frontend")]
+    fn request_frame_skip(&mut self, count: u32) {
+        // A frame always makes it through between skips, or a host that never catches up would never see one
+        if self.frames_to_skip == 0 && !self.skipped_last_frame {
+            self.frames_to_skip = count;
         }
     }
 }
@@ -77,15 +202,8 @@ impl Component for Chip8Display {}
 
 impl SnapshotableComponent for Chip8Display {
     fn save_snapshot(&mut self) -> rmpv::Value {
-        let display_buffer = match &mut self.state {
-            #[cfg(desktop)]
-            Some(InternalState::Vulkan(vulkan_state)) => vulkan_state.get_display_buffer(),
-            Some(InternalState::Software(software_state)) => software_state.get_display_buffer(),
-            _ => panic!("Internal state not initialized"),
-        };
-
         rmpv::ext::to_value(Chip8DisplaySnapshot {
-            screen_buffer: display_buffer,
+            screen_buffer: self.handle.screen_buffer(),
         })
         .unwrap()
     }
@@ -93,22 +211,21 @@ impl SnapshotableComponent for Chip8Display {
     fn load_snapshot(&mut self, state: rmpv::Value) {
         let snapshot: Chip8DisplaySnapshot = rmpv::ext::from_value(state).unwrap();
 
-        match &mut self.state {
-            #[cfg(desktop)]
-            Some(InternalState::Vulkan(vulkan_state)) => {
-                vulkan_state.set_screen_buffer(snapshot.screen_buffer);
-            }
-            Some(InternalState::Software(software_state)) => {
-                software_state.set_screen_buffer(snapshot.screen_buffer);
-            }
-            _ => panic!("Internal state not initialized"),
-        }
+        self.handle.set_screen_buffer(&snapshot.screen_buffer);
+        self.commit_display();
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Serialize)]
 pub struct Chip8DisplayConfig {
     pub kind: Chip8Kind,
+    /// Whether sprites going past the edge of the screen wrap around instead of being clipped
+    pub quirk_sprite_wrapping: bool,
+}
+
+impl ComponentVersion for Chip8Display {
+    const NAME: &'static str = "chip8_display";
+    const VERSION: SemanticVersion = SemanticVersion::new(1, 0, 0);
 }
 
 impl FromConfig for Chip8Display {
@@ -116,18 +233,67 @@ impl FromConfig for Chip8Display {
 
     fn from_config(_rom_manager: Arc<RomManager>, config: Self::Config) -> Self {
         Chip8Display {
-            config,
+            handle: Chip8DisplayHandle::new(&config),
             state: None,
+            frames_to_skip: 0,
+            skipped_last_frame: false,
+        }
+    }
+}
+
+#[inline]
+fn pixel_mask(x: usize, width: usize) -> u128 {
+    1 << (width - 1 - x)
+}
+
+fn rasterize_screen(screen: &[ScreenRow], width: usize) -> DMatrix<Pixel> {
+    DMatrix::from_fn(width, screen.len(), |x, y| {
+        if load_row(&screen[y]) & pixel_mask(x, width) != 0 {
+            PIXEL_ON
+        } else {
+            PIXEL_OFF
+        }
+    })
+}
+
+/// XORs a 8 pixel wide sprite onto the screen, returning true if any pixel was turned off
+fn blit_sprite(
+    screen: &[ScreenRow],
+    width: usize,
+    position: ScreenPosition,
+    sprite: &[u8],
+    wrap: bool,
+) -> bool {
+    let mut collided = false;
+    let row_mask = u128::MAX >> (u128::BITS as usize - width);
+
+    for (sprite_y, sprite_row) in sprite.iter().enumerate() {
+        let mut y = position.y + sprite_y;
+
+        if wrap {
+            y %= screen.len();
+        } else if y >= screen.len() {
+            break;
         }
+
+        // Line the sprite row up with the left edge and then move it into place
+        let sprite_row = (*sprite_row as u128) << (width - 8);
+        let sprite_row = if wrap && position.x != 0 {
+            ((sprite_row >> position.x) | (sprite_row << (width - position.x))) & row_mask
+        } else {
+            sprite_row >> position.x
+        };
+
+        let old_row = xor_row(&screen[y], sprite_row);
+        collided |= old_row & sprite_row != 0;
     }
+
+    collided
 }
 
 trait Chip8DisplayImplementation {
-    fn draw_sprite(&mut self, position: Point2<u8>, sprite: &[u8]) -> bool;
-    fn clear_display(&mut self);
-    fn get_display_buffer(&mut self) -> DMatrix<Srgba<u8>>;
-    fn set_screen_buffer(&mut self, buffer: DMatrix<Srgba<u8>>);
-    fn commit_display(&mut self);
+    /// Uploads the screen as it is at vblank
+    fn commit_display(&mut self, screen_buffer: &DMatrix<Pixel>);
 }
 
 impl SchedulableComponent for Chip8Display {
@@ -137,15 +303,146 @@ impl SchedulableComponent for Chip8Display {
     }
 
     fn tick(&mut self, _memory_translation_table: &MemoryTranslationTable) {
-        match &mut self.state {
-            #[cfg(desktop)]
-            Some(InternalState::Vulkan(vulkan_state)) => {
-                vulkan_state.commit_display();
-            }
-            Some(InternalState::Software(software_state)) => {
-                software_state.commit_display();
-            }
-            _ => panic!("Internal state not initialized"),
+        if self.frames_to_skip != 0 {
+            self.frames_to_skip -= 1;
+            self.skipped_last_frame = true;
+            return;
         }
+
+        self.skipped_last_frame = false;
+        self.commit_display();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn blank_screen() -> Vec<ScreenRow> {
+        (0..SCREEN_HEIGHT).map(|_| Default::default()).collect()
+    }
+
+    #[test]
+    fn sprite_axes() {
+        let screen = blank_screen();
+
+        // One pixel in from the left on the first row, the left most pixel on the second
+        blit_sprite(
+            &screen,
+            SCREEN_WIDTH,
+            ScreenPosition::new(10, 5),
+            &[0b0100_0000, 0b1000_0000],
+            false,
+        );
+
+        let screen = rasterize_screen(&screen, SCREEN_WIDTH);
+        assert_eq!(screen[(11, 5)], PIXEL_ON);
+        assert_eq!(screen[(10, 6)], PIXEL_ON);
+        assert_eq!(screen.iter().filter(|pixel| **pixel == PIXEL_ON).count(), 2);
+    }
+
+    #[test]
+    fn sprite_collision() {
+        let screen = blank_screen();
+        let position = ScreenPosition::new(0, 0);
+
+        assert!(!blit_sprite(
+            &screen,
+            SCREEN_WIDTH,
+            position,
+            &[0b1111_0000],
+            false
+        ));
+        // Drawing over it again turns it off
+        assert!(blit_sprite(
+            &screen,
+            SCREEN_WIDTH,
+            position,
+            &[0b1000_0000],
+            false
+        ));
+        assert_eq!(rasterize_screen(&screen, SCREEN_WIDTH)[(0, 0)], PIXEL_OFF);
+        assert_eq!(rasterize_screen(&screen, SCREEN_WIDTH)[(1, 0)], PIXEL_ON);
+        // Drawing only over unset pixels is no collision
+        assert!(!blit_sprite(
+            &screen,
+            SCREEN_WIDTH,
+            position,
+            &[0b0000_1111],
+            false
+        ));
+    }
+
+    #[test]
+    fn sprite_clipping_and_wrapping() {
+        let sprite = [0xff, 0xff];
+        let position = ScreenPosition::new(SCREEN_WIDTH - 4, SCREEN_HEIGHT - 1);
+
+        let clipped_screen = blank_screen();
+        blit_sprite(&clipped_screen, SCREEN_WIDTH, position, &sprite, false);
+        let clipped_screen = rasterize_screen(&clipped_screen, SCREEN_WIDTH);
+        assert_eq!(
+            clipped_screen
+                .iter()
+                .filter(|pixel| **pixel == PIXEL_ON)
+                .count(),
+            4
+        );
+        assert_eq!(clipped_screen[(0, 0)], PIXEL_OFF);
+
+        let wrapped_screen = blank_screen();
+        blit_sprite(&wrapped_screen, SCREEN_WIDTH, position, &sprite, true);
+        let wrapped_screen = rasterize_screen(&wrapped_screen, SCREEN_WIDTH);
+        assert_eq!(
+            wrapped_screen
+                .iter()
+                .filter(|pixel| **pixel == PIXEL_ON)
+                .count(),
+            16
+        );
+        assert_eq!(wrapped_screen[(0, 0)], PIXEL_ON);
+        assert_eq!(wrapped_screen[(3, 0)], PIXEL_ON);
+    }
+
+    #[test]
+    fn hires_sprite_wrapping() {
+        let handle = Chip8DisplayHandle::new(&Chip8DisplayConfig {
+            kind: Chip8Kind::SuperChip8,
+            quirk_sprite_wrapping: true,
+        });
+        assert_eq!(
+            handle.dimensions(),
+            (HIRES_SCREEN_WIDTH, HIRES_SCREEN_HEIGHT)
+        );
+
+        // Straddles the right edge of the upper word and of the screen
+        handle.draw_sprite(Point2::new(60, 0), &[0xff]);
+        handle.draw_sprite(Point2::new(124, 63), &[0xff]);
+
+        let screen = handle.screen_buffer();
+        assert_eq!(screen[(60, 0)], PIXEL_ON);
+        assert_eq!(screen[(67, 0)], PIXEL_ON);
+        assert_eq!(screen[(127, 63)], PIXEL_ON);
+        assert_eq!(screen[(3, 63)], PIXEL_ON);
+        assert_eq!(screen[(4, 63)], PIXEL_OFF);
+        assert_eq!(
+            screen.iter().filter(|pixel| **pixel == PIXEL_ON).count(),
+            16
+        );
+    }
+
+    #[test]
+    fn screen_buffer_roundtrip() {
+        let handle = Chip8DisplayHandle::new(&Chip8DisplayConfig {
+            kind: Chip8Kind::Chip8,
+            quirk_sprite_wrapping: false,
+        });
+        handle.draw_sprite(Point2::new(60, 30), &[0b1010_0101]);
+
+        let screen_buffer = handle.screen_buffer();
+        handle.clear_display();
+        handle.set_screen_buffer(&screen_buffer);
+
+        assert_eq!(handle.screen_buffer(), screen_buffer);
     }
 }
@@ -1,62 +1,27 @@
 use crate::{
     component::{
-        definitions::chip8::display::{Chip8Display, Chip8DisplayImplementation, InternalState},
-        display::DisplayComponent,
+        definitions::chip8::display::{
+            Chip8Display, Chip8DisplayImplementation, InternalState, PIXEL_OFF,
+        },
+        display::{
+            color::{DisplayBuffer, Pixel},
+            DisplayComponent,
+        },
     },
     runtime::{RenderingBackend, SoftwareRendering},
 };
-use bitvec::{prelude::Msb0, view::BitView};
 use nalgebra::DMatrix;
-use palette::Srgba;
 
 pub struct SoftwareState {
-    pub screen_buffer: DMatrix<Srgba<u8>>,
+    /// Always direct, the screen is rasterized for Vulkan anyway
+    pub screen_buffer: DisplayBuffer,
 }
 
 impl Chip8DisplayImplementation for SoftwareState {
-    fn draw_sprite(&mut self, position: nalgebra::Point2<u8>, sprite: &[u8]) -> bool {
-        let mut collided = false;
-
-        for (y, sprite_row) in sprite.view_bits::<Msb0>().chunks(8).enumerate() {
-            for (x, sprite_pixel) in sprite_row.iter().enumerate() {
-                let x = position.x as usize + x;
-                let y = position.y as usize + y;
-
-                if x >= 64 || y >= 32 {
-                    continue;
-                }
-
-                let old_sprite_pixel = self.screen_buffer[(x, y)] == Srgba::new(255, 255, 255, 255);
-
-                if *sprite_pixel && old_sprite_pixel {
-                    collided = true;
-                }
-
-                self.screen_buffer[(x, y)] = if *sprite_pixel ^ old_sprite_pixel {
-                    Srgba::new(255, 255, 255, 255)
-                } else {
-                    Srgba::new(0, 0, 0, 255)
-                };
-            }
+    fn commit_display(&mut self, screen_buffer: &DMatrix<Pixel>) {
+        if let DisplayBuffer::Direct(pixels) = &mut self.screen_buffer {
+            pixels.copy_from(screen_buffer);
         }
-
-        collided
-    }
-
-    fn clear_display(&mut self) {
-        self.screen_buffer.fill(Srgba::new(0, 0, 0, 255));
-    }
-
-    fn get_display_buffer(&mut self) -> DMatrix<Srgba<u8>> {
-        self.screen_buffer.clone()
-    }
-
-    fn set_screen_buffer(&mut self, buffer: DMatrix<Srgba<u8>>) {
-        self.screen_buffer = buffer;
-    }
-
-    fn commit_display(&mut self) {
-        // We don't use an extra staging buffer
     }
 }
 
@@ -65,7 +30,8 @@ impl DisplayComponent<SoftwareRendering> for Chip8Display {
         &mut self,
         _initialization_data: <SoftwareRendering as RenderingBackend>::ComponentInitializationData,
     ) {
-        let screen_buffer = DMatrix::from_element(64, 32, Srgba::new(0, 0, 0, 255));
+        let (width, height) = self.handle.dimensions();
+        let screen_buffer = DMatrix::from_element(width, height, PIXEL_OFF).into();
         self.state = Some(InternalState::Software(SoftwareState { screen_buffer }));
     }
 
@@ -77,4 +43,38 @@ impl DisplayComponent<SoftwareRendering> for Chip8Display {
 
         screen_buffer
     }
+
+    #[cfg(feature = "frontend")]
+    fn skip_frames(&mut self, count: u32) {
+        self.request_frame_skip(count);
+    }
+
+    #[cfg(feature = "frontend")]
+    fn dump_display_data(&mut self) -> Option<DMatrix<Pixel>> {
+        let Some(InternalState::Software(_)) = self.state.as_ref() else {
+            return None;
+        };
+
+        Some(self.handle.screen_buffer())
+    }
+
+    #[cfg(feature = "frontend")]
+    fn import_display_data(&mut self, buffer: DMatrix<Pixel>) -> bool {
+        let Some(InternalState::Software(software_state)) = self.state.as_mut() else {
+            return false;
+        };
+
+        if buffer.shape() != software_state.screen_buffer.dimensions() {
+            tracing::error!(
+                "Imported image is {:?} but the display is {:?}",
+                buffer.shape(),
+                software_state.screen_buffer.dimensions()
+            );
+            return false;
+        }
+
+        self.handle.set_screen_buffer(&buffer);
+        self.commit_display();
+        true
+    }
 }
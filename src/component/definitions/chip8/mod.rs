@@ -1,10 +1,19 @@
+#[cfg(feature = "chip8")]
 pub mod audio;
 pub mod display;
+#[cfg(feature = "chip8")]
 pub mod processor;
+#[cfg(feature = "chip8")]
+pub mod rpl;
+#[cfg(feature = "chip8")]
 pub mod timer;
 
+use serde::Serialize;
+
+// Only some of these have machines yet
+#[allow(dead_code)]
 #[repr(u8)]
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
 pub enum Chip8Kind {
     Chip8,
     Chip8x,
@@ -128,3 +137,46 @@ pub const CHIP8_FONT: [[u8; 5]; 16] = [
         0b10000000,
     ],
 ];
+
+/// The SuperChip8 large digits, 8x10 and only 0 to 9
+#[rustfmt::skip]
+pub const SUPERCHIP8_FONT: [[u8; 10]; 10] = [
+    [0x3c, 0x7e, 0xe7, 0xc3, 0xc3, 0xc3, 0xc3, 0xe7, 0x7e, 0x3c],
+    [0x18, 0x38, 0x58, 0x18, 0x18, 0x18, 0x18, 0x18, 0x18, 0x3c],
+    [0x3e, 0x7f, 0xc3, 0x06, 0x0c, 0x18, 0x30, 0x60, 0xff, 0xff],
+    [0x3c, 0x7e, 0xc3, 0x03, 0x0e, 0x0e, 0x03, 0xc3, 0x7e, 0x3c],
+    [0x06, 0x0e, 0x1e, 0x36, 0x66, 0xc6, 0xff, 0xff, 0x06, 0x06],
+    [0xff, 0xff, 0xc0, 0xc0, 0xfc, 0xfe, 0x03, 0xc3, 0x7e, 0x3c],
+    [0x3e, 0x7c, 0xe0, 0xc0, 0xfc, 0xfe, 0xc3, 0xc3, 0x7e, 0x3c],
+    [0xff, 0xff, 0x03, 0x06, 0x0c, 0x18, 0x30, 0x60, 0x60, 0x60],
+    [0x3c, 0x7e, 0xc3, 0xc3, 0x7e, 0x7e, 0xc3, 0xc3, 0x7e, 0x3c],
+    [0x3c, 0x7e, 0xc3, 0xc3, 0x7f, 0x3f, 0x03, 0x03, 0x3e, 0x7c],
+];
+
+/// Where the large digits are placed, right after the small ones
+pub const SUPERCHIP8_FONT_ADDRESS: usize = CHIP8_FONT.len() * CHIP8_FONT[0].len();
+
+/// Draws the font glyphs 0 to 7 in a row and then spins forever, small enough to embed for diagnostics
+#[cfg(feature = "frontend")]
+#[rustfmt::skip]
+pub const CHIP8_DEMO: [u8; 22] = [
+    // Clear the screen and zero x, y and the glyph
+    0x00, 0xe0,
+    0x60, 0x00,
+    0x61, 0x00,
+    0x62, 0x00,
+    // Point to the glyph, draw it and move along
+    0xf2, 0x29,
+    0xd0, 0x15,
+    0x70, 0x05,
+    0x72, 0x01,
+    // Loop until 8 glyphs are drawn
+    0x32, 0x08,
+    0x12, 0x08,
+    // Spin
+    0x12, 0x14,
+];
+
+/// How many glyphs [CHIP8_DEMO] draws
+#[cfg(feature = "frontend")]
+pub const CHIP8_DEMO_GLYPHS: usize = 8;
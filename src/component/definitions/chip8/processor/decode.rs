@@ -2,6 +2,14 @@ use super::instruction::{Chip8InstructionSet, InstructionSetChip8, Register};
 use bitvec::{field::BitField, prelude::Msb0, view::BitView};
 use nalgebra::Point2;
 
+fn unknown_instruction(instruction: [u8; 2]) -> Box<dyn std::error::Error> {
+    format!(
+        "Unknown instruction {:#06x}",
+        u16::from_be_bytes(instruction)
+    )
+    .into()
+}
+
 pub fn decode_instruction(
     instruction: [u8; 2],
 ) -> Result<Chip8InstructionSet, Box<dyn std::error::Error>> {
@@ -117,9 +125,7 @@ pub fn decode_instruction(
                     register: Register::try_from(param_register_1).unwrap(),
                     value: Register::try_from(param_register_2).unwrap(),
                 })),
-                _ => {
-                    unimplemented!()
-                }
+                _ => Err(unknown_instruction(instruction)),
             }
         }
         0x9 => {
@@ -131,9 +137,7 @@ pub fn decode_instruction(
                     param_register_1: Register::try_from(param_register_1).unwrap(),
                     param_register_2: Register::try_from(param_register_2).unwrap(),
                 })),
-                _ => {
-                    unimplemented!()
-                }
+                _ => Err(unknown_instruction(instruction)),
             }
         }
         0xa => {
@@ -182,9 +186,7 @@ pub fn decode_instruction(
                 0xa1 => Ok(Chip8InstructionSet::Chip8(InstructionSetChip8::Skup {
                     key: Register::try_from(register).unwrap(),
                 })),
-                _ => {
-                    unimplemented!()
-                }
+                _ => Err(unknown_instruction(instruction)),
             }
         }
         0xf => {
@@ -218,9 +220,7 @@ pub fn decode_instruction(
                 0x65 => Ok(Chip8InstructionSet::Chip8(InstructionSetChip8::Restore {
                     count: register,
                 })),
-                _ => {
-                    unimplemented!("{:#04x?}", instruction);
-                }
+                _ => Err(unknown_instruction(instruction)),
             }
         }
         _ => {
@@ -1,6 +1,7 @@
 use crate::input::{keyboard::KeyboardInput, Input};
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct Chip8Key(pub u8);
 
 impl TryFrom<Input> for Chip8Key {
@@ -1,19 +1,15 @@
-use crate::component::processor::{InstructionSet, InstructionTextRepresentation};
+use crate::component::processor::InstructionSet;
+#[cfg(feature = "frontend")]
+use crate::component::processor::InstructionTextRepresentation;
 
 use nalgebra::Point2;
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "frontend")]
+use std::borrow::Cow;
 use std::ops::Range;
-use thiserror::Error;
-
-#[derive(Debug, Error)]
-pub enum DecodingError {
-    #[error("Invalid instruction: {0:?}")]
-    InvalidInstruction([u8; 2]),
-    #[error("Unknown nom error")]
-    Nom,
-}
 
 #[repr(u8)]
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
 pub enum Register {
     V0,
     V1,
@@ -183,6 +179,8 @@ pub enum InstructionSetChip8 {
     },
 }
 
+// The decoder doesn't produce the extensions yet
+#[allow(dead_code)]
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum InstructionSetSuperChip8 {
     Scrd { amount: u8 },
@@ -192,12 +190,14 @@ pub enum InstructionSetSuperChip8 {
     Rrpl { amount: u8 },
 }
 
+#[allow(dead_code)]
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum InstructionSetXoChip {
     Ssub { bounds: Range<Register> },
     Rsub { bounds: Range<Register> },
 }
 
+#[allow(dead_code)]
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum Chip8InstructionSet {
     Chip8(InstructionSetChip8),
@@ -206,7 +206,10 @@ pub enum Chip8InstructionSet {
 }
 
 impl InstructionSet for Chip8InstructionSet {
+    #[cfg(feature = "frontend")]
     fn to_text_representation(&self) -> InstructionTextRepresentation {
-        todo!()
+        InstructionTextRepresentation {
+            instruction_mnemonic: Cow::Owned(format!("{:?}", self)),
+        }
     }
 }
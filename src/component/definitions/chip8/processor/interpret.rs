@@ -7,7 +7,7 @@ use crate::{
     component::{
         definitions::chip8::{Chip8Kind, CHIP8_FONT},
         memory::MemoryTranslationTable,
-        processor::ProcessorComponent,
+        processor::{InstructionInterpretingError, ProcessorComponent},
     },
     input::Input,
 };
@@ -19,7 +19,6 @@ use bitvec::{
 };
 use nalgebra::Point2;
 use rand::{thread_rng, Rng};
-use ringbuffer::RingBuffer;
 
 impl Chip8Processor {
     pub fn interpret_instruction(
@@ -27,13 +26,13 @@ impl Chip8Processor {
         program_pointer: &mut usize,
         instruction: <Chip8Processor as ProcessorComponent>::InstructionSet,
         memory_translation_table: &MemoryTranslationTable,
-    ) {
+    ) -> Result<(), InstructionInterpretingError> {
         let imported_components = self.imported.as_ref().unwrap();
 
         match instruction {
             Chip8InstructionSet::Chip8(InstructionSetChip8::Sys { syscall }) => match syscall {
                 0x0e0 => {
-                    imported_components.display.lock().unwrap().clear_display();
+                    imported_components.display.clear_display();
                 }
                 0x0ee => {
                     if let Some(address) = self.stack.pop() {
@@ -241,13 +240,12 @@ impl Chip8Processor {
                 height,
             }) => {
                 let mut buffer =
-                    ArrayVec::<_, 16>::from_iter(std::iter::repeat(0).take(height as usize));
+                    ArrayVec::<_, 16>::from_iter(std::iter::repeat_n(0, height as usize));
 
                 let mut cursor = 0;
                 for buffer_section in buffer.chunks_mut(2) {
                     memory_translation_table
-                        .read(self.registers.index as usize + cursor, buffer_section)
-                        .unwrap();
+                        .read_or_open_bus(self.registers.index as usize + cursor, buffer_section)?;
                     cursor += buffer_section.len();
                 }
 
@@ -259,8 +257,6 @@ impl Chip8Processor {
                 // Sets VF to 1 if any pixel turned off otherwise set on
                 self.registers.work_registers[0xf] = imported_components
                     .display
-                    .lock()
-                    .unwrap()
                     .draw_sprite(actual_coords, &buffer)
                     as u8;
             }
@@ -301,7 +297,7 @@ impl Chip8Processor {
                 }
             }
             Chip8InstructionSet::Chip8(InstructionSetChip8::Moved { register }) => {
-                let delay_timer_value = imported_components.timer.lock().unwrap().delay_timer;
+                let delay_timer_value = imported_components.timer.get();
 
                 self.registers.work_registers[register as usize] = delay_timer_value;
             }
@@ -311,12 +307,12 @@ impl Chip8Processor {
             Chip8InstructionSet::Chip8(InstructionSetChip8::Loadd { register }) => {
                 let register_value = self.registers.work_registers[register as usize];
 
-                imported_components.timer.lock().unwrap().delay_timer = register_value;
+                imported_components.timer.set(register_value);
             }
             Chip8InstructionSet::Chip8(InstructionSetChip8::Loads { register }) => {
                 let register_value = self.registers.work_registers[register as usize];
 
-                imported_components.audio.lock().unwrap().sound_timer = register_value;
+                imported_components.sound_timer.set(register_value);
             }
             Chip8InstructionSet::Chip8(InstructionSetChip8::Addi { register }) => {
                 let register_value = self.registers.work_registers[register as usize];
@@ -333,33 +329,25 @@ impl Chip8Processor {
 
                 let [hundreds, tens, ones] = bcd_encode(register_value);
 
-                memory_translation_table
-                    .write(
-                        self.registers.index as usize,
-                        std::slice::from_ref(&hundreds),
-                    )
-                    .unwrap();
-                memory_translation_table
-                    .write(
-                        self.registers.index as usize + 1,
-                        std::slice::from_ref(&tens),
-                    )
-                    .unwrap();
-                memory_translation_table
-                    .write(
-                        self.registers.index as usize + 2,
-                        std::slice::from_ref(&ones),
-                    )
-                    .unwrap();
+                memory_translation_table.write_or_open_bus(
+                    self.registers.index as usize,
+                    std::slice::from_ref(&hundreds),
+                )?;
+                memory_translation_table.write_or_open_bus(
+                    self.registers.index as usize + 1,
+                    std::slice::from_ref(&tens),
+                )?;
+                memory_translation_table.write_or_open_bus(
+                    self.registers.index as usize + 2,
+                    std::slice::from_ref(&ones),
+                )?;
             }
             Chip8InstructionSet::Chip8(InstructionSetChip8::Save { count }) => {
                 for i in 0..=count {
-                    memory_translation_table
-                        .write(
-                            self.registers.index as usize + i as usize,
-                            &self.registers.work_registers[i as usize..=i as usize],
-                        )
-                        .unwrap();
+                    memory_translation_table.write_or_open_bus(
+                        self.registers.index as usize + i as usize,
+                        &self.registers.work_registers[i as usize..=i as usize],
+                    )?;
                 }
 
                 // Only the original chip8 modifies the index register for this operation
@@ -369,12 +357,10 @@ impl Chip8Processor {
             }
             Chip8InstructionSet::Chip8(InstructionSetChip8::Restore { count }) => {
                 for i in 0..=count {
-                    memory_translation_table
-                        .read(
-                            self.registers.index as usize + i as usize,
-                            &mut self.registers.work_registers[i as usize..=i as usize],
-                        )
-                        .unwrap();
+                    memory_translation_table.read_or_open_bus(
+                        self.registers.index as usize + i as usize,
+                        &mut self.registers.work_registers[i as usize..=i as usize],
+                    )?;
                 }
 
                 // Only the original chip8 modifies the index register for this operation
@@ -382,9 +368,11 @@ impl Chip8Processor {
                     self.registers.index = self.registers.index.wrapping_add(count as u16 + 1);
                 }
             }
-            Chip8InstructionSet::SuperChip8(chip8_instruction_set_super) => todo!(),
-            Chip8InstructionSet::XoChip(chip8_instruction_set_xo) => todo!(),
+            Chip8InstructionSet::SuperChip8(_) => todo!(),
+            Chip8InstructionSet::XoChip(_) => todo!(),
         }
+
+        Ok(())
     }
 }
 
@@ -1,12 +1,21 @@
-use super::{audio::Chip8Audio, display::Chip8Display, timer::Chip8Timer, Chip8Kind};
+use super::{
+    audio::SOUND_TIMER_TOPIC,
+    display::{Chip8Display, Chip8DisplayHandle},
+    rpl::{Chip8RplFlags, Chip8RplFlagsHandle},
+    timer::{Chip8TimerHandle, DELAY_TIMER_TOPIC},
+    Chip8Kind,
+};
 use crate::{
     component::{
         input::InputComponent,
         memory::MemoryTranslationTable,
-        processor::{InstructionDecompilingError, ProcessorComponent},
+        processor::{
+            InstructionDecompilingError, InstructionInterpretingError, ProcessorComponent,
+            ProcessorRegister,
+        },
         schedulable::SchedulableComponent,
         snapshot::SnapshotableComponent,
-        Component, FromConfig,
+        Component, ComponentVersion, FromConfig, SemanticVersion,
     },
     input::{keyboard::KeyboardInput, EmulatedGamepad, Input},
     machine::QueryableComponents,
@@ -18,14 +27,14 @@ use input::Chip8Key;
 use instruction::{Chip8InstructionSet, Register};
 use num::rational::Ratio;
 use serde::{Deserialize, Serialize};
-use std::sync::{Arc, Mutex};
+use std::sync::Arc;
 
 mod decode;
 mod input;
 mod instruction;
 mod interpret;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum ExecutionState {
     Normal,
     AwaitingKeyPress { register: Register },
@@ -35,22 +44,26 @@ pub enum ExecutionState {
 
 // This is extremely complex because the chip8 cpu has a lot of non cpu machinery
 
-#[derive(Default, Debug, Deserialize, Serialize)]
+#[derive(Default, Debug, Clone, Deserialize, Serialize)]
 pub struct Chip8ProcessorRegisters {
     work_registers: [u8; 16],
     index: u16,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Serialize)]
 pub struct Chip8ProcessorConfig {
     pub frequency: Ratio<u32>,
     pub kind: Chip8Kind,
 }
 
+/// Handles to the parts of the other components the processor drives, so it never has to lock them
 pub struct ImportedComponents {
-    pub display: Arc<Mutex<Chip8Display>>,
-    pub timer: Arc<Mutex<Chip8Timer>>,
-    pub audio: Arc<Mutex<Chip8Audio>>,
+    pub display: Chip8DisplayHandle,
+    pub timer: Chip8TimerHandle,
+    pub sound_timer: Chip8TimerHandle,
+    /// Only the SuperChip8 has these, read by instructions that aren't decoded yet
+    #[allow(dead_code)]
+    pub rpl_flags: Option<Chip8RplFlagsHandle>,
 }
 
 /// The chip8 cpu is not only a cpu but a display controller btw
@@ -60,34 +73,73 @@ pub struct Chip8Processor {
     registers: Chip8ProcessorRegisters,
     imported: Option<ImportedComponents>,
     controller: Option<Arc<EmulatedGamepad>>,
+    /// Controller generation as of the last tick, for edge detection
+    last_input_generation: u64,
     execution_state: ExecutionState,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Chip8ProcessorSnapshot {
     registers: Chip8ProcessorRegisters,
+    stack: Vec<u16>,
+    execution_state: ExecutionState,
+}
+
+impl Chip8Processor {
+    #[cfg(feature = "frontend")]
+    pub fn registers(&self) -> &Chip8ProcessorRegisters {
+        &self.registers
+    }
+
+    #[cfg(feature = "frontend")]
+    pub fn execution_state(&self) -> ExecutionState {
+        self.execution_state
+    }
 }
 
 impl Component for Chip8Processor {
     fn query_components(&mut self, query: &QueryableComponents) {
         self.imported = Some(ImportedComponents {
-            display: query.query_component("display").unwrap(),
-            timer: query.query_component("timer").unwrap(),
-            audio: query.query_component("audio").unwrap(),
+            display: query
+                .query_component::<Chip8Display>("display")
+                .unwrap()
+                .lock()
+                .unwrap()
+                .handle(),
+            // The timers come off the event bus, the processor doesn't have to know which components own them
+            timer: query.event_bus().subscribe(DELAY_TIMER_TOPIC).unwrap(),
+            sound_timer: query.event_bus().subscribe(SOUND_TIMER_TOPIC).unwrap(),
+            rpl_flags: query
+                .query_component::<Chip8RplFlags>("rpl_flags")
+                .map(|rpl_flags| rpl_flags.lock().unwrap().handle()),
         })
     }
 }
 
 impl SnapshotableComponent for Chip8Processor {
     fn save_snapshot(&mut self) -> rmpv::Value {
-        todo!()
+        rmpv::ext::to_value(Chip8ProcessorSnapshot {
+            registers: self.registers.clone(),
+            stack: self.stack.to_vec(),
+            execution_state: self.execution_state,
+        })
+        .unwrap()
     }
 
-    fn load_snapshot(&mut self, _state: rmpv::Value) {
-        todo!()
+    fn load_snapshot(&mut self, state: rmpv::Value) {
+        let snapshot: Chip8ProcessorSnapshot = rmpv::ext::from_value(state).unwrap();
+
+        self.registers = snapshot.registers;
+        self.stack = snapshot.stack.into_iter().collect();
+        self.execution_state = snapshot.execution_state;
     }
 }
 
+impl ComponentVersion for Chip8Processor {
+    const NAME: &'static str = "chip8_processor";
+    const VERSION: SemanticVersion = SemanticVersion::new(1, 0, 0);
+}
+
 impl FromConfig for Chip8Processor {
     type Config = Chip8ProcessorConfig;
 
@@ -101,6 +153,7 @@ impl FromConfig for Chip8Processor {
             registers: Chip8ProcessorRegisters::default(),
             imported: None,
             controller: None,
+            last_input_generation: 0,
             execution_state: ExecutionState::Normal,
         }
     }
@@ -112,40 +165,32 @@ impl SchedulableComponent for Chip8Processor {
     }
 
     fn tick(&mut self, _: &MemoryTranslationTable) {
+        let Some(controller) = self.controller.as_ref() else {
+            return;
+        };
+
         // The CPU is awaiting a key release
         match self.execution_state {
             ExecutionState::AwaitingKeyPress { register } => {
-                if let Some(key) =
-                    self.controller
-                        .as_ref()
-                        .unwrap()
-                        .iter_pressed()
-                        .find_map(|input| {
-                            if let Ok(key) = Chip8Key::try_from(input) {
-                                Some(key)
-                            } else {
-                                None
-                            }
-                        })
+                // Keys held from before the instruction don't count
+                if let Some(key) = controller
+                    .iter_just_pressed(self.last_input_generation)
+                    .find_map(|input| Chip8Key::try_from(input).ok())
                 {
                     self.execution_state = ExecutionState::AwaitingKeyRelease { register, key };
                 }
             }
-            ExecutionState::AwaitingKeyRelease { register, key } => {
-                if !self
-                    .controller
-                    .as_ref()
-                    .unwrap()
-                    .get_input_state(key.try_into().unwrap())
-                    .unwrap()
-                    .as_digital()
-                {
-                    self.registers.work_registers[register as usize] = key.0;
-                    self.execution_state = ExecutionState::Normal;
-                }
+            ExecutionState::AwaitingKeyRelease { register, key }
+                if controller
+                    .just_released(key.try_into().unwrap(), self.last_input_generation) =>
+            {
+                self.registers.work_registers[register as usize] = key.0;
+                self.execution_state = ExecutionState::Normal;
             }
             _ => {}
         }
+
+        self.last_input_generation = controller.generation();
     }
 }
 
@@ -157,6 +202,23 @@ impl ProcessorComponent for Chip8Processor {
         self.execution_state == ExecutionState::Normal
     }
 
+    fn debug_registers(&self, program_pointer: usize) -> Vec<ProcessorRegister> {
+        const NAMES: [&str; 16] = [
+            "v0", "v1", "v2", "v3", "v4", "v5", "v6", "v7", "v8", "v9", "va", "vb", "vc", "vd",
+            "ve", "vf",
+        ];
+
+        NAMES
+            .into_iter()
+            .zip(self.registers.work_registers)
+            .map(|(name, value)| ProcessorRegister::new(name, 8, value))
+            .chain([
+                ProcessorRegister::new("i", 16, self.registers.index),
+                ProcessorRegister::new("pc", 16, program_pointer as u64),
+            ])
+            .collect()
+    }
+
     fn decompile(
         &self,
         cursor: usize,
@@ -168,9 +230,11 @@ impl ProcessorComponent for Chip8Processor {
         let mut instruction = [0; 2];
         memory_translation_table
             .read(cursor, &mut instruction)
-            .unwrap();
+            .map_err(|_| InstructionDecompilingError::at(cursor, 2, memory_translation_table))?;
 
-        let decompiled_instruction = decode_instruction(instruction).unwrap();
+        let decompiled_instruction = decode_instruction(instruction).map_err(|_| {
+            InstructionDecompilingError::InstructionDecompilingFailed(instruction.to_vec())
+        })?;
 
         Ok((decompiled_instruction, 2))
     }
@@ -180,11 +244,9 @@ impl ProcessorComponent for Chip8Processor {
         program_pointer: &mut usize,
         instruction: Self::InstructionSet,
         memory_translation_table: &MemoryTranslationTable,
-    ) -> Result<(), String> {
+    ) -> Result<(), InstructionInterpretingError> {
         // Delegated here because its really large
-        self.interpret_instruction(program_pointer, instruction, memory_translation_table);
-
-        Ok(())
+        self.interpret_instruction(program_pointer, instruction, memory_translation_table)
     }
 }
 
@@ -0,0 +1,77 @@
+use crate::{
+    component::{
+        snapshot::SnapshotableComponent, Component, ComponentVersion, FromConfig, SemanticVersion,
+    },
+    rom::RomManager,
+};
+use std::sync::{
+    atomic::{AtomicU8, Ordering},
+    Arc,
+};
+
+/// How many registers the SuperChip8 can save, the HP48 only had room for 8
+pub const RPL_FLAG_COUNT: usize = 8;
+
+/// The HP48 user flags that SuperChip8 programs stash registers in, shared with the processor so it can reach them
+/// without locking the component
+#[derive(Clone, Debug, Default)]
+pub struct Chip8RplFlagsHandle(Arc<[AtomicU8; RPL_FLAG_COUNT]>);
+
+impl Chip8RplFlagsHandle {
+    /// Stores the registers into the flags, anything past the flag count is ignored
+    pub fn save(&self, registers: &[u8]) {
+        for (flag, register) in self.0.iter().zip(registers) {
+            flag.store(*register, Ordering::Relaxed);
+        }
+    }
+
+    /// Loads the flags into the registers, anything past the flag count is left alone
+    pub fn restore(&self, registers: &mut [u8]) {
+        for (flag, register) in self.0.iter().zip(registers) {
+            *register = flag.load(Ordering::Relaxed);
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Chip8RplFlags {
+    flags: Chip8RplFlagsHandle,
+}
+
+impl Chip8RplFlags {
+    pub fn handle(&self) -> Chip8RplFlagsHandle {
+        self.flags.clone()
+    }
+}
+
+impl Component for Chip8RplFlags {}
+
+impl SnapshotableComponent for Chip8RplFlags {
+    fn save_snapshot(&mut self) -> rmpv::Value {
+        let mut flags = [0; RPL_FLAG_COUNT];
+        self.flags.restore(&mut flags);
+
+        rmpv::ext::to_value(flags).unwrap()
+    }
+
+    fn load_snapshot(&mut self, state: rmpv::Value) {
+        let flags: [u8; RPL_FLAG_COUNT] = rmpv::ext::from_value(state).unwrap();
+
+        self.flags.save(&flags);
+    }
+}
+
+impl ComponentVersion for Chip8RplFlags {
+    const NAME: &'static str = "chip8_rpl_flags";
+    const VERSION: SemanticVersion = SemanticVersion::new(1, 0, 0);
+}
+
+impl FromConfig for Chip8RplFlags {
+    type Config = ();
+
+    fn from_config(_rom_manager: Arc<RomManager>, _config: Self::Config) -> Self {
+        Self {
+            flags: Chip8RplFlagsHandle::default(),
+        }
+    }
+}
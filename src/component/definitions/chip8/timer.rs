@@ -1,26 +1,84 @@
-use std::sync::Arc;
+use std::sync::{
+    atomic::{AtomicU8, Ordering},
+    Arc,
+};
 
 use crate::{
     component::{
-        memory::MemoryTranslationTable, schedulable::SchedulableComponent, Component, FromConfig,
+        memory::MemoryTranslationTable, schedulable::SchedulableComponent,
+        snapshot::SnapshotableComponent, Component, ComponentVersion, FromConfig, SemanticVersion,
     },
+    machine::event_bus::EventBus,
     rom::RomManager,
 };
 use num::rational::Ratio;
 
+/// Where the delay timer is published on the event bus
+pub const DELAY_TIMER_TOPIC: &str = "chip8_delay_timer";
+
+/// A 60hz down counter shared with the processor, so it can be set and read without locking the component
+#[derive(Clone, Debug, Default)]
+pub struct Chip8TimerHandle(Arc<AtomicU8>);
+
+impl Chip8TimerHandle {
+    pub fn get(&self) -> u8 {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    pub fn set(&self, value: u8) {
+        self.0.store(value, Ordering::Relaxed);
+    }
+
+    /// Counts down by one, stopping at zero
+    pub(super) fn decrement(&self) {
+        let _ = self
+            .0
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |value| {
+                value.checked_sub(1)
+            });
+    }
+}
+
 #[derive(Debug)]
 pub struct Chip8Timer {
     // The CPU will set this according to what the program wants
-    pub delay_timer: u8,
+    delay_timer: Chip8TimerHandle,
+}
+
+impl Chip8Timer {
+    pub fn handle(&self) -> Chip8TimerHandle {
+        self.delay_timer.clone()
+    }
+}
+
+impl Component for Chip8Timer {
+    fn publish_handles(&self, event_bus: &mut EventBus) {
+        event_bus.publish(DELAY_TIMER_TOPIC, self.handle());
+    }
 }
 
-impl Component for Chip8Timer {}
+impl SnapshotableComponent for Chip8Timer {
+    fn save_snapshot(&mut self) -> rmpv::Value {
+        rmpv::Value::from(self.delay_timer.get())
+    }
+
+    fn load_snapshot(&mut self, state: rmpv::Value) {
+        self.delay_timer.set(state.as_u64().unwrap() as u8);
+    }
+}
+
+impl ComponentVersion for Chip8Timer {
+    const NAME: &'static str = "chip8_timer";
+    const VERSION: SemanticVersion = SemanticVersion::new(1, 0, 0);
+}
 
 impl FromConfig for Chip8Timer {
     type Config = ();
 
     fn from_config(_rom_manager: Arc<RomManager>, _config: Self::Config) -> Self {
-        Self { delay_timer: 0 }
+        Self {
+            delay_timer: Chip8TimerHandle::default(),
+        }
     }
 }
 
@@ -30,6 +88,6 @@ impl SchedulableComponent for Chip8Timer {
     }
 
     fn tick(&mut self, _: &MemoryTranslationTable) {
-        self.delay_timer = self.delay_timer.saturating_sub(1);
+        self.delay_timer.decrement();
     }
 }
@@ -0,0 +1,509 @@
+use crate::{
+    component::{
+        memory::{MemoryComponent, PreviewMemoryRecord, ReadMemoryRecord, WriteMemoryRecord},
+        snapshot::SnapshotableComponent,
+        Component, ComponentVersion, FromConfig, SemanticVersion,
+    },
+    machine::{event_bus::EventBus, QueryableComponents},
+    rom::{RomId, RomManager, RomRequirement},
+};
+use arrayvec::ArrayVec;
+use rand::{thread_rng, RngCore};
+use serde::{Deserialize, Serialize};
+use std::{
+    io::Read,
+    ops::Range,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+};
+
+/// Which bank a [BankedMemory] shows, shared with whatever switches it so that needs no lock on the memory
+#[derive(Clone, Debug, Default)]
+pub struct BankSelectHandle(Arc<AtomicUsize>);
+
+impl BankSelectHandle {
+    pub fn get(&self) -> usize {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    /// Banks past the last wrap around, like they do on mapper chips that ignore the upper bits
+    pub fn set(&self, bank: usize) {
+        self.0.store(bank, Ordering::Relaxed);
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub enum BankedMemoryInitialContents {
+    Value {
+        value: u8,
+    },
+    /// Fills the banks in order from the start
+    Array {
+        value: &'static [u8],
+    },
+    /// Fills the banks in order from `offset` into the ROM, which skips headers
+    Rom {
+        rom_id: RomId,
+        offset: usize,
+    },
+    Random,
+}
+
+/// What writes to the window do
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum BankedMemoryWrites {
+    /// Banked ROM
+    Denied,
+    /// Banked RAM
+    Stored,
+    /// The value written picks the bank, for cartridges that latch the bank from the data bus whenever their ROM is
+    /// written to
+    SelectBank,
+}
+
+#[derive(Debug, Serialize)]
+pub struct BankedMemoryConfig {
+    pub readable: bool,
+    pub writes: BankedMemoryWrites,
+    // The maximum word size
+    pub max_word_size: u8,
+    // The penalty for each cycle
+    #[serde(skip)]
+    pub read_cycle_penalty_calculator: fn(range: Range<usize>, denied: bool) -> u64,
+    #[serde(skip)]
+    pub write_cycle_penalty_calculator: fn(range: Range<usize>, denied: bool) -> u64,
+    /// The window the selected bank shows through, which is as large as a bank
+    pub assigned_range: Range<usize>,
+    pub bank_count: usize,
+    /// Selected on power on and reset
+    pub initial_bank: usize,
+    pub initial_contents: BankedMemoryInitialContents,
+    /// Where the [BankSelectHandle] is published on the event bus, for mappers and [BankRegister]s to subscribe to
+    pub bank_select_topic: Option<&'static str>,
+}
+
+impl Default for BankedMemoryConfig {
+    fn default() -> Self {
+        Self {
+            readable: true,
+            writes: BankedMemoryWrites::Denied,
+            max_word_size: 8,
+            read_cycle_penalty_calculator: |_, _| 0,
+            write_cycle_penalty_calculator: |_, _| 0,
+            assigned_range: 0..0,
+            bank_count: 1,
+            initial_bank: 0,
+            initial_contents: BankedMemoryInitialContents::Value { value: 0 },
+            bank_select_topic: None,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BankedMemorySnapshot {
+    pub bank: usize,
+    /// Only kept if the banks can be written, the rest is in the ROM anyway
+    pub memory: Option<Vec<u8>>,
+}
+
+/// Memory with more banks than fit its window, any one of which can be switched in while running
+///
+/// Mapper chips and banked cartridges are built out of these, the bank is picked through a [BankSelectHandle], a
+/// [BankRegister] or writes to the window depending on how the hardware does it
+pub struct BankedMemory {
+    config: BankedMemoryConfig,
+    rom_manager: Arc<RomManager>,
+    /// Every bank back to back
+    buffer: Vec<u8>,
+    bank: BankSelectHandle,
+}
+
+impl BankedMemory {
+    pub fn handle(&self) -> BankSelectHandle {
+        self.bank.clone()
+    }
+
+    pub fn bank_size(&self) -> usize {
+        self.config.assigned_range.len()
+    }
+
+    /// Where `address` in the window is in [Self::buffer] with the current bank
+    fn buffer_range(&self, address: usize, length: usize) -> Range<usize> {
+        let bank = self.bank.get() % self.config.bank_count;
+        let start = bank * self.bank_size() + address - self.config.assigned_range.start;
+
+        start..start + length
+    }
+}
+
+impl Component for BankedMemory {
+    fn reset(&mut self) {
+        initialize_internal_buffer(&self.config, &mut self.buffer, &self.rom_manager);
+        self.bank.set(self.config.initial_bank);
+    }
+
+    fn publish_handles(&self, event_bus: &mut EventBus) {
+        if let Some(topic) = self.config.bank_select_topic {
+            event_bus.publish(topic, self.handle());
+        }
+    }
+}
+
+impl SnapshotableComponent for BankedMemory {
+    fn save_snapshot(&mut self) -> rmpv::Value {
+        let state = BankedMemorySnapshot {
+            bank: self.bank.get(),
+            memory: (self.config.writes == BankedMemoryWrites::Stored).then(|| self.buffer.clone()),
+        };
+
+        rmpv::ext::to_value(&state).unwrap()
+    }
+
+    fn load_snapshot(&mut self, state: rmpv::Value) {
+        let state = rmpv::ext::from_value::<BankedMemorySnapshot>(state).unwrap();
+
+        self.bank.set(state.bank);
+        if let Some(memory) = state.memory {
+            // This also does size validation
+            self.buffer.copy_from_slice(&memory);
+        }
+    }
+}
+
+impl ComponentVersion for BankedMemory {
+    const NAME: &'static str = "banked_memory";
+    const VERSION: SemanticVersion = SemanticVersion::new(1, 0, 0);
+}
+
+impl FromConfig for BankedMemory {
+    type Config = BankedMemoryConfig;
+
+    fn from_config(rom_manager: Arc<RomManager>, config: Self::Config) -> Self {
+        assert!(
+            [1, 2, 4, 8].contains(&config.max_word_size),
+            "Invalid word size"
+        );
+        assert!(
+            !config.assigned_range.is_empty(),
+            "Memory assigned must be non-empty"
+        );
+        assert!(config.bank_count != 0, "There has to be at least one bank");
+
+        let mut buffer = vec![0; config.assigned_range.len() * config.bank_count];
+        initialize_internal_buffer(&config, &mut buffer, &rom_manager);
+        let bank = BankSelectHandle::default();
+        bank.set(config.initial_bank);
+
+        Self {
+            config,
+            rom_manager,
+            buffer,
+            bank,
+        }
+    }
+}
+
+fn initialize_internal_buffer(
+    config: &BankedMemoryConfig,
+    buffer: &mut [u8],
+    rom_manager: &RomManager,
+) {
+    match config.initial_contents {
+        BankedMemoryInitialContents::Value { value } => {
+            buffer.fill(value);
+        }
+        BankedMemoryInitialContents::Random => {
+            thread_rng().fill_bytes(buffer);
+        }
+        BankedMemoryInitialContents::Array { value: data } => {
+            buffer.fill(0);
+            buffer[..data.len()].copy_from_slice(data);
+        }
+        BankedMemoryInitialContents::Rom { rom_id, offset } => {
+            let mut rom_buffer = Vec::new();
+
+            let mut rom_file = rom_manager.open(rom_id, RomRequirement::Required).unwrap();
+            rom_file.read_to_end(&mut rom_buffer).unwrap();
+
+            // ROMs smaller than every bank together leave the rest empty
+            let rom_buffer = rom_buffer.get(offset..).unwrap_or_default();
+            let length = rom_buffer.len().min(buffer.len());
+            buffer.fill(0);
+            buffer[..length].copy_from_slice(&rom_buffer[..length]);
+        }
+    }
+}
+
+impl MemoryComponent for BankedMemory {
+    fn assigned_memory_range(&self) -> Range<usize> {
+        self.config.assigned_range.clone()
+    }
+
+    fn read_memory(
+        &mut self,
+        address: usize,
+        buffer: &mut [u8],
+        records: &mut ArrayVec<(Range<usize>, ReadMemoryRecord), 8>,
+    ) -> u64 {
+        debug_assert!(
+            [1, 2, 4, 8].contains(&buffer.len()),
+            "Invalid memory access size {}",
+            buffer.len()
+        );
+
+        let affected_range = address..address + buffer.len();
+
+        if !self.config.readable || buffer.len() > self.config.max_word_size as usize {
+            records.push((affected_range.clone(), ReadMemoryRecord::Denied));
+
+            return (self.config.read_cycle_penalty_calculator)(affected_range, true);
+        }
+
+        buffer.copy_from_slice(&self.buffer[self.buffer_range(address, buffer.len())]);
+
+        (self.config.read_cycle_penalty_calculator)(affected_range, false)
+    }
+
+    fn write_memory(
+        &mut self,
+        address: usize,
+        buffer: &[u8],
+        records: &mut ArrayVec<(Range<usize>, WriteMemoryRecord), 8>,
+    ) -> u64 {
+        debug_assert!(
+            [1, 2, 4, 8].contains(&buffer.len()),
+            "Invalid memory access size {}",
+            buffer.len()
+        );
+
+        let affected_range = address..address + buffer.len();
+
+        if self.config.writes == BankedMemoryWrites::Denied
+            || buffer.len() > self.config.max_word_size as usize
+        {
+            records.push((affected_range.clone(), WriteMemoryRecord::Denied));
+
+            return (self.config.write_cycle_penalty_calculator)(affected_range, true);
+        }
+
+        if self.config.writes == BankedMemoryWrites::SelectBank {
+            self.bank.set(read_le(buffer));
+        } else {
+            let buffer_range = self.buffer_range(address, buffer.len());
+            self.buffer[buffer_range].copy_from_slice(buffer);
+        }
+
+        (self.config.write_cycle_penalty_calculator)(affected_range, false)
+    }
+
+    fn preview_memory(
+        &mut self,
+        address: usize,
+        buffer: &mut [u8],
+        records: &mut ArrayVec<(Range<usize>, PreviewMemoryRecord), 8>,
+    ) {
+        if !self.config.readable {
+            records.push((address..address + buffer.len(), PreviewMemoryRecord::Denied));
+            return;
+        }
+
+        buffer.copy_from_slice(&self.buffer[self.buffer_range(address, buffer.len())]);
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct BankRegisterConfig {
+    /// As wide as the register, which is little endian
+    pub assigned_range: Range<usize>,
+    /// Where the [BankSelectHandle] of the memory this switches is published
+    pub bank_select_topic: &'static str,
+    /// Bits of the written value that pick the bank, the rest are left for whatever else shares the register
+    pub mask: usize,
+}
+
+/// A register somewhere on the bus that picks the bank of a [BankedMemory] when written, and reads back the bank
+///
+/// Holds no state of its own, so there is nothing to snapshot
+pub struct BankRegister {
+    config: BankRegisterConfig,
+    /// Subscribed once every component published their handles
+    bank: Option<BankSelectHandle>,
+}
+
+impl Component for BankRegister {
+    fn query_components(&mut self, query: &QueryableComponents) {
+        self.bank = query.event_bus().subscribe(self.config.bank_select_topic);
+    }
+}
+
+impl ComponentVersion for BankRegister {
+    const NAME: &'static str = "bank_register";
+    const VERSION: SemanticVersion = SemanticVersion::new(1, 0, 0);
+}
+
+impl FromConfig for BankRegister {
+    type Config = BankRegisterConfig;
+
+    fn from_config(_rom_manager: Arc<RomManager>, config: Self::Config) -> Self {
+        assert!(
+            (1..=8).contains(&config.assigned_range.len()),
+            "Bank registers are 1 to 8 bytes wide"
+        );
+
+        Self { config, bank: None }
+    }
+}
+
+impl BankRegister {
+    fn register_bytes(&self) -> [u8; 8] {
+        self.bank
+            .as_ref()
+            .map_or(0, |bank| bank.get() & self.config.mask)
+            .to_le_bytes()
+    }
+}
+
+impl MemoryComponent for BankRegister {
+    fn assigned_memory_range(&self) -> Range<usize> {
+        self.config.assigned_range.clone()
+    }
+
+    fn read_memory(
+        &mut self,
+        address: usize,
+        buffer: &mut [u8],
+        _records: &mut ArrayVec<(Range<usize>, ReadMemoryRecord), 8>,
+    ) -> u64 {
+        let offset = address - self.config.assigned_range.start;
+        buffer.copy_from_slice(&self.register_bytes()[offset..offset + buffer.len()]);
+
+        0
+    }
+
+    fn write_memory(
+        &mut self,
+        address: usize,
+        buffer: &[u8],
+        records: &mut ArrayVec<(Range<usize>, WriteMemoryRecord), 8>,
+    ) -> u64 {
+        let Some(bank) = &self.bank else {
+            records.push((address..address + buffer.len(), WriteMemoryRecord::Denied));
+            return 0;
+        };
+
+        // Partial writes only change the bytes they cover
+        let offset = address - self.config.assigned_range.start;
+        let mut register = bank.get().to_le_bytes();
+        register[offset..offset + buffer.len()].copy_from_slice(buffer);
+        bank.set(usize::from_le_bytes(register) & self.config.mask);
+
+        0
+    }
+
+    fn preview_memory(
+        &mut self,
+        address: usize,
+        buffer: &mut [u8],
+        _records: &mut ArrayVec<(Range<usize>, PreviewMemoryRecord), 8>,
+    ) {
+        let offset = address - self.config.assigned_range.start;
+        buffer.copy_from_slice(&self.register_bytes()[offset..offset + buffer.len()]);
+    }
+}
+
+fn read_le(buffer: &[u8]) -> usize {
+    let mut bytes = [0; 8];
+    bytes[..buffer.len()].copy_from_slice(buffer);
+
+    u64::from_le_bytes(bytes) as usize
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::component::memory::MemoryTranslationTable;
+    use std::sync::Mutex;
+
+    const BANKS: [u8; 12] = [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11];
+
+    fn banked_memory(config: BankedMemoryConfig) -> Arc<Mutex<BankedMemory>> {
+        Arc::new(Mutex::new(BankedMemory::from_config(
+            Arc::new(RomManager::default()),
+            BankedMemoryConfig {
+                assigned_range: 0x100..0x104,
+                bank_count: 3,
+                initial_contents: BankedMemoryInitialContents::Array { value: &BANKS },
+                ..config
+            },
+        )))
+    }
+
+    #[test]
+    fn register_switches_banks() {
+        let memory = banked_memory(BankedMemoryConfig {
+            writes: BankedMemoryWrites::Stored,
+            bank_select_topic: Some("bank"),
+            ..Default::default()
+        });
+        let register = Arc::new(Mutex::new(BankRegister::from_config(
+            Arc::new(RomManager::default()),
+            BankRegisterConfig {
+                assigned_range: 0x200..0x201,
+                bank_select_topic: "bank",
+                mask: 0b11,
+            },
+        )));
+
+        let mut queryable_components = QueryableComponents::default();
+        queryable_components.insert("memory", memory.clone());
+        queryable_components.insert("register", register.clone());
+        register
+            .lock()
+            .unwrap()
+            .query_components(&queryable_components);
+
+        let mut memory_translation_table = MemoryTranslationTable::default();
+        memory_translation_table.insert(0x100..0x104, memory.clone());
+        memory_translation_table.insert(0x200..0x201, register);
+
+        let mut buffer = [0; 2];
+        memory_translation_table.read(0x102, &mut buffer).unwrap();
+        assert_eq!(buffer, [2, 3]);
+
+        // The upper bits are masked off, and the bank past the last wraps around to the first
+        memory_translation_table.write(0x200, &[0b1110]).unwrap();
+        assert_eq!(memory.lock().unwrap().handle().get(), 2);
+        memory_translation_table.read(0x102, &mut buffer).unwrap();
+        assert_eq!(buffer, [10, 11]);
+
+        // Writes only land in the selected bank
+        memory_translation_table.write(0x100, &[0xff]).unwrap();
+        memory_translation_table.write(0x200, &[3]).unwrap();
+        memory_translation_table.read(0x100, &mut buffer).unwrap();
+        assert_eq!(buffer, [0, 1]);
+        memory_translation_table.write(0x200, &[2]).unwrap();
+        memory_translation_table.read(0x100, &mut buffer).unwrap();
+        assert_eq!(buffer, [0xff, 9]);
+    }
+
+    #[test]
+    fn writes_to_the_window_select_the_bank() {
+        let memory = banked_memory(BankedMemoryConfig {
+            writes: BankedMemoryWrites::SelectBank,
+            ..Default::default()
+        });
+        let mut memory_translation_table = MemoryTranslationTable::default();
+        memory_translation_table.insert(0x100..0x104, memory.clone());
+
+        memory_translation_table.write(0x103, &[1]).unwrap();
+        let mut buffer = [0];
+        memory_translation_table.read(0x100, &mut buffer).unwrap();
+        assert_eq!(buffer, [4]);
+
+        memory.lock().unwrap().reset();
+        memory_translation_table.read(0x100, &mut buffer).unwrap();
+        assert_eq!(buffer, [0]);
+    }
+}
@@ -0,0 +1,446 @@
+//! A made up device that implements every component trait, meant to be read top to bottom before writing a component
+//!
+//! It is a 8x8 grid of lamps with a speaker and a single button. Each lamp has a brightness register, there is a tone
+//! register for the speaker and a read only register with the state of the button. Nothing real works like this, but
+//! every piece of it maps to something real hardware does
+//!
+//! None of the machines use it, the test at the bottom builds a machine around it the way a machine definition would
+
+use crate::{
+    component::{
+        audio::{AudioBuffer, AudioComponent},
+        display::{
+            color::{DisplayBuffer, IndexedFramebuffer, Palette},
+            DisplayComponent,
+        },
+        input::InputComponent,
+        memory::{
+            MemoryComponent, MemoryTranslationTable, PreviewMemoryRecord, ReadMemoryRecord,
+            WriteMemoryRecord,
+        },
+        schedulable::SchedulableComponent,
+        snapshot::SnapshotableComponent,
+        Component, ComponentVersion, FromConfig, SemanticVersion,
+    },
+    input::{gamepad::GamepadInput, EmulatedGamepad, Input},
+    machine::QueryableComponents,
+    rom::RomManager,
+    runtime::{RenderingBackend, SoftwareRendering},
+};
+use arrayvec::ArrayVec;
+#[cfg(feature = "frontend")]
+use nalgebra::DMatrix;
+use num::rational::Ratio;
+use palette::Srgba;
+use serde::{Deserialize, Serialize};
+use std::{ops::Range, sync::Arc};
+
+/// Lamps on each side of the grid
+pub const GRID_SIZE: usize = 8;
+/// Offsets of the registers from the start of the assigned range. One byte per lamp, row by row
+pub const LAMP_REGISTERS: usize = 0;
+/// Half periods of the tone in samples, 0 silences the speaker
+pub const TONE_REGISTER: usize = GRID_SIZE * GRID_SIZE;
+/// Bit 0 is set while the button is held, writes are ignored
+pub const BUTTON_REGISTER: usize = TONE_REGISTER + 1;
+/// Size of the register block
+pub const REGISTERS_SIZE: usize = BUTTON_REGISTER + 1;
+
+const SAMPLE_RATE: u32 = 48000;
+const TONE_AMPLITUDE: i16 = i16::MAX / 8;
+/// The only input, what it is bound to on the host is up to the controller config
+const BUTTON: Input = Input::Gamepad(GamepadInput::FPadDown);
+
+/// Components are made from a config by the machine definition. Anything that differs between the machines using a
+/// component goes here, and it has to be Serialize as the fingerprint of a machine is made from it
+#[derive(Debug, Serialize)]
+pub struct ExampleComponentConfig {
+    /// Where the registers are mapped, see the register constants for the layout
+    pub assigned_range: Range<usize>,
+    /// Lamps are lit in this color, scaled by their brightness
+    pub lamp_color: Srgba<u8>,
+    /// How often the grid is shown and the button is sampled
+    pub refresh_rate: Ratio<u32>,
+}
+
+impl Default for ExampleComponentConfig {
+    fn default() -> Self {
+        Self {
+            assigned_range: 0x0000..REGISTERS_SIZE,
+            lamp_color: Srgba::new(255, 176, 0, 255),
+            refresh_rate: Ratio::from_integer(60),
+        }
+    }
+}
+
+/// Only what the machine can observe goes into a snapshot. The screen buffer is redrawn from the lamps on the next
+/// tick and the gamepad is owned by the runtime, so neither is saved
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ExampleComponentSnapshot {
+    lamps: Vec<u8>,
+    tone: u8,
+    /// Where in its period the tone is, so a loaded state doesn't click
+    phase: u32,
+}
+
+pub struct ExampleComponent {
+    config: ExampleComponentConfig,
+    lamps: [u8; GRID_SIZE * GRID_SIZE],
+    tone: u8,
+    phase: u32,
+    /// Sampled on every tick rather than when read, like a latch on real hardware
+    button_held: bool,
+    /// Handed out by [AudioComponent::audio_buffer], the runtime drains it
+    audio_buffer: AudioBuffer,
+    /// None until the machine builder assigns one with [InputComponent::assign_controller]
+    gamepad: Option<Arc<EmulatedGamepad>>,
+    /// None until the rendering backend calls [DisplayComponent::initialize_display]. Lamps are drawn as palette
+    /// indices, with their brightness picking one of the shades of the lamp color
+    screen_buffer: Option<DisplayBuffer>,
+}
+
+impl ExampleComponent {
+    fn registers(&self) -> [u8; REGISTERS_SIZE] {
+        let mut registers = [0; REGISTERS_SIZE];
+        registers[LAMP_REGISTERS..TONE_REGISTER].copy_from_slice(&self.lamps);
+        registers[TONE_REGISTER] = self.tone;
+        registers[BUTTON_REGISTER] = self.button_held as u8;
+
+        registers
+    }
+
+    fn register_offset(&self, address: usize) -> usize {
+        address - self.config.assigned_range.start
+    }
+
+    /// The frames of audio that make up one tick
+    fn generate_audio(&mut self) {
+        let frames = (Ratio::from_integer(SAMPLE_RATE) / self.config.refresh_rate).to_integer();
+        let half_period = self.tone as u32;
+
+        if half_period == 0 {
+            self.phase = 0;
+            self.audio_buffer.extend((0..frames).map(|_| [0; 2]));
+            return;
+        }
+
+        self.audio_buffer.extend((0..frames).map(|_| {
+            let sample = if self.phase < half_period {
+                TONE_AMPLITUDE
+            } else {
+                -TONE_AMPLITUDE
+            };
+            self.phase = (self.phase + 1) % (half_period * 2);
+
+            [sample; 2]
+        }));
+    }
+
+    fn draw_lamps(&mut self) {
+        let Some(DisplayBuffer::Indexed(screen_buffer)) = self.screen_buffer.as_mut() else {
+            return;
+        };
+
+        // Screen buffers are indexed by column then row
+        for (index, brightness) in self.lamps.iter().enumerate() {
+            screen_buffer.indices[(index % GRID_SIZE, index / GRID_SIZE)] = *brightness;
+        }
+    }
+}
+
+// Every component implements this. Both methods are optional
+impl Component for ExampleComponent {
+    /// The reset button, or power cycling. Config derived state is kept, everything the machine changed is not
+    fn reset(&mut self) {
+        self.lamps = [0; GRID_SIZE * GRID_SIZE];
+        self.tone = 0;
+        self.phase = 0;
+    }
+
+    /// Called once the whole machine is built, for finding the components this one talks to directly instead of
+    /// through memory. This device has none, see the chip8 processor for one that does
+    fn query_components(&mut self, _query: &QueryableComponents) {}
+}
+
+/// Bump this along with changes to the component, see [SemanticVersion] for which part
+impl ComponentVersion for ExampleComponent {
+    const NAME: &'static str = "example";
+    const VERSION: SemanticVersion = SemanticVersion::new(1, 0, 0);
+}
+
+impl FromConfig for ExampleComponent {
+    type Config = ExampleComponentConfig;
+
+    /// Components that need ROMs load them from the rom manager here
+    fn from_config(_rom_manager: Arc<RomManager>, config: Self::Config) -> Self {
+        assert_eq!(
+            config.assigned_range.len(),
+            REGISTERS_SIZE,
+            "Example component registers must be mapped to exactly {} bytes",
+            REGISTERS_SIZE
+        );
+
+        Self {
+            config,
+            lamps: [0; GRID_SIZE * GRID_SIZE],
+            tone: 0,
+            phase: 0,
+            button_held: false,
+            audio_buffer: AudioBuffer::new(Ratio::from_integer(SAMPLE_RATE)),
+            gamepad: None,
+            screen_buffer: None,
+        }
+    }
+}
+
+/// Components on the schedule are ticked by a task at the rate they ask for. The task may run many ticks in a row
+/// without anything else running in between, so components that must interleave finely need a faster tick rate
+impl SchedulableComponent for ExampleComponent {
+    fn tick_rate(&self) -> Ratio<u32> {
+        self.config.refresh_rate
+    }
+
+    fn tick(&mut self, _memory_translation_table: &MemoryTranslationTable) {
+        self.button_held = self.gamepad.as_ref().is_some_and(|gamepad| {
+            gamepad
+                .get_input_state(BUTTON)
+                .is_some_and(|state| state.as_digital())
+        });
+
+        self.generate_audio();
+        self.draw_lamps();
+    }
+}
+
+/// Memory components own a range of the address space. Accesses are never split across components, and the buffer is
+/// never longer than the largest word the processors on the machine use
+impl MemoryComponent for ExampleComponent {
+    fn assigned_memory_range(&self) -> Range<usize> {
+        self.config.assigned_range.clone()
+    }
+
+    /// Returns the extra cycles the access took. Records are for redirecting the access elsewhere, like mirrors do
+    fn read_memory(
+        &mut self,
+        address: usize,
+        buffer: &mut [u8],
+        _records: &mut ArrayVec<(Range<usize>, ReadMemoryRecord), 8>,
+    ) -> u64 {
+        let offset = self.register_offset(address);
+        buffer.copy_from_slice(&self.registers()[offset..offset + buffer.len()]);
+
+        0
+    }
+
+    fn write_memory(
+        &mut self,
+        address: usize,
+        buffer: &[u8],
+        _records: &mut ArrayVec<(Range<usize>, WriteMemoryRecord), 8>,
+    ) -> u64 {
+        let offset = self.register_offset(address);
+
+        for (offset, value) in (offset..).zip(buffer) {
+            match offset {
+                TONE_REGISTER => self.tone = *value,
+                BUTTON_REGISTER => {}
+                _ => self.lamps[offset - LAMP_REGISTERS] = *value,
+            }
+        }
+
+        0
+    }
+
+    /// Like a read but for debuggers, so it must not change anything. Registers that clear on read must not clear here
+    fn preview_memory(
+        &mut self,
+        address: usize,
+        buffer: &mut [u8],
+        _records: &mut ArrayVec<(Range<usize>, PreviewMemoryRecord), 8>,
+    ) {
+        let offset = self.register_offset(address);
+        buffer.copy_from_slice(&self.registers()[offset..offset + buffer.len()]);
+    }
+}
+
+/// Snapshots are taken and loaded between executor runs, so there is never a tick in progress
+impl SnapshotableComponent for ExampleComponent {
+    fn save_snapshot(&mut self) -> rmpv::Value {
+        let state = ExampleComponentSnapshot {
+            lamps: self.lamps.to_vec(),
+            tone: self.tone,
+            phase: self.phase,
+        };
+
+        rmpv::ext::to_value(&state).unwrap()
+    }
+
+    fn load_snapshot(&mut self, state: rmpv::Value) {
+        let state = rmpv::ext::from_value::<ExampleComponentSnapshot>(state).unwrap();
+
+        self.lamps.copy_from_slice(&state.lamps);
+        self.tone = state.tone;
+        self.phase = state.phase;
+    }
+}
+
+/// Each rendering backend gets its own implementation. Only software is done here, see the chip8 display for vulkan
+impl DisplayComponent<SoftwareRendering> for ExampleComponent {
+    fn initialize_display(
+        &mut self,
+        _initialization_data: <SoftwareRendering as RenderingBackend>::ComponentInitializationData,
+    ) {
+        let color = self.config.lamp_color;
+        let palette = Palette::new((0..=255u16).map(|brightness| {
+            let scale = |channel: u8| (channel as u16 * brightness / 255) as u8;
+
+            Srgba::new(scale(color.red), scale(color.green), scale(color.blue), 255)
+        }));
+
+        self.screen_buffer = Some(IndexedFramebuffer::new(GRID_SIZE, GRID_SIZE, palette).into());
+        self.draw_lamps();
+    }
+
+    fn display_data(&self) -> &<SoftwareRendering as RenderingBackend>::ComponentDisplayBuffer {
+        self.screen_buffer
+            .as_ref()
+            .expect("Display has not been initialized")
+    }
+
+    #[cfg(feature = "frontend")]
+    fn dump_display_data(&mut self) -> Option<DMatrix<Srgba<u8>>> {
+        self.screen_buffer
+            .as_ref()
+            .map(|screen_buffer| screen_buffer.to_pixels().into_owned())
+    }
+}
+
+/// The inputs listed here are what the controller config can bind host inputs to
+impl InputComponent for ExampleComponent {
+    fn registered_inputs(&self) -> &'static [Input] {
+        &[BUTTON]
+    }
+
+    fn assign_controller(&mut self, controller: Arc<EmulatedGamepad>) {
+        self.gamepad = Some(controller);
+    }
+}
+
+impl AudioComponent for ExampleComponent {
+    #[cfg(feature = "frontend")]
+    fn audio_channels(&self) -> &'static [&'static str] {
+        &["Speaker"]
+    }
+
+    // The speaker can't be turned off, the debug menu shows it as always enabled
+    #[cfg(feature = "frontend")]
+    fn set_audio_channel_enabled(&mut self, _channel: usize, _enabled: bool) {}
+
+    #[cfg(feature = "frontend")]
+    fn is_audio_channel_enabled(&self, channel: usize) -> bool {
+        channel == 0
+    }
+
+    #[cfg(feature = "frontend")]
+    fn sample_rate(&self) -> Ratio<u32> {
+        Ratio::from_integer(SAMPLE_RATE)
+    }
+
+    fn audio_buffer(&self) -> AudioBuffer {
+        self.audio_buffer.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        config::GlobalConfig,
+        input::InputState,
+        machine::{
+            executor::{single::SingleThreadedExecutor, Executor},
+            Machine,
+        },
+        task::generic::GenericTask,
+    };
+    use std::{sync::RwLock, time::Duration};
+
+    #[cfg(desktop)]
+    use crate::runtime::desktop::display::software::SoftwareState;
+    #[cfg(feature = "core-only")]
+    use crate::runtime::headless::SoftwareState;
+
+    #[test]
+    fn example_machine() {
+        let mut rendering_state =
+            SoftwareState::headless(Arc::new(RwLock::new(GlobalConfig::default())));
+
+        // This is all a machine definition is
+        let machine = Machine::<SoftwareRendering>::build(Arc::new(RomManager::default()))
+            .component_default::<ExampleComponent>("example")
+            .insert_schedule::<GenericTask<_>>(())
+            .with_memory_map()
+            .with_snapshot()
+            .with_displayable()
+            .with_gamepad()
+            .with_audio()
+            .finalize_component()
+            .finalize_machine();
+        machine.initialize_displays(&mut rendering_state);
+
+        let component = machine
+            .queryable_components
+            .query_component::<ExampleComponent>("example")
+            .unwrap();
+        let memory_translation_table = machine.memory_translation_table.clone();
+        let gamepad = machine.controllers[0].clone();
+        let audio_buffer = machine.audio_components[0].lock().unwrap().audio_buffer();
+        let mut executor =
+            SingleThreadedExecutor::new(machine.tasks, machine.memory_translation_table);
+
+        memory_translation_table
+            .write(LAMP_REGISTERS + 9, &[255])
+            .unwrap();
+        memory_translation_table
+            .write(TONE_REGISTER, &[24])
+            .unwrap();
+        gamepad.set_input_state(BUTTON, InputState::Digital(true));
+        executor.run_unthrottled(Duration::from_secs(1) / 60);
+
+        // One lamp lit, in the second row
+        let display = machine.display_components[0].lock().unwrap();
+        assert_eq!(
+            display.display_data().pixel(1, 1),
+            ExampleComponentConfig::default().lamp_color
+        );
+        assert_eq!(display.display_data().pixel(0, 1), Srgba::new(0, 0, 0, 255));
+        drop(display);
+
+        // A 1000Hz tone, 24 samples high then 24 low
+        assert_eq!(audio_buffer.len(), 800);
+        assert_eq!(audio_buffer.pop(), Some([TONE_AMPLITUDE; 2]));
+        for _ in 0..23 {
+            audio_buffer.pop();
+        }
+        assert_eq!(audio_buffer.pop(), Some([-TONE_AMPLITUDE; 2]));
+
+        let mut button = [0];
+        memory_translation_table
+            .read(BUTTON_REGISTER, &mut button)
+            .unwrap();
+        assert_eq!(button, [1]);
+
+        // Round trip through a snapshot
+        let state = component.lock().unwrap().save_snapshot();
+        component.lock().unwrap().reset();
+        memory_translation_table
+            .read(LAMP_REGISTERS + 9, &mut button)
+            .unwrap();
+        assert_eq!(button, [0]);
+        component.lock().unwrap().load_snapshot(state);
+        memory_translation_table
+            .read(LAMP_REGISTERS + 9, &mut button)
+            .unwrap();
+        assert_eq!(button, [255]);
+    }
+}
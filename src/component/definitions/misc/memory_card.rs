@@ -0,0 +1,347 @@
+use crate::{
+    atomic_write::{read_with_backup, write_atomically_with_backup},
+    component::{
+        memory::{
+            MemoryComponent, MemoryTranslationTable, PreviewMemoryRecord, ReadMemoryRecord,
+            WriteMemoryRecord,
+        },
+        Component, ComponentVersion, FromConfig, SemanticVersion,
+    },
+    env::MEMORY_CARD_DIRECTORY,
+    machine::peripheral::PeripheralDevice,
+    rom::RomManager,
+    task::Task,
+};
+use arrayvec::ArrayVec;
+use num::rational::Ratio;
+use serde::Serialize;
+use std::{
+    fs::{copy, create_dir_all, read, read_dir, remove_file, OpenOptions},
+    io::Write,
+    ops::Range,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+/// A kind of card, any image of a format fits any slot taking it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct MemoryCardFormat {
+    pub name: &'static str,
+    /// Where its images are kept, under the memory card directory
+    pub directory: &'static str,
+    pub extension: &'static str,
+    pub size: usize,
+    /// What a freshly formatted card is filled with
+    pub erased_value: u8,
+}
+
+#[cfg(feature = "frontend")]
+pub const PS1_MEMORY_CARD: MemoryCardFormat = MemoryCardFormat {
+    name: "PlayStation Memory Card",
+    directory: "ps1",
+    extension: "mcr",
+    size: 128 * 1024,
+    erased_value: 0x00,
+};
+
+pub const N64_CONTROLLER_PAK: MemoryCardFormat = MemoryCardFormat {
+    name: "Controller Pak",
+    directory: "n64",
+    extension: "mpk",
+    size: 32 * 1024,
+    erased_value: 0x00,
+};
+
+#[cfg(feature = "frontend")]
+pub const MEMORY_CARD_FORMATS: &[MemoryCardFormat] = &[PS1_MEMORY_CARD, N64_CONTROLLER_PAK];
+
+/// The card images on disk
+///
+/// The image named after a slot is the one inserted into it, so swapping cards is copying another image over it
+#[derive(Debug, Clone)]
+pub struct MemoryCardLibrary {
+    directory: PathBuf,
+}
+
+impl Default for MemoryCardLibrary {
+    fn default() -> Self {
+        Self::new(MEMORY_CARD_DIRECTORY.clone())
+    }
+}
+
+impl MemoryCardLibrary {
+    pub fn new(directory: PathBuf) -> Self {
+        Self { directory }
+    }
+
+    pub fn image_path(&self, format: MemoryCardFormat, name: &str) -> PathBuf {
+        self.directory
+            .join(format.directory)
+            .join(name)
+            .with_extension(format.extension)
+    }
+
+    /// Names of the images of the format, sorted
+    pub fn images(&self, format: MemoryCardFormat) -> Vec<String> {
+        let Ok(entries) = read_dir(self.directory.join(format.directory)) else {
+            return Vec::new();
+        };
+
+        let mut images: Vec<_> = entries
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .filter(|path| {
+                path.extension()
+                    .is_some_and(|extension| extension == format.extension)
+            })
+            .filter_map(|path| Some(path.file_stem()?.to_string_lossy().into_owned()))
+            .collect();
+        images.sort();
+
+        images
+    }
+
+    /// A formatted card, never replacing one that already exists
+    pub fn create(&self, format: MemoryCardFormat, name: &str) -> std::io::Result<()> {
+        let path = self.image_path(format, name);
+        create_dir_all(path.parent().unwrap())?;
+
+        OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(path)?
+            .write_all(&vec![format.erased_value; format.size])
+    }
+
+    /// Replaces whatever image is at the destination
+    pub fn copy(&self, format: MemoryCardFormat, from: &str, to: &str) -> std::io::Result<()> {
+        copy(self.image_path(format, from), self.image_path(format, to))?;
+
+        Ok(())
+    }
+
+    pub fn delete(&self, format: MemoryCardFormat, name: &str) -> std::io::Result<()> {
+        remove_file(self.image_path(format, name))
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct MemoryCardConfig {
+    pub format: MemoryCardFormat,
+    #[serde(skip)]
+    pub image: PathBuf,
+    /// Starts here and is as long as the format says
+    pub base_address: usize,
+}
+
+/// Fixed size storage backed by an image file, like memory cards and controller paks
+///
+/// Writes reach the image within a second through the task of [MemoryCard::peripheral], and when the card is pulled out
+pub struct MemoryCard {
+    config: MemoryCardConfig,
+    contents: Vec<u8>,
+    /// Written to since the image was last saved
+    dirty: bool,
+}
+
+impl MemoryCard {
+    /// For plugging into a slot, with the image named after it
+    pub fn peripheral(
+        rom_manager: Arc<RomManager>,
+        format: MemoryCardFormat,
+        base_address: usize,
+        port: &'static str,
+    ) -> PeripheralDevice {
+        let card = Arc::new(Mutex::new(Self::from_config(
+            rom_manager,
+            MemoryCardConfig {
+                format,
+                image: MemoryCardLibrary::default().image_path(format, port),
+                base_address,
+            },
+        )));
+
+        PeripheralDevice {
+            memory: Some(card.clone()),
+            task: Some((Ratio::from_integer(1), Box::new(MemoryCardFlushTask(card)))),
+            ..Default::default()
+        }
+    }
+
+    pub fn flush(&mut self) {
+        if !self.dirty {
+            return;
+        }
+
+        // The image is the only copy of the saves on it, the previous version is kept in case this one is cut short
+        let result =
+            write_atomically_with_backup(&self.config.image, |file| file.write_all(&self.contents));
+
+        match result {
+            Ok(()) => self.dirty = false,
+            Err(error) => tracing::error!(
+                "Could not save the memory card to {}: {}",
+                self.config.image.display(),
+                error
+            ),
+        }
+    }
+
+    fn offset(&self, address: usize, length: usize) -> Range<usize> {
+        address - self.config.base_address..address - self.config.base_address + length
+    }
+}
+
+impl Component for MemoryCard {
+    fn shutdown(&mut self) {
+        self.flush();
+    }
+}
+
+impl ComponentVersion for MemoryCard {
+    const NAME: &'static str = "memory_card";
+    const VERSION: SemanticVersion = SemanticVersion::new(1, 0, 0);
+}
+
+impl FromConfig for MemoryCard {
+    type Config = MemoryCardConfig;
+
+    fn from_config(_rom_manager: Arc<RomManager>, config: Self::Config) -> Self {
+        let format = config.format;
+
+        let (contents, dirty) = match load_image(&config.image, format) {
+            Some(contents) => (contents, false),
+            // A new card comes formatted, and saved right away so it shows up in the manager
+            None => (vec![format.erased_value; format.size], true),
+        };
+
+        let mut card = Self {
+            config,
+            contents,
+            dirty,
+        };
+        card.flush();
+
+        card
+    }
+}
+
+impl Drop for MemoryCard {
+    fn drop(&mut self) {
+        self.flush();
+    }
+}
+
+/// Images of the wrong size are cut or padded to fit
+fn load_image(path: &Path, format: MemoryCardFormat) -> Option<Vec<u8>> {
+    let mut contents = read_with_backup(path, |path| read(path)).ok()?;
+
+    if contents.len() != format.size {
+        tracing::warn!(
+            "{} is {} bytes, a {} is {}",
+            path.display(),
+            contents.len(),
+            format.name,
+            format.size
+        );
+        contents.resize(format.size, format.erased_value);
+    }
+
+    Some(contents)
+}
+
+impl MemoryComponent for MemoryCard {
+    fn assigned_memory_range(&self) -> Range<usize> {
+        self.config.base_address..self.config.base_address + self.config.format.size
+    }
+
+    fn read_memory(
+        &mut self,
+        address: usize,
+        buffer: &mut [u8],
+        _records: &mut ArrayVec<(Range<usize>, ReadMemoryRecord), 8>,
+    ) -> u64 {
+        buffer.copy_from_slice(&self.contents[self.offset(address, buffer.len())]);
+
+        0
+    }
+
+    fn write_memory(
+        &mut self,
+        address: usize,
+        buffer: &[u8],
+        _records: &mut ArrayVec<(Range<usize>, WriteMemoryRecord), 8>,
+    ) -> u64 {
+        let offset = self.offset(address, buffer.len());
+        self.contents[offset].copy_from_slice(buffer);
+        self.dirty = true;
+
+        0
+    }
+
+    fn preview_memory(
+        &mut self,
+        address: usize,
+        buffer: &mut [u8],
+        _records: &mut ArrayVec<(Range<usize>, PreviewMemoryRecord), 8>,
+    ) {
+        buffer.copy_from_slice(&self.contents[self.offset(address, buffer.len())]);
+    }
+}
+
+/// Saves the image once a second if the game wrote to it
+struct MemoryCardFlushTask(Arc<Mutex<MemoryCard>>);
+
+impl Task for MemoryCardFlushTask {
+    fn tick(&mut self, _batch_size: u32, _memory_translation_table: &MemoryTranslationTable) {
+        self.0.lock().unwrap().flush();
+    }
+
+    // The card lives outside of snapshots
+    fn save(&mut self) -> rmpv::Value {
+        rmpv::Value::Nil
+    }
+
+    fn load(&mut self, _state: rmpv::Value) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cards_persist_to_their_image() {
+        let directory =
+            std::env::temp_dir().join(format!("multiemu_memory_card_{}", std::process::id()));
+        let library = MemoryCardLibrary::new(directory.clone());
+        let rom_manager = Arc::new(RomManager::default());
+
+        let config = || MemoryCardConfig {
+            format: N64_CONTROLLER_PAK,
+            image: library.image_path(N64_CONTROLLER_PAK, "Slot 1"),
+            base_address: 0x8000,
+        };
+
+        let mut card = MemoryCard::from_config(rom_manager.clone(), config());
+        // Inserting a card that doesn't exist yet formats one
+        assert_eq!(library.images(N64_CONTROLLER_PAK), ["Slot 1"]);
+        card.write_memory(0x8010, &[0x12, 0x34], &mut ArrayVec::new());
+        drop(card);
+
+        library
+            .copy(N64_CONTROLLER_PAK, "Slot 1", "Backup")
+            .unwrap();
+        assert!(library.create(N64_CONTROLLER_PAK, "Backup").is_err());
+
+        let mut card = MemoryCard::from_config(rom_manager, config());
+        let mut buffer = [0; 2];
+        card.read_memory(0x8010, &mut buffer, &mut ArrayVec::new());
+        assert_eq!(buffer, [0x12, 0x34]);
+        drop(card);
+
+        library.delete(N64_CONTROLLER_PAK, "Slot 1").unwrap();
+        assert_eq!(library.images(N64_CONTROLLER_PAK), ["Backup"]);
+
+        std::fs::remove_dir_all(directory).unwrap();
+    }
+}
@@ -1,28 +1,32 @@
 use crate::{
     component::{
         memory::{MemoryComponent, PreviewMemoryRecord, ReadMemoryRecord, WriteMemoryRecord},
-        Component, FromConfig,
+        Component, ComponentVersion, FromConfig, SemanticVersion,
     },
     rom::RomManager,
 };
 use arrayvec::ArrayVec;
+use serde::Serialize;
 use std::{ops::Range, sync::Arc};
 
-#[derive(Debug)]
+#[derive(Debug, Serialize)]
 pub enum MirrorMemoryOverflowMode {
     // Deny if it goes outside the assigned range if the assigned range is larger than the target
+    #[allow(dead_code)]
     Deny,
     // Wrap X times
     Wrap(usize),
 }
 
-#[derive(Debug)]
+#[derive(Debug, Serialize)]
 pub struct MirrorMemoryConfig {
     pub readable: bool,
     pub writable: bool,
     pub assigned_range: Range<usize>,
     // The penalty for each cycle
+    #[serde(skip)]
     pub read_cycle_penalty_calculator: fn(range: Range<usize>, denied: bool) -> u64,
+    #[serde(skip)]
     pub write_cycle_penalty_calculator: fn(range: Range<usize>, denied: bool) -> u64,
     pub target: Range<usize>,
     pub overflow_mode: MirrorMemoryOverflowMode,
@@ -35,6 +39,11 @@ pub struct MirrorMemory {
 
 impl Component for MirrorMemory {}
 
+impl ComponentVersion for MirrorMemory {
+    const NAME: &'static str = "mirror_memory";
+    const VERSION: SemanticVersion = SemanticVersion::new(1, 0, 0);
+}
+
 impl FromConfig for MirrorMemory {
     type Config = MirrorMemoryConfig;
 
@@ -182,10 +191,12 @@ impl MemoryComponent for MirrorMemory {
             match self.config.overflow_mode {
                 MirrorMemoryOverflowMode::Deny => {
                     records.push((affected_range.clone(), PreviewMemoryRecord::Denied));
+                    return;
                 }
                 MirrorMemoryOverflowMode::Wrap(n) => {
                     if offset / target_range_size >= n {
                         records.push((affected_range.clone(), PreviewMemoryRecord::Denied));
+                        return;
                     }
 
                     let real_offset = offset % target_range_size;
@@ -196,6 +207,7 @@ impl MemoryComponent for MirrorMemory {
                             offset: real_offset,
                         },
                     ));
+                    return;
                 }
             }
         }
@@ -1,4 +1,15 @@
+// Building blocks for machines that don't exist yet, the tests are what use them for now
+#[allow(dead_code)]
+pub mod banked_memory;
+#[allow(dead_code)]
+pub mod example;
+#[cfg(any(feature = "frontend", test))]
+#[allow(dead_code)]
+pub mod memory_card;
 pub mod mirror_memory;
 pub mod plain_memory;
 pub mod processor;
+#[allow(dead_code)]
 pub mod rom_memory;
+#[allow(dead_code)]
+pub mod timer;
@@ -2,7 +2,7 @@ use crate::{
     component::{
         memory::{MemoryComponent, PreviewMemoryRecord, ReadMemoryRecord, WriteMemoryRecord},
         snapshot::SnapshotableComponent,
-        Component, FromConfig,
+        Component, ComponentVersion, FromConfig, SemanticVersion,
     },
     rom::{RomId, RomManager, RomRequirement},
 };
@@ -11,15 +11,24 @@ use rand::{thread_rng, RngCore};
 use serde::{Deserialize, Serialize};
 use std::{io::Read, ops::Range, sync::Arc};
 
-#[derive(Debug)]
+#[derive(Debug, Serialize)]
 pub enum PlainMemoryInitialContents {
-    Value { value: u8 },
-    Array { value: &'static [u8], offset: usize },
-    Rom { rom_id: RomId, offset: usize },
+    Value {
+        value: u8,
+    },
+    Array {
+        value: &'static [u8],
+        offset: usize,
+    },
+    Rom {
+        rom_id: RomId,
+        offset: usize,
+    },
+    #[allow(dead_code)]
     Random,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Serialize)]
 pub struct PlainMemoryConfig {
     // If the buffer is readable
     pub readable: bool,
@@ -28,7 +37,9 @@ pub struct PlainMemoryConfig {
     // The maximum word size
     pub max_word_size: u8,
     // The penalty for each cycle
+    #[serde(skip)]
     pub read_cycle_penalty_calculator: fn(range: Range<usize>, denied: bool) -> u64,
+    #[serde(skip)]
     pub write_cycle_penalty_calculator: fn(range: Range<usize>, denied: bool) -> u64,
     // Memory region this buffer will be mapped to
     pub assigned_range: Range<usize>,
@@ -84,6 +95,11 @@ impl SnapshotableComponent for PlainMemory {
     }
 }
 
+impl ComponentVersion for PlainMemory {
+    const NAME: &'static str = "plain_memory";
+    const VERSION: SemanticVersion = SemanticVersion::new(1, 0, 0);
+}
+
 impl FromConfig for PlainMemory {
     type Config = PlainMemoryConfig;
 
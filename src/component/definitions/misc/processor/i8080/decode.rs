@@ -1,49 +1,464 @@
-use crate::component::definitions::misc::processor::i8080::instruction::SingleByteArgument;
-use crate::component::memory::MemoryTranslationTable;
-use bitvec::field::BitField;
-use bitvec::prelude::Msb0;
-use bitvec::view::BitView;
+use super::{
+    instruction::{
+        AccumulatorIndirect, AluOperation, BlockOperation, Condition, I8080InstructionSet,
+        IndexRegister, Port, Register, RegisterPair, RotateOperation, SingleByteArgument,
+        SpecialRegister,
+    },
+    I8080Kind,
+};
+use crate::component::memory::{MemoryOperationError, MemoryTranslationTable};
+use bitvec::{field::BitField, prelude::Msb0, view::BitView};
 use std::ops::Range;
 
+// The x, y and z fields of http://www.z80.info/decoding.htm
 const INSTRUCTION_IDENTIFIER: Range<usize> = 0..2;
 const SECONDARY_INSTRUCTION_IDENTIFIER: Range<usize> = 5..8;
 const ARGUMENT: Range<usize> = 2..5;
 
+/// The bytes of a instruction as they are consumed, so the length falls out at the end
+struct InstructionCursor<'a> {
+    memory_translation_table: &'a MemoryTranslationTable,
+    start: usize,
+    length: u8,
+}
+
+impl InstructionCursor<'_> {
+    fn byte(&mut self) -> Result<u8, MemoryOperationError> {
+        let mut value = 0;
+        self.memory_translation_table.read(
+            self.start.wrapping_add(self.length as usize),
+            std::slice::from_mut(&mut value),
+        )?;
+        self.length += 1;
+
+        Ok(value)
+    }
+
+    fn word(&mut self) -> Result<u16, MemoryOperationError> {
+        Ok(u16::from_le_bytes([self.byte()?, self.byte()?]))
+    }
+}
+
+/// Splits a opcode into its x, y and z fields
+fn split_opcode(opcode: u8) -> (u8, u8, u8) {
+    let opcode = opcode.view_bits::<Msb0>();
+
+    (
+        opcode[INSTRUCTION_IDENTIFIER].load::<u8>(),
+        opcode[ARGUMENT].load::<u8>(),
+        opcode[SECONDARY_INSTRUCTION_IDENTIFIER].load::<u8>(),
+    )
+}
+
 pub fn decode_instruction(
+    kind: &I8080Kind,
     cursor: usize,
     memory_translation_table: &MemoryTranslationTable,
-) -> Result<(), Box<dyn std::error::Error>> {
-    let mut instruction_first_byte = 0;
-    memory_translation_table.read(cursor, std::slice::from_mut(&mut instruction_first_byte))?;
-    let instruction_first_byte = instruction_first_byte.view_bits::<Msb0>();
-    let instruction_identifier = instruction_first_byte[INSTRUCTION_IDENTIFIER].load::<u8>();
+) -> Result<(I8080InstructionSet, u8), Box<dyn std::error::Error>> {
+    let mut cursor = InstructionCursor {
+        memory_translation_table,
+        start: cursor,
+        length: 0,
+    };
 
-    match instruction_identifier {
-        0b00 => {
-            todo!()
-        }
-        0b01 => {
-            let source_register = instruction_first_byte[ARGUMENT].load::<u8>();
-            let destination_register =
-                instruction_first_byte[SECONDARY_INSTRUCTION_IDENTIFIER].load::<u8>();
+    let opcode = cursor.byte()?;
 
-            let source_register = SingleByteArgument::from_id(source_register).unwrap();
-            let destination_register = SingleByteArgument::from_id(destination_register).unwrap();
+    let instruction = match (kind, opcode) {
+        (I8080Kind::Z80, 0xdd | 0xfd) => {
+            let index = if opcode == 0xdd {
+                IndexRegister::Ix
+            } else {
+                IndexRegister::Iy
+            };
 
-            if source_register == SingleByteArgument::HlIndirect
-                && destination_register == SingleByteArgument::HlIndirect
-            {}
+            match cursor.byte()? {
+                // A prefix followed by another prefix does nothing
+                0xdd | 0xed | 0xfd => {
+                    return Ok((I8080InstructionSet::Nop, 1));
+                }
+                0xcb => decode_bit_instruction(kind, &mut cursor, Some(index))?,
+                opcode => decode_unprefixed_instruction(kind, &mut cursor, opcode, Some(index))?,
+            }
+        }
+        (I8080Kind::Z80, 0xed) => {
+            let opcode = cursor.byte()?;
+            decode_extended_instruction(&mut cursor, opcode)?
         }
-        0b10 => {
-            todo!()
+        (I8080Kind::Z80 | I8080Kind::Lr35902, 0xcb) => {
+            decode_bit_instruction(kind, &mut cursor, None)?
         }
-        0b11 => {
-            todo!()
+        _ => decode_unprefixed_instruction(kind, &mut cursor, opcode, None)?,
+    };
+
+    Ok((instruction, cursor.length))
+}
+
+/// A single byte argument, with HL standing in for the index register if there is one
+///
+/// The displacement of (IX+d) comes right after the opcode, so this has to be called before any immediate is read
+fn single_byte_argument(
+    cursor: &mut InstructionCursor,
+    id: u8,
+    index: Option<IndexRegister>,
+    substitute_halves: bool,
+) -> Result<SingleByteArgument, MemoryOperationError> {
+    let argument = SingleByteArgument::from_id(id).unwrap();
+
+    Ok(match (argument, index) {
+        (SingleByteArgument::HlIndirect, Some(register)) => SingleByteArgument::IndexIndirect {
+            register,
+            displacement: cursor.byte()? as i8,
+        },
+        (SingleByteArgument::Register(Register::H), Some(register)) if substitute_halves => {
+            SingleByteArgument::IndexHigh(register)
         }
-        _ => {
-            unreachable!()
+        (SingleByteArgument::Register(Register::L), Some(register)) if substitute_halves => {
+            SingleByteArgument::IndexLow(register)
         }
+        _ => argument,
+    })
+}
+
+fn substitute_pair(pair: RegisterPair, index: Option<IndexRegister>) -> RegisterPair {
+    match (pair, index) {
+        (RegisterPair::Hl, Some(IndexRegister::Ix)) => RegisterPair::Ix,
+        (RegisterPair::Hl, Some(IndexRegister::Iy)) => RegisterPair::Iy,
+        _ => pair,
     }
+}
+
+fn decode_unprefixed_instruction(
+    kind: &I8080Kind,
+    cursor: &mut InstructionCursor,
+    opcode: u8,
+    index: Option<IndexRegister>,
+) -> Result<I8080InstructionSet, MemoryOperationError> {
+    let (x, y, z) = split_opcode(opcode);
+    let (p, q) = (y >> 1, y & 1);
+    let lr35902 = *kind == I8080Kind::Lr35902;
+    let hl = substitute_pair(RegisterPair::Hl, index);
+
+    Ok(match (x, z) {
+        (0b00, 0b000) => match (kind, y) {
+            (_, 0) => I8080InstructionSet::Nop,
+            // The 8080 left the rest of this column as NOPs
+            (I8080Kind::I8080, _) => I8080InstructionSet::Nop,
+            (I8080Kind::Z80, 1) => I8080InstructionSet::ExchangeAf,
+            (I8080Kind::Lr35902, 1) => I8080InstructionSet::StorePairIndirect {
+                source: RegisterPair::Sp,
+                address: cursor.word()?,
+            },
+            (I8080Kind::Z80, 2) => I8080InstructionSet::DecrementJumpNotZero(cursor.byte()? as i8),
+            (I8080Kind::Lr35902, 2) => {
+                // STOP is followed by a byte that is skipped
+                cursor.byte()?;
+                I8080InstructionSet::Stop
+            }
+            (_, 3) => I8080InstructionSet::JumpRelative {
+                condition: None,
+                offset: cursor.byte()? as i8,
+            },
+            _ => I8080InstructionSet::JumpRelative {
+                condition: Condition::from_id(y - 4),
+                offset: cursor.byte()? as i8,
+            },
+        },
+        (0b00, 0b001) => {
+            let pair = substitute_pair(RegisterPair::from_id(p).unwrap(), index);
 
-    Ok(())
+            if q == 0 {
+                I8080InstructionSet::LoadPair {
+                    destination: pair,
+                    value: cursor.word()?,
+                }
+            } else {
+                I8080InstructionSet::AddPair {
+                    destination: hl,
+                    source: pair,
+                }
+            }
+        }
+        (0b00, 0b010) => {
+            let indirect = match (p, lr35902) {
+                (0, _) => AccumulatorIndirect::Pair(RegisterPair::Bc),
+                (1, _) => AccumulatorIndirect::Pair(RegisterPair::De),
+                (2, true) => AccumulatorIndirect::HlIncrement,
+                (3, true) => AccumulatorIndirect::HlDecrement,
+                // HL to memory rather than the accumulator
+                (2, false) => {
+                    let address = cursor.word()?;
+
+                    return Ok(if q == 0 {
+                        I8080InstructionSet::StorePairIndirect {
+                            source: hl,
+                            address,
+                        }
+                    } else {
+                        I8080InstructionSet::LoadPairIndirect {
+                            destination: hl,
+                            address,
+                        }
+                    });
+                }
+                _ => AccumulatorIndirect::Absolute(cursor.word()?),
+            };
+
+            if q == 0 {
+                I8080InstructionSet::StoreAccumulator {
+                    destination: indirect,
+                }
+            } else {
+                I8080InstructionSet::LoadAccumulator { source: indirect }
+            }
+        }
+        (0b00, 0b011) => {
+            let pair = substitute_pair(RegisterPair::from_id(p).unwrap(), index);
+
+            if q == 0 {
+                I8080InstructionSet::IncrementPair(pair)
+            } else {
+                I8080InstructionSet::DecrementPair(pair)
+            }
+        }
+        (0b00, 0b100) => {
+            I8080InstructionSet::Increment(single_byte_argument(cursor, y, index, true)?)
+        }
+        (0b00, 0b101) => {
+            I8080InstructionSet::Decrement(single_byte_argument(cursor, y, index, true)?)
+        }
+        (0b00, 0b110) => {
+            let destination = single_byte_argument(cursor, y, index, true)?;
+
+            I8080InstructionSet::Load {
+                destination,
+                source: SingleByteArgument::Immediate(cursor.byte()?),
+            }
+        }
+        (0b00, 0b111) => match y {
+            0 => I8080InstructionSet::RotateAccumulator(RotateOperation::RotateLeftCircular),
+            1 => I8080InstructionSet::RotateAccumulator(RotateOperation::RotateRightCircular),
+            2 => I8080InstructionSet::RotateAccumulator(RotateOperation::RotateLeft),
+            3 => I8080InstructionSet::RotateAccumulator(RotateOperation::RotateRight),
+            4 => I8080InstructionSet::DecimalAdjust,
+            5 => I8080InstructionSet::Complement,
+            6 => I8080InstructionSet::SetCarry,
+            _ => I8080InstructionSet::ComplementCarry,
+        },
+        (0b01, _) if y == 0b110 && z == 0b110 => I8080InstructionSet::Halt,
+        (0b01, _) => {
+            // If one side is (IX+d) the other side keeps the real H and L
+            let substitute_halves = y != 0b110 && z != 0b110;
+            let destination = single_byte_argument(cursor, y, index, substitute_halves)?;
+            let source = single_byte_argument(cursor, z, index, substitute_halves)?;
+
+            I8080InstructionSet::Load {
+                destination,
+                source,
+            }
+        }
+        (0b10, _) => I8080InstructionSet::Alu {
+            operation: AluOperation::from_id(y).unwrap(),
+            argument: single_byte_argument(cursor, z, index, true)?,
+        },
+        (_, 0b000) if lr35902 && y >= 4 => match y {
+            4 => I8080InstructionSet::StoreAccumulator {
+                destination: AccumulatorIndirect::HighPage(cursor.byte()?),
+            },
+            5 => I8080InstructionSet::AddStackPointer(cursor.byte()? as i8),
+            6 => I8080InstructionSet::LoadAccumulator {
+                source: AccumulatorIndirect::HighPage(cursor.byte()?),
+            },
+            _ => I8080InstructionSet::LoadStackOffset(cursor.byte()? as i8),
+        },
+        (_, 0b000) => I8080InstructionSet::Return {
+            condition: Condition::from_id(y),
+        },
+        (_, 0b001) => match (q, p) {
+            (0, _) => I8080InstructionSet::Pop(substitute_pair(
+                RegisterPair::from_stack_id(p).unwrap(),
+                index,
+            )),
+            (_, 0) => I8080InstructionSet::Return { condition: None },
+            (_, 1) => match kind {
+                I8080Kind::I8080 => I8080InstructionSet::Return { condition: None },
+                I8080Kind::Z80 => I8080InstructionSet::ExchangeShadow,
+                I8080Kind::Lr35902 => I8080InstructionSet::ReturnFromInterrupt,
+            },
+            (_, 2) => I8080InstructionSet::JumpIndirect(hl),
+            _ => I8080InstructionSet::LoadStackPointer { source: hl },
+        },
+        (_, 0b010) if lr35902 && y >= 4 => match y {
+            4 => I8080InstructionSet::StoreAccumulator {
+                destination: AccumulatorIndirect::HighPageC,
+            },
+            5 => I8080InstructionSet::StoreAccumulator {
+                destination: AccumulatorIndirect::Absolute(cursor.word()?),
+            },
+            6 => I8080InstructionSet::LoadAccumulator {
+                source: AccumulatorIndirect::HighPageC,
+            },
+            _ => I8080InstructionSet::LoadAccumulator {
+                source: AccumulatorIndirect::Absolute(cursor.word()?),
+            },
+        },
+        (_, 0b010) => I8080InstructionSet::Jump {
+            condition: Condition::from_id(y),
+            address: cursor.word()?,
+        },
+        (_, 0b011) => match y {
+            // 0xcb is only reached on the 8080, where it is a alias
+            0 | 1 => I8080InstructionSet::Jump {
+                condition: None,
+                address: cursor.word()?,
+            },
+            2..=5 if lr35902 => I8080InstructionSet::Lock,
+            2 => I8080InstructionSet::Output {
+                source: Some(Register::A),
+                port: Port::Immediate(cursor.byte()?),
+            },
+            3 => I8080InstructionSet::Input {
+                destination: Some(Register::A),
+                port: Port::Immediate(cursor.byte()?),
+            },
+            4 => I8080InstructionSet::ExchangeStack(hl),
+            // Never affected by the index prefixes
+            5 => I8080InstructionSet::ExchangeDeHl,
+            6 => I8080InstructionSet::DisableInterrupts,
+            _ => I8080InstructionSet::EnableInterrupts,
+        },
+        (_, 0b100) if lr35902 && y >= 4 => I8080InstructionSet::Lock,
+        (_, 0b100) => I8080InstructionSet::Call {
+            condition: Condition::from_id(y),
+            address: cursor.word()?,
+        },
+        (_, 0b101) => match (q, p) {
+            (0, _) => I8080InstructionSet::Push(substitute_pair(
+                RegisterPair::from_stack_id(p).unwrap(),
+                index,
+            )),
+            (_, 0) => I8080InstructionSet::Call {
+                condition: None,
+                address: cursor.word()?,
+            },
+            // The LR35902 doesn't have the Z80 prefixes
+            _ if lr35902 => I8080InstructionSet::Lock,
+            // The prefixes are only reached on the 8080, where they are aliases
+            _ => I8080InstructionSet::Call {
+                condition: None,
+                address: cursor.word()?,
+            },
+        },
+        (_, 0b110) => I8080InstructionSet::Alu {
+            operation: AluOperation::from_id(y).unwrap(),
+            argument: SingleByteArgument::Immediate(cursor.byte()?),
+        },
+        _ => I8080InstructionSet::Restart(y * 8),
+    })
+}
+
+/// The 0xcb prefixed instructions, the index prefixed ones always work on (IX+d)
+fn decode_bit_instruction(
+    kind: &I8080Kind,
+    cursor: &mut InstructionCursor,
+    index: Option<IndexRegister>,
+) -> Result<I8080InstructionSet, MemoryOperationError> {
+    // The displacement comes before the opcode here
+    let argument = match index {
+        Some(register) => Some(SingleByteArgument::IndexIndirect {
+            register,
+            displacement: cursor.byte()? as i8,
+        }),
+        None => None,
+    };
+    let opcode = cursor.byte()?;
+    let (x, y, z) = split_opcode(opcode);
+    // Undocumented, the index prefixed ones also copy the result into r[z] which is not emulated
+    let argument = argument.unwrap_or_else(|| SingleByteArgument::from_id(z).unwrap());
+
+    Ok(match x {
+        0b00 => I8080InstructionSet::Rotate {
+            operation: match (kind, y) {
+                (I8080Kind::Lr35902, 0b110) => RotateOperation::Swap,
+                _ => RotateOperation::from_id(y).unwrap(),
+            },
+            argument,
+        },
+        0b01 => I8080InstructionSet::TestBit { bit: y, argument },
+        0b10 => I8080InstructionSet::ResetBit { bit: y, argument },
+        _ => I8080InstructionSet::SetBit { bit: y, argument },
+    })
+}
+
+/// The 0xed prefixed instructions of the Z80
+fn decode_extended_instruction(
+    cursor: &mut InstructionCursor,
+    opcode: u8,
+) -> Result<I8080InstructionSet, MemoryOperationError> {
+    let (x, y, z) = split_opcode(opcode);
+    let (p, q) = (y >> 1, y & 1);
+    let register = match SingleByteArgument::from_id(y).unwrap() {
+        SingleByteArgument::Register(register) => Some(register),
+        _ => None,
+    };
+
+    Ok(match (x, z) {
+        (0b01, 0b000) => I8080InstructionSet::Input {
+            destination: register,
+            port: Port::C,
+        },
+        (0b01, 0b001) => I8080InstructionSet::Output {
+            source: register,
+            port: Port::C,
+        },
+        (0b01, 0b010) => {
+            let pair = RegisterPair::from_id(p).unwrap();
+
+            if q == 0 {
+                I8080InstructionSet::SubtractPairWithBorrow(pair)
+            } else {
+                I8080InstructionSet::AddPairWithCarry(pair)
+            }
+        }
+        (0b01, 0b011) => {
+            let pair = RegisterPair::from_id(p).unwrap();
+            let address = cursor.word()?;
+
+            if q == 0 {
+                I8080InstructionSet::StorePairIndirect {
+                    source: pair,
+                    address,
+                }
+            } else {
+                I8080InstructionSet::LoadPairIndirect {
+                    destination: pair,
+                    address,
+                }
+            }
+        }
+        (0b01, 0b100) => I8080InstructionSet::Negate,
+        // RETI and RETN do the same thing as far as the processor is concerned
+        (0b01, 0b101) => I8080InstructionSet::ReturnFromInterrupt,
+        (0b01, 0b110) => I8080InstructionSet::SetInterruptMode([0, 0, 1, 2][y as usize % 4]),
+        (0b01, 0b111) => match y {
+            0 => I8080InstructionSet::LoadToSpecial(SpecialRegister::I),
+            1 => I8080InstructionSet::LoadToSpecial(SpecialRegister::R),
+            2 => I8080InstructionSet::LoadFromSpecial(SpecialRegister::I),
+            3 => I8080InstructionSet::LoadFromSpecial(SpecialRegister::R),
+            4 => I8080InstructionSet::RotateDigit { left: false },
+            5 => I8080InstructionSet::RotateDigit { left: true },
+            _ => I8080InstructionSet::Nop,
+        },
+        (0b10, 0b000..=0b011) if y >= 4 => I8080InstructionSet::Block {
+            operation: match z {
+                0b000 => BlockOperation::Load,
+                0b001 => BlockOperation::Compare,
+                0b010 => BlockOperation::Input,
+                _ => BlockOperation::Output,
+            },
+            increment: y & 1 == 0,
+            repeat: y >= 6,
+        },
+        // Everything else is a two byte NOP
+        _ => I8080InstructionSet::Nop,
+    })
 }
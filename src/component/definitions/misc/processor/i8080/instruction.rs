@@ -1,3 +1,12 @@
+use crate::component::processor::InstructionSet;
+#[cfg(feature = "frontend")]
+use crate::component::processor::InstructionTextRepresentation;
+#[cfg(feature = "frontend")]
+use std::borrow::Cow;
+
+// http://www.z80.info/decoding.htm
+// https://gbdev.io/gb-opcodes/optables/
+
 #[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum Register {
     A,
@@ -9,10 +18,26 @@ pub enum Register {
     L,
 }
 
+/// The Z80 index registers, which stand in for HL when an instruction is prefixed
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum IndexRegister {
+    Ix,
+    Iy,
+}
+
 #[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum SingleByteArgument {
     Register(Register),
     HlIndirect,
+    /// (IX+d) and (IY+d)
+    IndexIndirect {
+        register: IndexRegister,
+        displacement: i8,
+    },
+    /// The undocumented halves of the index registers, IXH and friends
+    IndexHigh(IndexRegister),
+    IndexLow(IndexRegister),
+    Immediate(u8),
 }
 
 impl SingleByteArgument {
@@ -31,17 +56,305 @@ impl SingleByteArgument {
     }
 }
 
-pub enum I8080Instruction {
-    Nop,
-    Ld,
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum RegisterPair {
+    Bc,
+    De,
+    Hl,
+    Sp,
+    /// Only usable with push and pop
+    Af,
+    Ix,
+    Iy,
 }
 
-pub enum Lr35902Instruction {}
+impl RegisterPair {
+    /// The pairs as most instructions number them
+    pub fn from_id(id: u8) -> Option<Self> {
+        match id {
+            0b00 => Some(RegisterPair::Bc),
+            0b01 => Some(RegisterPair::De),
+            0b10 => Some(RegisterPair::Hl),
+            0b11 => Some(RegisterPair::Sp),
+            _ => None,
+        }
+    }
 
-pub enum Z80Instruction {}
+    /// The pairs as push and pop number them
+    pub fn from_stack_id(id: u8) -> Option<Self> {
+        match id {
+            0b11 => Some(RegisterPair::Af),
+            _ => Self::from_id(id),
+        }
+    }
+}
 
-pub enum InstructionSet {
-    I8080(I8080Instruction),
-    Lr35902(Lr35902Instruction),
-    Z80(Z80Instruction),
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Condition {
+    NotZero,
+    Zero,
+    NotCarry,
+    Carry,
+    /// Parity odd on the 8080, no overflow on the Z80
+    ParityOdd,
+    ParityEven,
+    Positive,
+    Negative,
+}
+
+impl Condition {
+    pub fn from_id(id: u8) -> Option<Self> {
+        match id {
+            0b000 => Some(Condition::NotZero),
+            0b001 => Some(Condition::Zero),
+            0b010 => Some(Condition::NotCarry),
+            0b011 => Some(Condition::Carry),
+            0b100 => Some(Condition::ParityOdd),
+            0b101 => Some(Condition::ParityEven),
+            0b110 => Some(Condition::Positive),
+            0b111 => Some(Condition::Negative),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum AluOperation {
+    Add,
+    AddWithCarry,
+    Subtract,
+    SubtractWithBorrow,
+    And,
+    Xor,
+    Or,
+    Compare,
+}
+
+impl AluOperation {
+    pub fn from_id(id: u8) -> Option<Self> {
+        match id {
+            0b000 => Some(AluOperation::Add),
+            0b001 => Some(AluOperation::AddWithCarry),
+            0b010 => Some(AluOperation::Subtract),
+            0b011 => Some(AluOperation::SubtractWithBorrow),
+            0b100 => Some(AluOperation::And),
+            0b101 => Some(AluOperation::Xor),
+            0b110 => Some(AluOperation::Or),
+            0b111 => Some(AluOperation::Compare),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum RotateOperation {
+    RotateLeftCircular,
+    RotateRightCircular,
+    RotateLeft,
+    RotateRight,
+    ShiftLeftArithmetic,
+    ShiftRightArithmetic,
+    /// Undocumented on the Z80, shifts a one in
+    ShiftLeftLogical,
+    ShiftRightLogical,
+    /// Takes the place of the undocumented shift on the LR35902
+    Swap,
+}
+
+impl RotateOperation {
+    pub fn from_id(id: u8) -> Option<Self> {
+        match id {
+            0b000 => Some(RotateOperation::RotateLeftCircular),
+            0b001 => Some(RotateOperation::RotateRightCircular),
+            0b010 => Some(RotateOperation::RotateLeft),
+            0b011 => Some(RotateOperation::RotateRight),
+            0b100 => Some(RotateOperation::ShiftLeftArithmetic),
+            0b101 => Some(RotateOperation::ShiftRightArithmetic),
+            0b110 => Some(RotateOperation::ShiftLeftLogical),
+            0b111 => Some(RotateOperation::ShiftRightLogical),
+            _ => None,
+        }
+    }
+}
+
+/// Memory the accumulator is loaded from or stored to
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum AccumulatorIndirect {
+    Absolute(u16),
+    Pair(RegisterPair),
+    /// LR35902 only, 0xff00 plus the argument
+    HighPage(u8),
+    /// LR35902 only, 0xff00 plus C
+    HighPageC,
+    /// LR35902 only, HL is adjusted after the access
+    HlIncrement,
+    HlDecrement,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum SpecialRegister {
+    /// Interrupt vector base
+    I,
+    /// Memory refresh counter
+    R,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Port {
+    Immediate(u8),
+    C,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum BlockOperation {
+    Load,
+    Compare,
+    Input,
+    Output,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum I8080InstructionSet {
+    Nop,
+    /// Stops execution until an interrupt arrives
+    Halt,
+    /// LR35902 only, also stops the clocks
+    Stop,
+    /// A opcode the LR35902 doesn't have, it hangs the processor
+    Lock,
+    Load {
+        destination: SingleByteArgument,
+        source: SingleByteArgument,
+    },
+    LoadPair {
+        destination: RegisterPair,
+        value: u16,
+    },
+    LoadAccumulator {
+        source: AccumulatorIndirect,
+    },
+    StoreAccumulator {
+        destination: AccumulatorIndirect,
+    },
+    LoadPairIndirect {
+        destination: RegisterPair,
+        address: u16,
+    },
+    StorePairIndirect {
+        source: RegisterPair,
+        address: u16,
+    },
+    LoadStackPointer {
+        source: RegisterPair,
+    },
+    /// Z80 only
+    LoadFromSpecial(SpecialRegister),
+    LoadToSpecial(SpecialRegister),
+    /// LR35902 only, HL = SP + offset
+    LoadStackOffset(i8),
+    /// LR35902 only
+    AddStackPointer(i8),
+    Push(RegisterPair),
+    Pop(RegisterPair),
+    /// DE and HL, or the index register standing in for it
+    ExchangeDeHl,
+    /// The top of the stack with HL, or the index register standing in for it
+    ExchangeStack(RegisterPair),
+    /// Z80 only, AF with the shadow AF
+    ExchangeAf,
+    /// Z80 only, BC DE and HL with their shadows
+    ExchangeShadow,
+    Alu {
+        operation: AluOperation,
+        argument: SingleByteArgument,
+    },
+    Increment(SingleByteArgument),
+    Decrement(SingleByteArgument),
+    IncrementPair(RegisterPair),
+    DecrementPair(RegisterPair),
+    AddPair {
+        destination: RegisterPair,
+        source: RegisterPair,
+    },
+    /// Z80 only, always into HL
+    AddPairWithCarry(RegisterPair),
+    SubtractPairWithBorrow(RegisterPair),
+    /// The accumulator only versions that leave most flags alone
+    RotateAccumulator(RotateOperation),
+    Rotate {
+        operation: RotateOperation,
+        argument: SingleByteArgument,
+    },
+    TestBit {
+        bit: u8,
+        argument: SingleByteArgument,
+    },
+    ResetBit {
+        bit: u8,
+        argument: SingleByteArgument,
+    },
+    SetBit {
+        bit: u8,
+        argument: SingleByteArgument,
+    },
+    /// Z80 only, rotates a digit through A and (HL)
+    RotateDigit {
+        left: bool,
+    },
+    DecimalAdjust,
+    Complement,
+    /// Z80 only
+    Negate,
+    SetCarry,
+    ComplementCarry,
+    Jump {
+        condition: Option<Condition>,
+        address: u16,
+    },
+    /// Z80 and LR35902 only
+    JumpRelative {
+        condition: Option<Condition>,
+        offset: i8,
+    },
+    JumpIndirect(RegisterPair),
+    /// Z80 only
+    DecrementJumpNotZero(i8),
+    Call {
+        condition: Option<Condition>,
+        address: u16,
+    },
+    Return {
+        condition: Option<Condition>,
+    },
+    /// RETI on the LR35902 enables interrupts, RETN on the Z80 restores them from before the NMI
+    ReturnFromInterrupt,
+    Restart(u8),
+    DisableInterrupts,
+    EnableInterrupts,
+    /// Z80 only
+    SetInterruptMode(u8),
+    /// None reads into nothing but still sets the flags, Z80 only
+    Input {
+        destination: Option<Register>,
+        port: Port,
+    },
+    Output {
+        source: Option<Register>,
+        port: Port,
+    },
+    /// Z80 only
+    Block {
+        operation: BlockOperation,
+        increment: bool,
+        repeat: bool,
+    },
+}
+
+impl InstructionSet for I8080InstructionSet {
+    #[cfg(feature = "frontend")]
+    fn to_text_representation(&self) -> InstructionTextRepresentation {
+        InstructionTextRepresentation {
+            instruction_mnemonic: Cow::Owned(format!("{:?}", self)),
+        }
+    }
 }
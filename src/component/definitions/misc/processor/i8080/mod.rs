@@ -1,10 +1,46 @@
-use crate::component::{Component, FromConfig};
-use crate::rom::RomManager;
+use crate::{
+    component::{
+        interrupt::InterruptLine,
+        memory::{MemoryOperationError, MemoryTranslationTable},
+        port::{PortTranslationTable, PORT_TRANSLATION_TABLE_TOPIC},
+        processor::{
+            InstructionDecompilingError, InstructionInterpretingError, ProcessorComponent,
+            ProcessorRegister,
+        },
+        schedulable::SchedulableComponent,
+        snapshot::SnapshotableComponent,
+        Component, ComponentVersion, FromConfig, SemanticVersion,
+    },
+    machine::QueryableComponents,
+    rom::RomManager,
+};
+use decode::decode_instruction;
 use enumflags2::bitflags;
+use instruction::{
+    AccumulatorIndirect, AluOperation, BlockOperation, Condition, I8080InstructionSet,
+    IndexRegister, Port, Register, RegisterPair, RotateOperation, SingleByteArgument,
+    SpecialRegister,
+};
+use num::rational::Ratio;
+use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 
-mod decode;
-mod instruction;
+pub mod decode;
+pub mod instruction;
+#[cfg(test)]
+pub mod test;
+pub mod timing;
+
+/// Where RST 7 and interrupt modes 0 and 1 go, the data bus floats to 0xff which happens to be RST 7
+const IRQ_VECTOR: u16 = 0x0038;
+/// Where the Z80 goes on a NMI
+const NMI_VECTOR: u16 = 0x0066;
+/// LR35902 interrupt enable register
+const INTERRUPT_ENABLE_ADDRESS: u16 = 0xffff;
+/// LR35902 interrupt flag register
+const INTERRUPT_FLAG_ADDRESS: u16 = 0xff0f;
+/// LR35902 interrupts each get 8 bytes starting here, in priority order
+const INTERRUPT_VECTOR_BASE: u16 = 0x0040;
 
 #[bitflags]
 #[repr(u8)]
@@ -15,8 +51,9 @@ enum Z80FlagRegister {
     __Unused0 = 0b0010_0000,
     HalfCarry = 0b0001_0000,
     __Unused1 = 0b0000_1000,
-    Overflow = 0b0000_0100,
-    Parity = 0b0000_0010,
+    /// Parity for logic operations, overflow for arithmetic
+    ParityOverflow = 0b0000_0100,
+    Subtract = 0b0000_0010,
     Carry = 0b0000_0001,
 }
 
@@ -44,50 +81,1408 @@ enum I8080FlagRegister {
     AuxiliaryCarry = 0b0001_0000,
     __Unused1 = 0b0000_1000,
     Parity = 0b0000_0100,
+    /// Always set
     __Unused2 = 0b0000_0010,
     Carry = 0b0000_0001,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+/// The flags the interpreter works with, which live in different places on each kind
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum Flag {
+    Sign,
+    Zero,
+    HalfCarry,
+    ParityOverflow,
+    Subtract,
+    Carry,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize)]
 pub enum I8080Kind {
     I8080,
     Z80,
     Lr35902,
 }
 
-pub struct I8080 {}
+impl I8080Kind {
+    /// Where the flag lives in F, zero if this kind doesn't have it so setting it does nothing
+    fn flag_mask(&self, flag: Flag) -> u8 {
+        match (self, flag) {
+            (I8080Kind::I8080, Flag::Sign) => I8080FlagRegister::Sign as u8,
+            (I8080Kind::I8080, Flag::Zero) => I8080FlagRegister::Zero as u8,
+            (I8080Kind::I8080, Flag::HalfCarry) => I8080FlagRegister::AuxiliaryCarry as u8,
+            (I8080Kind::I8080, Flag::ParityOverflow) => I8080FlagRegister::Parity as u8,
+            (I8080Kind::I8080, Flag::Carry) => I8080FlagRegister::Carry as u8,
+            (I8080Kind::Z80, Flag::Sign) => Z80FlagRegister::Sign as u8,
+            (I8080Kind::Z80, Flag::Zero) => Z80FlagRegister::Zero as u8,
+            (I8080Kind::Z80, Flag::HalfCarry) => Z80FlagRegister::HalfCarry as u8,
+            (I8080Kind::Z80, Flag::ParityOverflow) => Z80FlagRegister::ParityOverflow as u8,
+            (I8080Kind::Z80, Flag::Subtract) => Z80FlagRegister::Subtract as u8,
+            (I8080Kind::Z80, Flag::Carry) => Z80FlagRegister::Carry as u8,
+            (I8080Kind::Lr35902, Flag::Zero) => Lr35902FlagRegister::Zero as u8,
+            (I8080Kind::Lr35902, Flag::HalfCarry) => Lr35902FlagRegister::HalfCarry as u8,
+            (I8080Kind::Lr35902, Flag::Subtract) => Lr35902FlagRegister::Subtract as u8,
+            (I8080Kind::Lr35902, Flag::Carry) => Lr35902FlagRegister::Carry as u8,
+            _ => 0,
+        }
+    }
 
-impl Component for I8080 {}
+    /// Bits of F that can't be changed, even by popping AF
+    fn fixed_flags(&self, flags: u8) -> u8 {
+        match self {
+            I8080Kind::I8080 => {
+                (flags & !(I8080FlagRegister::__Unused0 as u8 | I8080FlagRegister::__Unused1 as u8))
+                    | I8080FlagRegister::__Unused2 as u8
+            }
+            I8080Kind::Z80 => flags,
+            I8080Kind::Lr35902 => flags & 0xf0,
+        }
+    }
+}
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct I8080Registers {
+    accumulator: u8,
+    flags: u8,
+    b: u8,
+    c: u8,
+    d: u8,
+    e: u8,
+    h: u8,
+    l: u8,
+    stack_pointer: u16,
+    /// IX and IY, Z80 only
+    index_registers: [u16; 2],
+    /// I and R, Z80 only
+    interrupt_vector: u8,
+    memory_refresh: u8,
+    /// AF', BC', DE' and HL', Z80 only
+    shadow: [u16; 4],
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct I8080Snapshot {
+    pub registers: I8080Registers,
+    pub interrupt_enable: bool,
+    pub interrupt_enable_backup: bool,
+    pub interrupt_enable_pending: bool,
+    pub interrupt_mode: u8,
+    pub halted: bool,
+    pub locked: bool,
+    pub reset_pending: bool,
+    pub cycles_remaining: u32,
+    pub irq: bool,
+    pub nmi: bool,
+}
+
+#[derive(Debug, Serialize)]
 pub struct I8080Config {
+    pub frequency: Ratio<u32>,
     pub kind: I8080Kind,
+    /// Level triggered, unused on the LR35902 which takes its interrupts from memory mapped registers
+    #[serde(skip)]
+    pub irq: InterruptLine,
+    /// Edge triggered, Z80 only
+    #[serde(skip)]
+    pub nmi: InterruptLine,
 }
 
 impl I8080Config {
-    pub fn lr35902() -> Self {
+    pub fn lr35902(frequency: Ratio<u32>) -> Self {
         Self {
+            frequency,
             kind: I8080Kind::Lr35902,
+            irq: InterruptLine::default(),
+            nmi: InterruptLine::default(),
         }
     }
 
-    pub fn z80() -> Self {
+    pub fn z80(frequency: Ratio<u32>) -> Self {
         Self {
+            frequency,
             kind: I8080Kind::Z80,
+            irq: InterruptLine::default(),
+            nmi: InterruptLine::default(),
         }
     }
 
-    pub fn i8080() -> Self {
+    pub fn i8080(frequency: Ratio<u32>) -> Self {
         Self {
+            frequency,
             kind: I8080Kind::I8080,
+            irq: InterruptLine::default(),
+            nmi: InterruptLine::default(),
+        }
+    }
+}
+
+pub struct I8080 {
+    config: I8080Config,
+    registers: I8080Registers,
+    /// The master interrupt enable, IME on the LR35902
+    interrupt_enable: bool,
+    /// Where the interrupt enable is kept while a NMI is serviced, Z80 only
+    interrupt_enable_backup: bool,
+    /// EI takes effect after the instruction following it
+    interrupt_enable_pending: bool,
+    /// Z80 only
+    interrupt_mode: u8,
+    /// Waiting for a interrupt after HALT or STOP
+    halted: bool,
+    /// A LR35902 illegal opcode hung the processor, only a reset gets it going again
+    locked: bool,
+    reset_pending: bool,
+    /// T-states left until the current instruction is done, counted down by [SchedulableComponent::tick]
+    cycles_remaining: u32,
+    /// Branches taken and blocks repeated by the current instruction on top of its base cost
+    extra_cycles: u32,
+    /// The first access of the current instruction that faulted, returned once it is done
+    bus_fault: Option<MemoryOperationError>,
+    /// The I/O port space, None if the machine has none
+    ports: Option<Arc<PortTranslationTable>>,
+}
+
+impl I8080 {
+    pub fn assert_irq(&self) {
+        self.config.irq.raise();
+    }
+
+    pub fn deassert_irq(&self) {
+        self.config.irq.lower();
+    }
+
+    pub fn assert_nmi(&self) {
+        self.config.nmi.raise();
+    }
+
+    fn flag(&self, flag: Flag) -> bool {
+        self.registers.flags & self.config.kind.flag_mask(flag) != 0
+    }
+
+    fn set_flag(&mut self, flag: Flag, value: bool) {
+        let mask = self.config.kind.flag_mask(flag);
+
+        if value {
+            self.registers.flags |= mask;
+        } else {
+            self.registers.flags &= !mask;
+        }
+    }
+
+    fn set_sign_and_zero(&mut self, value: u8) {
+        self.set_flag(Flag::Sign, value & 0x80 != 0);
+        self.set_flag(Flag::Zero, value == 0);
+    }
+
+    /// The 8080 has no overflow flag, it always reports parity instead
+    fn set_arithmetic_flags(
+        &mut self,
+        result: u8,
+        half_carry: bool,
+        overflow: bool,
+        subtract: bool,
+        carry: bool,
+    ) {
+        self.set_sign_and_zero(result);
+        self.set_flag(Flag::HalfCarry, half_carry);
+        self.set_flag(
+            Flag::ParityOverflow,
+            if self.config.kind == I8080Kind::I8080 {
+                parity(result)
+            } else {
+                overflow
+            },
+        );
+        self.set_flag(Flag::Subtract, subtract);
+        self.set_flag(Flag::Carry, carry);
+    }
+
+    fn set_logic_flags(&mut self, result: u8, half_carry: bool) {
+        self.set_sign_and_zero(result);
+        self.set_flag(Flag::HalfCarry, half_carry);
+        self.set_flag(Flag::ParityOverflow, parity(result));
+        self.set_flag(Flag::Subtract, false);
+        self.set_flag(Flag::Carry, false);
+    }
+
+    fn condition(&self, condition: Option<Condition>) -> bool {
+        match condition {
+            None => true,
+            Some(Condition::NotZero) => !self.flag(Flag::Zero),
+            Some(Condition::Zero) => self.flag(Flag::Zero),
+            Some(Condition::NotCarry) => !self.flag(Flag::Carry),
+            Some(Condition::Carry) => self.flag(Flag::Carry),
+            Some(Condition::ParityOdd) => !self.flag(Flag::ParityOverflow),
+            Some(Condition::ParityEven) => self.flag(Flag::ParityOverflow),
+            Some(Condition::Positive) => !self.flag(Flag::Sign),
+            Some(Condition::Negative) => self.flag(Flag::Sign),
+        }
+    }
+
+    fn register(&self, register: Register) -> u8 {
+        match register {
+            Register::A => self.registers.accumulator,
+            Register::B => self.registers.b,
+            Register::C => self.registers.c,
+            Register::D => self.registers.d,
+            Register::E => self.registers.e,
+            Register::H => self.registers.h,
+            Register::L => self.registers.l,
+        }
+    }
+
+    fn set_register(&mut self, register: Register, value: u8) {
+        *match register {
+            Register::A => &mut self.registers.accumulator,
+            Register::B => &mut self.registers.b,
+            Register::C => &mut self.registers.c,
+            Register::D => &mut self.registers.d,
+            Register::E => &mut self.registers.e,
+            Register::H => &mut self.registers.h,
+            Register::L => &mut self.registers.l,
+        } = value;
+    }
+
+    fn register_pair(&self, pair: RegisterPair) -> u16 {
+        match pair {
+            RegisterPair::Bc => u16::from_be_bytes([self.registers.b, self.registers.c]),
+            RegisterPair::De => u16::from_be_bytes([self.registers.d, self.registers.e]),
+            RegisterPair::Hl => u16::from_be_bytes([self.registers.h, self.registers.l]),
+            RegisterPair::Sp => self.registers.stack_pointer,
+            RegisterPair::Af => {
+                u16::from_be_bytes([self.registers.accumulator, self.registers.flags])
+            }
+            RegisterPair::Ix => self.registers.index_registers[0],
+            RegisterPair::Iy => self.registers.index_registers[1],
+        }
+    }
+
+    fn set_register_pair(&mut self, pair: RegisterPair, value: u16) {
+        let [high, low] = value.to_be_bytes();
+
+        match pair {
+            RegisterPair::Bc => [self.registers.b, self.registers.c] = [high, low],
+            RegisterPair::De => [self.registers.d, self.registers.e] = [high, low],
+            RegisterPair::Hl => [self.registers.h, self.registers.l] = [high, low],
+            RegisterPair::Sp => self.registers.stack_pointer = value,
+            RegisterPair::Af => {
+                self.registers.accumulator = high;
+                self.registers.flags = self.config.kind.fixed_flags(low);
+            }
+            RegisterPair::Ix => self.registers.index_registers[0] = value,
+            RegisterPair::Iy => self.registers.index_registers[1] = value,
+        }
+    }
+
+    fn index_register(&self, register: IndexRegister) -> u16 {
+        self.registers.index_registers[register as usize]
+    }
+
+    fn read_byte(&mut self, memory_translation_table: &MemoryTranslationTable, address: u16) -> u8 {
+        let mut value = 0;
+
+        if let Err(error) = memory_translation_table
+            .read_or_open_bus(address as usize, std::array::from_mut(&mut value))
+        {
+            self.bus_fault.get_or_insert(error);
+        }
+
+        value
+    }
+
+    fn write_byte(
+        &mut self,
+        memory_translation_table: &MemoryTranslationTable,
+        address: u16,
+        value: u8,
+    ) {
+        if let Err(error) = memory_translation_table
+            .write_or_open_bus(address as usize, std::array::from_ref(&value))
+        {
+            self.bus_fault.get_or_insert(error);
+        }
+    }
+
+    fn read_word(
+        &mut self,
+        memory_translation_table: &MemoryTranslationTable,
+        address: u16,
+    ) -> u16 {
+        u16::from_le_bytes([
+            self.read_byte(memory_translation_table, address),
+            self.read_byte(memory_translation_table, address.wrapping_add(1)),
+        ])
+    }
+
+    fn write_word(
+        &mut self,
+        memory_translation_table: &MemoryTranslationTable,
+        address: u16,
+        value: u16,
+    ) {
+        let [low, high] = value.to_le_bytes();
+        self.write_byte(memory_translation_table, address, low);
+        self.write_byte(memory_translation_table, address.wrapping_add(1), high);
+    }
+
+    fn push(&mut self, memory_translation_table: &MemoryTranslationTable, value: u16) {
+        self.registers.stack_pointer = self.registers.stack_pointer.wrapping_sub(2);
+        self.write_word(
+            memory_translation_table,
+            self.registers.stack_pointer,
+            value,
+        );
+    }
+
+    fn pop(&mut self, memory_translation_table: &MemoryTranslationTable) -> u16 {
+        let value = self.read_word(memory_translation_table, self.registers.stack_pointer);
+        self.registers.stack_pointer = self.registers.stack_pointer.wrapping_add(2);
+        value
+    }
+
+    /// Pushes the program pointer and jumps somewhere else, like a CALL
+    fn call(
+        &mut self,
+        program_pointer: &mut usize,
+        memory_translation_table: &MemoryTranslationTable,
+        address: u16,
+    ) {
+        self.push(memory_translation_table, *program_pointer as u16);
+        *program_pointer = address as usize;
+    }
+
+    /// Where a argument is in memory, if it is in memory at all
+    fn argument_address(&self, argument: SingleByteArgument) -> Option<u16> {
+        match argument {
+            SingleByteArgument::HlIndirect => Some(self.register_pair(RegisterPair::Hl)),
+            SingleByteArgument::IndexIndirect {
+                register,
+                displacement,
+            } => Some(
+                self.index_register(register)
+                    .wrapping_add_signed(displacement as i16),
+            ),
+            _ => None,
+        }
+    }
+
+    fn read_argument(
+        &mut self,
+        memory_translation_table: &MemoryTranslationTable,
+        argument: SingleByteArgument,
+    ) -> u8 {
+        if let Some(address) = self.argument_address(argument) {
+            return self.read_byte(memory_translation_table, address);
+        }
+
+        match argument {
+            SingleByteArgument::Register(register) => self.register(register),
+            SingleByteArgument::IndexHigh(register) => (self.index_register(register) >> 8) as u8,
+            SingleByteArgument::IndexLow(register) => self.index_register(register) as u8,
+            SingleByteArgument::Immediate(value) => value,
+            _ => unreachable!(),
+        }
+    }
+
+    fn write_argument(
+        &mut self,
+        memory_translation_table: &MemoryTranslationTable,
+        argument: SingleByteArgument,
+        value: u8,
+    ) {
+        if let Some(address) = self.argument_address(argument) {
+            self.write_byte(memory_translation_table, address, value);
+            return;
+        }
+
+        match argument {
+            SingleByteArgument::Register(register) => self.set_register(register, value),
+            SingleByteArgument::IndexHigh(register) => {
+                let index = &mut self.registers.index_registers[register as usize];
+                *index = (*index & 0x00ff) | ((value as u16) << 8);
+            }
+            SingleByteArgument::IndexLow(register) => {
+                let index = &mut self.registers.index_registers[register as usize];
+                *index = (*index & 0xff00) | value as u16;
+            }
+            SingleByteArgument::Immediate(_) => unreachable!("Immediates cannot be written to"),
+            _ => unreachable!(),
+        }
+    }
+
+    /// Runs a operation on a argument, writing the result back where it came from
+    fn modify(
+        &mut self,
+        memory_translation_table: &MemoryTranslationTable,
+        argument: SingleByteArgument,
+        operation: impl FnOnce(&mut Self, u8) -> u8,
+    ) {
+        let value = self.read_argument(memory_translation_table, argument);
+        let result = operation(self, value);
+        self.write_argument(memory_translation_table, argument, result);
+    }
+
+    fn accumulator_indirect_address(&mut self, indirect: AccumulatorIndirect) -> u16 {
+        match indirect {
+            AccumulatorIndirect::Absolute(address) => address,
+            AccumulatorIndirect::Pair(pair) => self.register_pair(pair),
+            AccumulatorIndirect::HighPage(offset) => 0xff00 | offset as u16,
+            AccumulatorIndirect::HighPageC => 0xff00 | self.registers.c as u16,
+            AccumulatorIndirect::HlIncrement | AccumulatorIndirect::HlDecrement => {
+                let address = self.register_pair(RegisterPair::Hl);
+
+                self.set_register_pair(
+                    RegisterPair::Hl,
+                    if indirect == AccumulatorIndirect::HlIncrement {
+                        address.wrapping_add(1)
+                    } else {
+                        address.wrapping_sub(1)
+                    },
+                );
+
+                address
+            }
+        }
+    }
+
+    /// Unconnected ports float high
+    fn input(&self, port: u16) -> u8 {
+        self.ports
+            .as_ref()
+            .and_then(|ports| ports.read(port))
+            .unwrap_or_else(|| {
+                tracing::trace!("Read from unconnected port {:#06x}", port);
+
+                0xff
+            })
+    }
+
+    fn output(&self, port: u16, value: u8) {
+        if !self
+            .ports
+            .as_ref()
+            .is_some_and(|ports| ports.write(port, value))
+        {
+            tracing::trace!("Wrote {:#04x} to unconnected port {:#06x}", value, port);
+        }
+    }
+
+    /// What ends up on all 16 address lines. The 8080 repeats the port in the high byte, the Z80 puts the accumulator
+    /// or B there
+    fn port(&self, port: Port) -> u16 {
+        match port {
+            Port::Immediate(port) if self.config.kind == I8080Kind::Z80 => {
+                u16::from_be_bytes([self.registers.accumulator, port])
+            }
+            Port::Immediate(port) => u16::from_be_bytes([port, port]),
+            Port::C => self.register_pair(RegisterPair::Bc),
+        }
+    }
+
+    fn subtract(&mut self, value: u8, operand: u8, borrow: bool) -> u8 {
+        let result = value as i16 - operand as i16 - borrow as i16;
+        let half_borrow = (value & 0x0f) as i16 - (operand & 0x0f) as i16 - (borrow as i16) < 0;
+
+        // The 8080 subtracts by adding the complement, so its auxiliary carry is the opposite of a half borrow
+        let half_carry = if self.config.kind == I8080Kind::I8080 {
+            !half_borrow
+        } else {
+            half_borrow
+        };
+
+        self.set_arithmetic_flags(
+            result as u8,
+            half_carry,
+            (value ^ operand) & (value ^ result as u8) & 0x80 != 0,
+            true,
+            result < 0,
+        );
+
+        result as u8
+    }
+
+    fn alu(&mut self, operation: AluOperation, value: u8) {
+        let accumulator = self.registers.accumulator;
+        let carry = self.flag(Flag::Carry);
+
+        match operation {
+            AluOperation::Add | AluOperation::AddWithCarry => {
+                let carry = (operation == AluOperation::AddWithCarry && carry) as u8;
+                let result = accumulator as u16 + value as u16 + carry as u16;
+
+                self.set_arithmetic_flags(
+                    result as u8,
+                    (accumulator & 0x0f) + (value & 0x0f) + carry > 0x0f,
+                    !(accumulator ^ value) & (accumulator ^ result as u8) & 0x80 != 0,
+                    false,
+                    result > 0xff,
+                );
+                self.registers.accumulator = result as u8;
+            }
+            AluOperation::Subtract | AluOperation::SubtractWithBorrow => {
+                self.registers.accumulator = self.subtract(
+                    accumulator,
+                    value,
+                    operation == AluOperation::SubtractWithBorrow && carry,
+                );
+            }
+            AluOperation::Compare => {
+                self.subtract(accumulator, value, false);
+            }
+            AluOperation::And => {
+                let result = accumulator & value;
+
+                // The 8080 takes the auxiliary carry from bit 3 of the operands
+                let half_carry = match self.config.kind {
+                    I8080Kind::I8080 => (accumulator | value) & 0x08 != 0,
+                    _ => true,
+                };
+                self.set_logic_flags(result, half_carry);
+                self.registers.accumulator = result;
+            }
+            AluOperation::Xor => {
+                let result = accumulator ^ value;
+                self.set_logic_flags(result, false);
+                self.registers.accumulator = result;
+            }
+            AluOperation::Or => {
+                let result = accumulator | value;
+                self.set_logic_flags(result, false);
+                self.registers.accumulator = result;
+            }
+        }
+    }
+
+    fn increment(&mut self, value: u8) -> u8 {
+        let result = value.wrapping_add(1);
+        let carry = self.flag(Flag::Carry);
+
+        self.set_arithmetic_flags(result, value & 0x0f == 0x0f, value == 0x7f, false, carry);
+
+        result
+    }
+
+    fn decrement(&mut self, value: u8) -> u8 {
+        let result = value.wrapping_sub(1);
+        let carry = self.flag(Flag::Carry);
+        let half_borrow = value & 0x0f == 0;
+
+        self.set_arithmetic_flags(
+            result,
+            if self.config.kind == I8080Kind::I8080 {
+                !half_borrow
+            } else {
+                half_borrow
+            },
+            value == 0x80,
+            true,
+            carry,
+        );
+
+        result
+    }
+
+    /// The rotated value and the bit that fell out into carry
+    fn rotate(&self, operation: RotateOperation, value: u8) -> (u8, bool) {
+        let carry = self.flag(Flag::Carry) as u8;
+
+        match operation {
+            RotateOperation::RotateLeftCircular => (value.rotate_left(1), value & 0x80 != 0),
+            RotateOperation::RotateRightCircular => (value.rotate_right(1), value & 0x01 != 0),
+            RotateOperation::RotateLeft => ((value << 1) | carry, value & 0x80 != 0),
+            RotateOperation::RotateRight => ((value >> 1) | (carry << 7), value & 0x01 != 0),
+            RotateOperation::ShiftLeftArithmetic => (value << 1, value & 0x80 != 0),
+            RotateOperation::ShiftRightArithmetic => {
+                ((value >> 1) | (value & 0x80), value & 0x01 != 0)
+            }
+            RotateOperation::ShiftLeftLogical => ((value << 1) | 0x01, value & 0x80 != 0),
+            RotateOperation::ShiftRightLogical => (value >> 1, value & 0x01 != 0),
+            RotateOperation::Swap => (value.rotate_left(4), false),
+        }
+    }
+
+    /// 16 bit additions leave the sign, zero and parity flags alone
+    fn add_pair(&mut self, value: u16, operand: u16) -> u16 {
+        let result = value as u32 + operand as u32;
+
+        // The 8080 only touches carry
+        if self.config.kind != I8080Kind::I8080 {
+            self.set_flag(
+                Flag::HalfCarry,
+                (value & 0x0fff) + (operand & 0x0fff) > 0x0fff,
+            );
+            self.set_flag(Flag::Subtract, false);
+        }
+        self.set_flag(Flag::Carry, result > 0xffff);
+
+        result as u16
+    }
+
+    /// ADC HL and SBC HL, which unlike ADD HL set every flag
+    fn add_pair_with_carry(&mut self, operand: u16, subtract: bool) {
+        let value = self.register_pair(RegisterPair::Hl);
+        let carry = self.flag(Flag::Carry) as i32;
+
+        let (result, half_carry) = if subtract {
+            (
+                value as i32 - operand as i32 - carry,
+                ((value & 0x0fff) as i32 - (operand & 0x0fff) as i32 - carry) < 0,
+            )
+        } else {
+            (
+                value as i32 + operand as i32 + carry,
+                ((value & 0x0fff) as i32 + (operand & 0x0fff) as i32 + carry) > 0x0fff,
+            )
+        };
+        let truncated = result as u16;
+        let overflow = if subtract {
+            (value ^ operand) & (value ^ truncated) & 0x8000 != 0
+        } else {
+            !(value ^ operand) & (value ^ truncated) & 0x8000 != 0
+        };
+
+        self.set_flag(Flag::Sign, truncated & 0x8000 != 0);
+        self.set_flag(Flag::Zero, truncated == 0);
+        self.set_flag(Flag::HalfCarry, half_carry);
+        self.set_flag(Flag::ParityOverflow, overflow);
+        self.set_flag(Flag::Subtract, subtract);
+        self.set_flag(Flag::Carry, !(0..=0xffff).contains(&result));
+        self.set_register_pair(RegisterPair::Hl, truncated);
+    }
+
+    /// SP plus a signed offset, with the flags coming from the low byte like a 8 bit addition
+    fn stack_offset(&mut self, offset: i8) -> u16 {
+        let stack_pointer = self.registers.stack_pointer;
+        let operand = offset as u8 as u16;
+
+        self.set_flag(Flag::Zero, false);
+        self.set_flag(Flag::Subtract, false);
+        self.set_flag(
+            Flag::HalfCarry,
+            (stack_pointer & 0x000f) + (operand & 0x000f) > 0x000f,
+        );
+        self.set_flag(
+            Flag::Carry,
+            (stack_pointer & 0x00ff) + (operand & 0x00ff) > 0x00ff,
+        );
+
+        stack_pointer.wrapping_add_signed(offset as i16)
+    }
+
+    fn decimal_adjust(&mut self) {
+        let accumulator = self.registers.accumulator;
+        let subtract = self.flag(Flag::Subtract);
+        let half_carry = self.flag(Flag::HalfCarry);
+        let mut carry = self.flag(Flag::Carry);
+        // After a subtraction the LR35902 only goes by the flags
+        let check_digits = !(self.config.kind == I8080Kind::Lr35902 && subtract);
+
+        let mut correction = 0;
+        if half_carry || (check_digits && accumulator & 0x0f > 0x09) {
+            correction |= 0x06;
+        }
+        if carry || (check_digits && accumulator > 0x99) {
+            correction |= 0x60;
+            carry = true;
+        }
+
+        let result = if subtract {
+            accumulator.wrapping_sub(correction)
+        } else {
+            accumulator.wrapping_add(correction)
+        };
+
+        let half_carry = match self.config.kind {
+            I8080Kind::Lr35902 => false,
+            _ if subtract => half_carry && accumulator & 0x0f < 0x06,
+            _ => accumulator & 0x0f > 0x09,
+        };
+
+        self.set_sign_and_zero(result);
+        self.set_flag(Flag::HalfCarry, half_carry);
+        self.set_flag(Flag::ParityOverflow, parity(result));
+        self.set_flag(Flag::Carry, carry);
+        self.registers.accumulator = result;
+    }
+
+    fn block(
+        &mut self,
+        program_pointer: &mut usize,
+        memory_translation_table: &MemoryTranslationTable,
+        operation: BlockOperation,
+        increment: bool,
+        repeat: bool,
+    ) -> bool {
+        let step = |value: u16| {
+            if increment {
+                value.wrapping_add(1)
+            } else {
+                value.wrapping_sub(1)
+            }
+        };
+        let hl = self.register_pair(RegisterPair::Hl);
+        self.set_register_pair(RegisterPair::Hl, step(hl));
+
+        let repeat = repeat
+            && match operation {
+                BlockOperation::Load | BlockOperation::Compare => {
+                    let counter = self.register_pair(RegisterPair::Bc).wrapping_sub(1);
+                    self.set_register_pair(RegisterPair::Bc, counter);
+
+                    if operation == BlockOperation::Load {
+                        let de = self.register_pair(RegisterPair::De);
+                        let value = self.read_byte(memory_translation_table, hl);
+                        self.write_byte(memory_translation_table, de, value);
+                        self.set_register_pair(RegisterPair::De, step(de));
+                        self.set_flag(Flag::HalfCarry, false);
+                        self.set_flag(Flag::Subtract, false);
+                        self.set_flag(Flag::ParityOverflow, counter != 0);
+
+                        counter != 0
+                    } else {
+                        let carry = self.flag(Flag::Carry);
+                        let value = self.read_byte(memory_translation_table, hl);
+                        self.subtract(self.registers.accumulator, value, false);
+                        self.set_flag(Flag::Carry, carry);
+                        self.set_flag(Flag::ParityOverflow, counter != 0);
+
+                        counter != 0 && !self.flag(Flag::Zero)
+                    }
+                }
+                BlockOperation::Input | BlockOperation::Output => {
+                    let counter = self.registers.b.wrapping_sub(1);
+
+                    // INI and friends put B on the address lines before counting down, OUTI and friends after
+                    if operation == BlockOperation::Input {
+                        let value = self.input(self.register_pair(RegisterPair::Bc));
+                        self.write_byte(memory_translation_table, hl, value);
+                    } else {
+                        let value = self.read_byte(memory_translation_table, hl);
+                        self.output(u16::from_be_bytes([counter, self.registers.c]), value);
+                    }
+
+                    self.registers.b = counter;
+                    self.set_flag(Flag::Zero, counter == 0);
+                    self.set_flag(Flag::Subtract, true);
+
+                    counter != 0
+                }
+            };
+
+        // Repeating is done by running the instruction again
+        if repeat {
+            *program_pointer = (*program_pointer as u16).wrapping_sub(2) as usize;
         }
+
+        repeat
+    }
+
+    /// The LR35902 keeps its interrupt state in memory, anything not mapped there reads as no interrupts
+    fn service_lr35902_interrupts(
+        &mut self,
+        program_pointer: &mut usize,
+        memory_translation_table: &MemoryTranslationTable,
+    ) {
+        let mut enabled = 0;
+        let mut requested = 0;
+        let _ = memory_translation_table.read(
+            INTERRUPT_ENABLE_ADDRESS as usize,
+            std::array::from_mut(&mut enabled),
+        );
+        let _ = memory_translation_table.read(
+            INTERRUPT_FLAG_ADDRESS as usize,
+            std::array::from_mut(&mut requested),
+        );
+
+        let pending = enabled & requested & 0x1f;
+        if pending == 0 {
+            return;
+        }
+
+        // HALT ends even if the interrupt isn't going to be serviced
+        self.halted = false;
+
+        if !self.interrupt_enable {
+            return;
+        }
+
+        let interrupt = pending.trailing_zeros() as u16;
+        self.interrupt_enable = false;
+        let _ = memory_translation_table.write(
+            INTERRUPT_FLAG_ADDRESS as usize,
+            std::array::from_ref(&(requested & !(1 << interrupt))),
+        );
+        self.call(
+            program_pointer,
+            memory_translation_table,
+            INTERRUPT_VECTOR_BASE + interrupt * 8,
+        );
+        self.cycles_remaining += timing::LR35902_INTERRUPT_CYCLES;
+    }
+}
+
+fn parity(value: u8) -> bool {
+    value.count_ones().is_multiple_of(2)
+}
+
+impl Component for I8080 {
+    fn reset(&mut self) {
+        self.reset_pending = true;
+    }
+
+    fn query_components(&mut self, query: &QueryableComponents) {
+        self.ports = query.event_bus().subscribe(PORT_TRANSLATION_TABLE_TOPIC);
     }
 }
 
+impl SnapshotableComponent for I8080 {
+    fn save_snapshot(&mut self) -> rmpv::Value {
+        let state = I8080Snapshot {
+            registers: self.registers.clone(),
+            interrupt_enable: self.interrupt_enable,
+            interrupt_enable_backup: self.interrupt_enable_backup,
+            interrupt_enable_pending: self.interrupt_enable_pending,
+            interrupt_mode: self.interrupt_mode,
+            halted: self.halted,
+            locked: self.locked,
+            reset_pending: self.reset_pending,
+            cycles_remaining: self.cycles_remaining,
+            irq: self.config.irq.is_raised(),
+            nmi: self.config.nmi.is_raised(),
+        };
+
+        rmpv::ext::to_value(&state).unwrap()
+    }
+
+    fn load_snapshot(&mut self, state: rmpv::Value) {
+        let state = rmpv::ext::from_value::<I8080Snapshot>(state).unwrap();
+
+        self.registers = state.registers;
+        self.interrupt_enable = state.interrupt_enable;
+        self.interrupt_enable_backup = state.interrupt_enable_backup;
+        self.interrupt_enable_pending = state.interrupt_enable_pending;
+        self.interrupt_mode = state.interrupt_mode;
+        self.halted = state.halted;
+        self.locked = state.locked;
+        self.reset_pending = state.reset_pending;
+        self.cycles_remaining = state.cycles_remaining;
+
+        for (line, raised) in [(&self.config.irq, state.irq), (&self.config.nmi, state.nmi)] {
+            if raised {
+                line.raise();
+            } else {
+                line.lower();
+            }
+        }
+    }
+}
+
+impl ComponentVersion for I8080 {
+    const NAME: &'static str = "i8080";
+    const VERSION: SemanticVersion = SemanticVersion::new(1, 0, 0);
+}
+
 impl FromConfig for I8080 {
     type Config = I8080Config;
 
-    fn from_config(rom_manager: Arc<RomManager>, config: Self::Config) -> Self {
-        todo!()
+    fn from_config(_rom_manager: Arc<RomManager>, config: Self::Config) -> Self {
+        Self {
+            registers: I8080Registers {
+                accumulator: 0,
+                flags: config.kind.fixed_flags(0),
+                b: 0,
+                c: 0,
+                d: 0,
+                e: 0,
+                h: 0,
+                l: 0,
+                stack_pointer: 0xffff,
+                index_registers: [0xffff, 0xffff],
+                interrupt_vector: 0,
+                memory_refresh: 0,
+                shadow: [0; 4],
+            },
+            config,
+            interrupt_enable: false,
+            interrupt_enable_backup: false,
+            interrupt_enable_pending: false,
+            interrupt_mode: 0,
+            halted: false,
+            locked: false,
+            reset_pending: true,
+            cycles_remaining: 0,
+            extra_cycles: 0,
+            bus_fault: None,
+            ports: None,
+        }
+    }
+}
+
+impl SchedulableComponent for I8080 {
+    fn tick_rate(&self) -> Ratio<u32> {
+        self.config.frequency
+    }
+
+    fn tick(&mut self, _memory_translation_table: &MemoryTranslationTable) {
+        self.cycles_remaining = self.cycles_remaining.saturating_sub(1);
+    }
+}
+
+impl ProcessorComponent for I8080 {
+    type InstructionSet = I8080InstructionSet;
+
+    fn should_execution_occur(&self) -> bool {
+        !self.halted && !self.locked && self.cycles_remaining == 0
+    }
+
+    fn debug_registers(&self, program_pointer: usize) -> Vec<ProcessorRegister> {
+        let registers = &self.registers;
+
+        vec![
+            ProcessorRegister::new("a", 8, registers.accumulator),
+            ProcessorRegister::new("f", 8, registers.flags),
+            ProcessorRegister::new("b", 8, registers.b),
+            ProcessorRegister::new("c", 8, registers.c),
+            ProcessorRegister::new("d", 8, registers.d),
+            ProcessorRegister::new("e", 8, registers.e),
+            ProcessorRegister::new("h", 8, registers.h),
+            ProcessorRegister::new("l", 8, registers.l),
+            ProcessorRegister::new("sp", 16, registers.stack_pointer),
+            ProcessorRegister::new("pc", 16, program_pointer as u64),
+        ]
+    }
+
+    fn service_interrupts(
+        &mut self,
+        program_pointer: &mut usize,
+        memory_translation_table: &MemoryTranslationTable,
+    ) {
+        if self.reset_pending {
+            self.reset_pending = false;
+            self.halted = false;
+            self.locked = false;
+            self.interrupt_enable = false;
+            self.interrupt_enable_backup = false;
+            self.interrupt_enable_pending = false;
+            self.interrupt_mode = 0;
+            self.registers.interrupt_vector = 0;
+            self.registers.memory_refresh = 0;
+            *program_pointer = 0;
+
+            return;
+        }
+
+        // Only a reset unlocks the processor, and interrupts are only taken between instructions
+        if self.locked || self.cycles_remaining != 0 {
+            return;
+        }
+
+        if self.config.kind == I8080Kind::Lr35902 {
+            self.service_lr35902_interrupts(program_pointer, memory_translation_table);
+            return;
+        }
+
+        if self.config.kind == I8080Kind::Z80 && self.config.nmi.acknowledge() {
+            self.halted = false;
+            self.interrupt_enable_backup = self.interrupt_enable;
+            self.interrupt_enable = false;
+            self.call(program_pointer, memory_translation_table, NMI_VECTOR);
+            self.cycles_remaining += timing::Z80_NMI_CYCLES;
+        } else if self.config.irq.is_raised() && self.interrupt_enable {
+            self.halted = false;
+            self.interrupt_enable = false;
+            self.interrupt_enable_backup = false;
+
+            // Nothing drives the data bus, so mode 0 gets RST 7 like mode 1 does
+            let (vector, cycles) = match (&self.config.kind, self.interrupt_mode) {
+                (I8080Kind::Z80, 2) => (
+                    self.read_word(
+                        memory_translation_table,
+                        u16::from_be_bytes([self.registers.interrupt_vector, 0xff]),
+                    ),
+                    timing::Z80_VECTORED_INTERRUPT_CYCLES,
+                ),
+                (I8080Kind::Z80, _) => (IRQ_VECTOR, timing::Z80_INTERRUPT_CYCLES),
+                _ => (IRQ_VECTOR, timing::I8080_INTERRUPT_CYCLES),
+            };
+
+            self.call(program_pointer, memory_translation_table, vector);
+            self.cycles_remaining += cycles;
+        }
+    }
+
+    fn decompile(
+        &self,
+        cursor: usize,
+        memory_translation_table: &MemoryTranslationTable,
+    ) -> Result<(Self::InstructionSet, u8), InstructionDecompilingError> {
+        // Prefixed Z80 instructions are up to 4 bytes
+        decode_instruction(&self.config.kind, cursor, memory_translation_table)
+            .map_err(|_| InstructionDecompilingError::at(cursor, 4, memory_translation_table))
+    }
+
+    fn interpret(
+        &mut self,
+        program_pointer: &mut usize,
+        instruction: Self::InstructionSet,
+        memory_translation_table: &MemoryTranslationTable,
+    ) -> Result<(), InstructionInterpretingError> {
+        self.extra_cycles = 0;
+
+        if std::mem::take(&mut self.interrupt_enable_pending) {
+            self.interrupt_enable = true;
+            self.interrupt_enable_backup = true;
+        }
+
+        if self.config.kind == I8080Kind::Z80 {
+            let memory_refresh = self.registers.memory_refresh;
+            self.registers.memory_refresh =
+                (memory_refresh & 0x80) | (memory_refresh.wrapping_add(1) & 0x7f);
+        }
+
+        match instruction {
+            I8080InstructionSet::Nop => {}
+            I8080InstructionSet::Halt | I8080InstructionSet::Stop => {
+                // Nothing can press a button to end STOP, so it waits for a interrupt like HALT
+                self.halted = true;
+            }
+            I8080InstructionSet::Lock => {
+                self.locked = true;
+            }
+            I8080InstructionSet::Load {
+                destination,
+                source,
+            } => {
+                let value = self.read_argument(memory_translation_table, source);
+                self.write_argument(memory_translation_table, destination, value);
+            }
+            I8080InstructionSet::LoadPair { destination, value } => {
+                self.set_register_pair(destination, value);
+            }
+            I8080InstructionSet::LoadAccumulator { source } => {
+                let address = self.accumulator_indirect_address(source);
+                self.registers.accumulator = self.read_byte(memory_translation_table, address);
+            }
+            I8080InstructionSet::StoreAccumulator { destination } => {
+                let address = self.accumulator_indirect_address(destination);
+                self.write_byte(
+                    memory_translation_table,
+                    address,
+                    self.registers.accumulator,
+                );
+            }
+            I8080InstructionSet::LoadPairIndirect {
+                destination,
+                address,
+            } => {
+                let value = self.read_word(memory_translation_table, address);
+                self.set_register_pair(destination, value);
+            }
+            I8080InstructionSet::StorePairIndirect { source, address } => {
+                self.write_word(
+                    memory_translation_table,
+                    address,
+                    self.register_pair(source),
+                );
+            }
+            I8080InstructionSet::LoadStackPointer { source } => {
+                self.registers.stack_pointer = self.register_pair(source);
+            }
+            I8080InstructionSet::LoadFromSpecial(register) => {
+                let value = match register {
+                    SpecialRegister::I => self.registers.interrupt_vector,
+                    SpecialRegister::R => self.registers.memory_refresh,
+                };
+
+                self.set_sign_and_zero(value);
+                self.set_flag(Flag::HalfCarry, false);
+                self.set_flag(Flag::Subtract, false);
+                self.set_flag(Flag::ParityOverflow, self.interrupt_enable_backup);
+                self.registers.accumulator = value;
+            }
+            I8080InstructionSet::LoadToSpecial(register) => match register {
+                SpecialRegister::I => self.registers.interrupt_vector = self.registers.accumulator,
+                SpecialRegister::R => self.registers.memory_refresh = self.registers.accumulator,
+            },
+            I8080InstructionSet::LoadStackOffset(offset) => {
+                let value = self.stack_offset(offset);
+                self.set_register_pair(RegisterPair::Hl, value);
+            }
+            I8080InstructionSet::AddStackPointer(offset) => {
+                self.registers.stack_pointer = self.stack_offset(offset);
+            }
+            I8080InstructionSet::Push(pair) => {
+                let value = self.register_pair(pair);
+                self.push(memory_translation_table, value);
+            }
+            I8080InstructionSet::Pop(pair) => {
+                let value = self.pop(memory_translation_table);
+                self.set_register_pair(pair, value);
+            }
+            I8080InstructionSet::ExchangeDeHl => {
+                let de = self.register_pair(RegisterPair::De);
+                let hl = self.register_pair(RegisterPair::Hl);
+                self.set_register_pair(RegisterPair::De, hl);
+                self.set_register_pair(RegisterPair::Hl, de);
+            }
+            I8080InstructionSet::ExchangeStack(pair) => {
+                let value = self.read_word(memory_translation_table, self.registers.stack_pointer);
+                self.write_word(
+                    memory_translation_table,
+                    self.registers.stack_pointer,
+                    self.register_pair(pair),
+                );
+                self.set_register_pair(pair, value);
+            }
+            I8080InstructionSet::ExchangeAf => {
+                let value = self.register_pair(RegisterPair::Af);
+                let shadow = std::mem::replace(&mut self.registers.shadow[0], value);
+                self.set_register_pair(RegisterPair::Af, shadow);
+            }
+            I8080InstructionSet::ExchangeShadow => {
+                for (index, pair) in [RegisterPair::Bc, RegisterPair::De, RegisterPair::Hl]
+                    .into_iter()
+                    .enumerate()
+                {
+                    let value = self.register_pair(pair);
+                    let shadow = std::mem::replace(&mut self.registers.shadow[index + 1], value);
+                    self.set_register_pair(pair, shadow);
+                }
+            }
+            I8080InstructionSet::Alu {
+                operation,
+                argument,
+            } => {
+                let value = self.read_argument(memory_translation_table, argument);
+                self.alu(operation, value);
+            }
+            I8080InstructionSet::Increment(argument) => {
+                self.modify(memory_translation_table, argument, Self::increment);
+            }
+            I8080InstructionSet::Decrement(argument) => {
+                self.modify(memory_translation_table, argument, Self::decrement);
+            }
+            I8080InstructionSet::IncrementPair(pair) => {
+                let value = self.register_pair(pair).wrapping_add(1);
+                self.set_register_pair(pair, value);
+            }
+            I8080InstructionSet::DecrementPair(pair) => {
+                let value = self.register_pair(pair).wrapping_sub(1);
+                self.set_register_pair(pair, value);
+            }
+            I8080InstructionSet::AddPair {
+                destination,
+                source,
+            } => {
+                let value =
+                    self.add_pair(self.register_pair(destination), self.register_pair(source));
+                self.set_register_pair(destination, value);
+            }
+            I8080InstructionSet::AddPairWithCarry(pair) => {
+                self.add_pair_with_carry(self.register_pair(pair), false);
+            }
+            I8080InstructionSet::SubtractPairWithBorrow(pair) => {
+                self.add_pair_with_carry(self.register_pair(pair), true);
+            }
+            I8080InstructionSet::RotateAccumulator(operation) => {
+                let (result, carry) = self.rotate(operation, self.registers.accumulator);
+
+                // The 8080 only touches carry, the LR35902 clears zero on top
+                if self.config.kind != I8080Kind::I8080 {
+                    self.set_flag(Flag::HalfCarry, false);
+                    self.set_flag(Flag::Subtract, false);
+                }
+                if self.config.kind == I8080Kind::Lr35902 {
+                    self.set_flag(Flag::Zero, false);
+                }
+                self.set_flag(Flag::Carry, carry);
+                self.registers.accumulator = result;
+            }
+            I8080InstructionSet::Rotate {
+                operation,
+                argument,
+            } => {
+                self.modify(memory_translation_table, argument, |processor, value| {
+                    let (result, carry) = processor.rotate(operation, value);
+
+                    processor.set_logic_flags(result, false);
+                    processor.set_flag(Flag::Carry, carry);
+
+                    result
+                });
+            }
+            I8080InstructionSet::TestBit { bit, argument } => {
+                let set = self.read_argument(memory_translation_table, argument) & (1 << bit) != 0;
+
+                self.set_flag(Flag::Sign, bit == 7 && set);
+                self.set_flag(Flag::Zero, !set);
+                self.set_flag(Flag::HalfCarry, true);
+                self.set_flag(Flag::ParityOverflow, !set);
+                self.set_flag(Flag::Subtract, false);
+            }
+            I8080InstructionSet::ResetBit { bit, argument } => {
+                self.modify(memory_translation_table, argument, |_, value| {
+                    value & !(1 << bit)
+                });
+            }
+            I8080InstructionSet::SetBit { bit, argument } => {
+                self.modify(memory_translation_table, argument, |_, value| {
+                    value | (1 << bit)
+                });
+            }
+            I8080InstructionSet::RotateDigit { left } => {
+                let address = self.register_pair(RegisterPair::Hl);
+                let value = self.read_byte(memory_translation_table, address);
+                let accumulator = self.registers.accumulator;
+
+                let (memory, accumulator) = if left {
+                    (
+                        (value << 4) | (accumulator & 0x0f),
+                        (accumulator & 0xf0) | (value >> 4),
+                    )
+                } else {
+                    (
+                        (accumulator << 4) | (value >> 4),
+                        (accumulator & 0xf0) | (value & 0x0f),
+                    )
+                };
+
+                self.write_byte(memory_translation_table, address, memory);
+                let carry = self.flag(Flag::Carry);
+                self.set_logic_flags(accumulator, false);
+                self.set_flag(Flag::Carry, carry);
+                self.registers.accumulator = accumulator;
+            }
+            I8080InstructionSet::DecimalAdjust => {
+                self.decimal_adjust();
+            }
+            I8080InstructionSet::Complement => {
+                self.registers.accumulator = !self.registers.accumulator;
+
+                if self.config.kind != I8080Kind::I8080 {
+                    self.set_flag(Flag::HalfCarry, true);
+                    self.set_flag(Flag::Subtract, true);
+                }
+            }
+            I8080InstructionSet::Negate => {
+                self.registers.accumulator = self.subtract(0, self.registers.accumulator, false);
+            }
+            I8080InstructionSet::SetCarry | I8080InstructionSet::ComplementCarry => {
+                let carry = self.flag(Flag::Carry);
+
+                if self.config.kind != I8080Kind::I8080 {
+                    // The Z80 keeps the old carry in half carry
+                    self.set_flag(
+                        Flag::HalfCarry,
+                        self.config.kind == I8080Kind::Z80
+                            && instruction == I8080InstructionSet::ComplementCarry
+                            && carry,
+                    );
+                    self.set_flag(Flag::Subtract, false);
+                }
+                self.set_flag(
+                    Flag::Carry,
+                    instruction == I8080InstructionSet::SetCarry || !carry,
+                );
+            }
+            I8080InstructionSet::Jump { condition, address } => {
+                if self.condition(condition) {
+                    *program_pointer = address as usize;
+                    self.extra_cycles += timing::taken_cycles(&self.config.kind, &instruction);
+                }
+            }
+            I8080InstructionSet::JumpRelative { condition, offset } => {
+                if self.condition(condition) {
+                    *program_pointer =
+                        (*program_pointer as u16).wrapping_add_signed(offset as i16) as usize;
+                    self.extra_cycles += timing::taken_cycles(&self.config.kind, &instruction);
+                }
+            }
+            I8080InstructionSet::JumpIndirect(pair) => {
+                *program_pointer = self.register_pair(pair) as usize;
+            }
+            I8080InstructionSet::DecrementJumpNotZero(offset) => {
+                self.registers.b = self.registers.b.wrapping_sub(1);
+
+                if self.registers.b != 0 {
+                    *program_pointer =
+                        (*program_pointer as u16).wrapping_add_signed(offset as i16) as usize;
+                    self.extra_cycles += timing::taken_cycles(&self.config.kind, &instruction);
+                }
+            }
+            I8080InstructionSet::Call { condition, address } => {
+                if self.condition(condition) {
+                    self.call(program_pointer, memory_translation_table, address);
+                    self.extra_cycles += timing::taken_cycles(&self.config.kind, &instruction);
+                }
+            }
+            I8080InstructionSet::Return { condition } => {
+                if self.condition(condition) {
+                    *program_pointer = self.pop(memory_translation_table) as usize;
+                    self.extra_cycles += timing::taken_cycles(&self.config.kind, &instruction);
+                }
+            }
+            I8080InstructionSet::ReturnFromInterrupt => {
+                *program_pointer = self.pop(memory_translation_table) as usize;
+
+                self.interrupt_enable = match self.config.kind {
+                    I8080Kind::Lr35902 => true,
+                    _ => self.interrupt_enable_backup,
+                };
+            }
+            I8080InstructionSet::Restart(address) => {
+                self.call(program_pointer, memory_translation_table, address as u16);
+            }
+            I8080InstructionSet::DisableInterrupts => {
+                self.interrupt_enable = false;
+                self.interrupt_enable_backup = false;
+            }
+            I8080InstructionSet::EnableInterrupts => {
+                self.interrupt_enable_pending = true;
+            }
+            I8080InstructionSet::SetInterruptMode(mode) => {
+                self.interrupt_mode = mode;
+            }
+            I8080InstructionSet::Input { destination, port } => {
+                let value = self.input(self.port(port));
+
+                // Only the Z80 IN r,(C) sets flags
+                if port == Port::C {
+                    let carry = self.flag(Flag::Carry);
+                    self.set_logic_flags(value, false);
+                    self.set_flag(Flag::Carry, carry);
+                }
+
+                if let Some(destination) = destination {
+                    self.set_register(destination, value);
+                }
+            }
+            I8080InstructionSet::Output { source, port } => {
+                let value = source.map_or(0, |source| self.register(source));
+                self.output(self.port(port), value);
+            }
+            I8080InstructionSet::Block {
+                operation,
+                increment,
+                repeat,
+            } => {
+                if self.block(
+                    program_pointer,
+                    memory_translation_table,
+                    operation,
+                    increment,
+                    repeat,
+                ) {
+                    self.extra_cycles += timing::taken_cycles(&self.config.kind, &instruction);
+                }
+            }
+        }
+
+        self.cycles_remaining +=
+            timing::base_cycles(&self.config.kind, &instruction) + self.extra_cycles;
+
+        self.bus_fault
+            .take()
+            .map_or(Ok(()), |error| Err(error.into()))
     }
 }
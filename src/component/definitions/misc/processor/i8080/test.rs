@@ -0,0 +1,744 @@
+use super::{
+    decode::decode_instruction,
+    instruction::{
+        AccumulatorIndirect, AluOperation, BlockOperation, Condition, I8080InstructionSet,
+        IndexRegister, Register, RegisterPair, RotateOperation, SingleByteArgument,
+    },
+    Flag, I8080Config, I8080Kind, I8080,
+};
+use crate::{
+    component::{
+        definitions::misc::plain_memory::{PlainMemory, PlainMemoryConfig},
+        memory::MemoryTranslationTable,
+        processor::ProcessorComponent,
+        schedulable::SchedulableComponent,
+        FromConfig,
+    },
+    rom::RomManager,
+};
+use num::rational::Ratio;
+use std::sync::{Arc, Mutex};
+
+fn i8080(config: I8080Config) -> (I8080, MemoryTranslationTable) {
+    let rom_manager = Arc::new(RomManager::default());
+    let mut memory_translation_table = MemoryTranslationTable::default();
+
+    let memory = PlainMemory::from_config(
+        rom_manager.clone(),
+        PlainMemoryConfig {
+            readable: true,
+            writable: true,
+            assigned_range: 0x0000..0x10000,
+            ..Default::default()
+        },
+    );
+    memory_translation_table.insert(0x0000..0x10000, Arc::new(Mutex::new(memory)));
+
+    let processor = I8080::from_config(rom_manager, config);
+
+    (processor, memory_translation_table)
+}
+
+/// Does what the processor task does for a single instruction, and waits it out so the processor is between
+/// instructions again. Returns how many T-states that took, including taking an interrupt first
+fn step(
+    processor: &mut I8080,
+    memory_translation_table: &MemoryTranslationTable,
+    program_pointer: &mut usize,
+) -> u32 {
+    processor.service_interrupts(program_pointer, memory_translation_table);
+    let mut cycles = wait(processor, memory_translation_table);
+
+    if !processor.should_execution_occur() {
+        return cycles;
+    }
+
+    let (instruction, length) = processor
+        .decompile(*program_pointer, memory_translation_table)
+        .unwrap();
+    *program_pointer = program_pointer.wrapping_add(length as usize);
+    processor
+        .interpret(program_pointer, instruction, memory_translation_table)
+        .unwrap();
+    cycles += wait(processor, memory_translation_table);
+
+    cycles
+}
+
+/// Ticks until the current instruction is done, returning how long that took
+fn wait(processor: &mut I8080, memory_translation_table: &MemoryTranslationTable) -> u32 {
+    let mut cycles = 0;
+
+    while processor.cycles_remaining != 0 {
+        processor.tick(memory_translation_table);
+        cycles += 1;
+    }
+
+    cycles
+}
+
+fn execute(
+    processor: &mut I8080,
+    memory_translation_table: &MemoryTranslationTable,
+    program_pointer: &mut usize,
+    instruction: I8080InstructionSet,
+) {
+    processor
+        .interpret(program_pointer, instruction, memory_translation_table)
+        .unwrap();
+}
+
+fn read(memory_translation_table: &MemoryTranslationTable, address: usize) -> u8 {
+    let mut value = 0;
+    memory_translation_table
+        .read(address, std::array::from_mut(&mut value))
+        .unwrap();
+    value
+}
+
+fn write(memory_translation_table: &MemoryTranslationTable, address: usize, bytes: &[u8]) {
+    for (offset, value) in bytes.iter().enumerate() {
+        memory_translation_table
+            .write(address + offset, std::array::from_ref(value))
+            .unwrap();
+    }
+}
+
+fn decode(kind: I8080Kind, bytes: &[u8]) -> (I8080InstructionSet, u8) {
+    let (_, memory_translation_table) = i8080(I8080Config::z80(Ratio::new(1, 1)));
+    write(&memory_translation_table, 0, bytes);
+
+    decode_instruction(&kind, 0, &memory_translation_table).unwrap()
+}
+
+#[test]
+fn i8080_instruction_decode() {
+    // The same opcodes mean different things on each kind
+    assert_eq!(
+        decode(I8080Kind::I8080, &[0x08]),
+        (I8080InstructionSet::Nop, 1)
+    );
+    assert_eq!(
+        decode(I8080Kind::Z80, &[0x08]),
+        (I8080InstructionSet::ExchangeAf, 1)
+    );
+    assert_eq!(
+        decode(I8080Kind::Lr35902, &[0x08, 0x34, 0x12]),
+        (
+            I8080InstructionSet::StorePairIndirect {
+                source: RegisterPair::Sp,
+                address: 0x1234
+            },
+            3
+        )
+    );
+    assert_eq!(
+        decode(I8080Kind::I8080, &[0xcb, 0x34, 0x12]),
+        (
+            I8080InstructionSet::Jump {
+                condition: None,
+                address: 0x1234
+            },
+            3
+        )
+    );
+    assert_eq!(
+        decode(I8080Kind::Lr35902, &[0x22]),
+        (
+            I8080InstructionSet::StoreAccumulator {
+                destination: AccumulatorIndirect::HlIncrement
+            },
+            1
+        )
+    );
+    assert_eq!(
+        decode(I8080Kind::Lr35902, &[0xf0, 0x44]),
+        (
+            I8080InstructionSet::LoadAccumulator {
+                source: AccumulatorIndirect::HighPage(0x44)
+            },
+            2
+        )
+    );
+    assert_eq!(
+        decode(I8080Kind::Lr35902, &[0xd3]),
+        (I8080InstructionSet::Lock, 1)
+    );
+    assert_eq!(
+        decode(I8080Kind::Lr35902, &[0xcb, 0x37]),
+        (
+            I8080InstructionSet::Rotate {
+                operation: RotateOperation::Swap,
+                argument: SingleByteArgument::Register(Register::A)
+            },
+            2
+        )
+    );
+    assert_eq!(
+        decode(I8080Kind::Z80, &[0x20, 0xfe]),
+        (
+            I8080InstructionSet::JumpRelative {
+                condition: Some(Condition::NotZero),
+                offset: -2
+            },
+            2
+        )
+    );
+    assert_eq!(
+        decode(I8080Kind::Z80, &[0xed, 0xb0]),
+        (
+            I8080InstructionSet::Block {
+                operation: BlockOperation::Load,
+                increment: true,
+                repeat: true
+            },
+            2
+        )
+    );
+    assert_eq!(
+        decode(I8080Kind::I8080, &[0xfe, 0x10]),
+        (
+            I8080InstructionSet::Alu {
+                operation: AluOperation::Compare,
+                argument: SingleByteArgument::Immediate(0x10)
+            },
+            2
+        )
+    );
+}
+
+#[test]
+fn z80_index_decode() {
+    // The displacement comes before the immediate
+    assert_eq!(
+        decode(I8080Kind::Z80, &[0xdd, 0x36, 0x05, 0x0a]),
+        (
+            I8080InstructionSet::Load {
+                destination: SingleByteArgument::IndexIndirect {
+                    register: IndexRegister::Ix,
+                    displacement: 5
+                },
+                source: SingleByteArgument::Immediate(0x0a)
+            },
+            4
+        )
+    );
+    // H stays H when (IX+d) is the other side
+    assert_eq!(
+        decode(I8080Kind::Z80, &[0xfd, 0x66, 0xfe]),
+        (
+            I8080InstructionSet::Load {
+                destination: SingleByteArgument::Register(Register::H),
+                source: SingleByteArgument::IndexIndirect {
+                    register: IndexRegister::Iy,
+                    displacement: -2
+                }
+            },
+            3
+        )
+    );
+    assert_eq!(
+        decode(I8080Kind::Z80, &[0xdd, 0x7c]),
+        (
+            I8080InstructionSet::Load {
+                destination: SingleByteArgument::Register(Register::A),
+                source: SingleByteArgument::IndexHigh(IndexRegister::Ix)
+            },
+            2
+        )
+    );
+    assert_eq!(
+        decode(I8080Kind::Z80, &[0xdd, 0xcb, 0x05, 0xc6]),
+        (
+            I8080InstructionSet::SetBit {
+                bit: 0,
+                argument: SingleByteArgument::IndexIndirect {
+                    register: IndexRegister::Ix,
+                    displacement: 5
+                }
+            },
+            4
+        )
+    );
+    assert_eq!(
+        decode(I8080Kind::Z80, &[0xfd, 0x21, 0x34, 0x12]),
+        (
+            I8080InstructionSet::LoadPair {
+                destination: RegisterPair::Iy,
+                value: 0x1234
+            },
+            4
+        )
+    );
+    // EX DE,HL ignores the prefix
+    assert_eq!(
+        decode(I8080Kind::Z80, &[0xdd, 0xeb]),
+        (I8080InstructionSet::ExchangeDeHl, 2)
+    );
+    assert_eq!(
+        decode(I8080Kind::Z80, &[0xdd, 0xfd, 0x21]),
+        (I8080InstructionSet::Nop, 1)
+    );
+}
+
+#[test]
+fn i8080_add_flags() {
+    let add = I8080InstructionSet::Alu {
+        operation: AluOperation::Add,
+        argument: SingleByteArgument::Immediate(0x01),
+    };
+
+    // The Z80 reports overflow
+    let (mut processor, memory_translation_table) = i8080(I8080Config::z80(Ratio::new(1, 1)));
+    let mut program_pointer = 0;
+    processor.registers.accumulator = 0x7f;
+    execute(
+        &mut processor,
+        &memory_translation_table,
+        &mut program_pointer,
+        add,
+    );
+    assert_eq!(processor.registers.accumulator, 0x80);
+    assert!(processor.flag(Flag::Sign));
+    assert!(processor.flag(Flag::HalfCarry));
+    assert!(processor.flag(Flag::ParityOverflow));
+    assert!(!processor.flag(Flag::Subtract));
+    assert!(!processor.flag(Flag::Carry));
+
+    // The 8080 reports parity, 0x80 has odd parity
+    let (mut processor, memory_translation_table) = i8080(I8080Config::i8080(Ratio::new(1, 1)));
+    processor.registers.accumulator = 0x7f;
+    execute(
+        &mut processor,
+        &memory_translation_table,
+        &mut program_pointer,
+        add,
+    );
+    assert!(!processor.flag(Flag::ParityOverflow));
+    assert_eq!(processor.registers.flags, 0b1001_0010);
+
+    // The LR35902 has no sign or parity at all
+    let (mut processor, memory_translation_table) = i8080(I8080Config::lr35902(Ratio::new(1, 1)));
+    processor.registers.accumulator = 0xff;
+    execute(
+        &mut processor,
+        &memory_translation_table,
+        &mut program_pointer,
+        add,
+    );
+    assert_eq!(processor.registers.accumulator, 0x00);
+    assert_eq!(processor.registers.flags, 0b1011_0000);
+}
+
+#[test]
+fn i8080_subtract_and_decimal_adjust() {
+    let (mut processor, memory_translation_table) = i8080(I8080Config::z80(Ratio::new(1, 1)));
+    let mut program_pointer = 0;
+
+    // 0x15 - 0x27 in BCD is 88 with a borrow
+    processor.registers.accumulator = 0x15;
+    execute(
+        &mut processor,
+        &memory_translation_table,
+        &mut program_pointer,
+        I8080InstructionSet::Alu {
+            operation: AluOperation::Subtract,
+            argument: SingleByteArgument::Immediate(0x27),
+        },
+    );
+    assert_eq!(processor.registers.accumulator, 0xee);
+    assert!(processor.flag(Flag::Subtract));
+    assert!(processor.flag(Flag::Carry));
+    assert!(processor.flag(Flag::HalfCarry));
+
+    execute(
+        &mut processor,
+        &memory_translation_table,
+        &mut program_pointer,
+        I8080InstructionSet::DecimalAdjust,
+    );
+    assert_eq!(processor.registers.accumulator, 0x88);
+    assert!(processor.flag(Flag::Carry));
+
+    // 0x19 + 0x28 in BCD is 47
+    let (mut processor, memory_translation_table) = i8080(I8080Config::lr35902(Ratio::new(1, 1)));
+    processor.registers.accumulator = 0x19;
+    execute(
+        &mut processor,
+        &memory_translation_table,
+        &mut program_pointer,
+        I8080InstructionSet::Alu {
+            operation: AluOperation::Add,
+            argument: SingleByteArgument::Immediate(0x28),
+        },
+    );
+    execute(
+        &mut processor,
+        &memory_translation_table,
+        &mut program_pointer,
+        I8080InstructionSet::DecimalAdjust,
+    );
+    assert_eq!(processor.registers.accumulator, 0x47);
+    assert!(!processor.flag(Flag::Carry));
+    assert!(!processor.flag(Flag::HalfCarry));
+}
+
+#[test]
+fn i8080_pop_af_keeps_fixed_flags() {
+    for (config, expected) in [
+        (I8080Config::i8080(Ratio::new(1, 1)), 0xd7),
+        (I8080Config::z80(Ratio::new(1, 1)), 0xff),
+        (I8080Config::lr35902(Ratio::new(1, 1)), 0xf0),
+    ] {
+        let (mut processor, memory_translation_table) = i8080(config);
+        let mut program_pointer = 0;
+
+        processor.registers.stack_pointer = 0x8000;
+        write(&memory_translation_table, 0x8000, &[0xff, 0x12]);
+        execute(
+            &mut processor,
+            &memory_translation_table,
+            &mut program_pointer,
+            I8080InstructionSet::Pop(RegisterPair::Af),
+        );
+        assert_eq!(processor.registers.accumulator, 0x12);
+        assert_eq!(processor.registers.flags, expected);
+    }
+}
+
+#[test]
+fn i8080_call_and_return() {
+    let (mut processor, memory_translation_table) = i8080(I8080Config::lr35902(Ratio::new(1, 1)));
+    let mut program_pointer = 0;
+
+    // LD SP,0xfffe; CALL 0x0100; ... 0x0100: RET
+    write(
+        &memory_translation_table,
+        0x0000,
+        &[0x31, 0xfe, 0xff, 0xcd, 0x00, 0x01],
+    );
+    write(&memory_translation_table, 0x0100, &[0xc9]);
+
+    step(
+        &mut processor,
+        &memory_translation_table,
+        &mut program_pointer,
+    );
+    step(
+        &mut processor,
+        &memory_translation_table,
+        &mut program_pointer,
+    );
+    assert_eq!(program_pointer, 0x0100);
+    assert_eq!(processor.registers.stack_pointer, 0xfffc);
+    assert_eq!(read(&memory_translation_table, 0xfffc), 0x06);
+    assert_eq!(read(&memory_translation_table, 0xfffd), 0x00);
+
+    step(
+        &mut processor,
+        &memory_translation_table,
+        &mut program_pointer,
+    );
+    assert_eq!(program_pointer, 0x0006);
+    assert_eq!(processor.registers.stack_pointer, 0xfffe);
+}
+
+#[test]
+fn z80_block_copy() {
+    let (mut processor, memory_translation_table) = i8080(I8080Config::z80(Ratio::new(1, 1)));
+    let mut program_pointer = 0;
+
+    // LD HL,0x1000; LD DE,0x2000; LD BC,3; LDIR
+    write(
+        &memory_translation_table,
+        0x0000,
+        &[
+            0x21, 0x00, 0x10, 0x11, 0x00, 0x20, 0x01, 0x03, 0x00, 0xed, 0xb0,
+        ],
+    );
+    write(&memory_translation_table, 0x1000, &[1, 2, 3]);
+
+    for _ in 0..6 {
+        step(
+            &mut processor,
+            &memory_translation_table,
+            &mut program_pointer,
+        );
+    }
+
+    assert_eq!(program_pointer, 0x000b);
+    assert_eq!(processor.register_pair(RegisterPair::Bc), 0);
+    assert_eq!(processor.register_pair(RegisterPair::Hl), 0x1003);
+    assert_eq!(processor.register_pair(RegisterPair::De), 0x2003);
+    assert_eq!(
+        [0x2000, 0x2001, 0x2002].map(|address| read(&memory_translation_table, address)),
+        [1, 2, 3]
+    );
+    assert!(!processor.flag(Flag::ParityOverflow));
+}
+
+#[test]
+fn z80_interrupts() {
+    let (mut processor, memory_translation_table) = i8080(I8080Config::z80(Ratio::new(1, 1)));
+    let mut program_pointer = 0;
+
+    // LD SP,0x8000; EI; NOP; HALT
+    write(
+        &memory_translation_table,
+        0x0000,
+        &[0x31, 0x00, 0x80, 0xfb, 0x00, 0x76],
+    );
+    step(
+        &mut processor,
+        &memory_translation_table,
+        &mut program_pointer,
+    );
+    step(
+        &mut processor,
+        &memory_translation_table,
+        &mut program_pointer,
+    );
+
+    // EI holds interrupts off for one more instruction
+    processor.assert_irq();
+    step(
+        &mut processor,
+        &memory_translation_table,
+        &mut program_pointer,
+    );
+    assert_eq!(program_pointer, 0x0005);
+
+    step(
+        &mut processor,
+        &memory_translation_table,
+        &mut program_pointer,
+    );
+    // The handler already ran its first instruction
+    assert_eq!(program_pointer, 0x0039);
+    assert_eq!(read(&memory_translation_table, 0x7ffe), 0x05);
+    processor.deassert_irq();
+
+    // Interrupt mode 2 goes through the table at I
+    let (mut processor, memory_translation_table) = i8080(I8080Config::z80(Ratio::new(1, 1)));
+    let mut program_pointer = 0;
+
+    // LD SP,0x8000; LD A,0x12; LD I,A; IM 2; EI; HALT
+    write(
+        &memory_translation_table,
+        0x0000,
+        &[
+            0x31, 0x00, 0x80, 0x3e, 0x12, 0xed, 0x47, 0xed, 0x5e, 0xfb, 0x76,
+        ],
+    );
+    write(&memory_translation_table, 0x12ff, &[0x00, 0x40]);
+    for _ in 0..6 {
+        step(
+            &mut processor,
+            &memory_translation_table,
+            &mut program_pointer,
+        );
+    }
+    assert!(processor.halted);
+
+    processor.assert_irq();
+    step(
+        &mut processor,
+        &memory_translation_table,
+        &mut program_pointer,
+    );
+    assert!(!processor.halted);
+    assert_eq!(program_pointer, 0x4001);
+    processor.deassert_irq();
+
+    // NMI ignores the interrupt enable
+    processor.assert_nmi();
+    step(
+        &mut processor,
+        &memory_translation_table,
+        &mut program_pointer,
+    );
+    assert_eq!(read(&memory_translation_table, 0x7ffc), 0x01);
+    assert_eq!(read(&memory_translation_table, 0x7ffd), 0x40);
+    assert_eq!(program_pointer, 0x0067);
+}
+
+#[test]
+fn lr35902_interrupts() {
+    let (mut processor, memory_translation_table) = i8080(I8080Config::lr35902(Ratio::new(1, 1)));
+    let mut program_pointer = 0;
+
+    // LD SP,0xd000; HALT
+    write(&memory_translation_table, 0x0000, &[0x31, 0x00, 0xd0, 0x76]);
+    step(
+        &mut processor,
+        &memory_translation_table,
+        &mut program_pointer,
+    );
+    step(
+        &mut processor,
+        &memory_translation_table,
+        &mut program_pointer,
+    );
+    assert!(processor.halted);
+
+    // With IME off a pending interrupt only wakes the processor
+    write(&memory_translation_table, 0xffff, &[0b0000_0101]);
+    write(&memory_translation_table, 0xff0f, &[0b0000_0100]);
+    step(
+        &mut processor,
+        &memory_translation_table,
+        &mut program_pointer,
+    );
+    assert!(!processor.halted);
+    assert_eq!(program_pointer, 0x0005);
+
+    // VBlank wins over the timer
+    processor.interrupt_enable = true;
+    write(&memory_translation_table, 0xff0f, &[0b0000_0101]);
+    processor.service_interrupts(&mut program_pointer, &memory_translation_table);
+    assert_eq!(program_pointer, 0x0040);
+    assert_eq!(read(&memory_translation_table, 0xff0f), 0b0000_0100);
+    assert!(!processor.interrupt_enable);
+
+    // RETI turns IME straight back on
+    execute(
+        &mut processor,
+        &memory_translation_table,
+        &mut program_pointer,
+        I8080InstructionSet::ReturnFromInterrupt,
+    );
+    assert_eq!(program_pointer, 0x0005);
+    assert!(processor.interrupt_enable);
+}
+
+#[test]
+fn i8080_cycle_timing() {
+    for (config, program, subroutine, expected) in [
+        (
+            I8080Config::i8080(Ratio::new(1, 1)),
+            // LXI SP,0x8000; LXI H,0x4000; MOV B,C; MVI M,0x12; XRA A; CNZ 0x1000; CZ 0x1000
+            [
+                0x31, 0x00, 0x80, 0x21, 0x00, 0x40, 0x41, 0x36, 0x12, 0xaf, 0xc4, 0x00, 0x10, 0xcc,
+                0x00, 0x10,
+            ]
+            .as_slice(),
+            // RNZ; RZ
+            [0xc0, 0xc8].as_slice(),
+            // Conditional calls and returns pay for the stack access only when taken
+            [10, 10, 5, 10, 4, 11, 17, 5, 11].as_slice(),
+        ),
+        (
+            I8080Config::z80(Ratio::new(1, 1)),
+            // LD SP,0x8000; LD B,C; LD IX,0x4000; LD A,(IX+1); XOR A; JR NZ,0; JR Z,0; CALL 0x1000
+            [
+                0x31, 0x00, 0x80, 0x41, 0xdd, 0x21, 0x00, 0x40, 0xdd, 0x7e, 0x01, 0xaf, 0x20, 0x00,
+                0x28, 0x00, 0xcd, 0x00, 0x10,
+            ]
+            .as_slice(),
+            // RET NZ; RET Z
+            [0xc0, 0xc8].as_slice(),
+            [10, 4, 14, 19, 4, 7, 12, 17, 5, 11].as_slice(),
+        ),
+        (
+            I8080Config::lr35902(Ratio::new(1, 1)),
+            // LD SP,0xd000; LD HL,0x4000; LD (HL),0x12; XOR A; JR Z,0; JR NZ,0; CALL 0x1000
+            [
+                0x31, 0x00, 0xd0, 0x21, 0x00, 0x40, 0x36, 0x12, 0xaf, 0x28, 0x00, 0x20, 0x00, 0xcd,
+                0x00, 0x10,
+            ]
+            .as_slice(),
+            // RET NZ; RET Z
+            [0xc0, 0xc8].as_slice(),
+            [12, 12, 12, 4, 12, 8, 24, 8, 20].as_slice(),
+        ),
+    ] {
+        let kind = config.kind.clone();
+        let (mut processor, memory_translation_table) = i8080(config);
+        let mut program_pointer = 0;
+        write(&memory_translation_table, 0x0000, program);
+        write(&memory_translation_table, 0x1000, subroutine);
+
+        let cycles: Vec<_> = expected
+            .iter()
+            .map(|_| {
+                step(
+                    &mut processor,
+                    &memory_translation_table,
+                    &mut program_pointer,
+                )
+            })
+            .collect();
+        assert_eq!(cycles, expected, "{:?}", kind);
+    }
+}
+
+#[test]
+fn z80_repeating_cycle_timing() {
+    let (mut processor, memory_translation_table) = i8080(I8080Config::z80(Ratio::new(1, 1)));
+    let mut program_pointer = 0;
+
+    // LD B,2; DJNZ -2; LD BC,2; LDIR
+    write(
+        &memory_translation_table,
+        0x0000,
+        &[0x06, 0x02, 0x10, 0xfe, 0x01, 0x02, 0x00, 0xed, 0xb0],
+    );
+
+    let cycles: Vec<_> = (0..6)
+        .map(|_| {
+            step(
+                &mut processor,
+                &memory_translation_table,
+                &mut program_pointer,
+            )
+        })
+        .collect();
+    // Going around again costs more than falling through
+    assert_eq!(cycles, [7, 13, 8, 10, 21, 16]);
+    assert_eq!(program_pointer, 0x0009);
+}
+
+#[test]
+fn i8080_waits_out_instructions() {
+    let (mut processor, memory_translation_table) = i8080(I8080Config::z80(Ratio::new(1, 1)));
+    let mut program_pointer = 0;
+
+    // LD SP,0x8000; EI; NOP
+    write(
+        &memory_translation_table,
+        0x0000,
+        &[0x31, 0x00, 0x80, 0xfb, 0x00],
+    );
+    for _ in 0..3 {
+        step(
+            &mut processor,
+            &memory_translation_table,
+            &mut program_pointer,
+        );
+    }
+
+    // Taking the interrupt costs as much as its RST, then the NOP at the vector runs
+    processor.assert_irq();
+    assert_eq!(
+        step(
+            &mut processor,
+            &memory_translation_table,
+            &mut program_pointer,
+        ),
+        13 + 4
+    );
+    processor.deassert_irq();
+
+    // Nothing else runs until the instruction is done
+    let (instruction, _) = processor
+        .decompile(program_pointer, &memory_translation_table)
+        .unwrap();
+    processor
+        .interpret(&mut program_pointer, instruction, &memory_translation_table)
+        .unwrap();
+    for _ in 0..4 {
+        assert!(!processor.should_execution_occur());
+        processor.tick(&memory_translation_table);
+    }
+    assert!(processor.should_execution_occur());
+}
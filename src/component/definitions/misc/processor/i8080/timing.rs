@@ -0,0 +1,268 @@
+use super::{
+    instruction::{
+        AccumulatorIndirect, I8080InstructionSet, Port, RegisterPair, SingleByteArgument,
+    },
+    I8080Kind,
+};
+
+// https://pastraiser.com/cpu/i8080/i8080_opcodes.html
+// https://clrhome.org/table/
+// https://gbdev.io/gb-opcodes/optables/
+
+/// Accepting a interrupt on the 8080 runs the RST it was handed
+pub const I8080_INTERRUPT_CYCLES: u32 = 11;
+pub const Z80_NMI_CYCLES: u32 = 11;
+/// Interrupt modes 0 and 1, which both end up running RST 7 here
+pub const Z80_INTERRUPT_CYCLES: u32 = 13;
+/// Interrupt mode 2, which reads the address out of the vector table first
+pub const Z80_VECTORED_INTERRUPT_CYCLES: u32 = 19;
+pub const LR35902_INTERRUPT_CYCLES: u32 = 20;
+
+/// Where a 8 bit operand comes from, which decides most of what it costs
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Operand {
+    Register,
+    HlIndirect,
+    /// Z80 only, the displacement has to be fetched and added first
+    IndexIndirect,
+    /// Z80 only, a register with a prefix in front
+    IndexHalf,
+    Immediate,
+}
+
+impl From<SingleByteArgument> for Operand {
+    fn from(argument: SingleByteArgument) -> Self {
+        match argument {
+            SingleByteArgument::Register(_) => Operand::Register,
+            SingleByteArgument::HlIndirect => Operand::HlIndirect,
+            SingleByteArgument::IndexIndirect { .. } => Operand::IndexIndirect,
+            SingleByteArgument::IndexHigh(_) | SingleByteArgument::IndexLow(_) => {
+                Operand::IndexHalf
+            }
+            SingleByteArgument::Immediate(_) => Operand::Immediate,
+        }
+    }
+}
+
+fn is_index_pair(pair: RegisterPair) -> bool {
+    matches!(pair, RegisterPair::Ix | RegisterPair::Iy)
+}
+
+/// Instructions that read a 8 bit operand and do something with it
+fn read_operand_cycles(argument: SingleByteArgument) -> [u32; 3] {
+    match Operand::from(argument) {
+        Operand::Register => [4, 4, 4],
+        Operand::HlIndirect | Operand::Immediate => [7, 7, 8],
+        Operand::IndexIndirect => [19, 19, 19],
+        Operand::IndexHalf => [8, 8, 8],
+    }
+}
+
+/// Instructions that read a 8 bit operand and write it back changed
+fn read_modify_write_operand_cycles(argument: SingleByteArgument) -> [u32; 3] {
+    match Operand::from(argument) {
+        Operand::Register | Operand::Immediate => [5, 4, 4],
+        Operand::HlIndirect => [10, 11, 12],
+        Operand::IndexIndirect => [23, 23, 23],
+        Operand::IndexHalf => [8, 8, 8],
+    }
+}
+
+/// The prefixed bit instructions, which the 8080 doesn't have
+fn bit_operand_cycles(argument: SingleByteArgument, only_reads: bool) -> [u32; 3] {
+    match (Operand::from(argument), only_reads) {
+        (Operand::HlIndirect, true) => [12, 12, 12],
+        (Operand::HlIndirect, false) => [15, 15, 16],
+        (Operand::IndexIndirect, true) => [20, 20, 20],
+        (Operand::IndexIndirect, false) => [23, 23, 23],
+        _ => [8, 8, 8],
+    }
+}
+
+/// T-states an instruction takes if it doesn't branch, or for block instructions if it doesn't go around again
+///
+/// The LR35902 is counted in T-states too, so everything it does is a multiple of 4
+pub fn base_cycles(kind: &I8080Kind, instruction: &I8080InstructionSet) -> u32 {
+    // Columns are the 8080, Z80, and LR35902. Things only one of them has repeat the same number
+    let [i8080, z80, lr35902] = match *instruction {
+        I8080InstructionSet::Nop
+        | I8080InstructionSet::Stop
+        | I8080InstructionSet::Lock
+        | I8080InstructionSet::ExchangeDeHl
+        | I8080InstructionSet::ExchangeAf
+        | I8080InstructionSet::ExchangeShadow
+        | I8080InstructionSet::RotateAccumulator(_)
+        | I8080InstructionSet::DecimalAdjust
+        | I8080InstructionSet::Complement
+        | I8080InstructionSet::SetCarry
+        | I8080InstructionSet::ComplementCarry
+        | I8080InstructionSet::DisableInterrupts
+        | I8080InstructionSet::EnableInterrupts => [4, 4, 4],
+        I8080InstructionSet::Halt => [7, 4, 4],
+        I8080InstructionSet::Load {
+            destination,
+            source,
+        } => match (Operand::from(destination), Operand::from(source)) {
+            (Operand::IndexIndirect, _) | (_, Operand::IndexIndirect) => [19, 19, 19],
+            (Operand::IndexHalf, Operand::Immediate) => [11, 11, 11],
+            (Operand::IndexHalf, _) | (_, Operand::IndexHalf) => [8, 8, 8],
+            (Operand::HlIndirect, Operand::Immediate) => [10, 10, 12],
+            (Operand::HlIndirect, _) | (_, Operand::HlIndirect) | (_, Operand::Immediate) => {
+                [7, 7, 8]
+            }
+            _ => [5, 4, 4],
+        },
+        I8080InstructionSet::LoadPair { destination, .. } => {
+            if is_index_pair(destination) {
+                [14, 14, 14]
+            } else {
+                [10, 10, 12]
+            }
+        }
+        I8080InstructionSet::LoadAccumulator { source: indirect }
+        | I8080InstructionSet::StoreAccumulator {
+            destination: indirect,
+        } => match indirect {
+            AccumulatorIndirect::Absolute(_) => [13, 13, 16],
+            AccumulatorIndirect::HighPage(_) => [12, 12, 12],
+            AccumulatorIndirect::Pair(_)
+            | AccumulatorIndirect::HighPageC
+            | AccumulatorIndirect::HlIncrement
+            | AccumulatorIndirect::HlDecrement => [7, 7, 8],
+        },
+        // The Z80 only has a short form for HL, the rest of the pairs take a prefix
+        I8080InstructionSet::LoadPairIndirect {
+            destination: pair, ..
+        }
+        | I8080InstructionSet::StorePairIndirect { source: pair, .. } => {
+            if pair == RegisterPair::Hl {
+                [16, 16, 20]
+            } else {
+                [20, 20, 20]
+            }
+        }
+        I8080InstructionSet::LoadStackPointer { source } => {
+            if is_index_pair(source) {
+                [10, 10, 10]
+            } else {
+                [5, 6, 8]
+            }
+        }
+        I8080InstructionSet::LoadFromSpecial(_) | I8080InstructionSet::LoadToSpecial(_) => {
+            [9, 9, 9]
+        }
+        I8080InstructionSet::LoadStackOffset(_) => [12, 12, 12],
+        I8080InstructionSet::AddStackPointer(_) => [16, 16, 16],
+        I8080InstructionSet::Push(pair) => {
+            if is_index_pair(pair) {
+                [15, 15, 15]
+            } else {
+                [11, 11, 16]
+            }
+        }
+        I8080InstructionSet::Pop(pair) => {
+            if is_index_pair(pair) {
+                [14, 14, 14]
+            } else {
+                [10, 10, 12]
+            }
+        }
+        I8080InstructionSet::ExchangeStack(pair) => {
+            if is_index_pair(pair) {
+                [23, 23, 23]
+            } else {
+                [18, 19, 19]
+            }
+        }
+        I8080InstructionSet::Alu { argument, .. } => read_operand_cycles(argument),
+        I8080InstructionSet::Increment(argument) | I8080InstructionSet::Decrement(argument) => {
+            read_modify_write_operand_cycles(argument)
+        }
+        I8080InstructionSet::IncrementPair(pair) | I8080InstructionSet::DecrementPair(pair) => {
+            if is_index_pair(pair) {
+                [10, 10, 10]
+            } else {
+                [5, 6, 8]
+            }
+        }
+        I8080InstructionSet::AddPair { destination, .. } => {
+            if is_index_pair(destination) {
+                [15, 15, 15]
+            } else {
+                [10, 11, 8]
+            }
+        }
+        I8080InstructionSet::AddPairWithCarry(_)
+        | I8080InstructionSet::SubtractPairWithBorrow(_) => [15, 15, 15],
+        I8080InstructionSet::Rotate { argument, .. }
+        | I8080InstructionSet::ResetBit { argument, .. }
+        | I8080InstructionSet::SetBit { argument, .. } => bit_operand_cycles(argument, false),
+        I8080InstructionSet::TestBit { argument, .. } => bit_operand_cycles(argument, true),
+        I8080InstructionSet::RotateDigit { .. } => [18, 18, 18],
+        I8080InstructionSet::Negate | I8080InstructionSet::SetInterruptMode(_) => [8, 8, 8],
+        // The 8080 and Z80 fetch the address whether or not they jump
+        I8080InstructionSet::Jump {
+            condition: None, ..
+        } => [10, 10, 16],
+        I8080InstructionSet::Jump { .. } => [10, 10, 12],
+        I8080InstructionSet::JumpRelative {
+            condition: None, ..
+        } => [12, 12, 12],
+        I8080InstructionSet::JumpRelative { .. } => [7, 7, 8],
+        I8080InstructionSet::JumpIndirect(pair) => {
+            if is_index_pair(pair) {
+                [8, 8, 8]
+            } else {
+                [5, 4, 4]
+            }
+        }
+        I8080InstructionSet::DecrementJumpNotZero(_) => [8, 8, 8],
+        I8080InstructionSet::Call {
+            condition: None, ..
+        } => [17, 17, 24],
+        I8080InstructionSet::Call { .. } => [11, 10, 12],
+        I8080InstructionSet::Return { condition: None } => [10, 10, 16],
+        I8080InstructionSet::Return { .. } => [5, 5, 8],
+        I8080InstructionSet::ReturnFromInterrupt => [10, 14, 16],
+        I8080InstructionSet::Restart(_) => [11, 11, 16],
+        I8080InstructionSet::Input { port, .. } | I8080InstructionSet::Output { port, .. } => {
+            match port {
+                Port::Immediate(_) => [10, 11, 11],
+                Port::C => [12, 12, 12],
+            }
+        }
+        I8080InstructionSet::Block { .. } => [16, 16, 16],
+    };
+
+    match kind {
+        I8080Kind::I8080 => i8080,
+        I8080Kind::Z80 => z80,
+        I8080Kind::Lr35902 => lr35902,
+    }
+}
+
+/// T-states added on when a conditional instruction takes its branch, or a repeating block instruction goes around
+/// again
+pub fn taken_cycles(kind: &I8080Kind, instruction: &I8080InstructionSet) -> u32 {
+    let [i8080, z80, lr35902] = match *instruction {
+        I8080InstructionSet::Jump {
+            condition: Some(_), ..
+        } => [0, 0, 4],
+        I8080InstructionSet::JumpRelative {
+            condition: Some(_), ..
+        } => [5, 5, 4],
+        I8080InstructionSet::DecrementJumpNotZero(_) => [5, 5, 5],
+        I8080InstructionSet::Call {
+            condition: Some(_), ..
+        } => [6, 7, 12],
+        I8080InstructionSet::Return { condition: Some(_) } => [6, 6, 12],
+        I8080InstructionSet::Block { repeat: true, .. } => [5, 5, 5],
+        _ => [0, 0, 0],
+    };
+
+    match kind {
+        I8080Kind::I8080 => i8080,
+        I8080Kind::Z80 => z80,
+        I8080Kind::Lr35902 => lr35902,
+    }
+}
@@ -1,4 +1,4 @@
-use super::instruction::M6502InstructionSet;
+use super::instruction::{AddressingMode, M6502InstructionSet, M6502InstructionSetSpecifier};
 use crate::component::memory::MemoryTranslationTable;
 use bitvec::{
     field::BitField,
@@ -11,12 +11,14 @@ const INSTRUCTION_IDENTIFIER: Range<usize> = 6..8;
 const SECONDARY_INSTRUCTION_IDENTIFIER: Range<usize> = 0..3;
 const ARGUMENT: Range<usize> = 3..6;
 
+// https://www.masswerk.at/6502/6502_instruction_set.html#layout
+
+/// Every one of the 256 opcodes does something on the NMOS parts, so only reading the instruction can fail
 pub fn decode_instruction(
     cursor: usize,
     memory_translation_table: &MemoryTranslationTable,
 ) -> Result<(M6502InstructionSet, u8), Box<dyn std::error::Error>> {
-    let mut instruction_first_byte = 0;
-    memory_translation_table.read(cursor, std::slice::from_mut(&mut instruction_first_byte))?;
+    let instruction_first_byte = read_operand_byte(cursor, 0, memory_translation_table)?;
     let instruction_first_byte = instruction_first_byte.view_bits::<Msb0>();
     let instruction_identifier = instruction_first_byte[INSTRUCTION_IDENTIFIER].load::<u8>();
 
@@ -71,6 +73,7 @@ pub fn decode_instruction(
     }
 }
 
+/// The ALU instructions, which take every addressing mode of [AddressingMode::from_group1_addressing]
 #[inline]
 pub fn decode_group1_space_instruction(
     cursor: usize,
@@ -80,37 +83,35 @@ pub fn decode_group1_space_instruction(
 ) -> Result<(M6502InstructionSet, u8), Box<dyn std::error::Error>> {
     let addressing_mode = instruction_first_byte[ARGUMENT].load::<u8>();
 
-    match instruction_identifier {
-        0b000 => {
-            todo!()
-        }
-        0b001 => {
-            todo!()
-        }
-        0b010 => {
-            todo!()
-        }
-        0b011 => {
-            todo!()
-        }
-        0b100 => {
-            todo!()
-        }
-        0b101 => {
-            todo!()
-        }
-        0b110 => {
-            todo!()
-        }
-        0b111 => {
-            todo!()
-        }
+    let specifier = match instruction_identifier {
+        0b000 => M6502InstructionSetSpecifier::Ora,
+        0b001 => M6502InstructionSetSpecifier::And,
+        0b010 => M6502InstructionSetSpecifier::Eor,
+        0b011 => M6502InstructionSetSpecifier::Adc,
+        // Storing to a immediate makes no sense, so that slot reads its operand and does nothing
+        0b100 if addressing_mode == 0b010 => M6502InstructionSetSpecifier::Nop,
+        0b100 => M6502InstructionSetSpecifier::Sta,
+        0b101 => M6502InstructionSetSpecifier::Lda,
+        0b110 => M6502InstructionSetSpecifier::Cmp,
+        0b111 => M6502InstructionSetSpecifier::Sbc,
         _ => {
             unreachable!()
         }
-    }
+    };
+
+    let (addressing_mode, size) =
+        AddressingMode::from_group1_addressing(addressing_mode, memory_translation_table, cursor)?;
+
+    Ok((
+        M6502InstructionSet {
+            specifier,
+            addressing_mode: Some(addressing_mode),
+        },
+        size,
+    ))
 }
 
+/// The shifts, increments and X register instructions
 #[inline]
 pub fn decode_group2_space_instruction(
     cursor: usize,
@@ -118,9 +119,111 @@ pub fn decode_group2_space_instruction(
     instruction_identifier: u8,
     instruction_first_byte: &BitSlice<u8, Msb0>,
 ) -> Result<(M6502InstructionSet, u8), Box<dyn std::error::Error>> {
-    todo!()
+    const SPECIFIERS: [M6502InstructionSetSpecifier; 8] = [
+        M6502InstructionSetSpecifier::Asl,
+        M6502InstructionSetSpecifier::Rol,
+        M6502InstructionSetSpecifier::Lsr,
+        M6502InstructionSetSpecifier::Ror,
+        M6502InstructionSetSpecifier::Stx,
+        M6502InstructionSetSpecifier::Ldx,
+        M6502InstructionSetSpecifier::Dec,
+        M6502InstructionSetSpecifier::Inc,
+    ];
+    const TRANSFERS: [M6502InstructionSetSpecifier; 4] = [
+        M6502InstructionSetSpecifier::Txa,
+        M6502InstructionSetSpecifier::Tax,
+        M6502InstructionSetSpecifier::Dex,
+        M6502InstructionSetSpecifier::Nop,
+    ];
+
+    let addressing_mode = instruction_first_byte[ARGUMENT].load::<u8>();
+    // STX and LDX index with Y where the rest index with X
+    let indexes_with_y = matches!(instruction_identifier, 0b100 | 0b101);
+
+    let specifier = SPECIFIERS[instruction_identifier as usize];
+
+    match addressing_mode {
+        0b000 => match instruction_identifier {
+            0b000..=0b011 => Ok(implied(M6502InstructionSetSpecifier::Jam)),
+            0b101 => with_byte_operand(
+                specifier,
+                AddressingMode::Immediate,
+                cursor,
+                memory_translation_table,
+            ),
+            _ => with_byte_operand(
+                M6502InstructionSetSpecifier::Nop,
+                AddressingMode::Immediate,
+                cursor,
+                memory_translation_table,
+            ),
+        },
+        0b001 => with_byte_operand(
+            specifier,
+            AddressingMode::ZeroPage,
+            cursor,
+            memory_translation_table,
+        ),
+        0b010 => match instruction_identifier {
+            0b000..=0b011 => Ok((
+                M6502InstructionSet {
+                    specifier,
+                    addressing_mode: Some(AddressingMode::Accumulator),
+                },
+                1,
+            )),
+            _ => Ok(implied(TRANSFERS[instruction_identifier as usize - 0b100])),
+        },
+        0b011 => with_word_operand(
+            specifier,
+            AddressingMode::Absolute,
+            cursor,
+            memory_translation_table,
+        ),
+        0b100 => Ok(implied(M6502InstructionSetSpecifier::Jam)),
+        0b101 => with_byte_operand(
+            specifier,
+            if indexes_with_y {
+                AddressingMode::YIndexedZeroPage
+            } else {
+                AddressingMode::XIndexedZeroPage
+            },
+            cursor,
+            memory_translation_table,
+        ),
+        0b110 => Ok(implied(match instruction_identifier {
+            0b100 => M6502InstructionSetSpecifier::Txs,
+            0b101 => M6502InstructionSetSpecifier::Tsx,
+            _ => M6502InstructionSetSpecifier::Nop,
+        })),
+        0b111 => match instruction_identifier {
+            // STX has no absolute indexed form, this is where SHX ended up
+            0b100 => with_word_operand(
+                M6502InstructionSetSpecifier::Shx,
+                AddressingMode::YIndexedAbsolute,
+                cursor,
+                memory_translation_table,
+            ),
+            0b101 => with_word_operand(
+                specifier,
+                AddressingMode::YIndexedAbsolute,
+                cursor,
+                memory_translation_table,
+            ),
+            _ => with_word_operand(
+                specifier,
+                AddressingMode::XIndexedAbsolute,
+                cursor,
+                memory_translation_table,
+            ),
+        },
+        _ => {
+            unreachable!()
+        }
+    }
 }
 
+/// Combinations of the group 1 and 2 instructions that happen to do both at once, and some stranger things
 #[inline]
 pub fn decode_undocumented_space_instruction(
     cursor: usize,
@@ -128,58 +231,268 @@ pub fn decode_undocumented_space_instruction(
     instruction_identifier: u8,
     instruction_first_byte: &BitSlice<u8, Msb0>,
 ) -> Result<(M6502InstructionSet, u8), Box<dyn std::error::Error>> {
-    match instruction_identifier {
-        0b000 => {
-            todo!()
-        }
-        0b001 => {
-            todo!()
-        }
-        0b010 => {
-            todo!()
-        }
-        0b011 => {
-            todo!()
-        }
-        0b100 => {
-            todo!()
-        }
-        0b101 => {
-            todo!()
-        }
-        0b110 => {
-            todo!()
-        }
-        0b111 => {
-            todo!()
-        }
+    const SPECIFIERS: [M6502InstructionSetSpecifier; 8] = [
+        M6502InstructionSetSpecifier::Slo,
+        M6502InstructionSetSpecifier::Rla,
+        M6502InstructionSetSpecifier::Sre,
+        M6502InstructionSetSpecifier::Rra,
+        M6502InstructionSetSpecifier::Sax,
+        M6502InstructionSetSpecifier::Lax,
+        M6502InstructionSetSpecifier::Dcp,
+        M6502InstructionSetSpecifier::Isc,
+    ];
+    const IMMEDIATE_SPECIFIERS: [M6502InstructionSetSpecifier; 8] = [
+        M6502InstructionSetSpecifier::Anc,
+        M6502InstructionSetSpecifier::Anc,
+        M6502InstructionSetSpecifier::Asr,
+        M6502InstructionSetSpecifier::Arr,
+        M6502InstructionSetSpecifier::Xaa,
+        M6502InstructionSetSpecifier::Lax,
+        M6502InstructionSetSpecifier::Sbx,
+        M6502InstructionSetSpecifier::Sbc,
+    ];
+
+    let addressing_mode = instruction_first_byte[ARGUMENT].load::<u8>();
+
+    if addressing_mode == 0b010 {
+        return with_byte_operand(
+            IMMEDIATE_SPECIFIERS[instruction_identifier as usize],
+            AddressingMode::Immediate,
+            cursor,
+            memory_translation_table,
+        );
+    }
+
+    let specifier = match (instruction_identifier, addressing_mode) {
+        (0b100, 0b100) | (0b100, 0b111) => M6502InstructionSetSpecifier::Sha,
+        (0b100, 0b110) => M6502InstructionSetSpecifier::Shs,
+        (0b101, 0b110) => M6502InstructionSetSpecifier::Las,
+        _ => SPECIFIERS[instruction_identifier as usize],
+    };
+
+    match (instruction_identifier, addressing_mode) {
+        // SAX and LAX index with Y like STX and LDX do
+        (0b100 | 0b101, 0b101) => with_byte_operand(
+            specifier,
+            AddressingMode::YIndexedZeroPage,
+            cursor,
+            memory_translation_table,
+        ),
+        (0b100 | 0b101, 0b111) => with_word_operand(
+            specifier,
+            AddressingMode::YIndexedAbsolute,
+            cursor,
+            memory_translation_table,
+        ),
         _ => {
-            unreachable!()
+            let (addressing_mode, size) = AddressingMode::from_group1_addressing(
+                addressing_mode,
+                memory_translation_table,
+                cursor,
+            )?;
+
+            Ok((
+                M6502InstructionSet {
+                    specifier,
+                    addressing_mode: Some(addressing_mode),
+                },
+                size,
+            ))
         }
     }
 }
 
+/// Control flow, flag and Y register instructions
 fn decode_group3_instruction(
     cursor: usize,
     memory_translation_table: &MemoryTranslationTable,
     instruction_identifier: u8,
     instruction_first_byte: &BitSlice<u8, Msb0>,
 ) -> Result<(M6502InstructionSet, u8), Box<dyn std::error::Error>> {
-    match instruction_identifier {
-        0b000 => {
-            todo!()
-        }
-        0b001 => {
-            todo!()
-        }
-        0b010 => todo!(),
-        0b011 => todo!(),
-        0b100 => todo!(),
-        0b101 => todo!(),
-        0b110 => todo!(),
-        0b111 => todo!(),
+    const SPECIFIERS: [M6502InstructionSetSpecifier; 8] = [
+        M6502InstructionSetSpecifier::Nop,
+        M6502InstructionSetSpecifier::Bit,
+        M6502InstructionSetSpecifier::Jmp,
+        M6502InstructionSetSpecifier::Jmp,
+        M6502InstructionSetSpecifier::Sty,
+        M6502InstructionSetSpecifier::Ldy,
+        M6502InstructionSetSpecifier::Cpy,
+        M6502InstructionSetSpecifier::Cpx,
+    ];
+    const STACK_AND_REGISTER_SPECIFIERS: [M6502InstructionSetSpecifier; 8] = [
+        M6502InstructionSetSpecifier::Php,
+        M6502InstructionSetSpecifier::Plp,
+        M6502InstructionSetSpecifier::Pha,
+        M6502InstructionSetSpecifier::Pla,
+        M6502InstructionSetSpecifier::Dey,
+        M6502InstructionSetSpecifier::Tay,
+        M6502InstructionSetSpecifier::Iny,
+        M6502InstructionSetSpecifier::Inx,
+    ];
+    const BRANCH_SPECIFIERS: [M6502InstructionSetSpecifier; 8] = [
+        M6502InstructionSetSpecifier::Bpl,
+        M6502InstructionSetSpecifier::Bmi,
+        M6502InstructionSetSpecifier::Bvc,
+        M6502InstructionSetSpecifier::Bvs,
+        M6502InstructionSetSpecifier::Bcc,
+        M6502InstructionSetSpecifier::Bcs,
+        M6502InstructionSetSpecifier::Bne,
+        M6502InstructionSetSpecifier::Beq,
+    ];
+    const FLAG_SPECIFIERS: [M6502InstructionSetSpecifier; 8] = [
+        M6502InstructionSetSpecifier::Clc,
+        M6502InstructionSetSpecifier::Sec,
+        M6502InstructionSetSpecifier::Cli,
+        M6502InstructionSetSpecifier::Sei,
+        M6502InstructionSetSpecifier::Tya,
+        M6502InstructionSetSpecifier::Clv,
+        M6502InstructionSetSpecifier::Cld,
+        M6502InstructionSetSpecifier::Sed,
+    ];
+
+    let addressing_mode = instruction_first_byte[ARGUMENT].load::<u8>();
+    let specifier = SPECIFIERS[instruction_identifier as usize];
+
+    match addressing_mode {
+        0b000 => match instruction_identifier {
+            0b000 => Ok(implied(M6502InstructionSetSpecifier::Brk)),
+            0b001 => with_word_operand(
+                M6502InstructionSetSpecifier::Jsr,
+                AddressingMode::Absolute,
+                cursor,
+                memory_translation_table,
+            ),
+            0b010 => Ok(implied(M6502InstructionSetSpecifier::Rti)),
+            0b011 => Ok(implied(M6502InstructionSetSpecifier::Rts)),
+            0b100 => with_byte_operand(
+                M6502InstructionSetSpecifier::Nop,
+                AddressingMode::Immediate,
+                cursor,
+                memory_translation_table,
+            ),
+            _ => with_byte_operand(
+                specifier,
+                AddressingMode::Immediate,
+                cursor,
+                memory_translation_table,
+            ),
+        },
+        0b001 => with_byte_operand(
+            // JMP has no zero page form
+            if matches!(instruction_identifier, 0b010 | 0b011) {
+                M6502InstructionSetSpecifier::Nop
+            } else {
+                specifier
+            },
+            AddressingMode::ZeroPage,
+            cursor,
+            memory_translation_table,
+        ),
+        0b010 => Ok(implied(
+            STACK_AND_REGISTER_SPECIFIERS[instruction_identifier as usize],
+        )),
+        0b011 => with_word_operand(
+            specifier,
+            if instruction_identifier == 0b011 {
+                AddressingMode::AbsoluteIndirect
+            } else {
+                AddressingMode::Absolute
+            },
+            cursor,
+            memory_translation_table,
+        ),
+        0b100 => with_byte_operand(
+            BRANCH_SPECIFIERS[instruction_identifier as usize],
+            |offset| AddressingMode::Relative(offset as i8),
+            cursor,
+            memory_translation_table,
+        ),
+        0b101 => with_byte_operand(
+            match instruction_identifier {
+                0b100 | 0b101 => specifier,
+                _ => M6502InstructionSetSpecifier::Nop,
+            },
+            AddressingMode::XIndexedZeroPage,
+            cursor,
+            memory_translation_table,
+        ),
+        0b110 => Ok(implied(FLAG_SPECIFIERS[instruction_identifier as usize])),
+        0b111 => with_word_operand(
+            match instruction_identifier {
+                // STY has no absolute indexed form, this is where SHY ended up
+                0b100 => M6502InstructionSetSpecifier::Shy,
+                0b101 => specifier,
+                _ => M6502InstructionSetSpecifier::Nop,
+            },
+            AddressingMode::XIndexedAbsolute,
+            cursor,
+            memory_translation_table,
+        ),
         _ => {
             unreachable!()
         }
     }
 }
+
+/// Reads the byte some distance into the instruction, the address space wraps around at 16 bits
+pub(super) fn read_operand_byte(
+    cursor: usize,
+    offset: u16,
+    memory_translation_table: &MemoryTranslationTable,
+) -> Result<u8, Box<dyn std::error::Error>> {
+    let mut value = 0;
+    // Decoding is also done for the debugger, so it mustn't set off any side effects
+    memory_translation_table.preview(
+        (cursor as u16).wrapping_add(offset) as usize,
+        std::slice::from_mut(&mut value),
+    )?;
+
+    Ok(value)
+}
+
+fn implied(specifier: M6502InstructionSetSpecifier) -> (M6502InstructionSet, u8) {
+    (
+        M6502InstructionSet {
+            specifier,
+            addressing_mode: None,
+        },
+        1,
+    )
+}
+
+fn with_byte_operand(
+    specifier: M6502InstructionSetSpecifier,
+    addressing_mode: impl FnOnce(u8) -> AddressingMode,
+    cursor: usize,
+    memory_translation_table: &MemoryTranslationTable,
+) -> Result<(M6502InstructionSet, u8), Box<dyn std::error::Error>> {
+    let operand = read_operand_byte(cursor, 1, memory_translation_table)?;
+
+    Ok((
+        M6502InstructionSet {
+            specifier,
+            addressing_mode: Some(addressing_mode(operand)),
+        },
+        2,
+    ))
+}
+
+fn with_word_operand(
+    specifier: M6502InstructionSetSpecifier,
+    addressing_mode: impl FnOnce(u16) -> AddressingMode,
+    cursor: usize,
+    memory_translation_table: &MemoryTranslationTable,
+) -> Result<(M6502InstructionSet, u8), Box<dyn std::error::Error>> {
+    let operand = u16::from_le_bytes([
+        read_operand_byte(cursor, 1, memory_translation_table)?,
+        read_operand_byte(cursor, 2, memory_translation_table)?,
+    ]);
+
+    Ok((
+        M6502InstructionSet {
+            specifier,
+            addressing_mode: Some(addressing_mode(operand)),
+        },
+        3,
+    ))
+}
@@ -0,0 +1,1020 @@
+use super::{
+    decode::decode_instruction,
+    instruction::{AddressingMode, M6502InstructionSet, M6502InstructionSetSpecifier},
+    timing, FlagRegister, M6502,
+};
+use crate::component::{
+    memory::MemoryTranslationTable,
+    processor::{
+        dynarec::{BlockCacheFile, DynarecConfig, JitCompiler},
+        InstructionInterpretingError,
+    },
+};
+use cranelift_codegen::ir::{
+    condcodes::IntCC, types, AbiParam, InstBuilder, MemFlags, SigRef, Type, Value,
+};
+use cranelift_frontend::{FunctionBuilder, Variable};
+use enumflags2::BitFlags;
+use std::{
+    collections::{BTreeMap, HashMap},
+    mem::offset_of,
+    path::PathBuf,
+};
+
+/// Blocks are cut off here so interrupts don't wait on them for too long
+const MAX_BLOCK_INSTRUCTIONS: usize = 32;
+/// Code rewritten this often is left to the interpreter for good
+const MAX_INVALIDATIONS: u8 = 4;
+
+/// The registers as compiled code sees them, copied in and out around every block
+#[repr(C)]
+#[derive(Debug, Default)]
+struct BlockState {
+    accumulator: u8,
+    index_registers: [u8; 2],
+    stack_pointer: u8,
+    flags: u8,
+    /// Taken by reads that indexed across a page
+    extra_cycles: u32,
+}
+
+type BlockFunction =
+    unsafe extern "C" fn(*mut BlockState, *mut M6502, *const MemoryTranslationTable);
+
+#[derive(Clone, Copy)]
+struct CompiledBlock {
+    function: BlockFunction,
+    /// Base cycles of every instruction in it
+    cycles: u32,
+    /// In bytes of guest code
+    length: usize,
+}
+
+struct Block {
+    /// The guest code the block was compiled from, it is thrown out once memory says otherwise
+    code: Vec<u8>,
+    /// None if the first instruction is one the compiler doesn't handle
+    compiled: Option<CompiledBlock>,
+    invalidations: u8,
+}
+
+/// Compiles straight runs of loads, stores, transfers and flag changes, anything else ends the block and is left to
+/// the interpreter
+pub(super) struct M6502Dynarec {
+    compiler: JitCompiler,
+    /// By where they start
+    blocks: HashMap<usize, Block>,
+    block_cache: Option<PathBuf>,
+    /// Blocks from the last run still to be compiled, which waits until memory can be looked at
+    pending: BTreeMap<usize, Vec<u8>>,
+}
+
+impl M6502Dynarec {
+    pub fn new(config: &DynarecConfig) -> Result<Self, Box<dyn std::error::Error>> {
+        Ok(Self {
+            compiler: JitCompiler::new()?,
+            blocks: HashMap::new(),
+            pending: config
+                .block_cache
+                .as_deref()
+                .map(|path| BlockCacheFile::load(path).blocks)
+                .unwrap_or_default(),
+            block_cache: config.block_cache.clone(),
+        })
+    }
+
+    /// The compiled block starting here, if memory still holds the code it was compiled from
+    fn block(
+        &mut self,
+        start: usize,
+        memory_translation_table: &MemoryTranslationTable,
+    ) -> Option<CompiledBlock> {
+        for (cached_start, code) in std::mem::take(&mut self.pending) {
+            if preview_code(cached_start, code.len(), memory_translation_table).as_ref()
+                == Some(&code)
+            {
+                self.insert_block(cached_start, memory_translation_table, 0);
+            }
+        }
+
+        let invalidations = match self.blocks.get(&start) {
+            Some(block) => {
+                if block.code.is_empty()
+                    || preview_code(start, block.code.len(), memory_translation_table).as_ref()
+                        == Some(&block.code)
+                {
+                    return block.compiled;
+                }
+
+                block.invalidations + 1
+            }
+            None => 0,
+        };
+
+        if invalidations >= MAX_INVALIDATIONS {
+            tracing::debug!(
+                "Code at 0x{:x} keeps changing, interpreting it from now on",
+                start
+            );
+            self.blocks.insert(
+                start,
+                Block {
+                    code: Vec::new(),
+                    compiled: None,
+                    invalidations,
+                },
+            );
+
+            return None;
+        }
+
+        self.insert_block(start, memory_translation_table, invalidations)
+    }
+
+    fn insert_block(
+        &mut self,
+        start: usize,
+        memory_translation_table: &MemoryTranslationTable,
+        invalidations: u8,
+    ) -> Option<CompiledBlock> {
+        let instructions = decode_block(start, memory_translation_table);
+        // Left for the interpreter to report
+        let (_, first_size) = instructions.first()?;
+
+        let supported: Vec<_> = instructions
+            .iter()
+            .take_while(|(instruction, _)| is_supported(instruction))
+            .copied()
+            .collect();
+
+        let compiled = if supported.is_empty() {
+            None
+        } else {
+            match self.compile(&supported) {
+                Ok(compiled) => Some(compiled),
+                Err(error) => {
+                    tracing::warn!("Compiling the block at 0x{:x} failed: {}", start, error);
+                    None
+                }
+            }
+        };
+
+        // Only the code that ended up compiled, or the one instruction that couldn't be, decides when it is stale
+        let code_length = compiled.map_or(*first_size as usize, |compiled| compiled.length);
+        let code = preview_code(start, code_length, memory_translation_table)
+            // Code that can't be looked at without side effects is never compiled, nor checked again
+            .unwrap_or_default();
+
+        self.blocks.insert(
+            start,
+            Block {
+                compiled: compiled.filter(|_| !code.is_empty()),
+                code,
+                invalidations,
+            },
+        );
+
+        self.blocks[&start].compiled
+    }
+
+    fn compile(
+        &mut self,
+        instructions: &[(M6502InstructionSet, u8)],
+    ) -> Result<CompiledBlock, Box<dyn std::error::Error>> {
+        let pointer_type = self.compiler.pointer_type();
+
+        let mut signature = self.compiler.make_signature();
+        signature.params = vec![AbiParam::new(pointer_type); 3];
+
+        let mut read_signature = self.compiler.make_signature();
+        read_signature.params = vec![
+            AbiParam::new(pointer_type),
+            AbiParam::new(pointer_type),
+            AbiParam::new(types::I32),
+        ];
+        read_signature.returns = vec![AbiParam::new(types::I32)];
+
+        let mut write_signature = self.compiler.make_signature();
+        write_signature.params = vec![
+            AbiParam::new(pointer_type),
+            AbiParam::new(pointer_type),
+            AbiParam::new(types::I32),
+            AbiParam::new(types::I32),
+        ];
+
+        let code = self.compiler.compile(signature, |builder| {
+            let entry = builder.create_block();
+            builder.append_block_params_for_function_params(entry);
+            builder.switch_to_block(entry);
+            builder.seal_block(entry);
+
+            let &[state, processor, memory_translation_table] = builder.block_params(entry) else {
+                unreachable!()
+            };
+            let read_signature = builder.import_signature(read_signature);
+            let write_signature = builder.import_signature(write_signature);
+
+            let mut translator = Translator {
+                builder,
+                pointer_type,
+                state,
+                processor,
+                memory_translation_table,
+                read_signature,
+                write_signature,
+            };
+
+            translator.load_registers();
+            for (instruction, _) in instructions {
+                translator.translate(instruction);
+            }
+            translator.store_registers();
+
+            translator.builder.ins().return_(&[]);
+        })?;
+
+        Ok(CompiledBlock {
+            // SAFETY: The function was built with the signature of a block function
+            function: unsafe { std::mem::transmute::<*const u8, BlockFunction>(code) },
+            cycles: instructions
+                .iter()
+                .map(|(instruction, _)| timing::base_cycles(instruction))
+                .sum(),
+            length: instructions.iter().map(|(_, size)| *size as usize).sum(),
+        })
+    }
+}
+
+impl Drop for M6502Dynarec {
+    fn drop(&mut self) {
+        let Some(path) = &self.block_cache else {
+            return;
+        };
+
+        let block_cache = BlockCacheFile {
+            blocks: self
+                .blocks
+                .iter()
+                .filter(|(_, block)| block.compiled.is_some())
+                .map(|(start, block)| (*start, block.code.clone()))
+                .collect(),
+        };
+
+        if let Err(error) = block_cache.save(path) {
+            tracing::warn!("Could not save the block cache: {}", error);
+        }
+    }
+}
+
+impl M6502 {
+    pub(super) fn run_block(
+        &mut self,
+        program_pointer: &mut usize,
+        memory_translation_table: &MemoryTranslationTable,
+    ) -> Option<Result<(), InstructionInterpretingError>> {
+        let block = self
+            .dynarec
+            .as_mut()?
+            .block(*program_pointer, memory_translation_table)?;
+
+        Some(self.execute_block(block, program_pointer, memory_translation_table))
+    }
+
+    fn execute_block(
+        &mut self,
+        block: CompiledBlock,
+        program_pointer: &mut usize,
+        memory_translation_table: &MemoryTranslationTable,
+    ) -> Result<(), InstructionInterpretingError> {
+        self.extra_cycles = 0;
+
+        let mut state = BlockState {
+            accumulator: self.registers.accumulator,
+            index_registers: self.registers.index_registers,
+            stack_pointer: self.registers.stack_pointer,
+            flags: self.registers.flags.bits(),
+            extra_cycles: 0,
+        };
+
+        let processor: *mut Self = self;
+        // SAFETY: The processor is only reached through the pointer until the block returns
+        unsafe { (block.function)(&mut state, processor, memory_translation_table) };
+
+        self.registers.accumulator = state.accumulator;
+        self.registers.index_registers = state.index_registers;
+        self.registers.stack_pointer = state.stack_pointer;
+        self.registers.flags = BitFlags::from_bits_truncate(state.flags);
+
+        *program_pointer = program_pointer.wrapping_add(block.length);
+        self.cycles_remaining += block.cycles + state.extra_cycles + self.extra_cycles;
+
+        self.bus_fault
+            .take()
+            .map_or(Ok(()), |error| Err(error.into()))
+    }
+}
+
+/// Memory accesses go back through the processor so wait states, open bus and faults work like when interpreting
+extern "C" fn read_callback(
+    processor: *mut M6502,
+    memory_translation_table: *const MemoryTranslationTable,
+    address: u32,
+) -> u32 {
+    // SAFETY: Blocks only get the pointers [M6502::execute_block] hands them
+    let (processor, memory_translation_table) =
+        unsafe { (&mut *processor, &*memory_translation_table) };
+
+    processor.read_byte(memory_translation_table, address as u16) as u32
+}
+
+extern "C" fn write_callback(
+    processor: *mut M6502,
+    memory_translation_table: *const MemoryTranslationTable,
+    address: u32,
+    value: u32,
+) {
+    // SAFETY: Blocks only get the pointers [M6502::execute_block] hands them
+    let (processor, memory_translation_table) =
+        unsafe { (&mut *processor, &*memory_translation_table) };
+
+    processor.write_byte(memory_translation_table, address as u16, value as u8);
+}
+
+fn preview_code(
+    start: usize,
+    length: usize,
+    memory_translation_table: &MemoryTranslationTable,
+) -> Option<Vec<u8>> {
+    let mut code = vec![0; length];
+    memory_translation_table.preview(start, &mut code).ok()?;
+
+    Some(code)
+}
+
+/// Up to and including the first instruction the compiler doesn't handle
+fn decode_block(
+    start: usize,
+    memory_translation_table: &MemoryTranslationTable,
+) -> Vec<(M6502InstructionSet, u8)> {
+    let mut instructions = Vec::new();
+    let mut cursor = start;
+
+    while instructions.len() < MAX_BLOCK_INSTRUCTIONS {
+        let Ok((instruction, size)) = decode_instruction(cursor, memory_translation_table) else {
+            break;
+        };
+
+        instructions.push((instruction, size));
+        if !is_supported(&instruction) {
+            break;
+        }
+        cursor = cursor.wrapping_add(size as usize);
+    }
+
+    instructions
+}
+
+fn is_supported(instruction: &M6502InstructionSet) -> bool {
+    let memory = matches!(
+        instruction.addressing_mode,
+        Some(
+            AddressingMode::ZeroPage(_)
+                | AddressingMode::XIndexedZeroPage(_)
+                | AddressingMode::YIndexedZeroPage(_)
+                | AddressingMode::Absolute(_)
+                | AddressingMode::XIndexedAbsolute(_)
+                | AddressingMode::YIndexedAbsolute(_)
+        )
+    );
+
+    match instruction.specifier {
+        M6502InstructionSetSpecifier::Lda
+        | M6502InstructionSetSpecifier::Ldx
+        | M6502InstructionSetSpecifier::Ldy
+        | M6502InstructionSetSpecifier::And
+        | M6502InstructionSetSpecifier::Ora
+        | M6502InstructionSetSpecifier::Eor
+        | M6502InstructionSetSpecifier::Cmp
+        | M6502InstructionSetSpecifier::Cpx
+        | M6502InstructionSetSpecifier::Cpy => {
+            memory
+                || matches!(
+                    instruction.addressing_mode,
+                    Some(AddressingMode::Immediate(_))
+                )
+        }
+        M6502InstructionSetSpecifier::Sta
+        | M6502InstructionSetSpecifier::Stx
+        | M6502InstructionSetSpecifier::Sty => memory,
+        M6502InstructionSetSpecifier::Tax
+        | M6502InstructionSetSpecifier::Tay
+        | M6502InstructionSetSpecifier::Txa
+        | M6502InstructionSetSpecifier::Tya
+        | M6502InstructionSetSpecifier::Tsx
+        | M6502InstructionSetSpecifier::Txs
+        | M6502InstructionSetSpecifier::Inx
+        | M6502InstructionSetSpecifier::Iny
+        | M6502InstructionSetSpecifier::Dex
+        | M6502InstructionSetSpecifier::Dey
+        | M6502InstructionSetSpecifier::Clc
+        | M6502InstructionSetSpecifier::Sec
+        | M6502InstructionSetSpecifier::Cli
+        | M6502InstructionSetSpecifier::Sei
+        | M6502InstructionSetSpecifier::Clv
+        | M6502InstructionSetSpecifier::Cld
+        | M6502InstructionSetSpecifier::Sed => true,
+        // The undocumented ones still read
+        M6502InstructionSetSpecifier::Nop => instruction.addressing_mode.is_none(),
+        _ => false,
+    }
+}
+
+/// Registers live in variables for the length of the block
+#[derive(Clone, Copy)]
+enum Register {
+    Accumulator,
+    X,
+    Y,
+    StackPointer,
+    Flags,
+    ExtraCycles,
+}
+
+impl Register {
+    const ALL: [Self; 6] = [
+        Self::Accumulator,
+        Self::X,
+        Self::Y,
+        Self::StackPointer,
+        Self::Flags,
+        Self::ExtraCycles,
+    ];
+
+    fn variable(self) -> Variable {
+        Variable::from_u32(self as u32)
+    }
+
+    fn offset(self) -> usize {
+        match self {
+            Self::Accumulator => offset_of!(BlockState, accumulator),
+            Self::X => offset_of!(BlockState, index_registers),
+            Self::Y => offset_of!(BlockState, index_registers) + 1,
+            Self::StackPointer => offset_of!(BlockState, stack_pointer),
+            Self::Flags => offset_of!(BlockState, flags),
+            Self::ExtraCycles => offset_of!(BlockState, extra_cycles),
+        }
+    }
+
+    fn ty(self) -> Type {
+        match self {
+            Self::ExtraCycles => types::I32,
+            _ => types::I8,
+        }
+    }
+}
+
+struct Translator<'a, 'b> {
+    builder: &'a mut FunctionBuilder<'b>,
+    pointer_type: Type,
+    state: Value,
+    processor: Value,
+    memory_translation_table: Value,
+    read_signature: SigRef,
+    write_signature: SigRef,
+}
+
+impl Translator<'_, '_> {
+    fn load_registers(&mut self) {
+        for register in Register::ALL {
+            self.builder.declare_var(register.variable(), register.ty());
+            let value = self.builder.ins().load(
+                register.ty(),
+                MemFlags::trusted(),
+                self.state,
+                register.offset() as i32,
+            );
+            self.builder.def_var(register.variable(), value);
+        }
+    }
+
+    fn store_registers(&mut self) {
+        for register in Register::ALL {
+            let value = self.builder.use_var(register.variable());
+            self.builder.ins().store(
+                MemFlags::trusted(),
+                value,
+                self.state,
+                register.offset() as i32,
+            );
+        }
+    }
+
+    fn get(&mut self, register: Register) -> Value {
+        self.builder.use_var(register.variable())
+    }
+
+    fn set(&mut self, register: Register, value: Value) {
+        self.builder.def_var(register.variable(), value);
+    }
+
+    fn read(&mut self, address: Value) -> Value {
+        let callee = self
+            .builder
+            .ins()
+            .iconst(self.pointer_type, read_callback as *const () as i64);
+        let call = self.builder.ins().call_indirect(
+            self.read_signature,
+            callee,
+            &[self.processor, self.memory_translation_table, address],
+        );
+        let value = self.builder.inst_results(call)[0];
+
+        self.builder.ins().ireduce(types::I8, value)
+    }
+
+    fn write(&mut self, address: Value, value: Value) {
+        let value = self.builder.ins().uextend(types::I32, value);
+        let callee = self
+            .builder
+            .ins()
+            .iconst(self.pointer_type, write_callback as *const () as i64);
+        self.builder.ins().call_indirect(
+            self.write_signature,
+            callee,
+            &[
+                self.processor,
+                self.memory_translation_table,
+                address,
+                value,
+            ],
+        );
+    }
+
+    /// Same as [M6502::effective_address] for the modes blocks support
+    fn effective_address(&mut self, addressing_mode: AddressingMode, page_penalty: bool) -> Value {
+        let (index, address) = match addressing_mode {
+            AddressingMode::ZeroPage(address) => {
+                return self.builder.ins().iconst(types::I32, address as i64)
+            }
+            AddressingMode::Absolute(address) => {
+                return self.builder.ins().iconst(types::I32, address as i64)
+            }
+            AddressingMode::XIndexedZeroPage(address) => (Register::X, address as u16),
+            AddressingMode::YIndexedZeroPage(address) => (Register::Y, address as u16),
+            AddressingMode::XIndexedAbsolute(address) => (Register::X, address),
+            AddressingMode::YIndexedAbsolute(address) => (Register::Y, address),
+            _ => unreachable!("{:?} is not supported in blocks", addressing_mode),
+        };
+
+        let index = self.get(index);
+
+        // Indexing wraps inside the zero page
+        if matches!(
+            addressing_mode,
+            AddressingMode::XIndexedZeroPage(_) | AddressingMode::YIndexedZeroPage(_)
+        ) {
+            let sum = self.builder.ins().iadd_imm(index, address as i64);
+            return self.builder.ins().uextend(types::I32, sum);
+        }
+
+        let index = self.builder.ins().uextend(types::I32, index);
+        let sum = self.builder.ins().iadd_imm(index, address as i64);
+        let effective_address = self.builder.ins().band_imm(sum, 0xffff);
+
+        if page_penalty {
+            let changed = self
+                .builder
+                .ins()
+                .bxor_imm(effective_address, address as i64);
+            let changed_page = self.builder.ins().band_imm(changed, 0xff00);
+            let crossed = self
+                .builder
+                .ins()
+                .icmp_imm(IntCC::NotEqual, changed_page, 0);
+            let crossed = self.builder.ins().uextend(types::I32, crossed);
+            let extra_cycles = self.get(Register::ExtraCycles);
+            let extra_cycles = self.builder.ins().iadd(extra_cycles, crossed);
+            self.set(Register::ExtraCycles, extra_cycles);
+        }
+
+        effective_address
+    }
+
+    fn load(&mut self, instruction: &M6502InstructionSet) -> Value {
+        match instruction.addressing_mode.unwrap() {
+            AddressingMode::Immediate(value) => self.builder.ins().iconst(types::I8, value as i64),
+            addressing_mode => {
+                let address = self.effective_address(
+                    addressing_mode,
+                    timing::page_crossing_penalty(instruction.specifier),
+                );
+                self.read(address)
+            }
+        }
+    }
+
+    fn set_flag(&mut self, flag: FlagRegister, set: Value) {
+        let flags = self.get(Register::Flags);
+        let flags = self.builder.ins().band_imm(flags, !(flag as u8) as i64);
+        let set = self
+            .builder
+            .ins()
+            .ishl_imm(set, (flag as u8).trailing_zeros() as i64);
+        let flags = self.builder.ins().bor(flags, set);
+        self.set(Register::Flags, flags);
+    }
+
+    fn change_flag(&mut self, flag: FlagRegister, set: bool) {
+        let flags = self.get(Register::Flags);
+        let flags = if set {
+            self.builder.ins().bor_imm(flags, flag as u8 as i64)
+        } else {
+            self.builder.ins().band_imm(flags, !(flag as u8) as i64)
+        };
+        self.set(Register::Flags, flags);
+    }
+
+    fn set_negative_and_zero(&mut self, value: Value) {
+        let negative = self.builder.ins().ushr_imm(value, 7);
+        self.set_flag(FlagRegister::Negative, negative);
+        let zero = self.builder.ins().icmp_imm(IntCC::Equal, value, 0);
+        self.set_flag(FlagRegister::Zero, zero);
+    }
+
+    fn transfer(&mut self, from: Register, to: Register, flags: bool) {
+        let value = self.get(from);
+        if flags {
+            self.set_negative_and_zero(value);
+        }
+        self.set(to, value);
+    }
+
+    fn step(&mut self, register: Register, amount: u8) {
+        let value = self.get(register);
+        let value = self.builder.ins().iadd_imm(value, amount as i64);
+        self.set_negative_and_zero(value);
+        self.set(register, value);
+    }
+
+    fn translate(&mut self, instruction: &M6502InstructionSet) {
+        match instruction.specifier {
+            M6502InstructionSetSpecifier::Lda => {
+                let value = self.load(instruction);
+                self.set_negative_and_zero(value);
+                self.set(Register::Accumulator, value);
+            }
+            M6502InstructionSetSpecifier::Ldx => {
+                let value = self.load(instruction);
+                self.set_negative_and_zero(value);
+                self.set(Register::X, value);
+            }
+            M6502InstructionSetSpecifier::Ldy => {
+                let value = self.load(instruction);
+                self.set_negative_and_zero(value);
+                self.set(Register::Y, value);
+            }
+            M6502InstructionSetSpecifier::And
+            | M6502InstructionSetSpecifier::Ora
+            | M6502InstructionSetSpecifier::Eor => {
+                let value = self.load(instruction);
+                let accumulator = self.get(Register::Accumulator);
+                let result = match instruction.specifier {
+                    M6502InstructionSetSpecifier::And => {
+                        self.builder.ins().band(accumulator, value)
+                    }
+                    M6502InstructionSetSpecifier::Ora => self.builder.ins().bor(accumulator, value),
+                    _ => self.builder.ins().bxor(accumulator, value),
+                };
+                self.set_negative_and_zero(result);
+                self.set(Register::Accumulator, result);
+            }
+            M6502InstructionSetSpecifier::Cmp
+            | M6502InstructionSetSpecifier::Cpx
+            | M6502InstructionSetSpecifier::Cpy => {
+                let value = self.load(instruction);
+                let register = self.get(match instruction.specifier {
+                    M6502InstructionSetSpecifier::Cmp => Register::Accumulator,
+                    M6502InstructionSetSpecifier::Cpx => Register::X,
+                    _ => Register::Y,
+                });
+
+                let carry =
+                    self.builder
+                        .ins()
+                        .icmp(IntCC::UnsignedGreaterThanOrEqual, register, value);
+                self.set_flag(FlagRegister::Carry, carry);
+                let difference = self.builder.ins().isub(register, value);
+                self.set_negative_and_zero(difference);
+            }
+            M6502InstructionSetSpecifier::Sta
+            | M6502InstructionSetSpecifier::Stx
+            | M6502InstructionSetSpecifier::Sty => {
+                let address = self.effective_address(instruction.addressing_mode.unwrap(), false);
+                let value = self.get(match instruction.specifier {
+                    M6502InstructionSetSpecifier::Sta => Register::Accumulator,
+                    M6502InstructionSetSpecifier::Stx => Register::X,
+                    _ => Register::Y,
+                });
+                self.write(address, value);
+            }
+            M6502InstructionSetSpecifier::Tax => {
+                self.transfer(Register::Accumulator, Register::X, true)
+            }
+            M6502InstructionSetSpecifier::Tay => {
+                self.transfer(Register::Accumulator, Register::Y, true)
+            }
+            M6502InstructionSetSpecifier::Txa => {
+                self.transfer(Register::X, Register::Accumulator, true)
+            }
+            M6502InstructionSetSpecifier::Tya => {
+                self.transfer(Register::Y, Register::Accumulator, true)
+            }
+            M6502InstructionSetSpecifier::Tsx => {
+                self.transfer(Register::StackPointer, Register::X, true)
+            }
+            M6502InstructionSetSpecifier::Txs => {
+                self.transfer(Register::X, Register::StackPointer, false)
+            }
+            M6502InstructionSetSpecifier::Inx => self.step(Register::X, 1),
+            M6502InstructionSetSpecifier::Iny => self.step(Register::Y, 1),
+            M6502InstructionSetSpecifier::Dex => self.step(Register::X, 0xff),
+            M6502InstructionSetSpecifier::Dey => self.step(Register::Y, 0xff),
+            M6502InstructionSetSpecifier::Clc => self.change_flag(FlagRegister::Carry, false),
+            M6502InstructionSetSpecifier::Sec => self.change_flag(FlagRegister::Carry, true),
+            M6502InstructionSetSpecifier::Cli => {
+                self.change_flag(FlagRegister::InterruptDisable, false)
+            }
+            M6502InstructionSetSpecifier::Sei => {
+                self.change_flag(FlagRegister::InterruptDisable, true)
+            }
+            M6502InstructionSetSpecifier::Clv => self.change_flag(FlagRegister::Overflow, false),
+            M6502InstructionSetSpecifier::Cld => self.change_flag(FlagRegister::Decimal, false),
+            M6502InstructionSetSpecifier::Sed => self.change_flag(FlagRegister::Decimal, true),
+            M6502InstructionSetSpecifier::Nop => {}
+            _ => unreachable!("{:?} is not supported in blocks", instruction),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        component::{
+            definitions::misc::{
+                plain_memory::{PlainMemory, PlainMemoryConfig},
+                processor::m6502::{M6502Config, M6502Kind},
+            },
+            interrupt::InterruptLine,
+            processor::ProcessorComponent,
+            FromConfig,
+        },
+        rom::RomManager,
+    };
+    use num::rational::Ratio;
+    use std::sync::{Arc, Mutex};
+
+    fn m6502() -> (M6502, MemoryTranslationTable) {
+        m6502_with_block_cache(None)
+    }
+
+    fn m6502_with_block_cache(block_cache: Option<PathBuf>) -> (M6502, MemoryTranslationTable) {
+        let rom_manager = Arc::new(RomManager::default());
+        let mut memory_translation_table = MemoryTranslationTable::default();
+        memory_translation_table.insert(
+            0x0000..0x10000,
+            Arc::new(Mutex::new(PlainMemory::from_config(
+                rom_manager.clone(),
+                PlainMemoryConfig {
+                    assigned_range: 0x0000..0x10000,
+                    ..Default::default()
+                },
+            ))),
+        );
+
+        let mut processor = M6502::from_config(
+            rom_manager,
+            M6502Config {
+                frequency: Ratio::new(1, 1),
+                kind: M6502Kind::M6507,
+                irq: InterruptLine::default(),
+                nmi: InterruptLine::default(),
+                dynarec: Some(DynarecConfig { block_cache }),
+            },
+        );
+        processor.reset_pending = false;
+
+        (processor, memory_translation_table)
+    }
+
+    fn write(memory_translation_table: &MemoryTranslationTable, address: usize, bytes: &[u8]) {
+        for (offset, byte) in bytes.iter().enumerate() {
+            memory_translation_table
+                .write(address + offset, std::array::from_ref(byte))
+                .unwrap();
+        }
+    }
+
+    fn read(memory_translation_table: &MemoryTranslationTable, address: usize) -> u8 {
+        let mut value = 0;
+        memory_translation_table
+            .read(address, std::array::from_mut(&mut value))
+            .unwrap();
+        value
+    }
+
+    /// LDA #$80, TAX, STA $0300, and a JMP back to the start that ends the block
+    const PROGRAM: [u8; 9] = [0xa9, 0x80, 0xaa, 0x8d, 0x00, 0x03, 0x4c, 0x00, 0x02];
+
+    #[test]
+    fn blocks_match_interpreter() {
+        let program = [
+            (
+                M6502InstructionSetSpecifier::Lda,
+                Some(AddressingMode::Immediate(0x80)),
+            ),
+            (M6502InstructionSetSpecifier::Tax, None),
+            (M6502InstructionSetSpecifier::Inx, None),
+            (
+                M6502InstructionSetSpecifier::Sta,
+                Some(AddressingMode::XIndexedAbsolute(0x12f0)),
+            ),
+            (
+                M6502InstructionSetSpecifier::Ldy,
+                Some(AddressingMode::XIndexedAbsolute(0x12f0)),
+            ),
+            (
+                M6502InstructionSetSpecifier::Eor,
+                Some(AddressingMode::Immediate(0x80)),
+            ),
+            (
+                M6502InstructionSetSpecifier::Cpy,
+                Some(AddressingMode::Immediate(0x81)),
+            ),
+            (M6502InstructionSetSpecifier::Dex, None),
+            (
+                M6502InstructionSetSpecifier::Stx,
+                Some(AddressingMode::ZeroPage(0x10)),
+            ),
+            (M6502InstructionSetSpecifier::Sec, None),
+        ]
+        .map(|(specifier, addressing_mode)| {
+            (
+                M6502InstructionSet {
+                    specifier,
+                    addressing_mode,
+                },
+                1,
+            )
+        });
+
+        let (mut interpreted, interpreted_memory) = m6502();
+        let mut interpreted_program_pointer = 0x200;
+        for (instruction, size) in program {
+            interpreted_program_pointer += size as usize;
+            interpreted
+                .interpret(
+                    &mut interpreted_program_pointer,
+                    instruction,
+                    &interpreted_memory,
+                )
+                .unwrap();
+        }
+
+        let (mut compiled, compiled_memory) = m6502();
+        let mut compiled_program_pointer = 0x200;
+        let block = compiled
+            .dynarec
+            .as_mut()
+            .unwrap()
+            .compile(&program)
+            .unwrap();
+        compiled
+            .execute_block(block, &mut compiled_program_pointer, &compiled_memory)
+            .unwrap();
+
+        assert_eq!(compiled_program_pointer, interpreted_program_pointer);
+        assert_eq!(compiled.cycles_remaining, interpreted.cycles_remaining);
+        assert_eq!(
+            compiled.registers.accumulator,
+            interpreted.registers.accumulator
+        );
+        assert_eq!(
+            compiled.registers.index_registers,
+            interpreted.registers.index_registers
+        );
+        assert_eq!(compiled.registers.flags, interpreted.registers.flags);
+
+        for address in [0x1371, 0x0010] {
+            let mut compiled_value = 0;
+            let mut interpreted_value = 0;
+            compiled_memory
+                .read(address, std::array::from_mut(&mut compiled_value))
+                .unwrap();
+            interpreted_memory
+                .read(address, std::array::from_mut(&mut interpreted_value))
+                .unwrap();
+            assert_eq!(compiled_value, interpreted_value);
+        }
+    }
+
+    #[test]
+    fn runs_blocks_from_memory() {
+        let (mut processor, memory_translation_table) = m6502();
+        write(&memory_translation_table, 0x200, &PROGRAM);
+
+        let mut program_pointer = 0x200;
+        processor
+            .run_recompiled(&mut program_pointer, &memory_translation_table)
+            .unwrap()
+            .unwrap();
+
+        // Stops in front of the JMP
+        assert_eq!(program_pointer, 0x206);
+        assert_eq!(processor.registers.accumulator, 0x80);
+        assert_eq!(processor.registers.index_registers[0], 0x80);
+        assert!(processor.registers.flags.contains(FlagRegister::Negative));
+        assert_eq!(read(&memory_translation_table, 0x300), 0x80);
+        assert_eq!(processor.cycles_remaining, 8);
+
+        // Left for the interpreter
+        assert!(processor
+            .run_recompiled(&mut program_pointer, &memory_translation_table)
+            .is_none());
+    }
+
+    #[test]
+    fn writes_into_blocks_invalidate_them() {
+        let (mut processor, memory_translation_table) = m6502();
+        write(&memory_translation_table, 0x200, &PROGRAM);
+
+        let mut program_pointer = 0x200;
+        processor
+            .run_recompiled(&mut program_pointer, &memory_translation_table)
+            .unwrap()
+            .unwrap();
+
+        for value in 1..MAX_INVALIDATIONS {
+            write(&memory_translation_table, 0x201, &[value]);
+
+            let mut program_pointer = 0x200;
+            processor
+                .run_recompiled(&mut program_pointer, &memory_translation_table)
+                .unwrap()
+                .unwrap();
+            assert_eq!(processor.registers.accumulator, value);
+            assert_eq!(read(&memory_translation_table, 0x300), value);
+        }
+
+        // Self modifying code gives up on the compiler eventually
+        write(&memory_translation_table, 0x201, &[0xff]);
+        let mut program_pointer = 0x200;
+        assert!(processor
+            .run_recompiled(&mut program_pointer, &memory_translation_table)
+            .is_none());
+        assert_eq!(program_pointer, 0x200);
+    }
+
+    #[test]
+    fn block_cache_round_trip() {
+        let path =
+            std::env::temp_dir().join(format!("multiemu_block_cache_{}", std::process::id()));
+
+        let (mut processor, memory_translation_table) = m6502_with_block_cache(Some(path.clone()));
+        write(&memory_translation_table, 0x200, &PROGRAM);
+        let mut program_pointer = 0x200;
+        processor
+            .run_recompiled(&mut program_pointer, &memory_translation_table)
+            .unwrap()
+            .unwrap();
+        // Saved on the way out
+        drop(processor);
+
+        assert_eq!(
+            BlockCacheFile::load(&path).blocks,
+            BTreeMap::from_iter([(0x200, PROGRAM[..6].to_vec())])
+        );
+
+        // Compiled ahead of time when the code is still there
+        let (mut processor, memory_translation_table) = m6502_with_block_cache(Some(path.clone()));
+        write(&memory_translation_table, 0x200, &PROGRAM);
+        let mut program_pointer = 0x206;
+        processor.run_recompiled(&mut program_pointer, &memory_translation_table);
+        assert!(processor.dynarec.as_ref().unwrap().blocks[&0x200]
+            .compiled
+            .is_some());
+        drop(processor);
+
+        // And not when something else was loaded there
+        let (mut processor, memory_translation_table) = m6502_with_block_cache(Some(path.clone()));
+        write(&memory_translation_table, 0x200, &[0xa9, 0x00]);
+        let mut program_pointer = 0x206;
+        processor.run_recompiled(&mut program_pointer, &memory_translation_table);
+        let dynarec = processor.dynarec.as_ref().unwrap();
+        assert!(dynarec.pending.is_empty());
+        assert!(!dynarec.blocks.contains_key(&0x200));
+        drop(processor);
+
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -1,7 +1,8 @@
-use crate::component::{
-    memory::MemoryTranslationTable,
-    processor::{InstructionSet, InstructionTextRepresentation},
-};
+use super::decode::read_operand_byte;
+#[cfg(feature = "frontend")]
+use crate::component::processor::InstructionTextRepresentation;
+use crate::component::{memory::MemoryTranslationTable, processor::InstructionSet};
+#[cfg(feature = "frontend")]
 use std::borrow::Cow;
 
 // https://www.pagetable.com/c64ref/6502/?tab=2
@@ -17,19 +18,38 @@ pub enum AddressingMode {
     ZeroPage(u8),
     XIndexedZeroPage(u8),
     YIndexedZeroPage(u8),
-    ZeroPageYIndexed(u8),
     XIndexedZeroPageIndirect(u8),
     ZeroPageIndirectYIndexed(u8),
     Relative(i8),
 }
 
 impl AddressingMode {
+    /// The addressing modes the ALU instructions pick from with the middle three bits of the opcode, along with how
+    /// long the instruction ends up
     pub fn from_group1_addressing(
         addressing_mode_id: u8,
         memory_translation_table: &MemoryTranslationTable,
         cursor: usize,
-    ) -> (Self, u64, u8) {
-        todo!()
+    ) -> Result<(Self, u8), Box<dyn std::error::Error>> {
+        let byte = || read_operand_byte(cursor, 1, memory_translation_table);
+        let word = || -> Result<u16, Box<dyn std::error::Error>> {
+            Ok(u16::from_le_bytes([
+                read_operand_byte(cursor, 1, memory_translation_table)?,
+                read_operand_byte(cursor, 2, memory_translation_table)?,
+            ]))
+        };
+
+        Ok(match addressing_mode_id {
+            0b000 => (Self::XIndexedZeroPageIndirect(byte()?), 2),
+            0b001 => (Self::ZeroPage(byte()?), 2),
+            0b010 => (Self::Immediate(byte()?), 2),
+            0b011 => (Self::Absolute(word()?), 3),
+            0b100 => (Self::ZeroPageIndirectYIndexed(byte()?), 2),
+            0b101 => (Self::XIndexedZeroPage(byte()?), 2),
+            0b110 => (Self::YIndexedAbsolute(word()?), 3),
+            0b111 => (Self::XIndexedAbsolute(word()?), 3),
+            _ => unreachable!(),
+        })
     }
 }
 
@@ -119,9 +139,10 @@ pub struct M6502InstructionSet {
 }
 
 impl InstructionSet for M6502InstructionSet {
+    #[cfg(feature = "frontend")]
     fn to_text_representation(&self) -> InstructionTextRepresentation {
         InstructionTextRepresentation {
-            instruction_mnemonic: Cow::Borrowed("TODO"),
+            instruction_mnemonic: Cow::Owned(format!("{:?}", self)),
         }
     }
 }
@@ -2,10 +2,15 @@ use std::sync::Arc;
 
 use crate::{
     component::{
-        memory::MemoryTranslationTable,
-        processor::{InstructionDecompilingError, ProcessorComponent},
+        interrupt::InterruptLine,
+        memory::{MemoryOperationError, MemoryTranslationTable},
+        processor::{
+            dynarec::DynarecConfig, InstructionDecompilingError, InstructionInterpretingError,
+            ProcessorComponent, ProcessorRegister,
+        },
         schedulable::SchedulableComponent,
-        Component, FromConfig,
+        snapshot::SnapshotableComponent,
+        Component, ComponentVersion, FromConfig, SemanticVersion,
     },
     rom::RomManager,
 };
@@ -14,14 +19,31 @@ use decode::decode_instruction;
 use enumflags2::{bitflags, BitFlag, BitFlags};
 use instruction::{AddressingMode, M6502InstructionSet, M6502InstructionSetSpecifier};
 use num::rational::Ratio;
+use serde::{Deserialize, Serialize};
 
 pub mod decode;
+#[cfg(feature = "jit")]
+mod dynarec;
 pub mod instruction;
 #[cfg(test)]
 pub mod test;
-
+pub mod timing;
+
+/// The stack always lives in the second page
+const STACK_PAGE: u16 = 0x0100;
+/// Where NMI takes the new program pointer from
+const NMI_VECTOR: u16 = 0xfffa;
+/// Where the program pointer starts after a reset
+const RESET_VECTOR: u16 = 0xfffc;
+/// Where BRK and IRQ take the new program pointer from
+const IRQ_VECTOR: u16 = 0xfffe;
+/// What the unstable undocumented instructions OR the accumulator with, it varies between chips and even temperature
+const UNSTABLE_MAGIC: u8 = 0xee;
+
+#[derive(Debug, Serialize)]
 pub enum M6502Kind {
-    /// Standard
+    /// Standard, no machine uses it yet
+    #[allow(dead_code)]
     M6502 {
         /// Whether to emulated the broken ROR instruction
         quirk_broken_ror: bool,
@@ -30,8 +52,13 @@ pub enum M6502Kind {
     M6507,
     /// NES version
     R2A03,
-    /// NES version
-    R2A07,
+}
+
+impl M6502Kind {
+    /// The NES versions had decimal mode cut out, the flag still exists but does nothing
+    fn supports_decimal_mode(&self) -> bool {
+        !matches!(self, M6502Kind::R2A03)
+    }
 }
 
 #[bitflags]
@@ -62,166 +89,596 @@ pub struct M6502Registers {
     flags: BitFlags<FlagRegister>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Serialize, Deserialize)]
+pub struct M6502Snapshot {
+    pub stack_pointer: u8,
+    pub accumulator: u8,
+    pub index_registers: [u8; 2],
+    pub flags: u8,
+    pub jammed: bool,
+    pub reset_pending: bool,
+    pub cycles_remaining: u32,
+    /// The lines belong to whatever raises them, but a interrupt raised and not yet serviced is the processor's
+    pub irq: bool,
+    pub nmi: bool,
+}
+
+#[derive(Debug, Serialize)]
 pub struct M6502Config {
     pub frequency: Ratio<u32>,
+    pub kind: M6502Kind,
+    /// Level triggered, serviced for as long as it is raised and interrupts are enabled
+    #[serde(skip)]
+    pub irq: InterruptLine,
+    /// Edge triggered, serviced once per raise no matter the interrupt disable flag
+    #[serde(skip)]
+    pub nmi: InterruptLine,
+    /// Interrupts wait for the block running to end, so this trades timing accuracy for speed. Needs the jit feature
+    pub dynarec: Option<DynarecConfig>,
 }
 
 pub struct M6502 {
     config: M6502Config,
     registers: M6502Registers,
+    /// A JAM instruction locked up the processor, only a reset gets it going again
+    jammed: bool,
+    /// The processor starts out by taking the reset vector like real hardware
+    reset_pending: bool,
+    /// Cycles left until the current instruction is done, counted down by [SchedulableComponent::tick]
+    cycles_remaining: u32,
+    /// Wait states and taken branches that the current instruction picked up on top of its base cost
+    extra_cycles: u32,
+    /// The current instruction indexed across a page boundary
+    page_crossed: bool,
+    /// The first access of the current instruction that faulted, returned once it is done
+    bus_fault: Option<MemoryOperationError>,
+    #[cfg(feature = "jit")]
+    dynarec: Option<dynarec::M6502Dynarec>,
 }
 
-impl Component for M6502 {}
+impl M6502 {
+    /// Pushes the program pointer and flags and jumps through the vector, like BRK but without the break flag
+    fn interrupt(
+        &mut self,
+        program_pointer: &mut usize,
+        memory_translation_table: &MemoryTranslationTable,
+        vector: u16,
+    ) {
+        self.extra_cycles = 0;
+        self.push_address(memory_translation_table, *program_pointer as u16);
 
-impl FromConfig for M6502 {
-    type Config = M6502Config;
+        let mut flags = self.registers.flags | FlagRegister::__Unused;
+        flags.remove(FlagRegister::Break);
+        self.push(memory_translation_table, flags.bits());
 
-    fn from_config(_rom_manager: Arc<RomManager>, config: Self::Config) -> Self {
-        Self {
-            config,
-            registers: M6502Registers {
-                stack_pointer: 0xff,
-                accumulator: 0,
-                index_registers: [0, 0],
-                flags: BitFlags::empty(),
-            },
+        self.registers.flags.insert(FlagRegister::InterruptDisable);
+        *program_pointer = self.read_vector(memory_translation_table, vector) as usize;
+
+        self.cycles_remaining += timing::INTERRUPT_CYCLES + self.extra_cycles;
+    }
+
+    fn set_negative_and_zero(&mut self, value: u8) {
+        self.registers
+            .flags
+            .set(FlagRegister::Negative, value.view_bits::<Lsb0>()[7]);
+        self.registers.flags.set(FlagRegister::Zero, value == 0);
+    }
+
+    fn decimal_mode_active(&self) -> bool {
+        self.registers.flags.contains(FlagRegister::Decimal)
+            && self.config.kind.supports_decimal_mode()
+    }
+
+    /// Memory that is slower than the processor stalls it for however long the memory translation table says
+    fn read_byte(&mut self, memory_translation_table: &MemoryTranslationTable, address: u16) -> u8 {
+        let mut value = 0;
+
+        match memory_translation_table
+            .read_or_open_bus(address as usize, std::array::from_mut(&mut value))
+        {
+            Ok(cycles) => self.extra_cycles += cycles as u32,
+            Err(error) => {
+                self.bus_fault.get_or_insert(error);
+            }
         }
+
+        value
     }
-}
 
-impl SchedulableComponent for M6502 {
-    fn tick_rate(&self) -> Ratio<u32> {
-        self.config.frequency
+    fn write_byte(
+        &mut self,
+        memory_translation_table: &MemoryTranslationTable,
+        address: u16,
+        value: u8,
+    ) {
+        match memory_translation_table
+            .write_or_open_bus(address as usize, std::array::from_ref(&value))
+        {
+            Ok(cycles) => self.extra_cycles += cycles as u32,
+            Err(error) => {
+                self.bus_fault.get_or_insert(error);
+            }
+        }
     }
 
-    fn tick(&mut self, memory_translation_table: &MemoryTranslationTable) {}
-}
+    /// Vectors are little endian pointers at the very top of memory
+    fn read_vector(
+        &mut self,
+        memory_translation_table: &MemoryTranslationTable,
+        vector: u16,
+    ) -> u16 {
+        u16::from_le_bytes([
+            self.read_byte(memory_translation_table, vector),
+            self.read_byte(memory_translation_table, vector.wrapping_add(1)),
+        ])
+    }
 
-macro_rules! load_m6502_addressing_modes {
-    ($instruction:expr, $register_store:expr, $memory_translation_table:expr, [$($modes:ident),*]) => {{
-        match $instruction.addressing_mode {
-            $(
-                Some(AddressingMode::$modes(argument)) => {
-                    load_m6502_addressing_modes!(@handler $modes, argument, $register_store, $memory_translation_table)
-                },
-            )*
+    /// Pointers in the zero page wrap around inside of it
+    fn read_zero_page_pointer(
+        &mut self,
+        memory_translation_table: &MemoryTranslationTable,
+        address: u8,
+    ) -> u16 {
+        let low = self.read_byte(memory_translation_table, address as u16);
+        let high = self.read_byte(memory_translation_table, address.wrapping_add(1) as u16);
+
+        u16::from_le_bytes([low, high])
+    }
+
+    /// Where in memory a addressing mode points to, noting if indexing carried into the next page
+    fn effective_address(
+        &mut self,
+        addressing_mode: AddressingMode,
+        memory_translation_table: &MemoryTranslationTable,
+    ) -> u16 {
+        let [x, y] = self.registers.index_registers;
+
+        let (base, address) = match addressing_mode {
+            AddressingMode::Absolute(address) => return address,
+            AddressingMode::XIndexedAbsolute(address) => (address, address.wrapping_add(x as u16)),
+            AddressingMode::YIndexedAbsolute(address) => (address, address.wrapping_add(y as u16)),
+            AddressingMode::AbsoluteIndirect(address) => {
+                // The high byte is fetched without carrying into the page
+                let low = self.read_byte(memory_translation_table, address);
+                let high = self.read_byte(
+                    memory_translation_table,
+                    (address & 0xff00) | (address.wrapping_add(1) & 0x00ff),
+                );
+
+                return u16::from_le_bytes([low, high]);
+            }
+            AddressingMode::ZeroPage(address) => return address as u16,
+            AddressingMode::XIndexedZeroPage(address) => return address.wrapping_add(x) as u16,
+            AddressingMode::YIndexedZeroPage(address) => return address.wrapping_add(y) as u16,
+            AddressingMode::XIndexedZeroPageIndirect(address) => {
+                return self
+                    .read_zero_page_pointer(memory_translation_table, address.wrapping_add(x))
+            }
+            AddressingMode::ZeroPageIndirectYIndexed(address) => {
+                let base = self.read_zero_page_pointer(memory_translation_table, address);
+                (base, base.wrapping_add(y as u16))
+            }
+            AddressingMode::Accumulator
+            | AddressingMode::Immediate(_)
+            | AddressingMode::Relative(_) => {
+                unreachable!("{:?} does not point to memory", addressing_mode)
+            }
+        };
+
+        self.page_crossed = (base ^ address) & 0xff00 != 0;
+
+        address
+    }
+
+    /// Taking a branch costs a cycle, and another if it lands in a different page
+    fn branch(&mut self, program_pointer: &mut usize, offset: i8) {
+        // The program counter is only 16 bits, so branches near the top of memory wrap around to the bottom
+        let source = *program_pointer as u16;
+        let destination = source.wrapping_add_signed(offset as i16);
+
+        self.extra_cycles += 1;
+        if (destination ^ source) & 0xff00 != 0 {
+            self.extra_cycles += 1;
+        }
+
+        *program_pointer = destination as usize;
+    }
+
+    fn push(&mut self, memory_translation_table: &MemoryTranslationTable, value: u8) {
+        self.write_byte(
+            memory_translation_table,
+            STACK_PAGE | self.registers.stack_pointer as u16,
+            value,
+        );
+        self.registers.stack_pointer = self.registers.stack_pointer.wrapping_sub(1);
+    }
+
+    fn pop(&mut self, memory_translation_table: &MemoryTranslationTable) -> u8 {
+        self.registers.stack_pointer = self.registers.stack_pointer.wrapping_add(1);
+        self.read_byte(
+            memory_translation_table,
+            STACK_PAGE | self.registers.stack_pointer as u16,
+        )
+    }
+
+    /// High byte first, so it comes off the stack little endian
+    fn push_address(&mut self, memory_translation_table: &MemoryTranslationTable, address: u16) {
+        let [low, high] = address.to_le_bytes();
+        self.push(memory_translation_table, high);
+        self.push(memory_translation_table, low);
+    }
+
+    fn pop_address(&mut self, memory_translation_table: &MemoryTranslationTable) -> u16 {
+        let low = self.pop(memory_translation_table);
+        let high = self.pop(memory_translation_table);
+        u16::from_le_bytes([low, high])
+    }
+
+    /// The break and unused bits only exist on the stack
+    fn pop_flags(&mut self, memory_translation_table: &MemoryTranslationTable) {
+        let mut flags = FlagRegister::from_bits_truncate(self.pop(memory_translation_table));
+        flags.remove(FlagRegister::Break | FlagRegister::__Unused);
+        self.registers.flags = flags;
+    }
+
+    fn store(
+        &mut self,
+        addressing_mode: Option<AddressingMode>,
+        memory_translation_table: &MemoryTranslationTable,
+        value: u8,
+    ) {
+        let address = self.effective_address(addressing_mode.unwrap(), memory_translation_table);
+        self.write_byte(memory_translation_table, address, value);
+    }
+
+    /// Runs a operation on either the accumulator or memory, writing the result back where it came from
+    fn modify(
+        &mut self,
+        addressing_mode: Option<AddressingMode>,
+        memory_translation_table: &MemoryTranslationTable,
+        operation: impl FnOnce(&mut Self, u8) -> u8,
+    ) -> u8 {
+        match addressing_mode {
+            Some(AddressingMode::Accumulator) => {
+                let result = operation(self, self.registers.accumulator);
+                self.registers.accumulator = result;
+                result
+            }
+            Some(addressing_mode) => {
+                let address = self.effective_address(addressing_mode, memory_translation_table);
+                let value = self.read_byte(memory_translation_table, address);
+                let result = operation(self, value);
+                self.write_byte(memory_translation_table, address, result);
+                result
+            }
+            None => unreachable!(),
+        }
+    }
+
+    /// Stores value ANDed with the high byte of the target address plus one, the SHA family of undocumented
+    /// instructions. If the index crosses a page the high byte of the address gets mangled into the value too
+    fn store_and_high_byte(
+        &mut self,
+        addressing_mode: Option<AddressingMode>,
+        memory_translation_table: &MemoryTranslationTable,
+        value: u8,
+    ) {
+        let [x, y] = self.registers.index_registers;
+        let (base, index) = match addressing_mode {
+            Some(AddressingMode::XIndexedAbsolute(address)) => (address, x),
+            Some(AddressingMode::YIndexedAbsolute(address)) => (address, y),
+            Some(AddressingMode::ZeroPageIndirectYIndexed(address)) => (
+                self.read_zero_page_pointer(memory_translation_table, address),
+                y,
+            ),
             _ => unreachable!(),
+        };
+
+        let address = base.wrapping_add(index as u16);
+        let value = value & ((base >> 8) as u8).wrapping_add(1);
+
+        let address = if (address ^ base) & 0xff00 != 0 {
+            (address & 0x00ff) | ((value as u16) << 8)
+        } else {
+            address
+        };
+
+        self.write_byte(memory_translation_table, address, value);
+    }
+
+    fn add_with_carry(&mut self, value: u8) {
+        let accumulator = self.registers.accumulator;
+        let carry = self.registers.flags.contains(FlagRegister::Carry) as u16;
+        let binary_result = accumulator as u16 + value as u16 + carry;
+
+        if self.decimal_mode_active() {
+            let mut low = (accumulator & 0x0f) as u16 + (value & 0x0f) as u16 + carry;
+            if low >= 0x0a {
+                low = ((low + 0x06) & 0x0f) + 0x10;
+            }
+            let mut result = (accumulator & 0xf0) as u16 + (value & 0xf0) as u16 + low;
+
+            // The NMOS parts take these from the result before the high digit is adjusted, and zero from the binary
+            // result
+            self.registers
+                .flags
+                .set(FlagRegister::Negative, result & 0x80 != 0);
+            self.registers.flags.set(
+                FlagRegister::Overflow,
+                !(accumulator ^ value) & (accumulator ^ result as u8) & 0x80 != 0,
+            );
+            self.registers
+                .flags
+                .set(FlagRegister::Zero, binary_result & 0xff == 0);
+
+            if result >= 0xa0 {
+                result += 0x60;
+            }
+
+            self.registers.flags.set(FlagRegister::Carry, result > 0xff);
+            self.registers.accumulator = result as u8;
+        } else {
+            let result = binary_result as u8;
+
+            self.registers
+                .flags
+                .set(FlagRegister::Carry, binary_result > 0xff);
+            self.registers.flags.set(
+                FlagRegister::Overflow,
+                !(accumulator ^ value) & (accumulator ^ result) & 0x80 != 0,
+            );
+            self.set_negative_and_zero(result);
+            self.registers.accumulator = result;
         }
-    }};
+    }
 
-    (@handler Immediate, $argument:expr, $register_store:expr, $memory_translation_table:expr) => {{
-        $argument
-    }};
+    fn subtract_with_borrow(&mut self, value: u8) {
+        let accumulator = self.registers.accumulator;
+        let borrow = !self.registers.flags.contains(FlagRegister::Carry) as i16;
+        let binary_result = accumulator as i16 - value as i16 - borrow;
+
+        // Flags always come from the binary result, even in decimal mode
+        self.registers
+            .flags
+            .set(FlagRegister::Carry, binary_result >= 0);
+        self.registers.flags.set(
+            FlagRegister::Overflow,
+            (accumulator ^ value) & (accumulator ^ binary_result as u8) & 0x80 != 0,
+        );
+        self.set_negative_and_zero(binary_result as u8);
+
+        if self.decimal_mode_active() {
+            let mut low = (accumulator & 0x0f) as i16 - (value & 0x0f) as i16 - borrow;
+            if low < 0 {
+                low = ((low - 0x06) & 0x0f) - 0x10;
+            }
+            let mut result = (accumulator & 0xf0) as i16 - (value & 0xf0) as i16 + low;
+            if result < 0 {
+                result -= 0x60;
+            }
 
-    (@handler Absolute, $argument:expr, $register_store:expr, $memory_translation_table:expr) => {{
-        let mut value = 0;
+            self.registers.accumulator = result as u8;
+        } else {
+            self.registers.accumulator = binary_result as u8;
+        }
+    }
 
-        $memory_translation_table
-            .read($argument as usize, std::array::from_mut(&mut value))
-            .unwrap();
+    fn compare(&mut self, register: u8, value: u8) {
+        self.registers
+            .flags
+            .set(FlagRegister::Carry, register >= value);
+        self.set_negative_and_zero(register.wrapping_sub(value));
+    }
 
-        value
-    }};
+    fn shift_left(&mut self, value: u8) -> u8 {
+        let result = value << 1;
 
-    (@handler XIndexedAbsolute, $argument:expr, $register_store:expr, $memory_translation_table:expr) => {{
-        let mut value = 0;
+        self.registers
+            .flags
+            .set(FlagRegister::Carry, value.view_bits::<Lsb0>()[7]);
+        self.set_negative_and_zero(result);
+
+        result
+    }
 
-        $memory_translation_table
-            .read($argument as usize, &mut [0])
-            .unwrap();
+    fn shift_right(&mut self, value: u8) -> u8 {
+        let result = value >> 1;
 
-        let actual_address = $argument.wrapping_add($register_store.index_registers[0] as u16);
-        $memory_translation_table
-            .read(actual_address as usize, std::array::from_mut(&mut value))
-            .unwrap();
-        value
-    }};
+        self.registers
+            .flags
+            .set(FlagRegister::Carry, value.view_bits::<Lsb0>()[0]);
+        self.set_negative_and_zero(result);
 
-    (@handler YIndexedAbsolute, $argument:expr, $register_store:expr, $memory_translation_table:expr) => {{
-        let mut value = 0;
+        result
+    }
 
-        $memory_translation_table
-            .read($argument as usize, &mut [0])
-            .unwrap();
+    fn rotate_left(&mut self, value: u8) -> u8 {
+        let carry = self.registers.flags.contains(FlagRegister::Carry) as u8;
+        let result = (value << 1) | carry;
 
-        let actual_address = $argument.wrapping_add($register_store.index_registers[1] as u16);
-        $memory_translation_table
-            .read(actual_address as usize, std::array::from_mut(&mut value))
-            .unwrap();
-        value
-    }};
+        self.registers
+            .flags
+            .set(FlagRegister::Carry, value.view_bits::<Lsb0>()[7]);
+        self.set_negative_and_zero(result);
 
-    (@handler ZeroPage, $argument:expr, $register_store:expr, $memory_translation_table:expr) => {{
-        let mut value = 0;
+        result
+    }
 
-        $memory_translation_table
-            .read($argument as usize, std::array::from_mut(&mut value))
-            .unwrap();
+    fn rotate_right(&mut self, value: u8) -> u8 {
+        // Early chips shift left instead and leave carry alone
+        if matches!(
+            self.config.kind,
+            M6502Kind::M6502 {
+                quirk_broken_ror: true
+            }
+        ) {
+            let result = value << 1;
+            self.set_negative_and_zero(result);
 
-        value
-    }};
+            return result;
+        }
 
-    (@handler XIndexedZeroPage, $argument:expr, $register_store:expr, $memory_translation_table:expr) => {{
-        let mut value = 0;
+        let carry = self.registers.flags.contains(FlagRegister::Carry) as u8;
+        let result = (value >> 1) | (carry << 7);
+
+        self.registers
+            .flags
+            .set(FlagRegister::Carry, value.view_bits::<Lsb0>()[0]);
+        self.set_negative_and_zero(result);
 
-        let actual_address = $argument.wrapping_add($register_store.index_registers[0]);
+        result
+    }
 
-        $memory_translation_table
-            .read(actual_address as usize, std::array::from_mut(&mut value))
-            .unwrap();
+    /// AND then rotate right, with flags that come out of the adder rather than the shifter
+    fn and_rotate_right(&mut self, value: u8) {
+        let anded = self.registers.accumulator & value;
+        let carry = self.registers.flags.contains(FlagRegister::Carry) as u8;
+        let mut result = (anded >> 1) | (carry << 7);
 
-        value
-    }};
+        self.set_negative_and_zero(result);
 
-    (@handler YIndexedZeroPage, $argument:expr, $register_store:expr, $memory_translation_table:expr) => {{
-        let mut value = 0;
+        if self.decimal_mode_active() {
+            self.registers
+                .flags
+                .set(FlagRegister::Overflow, (anded ^ result) & 0x40 != 0);
 
-        let actual_address = $argument.wrapping_add($register_store.index_registers[1]);
+            let (high, low) = (anded >> 4, anded & 0x0f);
+            if low + (low & 1) > 5 {
+                result = (result & 0xf0) | (result.wrapping_add(6) & 0x0f);
+            }
 
-        $memory_translation_table
-            .read(actual_address as usize, std::array::from_mut(&mut value))
-            .unwrap();
+            let carry = high + (high & 1) > 5;
+            if carry {
+                result = result.wrapping_add(0x60);
+            }
+            self.registers.flags.set(FlagRegister::Carry, carry);
+        } else {
+            let bits = result.view_bits::<Lsb0>();
+            self.registers.flags.set(FlagRegister::Carry, bits[6]);
+            self.registers
+                .flags
+                .set(FlagRegister::Overflow, bits[6] ^ bits[5]);
+        }
 
-        value
-    }};
+        self.registers.accumulator = result;
+    }
+}
 
-    (@handler XIndexedZeroPageIndirect, $argument:expr, $register_store:expr, $memory_translation_table:expr) => {{
-        let mut value = 0;
+impl Component for M6502 {
+    fn reset(&mut self) {
+        self.reset_pending = true;
+    }
+}
+
+impl SnapshotableComponent for M6502 {
+    fn save_snapshot(&mut self) -> rmpv::Value {
+        let state = M6502Snapshot {
+            stack_pointer: self.registers.stack_pointer,
+            accumulator: self.registers.accumulator,
+            index_registers: self.registers.index_registers,
+            flags: self.registers.flags.bits(),
+            jammed: self.jammed,
+            reset_pending: self.reset_pending,
+            cycles_remaining: self.cycles_remaining,
+            irq: self.config.irq.is_raised(),
+            nmi: self.config.nmi.is_raised(),
+        };
+
+        rmpv::ext::to_value(&state).unwrap()
+    }
 
-        let indirection_address = $argument.wrapping_add($register_store.index_registers[0]);
-        let mut actual_address = [0; 2];
+    fn load_snapshot(&mut self, state: rmpv::Value) {
+        let state = rmpv::ext::from_value::<M6502Snapshot>(state).unwrap();
+
+        self.registers = M6502Registers {
+            stack_pointer: state.stack_pointer,
+            accumulator: state.accumulator,
+            index_registers: state.index_registers,
+            flags: BitFlags::from_bits_truncate(state.flags),
+        };
+        self.jammed = state.jammed;
+        self.reset_pending = state.reset_pending;
+        self.cycles_remaining = state.cycles_remaining;
+
+        for (line, raised) in [(&self.config.irq, state.irq), (&self.config.nmi, state.nmi)] {
+            if raised {
+                line.raise();
+            } else {
+                line.lower();
+            }
+        }
+    }
+}
 
-        $memory_translation_table
-            .read(indirection_address as usize, &mut actual_address)
-            .unwrap();
+impl ComponentVersion for M6502 {
+    const NAME: &'static str = "m6502";
+    const VERSION: SemanticVersion = SemanticVersion::new(1, 0, 0);
+}
 
-        let actual_address = u16::from_le_bytes(actual_address);
+impl FromConfig for M6502 {
+    type Config = M6502Config;
 
-        $memory_translation_table
-            .read(actual_address as usize, std::array::from_mut(&mut value))
-            .unwrap();
+    fn from_config(_rom_manager: Arc<RomManager>, config: Self::Config) -> Self {
+        #[cfg(feature = "jit")]
+        let dynarec = config.dynarec.as_ref().and_then(|dynarec_config| {
+            dynarec::M6502Dynarec::new(dynarec_config)
+                .inspect_err(|error| {
+                    tracing::warn!("Dynamic recompilation is not available: {}", error)
+                })
+                .ok()
+        });
 
-        value
-    }};
+        Self {
+            config,
+            registers: M6502Registers {
+                stack_pointer: 0xff,
+                accumulator: 0,
+                index_registers: [0, 0],
+                flags: BitFlags::empty(),
+            },
+            jammed: false,
+            reset_pending: true,
+            cycles_remaining: 0,
+            extra_cycles: 0,
+            page_crossed: false,
+            bus_fault: None,
+            #[cfg(feature = "jit")]
+            dynarec,
+        }
+    }
+}
 
-    (@handler ZeroPageIndirectYIndexed, $argument:expr, $register_store:expr, $memory_translation_table:expr) => {{
-         let mut value = 0;
+impl SchedulableComponent for M6502 {
+    fn tick_rate(&self) -> Ratio<u32> {
+        self.config.frequency
+    }
 
-        let mut indirection_address = 0;
+    fn tick(&mut self, _memory_translation_table: &MemoryTranslationTable) {
+        self.cycles_remaining = self.cycles_remaining.saturating_sub(1);
+    }
+}
 
-        $memory_translation_table
-            .read($argument as usize, std::array::from_mut(&mut indirection_address))
-            .unwrap();
+macro_rules! load_m6502_addressing_modes {
+    ($instruction:expr, $processor:expr, $memory_translation_table:expr, [$($modes:ident),*]) => {{
+        match $instruction.addressing_mode {
+            $(
+                Some(AddressingMode::$modes(argument)) => {
+                    load_m6502_addressing_modes!(@handler $modes, argument, $processor, $memory_translation_table)
+                },
+            )*
+            _ => unreachable!(),
+        }
+    }};
 
-        let indirection_address = (indirection_address as u16)
-            .wrapping_add($register_store.index_registers[1] as u16);
+    (@handler Immediate, $argument:expr, $processor:expr, $memory_translation_table:expr) => {{
+        $argument
+    }};
 
-        $memory_translation_table
-            .read(indirection_address as usize, std::array::from_mut(&mut value))
-            .unwrap();
+    (@handler $mode:ident, $argument:expr, $processor:expr, $memory_translation_table:expr) => {{
+        let address =
+            $processor.effective_address(AddressingMode::$mode($argument), $memory_translation_table);
 
-        value
+        $processor.read_byte($memory_translation_table, address)
     }};
 }
 
@@ -229,7 +686,55 @@ impl ProcessorComponent for M6502 {
     type InstructionSet = M6502InstructionSet;
 
     fn should_execution_occur(&self) -> bool {
-        todo!()
+        !self.jammed && self.cycles_remaining == 0
+    }
+
+    fn debug_registers(&self, program_pointer: usize) -> Vec<ProcessorRegister> {
+        vec![
+            ProcessorRegister::new("a", 8, self.registers.accumulator),
+            ProcessorRegister::new("x", 8, self.registers.index_registers[0]),
+            ProcessorRegister::new("y", 8, self.registers.index_registers[1]),
+            ProcessorRegister::new("s", 8, self.registers.stack_pointer),
+            ProcessorRegister::new("p", 8, self.registers.flags.bits()),
+            ProcessorRegister::new("pc", 16, program_pointer as u64),
+        ]
+    }
+
+    fn service_interrupts(
+        &mut self,
+        program_pointer: &mut usize,
+        memory_translation_table: &MemoryTranslationTable,
+    ) {
+        if self.reset_pending {
+            self.reset_pending = false;
+            self.jammed = false;
+            self.extra_cycles = 0;
+
+            // Reset goes through the motions of an interrupt with the writes suppressed
+            self.registers.stack_pointer = self.registers.stack_pointer.wrapping_sub(3);
+            self.registers.flags.insert(FlagRegister::InterruptDisable);
+            *program_pointer = self.read_vector(memory_translation_table, RESET_VECTOR) as usize;
+            self.cycles_remaining += timing::INTERRUPT_CYCLES + self.extra_cycles;
+
+            return;
+        }
+
+        // Only a reset unjams the processor
+        if self.jammed {
+            return;
+        }
+
+        // NMI wins if both are pending
+        if self.config.nmi.acknowledge() {
+            self.interrupt(program_pointer, memory_translation_table, NMI_VECTOR);
+        } else if self.config.irq.is_raised()
+            && !self
+                .registers
+                .flags
+                .contains(FlagRegister::InterruptDisable)
+        {
+            self.interrupt(program_pointer, memory_translation_table, IRQ_VECTOR);
+        }
     }
 
     fn decompile(
@@ -237,7 +742,8 @@ impl ProcessorComponent for M6502 {
         cursor: usize,
         memory_translation_table: &MemoryTranslationTable,
     ) -> Result<(Self::InstructionSet, u8), InstructionDecompilingError> {
-        Ok(decode_instruction(cursor, memory_translation_table).unwrap())
+        decode_instruction(cursor, memory_translation_table)
+            .map_err(|_| InstructionDecompilingError::at(cursor, 3, memory_translation_table))
     }
 
     fn interpret(
@@ -245,12 +751,15 @@ impl ProcessorComponent for M6502 {
         program_pointer: &mut usize,
         instruction: Self::InstructionSet,
         memory_translation_table: &MemoryTranslationTable,
-    ) -> Result<(), String> {
+    ) -> Result<(), InstructionInterpretingError> {
+        self.extra_cycles = 0;
+        self.page_crossed = false;
+
         match instruction.specifier {
             M6502InstructionSetSpecifier::Adc => {
                 let value = load_m6502_addressing_modes!(
                     instruction,
-                    self.registers,
+                    self,
                     memory_translation_table,
                     [
                         Immediate,
@@ -264,43 +773,12 @@ impl ProcessorComponent for M6502 {
                     ]
                 );
 
-                let carry_value = self.registers.flags.contains(FlagRegister::Carry) as u8;
-
-                let (first_operation_result, first_operation_overflow) =
-                    self.registers.accumulator.overflowing_add(value);
-
-                let (second_operation_result, second_operation_overflow) =
-                    first_operation_result.overflowing_add(carry_value);
-
-                self.registers.flags.set(
-                    FlagRegister::Overflow,
-                    // If it overflowed at any point this is set
-                    first_operation_overflow || second_operation_overflow,
-                );
-
-                self.registers.flags.set(
-                    FlagRegister::Carry,
-                    first_operation_overflow || second_operation_overflow,
-                );
-
-                self.registers.flags.set(
-                    FlagRegister::Negative,
-                    // Check would be sign value
-                    second_operation_result.view_bits::<Lsb0>()[7],
-                );
-
-                self.registers.flags.set(
-                    FlagRegister::Zero,
-                    // Check would be carry value
-                    second_operation_result == 0,
-                );
-
-                self.registers.accumulator = second_operation_result;
+                self.add_with_carry(value);
             }
             M6502InstructionSetSpecifier::Anc => {
                 let value = load_m6502_addressing_modes!(
                     instruction,
-                    self.registers,
+                    self,
                     memory_translation_table,
                     [Immediate]
                 );
@@ -322,7 +800,7 @@ impl ProcessorComponent for M6502 {
             M6502InstructionSetSpecifier::And => {
                 let value = load_m6502_addressing_modes!(
                     instruction,
-                    self.registers,
+                    self,
                     memory_translation_table,
                     [
                         Immediate,
@@ -346,9 +824,33 @@ impl ProcessorComponent for M6502 {
 
                 self.registers.accumulator = new_value;
             }
-            M6502InstructionSetSpecifier::Arr => todo!(),
-            M6502InstructionSetSpecifier::Asl => todo!(),
-            M6502InstructionSetSpecifier::Asr => todo!(),
+            M6502InstructionSetSpecifier::Arr => {
+                let value = load_m6502_addressing_modes!(
+                    instruction,
+                    self,
+                    memory_translation_table,
+                    [Immediate]
+                );
+
+                self.and_rotate_right(value);
+            }
+            M6502InstructionSetSpecifier::Asl => {
+                self.modify(
+                    instruction.addressing_mode,
+                    memory_translation_table,
+                    Self::shift_left,
+                );
+            }
+            M6502InstructionSetSpecifier::Asr => {
+                let value = load_m6502_addressing_modes!(
+                    instruction,
+                    self,
+                    memory_translation_table,
+                    [Immediate]
+                );
+
+                self.registers.accumulator = self.shift_right(self.registers.accumulator & value);
+            }
             M6502InstructionSetSpecifier::Bcc => {
                 let value = match instruction.addressing_mode {
                     Some(AddressingMode::Relative(value)) => value,
@@ -356,7 +858,7 @@ impl ProcessorComponent for M6502 {
                 };
 
                 if !self.registers.flags.contains(FlagRegister::Carry) {
-                    *program_pointer = program_pointer.wrapping_add_signed(value as isize);
+                    self.branch(program_pointer, value);
                 }
             }
             M6502InstructionSetSpecifier::Bcs => {
@@ -366,7 +868,7 @@ impl ProcessorComponent for M6502 {
                 };
 
                 if self.registers.flags.contains(FlagRegister::Carry) {
-                    *program_pointer = program_pointer.wrapping_add_signed(value as isize);
+                    self.branch(program_pointer, value);
                 }
             }
             M6502InstructionSetSpecifier::Beq => {
@@ -376,10 +878,29 @@ impl ProcessorComponent for M6502 {
                 };
 
                 if self.registers.flags.contains(FlagRegister::Zero) {
-                    *program_pointer = program_pointer.wrapping_add_signed(value as isize);
+                    self.branch(program_pointer, value);
                 }
             }
-            M6502InstructionSetSpecifier::Bit => todo!(),
+            M6502InstructionSetSpecifier::Bit => {
+                let value = load_m6502_addressing_modes!(
+                    instruction,
+                    self,
+                    memory_translation_table,
+                    [Absolute, ZeroPage]
+                );
+
+                let value_bits = value.view_bits::<Lsb0>();
+
+                self.registers
+                    .flags
+                    .set(FlagRegister::Negative, value_bits[7]);
+                self.registers
+                    .flags
+                    .set(FlagRegister::Overflow, value_bits[6]);
+                self.registers
+                    .flags
+                    .set(FlagRegister::Zero, self.registers.accumulator & value == 0);
+            }
             M6502InstructionSetSpecifier::Bmi => {
                 let value = match instruction.addressing_mode {
                     Some(AddressingMode::Relative(value)) => value,
@@ -387,7 +908,7 @@ impl ProcessorComponent for M6502 {
                 };
 
                 if self.registers.flags.contains(FlagRegister::Negative) {
-                    *program_pointer = program_pointer.wrapping_add_signed(value as isize);
+                    self.branch(program_pointer, value);
                 }
             }
             M6502InstructionSetSpecifier::Bne => {
@@ -397,7 +918,7 @@ impl ProcessorComponent for M6502 {
                 };
 
                 if !self.registers.flags.contains(FlagRegister::Zero) {
-                    *program_pointer = program_pointer.wrapping_add_signed(value as isize);
+                    self.branch(program_pointer, value);
                 }
             }
             M6502InstructionSetSpecifier::Bpl => {
@@ -407,10 +928,23 @@ impl ProcessorComponent for M6502 {
                 };
 
                 if !self.registers.flags.contains(FlagRegister::Negative) {
-                    *program_pointer = program_pointer.wrapping_add_signed(value as isize);
+                    self.branch(program_pointer, value);
                 }
             }
-            M6502InstructionSetSpecifier::Brk => todo!(),
+            M6502InstructionSetSpecifier::Brk => {
+                // BRK is followed by a padding byte that the return address skips
+                self.push_address(
+                    memory_translation_table,
+                    (*program_pointer as u16).wrapping_add(1),
+                );
+
+                let flags = self.registers.flags | FlagRegister::Break | FlagRegister::__Unused;
+                self.push(memory_translation_table, flags.bits());
+
+                self.registers.flags.insert(FlagRegister::InterruptDisable);
+
+                *program_pointer = self.read_vector(memory_translation_table, IRQ_VECTOR) as usize;
+            }
             M6502InstructionSetSpecifier::Bvc => {
                 let value = match instruction.addressing_mode {
                     Some(AddressingMode::Relative(value)) => value,
@@ -418,7 +952,7 @@ impl ProcessorComponent for M6502 {
                 };
 
                 if !self.registers.flags.contains(FlagRegister::Overflow) {
-                    *program_pointer = program_pointer.wrapping_add_signed(value as isize);
+                    self.branch(program_pointer, value);
                 }
             }
             M6502InstructionSetSpecifier::Bvs => {
@@ -428,7 +962,7 @@ impl ProcessorComponent for M6502 {
                 };
 
                 if self.registers.flags.contains(FlagRegister::Overflow) {
-                    *program_pointer = program_pointer.wrapping_add_signed(value as isize);
+                    self.branch(program_pointer, value);
                 }
             }
             M6502InstructionSetSpecifier::Clc => {
@@ -443,32 +977,269 @@ impl ProcessorComponent for M6502 {
             M6502InstructionSetSpecifier::Clv => {
                 self.registers.flags.remove(FlagRegister::Overflow);
             }
-            M6502InstructionSetSpecifier::Cmp => todo!(),
-            M6502InstructionSetSpecifier::Cpx => todo!(),
-            M6502InstructionSetSpecifier::Cpy => todo!(),
-            M6502InstructionSetSpecifier::Dcp => todo!(),
-            M6502InstructionSetSpecifier::Dec => todo!(),
-            M6502InstructionSetSpecifier::Dex => todo!(),
-            M6502InstructionSetSpecifier::Dey => todo!(),
-            M6502InstructionSetSpecifier::Eor => todo!(),
-            M6502InstructionSetSpecifier::Inc => todo!(),
-            M6502InstructionSetSpecifier::Inx => todo!(),
-            M6502InstructionSetSpecifier::Iny => todo!(),
-            M6502InstructionSetSpecifier::Isc => todo!(),
-            M6502InstructionSetSpecifier::Jam => todo!(),
-            M6502InstructionSetSpecifier::Jmp => todo!(),
-            M6502InstructionSetSpecifier::Jsr => todo!(),
-            M6502InstructionSetSpecifier::Las => todo!(),
-            M6502InstructionSetSpecifier::Lax => todo!(),
-            M6502InstructionSetSpecifier::Lda => todo!(),
-            M6502InstructionSetSpecifier::Ldx => todo!(),
-            M6502InstructionSetSpecifier::Ldy => todo!(),
-            M6502InstructionSetSpecifier::Lsr => todo!(),
-            M6502InstructionSetSpecifier::Nop => todo!(),
+            M6502InstructionSetSpecifier::Cmp => {
+                let value = load_m6502_addressing_modes!(
+                    instruction,
+                    self,
+                    memory_translation_table,
+                    [
+                        Immediate,
+                        Absolute,
+                        XIndexedAbsolute,
+                        YIndexedAbsolute,
+                        ZeroPage,
+                        XIndexedZeroPage,
+                        XIndexedZeroPageIndirect,
+                        ZeroPageIndirectYIndexed
+                    ]
+                );
+
+                self.compare(self.registers.accumulator, value);
+            }
+            M6502InstructionSetSpecifier::Cpx => {
+                let value = load_m6502_addressing_modes!(
+                    instruction,
+                    self,
+                    memory_translation_table,
+                    [Immediate, Absolute, ZeroPage]
+                );
+
+                self.compare(self.registers.index_registers[0], value);
+            }
+            M6502InstructionSetSpecifier::Cpy => {
+                let value = load_m6502_addressing_modes!(
+                    instruction,
+                    self,
+                    memory_translation_table,
+                    [Immediate, Absolute, ZeroPage]
+                );
+
+                self.compare(self.registers.index_registers[1], value);
+            }
+            M6502InstructionSetSpecifier::Dcp => {
+                let value = self.modify(
+                    instruction.addressing_mode,
+                    memory_translation_table,
+                    |_, value| value.wrapping_sub(1),
+                );
+
+                self.compare(self.registers.accumulator, value);
+            }
+            M6502InstructionSetSpecifier::Dec => {
+                self.modify(
+                    instruction.addressing_mode,
+                    memory_translation_table,
+                    |processor, value| {
+                        let result = value.wrapping_sub(1);
+                        processor.set_negative_and_zero(result);
+                        result
+                    },
+                );
+            }
+            M6502InstructionSetSpecifier::Dex => {
+                let result = self.registers.index_registers[0].wrapping_sub(1);
+                self.set_negative_and_zero(result);
+                self.registers.index_registers[0] = result;
+            }
+            M6502InstructionSetSpecifier::Dey => {
+                let result = self.registers.index_registers[1].wrapping_sub(1);
+                self.set_negative_and_zero(result);
+                self.registers.index_registers[1] = result;
+            }
+            M6502InstructionSetSpecifier::Eor => {
+                let value = load_m6502_addressing_modes!(
+                    instruction,
+                    self,
+                    memory_translation_table,
+                    [
+                        Immediate,
+                        Absolute,
+                        XIndexedAbsolute,
+                        YIndexedAbsolute,
+                        ZeroPage,
+                        XIndexedZeroPage,
+                        XIndexedZeroPageIndirect,
+                        ZeroPageIndirectYIndexed
+                    ]
+                );
+
+                let new_value = self.registers.accumulator ^ value;
+                self.set_negative_and_zero(new_value);
+                self.registers.accumulator = new_value;
+            }
+            M6502InstructionSetSpecifier::Inc => {
+                self.modify(
+                    instruction.addressing_mode,
+                    memory_translation_table,
+                    |processor, value| {
+                        let result = value.wrapping_add(1);
+                        processor.set_negative_and_zero(result);
+                        result
+                    },
+                );
+            }
+            M6502InstructionSetSpecifier::Inx => {
+                let result = self.registers.index_registers[0].wrapping_add(1);
+                self.set_negative_and_zero(result);
+                self.registers.index_registers[0] = result;
+            }
+            M6502InstructionSetSpecifier::Iny => {
+                let result = self.registers.index_registers[1].wrapping_add(1);
+                self.set_negative_and_zero(result);
+                self.registers.index_registers[1] = result;
+            }
+            M6502InstructionSetSpecifier::Isc => {
+                let value = self.modify(
+                    instruction.addressing_mode,
+                    memory_translation_table,
+                    |_, value| value.wrapping_add(1),
+                );
+
+                self.subtract_with_borrow(value);
+            }
+            M6502InstructionSetSpecifier::Jam => {
+                tracing::warn!("Processor jammed at 0x{:04x}", program_pointer);
+
+                self.jammed = true;
+            }
+            M6502InstructionSetSpecifier::Jmp => {
+                *program_pointer = match instruction.addressing_mode {
+                    Some(AddressingMode::Absolute(address)) => address,
+                    Some(addressing_mode @ AddressingMode::AbsoluteIndirect(_)) => {
+                        self.effective_address(addressing_mode, memory_translation_table)
+                    }
+                    _ => unreachable!(),
+                } as usize;
+            }
+            M6502InstructionSetSpecifier::Jsr => {
+                let address = match instruction.addressing_mode {
+                    Some(AddressingMode::Absolute(address)) => address,
+                    _ => unreachable!(),
+                };
+
+                // The pushed address is the last byte of this instruction, RTS makes up for it
+                self.push_address(
+                    memory_translation_table,
+                    (*program_pointer as u16).wrapping_sub(1),
+                );
+
+                *program_pointer = address as usize;
+            }
+            M6502InstructionSetSpecifier::Las => {
+                let value = load_m6502_addressing_modes!(
+                    instruction,
+                    self,
+                    memory_translation_table,
+                    [YIndexedAbsolute]
+                );
+
+                let new_value = value & self.registers.stack_pointer;
+                self.set_negative_and_zero(new_value);
+                self.registers.accumulator = new_value;
+                self.registers.index_registers[0] = new_value;
+                self.registers.stack_pointer = new_value;
+            }
+            M6502InstructionSetSpecifier::Lax => {
+                let new_value = match instruction.addressing_mode {
+                    // Unstable like XAA
+                    Some(AddressingMode::Immediate(value)) => {
+                        (self.registers.accumulator | UNSTABLE_MAGIC) & value
+                    }
+                    _ => load_m6502_addressing_modes!(
+                        instruction,
+                        self,
+                        memory_translation_table,
+                        [
+                            Absolute,
+                            YIndexedAbsolute,
+                            ZeroPage,
+                            YIndexedZeroPage,
+                            XIndexedZeroPageIndirect,
+                            ZeroPageIndirectYIndexed
+                        ]
+                    ),
+                };
+
+                self.set_negative_and_zero(new_value);
+                self.registers.accumulator = new_value;
+                self.registers.index_registers[0] = new_value;
+            }
+            M6502InstructionSetSpecifier::Lda => {
+                let value = load_m6502_addressing_modes!(
+                    instruction,
+                    self,
+                    memory_translation_table,
+                    [
+                        Immediate,
+                        Absolute,
+                        XIndexedAbsolute,
+                        YIndexedAbsolute,
+                        ZeroPage,
+                        XIndexedZeroPage,
+                        XIndexedZeroPageIndirect,
+                        ZeroPageIndirectYIndexed
+                    ]
+                );
+
+                self.set_negative_and_zero(value);
+                self.registers.accumulator = value;
+            }
+            M6502InstructionSetSpecifier::Ldx => {
+                let value = load_m6502_addressing_modes!(
+                    instruction,
+                    self,
+                    memory_translation_table,
+                    [
+                        Immediate,
+                        Absolute,
+                        YIndexedAbsolute,
+                        ZeroPage,
+                        YIndexedZeroPage
+                    ]
+                );
+
+                self.set_negative_and_zero(value);
+                self.registers.index_registers[0] = value;
+            }
+            M6502InstructionSetSpecifier::Ldy => {
+                let value = load_m6502_addressing_modes!(
+                    instruction,
+                    self,
+                    memory_translation_table,
+                    [
+                        Immediate,
+                        Absolute,
+                        XIndexedAbsolute,
+                        ZeroPage,
+                        XIndexedZeroPage
+                    ]
+                );
+
+                self.set_negative_and_zero(value);
+                self.registers.index_registers[1] = value;
+            }
+            M6502InstructionSetSpecifier::Lsr => {
+                self.modify(
+                    instruction.addressing_mode,
+                    memory_translation_table,
+                    Self::shift_right,
+                );
+            }
+            M6502InstructionSetSpecifier::Nop => {
+                // The undocumented ones still do the read
+                match instruction.addressing_mode {
+                    None | Some(AddressingMode::Immediate(_)) => {}
+                    Some(addressing_mode) => {
+                        let address =
+                            self.effective_address(addressing_mode, memory_translation_table);
+                        self.read_byte(memory_translation_table, address);
+                    }
+                }
+            }
             M6502InstructionSetSpecifier::Ora => {
                 let value = load_m6502_addressing_modes!(
                     instruction,
-                    self.registers,
+                    self,
                     memory_translation_table,
                     [
                         Immediate,
@@ -493,67 +1264,104 @@ impl ProcessorComponent for M6502 {
                 self.registers.accumulator = new_value;
             }
             M6502InstructionSetSpecifier::Pha => {
-                memory_translation_table
-                    .write(
-                        self.registers.stack_pointer as usize,
-                        std::array::from_ref(&self.registers.accumulator),
-                    )
-                    .unwrap();
-
-                self.registers.stack_pointer = self.registers.stack_pointer.wrapping_sub(1);
+                self.push(memory_translation_table, self.registers.accumulator);
             }
             M6502InstructionSetSpecifier::Php => {
                 // https://www.nesdev.org/wiki/Status_flags
 
-                let mut flags = self.registers.flags;
-                flags.insert(FlagRegister::__Unused);
+                let flags = self.registers.flags | FlagRegister::Break | FlagRegister::__Unused;
+                self.push(memory_translation_table, flags.bits());
+            }
+            M6502InstructionSetSpecifier::Pla => {
+                let value = self.pop(memory_translation_table);
 
-                memory_translation_table
-                    .write(
-                        self.registers.stack_pointer as usize,
-                        std::array::from_ref(&flags.bits()),
-                    )
-                    .unwrap();
+                self.set_negative_and_zero(value);
+                self.registers.accumulator = value;
+            }
+            M6502InstructionSetSpecifier::Plp => {
+                self.pop_flags(memory_translation_table);
+            }
+            M6502InstructionSetSpecifier::Rla => {
+                let value = self.modify(
+                    instruction.addressing_mode,
+                    memory_translation_table,
+                    Self::rotate_left,
+                );
 
-                self.registers.stack_pointer = self.registers.stack_pointer.wrapping_sub(1);
+                let new_value = self.registers.accumulator & value;
+                self.set_negative_and_zero(new_value);
+                self.registers.accumulator = new_value;
             }
-            M6502InstructionSetSpecifier::Pla => {
-                self.registers.stack_pointer = self.registers.stack_pointer.wrapping_add(1);
+            M6502InstructionSetSpecifier::Rol => {
+                self.modify(
+                    instruction.addressing_mode,
+                    memory_translation_table,
+                    Self::rotate_left,
+                );
+            }
+            M6502InstructionSetSpecifier::Ror => {
+                self.modify(
+                    instruction.addressing_mode,
+                    memory_translation_table,
+                    Self::rotate_right,
+                );
+            }
+            M6502InstructionSetSpecifier::Rra => {
+                let value = self.modify(
+                    instruction.addressing_mode,
+                    memory_translation_table,
+                    Self::rotate_right,
+                );
 
-                let mut value = 0;
+                self.add_with_carry(value);
+            }
+            M6502InstructionSetSpecifier::Rti => {
+                self.pop_flags(memory_translation_table);
+                *program_pointer = self.pop_address(memory_translation_table) as usize;
+            }
+            M6502InstructionSetSpecifier::Rts => {
+                *program_pointer =
+                    self.pop_address(memory_translation_table).wrapping_add(1) as usize;
+            }
+            M6502InstructionSetSpecifier::Sax => {
+                self.store(
+                    instruction.addressing_mode,
+                    memory_translation_table,
+                    self.registers.accumulator & self.registers.index_registers[0],
+                );
+            }
+            M6502InstructionSetSpecifier::Sbc => {
+                let value = load_m6502_addressing_modes!(
+                    instruction,
+                    self,
+                    memory_translation_table,
+                    [
+                        Immediate,
+                        Absolute,
+                        XIndexedAbsolute,
+                        YIndexedAbsolute,
+                        ZeroPage,
+                        XIndexedZeroPage,
+                        XIndexedZeroPageIndirect,
+                        ZeroPageIndirectYIndexed
+                    ]
+                );
 
-                memory_translation_table
-                    .read(
-                        self.registers.stack_pointer as usize,
-                        std::array::from_mut(&mut value),
-                    )
-                    .unwrap();
+                self.subtract_with_borrow(value);
+            }
+            M6502InstructionSetSpecifier::Sbx => {
+                let value = load_m6502_addressing_modes!(
+                    instruction,
+                    self,
+                    memory_translation_table,
+                    [Immediate]
+                );
 
-                self.registers.accumulator = value;
+                // Like CMP, so no borrow and decimal mode is ignored
+                let anded = self.registers.accumulator & self.registers.index_registers[0];
+                self.compare(anded, value);
+                self.registers.index_registers[0] = anded.wrapping_sub(value);
             }
-            M6502InstructionSetSpecifier::Plp => {
-                self.registers.stack_pointer = self.registers.stack_pointer.wrapping_add(1);
-
-                let mut value = 0;
-
-                memory_translation_table
-                    .read(
-                        self.registers.stack_pointer as usize,
-                        std::array::from_mut(&mut value),
-                    )
-                    .unwrap();
-
-                self.registers.flags = FlagRegister::from_bits(value).unwrap();
-            }
-            M6502InstructionSetSpecifier::Rla => todo!(),
-            M6502InstructionSetSpecifier::Rol => todo!(),
-            M6502InstructionSetSpecifier::Ror => todo!(),
-            M6502InstructionSetSpecifier::Rra => todo!(),
-            M6502InstructionSetSpecifier::Rti => todo!(),
-            M6502InstructionSetSpecifier::Rts => todo!(),
-            M6502InstructionSetSpecifier::Sax => todo!(),
-            M6502InstructionSetSpecifier::Sbc => todo!(),
-            M6502InstructionSetSpecifier::Sbx => todo!(),
             M6502InstructionSetSpecifier::Sec => {
                 self.registers.flags.insert(FlagRegister::Carry);
             }
@@ -563,31 +1371,141 @@ impl ProcessorComponent for M6502 {
             M6502InstructionSetSpecifier::Sei => {
                 self.registers.flags.insert(FlagRegister::InterruptDisable);
             }
-            M6502InstructionSetSpecifier::Sha => todo!(),
-            M6502InstructionSetSpecifier::Shs => todo!(),
-            M6502InstructionSetSpecifier::Shx => todo!(),
-            M6502InstructionSetSpecifier::Shy => todo!(),
-            M6502InstructionSetSpecifier::Slo => todo!(),
-            M6502InstructionSetSpecifier::Sre => todo!(),
-            M6502InstructionSetSpecifier::Sta => todo!(),
-            M6502InstructionSetSpecifier::Stx => todo!(),
-            M6502InstructionSetSpecifier::Sty => todo!(),
-            M6502InstructionSetSpecifier::Tax => todo!(),
-            M6502InstructionSetSpecifier::Tay => todo!(),
-            M6502InstructionSetSpecifier::Tsx => todo!(),
-            M6502InstructionSetSpecifier::Txa => todo!(),
-            M6502InstructionSetSpecifier::Txs => todo!(),
-            M6502InstructionSetSpecifier::Tya => todo!(),
+            M6502InstructionSetSpecifier::Sha => {
+                self.store_and_high_byte(
+                    instruction.addressing_mode,
+                    memory_translation_table,
+                    self.registers.accumulator & self.registers.index_registers[0],
+                );
+            }
+            M6502InstructionSetSpecifier::Shs => {
+                self.registers.stack_pointer =
+                    self.registers.accumulator & self.registers.index_registers[0];
+
+                self.store_and_high_byte(
+                    instruction.addressing_mode,
+                    memory_translation_table,
+                    self.registers.stack_pointer,
+                );
+            }
+            M6502InstructionSetSpecifier::Shx => {
+                self.store_and_high_byte(
+                    instruction.addressing_mode,
+                    memory_translation_table,
+                    self.registers.index_registers[0],
+                );
+            }
+            M6502InstructionSetSpecifier::Shy => {
+                self.store_and_high_byte(
+                    instruction.addressing_mode,
+                    memory_translation_table,
+                    self.registers.index_registers[1],
+                );
+            }
+            M6502InstructionSetSpecifier::Slo => {
+                let value = self.modify(
+                    instruction.addressing_mode,
+                    memory_translation_table,
+                    Self::shift_left,
+                );
+
+                let new_value = self.registers.accumulator | value;
+                self.set_negative_and_zero(new_value);
+                self.registers.accumulator = new_value;
+            }
+            M6502InstructionSetSpecifier::Sre => {
+                let value = self.modify(
+                    instruction.addressing_mode,
+                    memory_translation_table,
+                    Self::shift_right,
+                );
+
+                let new_value = self.registers.accumulator ^ value;
+                self.set_negative_and_zero(new_value);
+                self.registers.accumulator = new_value;
+            }
+            M6502InstructionSetSpecifier::Sta => {
+                self.store(
+                    instruction.addressing_mode,
+                    memory_translation_table,
+                    self.registers.accumulator,
+                );
+            }
+            M6502InstructionSetSpecifier::Stx => {
+                self.store(
+                    instruction.addressing_mode,
+                    memory_translation_table,
+                    self.registers.index_registers[0],
+                );
+            }
+            M6502InstructionSetSpecifier::Sty => {
+                self.store(
+                    instruction.addressing_mode,
+                    memory_translation_table,
+                    self.registers.index_registers[1],
+                );
+            }
+            M6502InstructionSetSpecifier::Tax => {
+                let value = self.registers.accumulator;
+                self.set_negative_and_zero(value);
+                self.registers.index_registers[0] = value;
+            }
+            M6502InstructionSetSpecifier::Tay => {
+                let value = self.registers.accumulator;
+                self.set_negative_and_zero(value);
+                self.registers.index_registers[1] = value;
+            }
+            M6502InstructionSetSpecifier::Tsx => {
+                let value = self.registers.stack_pointer;
+                self.set_negative_and_zero(value);
+                self.registers.index_registers[0] = value;
+            }
+            M6502InstructionSetSpecifier::Txa => {
+                let value = self.registers.index_registers[0];
+                self.set_negative_and_zero(value);
+                self.registers.accumulator = value;
+            }
+            M6502InstructionSetSpecifier::Txs => {
+                self.registers.stack_pointer = self.registers.index_registers[0];
+            }
+            M6502InstructionSetSpecifier::Tya => {
+                let value = self.registers.index_registers[1];
+                self.set_negative_and_zero(value);
+                self.registers.accumulator = value;
+            }
             M6502InstructionSetSpecifier::Xaa => {
                 let value = load_m6502_addressing_modes!(
                     instruction,
-                    self.registers,
+                    self,
                     memory_translation_table,
                     [Immediate]
                 );
+
+                let new_value = (self.registers.accumulator | UNSTABLE_MAGIC)
+                    & self.registers.index_registers[0]
+                    & value;
+                self.set_negative_and_zero(new_value);
+                self.registers.accumulator = new_value;
             }
         }
 
-        Ok(())
+        let mut cycles = timing::base_cycles(&instruction) + self.extra_cycles;
+        if self.page_crossed && timing::page_crossing_penalty(instruction.specifier) {
+            cycles += 1;
+        }
+        self.cycles_remaining += cycles;
+
+        self.bus_fault
+            .take()
+            .map_or(Ok(()), |error| Err(error.into()))
+    }
+
+    #[cfg(feature = "jit")]
+    fn run_recompiled(
+        &mut self,
+        program_pointer: &mut usize,
+        memory_translation_table: &MemoryTranslationTable,
+    ) -> Option<Result<(), InstructionInterpretingError>> {
+        self.run_block(program_pointer, memory_translation_table)
     }
 }
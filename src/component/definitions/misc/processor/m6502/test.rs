@@ -1,15 +1,23 @@
-use super::instruction::{AddressingMode, M6502InstructionSet, M6502InstructionSetSpecifier};
+use super::{
+    instruction::{AddressingMode, M6502InstructionSet, M6502InstructionSetSpecifier},
+    FlagRegister, M6502Config, M6502Kind, M6502,
+};
 use crate::{
     component::{
         definitions::misc::{
             plain_memory::{PlainMemory, PlainMemoryConfig, PlainMemoryInitialContents},
             processor::m6502::decode::decode_instruction,
         },
+        interrupt::InterruptLine,
         memory::MemoryTranslationTable,
+        processor::ProcessorComponent,
+        schedulable::SchedulableComponent,
         FromConfig,
     },
     rom::RomManager,
 };
+use enumflags2::BitFlags;
+use num::rational::Ratio;
 use std::{
     collections::HashMap,
     sync::{Arc, Mutex},
@@ -34,7 +42,7 @@ fn m6502_instruction_decode() {
             (
                 M6502InstructionSet {
                     specifier: M6502InstructionSetSpecifier::Ora,
-                    addressing_mode: Some(AddressingMode::Immediate(0xff)),
+                    addressing_mode: Some(AddressingMode::XIndexedZeroPageIndirect(0xff)),
                 },
                 2,
             ),
@@ -338,3 +346,690 @@ fn m6502_instruction_decode() {
         );
     }
 }
+
+#[test]
+fn m6502_instruction_decode_unmapped() {
+    let memory_translation_table = MemoryTranslationTable::default();
+
+    assert!(decode_instruction(0x0, &memory_translation_table).is_err());
+}
+
+fn m6502(kind: M6502Kind) -> (M6502, MemoryTranslationTable) {
+    let rom_manager = Arc::new(RomManager::default());
+    let mut memory_translation_table = MemoryTranslationTable::default();
+
+    let memory = PlainMemory::from_config(
+        rom_manager.clone(),
+        PlainMemoryConfig {
+            readable: true,
+            writable: true,
+            assigned_range: 0x0000..0x10000,
+            ..Default::default()
+        },
+    );
+    memory_translation_table.insert(0x0000..0x10000, Arc::new(Mutex::new(memory)));
+
+    let processor = M6502::from_config(
+        rom_manager,
+        M6502Config {
+            frequency: Ratio::new(1, 1),
+            kind,
+            irq: InterruptLine::default(),
+            nmi: InterruptLine::default(),
+            dynarec: None,
+        },
+    );
+
+    (processor, memory_translation_table)
+}
+
+/// Writes the instruction bytes at the program pointer and runs them through the decompiler and interpreter
+fn execute(
+    processor: &mut M6502,
+    memory_translation_table: &MemoryTranslationTable,
+    program_pointer: &mut usize,
+    bytes: &[u8],
+) {
+    for (offset, byte) in bytes.iter().enumerate() {
+        write(memory_translation_table, *program_pointer + offset, *byte);
+    }
+
+    let (instruction, size) = processor
+        .decompile(*program_pointer, memory_translation_table)
+        .unwrap();
+    assert_eq!(usize::from(size), bytes.len());
+    *program_pointer += usize::from(size);
+
+    processor
+        .interpret(program_pointer, instruction, memory_translation_table)
+        .unwrap();
+}
+
+fn read(memory_translation_table: &MemoryTranslationTable, address: usize) -> u8 {
+    let mut value = 0;
+    memory_translation_table
+        .read(address, std::array::from_mut(&mut value))
+        .unwrap();
+    value
+}
+
+fn write(memory_translation_table: &MemoryTranslationTable, address: usize, value: u8) {
+    memory_translation_table
+        .write(address, std::array::from_ref(&value))
+        .unwrap();
+}
+
+#[test]
+fn m6502_lda() {
+    let (mut processor, memory_translation_table) = m6502(M6502Kind::M6507);
+    let mut program_pointer = 0;
+
+    write(&memory_translation_table, 0x1234, 0x80);
+    processor.registers.index_registers[0] = 0x04;
+    execute(
+        &mut processor,
+        &memory_translation_table,
+        &mut program_pointer,
+        &[0xbd, 0x30, 0x12],
+    );
+    assert_eq!(processor.registers.accumulator, 0x80);
+    assert!(processor.registers.flags.contains(FlagRegister::Negative));
+    assert!(!processor.registers.flags.contains(FlagRegister::Zero));
+
+    execute(
+        &mut processor,
+        &memory_translation_table,
+        &mut program_pointer,
+        &[0xa9, 0x00],
+    );
+    assert!(processor.registers.flags.contains(FlagRegister::Zero));
+    assert!(!processor.registers.flags.contains(FlagRegister::Negative));
+}
+
+#[test]
+fn m6502_adc() {
+    let (mut processor, memory_translation_table) = m6502(M6502Kind::M6507);
+    let mut program_pointer = 0;
+
+    // 0x50 + 0x50 overflows into the sign bit
+    processor.registers.accumulator = 0x50;
+    execute(
+        &mut processor,
+        &memory_translation_table,
+        &mut program_pointer,
+        &[0x69, 0x50],
+    );
+    assert_eq!(processor.registers.accumulator, 0xa0);
+    assert!(processor.registers.flags.contains(FlagRegister::Overflow));
+    assert!(processor.registers.flags.contains(FlagRegister::Negative));
+    assert!(!processor.registers.flags.contains(FlagRegister::Carry));
+
+    // 0xff + 0x01 carries out
+    processor.registers.accumulator = 0xff;
+    execute(
+        &mut processor,
+        &memory_translation_table,
+        &mut program_pointer,
+        &[0x69, 0x01],
+    );
+    assert_eq!(processor.registers.accumulator, 0x00);
+    assert!(processor.registers.flags.contains(FlagRegister::Carry));
+    assert!(processor.registers.flags.contains(FlagRegister::Zero));
+    assert!(!processor.registers.flags.contains(FlagRegister::Overflow));
+}
+
+#[test]
+fn m6502_adc_decimal() {
+    let (mut processor, memory_translation_table) = m6502(M6502Kind::M6507);
+    let mut program_pointer = 0;
+
+    processor.registers.flags.insert(FlagRegister::Decimal);
+    processor.registers.accumulator = 0x58;
+    execute(
+        &mut processor,
+        &memory_translation_table,
+        &mut program_pointer,
+        &[0x69, 0x46],
+    );
+    assert_eq!(processor.registers.accumulator, 0x04);
+    assert!(processor.registers.flags.contains(FlagRegister::Carry));
+
+    // The NES chips ignore the flag
+    let (mut processor, memory_translation_table) = m6502(M6502Kind::R2A03);
+    processor.registers.flags.insert(FlagRegister::Decimal);
+    processor.registers.accumulator = 0x58;
+    execute(
+        &mut processor,
+        &memory_translation_table,
+        &mut program_pointer,
+        &[0x69, 0x46],
+    );
+    assert_eq!(processor.registers.accumulator, 0x9e);
+}
+
+#[test]
+fn m6502_sbc() {
+    let (mut processor, memory_translation_table) = m6502(M6502Kind::M6507);
+    let mut program_pointer = 0;
+
+    processor.registers.flags.insert(FlagRegister::Carry);
+    processor.registers.accumulator = 0x00;
+    execute(
+        &mut processor,
+        &memory_translation_table,
+        &mut program_pointer,
+        &[0xe9, 0x01],
+    );
+    assert_eq!(processor.registers.accumulator, 0xff);
+    assert!(!processor.registers.flags.contains(FlagRegister::Carry));
+    assert!(processor.registers.flags.contains(FlagRegister::Negative));
+
+    // 0x80 - 0x01 overflows out of the sign bit
+    processor.registers.flags.insert(FlagRegister::Carry);
+    processor.registers.accumulator = 0x80;
+    execute(
+        &mut processor,
+        &memory_translation_table,
+        &mut program_pointer,
+        &[0xe9, 0x01],
+    );
+    assert_eq!(processor.registers.accumulator, 0x7f);
+    assert!(processor.registers.flags.contains(FlagRegister::Overflow));
+    assert!(processor.registers.flags.contains(FlagRegister::Carry));
+}
+
+#[test]
+fn m6502_sbc_decimal() {
+    let (mut processor, memory_translation_table) = m6502(M6502Kind::M6507);
+    let mut program_pointer = 0;
+
+    processor.registers.flags.insert(FlagRegister::Decimal);
+    processor.registers.flags.insert(FlagRegister::Carry);
+    processor.registers.accumulator = 0x40;
+    execute(
+        &mut processor,
+        &memory_translation_table,
+        &mut program_pointer,
+        &[0xe9, 0x13],
+    );
+    assert_eq!(processor.registers.accumulator, 0x27);
+    assert!(processor.registers.flags.contains(FlagRegister::Carry));
+
+    processor.registers.accumulator = 0x00;
+    execute(
+        &mut processor,
+        &memory_translation_table,
+        &mut program_pointer,
+        &[0xe9, 0x01],
+    );
+    assert_eq!(processor.registers.accumulator, 0x99);
+    assert!(!processor.registers.flags.contains(FlagRegister::Carry));
+}
+
+#[test]
+fn m6502_compare() {
+    let (mut processor, memory_translation_table) = m6502(M6502Kind::M6507);
+    let mut program_pointer = 0;
+
+    processor.registers.index_registers[1] = 0x10;
+    execute(
+        &mut processor,
+        &memory_translation_table,
+        &mut program_pointer,
+        &[0xc0, 0x10],
+    );
+    assert!(processor.registers.flags.contains(FlagRegister::Carry));
+    assert!(processor.registers.flags.contains(FlagRegister::Zero));
+
+    processor.registers.accumulator = 0x10;
+    execute(
+        &mut processor,
+        &memory_translation_table,
+        &mut program_pointer,
+        &[0xc9, 0x20],
+    );
+    assert!(!processor.registers.flags.contains(FlagRegister::Carry));
+    assert!(processor.registers.flags.contains(FlagRegister::Negative));
+}
+
+#[test]
+fn m6502_shifts_and_rotates() {
+    let (mut processor, memory_translation_table) = m6502(M6502Kind::M6507);
+    let mut program_pointer = 0;
+
+    processor.registers.accumulator = 0x81;
+    execute(
+        &mut processor,
+        &memory_translation_table,
+        &mut program_pointer,
+        &[0x0a],
+    );
+    assert_eq!(processor.registers.accumulator, 0x02);
+    assert!(processor.registers.flags.contains(FlagRegister::Carry));
+
+    // The carry from ASL rotates back in
+    write(&memory_translation_table, 0x0010, 0x40);
+    execute(
+        &mut processor,
+        &memory_translation_table,
+        &mut program_pointer,
+        &[0x66, 0x10],
+    );
+    assert_eq!(read(&memory_translation_table, 0x0010), 0xa0);
+    assert!(!processor.registers.flags.contains(FlagRegister::Carry));
+    assert!(processor.registers.flags.contains(FlagRegister::Negative));
+
+    write(&memory_translation_table, 0x0010, 0x01);
+    execute(
+        &mut processor,
+        &memory_translation_table,
+        &mut program_pointer,
+        &[0x46, 0x10],
+    );
+    assert_eq!(read(&memory_translation_table, 0x0010), 0x00);
+    assert!(processor.registers.flags.contains(FlagRegister::Carry));
+    assert!(processor.registers.flags.contains(FlagRegister::Zero));
+
+    processor.registers.accumulator = 0x80;
+    execute(
+        &mut processor,
+        &memory_translation_table,
+        &mut program_pointer,
+        &[0x2a],
+    );
+    assert_eq!(processor.registers.accumulator, 0x01);
+    assert!(processor.registers.flags.contains(FlagRegister::Carry));
+
+    // Early chips shift left and leave the carry alone
+    let (mut processor, memory_translation_table) = m6502(M6502Kind::M6502 {
+        quirk_broken_ror: true,
+    });
+    processor.registers.accumulator = 0x81;
+    execute(
+        &mut processor,
+        &memory_translation_table,
+        &mut program_pointer,
+        &[0x6a],
+    );
+    assert_eq!(processor.registers.accumulator, 0x02);
+    assert!(!processor.registers.flags.contains(FlagRegister::Carry));
+}
+
+#[test]
+fn m6502_jsr_rts() {
+    let (mut processor, memory_translation_table) = m6502(M6502Kind::M6507);
+    let mut program_pointer = 0x1000;
+
+    execute(
+        &mut processor,
+        &memory_translation_table,
+        &mut program_pointer,
+        &[0x20, 0x00, 0x20],
+    );
+    assert_eq!(program_pointer, 0x2000);
+    assert_eq!(processor.registers.stack_pointer, 0xfd);
+    assert_eq!(read(&memory_translation_table, 0x01ff), 0x10);
+    assert_eq!(read(&memory_translation_table, 0x01fe), 0x02);
+
+    execute(
+        &mut processor,
+        &memory_translation_table,
+        &mut program_pointer,
+        &[0x60],
+    );
+    assert_eq!(program_pointer, 0x1003);
+    assert_eq!(processor.registers.stack_pointer, 0xff);
+}
+
+#[test]
+fn m6502_brk_rti() {
+    let (mut processor, memory_translation_table) = m6502(M6502Kind::M6507);
+    let mut program_pointer = 0x1000;
+
+    write(&memory_translation_table, 0xfffe, 0x00);
+    write(&memory_translation_table, 0xffff, 0x30);
+    processor.registers.flags.insert(FlagRegister::Carry);
+
+    execute(
+        &mut processor,
+        &memory_translation_table,
+        &mut program_pointer,
+        &[0x00],
+    );
+    assert_eq!(program_pointer, 0x3000);
+    assert!(processor
+        .registers
+        .flags
+        .contains(FlagRegister::InterruptDisable));
+    // Carry, unused and break
+    assert_eq!(read(&memory_translation_table, 0x01fd), 0b0011_0001);
+
+    execute(
+        &mut processor,
+        &memory_translation_table,
+        &mut program_pointer,
+        &[0x40],
+    );
+    // Skips the padding byte
+    assert_eq!(program_pointer, 0x1002);
+    assert_eq!(processor.registers.flags, FlagRegister::Carry);
+}
+
+#[test]
+fn m6502_jmp_indirect_page_wrap() {
+    let (mut processor, memory_translation_table) = m6502(M6502Kind::M6507);
+    let mut program_pointer = 0;
+
+    write(&memory_translation_table, 0x10ff, 0x34);
+    write(&memory_translation_table, 0x1000, 0x12);
+    write(&memory_translation_table, 0x1100, 0x56);
+
+    execute(
+        &mut processor,
+        &memory_translation_table,
+        &mut program_pointer,
+        &[0x6c, 0xff, 0x10],
+    );
+    assert_eq!(program_pointer, 0x1234);
+}
+
+#[test]
+fn m6502_branch_wraps_around() {
+    let (mut processor, memory_translation_table) = m6502(M6502Kind::M6507);
+    let mut program_pointer = 0xfff0;
+
+    processor.registers.flags.insert(FlagRegister::Zero);
+    processor.cycles_remaining = 0;
+    execute(
+        &mut processor,
+        &memory_translation_table,
+        &mut program_pointer,
+        &[0xf0, 0x20],
+    );
+    assert_eq!(program_pointer, 0x0012);
+    // Taken into another page
+    assert_eq!(processor.cycles_remaining, 4);
+
+    // And back again
+    processor.cycles_remaining = 0;
+    execute(
+        &mut processor,
+        &memory_translation_table,
+        &mut program_pointer,
+        &[0xf0, 0xe0],
+    );
+    assert_eq!(program_pointer, 0xfff4);
+    assert_eq!(processor.cycles_remaining, 4);
+}
+
+#[test]
+fn m6502_php_plp() {
+    let (mut processor, memory_translation_table) = m6502(M6502Kind::M6507);
+    let mut program_pointer = 0;
+
+    processor.registers.flags = FlagRegister::Negative | FlagRegister::Decimal;
+    execute(
+        &mut processor,
+        &memory_translation_table,
+        &mut program_pointer,
+        &[0x08],
+    );
+    assert_eq!(read(&memory_translation_table, 0x01ff), 0b1011_1000);
+
+    processor.registers.flags = BitFlags::empty();
+    execute(
+        &mut processor,
+        &memory_translation_table,
+        &mut program_pointer,
+        &[0x28],
+    );
+    assert_eq!(
+        processor.registers.flags,
+        FlagRegister::Negative | FlagRegister::Decimal
+    );
+}
+
+#[test]
+fn m6502_undocumented_read_modify_write() {
+    let (mut processor, memory_translation_table) = m6502(M6502Kind::M6507);
+    let mut program_pointer = 0;
+
+    // SLO
+    write(&memory_translation_table, 0x0020, 0x81);
+    processor.registers.accumulator = 0x01;
+    execute(
+        &mut processor,
+        &memory_translation_table,
+        &mut program_pointer,
+        &[0x07, 0x20],
+    );
+    assert_eq!(read(&memory_translation_table, 0x0020), 0x02);
+    assert_eq!(processor.registers.accumulator, 0x03);
+    assert!(processor.registers.flags.contains(FlagRegister::Carry));
+
+    // DCP
+    write(&memory_translation_table, 0x0020, 0x04);
+    execute(
+        &mut processor,
+        &memory_translation_table,
+        &mut program_pointer,
+        &[0xc7, 0x20],
+    );
+    assert_eq!(read(&memory_translation_table, 0x0020), 0x03);
+    assert!(processor.registers.flags.contains(FlagRegister::Zero));
+    assert!(processor.registers.flags.contains(FlagRegister::Carry));
+
+    // ISC
+    processor.registers.accumulator = 0x10;
+    execute(
+        &mut processor,
+        &memory_translation_table,
+        &mut program_pointer,
+        &[0xe7, 0x20],
+    );
+    assert_eq!(read(&memory_translation_table, 0x0020), 0x04);
+    assert_eq!(processor.registers.accumulator, 0x0c);
+}
+
+#[test]
+fn m6502_undocumented_loads_and_stores() {
+    let (mut processor, memory_translation_table) = m6502(M6502Kind::M6507);
+    let mut program_pointer = 0;
+
+    write(&memory_translation_table, 0x0030, 0x5a);
+    execute(
+        &mut processor,
+        &memory_translation_table,
+        &mut program_pointer,
+        &[0xa7, 0x30],
+    );
+    assert_eq!(processor.registers.accumulator, 0x5a);
+    assert_eq!(processor.registers.index_registers[0], 0x5a);
+
+    processor.registers.index_registers[0] = 0x0f;
+    execute(
+        &mut processor,
+        &memory_translation_table,
+        &mut program_pointer,
+        &[0x8f, 0x00, 0x04],
+    );
+    assert_eq!(read(&memory_translation_table, 0x0400), 0x0a);
+
+    processor.registers.accumulator = 0xff;
+    processor.registers.index_registers[0] = 0x10;
+    execute(
+        &mut processor,
+        &memory_translation_table,
+        &mut program_pointer,
+        &[0xcb, 0x01],
+    );
+    assert_eq!(processor.registers.index_registers[0], 0x0f);
+    assert!(processor.registers.flags.contains(FlagRegister::Carry));
+}
+
+#[test]
+fn m6502_arr() {
+    let (mut processor, memory_translation_table) = m6502(M6502Kind::M6507);
+    let mut program_pointer = 0;
+
+    processor.registers.flags.insert(FlagRegister::Carry);
+    processor.registers.accumulator = 0xff;
+    execute(
+        &mut processor,
+        &memory_translation_table,
+        &mut program_pointer,
+        &[0x6b, 0xc0],
+    );
+    assert_eq!(processor.registers.accumulator, 0xe0);
+    assert!(processor.registers.flags.contains(FlagRegister::Carry));
+    assert!(!processor.registers.flags.contains(FlagRegister::Overflow));
+    assert!(processor.registers.flags.contains(FlagRegister::Negative));
+}
+
+#[test]
+fn m6502_sha_page_cross() {
+    let (mut processor, memory_translation_table) = m6502(M6502Kind::M6507);
+    let mut program_pointer = 0;
+
+    processor.registers.accumulator = 0xff;
+    processor.registers.index_registers = [0xff, 0x00];
+    execute(
+        &mut processor,
+        &memory_translation_table,
+        &mut program_pointer,
+        &[0x9e, 0x00, 0x12],
+    );
+    // High byte plus one
+    assert_eq!(read(&memory_translation_table, 0x1200), 0x13);
+
+    processor.registers.accumulator = 0x0f;
+    processor.registers.index_registers = [0xff, 0x01];
+    execute(
+        &mut processor,
+        &memory_translation_table,
+        &mut program_pointer,
+        &[0x9f, 0xff, 0x20],
+    );
+    // The page cross replaces the high byte of the address with the stored value
+    assert_eq!(read(&memory_translation_table, 0x2100), 0x00);
+    assert_eq!(read(&memory_translation_table, 0x0100), 0x01);
+}
+
+#[test]
+fn m6502_jam() {
+    let (mut processor, memory_translation_table) = m6502(M6502Kind::M6507);
+    let mut program_pointer = 0;
+
+    assert!(processor.should_execution_occur());
+    execute(
+        &mut processor,
+        &memory_translation_table,
+        &mut program_pointer,
+        &[0x02],
+    );
+    assert!(!processor.should_execution_occur());
+}
+
+#[test]
+fn m6502_interrupts() {
+    let (mut processor, memory_translation_table) = m6502(M6502Kind::M6507);
+    let mut program_pointer = 0;
+
+    for (vector, address) in [(0xfffa, 0x1000u16), (0xfffc, 0x2000), (0xfffe, 0x3000)] {
+        for (offset, byte) in address.to_le_bytes().into_iter().enumerate() {
+            write(&memory_translation_table, vector + offset, byte);
+        }
+    }
+
+    // Starts out at the reset vector with interrupts disabled
+    processor.service_interrupts(&mut program_pointer, &memory_translation_table);
+    assert_eq!(program_pointer, 0x2000);
+    assert_eq!(processor.registers.stack_pointer, 0xfc);
+
+    // Masked until interrupts are enabled
+    processor.config.irq.raise();
+    processor.service_interrupts(&mut program_pointer, &memory_translation_table);
+    assert_eq!(program_pointer, 0x2000);
+
+    processor
+        .registers
+        .flags
+        .remove(FlagRegister::InterruptDisable);
+    processor.config.nmi.raise();
+    processor.service_interrupts(&mut program_pointer, &memory_translation_table);
+    // NMI wins and pushes the flags without the break flag
+    assert_eq!(program_pointer, 0x1000);
+    assert_eq!(read(&memory_translation_table, 0x01fa), 0b0010_0000);
+
+    // The NMI was consumed and the IRQ is masked again by the NMI
+    processor.service_interrupts(&mut program_pointer, &memory_translation_table);
+    assert_eq!(program_pointer, 0x1000);
+
+    execute(
+        &mut processor,
+        &memory_translation_table,
+        &mut program_pointer,
+        &[0x40],
+    );
+    assert_eq!(program_pointer, 0x2000);
+
+    // Level triggered, so the IRQ is still there
+    processor.service_interrupts(&mut program_pointer, &memory_translation_table);
+    assert_eq!(program_pointer, 0x3000);
+}
+
+#[test]
+fn m6502_cycle_timing() {
+    let (mut processor, memory_translation_table) = m6502(M6502Kind::M6507);
+    let mut program_pointer = 0x0210;
+
+    let cycles = |processor: &mut M6502, program_pointer: &mut usize, bytes: &[u8]| {
+        processor.cycles_remaining = 0;
+        execute(processor, &memory_translation_table, program_pointer, bytes);
+        processor.cycles_remaining
+    };
+
+    processor.registers.index_registers = [0x10, 0x00];
+    assert_eq!(
+        cycles(&mut processor, &mut program_pointer, &[0xbd, 0x00, 0x12]),
+        4
+    );
+    // Reads pay for the page crossing
+    assert_eq!(
+        cycles(&mut processor, &mut program_pointer, &[0xbd, 0xf8, 0x12]),
+        5
+    );
+    // Writes always pay for it
+    assert_eq!(
+        cycles(&mut processor, &mut program_pointer, &[0x9d, 0x00, 0x12]),
+        5
+    );
+    assert_eq!(
+        cycles(&mut processor, &mut program_pointer, &[0xfe, 0x00, 0x12]),
+        7
+    );
+
+    // Not taken, taken, and taken into another page
+    processor.registers.flags.insert(FlagRegister::Zero);
+    assert_eq!(
+        cycles(&mut processor, &mut program_pointer, &[0xd0, 0x10]),
+        2
+    );
+    assert_eq!(
+        cycles(&mut processor, &mut program_pointer, &[0xf0, 0x10]),
+        3
+    );
+    assert_eq!(program_pointer, 0x0230);
+    assert_eq!(
+        cycles(&mut processor, &mut program_pointer, &[0xf0, 0xc0]),
+        4
+    );
+    assert_eq!(program_pointer, 0x01f2);
+
+    // The processor waits out the instruction before starting another
+    assert!(!processor.should_execution_occur());
+    for _ in 0..4 {
+        processor.tick(&memory_translation_table);
+    }
+    assert!(processor.should_execution_occur());
+}
@@ -0,0 +1,89 @@
+use super::instruction::{AddressingMode, M6502InstructionSet, M6502InstructionSetSpecifier};
+
+// https://www.nesdev.org/wiki/6502_cycle_times
+
+/// NMI, IRQ and reset all go through the same seven cycle sequence as BRK
+pub const INTERRUPT_CYCLES: u32 = 7;
+
+/// How an instruction touches memory, which decides most of what it costs
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum AccessKind {
+    Read,
+    Write,
+    ReadModifyWrite,
+}
+
+fn access_kind(specifier: M6502InstructionSetSpecifier) -> AccessKind {
+    match specifier {
+        M6502InstructionSetSpecifier::Sta
+        | M6502InstructionSetSpecifier::Stx
+        | M6502InstructionSetSpecifier::Sty
+        | M6502InstructionSetSpecifier::Sax
+        | M6502InstructionSetSpecifier::Sha
+        | M6502InstructionSetSpecifier::Shs
+        | M6502InstructionSetSpecifier::Shx
+        | M6502InstructionSetSpecifier::Shy => AccessKind::Write,
+        M6502InstructionSetSpecifier::Asl
+        | M6502InstructionSetSpecifier::Lsr
+        | M6502InstructionSetSpecifier::Rol
+        | M6502InstructionSetSpecifier::Ror
+        | M6502InstructionSetSpecifier::Inc
+        | M6502InstructionSetSpecifier::Dec
+        | M6502InstructionSetSpecifier::Slo
+        | M6502InstructionSetSpecifier::Sre
+        | M6502InstructionSetSpecifier::Rla
+        | M6502InstructionSetSpecifier::Rra
+        | M6502InstructionSetSpecifier::Dcp
+        | M6502InstructionSetSpecifier::Isc => AccessKind::ReadModifyWrite,
+        _ => AccessKind::Read,
+    }
+}
+
+/// Cycles an instruction takes before page crossings, taken branches and wait states are added on
+pub fn base_cycles(instruction: &M6502InstructionSet) -> u32 {
+    match instruction.specifier {
+        M6502InstructionSetSpecifier::Brk => return INTERRUPT_CYCLES,
+        M6502InstructionSetSpecifier::Jsr
+        | M6502InstructionSetSpecifier::Rti
+        | M6502InstructionSetSpecifier::Rts => return 6,
+        M6502InstructionSetSpecifier::Pha | M6502InstructionSetSpecifier::Php => return 3,
+        M6502InstructionSetSpecifier::Pla | M6502InstructionSetSpecifier::Plp => return 4,
+        M6502InstructionSetSpecifier::Jmp => {
+            return match instruction.addressing_mode {
+                Some(AddressingMode::AbsoluteIndirect(_)) => 5,
+                _ => 3,
+            }
+        }
+        _ => {}
+    }
+
+    let Some(addressing_mode) = instruction.addressing_mode else {
+        return 2;
+    };
+
+    // Columns are read, write, and read modify write
+    let [read, write, read_modify_write] = match addressing_mode {
+        AddressingMode::Accumulator
+        | AddressingMode::Immediate(_)
+        | AddressingMode::Relative(_) => [2, 2, 2],
+        AddressingMode::ZeroPage(_) => [3, 3, 5],
+        AddressingMode::XIndexedZeroPage(_)
+        | AddressingMode::YIndexedZeroPage(_)
+        | AddressingMode::Absolute(_) => [4, 4, 6],
+        AddressingMode::XIndexedAbsolute(_) | AddressingMode::YIndexedAbsolute(_) => [4, 5, 7],
+        AddressingMode::AbsoluteIndirect(_) => [5, 5, 5],
+        AddressingMode::XIndexedZeroPageIndirect(_) => [6, 6, 8],
+        AddressingMode::ZeroPageIndirectYIndexed(_) => [5, 6, 8],
+    };
+
+    match access_kind(instruction.specifier) {
+        AccessKind::Read => read,
+        AccessKind::Write => write,
+        AccessKind::ReadModifyWrite => read_modify_write,
+    }
+}
+
+/// Only reads get to skip the fixup cycle, writes and read modify writes always pay it in their base cost
+pub fn page_crossing_penalty(specifier: M6502InstructionSetSpecifier) -> bool {
+    access_kind(specifier) == AccessKind::Read
+}
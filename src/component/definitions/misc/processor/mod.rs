@@ -1,2 +1,7 @@
+// No machine runs these yet
+#[allow(dead_code)]
 pub mod i8080;
+#[cfg(any(feature = "nes", feature = "atari2600"))]
 pub mod m6502;
+#[allow(dead_code)]
+pub mod r3000;
@@ -0,0 +1,247 @@
+use super::instruction::{
+    BranchCondition, ImmediateOperation, LoadWidth, MultiplyOperation, R3000InstructionSet,
+    Register, RegisterOperation, ShiftOperation, StoreWidth,
+};
+use crate::component::memory::MemoryTranslationTable;
+use bitvec::{field::BitField, prelude::Msb0, view::BitView};
+use std::ops::Range;
+
+const OPCODE: Range<usize> = 0..6;
+const RS: Range<usize> = 6..11;
+const RT: Range<usize> = 11..16;
+const RD: Range<usize> = 16..21;
+const SHIFT_AMOUNT: Range<usize> = 21..26;
+const FUNCTION: Range<usize> = 26..32;
+const IMMEDIATE: Range<usize> = 16..32;
+const TARGET: Range<usize> = 6..32;
+/// Syscall and break have a code where the registers usually are
+const CODE: Range<usize> = 6..26;
+
+/// Reads the word at a physical address and decodes it
+pub fn decode_instruction(
+    cursor: usize,
+    memory_translation_table: &MemoryTranslationTable,
+) -> Result<(R3000InstructionSet, u8), Box<dyn std::error::Error>> {
+    let mut instruction = [0; 4];
+    memory_translation_table.read(cursor, &mut instruction)?;
+
+    Ok((decode_word(u32::from_le_bytes(instruction)), 4))
+}
+
+/// Every word decodes to something, anything unknown becomes [R3000InstructionSet::Illegal]
+pub fn decode_word(word: u32) -> R3000InstructionSet {
+    let bits = word.view_bits::<Msb0>();
+
+    let opcode = bits[OPCODE].load::<u8>();
+    let rs = Register(bits[RS].load::<u8>());
+    let rt = Register(bits[RT].load::<u8>());
+    let rd = Register(bits[RD].load::<u8>());
+    let shift_amount = bits[SHIFT_AMOUNT].load::<u8>();
+    let function = bits[FUNCTION].load::<u8>();
+    let immediate = bits[IMMEDIATE].load::<u16>();
+    let offset = immediate as i16;
+
+    let register = |operation| R3000InstructionSet::Register {
+        operation,
+        rd,
+        rs,
+        rt,
+    };
+    let immediate_operation = |operation| R3000InstructionSet::Immediate {
+        operation,
+        rt,
+        rs,
+        immediate,
+    };
+    let load = |width| R3000InstructionSet::Load {
+        width,
+        rt,
+        base: rs,
+        offset,
+    };
+    let store = |width| R3000InstructionSet::Store {
+        width,
+        rt,
+        base: rs,
+        offset,
+    };
+    let branch = |condition| R3000InstructionSet::Branch {
+        condition,
+        rs,
+        offset,
+    };
+
+    match opcode {
+        0x00 => match function {
+            0x00 => R3000InstructionSet::Shift {
+                operation: ShiftOperation::LeftLogical,
+                rd,
+                rt,
+                amount: shift_amount,
+            },
+            0x02 => R3000InstructionSet::Shift {
+                operation: ShiftOperation::RightLogical,
+                rd,
+                rt,
+                amount: shift_amount,
+            },
+            0x03 => R3000InstructionSet::Shift {
+                operation: ShiftOperation::RightArithmetic,
+                rd,
+                rt,
+                amount: shift_amount,
+            },
+            0x04 => R3000InstructionSet::ShiftVariable {
+                operation: ShiftOperation::LeftLogical,
+                rd,
+                rt,
+                rs,
+            },
+            0x06 => R3000InstructionSet::ShiftVariable {
+                operation: ShiftOperation::RightLogical,
+                rd,
+                rt,
+                rs,
+            },
+            0x07 => R3000InstructionSet::ShiftVariable {
+                operation: ShiftOperation::RightArithmetic,
+                rd,
+                rt,
+                rs,
+            },
+            0x08 => R3000InstructionSet::JumpRegister { rs, link: None },
+            0x09 => R3000InstructionSet::JumpRegister { rs, link: Some(rd) },
+            0x0c => R3000InstructionSet::Syscall {
+                code: bits[CODE].load::<u32>(),
+            },
+            0x0d => R3000InstructionSet::Break {
+                code: bits[CODE].load::<u32>(),
+            },
+            0x10 => R3000InstructionSet::MoveFromHi { rd },
+            0x11 => R3000InstructionSet::MoveToHi { rs },
+            0x12 => R3000InstructionSet::MoveFromLo { rd },
+            0x13 => R3000InstructionSet::MoveToLo { rs },
+            0x18 => R3000InstructionSet::Multiply {
+                operation: MultiplyOperation::Multiply,
+                rs,
+                rt,
+            },
+            0x19 => R3000InstructionSet::Multiply {
+                operation: MultiplyOperation::MultiplyUnsigned,
+                rs,
+                rt,
+            },
+            0x1a => R3000InstructionSet::Multiply {
+                operation: MultiplyOperation::Divide,
+                rs,
+                rt,
+            },
+            0x1b => R3000InstructionSet::Multiply {
+                operation: MultiplyOperation::DivideUnsigned,
+                rs,
+                rt,
+            },
+            0x20 => register(RegisterOperation::Add),
+            0x21 => register(RegisterOperation::AddUnsigned),
+            0x22 => register(RegisterOperation::Subtract),
+            0x23 => register(RegisterOperation::SubtractUnsigned),
+            0x24 => register(RegisterOperation::And),
+            0x25 => register(RegisterOperation::Or),
+            0x26 => register(RegisterOperation::Xor),
+            0x27 => register(RegisterOperation::Nor),
+            0x2a => register(RegisterOperation::SetLessThan),
+            0x2b => register(RegisterOperation::SetLessThanUnsigned),
+            _ => R3000InstructionSet::Illegal(word),
+        },
+        // The condition is picked by rt, anything that isn't a known one is decided by its low and high bits
+        0x01 => {
+            let link = rt.0 & 0x1e == 0x10;
+
+            if rt.0 & 0x01 == 0 {
+                branch(BranchCondition::LessThanZero { link })
+            } else {
+                branch(BranchCondition::GreaterThanOrEqualZero { link })
+            }
+        }
+        0x02 | 0x03 => R3000InstructionSet::Jump {
+            target: bits[TARGET].load::<u32>(),
+            link: opcode == 0x03,
+        },
+        0x04 => branch(BranchCondition::Equal { rt }),
+        0x05 => branch(BranchCondition::NotEqual { rt }),
+        0x06 => branch(BranchCondition::LessThanOrEqualZero),
+        0x07 => branch(BranchCondition::GreaterThanZero),
+        0x08 => immediate_operation(ImmediateOperation::Add),
+        0x09 => immediate_operation(ImmediateOperation::AddUnsigned),
+        0x0a => immediate_operation(ImmediateOperation::SetLessThan),
+        0x0b => immediate_operation(ImmediateOperation::SetLessThanUnsigned),
+        0x0c => immediate_operation(ImmediateOperation::And),
+        0x0d => immediate_operation(ImmediateOperation::Or),
+        0x0e => immediate_operation(ImmediateOperation::Xor),
+        0x0f => R3000InstructionSet::LoadUpperImmediate { rt, immediate },
+        0x10..=0x13 => {
+            let coprocessor = opcode & 0b11;
+
+            match rs.0 {
+                0x00 => R3000InstructionSet::MoveFromCoprocessor {
+                    coprocessor,
+                    rt,
+                    rd: rd.0,
+                },
+                0x02 => R3000InstructionSet::MoveFromCoprocessorControl {
+                    coprocessor,
+                    rt,
+                    rd: rd.0,
+                },
+                0x04 => R3000InstructionSet::MoveToCoprocessor {
+                    coprocessor,
+                    rt,
+                    rd: rd.0,
+                },
+                0x06 => R3000InstructionSet::MoveToCoprocessorControl {
+                    coprocessor,
+                    rt,
+                    rd: rd.0,
+                },
+                0x08 => R3000InstructionSet::BranchCoprocessor {
+                    coprocessor,
+                    on_true: rt.0 & 0x01 != 0,
+                    offset,
+                },
+                0x10..=0x1f if coprocessor == 0 && function == 0x10 => {
+                    R3000InstructionSet::ReturnFromException
+                }
+                0x10..=0x1f => R3000InstructionSet::CoprocessorCommand {
+                    coprocessor,
+                    command: word & 0x01ff_ffff,
+                },
+                _ => R3000InstructionSet::Illegal(word),
+            }
+        }
+        0x20 => load(LoadWidth::Byte),
+        0x21 => load(LoadWidth::HalfWord),
+        0x22 => load(LoadWidth::WordLeft),
+        0x23 => load(LoadWidth::Word),
+        0x24 => load(LoadWidth::ByteUnsigned),
+        0x25 => load(LoadWidth::HalfWordUnsigned),
+        0x26 => load(LoadWidth::WordRight),
+        0x28 => store(StoreWidth::Byte),
+        0x29 => store(StoreWidth::HalfWord),
+        0x2a => store(StoreWidth::WordLeft),
+        0x2b => store(StoreWidth::Word),
+        0x2e => store(StoreWidth::WordRight),
+        0x30..=0x33 => R3000InstructionSet::LoadCoprocessor {
+            coprocessor: opcode & 0b11,
+            rt: rt.0,
+            base: rs,
+            offset,
+        },
+        0x38..=0x3b => R3000InstructionSet::StoreCoprocessor {
+            coprocessor: opcode & 0b11,
+            rt: rt.0,
+            base: rs,
+            offset,
+        },
+        _ => R3000InstructionSet::Illegal(word),
+    }
+}
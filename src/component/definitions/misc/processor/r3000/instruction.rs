@@ -0,0 +1,234 @@
+use crate::component::processor::InstructionSet;
+#[cfg(feature = "frontend")]
+use crate::component::processor::InstructionTextRepresentation;
+#[cfg(feature = "frontend")]
+use std::borrow::Cow;
+use std::fmt::Debug;
+
+// https://psx-spx.consoledev.net/cpuspecifications/
+
+/// One of the 32 general purpose registers, $zero always reads as zero
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Register(pub u8);
+
+impl Debug for Register {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "${}", self.0)
+    }
+}
+
+/// The instructions that only differ by what they do with two registers and write to a third
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum RegisterOperation {
+    /// Traps on signed overflow
+    Add,
+    AddUnsigned,
+    /// Traps on signed overflow
+    Subtract,
+    SubtractUnsigned,
+    And,
+    Or,
+    Xor,
+    Nor,
+    SetLessThan,
+    SetLessThanUnsigned,
+}
+
+/// The instructions that only differ by what they do with a register and a immediate
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ImmediateOperation {
+    /// Traps on signed overflow
+    Add,
+    AddUnsigned,
+    SetLessThan,
+    /// The immediate is still sign extended, only the comparison is unsigned
+    SetLessThanUnsigned,
+    /// The logic operations zero extend their immediate
+    And,
+    Or,
+    Xor,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ShiftOperation {
+    LeftLogical,
+    RightLogical,
+    RightArithmetic,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum MultiplyOperation {
+    Multiply,
+    MultiplyUnsigned,
+    Divide,
+    DivideUnsigned,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum BranchCondition {
+    Equal { rt: Register },
+    NotEqual { rt: Register },
+    LessThanOrEqualZero,
+    GreaterThanZero,
+    LessThanZero { link: bool },
+    GreaterThanOrEqualZero { link: bool },
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum LoadWidth {
+    Byte,
+    ByteUnsigned,
+    HalfWord,
+    HalfWordUnsigned,
+    Word,
+    /// The unaligned halves, which merge into the register instead of replacing it
+    WordLeft,
+    WordRight,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum StoreWidth {
+    Byte,
+    HalfWord,
+    Word,
+    WordLeft,
+    WordRight,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum R3000InstructionSet {
+    Register {
+        operation: RegisterOperation,
+        rd: Register,
+        rs: Register,
+        rt: Register,
+    },
+    Immediate {
+        operation: ImmediateOperation,
+        rt: Register,
+        rs: Register,
+        immediate: u16,
+    },
+    LoadUpperImmediate {
+        rt: Register,
+        immediate: u16,
+    },
+    Shift {
+        operation: ShiftOperation,
+        rd: Register,
+        rt: Register,
+        amount: u8,
+    },
+    /// The amount comes from the bottom 5 bits of rs
+    ShiftVariable {
+        operation: ShiftOperation,
+        rd: Register,
+        rt: Register,
+        rs: Register,
+    },
+    Multiply {
+        operation: MultiplyOperation,
+        rs: Register,
+        rt: Register,
+    },
+    MoveFromHi {
+        rd: Register,
+    },
+    MoveToHi {
+        rs: Register,
+    },
+    MoveFromLo {
+        rd: Register,
+    },
+    MoveToLo {
+        rs: Register,
+    },
+    /// The target replaces the lower 28 bits of the address of the delay slot
+    Jump {
+        target: u32,
+        link: bool,
+    },
+    JumpRegister {
+        rs: Register,
+        link: Option<Register>,
+    },
+    /// The offset is in instructions and relative to the delay slot
+    Branch {
+        condition: BranchCondition,
+        rs: Register,
+        offset: i16,
+    },
+    Load {
+        width: LoadWidth,
+        rt: Register,
+        base: Register,
+        offset: i16,
+    },
+    Store {
+        width: StoreWidth,
+        rt: Register,
+        base: Register,
+        offset: i16,
+    },
+    Syscall {
+        code: u32,
+    },
+    Break {
+        code: u32,
+    },
+    MoveFromCoprocessor {
+        coprocessor: u8,
+        rt: Register,
+        rd: u8,
+    },
+    MoveFromCoprocessorControl {
+        coprocessor: u8,
+        rt: Register,
+        rd: u8,
+    },
+    MoveToCoprocessor {
+        coprocessor: u8,
+        rt: Register,
+        rd: u8,
+    },
+    MoveToCoprocessorControl {
+        coprocessor: u8,
+        rt: Register,
+        rd: u8,
+    },
+    BranchCoprocessor {
+        coprocessor: u8,
+        on_true: bool,
+        offset: i16,
+    },
+    /// A command for the coprocessor itself, like the GTE operations
+    CoprocessorCommand {
+        coprocessor: u8,
+        command: u32,
+    },
+    /// Pops the mode stack in COP0 status after a exception
+    ReturnFromException,
+    LoadCoprocessor {
+        coprocessor: u8,
+        rt: u8,
+        base: Register,
+        offset: i16,
+    },
+    StoreCoprocessor {
+        coprocessor: u8,
+        rt: u8,
+        base: Register,
+        offset: i16,
+    },
+    /// Raises a reserved instruction exception
+    Illegal(u32),
+}
+
+impl InstructionSet for R3000InstructionSet {
+    #[cfg(feature = "frontend")]
+    fn to_text_representation(&self) -> InstructionTextRepresentation {
+        InstructionTextRepresentation {
+            instruction_mnemonic: Cow::Owned(format!("{:?}", self)),
+        }
+    }
+}
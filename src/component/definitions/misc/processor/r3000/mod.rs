@@ -0,0 +1,859 @@
+use crate::{
+    component::{
+        interrupt::InterruptLine,
+        memory::MemoryTranslationTable,
+        processor::{
+            InstructionDecompilingError, InstructionInterpretingError, ProcessorComponent,
+            ProcessorRegister,
+        },
+        schedulable::SchedulableComponent,
+        snapshot::SnapshotableComponent,
+        Component, ComponentVersion, FromConfig, SemanticVersion,
+    },
+    rom::RomManager,
+};
+use decode::decode_instruction;
+use instruction::{
+    BranchCondition, ImmediateOperation, LoadWidth, MultiplyOperation, R3000InstructionSet,
+    Register, RegisterOperation, ShiftOperation, StoreWidth,
+};
+use num::rational::Ratio;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+pub mod decode;
+pub mod instruction;
+#[cfg(test)]
+pub mod test;
+
+/// Where the processor starts, the BIOS through KSEG1
+const RESET_VECTOR: u32 = 0xbfc0_0000;
+/// Where exceptions go while SR.BEV is set
+const BOOT_EXCEPTION_VECTOR: u32 = 0xbfc0_0180;
+/// Where exceptions go normally, through KSEG0
+const EXCEPTION_VECTOR: u32 = 0x8000_0080;
+/// Anything at or above this is KSEG2, which only has the cache control register on the PlayStation
+const KSEG2_START: u32 = 0xc000_0000;
+
+/// COP0 registers the interpreter cares about
+const COP0_BAD_VIRTUAL_ADDRESS: usize = 8;
+const COP0_STATUS: usize = 12;
+const COP0_CAUSE: usize = 13;
+const COP0_EXCEPTION_PROGRAM_COUNTER: usize = 14;
+const COP0_PROCESSOR_ID: usize = 15;
+
+/// Current interrupt enable, the bottom of the mode stack
+const STATUS_INTERRUPT_ENABLE: u32 = 1 << 0;
+/// Stores go to the data cache instead of memory, the BIOS uses it to flush the caches
+const STATUS_ISOLATE_CACHE: u32 = 1 << 16;
+/// Exceptions go to the BIOS instead of RAM
+const STATUS_BOOT_EXCEPTION_VECTORS: u32 = 1 << 22;
+/// The interrupt mask, paired with the pending bits in cause
+const INTERRUPT_MASK: u32 = 0xff00;
+/// Hardware interrupt 0, which is where the PlayStation interrupt controller is wired
+const CAUSE_HARDWARE_INTERRUPT: u32 = 1 << 10;
+/// Only the software interrupt bits in cause are writable
+const CAUSE_SOFTWARE_INTERRUPTS: u32 = 0x0300;
+/// The exception happened in a branch delay slot
+const CAUSE_BRANCH_DELAY: u32 = 1 << 31;
+
+#[repr(u32)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Exception {
+    Interrupt = 0x00,
+    AddressErrorLoad = 0x04,
+    AddressErrorStore = 0x05,
+    BusErrorData = 0x07,
+    Syscall = 0x08,
+    Break = 0x09,
+    ReservedInstruction = 0x0a,
+    CoprocessorUnusable = 0x0b,
+    Overflow = 0x0c,
+}
+
+/// KSEG0 and KSEG1 are windows onto the bottom 512MiB, and without a TLB KUSEG is too
+fn physical_address(address: u32) -> usize {
+    match address >> 29 {
+        0b100 | 0b101 => (address & 0x1fff_ffff) as usize,
+        _ => address as usize,
+    }
+}
+
+fn sign_extend(immediate: u16) -> u32 {
+    immediate as i16 as i32 as u32
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct R3000Registers {
+    /// What instructions read
+    general_purpose: [u32; 32],
+    /// What instructions write, copied back after each instruction so a load can land a instruction late
+    general_purpose_out: [u32; 32],
+    hi: u32,
+    lo: u32,
+    cop0: [u32; 32],
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct R3000Snapshot {
+    pub registers: R3000Registers,
+    /// Register number and value
+    pub pending_load: Option<(u8, u32)>,
+    pub pending_branch: Option<u32>,
+    pub reset_pending: bool,
+    pub irq: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct R3000Config {
+    pub frequency: Ratio<u32>,
+    /// Level triggered, the PlayStation interrupt controller drives this
+    #[serde(skip)]
+    pub irq: InterruptLine,
+}
+
+impl R3000Config {
+    pub fn playstation() -> Self {
+        Self {
+            frequency: Ratio::new(33868800, 1),
+            irq: InterruptLine::default(),
+        }
+    }
+}
+
+pub struct R3000 {
+    config: R3000Config,
+    registers: R3000Registers,
+    /// A load that lands after the next instruction
+    pending_load: Option<(Register, u32)>,
+    /// A branch that happens after the delay slot
+    pending_branch: Option<u32>,
+    reset_pending: bool,
+}
+
+impl R3000 {
+    pub fn assert_irq(&self) {
+        self.config.irq.raise();
+    }
+
+    pub fn deassert_irq(&self) {
+        self.config.irq.lower();
+    }
+
+    fn register(&self, register: Register) -> u32 {
+        self.registers.general_purpose[register.0 as usize]
+    }
+
+    fn set_register(&mut self, register: Register, value: u32) {
+        self.registers.general_purpose_out[register.0 as usize] = value;
+        self.registers.general_purpose_out[0] = 0;
+    }
+
+    fn load_address(&self, base: Register, offset: i16) -> u32 {
+        self.register(base).wrapping_add(offset as i32 as u32)
+    }
+
+    fn read(
+        &mut self,
+        memory_translation_table: &MemoryTranslationTable,
+        address: u32,
+        buffer: &mut [u8],
+    ) -> Result<(), Exception> {
+        if address >= KSEG2_START {
+            tracing::trace!("Read from cache control at {:#010x}", address);
+            buffer.fill(0);
+
+            return Ok(());
+        }
+
+        memory_translation_table
+            .read(physical_address(address), buffer)
+            .map(|_| ())
+            .map_err(|_| Exception::BusErrorData)
+    }
+
+    fn write(
+        &mut self,
+        memory_translation_table: &MemoryTranslationTable,
+        address: u32,
+        buffer: &[u8],
+    ) -> Result<(), Exception> {
+        if self.registers.cop0[COP0_STATUS] & STATUS_ISOLATE_CACHE != 0 {
+            return Ok(());
+        }
+
+        if address >= KSEG2_START {
+            tracing::trace!("Wrote to cache control at {:#010x}", address);
+
+            return Ok(());
+        }
+
+        memory_translation_table
+            .write(physical_address(address), buffer)
+            .map(|_| ())
+            .map_err(|_| Exception::BusErrorData)
+    }
+
+    fn read_word(
+        &mut self,
+        memory_translation_table: &MemoryTranslationTable,
+        address: u32,
+    ) -> Result<u32, Exception> {
+        let mut buffer = [0; 4];
+        self.read(memory_translation_table, address, &mut buffer)?;
+
+        Ok(u32::from_le_bytes(buffer))
+    }
+
+    fn load(
+        &mut self,
+        memory_translation_table: &MemoryTranslationTable,
+        width: LoadWidth,
+        rt: Register,
+        address: u32,
+    ) -> Result<(), Exception> {
+        let alignment = match width {
+            LoadWidth::HalfWord | LoadWidth::HalfWordUnsigned => 2,
+            LoadWidth::Word => 4,
+            _ => 1,
+        };
+
+        if !address.is_multiple_of(alignment) {
+            self.registers.cop0[COP0_BAD_VIRTUAL_ADDRESS] = address;
+            return Err(Exception::AddressErrorLoad);
+        }
+
+        let value = match width {
+            LoadWidth::Byte | LoadWidth::ByteUnsigned => {
+                let mut value = 0;
+                self.read(
+                    memory_translation_table,
+                    address,
+                    std::array::from_mut(&mut value),
+                )?;
+
+                if width == LoadWidth::Byte {
+                    value as i8 as i32 as u32
+                } else {
+                    value as u32
+                }
+            }
+            LoadWidth::HalfWord | LoadWidth::HalfWordUnsigned => {
+                let mut buffer = [0; 2];
+                self.read(memory_translation_table, address, &mut buffer)?;
+                let value = u16::from_le_bytes(buffer);
+
+                if width == LoadWidth::HalfWord {
+                    sign_extend(value)
+                } else {
+                    value as u32
+                }
+            }
+            LoadWidth::Word => self.read_word(memory_translation_table, address)?,
+            // These merge with whatever is in flight for the register, so they read what will be written
+            LoadWidth::WordLeft => {
+                let word = self.read_word(memory_translation_table, address & !0b11)?;
+                let current = self.registers.general_purpose_out[rt.0 as usize];
+
+                match address & 0b11 {
+                    0 => (current & 0x00ff_ffff) | (word << 24),
+                    1 => (current & 0x0000_ffff) | (word << 16),
+                    2 => (current & 0x0000_00ff) | (word << 8),
+                    _ => word,
+                }
+            }
+            LoadWidth::WordRight => {
+                let word = self.read_word(memory_translation_table, address & !0b11)?;
+                let current = self.registers.general_purpose_out[rt.0 as usize];
+
+                match address & 0b11 {
+                    0 => word,
+                    1 => (current & 0xff00_0000) | (word >> 8),
+                    2 => (current & 0xffff_0000) | (word >> 16),
+                    _ => (current & 0xffff_ff00) | (word >> 24),
+                }
+            }
+        };
+
+        self.pending_load = Some((rt, value));
+
+        Ok(())
+    }
+
+    fn store(
+        &mut self,
+        memory_translation_table: &MemoryTranslationTable,
+        width: StoreWidth,
+        value: u32,
+        address: u32,
+    ) -> Result<(), Exception> {
+        let alignment = match width {
+            StoreWidth::HalfWord => 2,
+            StoreWidth::Word => 4,
+            _ => 1,
+        };
+
+        if !address.is_multiple_of(alignment) {
+            self.registers.cop0[COP0_BAD_VIRTUAL_ADDRESS] = address;
+            return Err(Exception::AddressErrorStore);
+        }
+
+        match width {
+            StoreWidth::Byte => self.write(memory_translation_table, address, &[value as u8]),
+            StoreWidth::HalfWord => self.write(
+                memory_translation_table,
+                address,
+                &(value as u16).to_le_bytes(),
+            ),
+            StoreWidth::Word => self.write(memory_translation_table, address, &value.to_le_bytes()),
+            StoreWidth::WordLeft | StoreWidth::WordRight => {
+                let aligned = address & !0b11;
+                let memory = self.read_word(memory_translation_table, aligned)?;
+
+                let merged = if width == StoreWidth::WordLeft {
+                    match address & 0b11 {
+                        0 => (memory & 0xffff_ff00) | (value >> 24),
+                        1 => (memory & 0xffff_0000) | (value >> 16),
+                        2 => (memory & 0xff00_0000) | (value >> 8),
+                        _ => value,
+                    }
+                } else {
+                    match address & 0b11 {
+                        0 => value,
+                        1 => (memory & 0x0000_00ff) | (value << 8),
+                        2 => (memory & 0x0000_ffff) | (value << 16),
+                        _ => (memory & 0x00ff_ffff) | (value << 24),
+                    }
+                };
+
+                self.write(memory_translation_table, aligned, &merged.to_le_bytes())
+            }
+        }
+    }
+
+    fn branch_condition(&self, condition: BranchCondition, rs: Register) -> bool {
+        let value = self.register(rs) as i32;
+
+        match condition {
+            BranchCondition::Equal { rt } => self.register(rs) == self.register(rt),
+            BranchCondition::NotEqual { rt } => self.register(rs) != self.register(rt),
+            BranchCondition::LessThanOrEqualZero => value <= 0,
+            BranchCondition::GreaterThanZero => value > 0,
+            BranchCondition::LessThanZero { .. } => value < 0,
+            BranchCondition::GreaterThanOrEqualZero { .. } => value >= 0,
+        }
+    }
+
+    fn multiply(&mut self, operation: MultiplyOperation, rs: Register, rt: Register) {
+        let numerator = self.register(rs);
+        let denominator = self.register(rt);
+
+        match operation {
+            MultiplyOperation::Multiply => {
+                let result = (numerator as i32 as i64 * denominator as i32 as i64) as u64;
+                self.registers.hi = (result >> 32) as u32;
+                self.registers.lo = result as u32;
+            }
+            MultiplyOperation::MultiplyUnsigned => {
+                let result = numerator as u64 * denominator as u64;
+                self.registers.hi = (result >> 32) as u32;
+                self.registers.lo = result as u32;
+            }
+            // Dividing by zero doesn't trap, it leaves these results behind
+            MultiplyOperation::Divide => {
+                let numerator = numerator as i32;
+                let denominator = denominator as i32;
+
+                if denominator == 0 {
+                    self.registers.hi = numerator as u32;
+                    self.registers.lo = if numerator >= 0 { 0xffff_ffff } else { 1 };
+                } else {
+                    self.registers.hi = numerator.wrapping_rem(denominator) as u32;
+                    self.registers.lo = numerator.wrapping_div(denominator) as u32;
+                }
+            }
+            MultiplyOperation::DivideUnsigned => {
+                if denominator == 0 {
+                    self.registers.hi = numerator;
+                    self.registers.lo = 0xffff_ffff;
+                } else {
+                    self.registers.hi = numerator % denominator;
+                    self.registers.lo = numerator / denominator;
+                }
+            }
+        }
+    }
+
+    /// COP0 is always usable in kernel mode, COP2 is the GTE which isn't here yet and the other two don't exist
+    fn check_coprocessor(&self, coprocessor: u8) -> Result<(), Exception> {
+        match coprocessor {
+            0 => Ok(()),
+            2 => {
+                tracing::warn!("GTE instructions are not implemented yet, ignoring");
+                Ok(())
+            }
+            _ => Err(Exception::CoprocessorUnusable),
+        }
+    }
+
+    fn execute(
+        &mut self,
+        program_pointer: &mut usize,
+        instruction: R3000InstructionSet,
+        memory_translation_table: &MemoryTranslationTable,
+    ) -> Result<(), Exception> {
+        // The program pointer already points at the delay slot
+        let delay_slot = *program_pointer as u32;
+
+        match instruction {
+            R3000InstructionSet::Register {
+                operation,
+                rd,
+                rs,
+                rt,
+            } => {
+                let left = self.register(rs);
+                let right = self.register(rt);
+
+                let result = match operation {
+                    RegisterOperation::Add => (left as i32)
+                        .checked_add(right as i32)
+                        .ok_or(Exception::Overflow)?
+                        as u32,
+                    RegisterOperation::AddUnsigned => left.wrapping_add(right),
+                    RegisterOperation::Subtract => (left as i32)
+                        .checked_sub(right as i32)
+                        .ok_or(Exception::Overflow)?
+                        as u32,
+                    RegisterOperation::SubtractUnsigned => left.wrapping_sub(right),
+                    RegisterOperation::And => left & right,
+                    RegisterOperation::Or => left | right,
+                    RegisterOperation::Xor => left ^ right,
+                    RegisterOperation::Nor => !(left | right),
+                    RegisterOperation::SetLessThan => ((left as i32) < (right as i32)) as u32,
+                    RegisterOperation::SetLessThanUnsigned => (left < right) as u32,
+                };
+
+                self.set_register(rd, result);
+            }
+            R3000InstructionSet::Immediate {
+                operation,
+                rt,
+                rs,
+                immediate,
+            } => {
+                let value = self.register(rs);
+
+                let result = match operation {
+                    ImmediateOperation::Add => (value as i32)
+                        .checked_add(immediate as i16 as i32)
+                        .ok_or(Exception::Overflow)?
+                        as u32,
+                    ImmediateOperation::AddUnsigned => value.wrapping_add(sign_extend(immediate)),
+                    ImmediateOperation::SetLessThan => {
+                        ((value as i32) < (immediate as i16 as i32)) as u32
+                    }
+                    ImmediateOperation::SetLessThanUnsigned => {
+                        (value < sign_extend(immediate)) as u32
+                    }
+                    ImmediateOperation::And => value & immediate as u32,
+                    ImmediateOperation::Or => value | immediate as u32,
+                    ImmediateOperation::Xor => value ^ immediate as u32,
+                };
+
+                self.set_register(rt, result);
+            }
+            R3000InstructionSet::LoadUpperImmediate { rt, immediate } => {
+                self.set_register(rt, (immediate as u32) << 16);
+            }
+            R3000InstructionSet::Shift {
+                operation,
+                rd,
+                rt,
+                amount,
+            } => {
+                let value = self.register(rt);
+                self.set_register(rd, shift(operation, value, amount as u32));
+            }
+            R3000InstructionSet::ShiftVariable {
+                operation,
+                rd,
+                rt,
+                rs,
+            } => {
+                let value = self.register(rt);
+                let amount = self.register(rs) & 0x1f;
+                self.set_register(rd, shift(operation, value, amount));
+            }
+            R3000InstructionSet::Multiply { operation, rs, rt } => {
+                self.multiply(operation, rs, rt);
+            }
+            R3000InstructionSet::MoveFromHi { rd } => {
+                self.set_register(rd, self.registers.hi);
+            }
+            R3000InstructionSet::MoveToHi { rs } => {
+                self.registers.hi = self.register(rs);
+            }
+            R3000InstructionSet::MoveFromLo { rd } => {
+                self.set_register(rd, self.registers.lo);
+            }
+            R3000InstructionSet::MoveToLo { rs } => {
+                self.registers.lo = self.register(rs);
+            }
+            R3000InstructionSet::Jump { target, link } => {
+                if link {
+                    self.set_register(Register(31), delay_slot.wrapping_add(4));
+                }
+
+                self.pending_branch = Some((delay_slot & 0xf000_0000) | (target << 2));
+            }
+            R3000InstructionSet::JumpRegister { rs, link } => {
+                let target = self.register(rs);
+
+                if let Some(link) = link {
+                    self.set_register(link, delay_slot.wrapping_add(4));
+                }
+
+                // The misaligned address is only noticed when it gets fetched
+                self.pending_branch = Some(target);
+            }
+            R3000InstructionSet::Branch {
+                condition,
+                rs,
+                offset,
+            } => {
+                let taken = self.branch_condition(condition, rs);
+
+                // The link happens even when the branch isn't taken
+                if let BranchCondition::LessThanZero { link: true }
+                | BranchCondition::GreaterThanOrEqualZero { link: true } = condition
+                {
+                    self.set_register(Register(31), delay_slot.wrapping_add(4));
+                }
+
+                if taken {
+                    self.pending_branch =
+                        Some(delay_slot.wrapping_add((offset as i32 as u32) << 2));
+                }
+            }
+            R3000InstructionSet::Load {
+                width,
+                rt,
+                base,
+                offset,
+            } => {
+                let address = self.load_address(base, offset);
+                self.load(memory_translation_table, width, rt, address)?;
+            }
+            R3000InstructionSet::Store {
+                width,
+                rt,
+                base,
+                offset,
+            } => {
+                let address = self.load_address(base, offset);
+                let value = self.register(rt);
+                self.store(memory_translation_table, width, value, address)?;
+            }
+            R3000InstructionSet::Syscall { .. } => return Err(Exception::Syscall),
+            R3000InstructionSet::Break { .. } => return Err(Exception::Break),
+            R3000InstructionSet::MoveFromCoprocessor {
+                coprocessor,
+                rt,
+                rd,
+            } => {
+                self.check_coprocessor(coprocessor)?;
+
+                // Coprocessor moves have the same delay as loads
+                if coprocessor == 0 {
+                    self.pending_load = Some((rt, self.registers.cop0[rd as usize]));
+                }
+            }
+            R3000InstructionSet::MoveToCoprocessor {
+                coprocessor,
+                rt,
+                rd,
+            } => {
+                self.check_coprocessor(coprocessor)?;
+
+                if coprocessor == 0 {
+                    let value = self.register(rt);
+
+                    match rd as usize {
+                        COP0_CAUSE => {
+                            let cause = &mut self.registers.cop0[COP0_CAUSE];
+                            *cause = (*cause & !CAUSE_SOFTWARE_INTERRUPTS)
+                                | (value & CAUSE_SOFTWARE_INTERRUPTS);
+                        }
+                        COP0_PROCESSOR_ID => {}
+                        register => self.registers.cop0[register] = value,
+                    }
+                }
+            }
+            R3000InstructionSet::MoveFromCoprocessorControl { coprocessor, .. }
+            | R3000InstructionSet::MoveToCoprocessorControl { coprocessor, .. }
+            | R3000InstructionSet::CoprocessorCommand { coprocessor, .. }
+            | R3000InstructionSet::LoadCoprocessor { coprocessor, .. }
+            | R3000InstructionSet::StoreCoprocessor { coprocessor, .. } => {
+                // COP0 has no control registers or commands besides RFE, so they are reserved
+                if coprocessor == 0 {
+                    return Err(Exception::ReservedInstruction);
+                }
+
+                self.check_coprocessor(coprocessor)?;
+            }
+            R3000InstructionSet::BranchCoprocessor { coprocessor, .. } => {
+                // Nothing drives the condition lines, so BCzF would always be taken if the coprocessor existed
+                if coprocessor == 0 {
+                    return Err(Exception::ReservedInstruction);
+                }
+
+                self.check_coprocessor(coprocessor)?;
+            }
+            R3000InstructionSet::ReturnFromException => {
+                let status = &mut self.registers.cop0[COP0_STATUS];
+                *status = (*status & !0x0f) | ((*status & 0x3f) >> 2);
+            }
+            R3000InstructionSet::Illegal(word) => {
+                tracing::warn!("Illegal instruction {:#010x}", word);
+
+                return Err(Exception::ReservedInstruction);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Pushes the mode stack and jumps to the handler, the return address is where the instruction or its branch was
+    fn enter_exception(
+        &mut self,
+        program_pointer: &mut usize,
+        exception: Exception,
+        instruction_address: u32,
+    ) {
+        let in_delay_slot = self.pending_branch.take().is_some();
+
+        let status = &mut self.registers.cop0[COP0_STATUS];
+        *status = (*status & !0x3f) | ((*status << 2) & 0x3f);
+
+        let cause = &mut self.registers.cop0[COP0_CAUSE];
+        *cause = (*cause & INTERRUPT_MASK) | ((exception as u32) << 2);
+
+        self.registers.cop0[COP0_EXCEPTION_PROGRAM_COUNTER] = if in_delay_slot {
+            self.registers.cop0[COP0_CAUSE] |= CAUSE_BRANCH_DELAY;
+            instruction_address.wrapping_sub(4)
+        } else {
+            instruction_address
+        };
+
+        *program_pointer = if self.registers.cop0[COP0_STATUS] & STATUS_BOOT_EXCEPTION_VECTORS != 0
+        {
+            BOOT_EXCEPTION_VECTOR
+        } else {
+            EXCEPTION_VECTOR
+        } as usize;
+    }
+}
+
+fn shift(operation: ShiftOperation, value: u32, amount: u32) -> u32 {
+    match operation {
+        ShiftOperation::LeftLogical => value << amount,
+        ShiftOperation::RightLogical => value >> amount,
+        ShiftOperation::RightArithmetic => ((value as i32) >> amount) as u32,
+    }
+}
+
+impl Component for R3000 {
+    fn reset(&mut self) {
+        self.reset_pending = true;
+    }
+}
+
+impl SnapshotableComponent for R3000 {
+    fn save_snapshot(&mut self) -> rmpv::Value {
+        let state = R3000Snapshot {
+            registers: self.registers.clone(),
+            pending_load: self
+                .pending_load
+                .map(|(register, value)| (register.0, value)),
+            pending_branch: self.pending_branch,
+            reset_pending: self.reset_pending,
+            irq: self.config.irq.is_raised(),
+        };
+
+        rmpv::ext::to_value(&state).unwrap()
+    }
+
+    fn load_snapshot(&mut self, state: rmpv::Value) {
+        let state = rmpv::ext::from_value::<R3000Snapshot>(state).unwrap();
+
+        self.registers = state.registers;
+        self.pending_load = state
+            .pending_load
+            .map(|(register, value)| (Register(register), value));
+        self.pending_branch = state.pending_branch;
+        self.reset_pending = state.reset_pending;
+
+        if state.irq {
+            self.config.irq.raise();
+        } else {
+            self.config.irq.lower();
+        }
+    }
+}
+
+impl ComponentVersion for R3000 {
+    const NAME: &'static str = "r3000";
+    const VERSION: SemanticVersion = SemanticVersion::new(1, 0, 0);
+}
+
+impl FromConfig for R3000 {
+    type Config = R3000Config;
+
+    fn from_config(_rom_manager: Arc<RomManager>, config: Self::Config) -> Self {
+        let mut cop0 = [0; 32];
+        cop0[COP0_STATUS] = STATUS_BOOT_EXCEPTION_VECTORS;
+        cop0[COP0_PROCESSOR_ID] = 0x0000_0002;
+
+        Self {
+            config,
+            registers: R3000Registers {
+                general_purpose: [0; 32],
+                general_purpose_out: [0; 32],
+                hi: 0,
+                lo: 0,
+                cop0,
+            },
+            pending_load: None,
+            pending_branch: None,
+            reset_pending: true,
+        }
+    }
+}
+
+impl SchedulableComponent for R3000 {
+    fn tick_rate(&self) -> Ratio<u32> {
+        self.config.frequency
+    }
+
+    fn tick(&mut self, _memory_translation_table: &MemoryTranslationTable) {}
+}
+
+impl ProcessorComponent for R3000 {
+    type InstructionSet = R3000InstructionSet;
+
+    fn should_execution_occur(&self) -> bool {
+        true
+    }
+
+    /// Laid out like GDB does for MIPS
+    fn debug_registers(&self, program_pointer: usize) -> Vec<ProcessorRegister> {
+        const NAMES: [&str; 32] = [
+            "zero", "at", "v0", "v1", "a0", "a1", "a2", "a3", "t0", "t1", "t2", "t3", "t4", "t5",
+            "t6", "t7", "s0", "s1", "s2", "s3", "s4", "s5", "s6", "s7", "t8", "t9", "k0", "k1",
+            "gp", "sp", "s8", "ra",
+        ];
+        let registers = &self.registers;
+
+        NAMES
+            .into_iter()
+            .zip(registers.general_purpose)
+            .map(|(name, value)| ProcessorRegister::new(name, 32, value))
+            .chain([
+                ProcessorRegister::new("sr", 32, registers.cop0[COP0_STATUS]),
+                ProcessorRegister::new("lo", 32, registers.lo),
+                ProcessorRegister::new("hi", 32, registers.hi),
+                ProcessorRegister::new("bad", 32, registers.cop0[COP0_BAD_VIRTUAL_ADDRESS]),
+                ProcessorRegister::new("cause", 32, registers.cop0[COP0_CAUSE]),
+                ProcessorRegister::new("pc", 32, program_pointer as u64),
+            ])
+            .collect()
+    }
+
+    fn service_interrupts(
+        &mut self,
+        program_pointer: &mut usize,
+        _memory_translation_table: &MemoryTranslationTable,
+    ) {
+        if self.reset_pending {
+            self.reset_pending = false;
+            self.pending_load = None;
+            self.pending_branch = None;
+            self.registers.cop0[COP0_STATUS] = STATUS_BOOT_EXCEPTION_VECTORS;
+            self.registers.cop0[COP0_CAUSE] = 0;
+            *program_pointer = RESET_VECTOR as usize;
+
+            return;
+        }
+
+        // A misaligned jump is caught here, before the fetch
+        if !program_pointer.is_multiple_of(4) {
+            let address = *program_pointer as u32;
+            self.registers.cop0[COP0_BAD_VIRTUAL_ADDRESS] = address;
+            self.pending_branch = None;
+            self.enter_exception(program_pointer, Exception::AddressErrorLoad, address);
+
+            return;
+        }
+
+        if self.config.irq.is_raised() {
+            self.registers.cop0[COP0_CAUSE] |= CAUSE_HARDWARE_INTERRUPT;
+        } else {
+            self.registers.cop0[COP0_CAUSE] &= !CAUSE_HARDWARE_INTERRUPT;
+        }
+
+        let status = self.registers.cop0[COP0_STATUS];
+        if status & STATUS_INTERRUPT_ENABLE != 0
+            && status & self.registers.cop0[COP0_CAUSE] & INTERRUPT_MASK != 0
+        {
+            // Nothing has run yet, so the return address is the instruction about to be skipped
+            let address = *program_pointer as u32;
+            self.enter_exception(program_pointer, Exception::Interrupt, address);
+        }
+    }
+
+    fn decompile(
+        &self,
+        cursor: usize,
+        memory_translation_table: &MemoryTranslationTable,
+    ) -> Result<(Self::InstructionSet, u8), InstructionDecompilingError> {
+        let address = physical_address(cursor as u32);
+
+        decode_instruction(address, memory_translation_table)
+            .map_err(|_| InstructionDecompilingError::at(address, 4, memory_translation_table))
+    }
+
+    fn interpret(
+        &mut self,
+        program_pointer: &mut usize,
+        instruction: Self::InstructionSet,
+        memory_translation_table: &MemoryTranslationTable,
+    ) -> Result<(), InstructionInterpretingError> {
+        // Whatever was loaded by the last instruction is visible after this one
+        if let Some((register, value)) = self.pending_load.take() {
+            self.set_register(register, value);
+        }
+
+        let instruction_address = (*program_pointer as u32).wrapping_sub(4);
+        let branch = self.pending_branch.take();
+
+        match self.execute(program_pointer, instruction, memory_translation_table) {
+            Ok(()) => {
+                if let Some(target) = branch {
+                    *program_pointer = target as usize;
+                }
+            }
+            Err(exception) => {
+                // A load that faulted never lands, and the branch is put back so the exception knows it was in the slot
+                self.pending_load = None;
+                self.pending_branch = branch;
+
+                self.enter_exception(program_pointer, exception, instruction_address);
+            }
+        }
+
+        self.registers.general_purpose = self.registers.general_purpose_out;
+
+        Ok(())
+    }
+}
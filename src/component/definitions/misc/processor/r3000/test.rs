@@ -0,0 +1,407 @@
+use super::{
+    decode::decode_word,
+    instruction::{
+        BranchCondition, ImmediateOperation, LoadWidth, R3000InstructionSet, Register,
+        RegisterOperation, StoreWidth,
+    },
+    R3000Config, COP0_BAD_VIRTUAL_ADDRESS, COP0_CAUSE, COP0_EXCEPTION_PROGRAM_COUNTER, COP0_STATUS,
+    R3000,
+};
+use crate::{
+    component::{
+        definitions::misc::plain_memory::{PlainMemory, PlainMemoryConfig},
+        memory::MemoryTranslationTable,
+        processor::ProcessorComponent,
+        FromConfig,
+    },
+    rom::RomManager,
+};
+use std::sync::{Arc, Mutex};
+
+/// A bit of RAM at the bottom and the BIOS region, enough for the reset and exception vectors
+fn r3000() -> (R3000, MemoryTranslationTable) {
+    let rom_manager = Arc::new(RomManager::default());
+    let mut memory_translation_table = MemoryTranslationTable::default();
+
+    for range in [0x0000_0000..0x0001_0000, 0x1fc0_0000..0x1fc0_1000] {
+        let memory = PlainMemory::from_config(
+            rom_manager.clone(),
+            PlainMemoryConfig {
+                readable: true,
+                writable: true,
+                assigned_range: range.clone(),
+                ..Default::default()
+            },
+        );
+        memory_translation_table.insert(range, Arc::new(Mutex::new(memory)));
+    }
+
+    let processor = R3000::from_config(rom_manager, R3000Config::playstation());
+
+    (processor, memory_translation_table)
+}
+
+/// Does what the processor task does for a single instruction
+fn step(
+    processor: &mut R3000,
+    memory_translation_table: &MemoryTranslationTable,
+    program_pointer: &mut usize,
+) {
+    processor.service_interrupts(program_pointer, memory_translation_table);
+
+    if !processor.should_execution_occur() {
+        return;
+    }
+
+    let (instruction, length) = processor
+        .decompile(*program_pointer, memory_translation_table)
+        .unwrap();
+    *program_pointer = program_pointer.wrapping_add(length as usize);
+    processor
+        .interpret(program_pointer, instruction, memory_translation_table)
+        .unwrap();
+}
+
+/// Writes the program at a virtual address
+fn load_program(memory_translation_table: &MemoryTranslationTable, address: u32, program: &[u32]) {
+    for (index, word) in program.iter().enumerate() {
+        memory_translation_table
+            .write(
+                super::physical_address(address) + index * 4,
+                &word.to_le_bytes(),
+            )
+            .unwrap();
+    }
+}
+
+fn read_word(memory_translation_table: &MemoryTranslationTable, address: usize) -> u32 {
+    let mut buffer = [0; 4];
+    memory_translation_table.read(address, &mut buffer).unwrap();
+    u32::from_le_bytes(buffer)
+}
+
+#[test]
+fn r3000_instruction_decode() {
+    // addu $3, $1, $2
+    assert_eq!(
+        decode_word(0x0022_1821),
+        R3000InstructionSet::Register {
+            operation: RegisterOperation::AddUnsigned,
+            rd: Register(3),
+            rs: Register(1),
+            rt: Register(2),
+        }
+    );
+    // addiu $29, $29, -8
+    assert_eq!(
+        decode_word(0x27bd_fff8),
+        R3000InstructionSet::Immediate {
+            operation: ImmediateOperation::AddUnsigned,
+            rt: Register(29),
+            rs: Register(29),
+            immediate: 0xfff8,
+        }
+    );
+    // lw $8, 4($29)
+    assert_eq!(
+        decode_word(0x8fa8_0004),
+        R3000InstructionSet::Load {
+            width: LoadWidth::Word,
+            rt: Register(8),
+            base: Register(29),
+            offset: 4,
+        }
+    );
+    // sb $4, -1($5)
+    assert_eq!(
+        decode_word(0xa0a4_ffff),
+        R3000InstructionSet::Store {
+            width: StoreWidth::Byte,
+            rt: Register(4),
+            base: Register(5),
+            offset: -1,
+        }
+    );
+    // bgezal $4, 0x10
+    assert_eq!(
+        decode_word(0x0491_0010),
+        R3000InstructionSet::Branch {
+            condition: BranchCondition::GreaterThanOrEqualZero { link: true },
+            rs: Register(4),
+            offset: 0x10,
+        }
+    );
+    // jal 0x0bf00054
+    assert_eq!(
+        decode_word(0x0ff0_0015),
+        R3000InstructionSet::Jump {
+            target: 0x3f0_0015,
+            link: true,
+        }
+    );
+    // mtc0 $12, $12
+    assert_eq!(
+        decode_word(0x408c_6000),
+        R3000InstructionSet::MoveToCoprocessor {
+            coprocessor: 0,
+            rt: Register(12),
+            rd: 12,
+        }
+    );
+    assert_eq!(
+        decode_word(0x4200_0010),
+        R3000InstructionSet::ReturnFromException
+    );
+    assert_eq!(
+        decode_word(0x0000_000c),
+        R3000InstructionSet::Syscall { code: 0 }
+    );
+    assert_eq!(
+        decode_word(0xfc00_0000),
+        R3000InstructionSet::Illegal(0xfc00_0000)
+    );
+}
+
+#[test]
+fn r3000_alu_and_overflow() {
+    let (mut processor, memory_translation_table) = r3000();
+    let mut program_pointer = 0;
+
+    load_program(
+        &memory_translation_table,
+        0xbfc0_0000,
+        &[
+            // lui $1, 0x7fff
+            0x3c01_7fff,
+            // ori $1, $1, 0xffff
+            0x3421_ffff,
+            // addiu $2, $1, 1
+            0x2422_0001,
+            // addi $3, $1, 1
+            0x2023_0001,
+        ],
+    );
+
+    for _ in 0..3 {
+        step(
+            &mut processor,
+            &memory_translation_table,
+            &mut program_pointer,
+        );
+    }
+
+    assert_eq!(processor.registers.general_purpose[1], 0x7fff_ffff);
+    assert_eq!(processor.registers.general_purpose[2], 0x8000_0000);
+
+    // The trapping add leaves its destination alone
+    step(
+        &mut processor,
+        &memory_translation_table,
+        &mut program_pointer,
+    );
+
+    assert_eq!(processor.registers.general_purpose[3], 0);
+    assert_eq!(program_pointer, 0xbfc0_0180);
+    assert_eq!((processor.registers.cop0[COP0_CAUSE] >> 2) & 0x1f, 0x0c);
+    assert_eq!(
+        processor.registers.cop0[COP0_EXCEPTION_PROGRAM_COUNTER],
+        0xbfc0_000c
+    );
+}
+
+#[test]
+fn r3000_branch_delay_slot() {
+    let (mut processor, memory_translation_table) = r3000();
+    let mut program_pointer = 0;
+
+    load_program(
+        &memory_translation_table,
+        0xbfc0_0000,
+        &[
+            // beq $0, $0, 2
+            0x1000_0002,
+            // addiu $1, $0, 1, runs in the delay slot
+            0x2401_0001,
+            // addiu $2, $0, 1, skipped
+            0x2402_0001,
+            // addiu $3, $0, 1
+            0x2403_0001,
+        ],
+    );
+
+    for _ in 0..3 {
+        step(
+            &mut processor,
+            &memory_translation_table,
+            &mut program_pointer,
+        );
+    }
+
+    assert_eq!(processor.registers.general_purpose[1], 1);
+    assert_eq!(processor.registers.general_purpose[2], 0);
+    assert_eq!(processor.registers.general_purpose[3], 1);
+    assert_eq!(program_pointer, 0xbfc0_0010);
+}
+
+#[test]
+fn r3000_load_delay_slot() {
+    let (mut processor, memory_translation_table) = r3000();
+    let mut program_pointer = 0;
+
+    memory_translation_table
+        .write(0x100, &0x1234_5678u32.to_le_bytes())
+        .unwrap();
+    load_program(
+        &memory_translation_table,
+        0xbfc0_0000,
+        &[
+            // lw $1, 0x100($0)
+            0x8c01_0100,
+            // addu $2, $1, $0, still sees the old value
+            0x0020_1021,
+            // addu $3, $1, $0
+            0x0020_1821,
+            // sw $1, 0x104($0)
+            0xac01_0104,
+        ],
+    );
+
+    for _ in 0..4 {
+        step(
+            &mut processor,
+            &memory_translation_table,
+            &mut program_pointer,
+        );
+    }
+
+    assert_eq!(processor.registers.general_purpose[2], 0);
+    assert_eq!(processor.registers.general_purpose[3], 0x1234_5678);
+    assert_eq!(read_word(&memory_translation_table, 0x104), 0x1234_5678);
+}
+
+#[test]
+fn r3000_syscall_and_return() {
+    let (mut processor, memory_translation_table) = r3000();
+    let mut program_pointer = 0;
+
+    load_program(
+        &memory_translation_table,
+        0xbfc0_0000,
+        &[
+            // ori $1, $0, 1
+            0x3401_0001,
+            // mtc0 $1, $12, interrupts on and BEV off
+            0x4081_6000,
+            // syscall
+            0x0000_000c,
+        ],
+    );
+    load_program(
+        &memory_translation_table,
+        0x8000_0080,
+        &[
+            // mfc0 $26, $14
+            0x401a_7000,
+            // nop
+            0x0000_0000,
+            // addiu $26, $26, 4
+            0x275a_0004,
+            // jr $26
+            0x0340_0008,
+            // rfe
+            0x4200_0010,
+        ],
+    );
+
+    for _ in 0..3 {
+        step(
+            &mut processor,
+            &memory_translation_table,
+            &mut program_pointer,
+        );
+    }
+
+    assert_eq!(program_pointer, 0x8000_0080);
+    assert_eq!((processor.registers.cop0[COP0_CAUSE] >> 2) & 0x1f, 0x08);
+    // The mode stack was pushed, so interrupts are off
+    assert_eq!(processor.registers.cop0[COP0_STATUS] & 0x3f, 0b00_0100);
+
+    for _ in 0..5 {
+        step(
+            &mut processor,
+            &memory_translation_table,
+            &mut program_pointer,
+        );
+    }
+
+    assert_eq!(program_pointer, 0xbfc0_000c);
+    assert_eq!(processor.registers.cop0[COP0_STATUS] & 0x3f, 0b00_0001);
+}
+
+#[test]
+fn r3000_unaligned_load() {
+    let (mut processor, memory_translation_table) = r3000();
+    let mut program_pointer = 0;
+
+    load_program(
+        &memory_translation_table,
+        0xbfc0_0000,
+        &[
+            // lw $1, 0x102($0)
+            0x8c01_0102,
+        ],
+    );
+
+    step(
+        &mut processor,
+        &memory_translation_table,
+        &mut program_pointer,
+    );
+
+    assert_eq!(program_pointer, 0xbfc0_0180);
+    assert_eq!((processor.registers.cop0[COP0_CAUSE] >> 2) & 0x1f, 0x04);
+    assert_eq!(processor.registers.cop0[COP0_BAD_VIRTUAL_ADDRESS], 0x102);
+}
+
+#[test]
+fn r3000_interrupt() {
+    let (mut processor, memory_translation_table) = r3000();
+    let mut program_pointer = 0;
+
+    load_program(
+        &memory_translation_table,
+        0xbfc0_0000,
+        &[
+            // ori $1, $0, 0x0401, hardware interrupt 0 unmasked and interrupts on
+            0x3401_0401,
+            // mtc0 $1, $12
+            0x4081_6000,
+            // nop
+            0x0000_0000,
+        ],
+    );
+
+    for _ in 0..2 {
+        step(
+            &mut processor,
+            &memory_translation_table,
+            &mut program_pointer,
+        );
+    }
+
+    processor.assert_irq();
+    step(
+        &mut processor,
+        &memory_translation_table,
+        &mut program_pointer,
+    );
+
+    // The handler's first instruction ran in the same step
+    assert_eq!(program_pointer, 0x8000_0084);
+    assert_eq!(processor.registers.cop0[COP0_CAUSE] & 0x7c, 0);
+    assert_ne!(processor.registers.cop0[COP0_CAUSE] & 0x0400, 0);
+    assert_eq!(
+        processor.registers.cop0[COP0_EXCEPTION_PROGRAM_COUNTER],
+        0xbfc0_0008
+    );
+}
@@ -1,11 +1,12 @@
 use crate::{
     component::{
         memory::{MemoryComponent, PreviewMemoryRecord, ReadMemoryRecord, WriteMemoryRecord},
-        Component, FromConfig,
+        Component, ComponentVersion, FromConfig, SemanticVersion,
     },
     rom::{RomId, RomManager, RomRequirement},
 };
 use arrayvec::ArrayVec;
+use serde::Serialize;
 use std::{
     fs::File,
     io::{BufReader, Read, Seek, SeekFrom},
@@ -13,13 +14,15 @@ use std::{
     sync::Arc,
 };
 
-#[derive(Debug)]
+#[derive(Debug, Serialize)]
 pub struct RomMemoryConfig {
     pub rom_id: RomId,
     // The maximum word size
     pub max_word_size: u8,
     // The penalty for each cycle
+    #[serde(skip)]
     pub read_cycle_penalty_calculator: fn(range: Range<usize>, denied: bool) -> u64,
+    #[serde(skip)]
     pub write_cycle_penalty_calculator: fn(range: Range<usize>) -> u64,
     // Memory region this buffer will be mapped to
     pub assigned_range: Range<usize>,
@@ -44,6 +47,11 @@ pub struct RomMemory {
 
 impl Component for RomMemory {}
 
+impl ComponentVersion for RomMemory {
+    const NAME: &'static str = "rom_memory";
+    const VERSION: SemanticVersion = SemanticVersion::new(1, 0, 0);
+}
+
 impl FromConfig for RomMemory {
     type Config = RomMemoryConfig;
 
@@ -0,0 +1,355 @@
+use crate::{
+    component::{
+        interrupt::InterruptLine,
+        memory::{
+            MemoryComponent, MemoryTranslationTable, PreviewMemoryRecord, ReadMemoryRecord,
+            WriteMemoryRecord,
+        },
+        schedulable::SchedulableComponent,
+        snapshot::SnapshotableComponent,
+        Component, ComponentVersion, FromConfig, SemanticVersion,
+    },
+    rom::RomManager,
+};
+use arrayvec::ArrayVec;
+use num::rational::Ratio;
+use serde::{Deserialize, Serialize};
+use std::{ops::Range, sync::Arc};
+
+/// Offsets of the registers from the start of the assigned range, 16 bit registers are little endian
+pub const COUNTER_REGISTER: usize = 0;
+pub const RELOAD_REGISTER: usize = 2;
+pub const CONTROL_REGISTER: usize = 4;
+/// Size of the register block
+pub const REGISTERS_SIZE: usize = 5;
+
+/// Bits of the control register
+pub const CONTROL_ENABLED: u8 = 0b0000_0001;
+pub const CONTROL_INTERRUPT_ENABLED: u8 = 0b0000_0010;
+
+#[derive(Debug, Serialize)]
+pub struct TimerConfig {
+    /// Frequency of the clock feeding the prescaler
+    pub frequency: Ratio<u32>,
+    /// Input clocks per counter decrement
+    pub prescaler: u32,
+    /// Loaded into the counter when it expires, if None the timer stops instead
+    pub reload: Option<u16>,
+    /// Raised every time the counter expires
+    #[serde(skip)]
+    pub interrupt: Option<InterruptLine>,
+    /// Where the registers are mapped, see the register constants for the layout
+    pub assigned_range: Option<Range<usize>>,
+    pub enabled: bool,
+}
+
+impl Default for TimerConfig {
+    fn default() -> Self {
+        Self {
+            frequency: Ratio::new(1, 1),
+            prescaler: 1,
+            reload: Some(u16::MAX),
+            interrupt: None,
+            assigned_range: None,
+            enabled: true,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TimerSnapshot {
+    divider: u32,
+    counter: u16,
+    reload: u16,
+    control: u8,
+}
+
+/// Generic down counter behind a prescaler, for the countless timers and dividers systems have
+///
+/// On every prescaled clock the counter is decremented, and if it was already zero it expires instead, so a reload
+/// value of n expires every n + 1 prescaled clocks
+#[derive(Debug)]
+pub struct Timer {
+    config: TimerConfig,
+    /// Input clocks since the counter was last decremented
+    divider: u32,
+    counter: u16,
+    reload: u16,
+    control: u8,
+}
+
+impl Timer {
+    pub fn counter(&self) -> u16 {
+        self.counter
+    }
+
+    pub fn set_counter(&mut self, counter: u16) {
+        self.counter = counter;
+    }
+
+    fn expire(&mut self) {
+        if self.control & CONTROL_INTERRUPT_ENABLED != 0 {
+            if let Some(interrupt) = &self.config.interrupt {
+                interrupt.raise();
+            }
+        }
+
+        if self.config.reload.is_some() {
+            self.counter = self.reload;
+        } else {
+            self.control &= !CONTROL_ENABLED;
+        }
+    }
+
+    fn registers(&self) -> [u8; REGISTERS_SIZE] {
+        let [counter_low, counter_high] = self.counter.to_le_bytes();
+        let [reload_low, reload_high] = self.reload.to_le_bytes();
+
+        [
+            counter_low,
+            counter_high,
+            reload_low,
+            reload_high,
+            self.control,
+        ]
+    }
+
+    fn write_register(&mut self, offset: usize, value: u8) {
+        match offset {
+            COUNTER_REGISTER | 1 => {
+                let mut bytes = self.counter.to_le_bytes();
+                bytes[offset - COUNTER_REGISTER] = value;
+                self.counter = u16::from_le_bytes(bytes);
+            }
+            RELOAD_REGISTER | 3 => {
+                let mut bytes = self.reload.to_le_bytes();
+                bytes[offset - RELOAD_REGISTER] = value;
+                self.reload = u16::from_le_bytes(bytes);
+            }
+            CONTROL_REGISTER => {
+                // Restarting the timer restarts the prescaler too
+                if self.control & CONTROL_ENABLED == 0 && value & CONTROL_ENABLED != 0 {
+                    self.divider = 0;
+                }
+
+                self.control = value;
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    fn register_offset(&self, address: usize) -> usize {
+        address - self.assigned_memory_range().start
+    }
+
+    fn initial_control(config: &TimerConfig) -> u8 {
+        let mut control = CONTROL_INTERRUPT_ENABLED;
+
+        if config.enabled {
+            control |= CONTROL_ENABLED;
+        }
+
+        control
+    }
+}
+
+impl Component for Timer {
+    fn reset(&mut self) {
+        self.divider = 0;
+        self.reload = self.config.reload.unwrap_or_default();
+        self.counter = self.reload;
+        self.control = Self::initial_control(&self.config);
+    }
+}
+
+impl ComponentVersion for Timer {
+    const NAME: &'static str = "timer";
+    const VERSION: SemanticVersion = SemanticVersion::new(1, 0, 0);
+}
+
+impl FromConfig for Timer {
+    type Config = TimerConfig;
+
+    fn from_config(_rom_manager: Arc<RomManager>, config: Self::Config) -> Self {
+        assert!(config.prescaler != 0, "Prescaler must be non-zero");
+
+        if let Some(assigned_range) = &config.assigned_range {
+            assert_eq!(
+                assigned_range.len(),
+                REGISTERS_SIZE,
+                "Timer registers must be mapped to exactly {} bytes",
+                REGISTERS_SIZE
+            );
+        }
+
+        let reload = config.reload.unwrap_or_default();
+
+        Self {
+            divider: 0,
+            counter: reload,
+            reload,
+            control: Self::initial_control(&config),
+            config,
+        }
+    }
+}
+
+impl SchedulableComponent for Timer {
+    fn tick_rate(&self) -> Ratio<u32> {
+        self.config.frequency
+    }
+
+    fn tick(&mut self, _: &MemoryTranslationTable) {
+        if self.control & CONTROL_ENABLED == 0 {
+            return;
+        }
+
+        self.divider += 1;
+        if self.divider < self.config.prescaler {
+            return;
+        }
+        self.divider = 0;
+
+        match self.counter.checked_sub(1) {
+            Some(counter) => self.counter = counter,
+            None => self.expire(),
+        }
+    }
+}
+
+impl SnapshotableComponent for Timer {
+    fn save_snapshot(&mut self) -> rmpv::Value {
+        let state = TimerSnapshot {
+            divider: self.divider,
+            counter: self.counter,
+            reload: self.reload,
+            control: self.control,
+        };
+
+        rmpv::ext::to_value(&state).unwrap()
+    }
+
+    fn load_snapshot(&mut self, state: rmpv::Value) {
+        let state = rmpv::ext::from_value::<TimerSnapshot>(state).unwrap();
+
+        self.divider = state.divider;
+        self.counter = state.counter;
+        self.reload = state.reload;
+        self.control = state.control;
+    }
+}
+
+impl MemoryComponent for Timer {
+    fn assigned_memory_range(&self) -> Range<usize> {
+        self.config.assigned_range.clone().unwrap_or(0..0)
+    }
+
+    fn read_memory(
+        &mut self,
+        address: usize,
+        buffer: &mut [u8],
+        _records: &mut ArrayVec<(Range<usize>, ReadMemoryRecord), 8>,
+    ) -> u64 {
+        let offset = self.register_offset(address);
+        buffer.copy_from_slice(&self.registers()[offset..offset + buffer.len()]);
+
+        0
+    }
+
+    fn write_memory(
+        &mut self,
+        address: usize,
+        buffer: &[u8],
+        _records: &mut ArrayVec<(Range<usize>, WriteMemoryRecord), 8>,
+    ) -> u64 {
+        let offset = self.register_offset(address);
+
+        for (index, value) in buffer.iter().enumerate() {
+            self.write_register(offset + index, *value);
+        }
+
+        0
+    }
+
+    fn preview_memory(
+        &mut self,
+        address: usize,
+        buffer: &mut [u8],
+        _records: &mut ArrayVec<(Range<usize>, PreviewMemoryRecord), 8>,
+    ) {
+        let offset = self.register_offset(address);
+        buffer.copy_from_slice(&self.registers()[offset..offset + buffer.len()]);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn timer(config: TimerConfig) -> Timer {
+        Timer::from_config(Arc::new(RomManager::default()), config)
+    }
+
+    #[test]
+    fn expiry_raises_interrupt_and_reloads() {
+        let interrupt = InterruptLine::default();
+        let mut timer = timer(TimerConfig {
+            prescaler: 4,
+            reload: Some(2),
+            interrupt: Some(interrupt.clone()),
+            ..Default::default()
+        });
+        let memory_translation_table = MemoryTranslationTable::default();
+
+        // Reload of 2 with a prescaler of 4 expires every 12 clocks
+        for _ in 0..11 {
+            timer.tick(&memory_translation_table);
+        }
+        assert!(!interrupt.is_raised());
+
+        timer.tick(&memory_translation_table);
+        assert!(interrupt.acknowledge());
+        assert_eq!(timer.counter(), 2);
+    }
+
+    #[test]
+    fn one_shot_timer_stops() {
+        let interrupt = InterruptLine::default();
+        let mut timer = timer(TimerConfig {
+            reload: None,
+            interrupt: Some(interrupt.clone()),
+            ..Default::default()
+        });
+        let memory_translation_table = MemoryTranslationTable::default();
+
+        timer.tick(&memory_translation_table);
+        assert!(interrupt.acknowledge());
+
+        timer.tick(&memory_translation_table);
+        assert!(!interrupt.is_raised());
+        assert_eq!(timer.registers()[CONTROL_REGISTER] & CONTROL_ENABLED, 0);
+    }
+
+    #[test]
+    fn registers() {
+        let mut timer = timer(TimerConfig {
+            assigned_range: Some(0x100..0x100 + REGISTERS_SIZE),
+            ..Default::default()
+        });
+
+        timer.write_memory(
+            0x100 + RELOAD_REGISTER,
+            &0x1234u16.to_le_bytes(),
+            &mut ArrayVec::default(),
+        );
+        timer.write_memory(
+            0x100 + COUNTER_REGISTER,
+            &0x0042u16.to_le_bytes(),
+            &mut ArrayVec::default(),
+        );
+
+        let mut buffer = [0; 4];
+        timer.read_memory(0x100, &mut buffer, &mut ArrayVec::default());
+        assert_eq!(buffer, [0x42, 0x00, 0x34, 0x12]);
+    }
+}
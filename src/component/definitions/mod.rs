@@ -1,3 +1,7 @@
+#[cfg(feature = "atari2600")]
 pub mod atari2600;
+/// The display is built either way, every rendering backend is required to support it
 pub mod chip8;
 pub mod misc;
+#[cfg(feature = "nes")]
+pub mod nes;
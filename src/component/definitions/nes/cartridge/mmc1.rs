@@ -0,0 +1,318 @@
+use super::{
+    Cartridge, CartridgeSnapshot, Mapper, Mirroring, NesCartridgeConfig, CARTRIDGE_START,
+    PRG_ROM_START, PRG_ROM_UNIT,
+};
+use crate::{
+    component::{
+        memory::{MemoryComponent, PreviewMemoryRecord, ReadMemoryRecord, WriteMemoryRecord},
+        snapshot::SnapshotableComponent,
+        Component, ComponentVersion, FromConfig, SemanticVersion,
+    },
+    rom::RomManager,
+};
+use arrayvec::ArrayVec;
+use serde::{Deserialize, Serialize};
+use std::{ops::Range, sync::Arc};
+
+// https://www.nesdev.org/wiki/MMC1
+
+const CHR_BANK_SIZE: usize = 0x1000;
+/// Writing with this bit set empties the shift register
+const SHIFT_RESET: u8 = 0x80;
+/// PRG mode 3, which is what the board powers up in
+const CONTROL_DEFAULT: u8 = 0x0c;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Mmc1Snapshot {
+    pub cartridge: CartridgeSnapshot,
+    pub shift_register: u8,
+    pub shift_count: u8,
+    pub control: u8,
+    pub chr_banks: [u8; 2],
+    pub prg_bank: u8,
+}
+
+/// Mapper 1, registers are written a bit at a time through a serial port
+pub struct Mmc1 {
+    cartridge: Cartridge,
+    shift_register: u8,
+    shift_count: u8,
+    control: u8,
+    chr_banks: [u8; 2],
+    prg_bank: u8,
+}
+
+impl Mmc1 {
+    pub fn new(cartridge: Cartridge) -> Self {
+        Self {
+            cartridge,
+            shift_register: 0,
+            shift_count: 0,
+            control: CONTROL_DEFAULT,
+            chr_banks: [0; 2],
+            prg_bank: 0,
+        }
+    }
+
+    /// PRG RAM is enabled when bit 4 of the PRG bank is clear, on the MMC1B and later
+    fn prg_ram_enabled(&self) -> bool {
+        self.prg_bank & 0x10 == 0
+    }
+
+    fn read(&self, address: usize) -> u8 {
+        if address < PRG_ROM_START {
+            return if self.prg_ram_enabled() {
+                self.cartridge.read_prg_ram(address)
+            } else {
+                0
+            };
+        }
+
+        let bank = (self.prg_bank & 0x0f) as usize;
+        let last_bank = self.cartridge.prg_rom.len() / PRG_ROM_UNIT - 1;
+        let offset = address - PRG_ROM_START;
+
+        match ((self.control >> 2) & 0b11, address >= 0xc000) {
+            // 32KiB mode ignores the bottom bit
+            (0 | 1, _) => self.cartridge.read_prg_rom(0x8000, bank >> 1, offset),
+            (2, false) => self.cartridge.read_prg_rom(PRG_ROM_UNIT, 0, offset),
+            (2, true) => self.cartridge.read_prg_rom(PRG_ROM_UNIT, bank, offset),
+            (_, false) => self.cartridge.read_prg_rom(PRG_ROM_UNIT, bank, offset),
+            (_, true) => self.cartridge.read_prg_rom(PRG_ROM_UNIT, last_bank, offset),
+        }
+    }
+
+    fn write_register(&mut self, address: usize, value: u8) {
+        if value & SHIFT_RESET != 0 {
+            self.shift_register = 0;
+            self.shift_count = 0;
+            self.control |= CONTROL_DEFAULT;
+
+            return;
+        }
+
+        // The bits come in least significant first
+        self.shift_register |= (value & 1) << self.shift_count;
+        self.shift_count += 1;
+
+        if self.shift_count < 5 {
+            return;
+        }
+
+        let value = std::mem::take(&mut self.shift_register);
+        self.shift_count = 0;
+
+        match (address >> 13) & 0b11 {
+            0 => self.control = value,
+            1 => self.chr_banks[0] = value,
+            2 => self.chr_banks[1] = value,
+            _ => self.prg_bank = value,
+        }
+    }
+
+    /// Which 4KiB bank and offset a pattern table address lands in
+    fn chr_location(&self, address: u16) -> (usize, usize) {
+        let address = address as usize;
+        let offset = address % CHR_BANK_SIZE;
+
+        if self.control & 0x10 == 0 {
+            // 8KiB mode ignores the bottom bit and the second register
+            let bank = (self.chr_banks[0] & !1) as usize + address / CHR_BANK_SIZE;
+
+            (bank, offset)
+        } else {
+            (self.chr_banks[address / CHR_BANK_SIZE] as usize, offset)
+        }
+    }
+}
+
+impl Component for Mmc1 {
+    fn reset(&mut self) {
+        self.shift_register = 0;
+        self.shift_count = 0;
+        self.control |= CONTROL_DEFAULT;
+    }
+}
+
+impl SnapshotableComponent for Mmc1 {
+    fn save_snapshot(&mut self) -> rmpv::Value {
+        let state = Mmc1Snapshot {
+            cartridge: self.cartridge.save_snapshot(),
+            shift_register: self.shift_register,
+            shift_count: self.shift_count,
+            control: self.control,
+            chr_banks: self.chr_banks,
+            prg_bank: self.prg_bank,
+        };
+
+        rmpv::ext::to_value(&state).unwrap()
+    }
+
+    fn load_snapshot(&mut self, state: rmpv::Value) {
+        let state = rmpv::ext::from_value::<Mmc1Snapshot>(state).unwrap();
+
+        self.cartridge.load_snapshot(state.cartridge);
+        self.shift_register = state.shift_register;
+        self.shift_count = state.shift_count;
+        self.control = state.control;
+        self.chr_banks = state.chr_banks;
+        self.prg_bank = state.prg_bank;
+    }
+}
+
+impl ComponentVersion for Mmc1 {
+    const NAME: &'static str = "mmc1";
+    const VERSION: SemanticVersion = SemanticVersion::new(1, 0, 0);
+}
+
+impl FromConfig for Mmc1 {
+    type Config = NesCartridgeConfig;
+
+    fn from_config(rom_manager: Arc<RomManager>, config: Self::Config) -> Self {
+        Self::new(Cartridge::load(&rom_manager, config.rom_id).unwrap())
+    }
+}
+
+impl MemoryComponent for Mmc1 {
+    fn assigned_memory_range(&self) -> Range<usize> {
+        CARTRIDGE_START..0x10000
+    }
+
+    fn read_memory(
+        &mut self,
+        address: usize,
+        buffer: &mut [u8],
+        _records: &mut ArrayVec<(Range<usize>, ReadMemoryRecord), 8>,
+    ) -> u64 {
+        for (address, value) in (address..).zip(buffer.iter_mut()) {
+            *value = self.read(address);
+        }
+
+        0
+    }
+
+    fn write_memory(
+        &mut self,
+        address: usize,
+        buffer: &[u8],
+        _records: &mut ArrayVec<(Range<usize>, WriteMemoryRecord), 8>,
+    ) -> u64 {
+        for (address, value) in (address..).zip(buffer.iter()) {
+            if address >= PRG_ROM_START {
+                self.write_register(address, *value);
+            } else if self.prg_ram_enabled() {
+                self.cartridge.write_prg_ram(address, *value);
+            }
+        }
+
+        0
+    }
+
+    fn preview_memory(
+        &mut self,
+        address: usize,
+        buffer: &mut [u8],
+        _records: &mut ArrayVec<(Range<usize>, PreviewMemoryRecord), 8>,
+    ) {
+        for (address, value) in (address..).zip(buffer.iter_mut()) {
+            *value = self.read(address);
+        }
+    }
+}
+
+impl Mapper for Mmc1 {
+    fn mirroring(&self) -> Mirroring {
+        match self.control & 0b11 {
+            0 => Mirroring::SingleScreenLower,
+            1 => Mirroring::SingleScreenUpper,
+            2 => Mirroring::Vertical,
+            _ => Mirroring::Horizontal,
+        }
+    }
+
+    fn read_chr(&mut self, address: u16) -> u8 {
+        let (bank, offset) = self.chr_location(address);
+
+        self.cartridge.read_chr(CHR_BANK_SIZE, bank, offset)
+    }
+
+    fn write_chr(&mut self, address: u16, value: u8) {
+        let (bank, offset) = self.chr_location(address);
+
+        self.cartridge.write_chr(CHR_BANK_SIZE, bank, offset, value);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::component::definitions::nes::cartridge::tests::test_rom;
+
+    fn write_serial(mapper: &mut Mmc1, address: usize, value: u8) {
+        for bit in 0..5 {
+            mapper.write_memory(address, &[value >> bit], &mut ArrayVec::new());
+        }
+    }
+
+    fn read(mapper: &mut Mmc1, address: usize) -> u8 {
+        let mut value = 0;
+        mapper.read_memory(
+            address,
+            std::array::from_mut(&mut value),
+            &mut ArrayVec::new(),
+        );
+        value
+    }
+
+    #[test]
+    fn serial_banking() {
+        let mut mapper = Mmc1::new(Cartridge::parse(test_rom(1, 8, 0).as_slice()).unwrap());
+
+        // Powers up with the last bank fixed at 0xc000
+        assert_eq!(read(&mut mapper, 0x8000), 0);
+        assert_eq!(read(&mut mapper, 0xc000), 7);
+
+        write_serial(&mut mapper, 0xe000, 5);
+        assert_eq!(read(&mut mapper, 0x8000), 5);
+
+        // A reset partway through throws the bits away
+        mapper.write_memory(0xe000, &[1], &mut ArrayVec::new());
+        mapper.write_memory(0xe000, &[SHIFT_RESET], &mut ArrayVec::new());
+        write_serial(&mut mapper, 0xe000, 2);
+        assert_eq!(read(&mut mapper, 0x8000), 2);
+
+        // 32KiB mode, vertical mirroring
+        write_serial(&mut mapper, 0x8000, 0b0_00_10);
+        assert_eq!(mapper.mirroring(), Mirroring::Vertical);
+        assert_eq!(read(&mut mapper, 0x8000), 2);
+        assert_eq!(read(&mut mapper, 0xc000), 3);
+
+        // PRG RAM goes away when disabled
+        mapper.write_memory(0x6000, &[0x42], &mut ArrayVec::new());
+        assert_eq!(read(&mut mapper, 0x6000), 0x42);
+        write_serial(&mut mapper, 0xe000, 0x10);
+        assert_eq!(read(&mut mapper, 0x6000), 0);
+    }
+
+    #[test]
+    fn snapshots_keep_banks_and_prg_ram() {
+        let rom = test_rom(1, 8, 0);
+        let mut mapper = Mmc1::new(Cartridge::parse(rom.as_slice()).unwrap());
+
+        write_serial(&mut mapper, 0xe000, 5);
+        mapper.write_memory(0x6000, &[0x42], &mut ArrayVec::new());
+        // Halfway through shifting in the next value
+        mapper.write_memory(0xe000, &[1], &mut ArrayVec::new());
+        let state = mapper.save_snapshot();
+
+        let mut restored = Mmc1::new(Cartridge::parse(rom.as_slice()).unwrap());
+        restored.load_snapshot(state);
+        assert_eq!(read(&mut restored, 0x8000), 5);
+        assert_eq!(read(&mut restored, 0x6000), 0x42);
+
+        for bit in 1..5 {
+            restored.write_memory(0xe000, &[1 >> bit], &mut ArrayVec::new());
+        }
+        assert_eq!(read(&mut restored, 0x8000), 1);
+    }
+}
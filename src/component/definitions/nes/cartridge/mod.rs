@@ -0,0 +1,381 @@
+use crate::{
+    component::{memory::MemoryComponent, snapshot::SnapshotableComponent},
+    rom::{RomId, RomManager, RomRequirement},
+};
+use serde::{Deserialize, Serialize};
+use std::io::Read;
+use thiserror::Error;
+
+pub mod mmc1;
+pub mod nrom;
+pub mod uxrom;
+
+// https://www.nesdev.org/wiki/INES
+// https://www.nesdev.org/wiki/NES_2.0
+
+const HEADER_MAGIC: [u8; 4] = *b"NES\x1a";
+pub const HEADER_SIZE: usize = 16;
+pub const TRAINER_SIZE: usize = 512;
+pub const PRG_ROM_UNIT: usize = 0x4000;
+pub const CHR_ROM_UNIT: usize = 0x2000;
+/// What iNES assumes when the header doesn't say
+const DEFAULT_PRG_RAM_SIZE: usize = 0x2000;
+const DEFAULT_CHR_RAM_SIZE: usize = 0x2000;
+
+/// Where PRG RAM starts on the CPU bus, everything the cartridge answers to is at or above this
+pub const CARTRIDGE_START: usize = 0x6000;
+pub const PRG_ROM_START: usize = 0x8000;
+
+#[derive(Error, Debug)]
+pub enum CartridgeError {
+    #[error("Not a iNES file")]
+    BadMagic,
+    #[error("The file is shorter than the header says it is")]
+    Truncated,
+    #[error("Could not read the ROM: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("ROM {0} could not be found")]
+    Missing(RomId),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum HeaderFormat {
+    /// Includes the archaic variant where bytes 7 to 15 are garbage
+    INes,
+    Nes2,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Mirroring {
+    /// The top and bottom nametables are different, for vertical scrolling
+    Horizontal,
+    /// The left and right nametables are different, for horizontal scrolling
+    Vertical,
+    SingleScreenLower,
+    SingleScreenUpper,
+    /// The cartridge has its own nametable memory
+    FourScreen,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct NesHeader {
+    pub format: HeaderFormat,
+    pub mapper: u16,
+    pub submapper: u8,
+    /// All of these are in bytes
+    pub prg_rom_size: usize,
+    pub chr_rom_size: usize,
+    pub prg_ram_size: usize,
+    /// Used instead of CHR ROM when the cartridge has none
+    pub chr_ram_size: usize,
+    pub mirroring: Mirroring,
+    /// PRG RAM is kept across power cycles
+    pub battery: bool,
+    /// 512 bytes between the header and PRG ROM, loaded at 0x7000 by some copiers
+    pub trainer: bool,
+}
+
+impl NesHeader {
+    pub fn parse(header: &[u8; HEADER_SIZE]) -> Result<Self, CartridgeError> {
+        if header[0..4] != HEADER_MAGIC {
+            return Err(CartridgeError::BadMagic);
+        }
+
+        let format = if header[7] & 0x0c == 0x08 {
+            HeaderFormat::Nes2
+        } else {
+            HeaderFormat::INes
+        };
+
+        let mirroring = if header[6] & 0x08 != 0 {
+            Mirroring::FourScreen
+        } else if header[6] & 0x01 != 0 {
+            Mirroring::Vertical
+        } else {
+            Mirroring::Horizontal
+        };
+
+        let mut mapper = (header[6] >> 4) as u16;
+
+        match format {
+            HeaderFormat::INes => {
+                // Old dumping tools wrote their names over the end of the header, which ruins the upper mapper nibble
+                if header[12..16].iter().all(|byte| *byte == 0) {
+                    mapper |= (header[7] & 0xf0) as u16;
+                }
+
+                let chr_rom_size = header[5] as usize * CHR_ROM_UNIT;
+
+                Ok(Self {
+                    format,
+                    mapper,
+                    submapper: 0,
+                    prg_rom_size: header[4] as usize * PRG_ROM_UNIT,
+                    chr_rom_size,
+                    prg_ram_size: (header[8] as usize * 0x2000).max(DEFAULT_PRG_RAM_SIZE),
+                    chr_ram_size: if chr_rom_size == 0 {
+                        DEFAULT_CHR_RAM_SIZE
+                    } else {
+                        0
+                    },
+                    mirroring,
+                    battery: header[6] & 0x02 != 0,
+                    trainer: header[6] & 0x04 != 0,
+                })
+            }
+            HeaderFormat::Nes2 => {
+                mapper |= (header[7] & 0xf0) as u16 | ((header[8] & 0x0f) as u16) << 8;
+
+                // Volatile and battery backed RAM are lumped together, the battery bit says which matters
+                let ram_size = |shift: u8| if shift == 0 { 0 } else { 64 << shift };
+
+                Ok(Self {
+                    format,
+                    mapper,
+                    submapper: header[8] >> 4,
+                    prg_rom_size: nes2_rom_size(header[4], header[9] & 0x0f, PRG_ROM_UNIT),
+                    chr_rom_size: nes2_rom_size(header[5], header[9] >> 4, CHR_ROM_UNIT),
+                    prg_ram_size: ram_size(header[10] & 0x0f) + ram_size(header[10] >> 4),
+                    chr_ram_size: ram_size(header[11] & 0x0f) + ram_size(header[11] >> 4),
+                    mirroring,
+                    battery: header[6] & 0x02 != 0,
+                    trainer: header[6] & 0x04 != 0,
+                })
+            }
+        }
+    }
+
+    /// Reads just the header, for when the mapper needs to be picked before the cartridge is loaded
+    pub fn load(rom_manager: &RomManager, rom_id: RomId) -> Result<Self, CartridgeError> {
+        let mut rom = rom_manager
+            .open(rom_id, RomRequirement::Required)
+            .ok_or(CartridgeError::Missing(rom_id))?;
+
+        let mut header = [0; HEADER_SIZE];
+        rom.read_exact(&mut header)?;
+
+        Self::parse(&header)
+    }
+}
+
+/// NES 2.0 sizes are either a plain count of units or, when the top nibble is all ones, a exponent and multiplier
+fn nes2_rom_size(low: u8, high: u8, unit: usize) -> usize {
+    if high == 0x0f {
+        let exponent = low >> 2;
+        let multiplier = (low & 0b11) as usize * 2 + 1;
+
+        (1usize << exponent) * multiplier
+    } else {
+        ((high as usize) << 8 | low as usize) * unit
+    }
+}
+
+/// The contents of a cartridge, which mappers bank into the CPU and PPU buses
+pub struct Cartridge {
+    pub header: NesHeader,
+    pub prg_rom: Vec<u8>,
+    pub prg_ram: Vec<u8>,
+    /// CHR ROM, or CHR RAM if the cartridge has no ROM
+    pub chr: Vec<u8>,
+}
+
+impl Cartridge {
+    pub fn parse(mut rom: impl Read) -> Result<Self, CartridgeError> {
+        let mut header = [0; HEADER_SIZE];
+        rom.read_exact(&mut header)?;
+        let header = NesHeader::parse(&header)?;
+
+        if header.trainer {
+            rom.read_exact(&mut [0; TRAINER_SIZE])?;
+        }
+
+        let mut prg_rom = vec![0; header.prg_rom_size];
+        rom.read_exact(&mut prg_rom)
+            .map_err(|_| CartridgeError::Truncated)?;
+
+        let chr = if header.chr_rom_size == 0 {
+            vec![0; header.chr_ram_size]
+        } else {
+            let mut chr_rom = vec![0; header.chr_rom_size];
+            rom.read_exact(&mut chr_rom)
+                .map_err(|_| CartridgeError::Truncated)?;
+
+            chr_rom
+        };
+
+        Ok(Self {
+            prg_ram: vec![0; header.prg_ram_size],
+            prg_rom,
+            chr,
+            header,
+        })
+    }
+
+    pub fn load(rom_manager: &RomManager, rom_id: RomId) -> Result<Self, CartridgeError> {
+        let rom = rom_manager
+            .open(rom_id, RomRequirement::Required)
+            .ok_or(CartridgeError::Missing(rom_id))?;
+
+        Self::parse(std::io::BufReader::new(rom))
+    }
+
+    pub fn chr_is_ram(&self) -> bool {
+        self.header.chr_rom_size == 0
+    }
+
+    /// Reads from a PRG ROM bank, bank numbers wrap around like the unconnected address lines would make them. A ROM
+    /// smaller than a bank repeats to fill it
+    pub fn read_prg_rom(&self, bank_size: usize, bank: usize, offset: usize) -> u8 {
+        let banks = (self.prg_rom.len() / bank_size).max(1);
+
+        self.prg_rom[((bank % banks) * bank_size + offset % bank_size) % self.prg_rom.len()]
+    }
+
+    pub fn read_chr(&self, bank_size: usize, bank: usize, offset: usize) -> u8 {
+        let banks = (self.chr.len() / bank_size).max(1);
+
+        self.chr[(bank % banks) * bank_size + offset % bank_size]
+    }
+
+    /// Only does anything for CHR RAM
+    pub fn write_chr(&mut self, bank_size: usize, bank: usize, offset: usize, value: u8) {
+        if !self.chr_is_ram() {
+            return;
+        }
+
+        let banks = (self.chr.len() / bank_size).max(1);
+        self.chr[(bank % banks) * bank_size + offset % bank_size] = value;
+    }
+
+    /// Reading PRG RAM on a cartridge without any gets whatever was last on the bus, which isn't tracked
+    pub fn read_prg_ram(&self, address: usize) -> u8 {
+        if self.prg_ram.is_empty() {
+            return 0;
+        }
+
+        self.prg_ram[(address - CARTRIDGE_START) % self.prg_ram.len()]
+    }
+
+    pub fn write_prg_ram(&mut self, address: usize, value: u8) {
+        if self.prg_ram.is_empty() {
+            return;
+        }
+
+        let length = self.prg_ram.len();
+        self.prg_ram[(address - CARTRIDGE_START) % length] = value;
+    }
+
+    pub fn save_snapshot(&self) -> CartridgeSnapshot {
+        CartridgeSnapshot {
+            prg_ram: self.prg_ram.clone(),
+            chr_ram: self.chr_is_ram().then(|| self.chr.clone()),
+        }
+    }
+
+    pub fn load_snapshot(&mut self, snapshot: CartridgeSnapshot) {
+        // This also does size validation
+        self.prg_ram.copy_from_slice(&snapshot.prg_ram);
+
+        if let Some(chr_ram) = snapshot.chr_ram {
+            self.chr.copy_from_slice(&chr_ram);
+        }
+    }
+}
+
+/// The parts of a cartridge that change as it runs, the ROMs are loaded again anyway
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CartridgeSnapshot {
+    pub prg_ram: Vec<u8>,
+    pub chr_ram: Option<Vec<u8>>,
+}
+
+/// A cartridge board, which sits on the CPU bus from [CARTRIDGE_START] up and also answers to the PPU for pattern
+/// tables. Bank registers are state too, so every board saves them in snapshots
+// Nothing talks to the PPU side until there is a PPU
+#[allow(dead_code)]
+pub trait Mapper: MemoryComponent + SnapshotableComponent {
+    fn mirroring(&self) -> Mirroring;
+    /// The PPU reading the pattern tables, 0x0000 to 0x1fff
+    fn read_chr(&mut self, address: u16) -> u8;
+    fn write_chr(&mut self, address: u16, value: u8);
+}
+
+#[derive(Debug, Serialize)]
+pub struct NesCartridgeConfig {
+    pub rom_id: RomId,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    pub(super) fn test_rom(mapper: u8, prg_banks: u8, chr_banks: u8) -> Vec<u8> {
+        let mut rom = vec![
+            b'N',
+            b'E',
+            b'S',
+            0x1a,
+            prg_banks,
+            chr_banks,
+            (mapper & 0x0f) << 4 | 0x01,
+            mapper & 0xf0,
+        ];
+        rom.resize(HEADER_SIZE, 0);
+
+        // Tag each PRG bank with its number so banking can be checked
+        for bank in 0..prg_banks {
+            rom.extend(std::iter::repeat_n(bank, PRG_ROM_UNIT));
+        }
+        rom.extend(std::iter::repeat_n(0xcc, chr_banks as usize * CHR_ROM_UNIT));
+
+        rom
+    }
+
+    #[test]
+    fn ines_header() {
+        let cartridge = Cartridge::parse(test_rom(1, 8, 0).as_slice()).unwrap();
+
+        assert_eq!(cartridge.header.format, HeaderFormat::INes);
+        assert_eq!(cartridge.header.mapper, 1);
+        assert_eq!(cartridge.header.mirroring, Mirroring::Vertical);
+        assert_eq!(cartridge.prg_rom.len(), 8 * PRG_ROM_UNIT);
+        assert!(cartridge.chr_is_ram());
+        assert_eq!(cartridge.chr.len(), DEFAULT_CHR_RAM_SIZE);
+        assert_eq!(cartridge.read_prg_rom(PRG_ROM_UNIT, 9, 0), 1);
+
+        let mut truncated = test_rom(0, 2, 1);
+        truncated.truncate(HEADER_SIZE + PRG_ROM_UNIT);
+        assert!(matches!(
+            Cartridge::parse(truncated.as_slice()),
+            Err(CartridgeError::Truncated)
+        ));
+        assert!(matches!(
+            NesHeader::parse(&[0; HEADER_SIZE]),
+            Err(CartridgeError::BadMagic)
+        ));
+    }
+
+    #[test]
+    fn nes2_header() {
+        let mut header = [0; HEADER_SIZE];
+        header[0..4].copy_from_slice(&HEADER_MAGIC);
+        header[4] = 0x02;
+        // Exponent notation for CHR, 2^3 * 3
+        header[5] = 0b0000_1101;
+        header[6] = 0x42;
+        header[7] = 0x08 | 0x10;
+        header[8] = 0x31;
+        header[9] = 0xf0;
+        header[10] = 0x70;
+
+        let header = NesHeader::parse(&header).unwrap();
+
+        assert_eq!(header.format, HeaderFormat::Nes2);
+        assert_eq!(header.mapper, 0x114);
+        assert_eq!(header.submapper, 3);
+        assert_eq!(header.prg_rom_size, 2 * PRG_ROM_UNIT);
+        assert_eq!(header.chr_rom_size, 24);
+        assert_eq!(header.prg_ram_size, 0x2000);
+        assert!(header.battery);
+    }
+}
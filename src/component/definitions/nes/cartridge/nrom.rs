@@ -0,0 +1,120 @@
+use super::{
+    Cartridge, CartridgeSnapshot, Mapper, Mirroring, NesCartridgeConfig, CARTRIDGE_START,
+    CHR_ROM_UNIT, PRG_ROM_START,
+};
+use crate::{
+    component::{
+        memory::{MemoryComponent, PreviewMemoryRecord, ReadMemoryRecord, WriteMemoryRecord},
+        snapshot::SnapshotableComponent,
+        Component, ComponentVersion, FromConfig, SemanticVersion,
+    },
+    rom::RomManager,
+};
+use arrayvec::ArrayVec;
+use std::{ops::Range, sync::Arc};
+
+/// Mapper 0, no banking at all and a 16KiB PRG ROM is mirrored to fill the space
+pub struct Nrom {
+    cartridge: Cartridge,
+}
+
+impl Nrom {
+    pub fn new(cartridge: Cartridge) -> Self {
+        Self { cartridge }
+    }
+
+    fn read(&self, address: usize) -> u8 {
+        if address < PRG_ROM_START {
+            self.cartridge.read_prg_ram(address)
+        } else {
+            self.cartridge
+                .read_prg_rom(0x8000, 0, address - PRG_ROM_START)
+        }
+    }
+}
+
+impl Component for Nrom {}
+
+impl SnapshotableComponent for Nrom {
+    fn save_snapshot(&mut self) -> rmpv::Value {
+        rmpv::ext::to_value(self.cartridge.save_snapshot()).unwrap()
+    }
+
+    fn load_snapshot(&mut self, state: rmpv::Value) {
+        self.cartridge
+            .load_snapshot(rmpv::ext::from_value::<CartridgeSnapshot>(state).unwrap());
+    }
+}
+
+impl ComponentVersion for Nrom {
+    const NAME: &'static str = "nrom";
+    const VERSION: SemanticVersion = SemanticVersion::new(1, 0, 0);
+}
+
+impl FromConfig for Nrom {
+    type Config = NesCartridgeConfig;
+
+    fn from_config(rom_manager: Arc<RomManager>, config: Self::Config) -> Self {
+        Self::new(Cartridge::load(&rom_manager, config.rom_id).unwrap())
+    }
+}
+
+impl MemoryComponent for Nrom {
+    fn assigned_memory_range(&self) -> Range<usize> {
+        CARTRIDGE_START..0x10000
+    }
+
+    fn read_memory(
+        &mut self,
+        address: usize,
+        buffer: &mut [u8],
+        _records: &mut ArrayVec<(Range<usize>, ReadMemoryRecord), 8>,
+    ) -> u64 {
+        for (address, value) in (address..).zip(buffer.iter_mut()) {
+            *value = self.read(address);
+        }
+
+        0
+    }
+
+    fn write_memory(
+        &mut self,
+        address: usize,
+        buffer: &[u8],
+        _records: &mut ArrayVec<(Range<usize>, WriteMemoryRecord), 8>,
+    ) -> u64 {
+        for (address, value) in (address..).zip(buffer.iter()) {
+            if address < PRG_ROM_START {
+                self.cartridge.write_prg_ram(address, *value);
+            }
+        }
+
+        0
+    }
+
+    fn preview_memory(
+        &mut self,
+        address: usize,
+        buffer: &mut [u8],
+        _records: &mut ArrayVec<(Range<usize>, PreviewMemoryRecord), 8>,
+    ) {
+        for (address, value) in (address..).zip(buffer.iter_mut()) {
+            *value = self.read(address);
+        }
+    }
+}
+
+impl Mapper for Nrom {
+    fn mirroring(&self) -> Mirroring {
+        self.cartridge.header.mirroring
+    }
+
+    fn read_chr(&mut self, address: u16) -> u8 {
+        self.cartridge.read_chr(CHR_ROM_UNIT, 0, address as usize)
+    }
+
+    fn write_chr(&mut self, address: u16, value: u8) {
+        self.cartridge
+            .write_chr(CHR_ROM_UNIT, 0, address as usize, value);
+    }
+}
@@ -0,0 +1,148 @@
+use super::{
+    Cartridge, CartridgeSnapshot, Mapper, Mirroring, NesCartridgeConfig, CARTRIDGE_START,
+    CHR_ROM_UNIT, PRG_ROM_START, PRG_ROM_UNIT,
+};
+use crate::{
+    component::{
+        memory::{MemoryComponent, PreviewMemoryRecord, ReadMemoryRecord, WriteMemoryRecord},
+        snapshot::SnapshotableComponent,
+        Component, ComponentVersion, FromConfig, SemanticVersion,
+    },
+    rom::RomManager,
+};
+use arrayvec::ArrayVec;
+use serde::{Deserialize, Serialize};
+use std::{ops::Range, sync::Arc};
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UxromSnapshot {
+    pub cartridge: CartridgeSnapshot,
+    pub bank: u8,
+}
+
+/// Mapper 2, a switchable 16KiB bank at 0x8000 and the last bank fixed at 0xc000
+pub struct Uxrom {
+    cartridge: Cartridge,
+    bank: u8,
+}
+
+impl Uxrom {
+    pub fn new(cartridge: Cartridge) -> Self {
+        Self { cartridge, bank: 0 }
+    }
+
+    fn read(&self, address: usize) -> u8 {
+        match address {
+            ..PRG_ROM_START => self.cartridge.read_prg_ram(address),
+            PRG_ROM_START..0xc000 => self.cartridge.read_prg_rom(
+                PRG_ROM_UNIT,
+                self.bank as usize,
+                address - PRG_ROM_START,
+            ),
+            _ => self.cartridge.read_prg_rom(
+                PRG_ROM_UNIT,
+                self.cartridge.prg_rom.len() / PRG_ROM_UNIT - 1,
+                address - 0xc000,
+            ),
+        }
+    }
+}
+
+impl Component for Uxrom {
+    fn reset(&mut self) {
+        self.bank = 0;
+    }
+}
+
+impl SnapshotableComponent for Uxrom {
+    fn save_snapshot(&mut self) -> rmpv::Value {
+        let state = UxromSnapshot {
+            cartridge: self.cartridge.save_snapshot(),
+            bank: self.bank,
+        };
+
+        rmpv::ext::to_value(&state).unwrap()
+    }
+
+    fn load_snapshot(&mut self, state: rmpv::Value) {
+        let state = rmpv::ext::from_value::<UxromSnapshot>(state).unwrap();
+
+        self.cartridge.load_snapshot(state.cartridge);
+        self.bank = state.bank;
+    }
+}
+
+impl ComponentVersion for Uxrom {
+    const NAME: &'static str = "uxrom";
+    const VERSION: SemanticVersion = SemanticVersion::new(1, 0, 0);
+}
+
+impl FromConfig for Uxrom {
+    type Config = NesCartridgeConfig;
+
+    fn from_config(rom_manager: Arc<RomManager>, config: Self::Config) -> Self {
+        Self::new(Cartridge::load(&rom_manager, config.rom_id).unwrap())
+    }
+}
+
+impl MemoryComponent for Uxrom {
+    fn assigned_memory_range(&self) -> Range<usize> {
+        CARTRIDGE_START..0x10000
+    }
+
+    fn read_memory(
+        &mut self,
+        address: usize,
+        buffer: &mut [u8],
+        _records: &mut ArrayVec<(Range<usize>, ReadMemoryRecord), 8>,
+    ) -> u64 {
+        for (address, value) in (address..).zip(buffer.iter_mut()) {
+            *value = self.read(address);
+        }
+
+        0
+    }
+
+    fn write_memory(
+        &mut self,
+        address: usize,
+        buffer: &[u8],
+        _records: &mut ArrayVec<(Range<usize>, WriteMemoryRecord), 8>,
+    ) -> u64 {
+        for (address, value) in (address..).zip(buffer.iter()) {
+            if address < PRG_ROM_START {
+                self.cartridge.write_prg_ram(address, *value);
+            } else {
+                self.bank = *value;
+            }
+        }
+
+        0
+    }
+
+    fn preview_memory(
+        &mut self,
+        address: usize,
+        buffer: &mut [u8],
+        _records: &mut ArrayVec<(Range<usize>, PreviewMemoryRecord), 8>,
+    ) {
+        for (address, value) in (address..).zip(buffer.iter_mut()) {
+            *value = self.read(address);
+        }
+    }
+}
+
+impl Mapper for Uxrom {
+    fn mirroring(&self) -> Mirroring {
+        self.cartridge.header.mirroring
+    }
+
+    fn read_chr(&mut self, address: u16) -> u8 {
+        self.cartridge.read_chr(CHR_ROM_UNIT, 0, address as usize)
+    }
+
+    fn write_chr(&mut self, address: u16, value: u8) {
+        self.cartridge
+            .write_chr(CHR_ROM_UNIT, 0, address as usize, value);
+    }
+}
@@ -0,0 +1,272 @@
+//! The pixel format every display buffer is in, and conversions into it from what video hardware outputs
+//!
+//! Components convert their native colors or palette indices into [Pixel]s once per frame, and backends only ever
+//! convert [Pixel]s into whatever the host wants right before presenting
+
+use nalgebra::DMatrix;
+use palette::Srgba;
+use std::borrow::Cow;
+
+/// 8 bits per channel sRGB with straight alpha, laid out like R8G8B8A8_SRGB images on Vulkan
+pub type Pixel = Srgba<u8>;
+
+pub const BLACK: Pixel = Pixel::new(0, 0, 0, 0xff);
+
+/// A color as some video hardware stores it
+pub trait NativeColor: Copy {
+    fn to_pixel(self) -> Pixel;
+}
+
+impl NativeColor for Pixel {
+    fn to_pixel(self) -> Pixel {
+        self
+    }
+}
+
+/// Scales a channel of the given width to 8 bits, so the brightest value stays the brightest
+const fn expand_channel(value: u16, bits: u32) -> u8 {
+    let max = (1 << bits) - 1;
+    let value = value & max;
+
+    ((value as u32 * 0xff + max as u32 / 2) / max as u32) as u8
+}
+
+/// 5 bits per channel with red in the lowest bits, used by the Super Nintendo and Gameboy Advance
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Bgr555(pub u16);
+
+impl NativeColor for Bgr555 {
+    fn to_pixel(self) -> Pixel {
+        Pixel::new(
+            expand_channel(self.0, 5),
+            expand_channel(self.0 >> 5, 5),
+            expand_channel(self.0 >> 10, 5),
+            0xff,
+        )
+    }
+}
+
+/// 5 bits for red and blue and 6 for green, with red in the highest bits
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rgb565(pub u16);
+
+impl NativeColor for Rgb565 {
+    fn to_pixel(self) -> Pixel {
+        Pixel::new(
+            expand_channel(self.0 >> 11, 5),
+            expand_channel(self.0 >> 5, 6),
+            expand_channel(self.0, 5),
+            0xff,
+        )
+    }
+}
+
+/// 4 bits per channel with red in the lowest bits, used by the Game Gear
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Bgr444(pub u16);
+
+impl NativeColor for Bgr444 {
+    fn to_pixel(self) -> Pixel {
+        Pixel::new(
+            expand_channel(self.0, 4),
+            expand_channel(self.0 >> 4, 4),
+            expand_channel(self.0 >> 8, 4),
+            0xff,
+        )
+    }
+}
+
+/// 2 bits per channel with red in the lowest bits, used by the Master System
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Bgr222(pub u8);
+
+impl NativeColor for Bgr222 {
+    fn to_pixel(self) -> Pixel {
+        let value = self.0 as u16;
+
+        Pixel::new(
+            expand_channel(value, 2),
+            expand_channel(value >> 2, 2),
+            expand_channel(value >> 4, 2),
+            0xff,
+        )
+    }
+}
+
+/// The colors a palette index can stand for, already converted so looking one up is free
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Palette {
+    colors: Vec<Pixel>,
+}
+
+impl Palette {
+    pub fn new(colors: impl IntoIterator<Item = impl NativeColor>) -> Self {
+        Self {
+            colors: colors.into_iter().map(NativeColor::to_pixel).collect(),
+        }
+    }
+
+    /// Indices past the end are black, real hardware never has them so they can only come from a bug
+    pub fn get(&self, index: u8) -> Pixel {
+        self.colors.get(index as usize).copied().unwrap_or(BLACK)
+    }
+
+    pub fn set(&mut self, index: u8, color: impl NativeColor) {
+        let index = index as usize;
+
+        if index >= self.colors.len() {
+            self.colors.resize(index + 1, BLACK);
+        }
+        self.colors[index] = color.to_pixel();
+    }
+
+    pub fn colors(&self) -> &[Pixel] {
+        &self.colors
+    }
+}
+
+/// A frame as palette indices, for hardware that picks every pixel from a palette
+///
+/// Indexed by column and then row like every other screen buffer
+#[derive(Debug, Clone, PartialEq)]
+pub struct IndexedFramebuffer {
+    pub indices: DMatrix<u8>,
+    pub palette: Palette,
+}
+
+impl IndexedFramebuffer {
+    pub fn new(width: usize, height: usize, palette: Palette) -> Self {
+        Self {
+            indices: DMatrix::zeros(width, height),
+            palette,
+        }
+    }
+
+    /// Writes the frame into a buffer of the same size
+    pub fn resolve_into(&self, destination: &mut DMatrix<Pixel>) {
+        assert_eq!(self.indices.shape(), destination.shape());
+
+        for (index, pixel) in self.indices.iter().zip(destination.iter_mut()) {
+            *pixel = self.palette.get(*index);
+        }
+    }
+
+    pub fn resolve(&self) -> DMatrix<Pixel> {
+        let mut destination =
+            DMatrix::from_element(self.indices.nrows(), self.indices.ncols(), BLACK);
+        self.resolve_into(&mut destination);
+
+        destination
+    }
+}
+
+/// What the software backends get from display components
+///
+/// Indexed buffers are expanded through their palette as they are drawn, so palette based machines never build a full
+/// color frame and swapping the palette recolors the frame at no cost
+#[derive(Debug, Clone, PartialEq)]
+pub enum DisplayBuffer {
+    Direct(DMatrix<Pixel>),
+    Indexed(IndexedFramebuffer),
+}
+
+impl DisplayBuffer {
+    /// Width then height
+    #[cfg(any(feature = "frontend", test))]
+    pub fn dimensions(&self) -> (usize, usize) {
+        match self {
+            DisplayBuffer::Direct(pixels) => pixels.shape(),
+            DisplayBuffer::Indexed(framebuffer) => framebuffer.indices.shape(),
+        }
+    }
+
+    #[cfg(any(feature = "frontend", test))]
+    pub fn pixel(&self, x: usize, y: usize) -> Pixel {
+        match self {
+            DisplayBuffer::Direct(pixels) => pixels[(x, y)],
+            DisplayBuffer::Indexed(framebuffer) => {
+                framebuffer.palette.get(framebuffer.indices[(x, y)])
+            }
+        }
+    }
+
+    /// The frame as pixels, which is only a copy for indexed buffers
+    pub fn to_pixels(&self) -> Cow<'_, DMatrix<Pixel>> {
+        match self {
+            DisplayBuffer::Direct(pixels) => Cow::Borrowed(pixels),
+            DisplayBuffer::Indexed(framebuffer) => Cow::Owned(framebuffer.resolve()),
+        }
+    }
+}
+
+impl From<DMatrix<Pixel>> for DisplayBuffer {
+    fn from(pixels: DMatrix<Pixel>) -> Self {
+        Self::Direct(pixels)
+    }
+}
+
+impl From<IndexedFramebuffer> for DisplayBuffer {
+    fn from(framebuffer: IndexedFramebuffer) -> Self {
+        Self::Indexed(framebuffer)
+    }
+}
+
+/// Converts a whole frame of native colors into a buffer of the same size
+pub fn convert_framebuffer<C: NativeColor + nalgebra::Scalar>(
+    source: &DMatrix<C>,
+    destination: &mut DMatrix<Pixel>,
+) {
+    assert_eq!(source.shape(), destination.shape());
+
+    for (color, pixel) in source.iter().zip(destination.iter_mut()) {
+        *pixel = color.to_pixel();
+    }
+}
+
+/// What softbuffer presents, red in bits 16 to 23 and the top byte unused
+#[cfg(any(feature = "frontend", test))]
+pub fn to_xrgb8888(pixel: Pixel) -> u32 {
+    u32::from_be_bytes([0, pixel.red, pixel.green, pixel.blue])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn native_colors_reach_full_brightness() {
+        assert_eq!(
+            Bgr555(0x7fff).to_pixel(),
+            Pixel::new(0xff, 0xff, 0xff, 0xff)
+        );
+        assert_eq!(Bgr555(0x001f).to_pixel(), Pixel::new(0xff, 0, 0, 0xff));
+        assert_eq!(Rgb565(0x07e0).to_pixel(), Pixel::new(0, 0xff, 0, 0xff));
+        assert_eq!(Bgr444(0x0f00).to_pixel(), Pixel::new(0, 0, 0xff, 0xff));
+        assert_eq!(
+            Bgr222(0b01_10_11).to_pixel(),
+            Pixel::new(0xff, 0xaa, 0x55, 0xff)
+        );
+
+        assert_eq!(to_xrgb8888(Pixel::new(0x12, 0x34, 0x56, 0xff)), 0x00123456);
+    }
+
+    #[test]
+    fn indexed_framebuffers_resolve_through_their_palette() {
+        let mut framebuffer =
+            IndexedFramebuffer::new(2, 1, Palette::new([Bgr222(0b000000), Bgr222(0b000011)]));
+        framebuffer.indices[(1, 0)] = 1;
+        assert_eq!(framebuffer.resolve()[(1, 0)], Pixel::new(0xff, 0, 0, 0xff));
+
+        // Swapping the palette recolors the frame without redrawing it
+        framebuffer.palette.set(1, Bgr222(0b110000));
+        assert_eq!(framebuffer.resolve()[(1, 0)], Pixel::new(0, 0, 0xff, 0xff));
+        assert_eq!(framebuffer.palette.get(7), BLACK);
+
+        let display_buffer = DisplayBuffer::from(framebuffer);
+        assert_eq!(display_buffer.dimensions(), (2, 1));
+        assert_eq!(
+            display_buffer.pixel(1, 0),
+            display_buffer.to_pixels()[(1, 0)]
+        );
+    }
+}
@@ -1,7 +1,38 @@
 use super::Component;
 use crate::runtime::RenderingBackend;
+#[cfg(feature = "frontend")]
+use color::Pixel;
+#[cfg(feature = "frontend")]
+use nalgebra::DMatrix;
+
+// Most of these are for video hardware that isn't emulated yet
+#[allow(dead_code)]
+pub mod color;
+#[allow(dead_code)]
+pub mod tile;
 
 pub trait DisplayComponent<R: RenderingBackend>: Component {
     fn initialize_display(&mut self, initialization_data: R::ComponentInitializationData);
     fn display_data(&self) -> &R::ComponentDisplayBuffer;
+
+    /// Don't render the next count frames while still emulating them, for when the host can't keep up. Components
+    /// that can't skip ignore this
+    #[cfg(feature = "frontend")]
+    fn skip_frames(&mut self, _count: u32) {}
+
+    /// Drops whatever the component holds on the rendering backend, as the machine is shut down while the backend
+    /// still exists. Nothing else is called after this
+    fn release_display(&mut self) {}
+
+    /// Copy of the current image for debugging, if the backend can provide one
+    #[cfg(feature = "frontend")]
+    fn dump_display_data(&mut self) -> Option<DMatrix<Pixel>> {
+        None
+    }
+
+    /// Replaces the current image for debugging, returning false if the backend can't do this
+    #[cfg(feature = "frontend")]
+    fn import_display_data(&mut self, _buffer: DMatrix<Pixel>) -> bool {
+        false
+    }
 }
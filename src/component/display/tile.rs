@@ -0,0 +1,359 @@
+//! Shared pieces for tile based video hardware, so each PPU only has to describe its own memory layout and quirks
+
+use super::color::Pixel;
+use nalgebra::DMatrix;
+
+/// Every tile based system here uses 8 pixel wide tiles
+pub const TILE_WIDTH: usize = 8;
+
+/// How the bitplanes of a 8x8 tile are laid out in memory
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TileFormat {
+    /// Two bitplanes of 8 bytes one after the other, used by the NES
+    Planar2bpp,
+    /// Two bitplanes interleaved a row at a time, used by the Gameboy
+    Interleaved2bpp,
+    /// Four bitplanes interleaved a row at a time, used by the Master System and Game Gear
+    Interleaved4bpp,
+}
+
+impl TileFormat {
+    pub const fn bits_per_pixel(&self) -> usize {
+        match self {
+            TileFormat::Planar2bpp | TileFormat::Interleaved2bpp => 2,
+            TileFormat::Interleaved4bpp => 4,
+        }
+    }
+
+    /// How many bytes a 8x8 tile takes up, which is also the stride between tiles
+    pub const fn tile_size(&self) -> usize {
+        self.bits_per_pixel() * 8
+    }
+
+    /// Where the byte for a plane of a row is relative to the start of the tile
+    const fn plane_offset(&self, row: usize, plane: usize) -> usize {
+        match self {
+            TileFormat::Planar2bpp => plane * 8 + row,
+            TileFormat::Interleaved2bpp => row * 2 + plane,
+            TileFormat::Interleaved4bpp => row * 4 + plane,
+        }
+    }
+
+    /// Decodes one row of a tile into color indices, left most pixel first
+    ///
+    /// The tile data must be at least [TileFormat::tile_size] long
+    pub fn decode_row(&self, tile: &[u8], row: usize) -> [u8; TILE_WIDTH] {
+        debug_assert!(row < 8);
+
+        let mut pixels = [0; TILE_WIDTH];
+
+        for plane in 0..self.bits_per_pixel() {
+            let bits = tile[self.plane_offset(row, plane)];
+
+            for (x, pixel) in pixels.iter_mut().enumerate() {
+                // The most significant bit is the left most pixel
+                *pixel |= ((bits >> (7 - x)) & 1) << plane;
+            }
+        }
+
+        pixels
+    }
+
+    /// Decodes a whole tile into color indices, indexed by row and then column
+    pub fn decode(&self, tile: &[u8]) -> [[u8; TILE_WIDTH]; 8] {
+        std::array::from_fn(|row| self.decode_row(tile, row))
+    }
+}
+
+/// Fetches a row of a tile, taking care of flipping so callers can treat every tile the same
+pub fn fetch_tile_row(
+    format: TileFormat,
+    tile_data: &[u8],
+    tile_index: usize,
+    row: usize,
+    flip_horizontal: bool,
+    flip_vertical: bool,
+) -> [u8; TILE_WIDTH] {
+    let start = tile_index * format.tile_size();
+    let row = if flip_vertical { 7 - row } else { row };
+
+    let mut pixels = format.decode_row(&tile_data[start..start + format.tile_size()], row);
+
+    if flip_horizontal {
+        pixels.reverse();
+    }
+
+    pixels
+}
+
+/// Looks up a color index in one of several equally sized palettes, index 0 of each being transparent is up to the
+/// caller
+pub fn apply_palette(
+    palette: &[Pixel],
+    palette_size: usize,
+    palette_index: u8,
+    color_index: u8,
+) -> Pixel {
+    palette[palette_index as usize * palette_size + color_index as usize]
+}
+
+/// A sprite as the PPU sees it once the attributes are decoded
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Sprite {
+    /// Can be negative for sprites hanging off the left or top
+    pub x: isize,
+    pub y: isize,
+    pub height: usize,
+    pub palette: u8,
+    /// Drawn under any background pixel that isn't color 0
+    pub behind_background: bool,
+}
+
+impl Sprite {
+    pub fn covers_scanline(&self, scanline: usize) -> bool {
+        let scanline = scanline as isize;
+
+        (self.y..self.y + self.height as isize).contains(&scanline)
+    }
+}
+
+/// Picks the sprites on a scanline in the order they appear, returning if there were more than the hardware can show
+///
+/// The NES shows 8, the Gameboy 10 and the Master System 8
+pub fn evaluate_sprites(
+    sprites: &[Sprite],
+    scanline: usize,
+    limit: usize,
+    visible: &mut Vec<usize>,
+) -> bool {
+    visible.clear();
+
+    for (index, sprite) in sprites.iter().enumerate() {
+        if !sprite.covers_scanline(scanline) {
+            continue;
+        }
+
+        if visible.len() == limit {
+            return true;
+        }
+
+        visible.push(index);
+    }
+
+    false
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SpritePixel {
+    pub color_index: u8,
+    pub palette: u8,
+    pub behind_background: bool,
+    /// Which sprite this came from, the NES needs this for sprite 0 hit
+    pub sprite: usize,
+}
+
+/// What ends up on screen for a pixel once background and sprites are combined
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResolvedPixel {
+    Background { color_index: u8, palette: u8 },
+    Sprite(SpritePixel),
+}
+
+/// One line of color indices, built up by the background and sprite passes and then resolved into colors
+#[derive(Debug, Clone)]
+pub struct ScanlineBuffer {
+    background: Vec<(u8, u8)>,
+    sprites: Vec<Option<SpritePixel>>,
+}
+
+impl ScanlineBuffer {
+    pub fn new(width: usize) -> Self {
+        Self {
+            background: vec![(0, 0); width],
+            sprites: vec![None; width],
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.background.len()
+    }
+
+    pub fn clear(&mut self) {
+        self.background.fill((0, 0));
+        self.sprites.fill(None);
+    }
+
+    /// Places a row of background pixels, anything off either edge is dropped so fine scrolling can start off screen
+    pub fn draw_background_row(&mut self, x: isize, pixels: &[u8; TILE_WIDTH], palette: u8) {
+        for (offset, color_index) in pixels.iter().enumerate() {
+            if let Some(slot) = self.slot(x + offset as isize) {
+                self.background[slot] = (*color_index, palette);
+            }
+        }
+    }
+
+    /// Places a row of sprite pixels, color 0 is transparent and whichever sprite was drawn first keeps the pixel
+    ///
+    /// Draw sprites in priority order, which is OAM order on the NES and Master System
+    pub fn draw_sprite_row(
+        &mut self,
+        sprite_index: usize,
+        sprite: &Sprite,
+        pixels: &[u8; TILE_WIDTH],
+    ) {
+        for (offset, color_index) in pixels.iter().enumerate() {
+            if *color_index == 0 {
+                continue;
+            }
+
+            if let Some(slot) = self.slot(sprite.x + offset as isize) {
+                if self.sprites[slot].is_none() {
+                    self.sprites[slot] = Some(SpritePixel {
+                        color_index: *color_index,
+                        palette: sprite.palette,
+                        behind_background: sprite.behind_background,
+                        sprite: sprite_index,
+                    });
+                }
+            }
+        }
+    }
+
+    /// Picks between the background and sprite for a pixel
+    pub fn resolve(&self, x: usize) -> ResolvedPixel {
+        let (color_index, palette) = self.background[x];
+        let background = ResolvedPixel::Background {
+            color_index,
+            palette,
+        };
+
+        match self.sprites[x] {
+            Some(sprite) if !sprite.behind_background || color_index == 0 => {
+                ResolvedPixel::Sprite(sprite)
+            }
+            _ => background,
+        }
+    }
+
+    /// Resolves the whole line into a row of the screen buffer, letting the PPU decide how indices become colors
+    pub fn commit(
+        &self,
+        screen_buffer: &mut DMatrix<Pixel>,
+        scanline: usize,
+        mut color: impl FnMut(ResolvedPixel) -> Pixel,
+    ) {
+        for x in 0..self.width().min(screen_buffer.nrows()) {
+            screen_buffer[(x, scanline)] = color(self.resolve(x));
+        }
+    }
+
+    fn slot(&self, x: isize) -> Option<usize> {
+        usize::try_from(x).ok().filter(|x| *x < self.width())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tile_formats() {
+        // A row with every color: low plane 0b0110_0110, high plane 0b0101_1011
+        let mut planar = [0; 16];
+        planar[3] = 0b0110_0110;
+        planar[8 + 3] = 0b0101_1011;
+        assert_eq!(
+            TileFormat::Planar2bpp.decode_row(&planar, 3),
+            [0, 3, 1, 2, 2, 1, 3, 2]
+        );
+
+        // The same row laid out the Gameboy way
+        let mut interleaved = [0; 16];
+        interleaved[6] = 0b0110_0110;
+        interleaved[7] = 0b0101_1011;
+        assert_eq!(
+            TileFormat::Interleaved2bpp.decode_row(&interleaved, 3),
+            [0, 3, 1, 2, 2, 1, 3, 2]
+        );
+
+        let mut interleaved = [0; 32];
+        interleaved[4..8].copy_from_slice(&[0b1000_0000, 0, 0, 0b1000_0001]);
+        assert_eq!(
+            TileFormat::Interleaved4bpp.decode_row(&interleaved, 1),
+            [9, 0, 0, 0, 0, 0, 0, 8]
+        );
+
+        assert_eq!(
+            fetch_tile_row(TileFormat::Planar2bpp, &planar, 0, 4, true, true),
+            [2, 3, 1, 2, 2, 1, 3, 0]
+        );
+    }
+
+    #[test]
+    fn sprite_priority() {
+        let sprites = [
+            Sprite {
+                x: 0,
+                y: 0,
+                height: 8,
+                palette: 0,
+                behind_background: true,
+            },
+            Sprite {
+                x: 2,
+                y: 0,
+                height: 8,
+                palette: 1,
+                behind_background: false,
+            },
+            Sprite {
+                x: 0,
+                y: 8,
+                height: 8,
+                palette: 2,
+                behind_background: false,
+            },
+        ];
+
+        let mut visible = Vec::new();
+        assert!(!evaluate_sprites(&sprites, 4, 8, &mut visible));
+        assert_eq!(visible, [0, 1]);
+        assert!(evaluate_sprites(&sprites, 4, 1, &mut visible));
+
+        let mut scanline = ScanlineBuffer::new(8);
+        scanline.draw_background_row(-4, &[0, 0, 0, 0, 1, 0, 1, 0], 3);
+        scanline.draw_sprite_row(0, &sprites[0], &[1, 1, 1, 1, 0, 0, 0, 0]);
+        scanline.draw_sprite_row(1, &sprites[1], &[2; TILE_WIDTH]);
+
+        // Behind a opaque background pixel
+        assert_eq!(
+            scanline.resolve(0),
+            ResolvedPixel::Background {
+                color_index: 1,
+                palette: 3
+            }
+        );
+        // Behind a transparent one
+        assert!(matches!(
+            scanline.resolve(1),
+            ResolvedPixel::Sprite(SpritePixel { sprite: 0, .. })
+        ));
+        // The first sprite keeps the pixel even though it would be hidden
+        assert_eq!(
+            scanline.resolve(2),
+            ResolvedPixel::Background {
+                color_index: 1,
+                palette: 3
+            }
+        );
+        assert!(matches!(
+            scanline.resolve(3),
+            ResolvedPixel::Sprite(SpritePixel { sprite: 0, .. })
+        ));
+        // Where the first sprite is transparent the second shows through
+        assert!(matches!(
+            scanline.resolve(5),
+            ResolvedPixel::Sprite(SpritePixel { sprite: 1, .. })
+        ));
+    }
+}
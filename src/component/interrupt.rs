@@ -0,0 +1,27 @@
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
+
+/// A interrupt request line that devices raise and a processor services, cloning it shares the same line
+#[derive(Clone, Debug, Default)]
+pub struct InterruptLine(Arc<AtomicBool>);
+
+impl InterruptLine {
+    pub fn raise(&self) {
+        self.0.store(true, Ordering::Release);
+    }
+
+    pub fn lower(&self) {
+        self.0.store(false, Ordering::Release);
+    }
+
+    pub fn is_raised(&self) -> bool {
+        self.0.load(Ordering::Acquire)
+    }
+
+    /// Lowers the line, returning if it was raised
+    pub fn acknowledge(&self) -> bool {
+        self.0.swap(false, Ordering::AcqRel)
+    }
+}
@@ -2,7 +2,10 @@ use super::Component;
 use arrayvec::ArrayVec;
 use std::{
     ops::Range,
-    sync::{Arc, Mutex},
+    sync::{
+        atomic::{AtomicBool, AtomicU8, Ordering},
+        Arc, Mutex, RwLock,
+    },
 };
 use thiserror::Error;
 
@@ -32,27 +35,6 @@ pub trait MemoryComponent: Component {
     );
 }
 
-pub fn relocate_and_crop_range(from: &Range<usize>, to: &Range<usize>) -> Range<usize> {
-    let from_start = from.start as i128;
-    let from_end = from.end as i128;
-    let to_start = to.start as i128;
-    let to_end = to.end as i128;
-
-    // Calculate the offset between from and to
-    let offset = from_start - to_start;
-
-    // Adjust the start and end of the from range according to the offset
-    let relocated_start = from_start - offset;
-    let relocated_end = from_end - offset;
-
-    // Ensure the relocated range is within the bounds of to
-    let start = relocated_start.max(to_start);
-    let end = relocated_end.min(to_end);
-
-    // Return the resulting range as usize
-    start as usize..end as usize
-}
-
 #[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum ReadMemoryRecord {
     /// Memory could not be read
@@ -74,10 +56,9 @@ pub enum PreviewMemoryRecord {
     /// Memory denied
     Denied,
     /// Memory redirects somewhere else
-    Redirect {
-        offset: usize,
-    },
+    Redirect { offset: usize },
     // Memory here can't be read without an intense calculation or a state change
+    #[allow(dead_code)]
     PreviewImpossible,
 }
 
@@ -87,41 +68,140 @@ pub enum MemoryOperationError {
     Denied(Range<usize>),
     #[error("Memory access is out of bounds")]
     OutOfBounds(Range<usize>),
+    #[error("Memory can't be previewed without changing it")]
+    PreviewImpossible(Range<usize>),
+}
+
+/// What a processor sees when it reads memory nothing answers to, and what happens to writes there
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum OpenBusBehavior {
+    /// Pulled up data lines are the default
+    Fixed(u8),
+    /// Nothing drove the data lines, so they still hold whatever was last on them
+    LastValue,
+    #[allow(dead_code)]
+    Random,
+    /// The access is a error for the processor to surface, for tracking down bad accesses
+    Fault,
 }
 
+impl Default for OpenBusBehavior {
+    fn default() -> Self {
+        Self::Fixed(0xff)
+    }
+}
+
+/// A byte reads give instead of what is in memory, which is how cheats work
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ReadPatch {
+    pub address: usize,
+    pub value: u8,
+    /// Only patches if memory holds this, so codes for banked ROM leave the other banks alone
+    pub compare: Option<u8>,
+}
+
+type MemoryEntry = (Range<usize>, Arc<Mutex<dyn MemoryComponent>>);
+
+/// Where every component sits in the address space
+///
+/// Entries are kept sorted and never overlap, so finding the component behind a address is a binary search instead of
+/// a scan over every mapping
 #[derive(Default)]
 pub struct MemoryTranslationTable {
-    entries: Vec<(Range<usize>, Arc<Mutex<dyn MemoryComponent>>)>,
+    /// Sorted by the start of their range
+    entries: Vec<MemoryEntry>,
+    open_bus: OpenBusBehavior,
+    /// The last byte that went over the bus, for [OpenBusBehavior::LastValue]
+    last_value: AtomicU8,
+    /// Sorted by address
+    read_patches: RwLock<Vec<ReadPatch>>,
+    /// So reads only take the lock while there are patches
+    patched: AtomicBool,
+    /// Games poking ROM or missing hardware do so every frame, so only the first denied access makes it to the event log
+    denied_access_reported: AtomicBool,
 }
 
 impl MemoryTranslationTable {
+    pub fn set_open_bus_behavior(&mut self, open_bus: OpenBusBehavior) {
+        self.open_bus = open_bus;
+    }
+
+    /// Replaces every patch, these apply to reads and previews but never to what is in memory
+    #[cfg(any(feature = "frontend", test))]
+    pub fn set_read_patches(&self, mut read_patches: Vec<ReadPatch>) {
+        read_patches.sort_by_key(|read_patch| read_patch.address);
+        self.patched
+            .store(!read_patches.is_empty(), Ordering::Relaxed);
+        *self.read_patches.write().unwrap() = read_patches;
+    }
+
+    #[inline]
+    fn apply_read_patches(&self, offset: usize, buffer: &mut [u8]) {
+        if !self.patched.load(Ordering::Relaxed) {
+            return;
+        }
+
+        let read_patches = self.read_patches.read().unwrap();
+        let first = read_patches.partition_point(|read_patch| read_patch.address < offset);
+        let end = offset + buffer.len();
+
+        for read_patch in read_patches[first..]
+            .iter()
+            .take_while(|read_patch| read_patch.address < end)
+        {
+            let byte = &mut buffer[read_patch.address - offset];
+
+            if read_patch.compare.is_none_or(|compare| compare == *byte) {
+                *byte = read_patch.value;
+            }
+        }
+    }
+
+    /// Machines are laid out by hand, so a mapping on top of another one is a bug in the machine definition
     pub fn insert(&mut self, range: Range<usize>, component: Arc<Mutex<dyn MemoryComponent>>) {
-        self.entries.push((range, component));
+        assert!(
+            !self.is_overlapped(range.clone()),
+            "Memory mapped to {:#x?} overlaps with a existing mapping",
+            range
+        );
+
+        let index = self
+            .entries
+            .partition_point(|(existing_range, _)| existing_range.start < range.start);
+        self.entries.insert(index, (range, component));
+    }
+
+    /// Index of the first entry that ends after the address
+    #[inline]
+    fn first_ending_after(&self, address: usize) -> usize {
+        self.entries
+            .partition_point(|(range, _)| range.end <= address)
     }
 
     /// Get the component at a given address
+    #[cfg(any(feature = "frontend", test))]
     pub fn get(&self, address: usize) -> Option<Arc<Mutex<dyn MemoryComponent>>> {
         self.entries
-            .iter()
-            .find(|(range, _)| range.contains(&address))
+            .get(self.first_ending_after(address))
+            .filter(|(range, _)| range.contains(&address))
             .map(|(_, component)| component.clone())
     }
 
     /// Check if an entry is overlapped
     pub fn is_overlapped(&self, new_range: Range<usize>) -> bool {
-        self.entries.iter().any(|(existing_range, _)| {
-            existing_range.start < new_range.end && new_range.start < existing_range.end
-        })
+        self.overlaps(new_range).next().is_some()
     }
 
     /// Get all components that overlap with a range with their overlapping portions
+    #[inline]
     pub fn overlaps(
         &self,
         target: Range<usize>,
     ) -> impl Iterator<Item = (Range<usize>, &Arc<Mutex<dyn MemoryComponent>>)> + '_ {
-        self.entries.iter().filter_map(move |(range, component)| {
-            // Check if there is an overlap
-            if range.start < target.end && range.end > target.start {
+        self.entries[self.first_ending_after(target.start)..]
+            .iter()
+            .take_while(move |(range, _)| range.start < target.end)
+            .filter_map(move |(range, component)| {
                 // Crop range to the overlapping portion
                 let overlap_start = range.start.max(target.start);
                 let overlap_end = range.end.min(target.end);
@@ -131,8 +211,35 @@ impl MemoryTranslationTable {
                     let cropped_range = overlap_start..overlap_end;
                     return Some((cropped_range, component));
                 }
-            }
-            None
+
+                None
+            })
+    }
+
+    /// Where a redirected part of a access lands, along with the part of the buffer it belongs to
+    ///
+    /// The buffer subsection has to come from the range that was asked for, the redirect target can be anywhere
+    #[inline]
+    fn redirect<'a>(
+        &'a self,
+        entry_range: &Range<usize>,
+        buffer_subsection: &Range<usize>,
+        context_range: Range<usize>,
+        target: usize,
+    ) -> impl Iterator<
+        Item = (
+            Range<usize>,
+            Range<usize>,
+            &'a Arc<Mutex<dyn MemoryComponent>>,
+        ),
+    > + 'a {
+        let buffer_start = buffer_subsection.start + (context_range.start - entry_range.start);
+        let target_range = target..target + context_range.len();
+
+        self.overlaps(target_range).map(move |(range, component)| {
+            let start = buffer_start + (range.start - target);
+
+            (range.clone(), start..start + range.len(), component)
         })
     }
 
@@ -145,20 +252,29 @@ impl MemoryTranslationTable {
         let mut cycles = 0;
         let mut to_inspect = ArrayVec::<_, 8>::default();
 
-        to_inspect.extend(self.overlaps(buffer_target_range.clone()));
+        // Along with where each component's part of the access lands in the buffer
+        to_inspect.extend(
+            self.overlaps(buffer_target_range.clone())
+                .map(|(range, component)| {
+                    (
+                        range.clone(),
+                        range.start - offset..range.end - offset,
+                        component,
+                    )
+                }),
+        );
 
         if to_inspect.is_empty() {
             return Err(MemoryOperationError::OutOfBounds(buffer_target_range));
         }
 
-        while let Some((entry_range, memory_component)) = to_inspect.pop() {
-            let buffer_subsection = relocate_and_crop_range(&entry_range, &(0..buffer.len()));
+        while let Some((entry_range, buffer_subsection, memory_component)) = to_inspect.pop() {
             let mut records = ArrayVec::default();
 
             let mut memory_component = memory_component.lock().unwrap();
             let cycles_taken = memory_component.read_memory(
                 entry_range.start,
-                &mut buffer[buffer_subsection],
+                &mut buffer[buffer_subsection.clone()],
                 &mut records,
             );
             cycles += cycles_taken;
@@ -166,19 +282,23 @@ impl MemoryTranslationTable {
             for (context_range, error) in records {
                 match error {
                     ReadMemoryRecord::Denied => {
+                        self.report_denied_access("read", &context_range);
                         return Err(MemoryOperationError::Denied(context_range));
                     }
                     ReadMemoryRecord::Redirect { offset } => {
-                        let context_range =
-                            relocate_and_crop_range(&context_range, &(0..buffer.len()));
-                        let context_range =
-                            context_range.start + offset..context_range.end + offset;
-                        to_inspect.extend(self.overlaps(context_range));
+                        to_inspect.extend(self.redirect(
+                            &entry_range,
+                            &buffer_subsection,
+                            context_range,
+                            offset,
+                        ));
                     }
                 }
             }
         }
 
+        self.apply_read_patches(offset, buffer);
+
         Ok(cycles)
     }
 
@@ -189,21 +309,29 @@ impl MemoryTranslationTable {
         // Calculate the actual range that the buffer will be reading from
         let buffer_target_range = offset..offset + buffer.len();
         let mut cycles = 0;
-        let mut to_inspect =
-            ArrayVec::<_, 8>::from_iter(self.overlaps(buffer_target_range.clone()));
+        // Along with where each component's part of the access lands in the buffer
+        let mut to_inspect = ArrayVec::<_, 8>::from_iter(
+            self.overlaps(buffer_target_range.clone())
+                .map(|(range, component)| {
+                    (
+                        range.clone(),
+                        range.start - offset..range.end - offset,
+                        component,
+                    )
+                }),
+        );
 
         if to_inspect.is_empty() {
             return Err(MemoryOperationError::OutOfBounds(buffer_target_range));
         }
 
-        while let Some((entry_range, memory_component)) = to_inspect.pop() {
-            let buffer_subsection = relocate_and_crop_range(&entry_range, &(0..buffer.len()));
+        while let Some((entry_range, buffer_subsection, memory_component)) = to_inspect.pop() {
             let mut records = ArrayVec::default();
 
             let mut memory_component = memory_component.lock().unwrap();
             let cycles_taken = memory_component.write_memory(
                 entry_range.start,
-                &buffer[buffer_subsection],
+                &buffer[buffer_subsection.clone()],
                 &mut records,
             );
             cycles += cycles_taken;
@@ -211,14 +339,16 @@ impl MemoryTranslationTable {
             for (context_range, error) in records {
                 match error {
                     WriteMemoryRecord::Denied => {
+                        self.report_denied_access("write", &context_range);
                         return Err(MemoryOperationError::Denied(context_range));
                     }
                     WriteMemoryRecord::Redirect { offset } => {
-                        let context_range =
-                            relocate_and_crop_range(&context_range, &(0..buffer.len()));
-                        let context_range =
-                            context_range.start + offset..context_range.end + offset;
-                        to_inspect.extend(self.overlaps(context_range));
+                        to_inspect.extend(self.redirect(
+                            &entry_range,
+                            &buffer_subsection,
+                            context_range,
+                            offset,
+                        ));
                     }
                 }
             }
@@ -227,26 +357,102 @@ impl MemoryTranslationTable {
         Ok(cycles)
     }
 
+    /// A read as the processor sees it, failed accesses are filled in according to the open bus behavior
+    ///
+    /// Only errors if the open bus behavior is [OpenBusBehavior::Fault]
+    pub fn read_or_open_bus(
+        &self,
+        offset: usize,
+        buffer: &mut [u8],
+    ) -> Result<u64, MemoryOperationError> {
+        match self.read(offset, buffer) {
+            Ok(cycles) => {
+                self.last_value
+                    .store(*buffer.last().unwrap(), Ordering::Relaxed);
+
+                Ok(cycles)
+            }
+            Err(error) => {
+                tracing::trace!("Open bus read: {}", error);
+
+                match self.open_bus {
+                    OpenBusBehavior::Fixed(value) => buffer.fill(value),
+                    OpenBusBehavior::LastValue => {
+                        buffer.fill(self.last_value.load(Ordering::Relaxed))
+                    }
+                    OpenBusBehavior::Random => rand::Rng::fill(&mut rand::thread_rng(), buffer),
+                    OpenBusBehavior::Fault => return Err(error),
+                }
+
+                Ok(0)
+            }
+        }
+    }
+
+    /// A write as the processor sees it, failed accesses go nowhere
+    ///
+    /// Only errors if the open bus behavior is [OpenBusBehavior::Fault]
+    pub fn write_or_open_bus(
+        &self,
+        offset: usize,
+        buffer: &[u8],
+    ) -> Result<u64, MemoryOperationError> {
+        // The processor drove the data lines either way
+        self.last_value
+            .store(*buffer.last().unwrap(), Ordering::Relaxed);
+
+        match self.write(offset, buffer) {
+            Ok(cycles) => Ok(cycles),
+            Err(error) if self.open_bus == OpenBusBehavior::Fault => Err(error),
+            Err(error) => {
+                tracing::trace!("Open bus write: {}", error);
+
+                Ok(0)
+            }
+        }
+    }
+
+    fn report_denied_access(&self, operation: &str, range: &Range<usize>) {
+        if self.denied_access_reported.swap(true, Ordering::Relaxed) {
+            tracing::trace!("Denied memory {} at {:x?}", operation, range);
+        } else {
+            tracing::warn!(
+                "Denied memory {} at {:x?}, any more are only traced",
+                operation,
+                range
+            );
+        }
+    }
+
     pub fn preview(&self, offset: usize, buffer: &mut [u8]) -> Result<(), MemoryOperationError> {
         // Calculate the actual range that the buffer will be reading from
         let buffer_target_range = offset..offset + buffer.len();
         // We use a vec here cuz buffer could be infinitely large
         let mut to_inspect = Vec::new();
 
-        to_inspect.extend(self.overlaps(buffer_target_range.clone()));
+        // Along with where each component's part of the access lands in the buffer
+        to_inspect.extend(
+            self.overlaps(buffer_target_range.clone())
+                .map(|(range, component)| {
+                    (
+                        range.clone(),
+                        range.start - offset..range.end - offset,
+                        component,
+                    )
+                }),
+        );
 
         if to_inspect.is_empty() {
             return Err(MemoryOperationError::OutOfBounds(buffer_target_range));
         }
 
-        while let Some((entry_range, memory_component)) = to_inspect.pop() {
-            let buffer_subsection = relocate_and_crop_range(&entry_range, &(0..buffer.len()));
+        while let Some((entry_range, buffer_subsection, memory_component)) = to_inspect.pop() {
             let mut records = ArrayVec::default();
 
             let mut memory_component = memory_component.lock().unwrap();
             memory_component.preview_memory(
                 entry_range.start,
-                &mut buffer[buffer_subsection],
+                &mut buffer[buffer_subsection.clone()],
                 &mut records,
             );
 
@@ -256,25 +462,257 @@ impl MemoryTranslationTable {
                         return Err(MemoryOperationError::Denied(context_range));
                     }
                     PreviewMemoryRecord::Redirect { offset } => {
-                        let context_range =
-                            relocate_and_crop_range(&context_range, &(0..buffer.len()));
-                        let context_range =
-                            context_range.start + offset..context_range.end + offset;
-                        to_inspect.extend(self.overlaps(context_range));
+                        to_inspect.extend(self.redirect(
+                            &entry_range,
+                            &buffer_subsection,
+                            context_range,
+                            offset,
+                        ));
                     }
                     PreviewMemoryRecord::PreviewImpossible => {
-                        todo!()
+                        return Err(MemoryOperationError::PreviewImpossible(context_range));
                     }
                 }
             }
         }
 
+        // Debuggers and the dynarec see what the processor would
+        self.apply_read_patches(offset, buffer);
+
         Ok(())
     }
 }
 
-pub enum MemoryPermission {
-    Read,
-    Write,
-    Execute,
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        component::{
+            definitions::misc::{
+                mirror_memory::{MirrorMemory, MirrorMemoryConfig, MirrorMemoryOverflowMode},
+                plain_memory::{PlainMemory, PlainMemoryConfig, PlainMemoryInitialContents},
+            },
+            FromConfig,
+        },
+        rom::RomManager,
+    };
+
+    #[test]
+    fn copies_accesses_spanning_two_components() {
+        let rom_manager = Arc::new(RomManager::default());
+        let mut memory_translation_table = MemoryTranslationTable::default();
+
+        for (index, range) in [(1, 0x000..0x100), (2, 0x100..0x200)] {
+            memory_translation_table.insert(
+                range.clone(),
+                Arc::new(Mutex::new(PlainMemory::from_config(
+                    rom_manager.clone(),
+                    PlainMemoryConfig {
+                        assigned_range: range,
+                        initial_contents: PlainMemoryInitialContents::Value { value: index },
+                        ..Default::default()
+                    },
+                ))),
+            );
+        }
+
+        let mut buffer = [0; 2];
+        memory_translation_table.read(0x0ff, &mut buffer).unwrap();
+        assert_eq!(buffer, [1, 2]);
+
+        memory_translation_table.write(0x0ff, &[3, 4]).unwrap();
+        let mut buffer = [0; 4];
+        memory_translation_table.preview(0x0fe, &mut buffer).unwrap();
+        assert_eq!(buffer, [1, 3, 4, 2]);
+    }
+
+    #[test]
+    fn finds_components_inserted_out_of_order() {
+        let rom_manager = Arc::new(RomManager::default());
+        let mut memory_translation_table = MemoryTranslationTable::default();
+
+        // Each region is filled with its index, with a unmapped gap before the last one
+        for (index, range) in [
+            (2, 0x200..0x300),
+            (0, 0x000..0x100),
+            (3, 0x400..0x500),
+            (1, 0x100..0x200),
+        ] {
+            memory_translation_table.insert(
+                range.clone(),
+                Arc::new(Mutex::new(PlainMemory::from_config(
+                    rom_manager.clone(),
+                    PlainMemoryConfig {
+                        assigned_range: range,
+                        initial_contents: PlainMemoryInitialContents::Value { value: index },
+                        ..Default::default()
+                    },
+                ))),
+            );
+        }
+
+        for (address, expected) in [(0x000, 0), (0x0ff, 0), (0x100, 1), (0x2ff, 2), (0x4ff, 3)] {
+            let mut buffer = [0xff];
+            memory_translation_table.read(address, &mut buffer).unwrap();
+            assert_eq!(buffer[0], expected, "at {:#x}", address);
+        }
+
+        // Straddles two components
+        let mut buffer = [0; 2];
+        memory_translation_table.read(0x1ff, &mut buffer).unwrap();
+        assert_eq!(buffer, [1, 2]);
+
+        assert!(memory_translation_table.get(0x300).is_none());
+        assert!(memory_translation_table.get(0x500).is_none());
+        assert!(matches!(
+            memory_translation_table.read(0x350, &mut [0]),
+            Err(MemoryOperationError::OutOfBounds(_))
+        ));
+        assert!(memory_translation_table.is_overlapped(0x2f0..0x310));
+        assert!(!memory_translation_table.is_overlapped(0x300..0x400));
+    }
+
+    #[test]
+    fn follows_mirror_redirects() {
+        let rom_manager = Arc::new(RomManager::default());
+        let mut memory_translation_table = MemoryTranslationTable::default();
+
+        // Laid out like the work memory of the NES
+        memory_translation_table.insert(
+            0x0000..0x0800,
+            Arc::new(Mutex::new(PlainMemory::from_config(
+                rom_manager.clone(),
+                PlainMemoryConfig {
+                    assigned_range: 0x0000..0x0800,
+                    initial_contents: PlainMemoryInitialContents::Value { value: 0 },
+                    ..Default::default()
+                },
+            ))),
+        );
+        memory_translation_table.insert(
+            0x0800..0x2000,
+            Arc::new(Mutex::new(MirrorMemory::from_config(
+                rom_manager,
+                MirrorMemoryConfig {
+                    readable: true,
+                    writable: true,
+                    assigned_range: 0x0800..0x2000,
+                    read_cycle_penalty_calculator: |_, _| 0,
+                    write_cycle_penalty_calculator: |_, _| 0,
+                    target: 0x0000..0x0800,
+                    overflow_mode: MirrorMemoryOverflowMode::Wrap(3),
+                },
+            ))),
+        );
+
+        memory_translation_table.write(0x0805, &[0x12]).unwrap();
+        memory_translation_table.write(0x1806, &[0x34]).unwrap();
+
+        let mut buffer = [0; 2];
+        memory_translation_table.read(0x0005, &mut buffer).unwrap();
+        assert_eq!(buffer, [0x12, 0x34]);
+        memory_translation_table.read(0x1005, &mut buffer).unwrap();
+        assert_eq!(buffer, [0x12, 0x34]);
+
+        // Straddles the real memory and its first mirror
+        memory_translation_table
+            .write(0x07ff, &[0x56, 0x78])
+            .unwrap();
+        memory_translation_table.read(0x0000, &mut buffer).unwrap();
+        assert_eq!(buffer, [0x78, 0x00]);
+
+        let mut buffer = [0; 4];
+        memory_translation_table
+            .preview(0x1004, &mut buffer)
+            .unwrap();
+        assert_eq!(buffer, [0x00, 0x12, 0x34, 0x00]);
+    }
+
+    #[test]
+    fn open_bus_reads_follow_behavior() {
+        let rom_manager = Arc::new(RomManager::default());
+        let mut memory_translation_table = MemoryTranslationTable::default();
+        memory_translation_table.insert(
+            0x000..0x100,
+            Arc::new(Mutex::new(PlainMemory::from_config(
+                rom_manager,
+                PlainMemoryConfig {
+                    assigned_range: 0x000..0x100,
+                    initial_contents: PlainMemoryInitialContents::Value { value: 0x42 },
+                    ..Default::default()
+                },
+            ))),
+        );
+
+        let mut buffer = [0];
+        memory_translation_table
+            .read_or_open_bus(0x200, &mut buffer)
+            .unwrap();
+        assert_eq!(buffer, [0xff]);
+
+        memory_translation_table.set_open_bus_behavior(OpenBusBehavior::LastValue);
+        memory_translation_table
+            .read_or_open_bus(0x10, &mut buffer)
+            .unwrap();
+        memory_translation_table
+            .read_or_open_bus(0x200, &mut buffer)
+            .unwrap();
+        assert_eq!(buffer, [0x42]);
+        memory_translation_table
+            .write_or_open_bus(0x200, &[0x13])
+            .unwrap();
+        memory_translation_table
+            .read_or_open_bus(0x200, &mut buffer)
+            .unwrap();
+        assert_eq!(buffer, [0x13]);
+
+        memory_translation_table.set_open_bus_behavior(OpenBusBehavior::Fault);
+        assert!(memory_translation_table
+            .read_or_open_bus(0x200, &mut buffer)
+            .is_err());
+        assert!(memory_translation_table
+            .write_or_open_bus(0x200, &[0])
+            .is_err());
+    }
+
+    #[test]
+    fn read_patches() {
+        let rom_manager = Arc::new(RomManager::default());
+        let mut memory_translation_table = MemoryTranslationTable::default();
+        memory_translation_table.insert(
+            0x000..0x100,
+            Arc::new(Mutex::new(PlainMemory::from_config(
+                rom_manager,
+                PlainMemoryConfig {
+                    assigned_range: 0x000..0x100,
+                    initial_contents: PlainMemoryInitialContents::Value { value: 0x42 },
+                    ..Default::default()
+                },
+            ))),
+        );
+
+        memory_translation_table.set_read_patches(vec![
+            ReadPatch {
+                address: 0x11,
+                value: 0x01,
+                compare: None,
+            },
+            ReadPatch {
+                address: 0x10,
+                value: 0x02,
+                compare: Some(0x00),
+            },
+        ]);
+
+        let mut buffer = [0; 2];
+        memory_translation_table.read(0x10, &mut buffer).unwrap();
+        assert_eq!(buffer, [0x42, 0x01]);
+        memory_translation_table.preview(0x10, &mut buffer).unwrap();
+        assert_eq!(buffer, [0x42, 0x01]);
+
+        // Memory itself is left alone
+        memory_translation_table.set_read_patches(Vec::new());
+        memory_translation_table.read(0x10, &mut buffer).unwrap();
+        assert_eq!(buffer, [0x42, 0x42]);
+    }
 }
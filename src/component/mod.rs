@@ -1,26 +1,90 @@
-use crate::{machine::QueryableComponents, rom::RomManager};
-use downcast_rs::DowncastSync;
-use std::fmt::Debug;
+use crate::{
+    machine::{event_bus::EventBus, QueryableComponents},
+    rom::RomManager,
+};
+use downcast_rs::Downcast;
+use serde::{Deserialize, Serialize};
+use std::fmt::{Debug, Display};
 use std::{any::Any, sync::Arc};
 
 pub mod audio;
 pub mod definitions;
 pub mod display;
 pub mod input;
+pub mod interrupt;
 pub mod memory;
+pub mod port;
 pub mod processor;
 pub mod schedulable;
 pub mod snapshot;
 
 // Basic supertrait for all components
-pub trait Component: DowncastSync + Any + Send + Sync + 'static {
+//
+// Components are always shared behind a mutex, so they only have to be Send. They stay shared rather than being owned
+// by their task because other parts of the machine, like the memory map, reach into them too. Tasks keep contention
+// down by locking once per batch
+pub trait Component: Downcast + Any + Send + 'static {
     fn reset(&mut self) {}
-    fn query_components(&mut self, query: &QueryableComponents) {}
+    /// Called once the component is added to a machine, before any component queries the others
+    fn publish_handles(&self, _event_bus: &mut EventBus) {}
+    fn query_components(&mut self, _query: &QueryableComponents) {}
+    /// Called once when the machine is shut down, before anything is dropped. Whatever has to outlive the session, like
+    /// battery backed RAM, is written out here
+    fn shutdown(&mut self) {}
+}
+
+/// Revision of a component implementation
+///
+/// The major version is bumped when the saved state changes so older snapshots can't be loaded, the minor version
+/// when emulation behaves differently so netplay peers would desync, and the patch version for anything else
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize, Default,
+)]
+pub struct SemanticVersion {
+    pub major: u16,
+    pub minor: u16,
+    pub patch: u16,
+}
+
+impl SemanticVersion {
+    pub const fn new(major: u16, minor: u16, patch: u16) -> Self {
+        Self {
+            major,
+            minor,
+            patch,
+        }
+    }
+
+    /// Snapshots only carry state, so any revision with the same layout can load them
+    pub fn can_load_snapshot_of(&self, other: &SemanticVersion) -> bool {
+        self.major == other.major
+    }
+
+    /// Peers have to emulate exactly the same way or they drift apart
+    pub fn can_play_with(&self, other: &SemanticVersion) -> bool {
+        self.major == other.major && self.minor == other.minor
+    }
+}
+
+impl Display for SemanticVersion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+    }
+}
+
+/// Reported in machine fingerprints, so state and netplay peers from other revisions of a component are turned away
+/// with a clear reason instead of loading garbage or desyncing
+pub trait ComponentVersion {
+    /// Identifies the component in fingerprints, unlike the type name it stays put when the type is renamed or moved
+    const NAME: &'static str;
+    const VERSION: SemanticVersion;
 }
 
 // An initializable component
-pub trait FromConfig: Component + Sized {
-    type Config: Debug;
+pub trait FromConfig: Component + ComponentVersion + Sized {
+    /// Serialized into the machine fingerprint, fields that differ between runs or hosts like function pointers,
+    /// interrupt lines and paths are skipped
+    type Config: Debug + Serialize;
 
     /// Make a new component from the config
     fn from_config(rom_manager: Arc<RomManager>, config: Self::Config) -> Self;
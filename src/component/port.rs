@@ -0,0 +1,138 @@
+use super::Component;
+use std::{
+    ops::Range,
+    sync::{Arc, Mutex},
+};
+
+/// Where the port table of a machine is published on the event bus
+pub const PORT_TRANSLATION_TABLE_TOPIC: &str = "port_translation_table";
+
+/// A component on the separate I/O port space of processors like the Z80, reached with IN and OUT instead of loads
+/// and stores
+pub trait PortMappedComponent: Component {
+    fn assigned_ports(&self) -> Range<u16>;
+    /// Ports are as the component sees them, after the machine dropped the address lines it doesn't decode
+    fn read_port(&mut self, port: u16) -> u8;
+    fn write_port(&mut self, port: u16, value: u8);
+}
+
+type PortEntry = (Range<u16>, Arc<Mutex<dyn PortMappedComponent>>);
+
+/// Which component answers which port, the port space counterpart of
+/// [crate::component::memory::MemoryTranslationTable]
+pub struct PortTranslationTable {
+    /// Sorted by the start of their range
+    entries: Vec<PortEntry>,
+    /// Address lines the machine decodes, most only look at the low byte
+    address_mask: u16,
+}
+
+impl Default for PortTranslationTable {
+    fn default() -> Self {
+        Self {
+            entries: Vec::new(),
+            address_mask: u16::MAX,
+        }
+    }
+}
+
+impl PortTranslationTable {
+    pub fn set_address_mask(&mut self, address_mask: u16) {
+        self.address_mask = address_mask;
+    }
+
+    /// Like memory, ports mapped on top of each other are a bug in the machine definition
+    pub fn insert(&mut self, ports: Range<u16>, component: Arc<Mutex<dyn PortMappedComponent>>) {
+        assert!(
+            !self
+                .entries
+                .iter()
+                .any(|(entry_ports, _)| entry_ports.start < ports.end
+                    && ports.start < entry_ports.end),
+            "Ports {:#x?} overlap with a existing mapping",
+            ports
+        );
+
+        let index = self
+            .entries
+            .partition_point(|(entry_ports, _)| entry_ports.start < ports.start);
+        self.entries.insert(index, (ports, component));
+    }
+
+    fn get(&self, port: u16) -> Option<&Arc<Mutex<dyn PortMappedComponent>>> {
+        let index = self
+            .entries
+            .partition_point(|(entry_ports, _)| entry_ports.end <= port);
+
+        self.entries
+            .get(index)
+            .filter(|(entry_ports, _)| entry_ports.contains(&port))
+            .map(|(_, component)| component)
+    }
+
+    /// None if nothing answers the port
+    pub fn read(&self, port: u16) -> Option<u8> {
+        let port = port & self.address_mask;
+
+        Some(self.get(port)?.lock().unwrap().read_port(port))
+    }
+
+    /// Returns false if nothing answers the port
+    pub fn write(&self, port: u16, value: u8) -> bool {
+        let port = port & self.address_mask;
+
+        match self.get(port) {
+            Some(component) => {
+                component.lock().unwrap().write_port(port, value);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Remembers the last write, and reads back the port it is at otherwise
+    struct Latch {
+        ports: Range<u16>,
+        value: Option<u8>,
+    }
+
+    impl Component for Latch {}
+
+    impl PortMappedComponent for Latch {
+        fn assigned_ports(&self) -> Range<u16> {
+            self.ports.clone()
+        }
+
+        fn read_port(&mut self, port: u16) -> u8 {
+            self.value.unwrap_or(port as u8)
+        }
+
+        fn write_port(&mut self, _port: u16, value: u8) {
+            self.value = Some(value);
+        }
+    }
+
+    #[test]
+    fn masked_ports_reach_their_component() {
+        let mut port_translation_table = PortTranslationTable::default();
+        port_translation_table.set_address_mask(0x00ff);
+        for ports in [0x80..0xc0, 0x00..0x40] {
+            let latch = Latch { ports, value: None };
+            port_translation_table.insert(latch.assigned_ports(), Arc::new(Mutex::new(latch)));
+        }
+
+        // The high byte is whatever was in the other register, the machine doesn't look at it
+        assert_eq!(port_translation_table.read(0x1282), Some(0x82));
+        assert_eq!(port_translation_table.read(0x0050), None);
+
+        assert!(port_translation_table.write(0xff10, 0x55));
+        assert!(!port_translation_table.write(0x00c0, 0x55));
+        assert_eq!(port_translation_table.read(0x003f), Some(0x55));
+        assert_eq!(port_translation_table.read(0x00bf), Some(0xbf));
+    }
+}
@@ -0,0 +1,122 @@
+use serde::Serialize;
+use std::path::PathBuf;
+
+#[cfg(feature = "jit")]
+use crate::atomic_write::write_atomically;
+#[cfg(feature = "jit")]
+use cranelift_codegen::{
+    ir::{Signature, Type},
+    settings::{self, Configurable},
+    Context,
+};
+#[cfg(feature = "jit")]
+use cranelift_frontend::{FunctionBuilder, FunctionBuilderContext};
+#[cfg(feature = "jit")]
+use cranelift_jit::{JITBuilder, JITModule};
+#[cfg(feature = "jit")]
+use cranelift_module::{default_libcall_names, Module};
+#[cfg(feature = "jit")]
+use serde::Deserialize;
+#[cfg(feature = "jit")]
+use std::{collections::BTreeMap, fs::File, path::Path};
+
+/// Has a processor that supports it compile blocks of guest code to host code, only builds with the jit feature do
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct DynarecConfig {
+    /// Where the blocks found in one run are remembered for the next
+    #[serde(skip)]
+    #[cfg_attr(not(feature = "jit"), allow(dead_code))]
+    pub block_cache: Option<PathBuf>,
+}
+
+/// What is kept of compiled blocks between runs
+///
+/// Code generated in one process can't be loaded into another, so this only has where each block starts and the guest
+/// code it was compiled from. Blocks whose code still matches are compiled all at once when the next run starts
+#[cfg(feature = "jit")]
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct BlockCacheFile {
+    pub blocks: BTreeMap<usize, Vec<u8>>,
+}
+
+#[cfg(feature = "jit")]
+impl BlockCacheFile {
+    /// A missing or unreadable cache is an empty one
+    pub fn load(path: &Path) -> Self {
+        File::open(path)
+            .ok()
+            .and_then(|file| rmp_serde::from_read(file).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+        write_atomically(path, |file| {
+            rmp_serde::encode::write_named(file, self)?;
+
+            Ok(())
+        })
+    }
+}
+
+/// Turns functions built with cranelift into host code
+///
+/// Code stays allocated until the compiler is dropped, even for blocks that were thrown out
+#[cfg(feature = "jit")]
+pub struct JitCompiler {
+    module: JITModule,
+    context: Context,
+    function_builder_context: FunctionBuilderContext,
+}
+
+#[cfg(feature = "jit")]
+impl JitCompiler {
+    /// Fails on hosts cranelift has no backend for
+    pub fn new() -> Result<Self, Box<dyn std::error::Error>> {
+        let mut flags = settings::builder();
+        // Generated code calls back into the emulator through plain function pointers
+        flags.set("use_colocated_libcalls", "false")?;
+        flags.set("is_pic", "false")?;
+        let isa = cranelift_native::builder()?.finish(settings::Flags::new(flags))?;
+
+        let module = JITModule::new(JITBuilder::with_isa(isa, default_libcall_names()));
+
+        Ok(Self {
+            context: module.make_context(),
+            module,
+            function_builder_context: FunctionBuilderContext::new(),
+        })
+    }
+
+    pub fn pointer_type(&self) -> Type {
+        self.module.target_config().pointer_type()
+    }
+
+    /// In the calling convention of the host, so compiled functions can be called as extern "C"
+    pub fn make_signature(&self) -> Signature {
+        self.module.make_signature()
+    }
+
+    /// Has the callback fill in a function with the signature and returns its code
+    pub fn compile(
+        &mut self,
+        signature: Signature,
+        build: impl FnOnce(&mut FunctionBuilder),
+    ) -> Result<*const u8, Box<dyn std::error::Error>> {
+        self.context.func.signature = signature;
+
+        let mut builder =
+            FunctionBuilder::new(&mut self.context.func, &mut self.function_builder_context);
+        build(&mut builder);
+        builder.finalize();
+
+        let id = self
+            .module
+            .declare_anonymous_function(&self.context.func.signature)?;
+        let result = self.module.define_function(id, &mut self.context);
+        self.module.clear_context(&mut self.context);
+        result?;
+        self.module.finalize_definitions()?;
+
+        Ok(self.module.get_finalized_function(id))
+    }
+}
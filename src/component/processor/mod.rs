@@ -1,8 +1,15 @@
-use super::{memory::MemoryTranslationTable, schedulable::SchedulableComponent};
+use super::{
+    memory::{MemoryOperationError, MemoryTranslationTable},
+    schedulable::SchedulableComponent,
+    snapshot::SnapshotableComponent,
+};
 use std::fmt::Debug;
+#[cfg(feature = "frontend")]
 use std::{borrow::Cow, fmt::Display};
 use thiserror::Error;
 
+pub mod dynarec;
+
 /// The result of compiling an instruction was not ok
 #[derive(Error, Debug)]
 pub enum InstructionDecompilingError {
@@ -10,11 +17,35 @@ pub enum InstructionDecompilingError {
     InstructionDecompilingFailed(Vec<u8>),
 }
 
+impl InstructionDecompilingError {
+    /// For when whatever is at the cursor could not be decoded, up to length bytes of it end up in the message
+    pub fn at(
+        cursor: usize,
+        length: usize,
+        memory_translation_table: &MemoryTranslationTable,
+    ) -> Self {
+        let mut bytes = vec![0; length];
+        // Best effort, the decoding error is what matters
+        let _ = memory_translation_table.preview(cursor, &mut bytes);
+
+        Self::InstructionDecompilingFailed(bytes)
+    }
+}
+
+/// Executing an instruction went wrong in a way the emulated processor has no answer for
+#[derive(Error, Debug)]
+pub enum InstructionInterpretingError {
+    #[error("Bus fault: {0}")]
+    BusFault(#[from] MemoryOperationError),
+}
+
+#[cfg(feature = "frontend")]
 #[derive(Debug)]
 pub struct InstructionTextRepresentation {
     pub instruction_mnemonic: Cow<'static, str>,
 }
 
+#[cfg(feature = "frontend")]
 impl Display for InstructionTextRepresentation {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "{}", self.instruction_mnemonic)
@@ -22,24 +53,70 @@ impl Display for InstructionTextRepresentation {
 }
 
 pub trait InstructionSet: Debug + Sized {
+    #[cfg(feature = "frontend")]
     fn to_text_representation(&self) -> InstructionTextRepresentation;
 }
 
-pub trait ProcessorComponent: SchedulableComponent {
+/// A register as remote debuggers see it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ProcessorRegister {
+    pub name: &'static str,
+    pub bits: u8,
+    pub value: u64,
+}
+
+impl ProcessorRegister {
+    pub fn new(name: &'static str, bits: u8, value: impl Into<u64>) -> Self {
+        Self {
+            name,
+            bits,
+            value: value.into(),
+        }
+    }
+}
+
+/// Processors always have state worth keeping, so they always go in save states
+pub trait ProcessorComponent: SchedulableComponent + SnapshotableComponent {
     type InstructionSet: InstructionSet;
 
     fn should_execution_occur(&self) -> bool;
 
+    /// Called before every instruction fetch so pending interrupts can divert the program pointer
+    fn service_interrupts(
+        &mut self,
+        _program_pointer: &mut usize,
+        _memory_translation_table: &MemoryTranslationTable,
+    ) {
+    }
+
     fn decompile(
         &self,
         cursor: usize,
         memory_translation_table: &MemoryTranslationTable,
     ) -> Result<(Self::InstructionSet, u8), InstructionDecompilingError>;
 
+    /// Faults the processor can handle itself, like exceptions, should be emulated instead of returned. Errors pause
+    /// the processor for the debugger
     fn interpret(
         &mut self,
         program_pointer: &mut usize,
         instruction: Self::InstructionSet,
         memory_translation_table: &MemoryTranslationTable,
-    ) -> Result<(), String>;
+    ) -> Result<(), InstructionInterpretingError>;
+
+    /// Every register for remote debuggers, in the order the architecture usually lists them. The task keeps the
+    /// program pointer so it is passed in, processors that know its width should put it in their list themselves
+    fn debug_registers(&self, program_pointer: usize) -> Vec<ProcessorRegister> {
+        vec![ProcessorRegister::new("pc", 32, program_pointer as u64)]
+    }
+
+    /// Runs a block of instructions compiled to host code starting at the program pointer, None leaves it to the
+    /// interpreter. Interrupts and breakpoints are only looked at between blocks
+    fn run_recompiled(
+        &mut self,
+        _program_pointer: &mut usize,
+        _memory_translation_table: &MemoryTranslationTable,
+    ) -> Option<Result<(), InstructionInterpretingError>> {
+        None
+    }
 }
@@ -2,6 +2,8 @@ use super::{memory::MemoryTranslationTable, Component};
 use num::rational::Ratio;
 
 pub trait SchedulableComponent: Component {
+    /// May change while running, like a display switching video modes. The executor picks up the new rate between
+    /// runs and plans the machine timing again
     fn tick_rate(&self) -> Ratio<u32>;
 
     // Takes in the ticker resolution and returns how many times it needs to run in how many of this resolution
@@ -1,132 +1,1001 @@
 use crate::{
+    atomic_write::{read_with_backup, write_atomically_with_backup},
     env::{CONFIG_LOCATION, STORAGE_DIRECTORY},
     input::keyboard::KeyboardInput,
 };
 use crate::{
     input::{Hotkey, Input},
-    rom::{GameSystem, OtherSystem},
+    rom::{import::ImportPolicy, GameSystem, RomId, RomRegion},
+    runtime::{backend_benchmark::BackendBenchmarkResults, color_filter::ColorFilter},
+    symbols::AddressLabel,
 };
 use indexmap::IndexMap;
 use ron::ser::PrettyConfig;
-use serde::{Deserialize, Serialize};
+use serde::{
+    de::{
+        value::{MapAccessDeserializer, SeqAccessDeserializer},
+        MapAccess, SeqAccess, Visitor,
+    },
+    Deserialize, Deserializer, Serialize,
+};
 use serde_inline_default::serde_inline_default;
 use serde_with::serde_as;
-use std::{
-    fs::{create_dir_all, File},
-    ops::Deref,
-    path::PathBuf,
-};
+#[cfg(any(feature = "frontend", test))]
+use std::{collections::HashSet, sync::LazyLock, time::Duration};
+use std::{fmt::Display, fs::File, path::PathBuf};
+use strum::{Display, EnumIter};
+
+/// Default controller mappings shipped with the emulator
+#[cfg(any(feature = "frontend", test))]
+static DEFAULT_CONTROLLER_CONFIGS: LazyLock<IndexMap<GameSystem, IndexMap<Input, Input>>> =
+    LazyLock::new(|| {
+        ron::from_str(include_str!("default_controller_configs.ron"))
+            .expect("Embedded default controller configs are malformed")
+    });
+
+/// Host input to emulated input for each player in turn, which is the gamepad of the machine at the same index
+pub type PlayerMappings = Vec<IndexMap<Input, Input>>;
+
+/// Player mappings as they are read, configs from before mappings were per player have a single mapping for player one
+struct AnyPlayerMappings(PlayerMappings);
+
+impl<'de> Deserialize<'de> for AnyPlayerMappings {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct AnyPlayerMappingsVisitor;
+
+        impl<'de> Visitor<'de> for AnyPlayerMappingsVisitor {
+            type Value = AnyPlayerMappings;
+
+            fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+                formatter.write_str("a list of input mappings, one per player")
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, players: A) -> Result<Self::Value, A::Error> {
+                PlayerMappings::deserialize(SeqAccessDeserializer::new(players))
+                    .map(AnyPlayerMappings)
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, mapping: A) -> Result<Self::Value, A::Error> {
+                IndexMap::deserialize(MapAccessDeserializer::new(mapping))
+                    .map(|mapping| AnyPlayerMappings(vec![mapping]))
+            }
+        }
+
+        deserializer.deserialize_any(AnyPlayerMappingsVisitor)
+    }
+}
+
+fn deserialize_controller_configs<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<IndexMap<GameSystem, PlayerMappings>, D::Error> {
+    let configs: IndexMap<GameSystem, AnyPlayerMappings> = IndexMap::deserialize(deserializer)?;
+
+    Ok(configs
+        .into_iter()
+        .map(|(system, AnyPlayerMappings(players))| (system, players))
+        .collect())
+}
+
+fn deserialize_controller_profiles<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<IndexMap<GameSystem, IndexMap<String, PlayerMappings>>, D::Error> {
+    let profiles: IndexMap<GameSystem, IndexMap<String, AnyPlayerMappings>> =
+        IndexMap::deserialize(deserializer)?;
+
+    Ok(profiles
+        .into_iter()
+        .map(|(system, profiles)| {
+            (
+                system,
+                profiles
+                    .into_iter()
+                    .map(|(name, AnyPlayerMappings(players))| (name, players))
+                    .collect(),
+            )
+        })
+        .collect())
+}
+
+/// How many frames the renderer may have queued up, more smooths out stutter at the cost of latency
+#[derive(
+    Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize, EnumIter, Display,
+)]
+pub enum Buffering {
+    #[default]
+    Double,
+    Triple,
+}
+
+impl Buffering {
+    #[cfg(feature = "frontend")]
+    pub fn frame_count(&self) -> u32 {
+        match self {
+            Buffering::Double => 2,
+            Buffering::Triple => 3,
+        }
+    }
+}
+
+/// Which renderer draws the windows, only picked at startup
+#[derive(
+    Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize, EnumIter, Display,
+)]
+pub enum RenderingBackendKind {
+    /// Whichever the backend benchmark found fastest
+    #[default]
+    Auto,
+    Vulkan,
+    /// Metal, DirectX 12, Vulkan or OpenGL, whatever the host has
+    Wgpu,
+    Software,
+}
+
+/// What kind of image the renderer presents, for displays that misreport what they support
+#[derive(
+    Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize, EnumIter, Display,
+)]
+pub enum SurfaceFormatPreference {
+    #[default]
+    Srgb,
+    /// 10 bits per channel, falls back to sRGB
+    TenBit,
+    /// Extended linear sRGB, falls back to 10 bit
+    Hdr,
+}
+
+/// How display components are told to skip rendering when the host can't keep up, emulation never skips
+#[derive(
+    Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize, EnumIter, Display,
+)]
+pub enum FrameSkip {
+    #[default]
+    Disabled,
+    /// Skip as many frames as the machine is behind, up to the maximum
+    Automatic,
+    /// Always skip the maximum, for hosts known to be too slow
+    Fixed,
+}
+
+impl FrameSkip {
+    /// How many of the upcoming frames should not be rendered
+    #[cfg(any(feature = "frontend", test))]
+    pub fn frames_to_skip(&self, max_frame_skip: u8, lag: Duration, frame_time: Duration) -> u32 {
+        match self {
+            FrameSkip::Disabled => 0,
+            FrameSkip::Automatic => {
+                if frame_time.is_zero() {
+                    return 0;
+                }
+
+                let frames_behind = (lag.as_secs_f32() / frame_time.as_secs_f32()) as u32;
+                frames_behind.min(max_frame_skip as u32)
+            }
+            FrameSkip::Fixed => max_frame_skip as u32,
+        }
+    }
+}
+
+/// How the machine display is fit into the window
+#[derive(
+    Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize, EnumIter, Display,
+)]
+pub enum AspectMode {
+    /// Fill the whole window
+    #[default]
+    Stretch,
+    /// Scale as large as fits while keeping the shape, bordered on two sides
+    Preserve,
+    /// Like preserve but only whole multiples, so every pixel is the same size
+    Integer,
+}
+
+impl AspectMode {
+    /// Offset and size of the area inside the window the display should be drawn to
+    #[cfg(any(feature = "frontend", test))]
+    pub fn fit(&self, window: [u32; 2], display: [u32; 2]) -> ([u32; 2], [u32; 2]) {
+        if display.contains(&0) {
+            return ([0, 0], window);
+        }
+
+        let scale =
+            (window[0] as f32 / display[0] as f32).min(window[1] as f32 / display[1] as f32);
+        let scale = match self {
+            AspectMode::Stretch => return ([0, 0], window),
+            AspectMode::Preserve => scale,
+            // Overflowing is better than drawing nothing in a tiny window
+            AspectMode::Integer => scale.floor().max(1.0),
+        };
+
+        let size = display.map(|dimension| (dimension as f32 * scale).round() as u32);
+        let offset = [
+            window[0].saturating_sub(size[0]) / 2,
+            window[1].saturating_sub(size[1]) / 2,
+        ];
+
+        (offset, size)
+    }
+}
+
+/// How machines with more than one display, like a handheld with two screens, share the window
+#[derive(
+    Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize, EnumIter, Display,
+)]
+pub enum DisplayLayout {
+    /// Stacked top to bottom in the order the machine registered them
+    #[default]
+    Vertical,
+    /// Side by side, left to right
+    Horizontal,
+    /// The first display fills the window, the rest are small in the bottom right corner
+    PictureInPicture,
+}
+
+impl DisplayLayout {
+    /// Offset and size of the area each display should be drawn to, each one fit to its share of the window
+    #[cfg(any(feature = "frontend", test))]
+    pub fn arrange(
+        &self,
+        aspect_mode: AspectMode,
+        window: [u32; 2],
+        displays: &[[u32; 2]],
+    ) -> Vec<([u32; 2], [u32; 2])> {
+        let count = displays.len() as u32;
+        // Where display `index` starts along a split axis
+        let split = |length: u32, index: u32| (length as u64 * index as u64 / count as u64) as u32;
+
+        (0..count)
+            .zip(displays)
+            .map(|(index, display)| {
+                let (cell_offset, cell_size) = match self {
+                    DisplayLayout::Vertical => (
+                        [0, split(window[1], index)],
+                        [
+                            window[0],
+                            split(window[1], index + 1) - split(window[1], index),
+                        ],
+                    ),
+                    DisplayLayout::Horizontal => (
+                        [split(window[0], index), 0],
+                        [
+                            split(window[0], index + 1) - split(window[0], index),
+                            window[1],
+                        ],
+                    ),
+                    DisplayLayout::PictureInPicture if index == 0 => ([0, 0], window),
+                    DisplayLayout::PictureInPicture => {
+                        let size = window.map(|length| (length / 4).max(1));
+
+                        (
+                            [
+                                window[0].saturating_sub(size[0] * index),
+                                window[1] - size[1],
+                            ],
+                            size,
+                        )
+                    }
+                };
+
+                let (offset, size) = match aspect_mode.fit(cell_size, *display) {
+                    // Overflowing would cover the other displays
+                    (_, size)
+                        if count > 1 && (size[0] > cell_size[0] || size[1] > cell_size[1]) =>
+                    {
+                        AspectMode::Preserve.fit(cell_size, *display)
+                    }
+                    fitted => fitted,
+                };
+
+                (
+                    [cell_offset[0] + offset[0], cell_offset[1] + offset[1]],
+                    size,
+                )
+            })
+            .collect()
+    }
+}
+
+/// How the display is sampled when it is scaled to the window
+#[derive(
+    Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize, EnumIter, Display,
+)]
+pub enum ScaleFilter {
+    /// Sharp pixels, which come out uneven in size unless the scale is a whole number
+    #[default]
+    Nearest,
+    /// Smooth, at the cost of blurring the edges of pixels
+    Linear,
+}
+
+/// Settings a single game overrides, anything left as None follows the global config
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GameConfig {
+    /// Percentage of real time
+    #[serde(default)]
+    pub speed: Option<u16>,
+    /// Some(None) turns the global filter off for this game
+    #[serde(default)]
+    pub color_filter: Option<Option<ColorFilter>>,
+    #[serde(default)]
+    pub aspect_mode: Option<AspectMode>,
+    #[serde(default)]
+    pub scale_filter: Option<ScaleFilter>,
+    #[serde(default)]
+    pub frame_blend: Option<bool>,
+    /// Only has a effect while rewind points are being kept at all
+    #[serde(default)]
+    pub rewind: Option<bool>,
+    /// Name of one of the [GlobalConfig::controller_profiles] of the system of the game
+    #[serde(default)]
+    pub controller_profile: Option<String>,
+    /// Names for addresses the debugger shows, imported from symbol files or written by hand. Not a override, so
+    /// going back to the global settings keeps them
+    #[serde(default)]
+    pub labels: Vec<AddressLabel>,
+    /// File names of the scripts to run with the game, not a override either
+    #[serde(default)]
+    pub scripts: Vec<String>,
+}
+
+/// What the runtime has to rebuild for a changed setting to take effect
+#[cfg(any(feature = "frontend", test))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Display)]
+pub enum ConfigApplyScope {
+    Swapchain,
+    AudioStream,
+    InputMapping,
+    Rewind,
+    Labels,
+    Scripts,
+    /// The rendering backend is picked on startup
+    Restart,
+}
+
+/// A host input that does more than one thing. Host inputs map to a single emulated input per player, and players
+/// are free to share inputs, so the only possible conflict is with a hotkey
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BindingConflict {
+    pub host_input: Input,
+    pub hotkey: Hotkey,
+    pub system: GameSystem,
+    /// Starting from 0
+    pub player: usize,
+    pub emulated_input: Input,
+}
+
+impl Display for BindingConflict {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{:?} is bound to the hotkey {:?} and to {:?} for player {} of {}",
+            self.host_input,
+            self.hotkey,
+            self.emulated_input,
+            self.player + 1,
+            self.system
+        )
+    }
+}
 
 #[serde_as]
 #[serde_inline_default]
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct GlobalConfig {
-    #[serde(default)]
-    pub controller_configs: IndexMap<GameSystem, IndexMap<Input, Input>>,
+    #[serde(default, deserialize_with = "deserialize_controller_configs")]
+    pub controller_configs: IndexMap<GameSystem, PlayerMappings>,
     #[serde(default)]
     pub hotkeys: IndexMap<Input, Hotkey>,
-    #[serde_inline_default(true)]
-    pub hardware_acceleration: bool,
+    #[serde(default)]
+    pub rendering_backend: RenderingBackendKind,
+    /// None means the benchmark has never been run
+    #[serde(default)]
+    pub backend_benchmark: Option<BackendBenchmarkResults>,
     #[serde_inline_default(true)]
     pub vsync: bool,
+    /// Only the hardware accelerated backends respect this
+    #[serde(default)]
+    pub buffering: Buffering,
+    /// Only the hardware accelerated backends respect this
+    #[serde(default)]
+    pub surface_format: SurfaceFormatPreference,
+    #[serde(default)]
+    pub audio_muted: bool,
+    /// Audio capture writes every audio component to a file of its own instead of the mix
+    #[serde(default)]
+    pub audio_capture_per_component: bool,
+    #[serde(default)]
+    pub color_filter: Option<ColorFilter>,
+    #[serde(default)]
+    pub aspect_mode: AspectMode,
+    #[serde(default)]
+    pub scale_filter: ScaleFilter,
+    #[serde(default)]
+    pub display_layout: DisplayLayout,
+    /// Shows the average of the last two frames, for games that rely on the ghosting of handheld LCDs for
+    /// transparency
+    #[serde(default)]
+    pub frame_blend: bool,
+    #[serde(default)]
+    pub high_contrast_gui: bool,
+    /// Percentage
+    #[serde_inline_default(100)]
+    pub osd_text_scale: u16,
     pub file_browser_home: PathBuf,
+    /// Scanned for known ROMs on startup
+    #[serde(default)]
+    pub watch_folders: Vec<PathBuf>,
+    /// Used when a import doesn't specify one
+    #[serde(default)]
+    pub import_policy: ImportPolicy,
+    /// Which dump of a game the library shows when it collapses regional duplicates, None prefers World dumps
+    #[serde(default)]
+    pub preferred_region: Option<RomRegion>,
+    /// Reopen the game, windows and debugger that were open when the GUI last shut down
+    #[serde(default)]
+    pub restore_session: bool,
+    /// Pauses emulation and audio while the window is unfocused
+    #[serde_inline_default(true)]
+    pub pause_on_focus_loss: bool,
+    /// Pausing during netplay would desync the other players, so this is opt in
+    #[serde(default)]
+    pub pause_on_focus_loss_in_netplay: bool,
+    /// Frames local inputs are held back during netplay so they reach the other players in time. Sessions use the
+    /// largest any of the players asks for
+    #[serde_inline_default(2)]
+    pub netplay_input_delay: u8,
+    /// Minutes without any input before emulation pauses itself, 0 disables. Never applies during netplay
+    #[serde(default)]
+    pub idle_pause_minutes: u16,
+    /// Save a state to a slot of its own when pausing for being idle
+    #[serde(default)]
+    pub idle_pause_autosave: bool,
+    /// Tell the desktop when a long job like an import finishes while the window is in the background, it shows up
+    /// on screen otherwise
+    #[serde_inline_default(true)]
+    pub desktop_notifications: bool,
+    /// Seconds a single component may run without returning before it is reported as stalled, 0 disables the watchdog
+    #[serde_inline_default(5)]
+    pub watchdog_timeout: u32,
+    /// Trades smoothness and filters for power usage
+    #[serde(default)]
+    pub battery_saver: bool,
+    /// Drop game bindings that collide with hotkeys when loading
+    #[serde(default)]
+    pub auto_resolve_binding_conflicts: bool,
+    /// Rewind points kept, 0 disables rewinding. Takes effect when the next game starts
+    #[serde(default)]
+    pub rewind_depth: usize,
+    /// Milliseconds between rewind points
+    #[serde_inline_default(250)]
+    pub rewind_interval: u32,
+    #[serde(default)]
+    pub frame_skip: FrameSkip,
+    /// Most frames skipped in a row
+    #[serde_inline_default(4)]
+    pub max_frame_skip: u8,
+    /// Percentage the fast forward hotkey runs at
+    #[serde_inline_default(400)]
+    pub fast_forward_speed: u16,
+    /// Keep the machine running muted while the menu is open, instead of pausing it
+    #[serde(default)]
+    pub emulate_in_menu: bool,
+    /// Percentage of its usual speed the machine runs at while the menu is open
+    #[serde_inline_default(100)]
+    pub menu_emulation_speed: u16,
+    /// Named alternatives to the mapping in controller_configs that games can pick
+    #[serde(default, deserialize_with = "deserialize_controller_profiles")]
+    pub controller_profiles: IndexMap<GameSystem, IndexMap<String, PlayerMappings>>,
+    /// Layered over everything else while the game runs
+    #[serde(default)]
+    pub game_configs: IndexMap<RomId, GameConfig>,
+    /// Which game the per game layer is for, the runtime sets this and it is never saved
+    #[serde(skip)]
+    pub active_game: Option<RomId>,
 }
 
 impl GlobalConfig {
     pub fn save(&self) -> Result<(), Box<dyn std::error::Error>> {
-        create_dir_all(STORAGE_DIRECTORY.deref())?;
-        let config_file = File::create(CONFIG_LOCATION.deref())?;
-        ron::ser::to_writer_pretty(config_file, self, PrettyConfig::default())?;
+        write_atomically_with_backup(&CONFIG_LOCATION, |file| {
+            ron::ser::to_writer_pretty(file, self, PrettyConfig::default())?;
 
-        Ok(())
+            Ok(())
+        })
     }
 
+    /// A config that can't be read is replaced by the one it was saved over
     pub fn load(&mut self) -> Result<(), Box<dyn std::error::Error>> {
-        let config_file = File::open(CONFIG_LOCATION.deref())?;
-        *self = ron::de::from_reader(config_file)?;
+        *self = read_with_backup(&CONFIG_LOCATION, |path| {
+            Ok::<_, Box<dyn std::error::Error>>(ron::de::from_reader(File::open(path)?)?)
+        })?;
+
+        for conflict in self.binding_conflicts() {
+            tracing::warn!("Input binding conflict: {}", conflict);
+        }
+
+        if self.auto_resolve_binding_conflicts {
+            self.resolve_binding_conflicts();
+        }
 
         Ok(())
     }
+
+    /// Settings not mentioned here are read as they are used and need nothing done
+    #[cfg(any(feature = "frontend", test))]
+    pub fn changed_scopes(&self, previous: &GlobalConfig) -> HashSet<ConfigApplyScope> {
+        let mut scopes = HashSet::new();
+
+        if self.vsync != previous.vsync
+            || self.buffering != previous.buffering
+            || self.surface_format != previous.surface_format
+        {
+            scopes.insert(ConfigApplyScope::Swapchain);
+        }
+
+        if self.audio_muted != previous.audio_muted {
+            scopes.insert(ConfigApplyScope::AudioStream);
+        }
+
+        if self.controller_configs != previous.controller_configs
+            || self.hotkeys != previous.hotkeys
+            || self.controller_profiles != previous.controller_profiles
+            || self
+                .active_game_config()
+                .map(|config| &config.controller_profile)
+                != previous
+                    .active_game_config()
+                    .map(|config| &config.controller_profile)
+        {
+            scopes.insert(ConfigApplyScope::InputMapping);
+        }
+
+        if self.effective_rewind() != previous.effective_rewind() {
+            scopes.insert(ConfigApplyScope::Rewind);
+        }
+
+        if self.effective_labels() != previous.effective_labels() {
+            scopes.insert(ConfigApplyScope::Labels);
+        }
+
+        if self.effective_scripts() != previous.effective_scripts() {
+            scopes.insert(ConfigApplyScope::Scripts);
+        }
+
+        if self.rendering_backend != previous.rendering_backend {
+            scopes.insert(ConfigApplyScope::Restart);
+        }
+
+        scopes
+    }
+
+    /// The layer of the running game, if it overrides anything
+    #[cfg(any(feature = "frontend", test))]
+    pub fn active_game_config(&self) -> Option<&GameConfig> {
+        self.game_configs.get(&self.active_game?)
+    }
+
+    /// Percentage of real time the machine runs at
+    #[cfg(any(feature = "frontend", test))]
+    pub fn effective_speed(&self) -> u16 {
+        self.active_game_config()
+            .and_then(|config| config.speed)
+            .unwrap_or(100)
+    }
+
+    /// Percentage of real time the machine runs at while the menu is open, None if it pauses
+    #[cfg(any(feature = "frontend", test))]
+    pub fn speed_in_menu(&self) -> Option<u16> {
+        self.emulate_in_menu.then(|| {
+            (self.effective_speed() as u32 * self.menu_emulation_speed as u32 / 100).max(1) as u16
+        })
+    }
+
+    #[cfg(any(feature = "frontend", test))]
+    pub fn effective_color_filter(&self) -> Option<ColorFilter> {
+        self.active_game_config()
+            .and_then(|config| config.color_filter)
+            .unwrap_or(self.color_filter)
+    }
+
+    #[cfg(any(feature = "frontend", test))]
+    pub fn effective_aspect_mode(&self) -> AspectMode {
+        self.active_game_config()
+            .and_then(|config| config.aspect_mode)
+            .unwrap_or(self.aspect_mode)
+    }
+
+    /// The backend to start with, never [RenderingBackendKind::Auto]
+    #[cfg(feature = "frontend")]
+    pub fn selected_rendering_backend(&self) -> RenderingBackendKind {
+        match self.rendering_backend {
+            RenderingBackendKind::Auto => self
+                .backend_benchmark
+                .map_or(Self::default_rendering_backend(), |results| {
+                    results.recommended_backend()
+                }),
+            rendering_backend => rendering_backend,
+        }
+    }
+
+    /// The first hardware accelerated backend this build has
+    #[cfg(feature = "frontend")]
+    fn default_rendering_backend() -> RenderingBackendKind {
+        if cfg!(vulkan) {
+            RenderingBackendKind::Vulkan
+        } else if cfg!(wgpu) {
+            RenderingBackendKind::Wgpu
+        } else {
+            RenderingBackendKind::Software
+        }
+    }
+
+    #[cfg(feature = "frontend")]
+    pub fn effective_scale_filter(&self) -> ScaleFilter {
+        self.active_game_config()
+            .and_then(|config| config.scale_filter)
+            .unwrap_or(self.scale_filter)
+    }
+
+    #[cfg(feature = "frontend")]
+    pub fn effective_frame_blend(&self) -> bool {
+        self.active_game_config()
+            .and_then(|config| config.frame_blend)
+            .unwrap_or(self.frame_blend)
+    }
+
+    #[cfg(any(feature = "frontend", test))]
+    pub fn effective_rewind(&self) -> bool {
+        self.rewind_depth != 0
+            && self
+                .active_game_config()
+                .and_then(|config| config.rewind)
+                .unwrap_or(true)
+    }
+
+    /// Labels of the running game
+    #[cfg(any(feature = "frontend", test))]
+    pub fn effective_labels(&self) -> &[AddressLabel] {
+        self.active_game_config()
+            .map(|config| config.labels.as_slice())
+            .unwrap_or_default()
+    }
+
+    /// Scripts enabled for the running game
+    #[cfg(any(feature = "frontend", test))]
+    pub fn effective_scripts(&self) -> &[String] {
+        self.active_game_config()
+            .map(|config| config.scripts.as_slice())
+            .unwrap_or_default()
+    }
+
+    /// The profile the running game picked, falling back to the mapping of the system
+    #[cfg(any(feature = "frontend", test))]
+    pub fn effective_controller_config(&self, system: GameSystem) -> Option<&PlayerMappings> {
+        self.active_game_config()
+            .and_then(|config| config.controller_profile.as_ref())
+            .and_then(|profile| self.controller_profiles.get(&system)?.get(profile))
+            .or_else(|| self.controller_configs.get(&system))
+    }
+
+    pub fn binding_conflicts(&self) -> Vec<BindingConflict> {
+        let mut conflicts = Vec::new();
+
+        for (host_input, hotkey) in &self.hotkeys {
+            for (system, controller_config) in &self.controller_configs {
+                for (player, mapping) in controller_config.iter().enumerate() {
+                    if let Some(emulated_input) = mapping.get(host_input) {
+                        conflicts.push(BindingConflict {
+                            host_input: *host_input,
+                            hotkey: *hotkey,
+                            system: *system,
+                            player,
+                            emulated_input: *emulated_input,
+                        });
+                    }
+                }
+            }
+        }
+
+        conflicts
+    }
+
+    /// Removes game bindings that collide with hotkeys, as hotkeys take priority
+    pub fn resolve_binding_conflicts(&mut self) {
+        for conflict in self.binding_conflicts() {
+            tracing::info!("Resolving input binding conflict: {}", conflict);
+
+            if let Some(mapping) = self
+                .controller_configs
+                .get_mut(&conflict.system)
+                .and_then(|controller_config| controller_config.get_mut(conflict.player))
+            {
+                mapping.shift_remove(&conflict.host_input);
+            }
+        }
+    }
+
+    /// The shipped defaults only cover player one
+    #[cfg(any(feature = "frontend", test))]
+    pub fn default_controller_config(system: GameSystem) -> PlayerMappings {
+        vec![DEFAULT_CONTROLLER_CONFIGS
+            .get(&system)
+            .cloned()
+            .unwrap_or_default()]
+    }
+
+    /// Fills in the shipped defaults for a system the user has not configured yet
+    #[cfg(feature = "frontend")]
+    pub fn ensure_controller_config(&mut self, system: GameSystem) {
+        self.controller_configs
+            .entry(system)
+            .or_insert_with(|| Self::default_controller_config(system));
+    }
+
+    #[cfg(any(feature = "frontend", test))]
+    pub fn reset_controller_config(&mut self, system: GameSystem) {
+        self.controller_configs
+            .insert(system, Self::default_controller_config(system));
+    }
 }
 
 impl Default for GlobalConfig {
     fn default() -> Self {
         Self {
-            controller_configs: [(
-                GameSystem::Other(OtherSystem::Chip8),
-                [
-                    (
-                        Input::Keyboard(KeyboardInput::Digit1),
-                        Input::Keyboard(KeyboardInput::Numpad1),
-                    ),
-                    (
-                        Input::Keyboard(KeyboardInput::Digit2),
-                        Input::Keyboard(KeyboardInput::Numpad2),
-                    ),
-                    (
-                        Input::Keyboard(KeyboardInput::Digit3),
-                        Input::Keyboard(KeyboardInput::Numpad3),
-                    ),
-                    (
-                        Input::Keyboard(KeyboardInput::Digit4),
-                        Input::Keyboard(KeyboardInput::KeyC),
-                    ),
-                    (
-                        Input::Keyboard(KeyboardInput::KeyQ),
-                        Input::Keyboard(KeyboardInput::Numpad4),
-                    ),
-                    (
-                        Input::Keyboard(KeyboardInput::KeyW),
-                        Input::Keyboard(KeyboardInput::Numpad5),
-                    ),
-                    (
-                        Input::Keyboard(KeyboardInput::KeyE),
-                        Input::Keyboard(KeyboardInput::Numpad6),
-                    ),
-                    (
-                        Input::Keyboard(KeyboardInput::KeyR),
-                        Input::Keyboard(KeyboardInput::KeyD),
-                    ),
-                    (
-                        Input::Keyboard(KeyboardInput::KeyA),
-                        Input::Keyboard(KeyboardInput::Numpad7),
-                    ),
-                    (
-                        Input::Keyboard(KeyboardInput::KeyS),
-                        Input::Keyboard(KeyboardInput::Numpad8),
-                    ),
-                    (
-                        Input::Keyboard(KeyboardInput::KeyD),
-                        Input::Keyboard(KeyboardInput::Numpad9),
-                    ),
-                    (
-                        Input::Keyboard(KeyboardInput::KeyF),
-                        Input::Keyboard(KeyboardInput::KeyE),
-                    ),
-                    (
-                        Input::Keyboard(KeyboardInput::KeyZ),
-                        Input::Keyboard(KeyboardInput::KeyA),
-                    ),
-                    (
-                        Input::Keyboard(KeyboardInput::KeyX),
-                        Input::Keyboard(KeyboardInput::Numpad0),
-                    ),
-                    (
-                        Input::Keyboard(KeyboardInput::KeyC),
-                        Input::Keyboard(KeyboardInput::KeyB),
-                    ),
-                    (
-                        Input::Keyboard(KeyboardInput::KeyV),
-                        Input::Keyboard(KeyboardInput::KeyF),
-                    ),
-                ]
-                .into(),
-            )]
+            controller_configs: IndexMap::default(),
+            hotkeys: [
+                (Input::Keyboard(KeyboardInput::F1), Hotkey::OpenMenu),
+                (Input::Keyboard(KeyboardInput::F2), Hotkey::ToggleMute),
+                (Input::Keyboard(KeyboardInput::F3), Hotkey::OpenQuickMenu),
+                (
+                    Input::Keyboard(KeyboardInput::F4),
+                    Hotkey::OpenCommandPalette,
+                ),
+                (Input::Keyboard(KeyboardInput::F5), Hotkey::SaveSnapshot),
+                (Input::Keyboard(KeyboardInput::F7), Hotkey::LoadSnapshot),
+                (Input::Keyboard(KeyboardInput::F6), Hotkey::FastForward),
+                (Input::Keyboard(KeyboardInput::F8), Hotkey::Rewind),
+                (Input::Keyboard(KeyboardInput::F9), Hotkey::FrameAdvance),
+                (Input::Keyboard(KeyboardInput::Pause), Hotkey::TogglePause),
+                (
+                    Input::Keyboard(KeyboardInput::F11),
+                    Hotkey::ToggleFullscreen,
+                ),
+                (Input::Keyboard(KeyboardInput::F12), Hotkey::Screenshot),
+                (
+                    Input::Keyboard(KeyboardInput::F10),
+                    Hotkey::TogglePointerCapture,
+                ),
+            ]
             .into(),
-            hotkeys: [(Input::Keyboard(KeyboardInput::F1), Hotkey::OpenMenu)].into(),
-            hardware_acceleration: true,
+            rendering_backend: RenderingBackendKind::default(),
+            backend_benchmark: None,
             vsync: true,
+            buffering: Buffering::default(),
+            surface_format: SurfaceFormatPreference::default(),
+            audio_muted: false,
+            audio_capture_per_component: false,
+            color_filter: None,
+            aspect_mode: AspectMode::default(),
+            scale_filter: ScaleFilter::default(),
+            display_layout: DisplayLayout::default(),
+            frame_blend: false,
+            high_contrast_gui: false,
+            osd_text_scale: 100,
             file_browser_home: STORAGE_DIRECTORY.clone(),
+            watch_folders: Vec::new(),
+            import_policy: ImportPolicy::default(),
+            preferred_region: None,
+            restore_session: false,
+            pause_on_focus_loss: true,
+            pause_on_focus_loss_in_netplay: false,
+            netplay_input_delay: 2,
+            idle_pause_minutes: 0,
+            idle_pause_autosave: false,
+            desktop_notifications: true,
+            watchdog_timeout: 5,
+            battery_saver: false,
+            auto_resolve_binding_conflicts: false,
+            rewind_depth: 0,
+            rewind_interval: 250,
+            frame_skip: FrameSkip::default(),
+            max_frame_skip: 4,
+            fast_forward_speed: 400,
+            emulate_in_menu: false,
+            menu_emulation_speed: 100,
+            controller_profiles: IndexMap::default(),
+            game_configs: IndexMap::default(),
+            active_game: None,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rom::OtherSystem;
+
+    #[test]
+    fn changed_scopes() {
+        let previous = GlobalConfig::default();
+        let mut global_config = previous.clone();
+        assert!(global_config.changed_scopes(&previous).is_empty());
+
+        global_config.vsync = !global_config.vsync;
+        global_config.rendering_backend = RenderingBackendKind::Software;
+        global_config.color_filter = Some(ColorFilter::default());
+
+        assert_eq!(
+            global_config.changed_scopes(&previous),
+            HashSet::from([ConfigApplyScope::Swapchain, ConfigApplyScope::Restart])
+        );
+    }
+
+    #[test]
+    fn binding_conflicts() {
+        let system = GameSystem::Other(OtherSystem::Chip8);
+        let mut global_config = GlobalConfig::default();
+        global_config.reset_controller_config(system);
+
+        let (host_input, _) = global_config.controller_configs[&system][0]
+            .first()
+            .map(|(host_input, emulated_input)| (*host_input, *emulated_input))
+            .unwrap();
+        global_config.hotkeys.clear();
+        global_config.hotkeys.insert(host_input, Hotkey::OpenMenu);
+
+        assert_eq!(global_config.binding_conflicts().len(), 1);
+        global_config.resolve_binding_conflicts();
+        assert!(global_config.binding_conflicts().is_empty());
+        assert!(global_config.hotkeys.contains_key(&host_input));
+    }
+
+    #[test]
+    fn default_controller_configs() {
+        assert!(
+            !GlobalConfig::default_controller_config(GameSystem::Other(OtherSystem::Chip8))[0]
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn single_player_controller_configs_still_load() {
+        let system = GameSystem::Other(OtherSystem::Chip8);
+        let mut global_config = GlobalConfig::default();
+        global_config.reset_controller_config(system);
+        global_config.controller_configs[&system].push(
+            [(
+                Input::Keyboard(KeyboardInput::KeyI),
+                Input::Keyboard(KeyboardInput::Numpad2),
+            )]
+            .into(),
+        );
+
+        let saved = ron::to_string(&global_config).unwrap();
+        assert_eq!(
+            ron::from_str::<GlobalConfig>(&saved).unwrap(),
+            global_config
+        );
+
+        // Written before mappings were per player
+        let old = saved.replace(
+            &ron::to_string(&global_config.controller_configs).unwrap(),
+            &ron::to_string(&IndexMap::from([(
+                system,
+                GlobalConfig::default_controller_config(system).remove(0),
+            )]))
+            .unwrap(),
+        );
+        assert_ne!(old, saved);
+        assert_eq!(
+            ron::from_str::<GlobalConfig>(&old)
+                .unwrap()
+                .controller_configs[&system],
+            GlobalConfig::default_controller_config(system)
+        );
+    }
+
+    #[test]
+    fn automatic_frame_skip() {
+        let frame_time = Duration::from_millis(16);
+
+        assert_eq!(
+            FrameSkip::Automatic.frames_to_skip(4, Duration::from_millis(10), frame_time),
+            0
+        );
+        assert_eq!(
+            FrameSkip::Automatic.frames_to_skip(4, Duration::from_millis(40), frame_time),
+            2
+        );
+        // Capped no matter how far behind
+        assert_eq!(
+            FrameSkip::Automatic.frames_to_skip(4, Duration::from_secs(1), frame_time),
+            4
+        );
+        assert_eq!(
+            FrameSkip::Disabled.frames_to_skip(4, Duration::from_secs(1), frame_time),
+            0
+        );
+    }
+
+    #[test]
+    fn game_config_layer() {
+        let system = GameSystem::Other(OtherSystem::Chip8);
+        let game = RomId::new([1; 20]);
+        let mut global_config = GlobalConfig {
+            rewind_depth: 10,
+            color_filter: Some(ColorFilter::default()),
+            ..Default::default()
+        };
+        global_config.reset_controller_config(system);
+        global_config
+            .controller_profiles
+            .entry(system)
+            .or_default()
+            .insert("Empty".to_string(), vec![IndexMap::new()]);
+        global_config.game_configs.insert(
+            game,
+            GameConfig {
+                speed: Some(200),
+                color_filter: Some(None),
+                rewind: Some(false),
+                controller_profile: Some("Empty".to_string()),
+                ..Default::default()
+            },
+        );
+
+        // Nothing applies until the game runs
+        assert_eq!(global_config.effective_speed(), 100);
+        assert!(global_config.effective_rewind());
+        assert!(!global_config.effective_controller_config(system).unwrap()[0].is_empty());
+
+        let previous = global_config.clone();
+        global_config.active_game = Some(game);
+        assert_eq!(global_config.effective_speed(), 200);
+        assert_eq!(global_config.effective_color_filter(), None);
+        assert_eq!(global_config.effective_aspect_mode(), AspectMode::Stretch);
+        assert!(global_config.effective_controller_config(system).unwrap()[0].is_empty());
+        assert_eq!(
+            global_config.changed_scopes(&previous),
+            HashSet::from([ConfigApplyScope::InputMapping, ConfigApplyScope::Rewind])
+        );
+
+        // The menu slows down whatever speed the game runs at
+        assert_eq!(global_config.speed_in_menu(), None);
+        global_config.emulate_in_menu = true;
+        global_config.menu_emulation_speed = 50;
+        assert_eq!(global_config.speed_in_menu(), Some(100));
+    }
+
+    #[test]
+    fn aspect_mode_fit() {
+        assert_eq!(
+            AspectMode::Stretch.fit([640, 480], [64, 32]),
+            ([0, 0], [640, 480])
+        );
+        assert_eq!(
+            AspectMode::Preserve.fit([640, 480], [64, 32]),
+            ([0, 80], [640, 320])
+        );
+        assert_eq!(
+            AspectMode::Integer.fit([650, 480], [64, 32]),
+            ([5, 80], [640, 320])
+        );
+    }
+
+    #[test]
+    fn display_layouts_share_the_window() {
+        let screens = [[256, 192], [256, 192]];
+
+        assert_eq!(
+            DisplayLayout::Vertical.arrange(AspectMode::Preserve, [512, 768], &screens),
+            [([0, 0], [512, 384]), ([0, 384], [512, 384])]
+        );
+        // Integer scaling drops back to fitting once a display would spill into the other
+        assert_eq!(
+            DisplayLayout::Horizontal.arrange(AspectMode::Integer, [400, 192], &screens),
+            [([0, 21], [200, 150]), ([200, 21], [200, 150])]
+        );
+        assert_eq!(
+            DisplayLayout::PictureInPicture.arrange(AspectMode::Stretch, [800, 600], &screens),
+            [([0, 0], [800, 600]), ([600, 450], [200, 150])]
+        );
+    }
+}
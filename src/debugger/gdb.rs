@@ -0,0 +1,719 @@
+//! A stub for the GDB remote serial protocol, so gdb, lldb or IDA can attach to the emulated processors
+//!
+//! Every processor of the machine shows up as a thread. The runtime owns the executor, so running and stopping the
+//! machine goes through [GdbServer::take_requests] and [GdbServer::report_stop]
+
+use super::DebugTarget;
+use crate::component::{memory::MemoryTranslationTable, processor::ProcessorRegister};
+use data_encoding::{HEXLOWER, HEXLOWER_PERMISSIVE};
+use std::{
+    fmt::Write as _,
+    io::{ErrorKind, Read, Write},
+    net::{Ipv4Addr, TcpListener, TcpStream},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{channel, Receiver, RecvTimeoutError, Sender, TryRecvError},
+        Arc,
+    },
+    thread::JoinHandle,
+    time::Duration,
+};
+
+/// How often the server looks for new connections, interrupts and being shut down
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+/// Largest packet gdb may send us, it sizes its memory reads and writes by it
+const PACKET_SIZE: usize = 0x4000;
+
+const SIGINT: u8 = 2;
+const SIGTRAP: u8 = 5;
+const SIGSEGV: u8 = 11;
+
+/// What the debugger wants the machine to do
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GdbRequest {
+    Continue,
+    /// Run a single instruction then stop again
+    Step,
+    Halt,
+}
+
+/// Why the machine stopped, processors are by their index in the targets
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GdbStop {
+    Breakpoint(usize),
+    Fault(usize),
+    Stepped,
+    Halted,
+}
+
+/// Listens on localhost and serves one debugger at a time, until dropped
+pub struct GdbServer {
+    requests: Receiver<GdbRequest>,
+    stops: Sender<GdbStop>,
+    shutdown: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl GdbServer {
+    pub fn start(
+        port: u16,
+        targets: Vec<(&'static str, Arc<dyn DebugTarget>)>,
+        memory_translation_table: Arc<MemoryTranslationTable>,
+    ) -> std::io::Result<Self> {
+        if targets.is_empty() {
+            return Err(std::io::Error::new(
+                ErrorKind::InvalidInput,
+                "The machine has no processors to debug",
+            ));
+        }
+
+        // Anyone who can connect can poke at memory, so it is never reachable from other machines
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, port))?;
+        tracing::info!("GDB stub listening on {}", listener.local_addr()?);
+
+        Self::serve(listener, targets, memory_translation_table)
+    }
+
+    fn serve(
+        listener: TcpListener,
+        targets: Vec<(&'static str, Arc<dyn DebugTarget>)>,
+        memory_translation_table: Arc<MemoryTranslationTable>,
+    ) -> std::io::Result<Self> {
+        // So the thread notices it should stop even if nobody ever connects
+        listener.set_nonblocking(true)?;
+
+        let (request_sender, requests) = channel();
+        let (stops, stop_receiver) = channel();
+        let shutdown = Arc::new(AtomicBool::new(false));
+
+        let thread = std::thread::Builder::new().name("gdb".to_string()).spawn({
+            let shutdown = shutdown.clone();
+
+            move || {
+                while !shutdown.load(Ordering::Relaxed) {
+                    let stream = match listener.accept() {
+                        Ok((stream, address)) => {
+                            tracing::info!("Debugger connected from {}", address);
+                            stream
+                        }
+                        Err(error) if error.kind() == ErrorKind::WouldBlock => {
+                            std::thread::sleep(POLL_INTERVAL);
+                            continue;
+                        }
+                        Err(error) => {
+                            tracing::error!("GDB stub stopped listening: {}", error);
+                            return;
+                        }
+                    };
+
+                    let session = GdbSession {
+                        stream,
+                        pending: Vec::new(),
+                        no_ack: false,
+                        targets: &targets,
+                        memory_translation_table: &memory_translation_table,
+                        requests: &request_sender,
+                        stops: &stop_receiver,
+                        shutdown: &shutdown,
+                        selected: 0,
+                        last_stop: GdbStop::Halted,
+                    };
+
+                    match session.run() {
+                        Ok(()) => tracing::info!("Debugger detached"),
+                        Err(error) => tracing::warn!("Debugger connection ended: {}", error),
+                    }
+                }
+            }
+        })?;
+
+        Ok(Self {
+            requests,
+            stops,
+            shutdown,
+            thread: Some(thread),
+        })
+    }
+
+    /// What the debugger asked for since the last call, in order
+    pub fn take_requests(&self) -> impl Iterator<Item = GdbRequest> + '_ {
+        self.requests.try_iter()
+    }
+
+    /// Tells the debugger the machine stopped, whether it asked for that or not
+    pub fn report_stop(&self, stop: GdbStop) {
+        let _ = self.stops.send(stop);
+    }
+}
+
+impl Drop for GdbServer {
+    fn drop(&mut self) {
+        self.shutdown.store(true, Ordering::Relaxed);
+
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+/// What came in from the debugger
+enum Incoming {
+    Packet(String),
+    /// A lone 0x03, asking the running machine to stop
+    Interrupt,
+}
+
+struct GdbSession<'a> {
+    stream: TcpStream,
+    /// Read but not yet made sense of
+    pending: Vec<u8>,
+    /// The debugger asked to skip acknowledging packets, which is fine over TCP
+    no_ack: bool,
+    targets: &'a [(&'static str, Arc<dyn DebugTarget>)],
+    memory_translation_table: &'a MemoryTranslationTable,
+    requests: &'a Sender<GdbRequest>,
+    stops: &'a Receiver<GdbStop>,
+    shutdown: &'a AtomicBool,
+    /// Index of the target register and memory commands work on
+    selected: usize,
+    last_stop: GdbStop,
+}
+
+impl GdbSession<'_> {
+    fn run(mut self) -> std::io::Result<()> {
+        self.stream.set_nonblocking(false)?;
+        self.stream.set_read_timeout(Some(POLL_INTERVAL))?;
+        self.stream.set_nodelay(true)?;
+
+        // Debuggers expect whatever they attach to to hold still
+        self.resume(GdbRequest::Halt)?;
+
+        while !self.shutdown.load(Ordering::Relaxed) {
+            let Some(Incoming::Packet(packet)) = self.next_incoming()? else {
+                continue;
+            };
+
+            tracing::debug!("GDB packet: {}", packet);
+
+            match self.handle(&packet)? {
+                Some(reply) => self.send(&reply)?,
+                None => return Ok(()),
+            }
+
+            // Its OK still gets acknowledged, only what follows doesn't
+            if packet == "QStartNoAckMode" {
+                self.no_ack = true;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// The reply to a packet, None ends the session
+    fn handle(&mut self, packet: &str) -> std::io::Result<Option<String>> {
+        let reply = match packet.as_bytes().first() {
+            Some(b'?') => self.stop_reply(self.last_stop),
+            Some(b'g') => self.registers_reply(),
+            Some(b'p') => usize::from_str_radix(&packet[1..], 16)
+                .ok()
+                .and_then(|index| self.selected().registers().get(index).copied())
+                .map_or_else(|| "E01".to_string(), |register| encode_register(&register)),
+            Some(b'm') => self.read_memory(&packet[1..]).unwrap_or("E14".to_string()),
+            Some(b'M') => self.write_memory(&packet[1..]).unwrap_or("E14".to_string()),
+            Some(b'Z' | b'z') => self.change_breakpoint(packet),
+            Some(b'c') => self.resume(GdbRequest::Continue)?,
+            Some(b's') => self.resume(GdbRequest::Step)?,
+            Some(b'H') => {
+                let thread = packet.get(2..).unwrap_or_default();
+                match self.target_index(thread) {
+                    Some(index) if packet.starts_with("Hg") => {
+                        self.selected = index;
+                        "OK".to_string()
+                    }
+                    // Continuing and stepping always apply to the whole machine
+                    Some(_) => "OK".to_string(),
+                    None if thread == "0" || thread == "-1" => "OK".to_string(),
+                    None => "E01".to_string(),
+                }
+            }
+            Some(b'T') => match self.target_index(&packet[1..]) {
+                Some(_) => "OK".to_string(),
+                None => "E01".to_string(),
+            },
+            Some(b'D') => {
+                self.send("OK")?;
+                self.detach();
+                return Ok(None);
+            }
+            Some(b'k') => {
+                self.detach();
+                return Ok(None);
+            }
+            Some(b'q' | b'Q') => self.handle_query(packet),
+            // Unsupported, which gdb handles by falling back to something else
+            _ => String::new(),
+        };
+
+        Ok(Some(reply))
+    }
+
+    fn handle_query(&self, packet: &str) -> String {
+        if packet.starts_with("qSupported") {
+            format!(
+                "PacketSize={:x};qXfer:features:read+;QStartNoAckMode+;swbreak+",
+                PACKET_SIZE
+            )
+        } else if packet == "QStartNoAckMode" {
+            "OK".to_string()
+        } else if let Some(range) = packet.strip_prefix("qXfer:features:read:target.xml:") {
+            let description = target_description(&self.selected().registers());
+            let (offset, length) = parse_pair(range).unwrap_or((0, 0));
+            let chunk = description.get(offset..).unwrap_or_default();
+
+            if chunk.len() > length {
+                format!("m{}", &chunk[..length])
+            } else {
+                format!("l{}", chunk)
+            }
+        } else if packet == "qAttached" {
+            "1".to_string()
+        } else if packet == "qC" {
+            format!("QC{:x}", self.selected + 1)
+        } else if packet == "qfThreadInfo" {
+            let threads: Vec<_> = (1..=self.targets.len())
+                .map(|thread| format!("{:x}", thread))
+                .collect();
+            format!("m{}", threads.join(","))
+        } else if packet == "qsThreadInfo" {
+            "l".to_string()
+        } else if let Some(thread) = packet.strip_prefix("qThreadExtraInfo,") {
+            match self.target_index(thread) {
+                Some(index) => HEXLOWER.encode(self.targets[index].0.as_bytes()),
+                None => "E01".to_string(),
+            }
+        } else {
+            String::new()
+        }
+    }
+
+    fn selected(&self) -> &dyn DebugTarget {
+        self.targets[self.selected].1.as_ref()
+    }
+
+    /// Threads are numbered from 1, as 0 means any thread to gdb
+    fn target_index(&self, thread: &str) -> Option<usize> {
+        usize::from_str_radix(thread, 16)
+            .ok()
+            .filter(|thread| (1..=self.targets.len()).contains(thread))
+            .map(|thread| thread - 1)
+    }
+
+    fn registers_reply(&self) -> String {
+        self.selected()
+            .registers()
+            .iter()
+            .map(encode_register)
+            .collect()
+    }
+
+    fn read_memory(&self, arguments: &str) -> Option<String> {
+        let (address, length) = parse_pair(arguments)?;
+        let mut buffer = vec![0; length.min(PACKET_SIZE / 2)];
+
+        // Previewing so looking at IO registers doesn't set off their side effects
+        self.memory_translation_table
+            .preview(address, &mut buffer)
+            .ok()?;
+
+        Some(HEXLOWER.encode(&buffer))
+    }
+
+    fn write_memory(&self, arguments: &str) -> Option<String> {
+        let (range, data) = arguments.split_once(':')?;
+        let (address, length) = parse_pair(range)?;
+        let data = HEXLOWER_PERMISSIVE.decode(data.as_bytes()).ok()?;
+
+        if data.len() != length {
+            return None;
+        }
+
+        for (offset, byte) in data.into_iter().enumerate() {
+            self.memory_translation_table
+                .write(address + offset, &[byte])
+                .ok()?;
+        }
+
+        Some("OK".to_string())
+    }
+
+    /// Software and hardware breakpoints are the same thing here, watchpoints aren't supported
+    fn change_breakpoint(&self, packet: &str) -> String {
+        let mut arguments = packet[1..].split(',');
+        let (Some("0" | "1"), Some(address)) = (arguments.next(), arguments.next()) else {
+            return String::new();
+        };
+        let Ok(address) = usize::from_str_radix(address, 16) else {
+            return "E01".to_string();
+        };
+
+        let mut debug_state = self.selected().debug_state().lock().unwrap();
+        if packet.starts_with('Z') {
+            debug_state.breakpoints.insert(address);
+        } else {
+            debug_state.breakpoints.remove(&address);
+        }
+
+        "OK".to_string()
+    }
+
+    /// Asks the runtime for something and waits until the machine stops again, which for continuing is whenever a
+    /// breakpoint is hit or the debugger interrupts
+    fn resume(&mut self, request: GdbRequest) -> std::io::Result<String> {
+        let machine_gone = || std::io::Error::other("The machine went away");
+
+        // Anything from before was already told about or happened without a debugger
+        while self.stops.try_recv().is_ok() {}
+        self.requests.send(request).map_err(|_| machine_gone())?;
+
+        let mut halt_requested = request == GdbRequest::Halt;
+
+        loop {
+            // Once stopping there is nothing to listen for, and what the debugger sends next is left for after
+            let stop = if halt_requested {
+                self.stops
+                    .recv_timeout(POLL_INTERVAL)
+                    .map_err(|error| match error {
+                        RecvTimeoutError::Timeout => TryRecvError::Empty,
+                        RecvTimeoutError::Disconnected => TryRecvError::Disconnected,
+                    })
+            } else {
+                self.stops.try_recv()
+            };
+
+            match stop {
+                Ok(stop) => {
+                    self.last_stop = stop;
+                    if let GdbStop::Breakpoint(index) | GdbStop::Fault(index) = stop {
+                        self.selected = index;
+                    }
+
+                    return Ok(self.stop_reply(stop));
+                }
+                Err(TryRecvError::Empty) => {}
+                Err(TryRecvError::Disconnected) => return Err(machine_gone()),
+            }
+
+            if self.shutdown.load(Ordering::Relaxed) {
+                return Err(machine_gone());
+            }
+
+            // Packets other than the interrupt aren't allowed while running
+            if !halt_requested {
+                if let Some(Incoming::Interrupt) = self.next_incoming()? {
+                    halt_requested = true;
+                    self.requests
+                        .send(GdbRequest::Halt)
+                        .map_err(|_| machine_gone())?;
+                }
+            }
+        }
+    }
+
+    /// Lets the machine run on without the debugger, with the breakpoints it set gone
+    fn detach(&self) {
+        for (_, target) in self.targets {
+            target.debug_state().lock().unwrap().breakpoints.clear();
+        }
+
+        let _ = self.requests.send(GdbRequest::Continue);
+    }
+
+    fn stop_reply(&self, stop: GdbStop) -> String {
+        let (signal, thread, reason) = match stop {
+            GdbStop::Breakpoint(index) => (SIGTRAP, index, "swbreak:;"),
+            GdbStop::Fault(index) => (SIGSEGV, index, ""),
+            GdbStop::Stepped => (SIGTRAP, self.selected, ""),
+            GdbStop::Halted => (SIGINT, self.selected, ""),
+        };
+
+        format!("T{:02x}thread:{:x};{}", signal, thread + 1, reason)
+    }
+
+    /// Waits a poll interval at most, None if nothing complete came in
+    fn next_incoming(&mut self) -> std::io::Result<Option<Incoming>> {
+        loop {
+            if let Some(incoming) = self.parse_pending()? {
+                return Ok(Some(incoming));
+            }
+
+            let mut buffer = [0; 1024];
+            match self.stream.read(&mut buffer) {
+                Ok(0) => {
+                    return Err(std::io::Error::new(
+                        ErrorKind::UnexpectedEof,
+                        "The debugger hung up",
+                    ))
+                }
+                Ok(length) => self.pending.extend_from_slice(&buffer[..length]),
+                Err(error)
+                    if matches!(error.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) =>
+                {
+                    return Ok(None)
+                }
+                Err(error) => return Err(error),
+            }
+        }
+    }
+
+    /// Takes the first whole packet out of what was read so far, acknowledging it
+    fn parse_pending(&mut self) -> std::io::Result<Option<Incoming>> {
+        // Acknowledgements carry nothing, we never resend
+        let start = self
+            .pending
+            .iter()
+            .position(|byte| !matches!(byte, b'+' | b'-'))
+            .unwrap_or(self.pending.len());
+        self.pending.drain(..start);
+
+        match self.pending.first() {
+            None => return Ok(None),
+            Some(0x03) => {
+                self.pending.remove(0);
+                return Ok(Some(Incoming::Interrupt));
+            }
+            Some(b'$') => {}
+            Some(_) => {
+                // Line noise
+                self.pending.remove(0);
+                return Ok(None);
+            }
+        }
+
+        let Some(end) = self.pending.iter().position(|byte| *byte == b'#') else {
+            return Ok(None);
+        };
+        if self.pending.len() < end + 3 {
+            return Ok(None);
+        }
+
+        let packet: Vec<_> = self.pending.drain(..end + 3).collect();
+        let data = &packet[1..end];
+        let checksum = std::str::from_utf8(&packet[end + 1..])
+            .ok()
+            .and_then(|checksum| u8::from_str_radix(checksum, 16).ok());
+
+        if checksum != Some(checksum_of(data)) {
+            if !self.no_ack {
+                self.stream.write_all(b"-")?;
+            }
+            return Ok(None);
+        }
+
+        if !self.no_ack {
+            self.stream.write_all(b"+")?;
+        }
+
+        Ok(Some(Incoming::Packet(
+            String::from_utf8_lossy(&unescape(data)).into_owned(),
+        )))
+    }
+
+    fn send(&mut self, data: &str) -> std::io::Result<()> {
+        self.stream.write_all(&frame(data.as_bytes()))
+    }
+}
+
+fn checksum_of(data: &[u8]) -> u8 {
+    data.iter().fold(0, |sum, byte| sum.wrapping_add(*byte))
+}
+
+/// Puts data into a packet, escaping what would confuse the framing
+fn frame(data: &[u8]) -> Vec<u8> {
+    let mut escaped = Vec::with_capacity(data.len());
+    for byte in data {
+        if matches!(byte, b'$' | b'#' | b'}' | b'*') {
+            escaped.extend([b'}', byte ^ 0x20]);
+        } else {
+            escaped.push(*byte);
+        }
+    }
+
+    let mut packet = vec![b'$'];
+    packet.extend_from_slice(&escaped);
+    packet.push(b'#');
+    packet.extend(format!("{:02x}", checksum_of(&escaped)).into_bytes());
+
+    packet
+}
+
+fn unescape(data: &[u8]) -> Vec<u8> {
+    let mut unescaped = Vec::with_capacity(data.len());
+    let mut bytes = data.iter();
+
+    while let Some(byte) = bytes.next() {
+        match byte {
+            b'}' => unescaped.extend(bytes.next().map(|byte| byte ^ 0x20)),
+            byte => unescaped.push(*byte),
+        }
+    }
+
+    unescaped
+}
+
+/// Two hex numbers split by a comma, like the address and length of memory packets
+fn parse_pair(arguments: &str) -> Option<(usize, usize)> {
+    let (first, second) = arguments.split_once(',')?;
+
+    Some((
+        usize::from_str_radix(first, 16).ok()?,
+        usize::from_str_radix(second, 16).ok()?,
+    ))
+}
+
+/// Target byte order doesn't matter to the emulator, gdb gets everything little endian
+fn encode_register(register: &ProcessorRegister) -> String {
+    let bytes = (register.bits as usize).div_ceil(8);
+
+    HEXLOWER.encode(&register.value.to_le_bytes()[..bytes])
+}
+
+/// Tells gdb what registers there are, as most of what is emulated isn't a architecture it knows
+fn target_description(registers: &[ProcessorRegister]) -> String {
+    let mut description = String::from(
+        r#"<?xml version="1.0"?><!DOCTYPE target SYSTEM "gdb-target.dtd"><target version="1.0"><feature name="org.multiemu.processor">"#,
+    );
+
+    for (index, register) in registers.iter().enumerate() {
+        let kind = if register.name == "pc" {
+            "code_ptr"
+        } else {
+            "int"
+        };
+
+        write!(
+            description,
+            r#"<reg name="{}" bitsize="{}" regnum="{}" type="{}"/>"#,
+            register.name, register.bits, index, kind
+        )
+        .unwrap();
+    }
+
+    description.push_str("</feature></target>");
+    description
+}
+
+#[cfg(all(test, feature = "chip8"))]
+mod tests {
+    use super::*;
+    use crate::{
+        component::{
+            definitions::{
+                chip8::{
+                    processor::{Chip8Processor, Chip8ProcessorConfig},
+                    Chip8Kind,
+                },
+                misc::plain_memory::{PlainMemory, PlainMemoryConfig, PlainMemoryInitialContents},
+            },
+            FromConfig,
+        },
+        debugger::ProcessorDebugTarget,
+        rom::RomManager,
+        task::{
+            processor::{ProcessorTask, ProcessorTaskConfig},
+            InitializeableTask,
+        },
+    };
+    use num::rational::Ratio;
+    use std::sync::Mutex;
+
+    /// Sends a packet and returns the reply, skipping acknowledgements
+    fn exchange(stream: &mut TcpStream, data: &str) -> String {
+        stream.write_all(&frame(data.as_bytes())).unwrap();
+
+        let mut reply = Vec::new();
+        let mut byte = [0];
+        while !reply.ends_with(b"#") {
+            stream.read_exact(&mut byte).unwrap();
+            if !reply.is_empty() || byte[0] == b'$' {
+                reply.push(byte[0]);
+            }
+        }
+        let mut checksum = [0; 2];
+        stream.read_exact(&mut checksum).unwrap();
+
+        String::from_utf8(reply[1..reply.len() - 1].to_vec()).unwrap()
+    }
+
+    #[test]
+    fn serves_registers_memory_and_breakpoints() {
+        let rom_manager = Arc::new(RomManager::default());
+        let processor = Arc::new(Mutex::new(Chip8Processor::from_config(
+            rom_manager.clone(),
+            Chip8ProcessorConfig {
+                frequency: Ratio::from_integer(700),
+                kind: Chip8Kind::Chip8,
+            },
+        )));
+        let task = ProcessorTask::new(
+            processor.clone(),
+            ProcessorTaskConfig {
+                initial_program_pointer: 0x200,
+            },
+        );
+        let debug_state = task.debug_state();
+
+        let mut memory_translation_table = MemoryTranslationTable::default();
+        memory_translation_table.insert(
+            0x200..0x1000,
+            Arc::new(Mutex::new(PlainMemory::from_config(
+                rom_manager,
+                PlainMemoryConfig {
+                    max_word_size: 2,
+                    assigned_range: 0x200..0x1000,
+                    initial_contents: PlainMemoryInitialContents::Value { value: 0x60 },
+                    ..Default::default()
+                },
+            ))),
+        );
+
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+        let address = listener.local_addr().unwrap();
+        let server = GdbServer::serve(
+            listener,
+            vec![(
+                "processor",
+                Arc::new(ProcessorDebugTarget::new(processor, debug_state.clone())),
+            )],
+            Arc::new(memory_translation_table),
+        )
+        .unwrap();
+        let mut stream = TcpStream::connect(address).unwrap();
+
+        // Attaching halts the machine, which the runtime would answer
+        while server.take_requests().next() != Some(GdbRequest::Halt) {
+            std::thread::sleep(POLL_INTERVAL);
+        }
+        server.report_stop(GdbStop::Halted);
+
+        assert_eq!(exchange(&mut stream, "?"), "T02thread:1;");
+        // V0 through VF, then I, then the program pointer
+        assert_eq!(
+            exchange(&mut stream, "g"),
+            format!("{}{}{}", "00".repeat(16), "0000", "0002")
+        );
+        assert_eq!(exchange(&mut stream, "m200,4"), "60606060");
+        assert_eq!(exchange(&mut stream, "m0,4"), "E14");
+        assert_eq!(exchange(&mut stream, "M200,2:00e0"), "OK");
+        assert_eq!(exchange(&mut stream, "m200,2"), "00e0");
+
+        assert_eq!(exchange(&mut stream, "Z0,204,2"), "OK");
+        assert!(debug_state.lock().unwrap().breakpoints.contains(&0x204));
+        assert_eq!(exchange(&mut stream, "z0,204,2"), "OK");
+        assert!(debug_state.lock().unwrap().breakpoints.is_empty());
+
+        assert!(
+            exchange(&mut stream, "qXfer:features:read:target.xml:0,1000")
+                .contains(r#"<reg name="pc" bitsize="16" regnum="17" type="code_ptr"/>"#)
+        );
+    }
+}
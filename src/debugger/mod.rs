@@ -0,0 +1,44 @@
+use crate::{
+    component::processor::{ProcessorComponent, ProcessorRegister},
+    task::processor::ProcessorDebugState,
+};
+use std::sync::{Arc, Mutex};
+
+#[cfg(desktop)]
+pub mod gdb;
+
+/// A processor debuggers outside of the GUI can inspect and stop, with the concrete component hidden
+pub trait DebugTarget: Send + Sync {
+    /// As of the last time the processor ran
+    fn registers(&self) -> Vec<ProcessorRegister>;
+    fn debug_state(&self) -> &Arc<Mutex<ProcessorDebugState>>;
+}
+
+pub struct ProcessorDebugTarget<C: ProcessorComponent> {
+    component: Arc<Mutex<C>>,
+    debug_state: Arc<Mutex<ProcessorDebugState>>,
+}
+
+impl<C: ProcessorComponent> ProcessorDebugTarget<C> {
+    pub fn new(component: Arc<Mutex<C>>, debug_state: Arc<Mutex<ProcessorDebugState>>) -> Self {
+        Self {
+            component,
+            debug_state,
+        }
+    }
+}
+
+impl<C: ProcessorComponent> DebugTarget for ProcessorDebugTarget<C> {
+    fn registers(&self) -> Vec<ProcessorRegister> {
+        let program_pointer = self.debug_state.lock().unwrap().program_pointer;
+
+        self.component
+            .lock()
+            .unwrap()
+            .debug_registers(program_pointer)
+    }
+
+    fn debug_state(&self) -> &Arc<Mutex<ProcessorDebugState>> {
+        &self.debug_state
+    }
+}
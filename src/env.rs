@@ -1,19 +1,156 @@
+//! Where everything is kept. Desktops follow the XDG base directories (or what the host has instead), so the config,
+//! what is worth keeping and what can be thrown away each go where sandboxed packages like flatpaks expect them
+
+#[cfg(desktop)]
+use crate::atomic_write::backup_path;
+#[cfg(desktop)]
+use std::{fs, path::Path};
 use std::{path::PathBuf, sync::LazyLock};
 
+/// The database, imported ROMs, saves and everything else worth keeping
 #[cfg(desktop)]
 pub static STORAGE_DIRECTORY: LazyLock<PathBuf> =
     LazyLock::new(|| dirs::data_dir().unwrap().join("multiemu"));
 #[cfg(nintendo_3ds)]
 pub static STORAGE_DIRECTORY: LazyLock<PathBuf> = LazyLock::new(|| PathBuf::from("sdmc:/multiemu"));
+/// Nothing the core writes is meant to be kept
+#[cfg(feature = "core-only")]
+pub static STORAGE_DIRECTORY: LazyLock<PathBuf> =
+    LazyLock::new(|| std::env::temp_dir().join("multiemu"));
+
+#[cfg(desktop)]
+pub static CONFIG_DIRECTORY: LazyLock<PathBuf> =
+    LazyLock::new(|| dirs::config_dir().unwrap().join("multiemu"));
+/// Logs and whatever else can be made again, which hosts may clean up on their own
+#[cfg(desktop)]
+pub static CACHE_DIRECTORY: LazyLock<PathBuf> =
+    LazyLock::new(|| dirs::cache_dir().unwrap().join("multiemu"));
+/// Elsewhere there is no split
+#[cfg(not(desktop))]
+pub static CONFIG_DIRECTORY: LazyLock<PathBuf> = LazyLock::new(|| STORAGE_DIRECTORY.clone());
+#[cfg(not(desktop))]
+pub static CACHE_DIRECTORY: LazyLock<PathBuf> = LazyLock::new(|| STORAGE_DIRECTORY.clone());
 
 pub static CONFIG_LOCATION: LazyLock<PathBuf> =
-    LazyLock::new(|| STORAGE_DIRECTORY.join("config.ron"));
-pub static LOG_LOCATION: LazyLock<PathBuf> = LazyLock::new(|| STORAGE_DIRECTORY.join("log.txt"));
+    LazyLock::new(|| CONFIG_DIRECTORY.join("config.ron"));
+pub static LOG_LOCATION: LazyLock<PathBuf> = LazyLock::new(|| CACHE_DIRECTORY.join("log.txt"));
 pub static ROM_DATABASE_PATH: LazyLock<PathBuf> =
     LazyLock::new(|| STORAGE_DIRECTORY.join("database"));
-pub static SAVE_RAM_DIRECTORY: LazyLock<PathBuf> =
-    LazyLock::new(|| STORAGE_DIRECTORY.join("saves"));
+/// Cheats of every game, by ROM
+#[cfg(feature = "frontend")]
+pub static CHEAT_DATABASE_PATH: LazyLock<PathBuf> =
+    LazyLock::new(|| STORAGE_DIRECTORY.join("cheats"));
+/// What was open when the GUI last shut down
+#[cfg(desktop)]
+pub static SESSION_PATH: LazyLock<PathBuf> = LazyLock::new(|| CONFIG_DIRECTORY.join("session.ron"));
 pub static SNAPSHOT_DIRECTORY: LazyLock<PathBuf> =
     LazyLock::new(|| STORAGE_DIRECTORY.join("snapshot"));
+#[cfg(desktop)]
+pub static AUDIO_CAPTURE_DIRECTORY: LazyLock<PathBuf> =
+    LazyLock::new(|| STORAGE_DIRECTORY.join("audio_capture"));
+#[cfg(desktop)]
+pub static FRAMEBUFFER_DUMP_DIRECTORY: LazyLock<PathBuf> =
+    LazyLock::new(|| STORAGE_DIRECTORY.join("framebuffer_dumps"));
+/// Per game screenshots and such, in a directory named after the ROM
+#[cfg(feature = "frontend")]
+pub static MEDIA_DIRECTORY: LazyLock<PathBuf> = LazyLock::new(|| STORAGE_DIRECTORY.join("media"));
+/// Images of memory cards and the like, by format
+#[cfg(any(feature = "frontend", test))]
+pub static MEMORY_CARD_DIRECTORY: LazyLock<PathBuf> =
+    LazyLock::new(|| STORAGE_DIRECTORY.join("memory_cards"));
+/// Blocks the dynamic recompiler found, in a file named after the ROM
+pub static DYNAREC_CACHE_DIRECTORY: LazyLock<PathBuf> =
+    LazyLock::new(|| CACHE_DIRECTORY.join("dynarec_cache"));
+/// Compiled GPU pipelines, in a file per device and driver
+#[cfg(vulkan)]
+pub static PIPELINE_CACHE_DIRECTORY: LazyLock<PathBuf> =
+    LazyLock::new(|| CACHE_DIRECTORY.join("pipeline_cache"));
+/// Scripts the user wrote, which games run if they are enabled for them
+#[cfg(feature = "frontend")]
+pub static SCRIPT_DIRECTORY: LazyLock<PathBuf> =
+    LazyLock::new(|| STORAGE_DIRECTORY.join("scripts"));
 pub static IMPORTED_ROM_DIRECTORY: LazyLock<PathBuf> =
     LazyLock::new(|| STORAGE_DIRECTORY.join("roms"));
+
+/// Moves what older versions kept in the storage directory to where it goes now, doing nothing once that is done
+///
+/// Hosts where the config and data directories are the same have nothing to move for the config
+#[cfg(desktop)]
+pub fn migrate_legacy_layout() {
+    let legacy_config_location = STORAGE_DIRECTORY.join("config.ron");
+
+    for (legacy_path, path) in [
+        (legacy_config_location.clone(), CONFIG_LOCATION.clone()),
+        (
+            backup_path(&legacy_config_location),
+            backup_path(&CONFIG_LOCATION),
+        ),
+        (
+            STORAGE_DIRECTORY.join("dynarec_cache"),
+            DYNAREC_CACHE_DIRECTORY.clone(),
+        ),
+    ] {
+        migrate(&legacy_path, &path);
+    }
+
+    // Every run starts a new log anyway
+    let legacy_log_location = STORAGE_DIRECTORY.join("log.txt");
+    if legacy_log_location != *LOG_LOCATION {
+        let _ = fs::remove_file(legacy_log_location);
+    }
+}
+
+#[cfg(desktop)]
+fn migrate(legacy_path: &Path, path: &Path) {
+    if legacy_path == path || !legacy_path.exists() || path.exists() {
+        return;
+    }
+
+    tracing::info!("Moving {} to {}", legacy_path.display(), path.display());
+
+    if let Some(parent) = path.parent() {
+        let _ = fs::create_dir_all(parent);
+    }
+
+    if fs::rename(legacy_path, path).is_ok() {
+        return;
+    }
+
+    // Renaming fails across filesystems. Files are copied over, directories are only ever caches so they are dropped
+    let result = if legacy_path.is_dir() {
+        fs::remove_dir_all(legacy_path)
+    } else {
+        fs::copy(legacy_path, path).and_then(|_| fs::remove_file(legacy_path))
+    };
+
+    if let Err(error) = result {
+        tracing::error!("Could not move {}: {}", legacy_path.display(), error);
+    }
+}
+
+#[cfg(all(test, desktop))]
+mod tests {
+    use super::migrate;
+    use std::fs;
+
+    #[test]
+    fn migration_moves_once_and_keeps_newer_files() {
+        let directory =
+            std::env::temp_dir().join(format!("multiemu_env_migration_{}", std::process::id()));
+        let legacy_path = directory.join("legacy").join("config.ron");
+        let path = directory.join("config").join("config.ron");
+        fs::create_dir_all(legacy_path.parent().unwrap()).unwrap();
+
+        fs::write(&legacy_path, "old").unwrap();
+        migrate(&legacy_path, &path);
+        assert!(!legacy_path.exists());
+        assert_eq!(fs::read_to_string(&path).unwrap(), "old");
+
+        // What is already in the new place wins
+        fs::write(&legacy_path, "older").unwrap();
+        migrate(&legacy_path, &path);
+        assert_eq!(fs::read_to_string(&path).unwrap(), "old");
+
+        fs::remove_dir_all(directory).unwrap();
+    }
+}
@@ -0,0 +1,90 @@
+use ringbuffer::{AllocRingBuffer, RingBuffer};
+use std::{
+    fmt::Debug,
+    sync::{LazyLock, Mutex},
+    time::SystemTime,
+};
+use tracing::{field::Field, Event, Level, Subscriber};
+use tracing_subscriber::{layer::Context, Layer};
+
+const EVENT_LOG_CAPACITY: usize = 4096;
+
+/// Events of the current session, for showing the user what went wrong without making them dig through the log file
+pub static EVENT_LOG: LazyLock<Mutex<AllocRingBuffer<EventLogEntry>>> =
+    LazyLock::new(|| Mutex::new(AllocRingBuffer::new(EVENT_LOG_CAPACITY)));
+
+#[derive(Debug, Clone)]
+pub struct EventLogEntry {
+    pub time: SystemTime,
+    pub level: Level,
+    /// Where the event came from, either the `component` field of the event or its module
+    pub component: String,
+    pub message: String,
+}
+
+/// Tracing layer that copies events into [EVENT_LOG]
+pub struct EventLogLayer;
+
+impl<S: Subscriber> Layer<S> for EventLogLayer {
+    fn on_event(&self, event: &Event<'_>, _context: Context<'_, S>) {
+        let metadata = event.metadata();
+
+        // Nothing below info is useful to a user
+        if *metadata.level() > Level::INFO {
+            return;
+        }
+
+        let mut visitor = EventLogVisitor::default();
+        event.record(&mut visitor);
+
+        let component = visitor.component.unwrap_or_else(|| {
+            metadata
+                .target()
+                .trim_start_matches(concat!(env!("CARGO_CRATE_NAME"), "::"))
+                .to_string()
+        });
+
+        EVENT_LOG.lock().unwrap().push(EventLogEntry {
+            time: SystemTime::now(),
+            level: *metadata.level(),
+            component,
+            message: if visitor.fields.is_empty() {
+                visitor.message
+            } else {
+                format!("{} {}", visitor.message, visitor.fields)
+            },
+        });
+    }
+}
+
+#[derive(Default)]
+struct EventLogVisitor {
+    message: String,
+    component: Option<String>,
+    /// Any other fields, formatted
+    fields: String,
+}
+
+impl tracing::field::Visit for EventLogVisitor {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        match field.name() {
+            "component" => self.component = Some(value.to_string()),
+            "message" => self.message = value.to_string(),
+            _ => self.record_debug(field, &value),
+        }
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+        match field.name() {
+            "message" => self.message = format!("{:?}", value),
+            "component" => self.component = Some(format!("{:?}", value)),
+            name => {
+                if !self.fields.is_empty() {
+                    self.fields.push(' ');
+                }
+
+                self.fields.push_str(&format!("{}={:?}", name, value));
+            }
+        }
+    }
+}
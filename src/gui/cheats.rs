@@ -0,0 +1,107 @@
+use super::UiOutput;
+use crate::{
+    cheats::{decode, Cheat, CheatDatabase},
+    env::CHEAT_DATABASE_PATH,
+    rom::{GameSystem, RomId},
+};
+use egui::{Grid, TextEdit, Ui};
+
+#[derive(Clone, Debug, Default)]
+pub struct CheatsState {
+    /// Loaded the first time the page is shown
+    database: Option<CheatDatabase>,
+    new_name: String,
+    new_code: String,
+    error: Option<String>,
+}
+
+/// Adds, toggles and removes the cheats of the running game, saving them as they change. Returns the new cheats if
+/// they changed
+pub fn cheats_page(
+    ui: &mut Ui,
+    state: &mut CheatsState,
+    game: RomId,
+    system: GameSystem,
+) -> Option<UiOutput> {
+    let database = state.database.get_or_insert_with(|| {
+        CheatDatabase::load(&CHEAT_DATABASE_PATH).unwrap_or_else(|error| {
+            tracing::error!("Failed to load the cheat database: {}", error);
+            CheatDatabase::default()
+        })
+    });
+    let mut cheats = database.cheats.get(&game).cloned().unwrap_or_default();
+
+    ui.horizontal(|ui| {
+        ui.add(TextEdit::singleline(&mut state.new_name).hint_text("Name"));
+        ui.add(TextEdit::singleline(&mut state.new_code).hint_text("Code"));
+
+        if ui.button("Add").clicked() {
+            match decode(&state.new_code, system) {
+                Ok(_) => {
+                    let name = state.new_name.trim();
+
+                    cheats.push(Cheat {
+                        name: if name.is_empty() {
+                            state.new_code.trim().to_string()
+                        } else {
+                            name.to_string()
+                        },
+                        code: state.new_code.trim().to_string(),
+                        enabled: true,
+                    });
+                    state.new_name.clear();
+                    state.new_code.clear();
+                    state.error = None;
+                }
+                Err(error) => state.error = Some(error.to_string()),
+            }
+        }
+    });
+
+    ui.label("Codes can be joined with +, address:value and address:value:compare in hex work for every system");
+
+    if let Some(error) = &state.error {
+        ui.colored_label(ui.visuals().error_fg_color, error);
+    }
+
+    ui.separator();
+
+    let mut removed = None;
+    Grid::new("cheats").num_columns(3).show(ui, |ui| {
+        for (index, cheat) in cheats.iter_mut().enumerate() {
+            ui.checkbox(&mut cheat.enabled, &cheat.name);
+            ui.monospace(&cheat.code);
+
+            if ui.button("Remove").clicked() {
+                removed = Some(index);
+            }
+
+            ui.end_row();
+        }
+    });
+
+    if let Some(index) = removed {
+        cheats.remove(index);
+    }
+
+    if database
+        .cheats
+        .get(&game)
+        .map_or(cheats.is_empty(), |original| *original == cheats)
+    {
+        return None;
+    }
+
+    if cheats.is_empty() {
+        database.cheats.remove(&game);
+    } else {
+        database.cheats.insert(game, cheats.clone());
+    }
+
+    if let Err(error) = database.store(&CHEAT_DATABASE_PATH) {
+        tracing::error!("Failed to save the cheat database: {}", error);
+        state.error = Some(format!("Could not save the cheats: {}", error));
+    }
+
+    Some(UiOutput::SetCheats { cheats })
+}
@@ -0,0 +1,183 @@
+use super::{MenuItem, UiOutput};
+use egui::{Align2, Context, Key, RichText, SelectableLabel, TextEdit};
+
+/// How many matches are listed, more than this and the query needs to be more specific
+const SHOWN_MATCHES: usize = 12;
+
+/// What picking an entry does
+#[derive(Clone, Debug)]
+pub enum PaletteAction {
+    Output(UiOutput),
+    OpenPage(MenuItem),
+    Resume,
+    ToggleMute,
+    ToggleColorFilter,
+}
+
+#[derive(Clone, Debug)]
+pub struct PaletteEntry {
+    pub label: String,
+    /// Shown next to the label, like "Game" or "Settings"
+    pub kind: &'static str,
+    pub action: PaletteAction,
+}
+
+impl PaletteEntry {
+    pub fn new(kind: &'static str, label: impl Into<String>, action: PaletteAction) -> Self {
+        Self {
+            label: label.into(),
+            kind,
+            action,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct CommandPaletteState {
+    open: bool,
+    query: String,
+    /// Index into the matches
+    selected: usize,
+}
+
+impl CommandPaletteState {
+    pub fn is_open(&self) -> bool {
+        self.open
+    }
+
+    /// Starts with a empty query every time
+    pub fn open(&mut self) {
+        self.open = true;
+        self.query.clear();
+        self.selected = 0;
+    }
+}
+
+/// Fuzzy searches the entries as the user types, arrows pick a match and enter runs it
+pub fn command_palette(
+    ctx: &Context,
+    state: &mut CommandPaletteState,
+    entries: Vec<PaletteEntry>,
+) -> Option<PaletteAction> {
+    let mut matches: Vec<_> = entries
+        .into_iter()
+        .filter_map(|entry| Some((fuzzy_score(&state.query, &entry.label)?, entry)))
+        .collect();
+    // Best first, ties alphabetically so the list doesn't jump around while typing
+    matches.sort_by(|(a_score, a), (b_score, b)| {
+        b_score.cmp(a_score).then_with(|| a.label.cmp(&b.label))
+    });
+    matches.truncate(SHOWN_MATCHES);
+
+    let (up, down, enter, escape) = ctx.input(|input| {
+        (
+            input.key_pressed(Key::ArrowUp),
+            input.key_pressed(Key::ArrowDown),
+            input.key_pressed(Key::Enter),
+            input.key_pressed(Key::Escape),
+        )
+    });
+    if escape {
+        state.open = false;
+        return None;
+    }
+    if down {
+        state.selected += 1;
+    }
+    if up {
+        state.selected = state.selected.saturating_sub(1);
+    }
+    state.selected = state.selected.min(matches.len().saturating_sub(1));
+
+    let mut picked = enter.then_some(state.selected);
+
+    egui::Window::new("Command Palette")
+        .title_bar(false)
+        .collapsible(false)
+        .resizable(false)
+        .anchor(Align2::CENTER_TOP, [0.0, 64.0])
+        .default_width(400.0)
+        .show(ctx, |ui| {
+            let response = ui.add(
+                TextEdit::singleline(&mut state.query)
+                    .hint_text("Search games, actions and settings")
+                    .desired_width(f32::INFINITY),
+            );
+            response.request_focus();
+            if response.changed() {
+                state.selected = 0;
+            }
+
+            ui.separator();
+
+            if matches.is_empty() {
+                ui.label("Nothing matches");
+            }
+
+            for (index, (_, entry)) in matches.iter().enumerate() {
+                ui.horizontal(|ui| {
+                    if ui
+                        .add(SelectableLabel::new(index == state.selected, &entry.label))
+                        .clicked()
+                    {
+                        picked = Some(index);
+                    }
+                    ui.label(RichText::new(entry.kind).weak());
+                });
+            }
+        });
+
+    let (_, entry) = matches.into_iter().nth(picked?)?;
+    state.open = false;
+
+    Some(entry.action)
+}
+
+/// None if the query letters don't appear in order. Letters that follow each other or start a word score higher
+fn fuzzy_score(query: &str, candidate: &str) -> Option<u32> {
+    let candidate: Vec<char> = candidate.chars().flat_map(char::to_lowercase).collect();
+    let mut score = 0;
+    let mut position = 0;
+    let mut previous_match = None;
+
+    for query_character in query
+        .chars()
+        .filter(|character| !character.is_whitespace())
+        .flat_map(char::to_lowercase)
+    {
+        let found = position
+            + candidate[position..]
+                .iter()
+                .position(|character| *character == query_character)?;
+
+        score += 1;
+        if previous_match.is_some_and(|previous| previous + 1 == found) {
+            score += 4;
+        }
+        if found == 0 || !candidate[found - 1].is_alphanumeric() {
+            score += 2;
+        }
+
+        previous_match = Some(found);
+        position = found + 1;
+    }
+
+    Some(score)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fuzzy_matches_prefer_words_and_runs() {
+        assert_eq!(fuzzy_score("xyz", "Save State"), None);
+        assert_eq!(fuzzy_score("", "Save State"), Some(0));
+
+        let initials = fuzzy_score("ss", "Save State").unwrap();
+        assert!(fuzzy_score("save", "Save State").unwrap() > initials);
+        assert!(initials > fuzzy_score("ae", "Save State").unwrap());
+        // Order matters
+        assert_eq!(fuzzy_score("ts", "Stat"), None);
+    }
+}
@@ -0,0 +1,248 @@
+use crate::{
+    component::{
+        memory::MemoryTranslationTable,
+        processor::{InstructionSet, ProcessorComponent},
+    },
+    symbols::SymbolTable,
+    task::processor::{ProcessorDebugState, ProcessorStop},
+};
+use egui::{Grid, RichText, ScrollArea, TextStyle, Ui};
+use std::sync::{Arc, Mutex};
+
+/// Instructions shown before the program pointer
+const INSTRUCTIONS_BEFORE: usize = 8;
+/// Instructions shown from the program pointer on
+const INSTRUCTIONS_AFTER: usize = 24;
+/// How far back decoding may start to find the instructions before the program pointer
+const LOOKBEHIND: usize = 64;
+
+/// A processor the debugger can disassemble, with the concrete instruction set hidden
+pub trait DisassemblerPage: Send {
+    fn show(
+        &self,
+        ui: &mut Ui,
+        memory_translation_table: &MemoryTranslationTable,
+        symbol_table: &SymbolTable,
+    );
+
+    /// Why the processor stopped, if it did since the last call. It will continue once unpaused
+    fn take_stop(&self) -> Option<ProcessorStop>;
+}
+
+pub struct Disassembler<C: ProcessorComponent> {
+    component: Arc<Mutex<C>>,
+    debug_state: Arc<Mutex<ProcessorDebugState>>,
+}
+
+impl<C: ProcessorComponent> Disassembler<C> {
+    pub fn new(component: Arc<Mutex<C>>, debug_state: Arc<Mutex<ProcessorDebugState>>) -> Self {
+        Self {
+            component,
+            debug_state,
+        }
+    }
+}
+
+/// A decoded line, the text is None where decoding failed
+struct DisassembledLine {
+    address: usize,
+    text: Option<String>,
+}
+
+impl<C: ProcessorComponent> DisassemblerPage for Disassembler<C> {
+    fn show(
+        &self,
+        ui: &mut Ui,
+        memory_translation_table: &MemoryTranslationTable,
+        symbol_table: &SymbolTable,
+    ) {
+        let (program_pointer, breakpoints) = {
+            let debug_state = self.debug_state.lock().unwrap();
+            (debug_state.program_pointer, debug_state.breakpoints.clone())
+        };
+        let lines = {
+            let component = self.component.lock().unwrap();
+            disassemble_around(&*component, program_pointer, memory_translation_table)
+        };
+
+        ui.label("Click a address to toggle a breakpoint on it");
+
+        // Only the lines scrolled to are laid out
+        let row_height = ui.text_style_height(&TextStyle::Monospace);
+        ScrollArea::vertical().show_rows(ui, row_height, lines.len(), |ui, rows| {
+            Grid::new("disassembly")
+                .striped(true)
+                .num_columns(2)
+                .show(ui, |ui| {
+                    for line in &lines[rows] {
+                        let marker = if breakpoints.contains(&line.address) {
+                            "●"
+                        } else {
+                            " "
+                        };
+                        let address_text = match symbol_table.name(line.address) {
+                            Some(name) => format!("{} {}", marker, name),
+                            None => format!("{} {:08x}", marker, line.address),
+                        };
+
+                        if ui
+                            .selectable_label(false, RichText::new(address_text).monospace())
+                            .on_hover_text(format!("0x{:x}", line.address))
+                            .clicked()
+                        {
+                            let mut debug_state = self.debug_state.lock().unwrap();
+
+                            if !debug_state.breakpoints.remove(&line.address) {
+                                debug_state.breakpoints.insert(line.address);
+                            }
+                        }
+
+                        let text = RichText::new(line.text.as_deref().unwrap_or("???")).monospace();
+                        if line.address == program_pointer {
+                            ui.label(
+                                text.strong()
+                                    .background_color(ui.visuals().selection.bg_fill),
+                            );
+                        } else {
+                            ui.label(text);
+                        }
+                        ui.end_row();
+                    }
+                });
+        });
+    }
+
+    fn take_stop(&self) -> Option<ProcessorStop> {
+        let mut debug_state = self.debug_state.lock().unwrap();
+        let stop = debug_state
+            .fault
+            .clone()
+            .map(ProcessorStop::Fault)
+            .or(debug_state.breakpoint_hit.map(ProcessorStop::Breakpoint));
+        debug_state.resume();
+
+        stop
+    }
+}
+
+/// Decodes one instruction, failures are skipped a byte at a time
+fn disassemble_line<C: ProcessorComponent>(
+    component: &C,
+    address: usize,
+    memory_translation_table: &MemoryTranslationTable,
+) -> (DisassembledLine, usize) {
+    match component.decompile(address, memory_translation_table) {
+        Ok((instruction, size)) => (
+            DisassembledLine {
+                address,
+                text: Some(instruction.to_text_representation().to_string()),
+            },
+            (size as usize).max(1),
+        ),
+        Err(_) => (
+            DisassembledLine {
+                address,
+                text: None,
+            },
+            1,
+        ),
+    }
+}
+
+/// Instructions don't say where the ones before them start, so decoding starts at the earliest point that decodes
+/// cleanly and still lands on the program pointer
+fn disassemble_around<C: ProcessorComponent>(
+    component: &C,
+    program_pointer: usize,
+    memory_translation_table: &MemoryTranslationTable,
+) -> Vec<DisassembledLine> {
+    let mut before = Vec::new();
+
+    for start in program_pointer.saturating_sub(LOOKBEHIND)..program_pointer {
+        let mut lines = Vec::new();
+        let mut address = start;
+
+        while address < program_pointer {
+            let (line, size) = disassemble_line(component, address, memory_translation_table);
+            lines.push(line);
+            address += size;
+        }
+
+        if address == program_pointer && lines.iter().all(|line| line.text.is_some()) {
+            before = lines;
+            break;
+        }
+    }
+
+    let mut lines: Vec<_> = before
+        .into_iter()
+        .rev()
+        .take(INSTRUCTIONS_BEFORE)
+        .rev()
+        .collect();
+
+    let mut address = program_pointer;
+    for _ in 0..INSTRUCTIONS_AFTER {
+        let (line, size) = disassemble_line(component, address, memory_translation_table);
+        lines.push(line);
+        address = address.wrapping_add(size);
+    }
+
+    lines
+}
+
+#[cfg(all(test, feature = "chip8"))]
+mod tests {
+    use super::*;
+    use crate::{
+        component::{
+            definitions::{
+                chip8::{
+                    processor::{Chip8Processor, Chip8ProcessorConfig},
+                    Chip8Kind,
+                },
+                misc::plain_memory::{PlainMemory, PlainMemoryConfig, PlainMemoryInitialContents},
+            },
+            FromConfig,
+        },
+        rom::RomManager,
+    };
+    use num::rational::Ratio;
+
+    #[test]
+    fn disassembles_around_program_pointer() {
+        let rom_manager = Arc::new(RomManager::default());
+        let processor = Chip8Processor::from_config(
+            rom_manager.clone(),
+            Chip8ProcessorConfig {
+                frequency: Ratio::from_integer(700),
+                kind: Chip8Kind::Chip8,
+            },
+        );
+        let mut memory_translation_table = MemoryTranslationTable::default();
+        memory_translation_table.insert(
+            0x200..0x1000,
+            Arc::new(Mutex::new(PlainMemory::from_config(
+                rom_manager,
+                PlainMemoryConfig {
+                    max_word_size: 2,
+                    assigned_range: 0x200..0x1000,
+                    // Loads into V0 all the way
+                    initial_contents: PlainMemoryInitialContents::Value { value: 0x60 },
+                    ..Default::default()
+                },
+            ))),
+        );
+
+        // Decoding from the unmapped memory before 0x200 fails, so it can't be where the listing starts
+        let lines = disassemble_around(&processor, 0x210, &memory_translation_table);
+        assert_eq!(lines.len(), INSTRUCTIONS_BEFORE + INSTRUCTIONS_AFTER);
+        assert_eq!(lines[0].address, 0x200);
+        assert_eq!(lines[INSTRUCTIONS_BEFORE].address, 0x210);
+        assert!(lines.iter().all(|line| line.text.is_some()));
+
+        let lines = disassemble_around(&processor, 0x202, &memory_translation_table);
+        assert_eq!(lines[0].address, 0x200);
+        assert_eq!(lines[1].address, 0x202);
+    }
+}
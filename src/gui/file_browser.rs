@@ -34,8 +34,8 @@ impl FileBrowserState {
         &self.path
     }
 
-    pub fn directory_contents(&self) -> impl Iterator<Item = &Path> {
-        self.directory_contents.iter().map(Deref::deref)
+    pub fn directory_contents(&self) -> &[PathBuf] {
+        &self.directory_contents
     }
 
     pub fn get_sorting_method(&self) -> FileBrowserSortingMethod {
@@ -0,0 +1,167 @@
+use super::UiOutput;
+use crate::{
+    rom::RomId,
+    runtime::screenshot::{games_with_media, list_screenshots, screenshot_directory},
+};
+use egui::{ColorImage, Image, Label, TextureHandle, TextureOptions, Ui, Vec2};
+use std::{collections::HashMap, path::PathBuf};
+
+/// The space screenshots are shown in, they keep their aspect ratio within it so every row is as tall
+const THUMBNAIL_SIZE: Vec2 = Vec2::new(192.0, 144.0);
+
+#[derive(Clone, Default)]
+pub struct GalleryState {
+    game: Option<RomId>,
+    games: Vec<RomId>,
+    screenshots: Vec<PathBuf>,
+    /// Loaded lazily as they are shown, None if the image couldn't be read
+    thumbnails: HashMap<PathBuf, Option<TextureHandle>>,
+}
+
+impl std::fmt::Debug for GalleryState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("GalleryState")
+            .field("game", &self.game)
+            .field("screenshots", &self.screenshots)
+            .finish_non_exhaustive()
+    }
+}
+
+impl GalleryState {
+    fn select_game(&mut self, game: Option<RomId>) {
+        self.game = game;
+        self.refresh();
+    }
+
+    fn refresh(&mut self) {
+        self.games = games_with_media();
+        self.screenshots = self.game.map(list_screenshots).unwrap_or_default();
+        self.thumbnails.clear();
+    }
+
+    fn thumbnail(&mut self, ui: &Ui, path: &PathBuf) -> Option<TextureHandle> {
+        self.thumbnails
+            .entry(path.clone())
+            .or_insert_with(|| {
+                let image = match image::open(path) {
+                    Ok(image) => image.into_rgba8(),
+                    Err(error) => {
+                        tracing::warn!("Could not load screenshot {}: {}", path.display(), error);
+                        return None;
+                    }
+                };
+
+                let image = ColorImage::from_rgba_unmultiplied(
+                    [image.width() as usize, image.height() as usize],
+                    image.as_raw(),
+                );
+
+                Some(ui.ctx().load_texture(
+                    path.display().to_string(),
+                    image,
+                    // Pixel art should stay sharp when scaled
+                    TextureOptions::NEAREST,
+                ))
+            })
+            .clone()
+    }
+}
+
+/// Browses the screenshots saved for each game, starting on the running one
+pub fn gallery_page(
+    ui: &mut Ui,
+    state: &mut GalleryState,
+    running_game: Option<RomId>,
+) -> Option<UiOutput> {
+    let mut output = None;
+
+    if state.game.is_none() && running_game.is_some() {
+        state.select_game(running_game);
+    }
+
+    ui.horizontal(|ui| {
+        let mut selected = state.game;
+
+        egui::ComboBox::from_label("Game")
+            .selected_text(
+                selected
+                    .map(|game| game.to_string())
+                    .unwrap_or_else(|| "None".to_string()),
+            )
+            .show_ui(ui, |ui| {
+                for game in state.games.iter().copied() {
+                    ui.selectable_value(&mut selected, Some(game), game.to_string());
+                }
+            });
+
+        if selected != state.game {
+            state.select_game(selected);
+        }
+
+        if ui.button("🔄").clicked() {
+            state.refresh();
+        }
+
+        if let Some(game) = state.game {
+            if ui.button("Open Folder").clicked() {
+                output = Some(UiOutput::OpenInFileManager {
+                    path: screenshot_directory(game),
+                });
+            }
+        }
+    });
+
+    ui.separator();
+
+    if state.screenshots.is_empty() {
+        ui.label("No screenshots, they are taken whenever a state is saved");
+        return output;
+    }
+
+    let screenshots = state.screenshots.clone();
+
+    // Every state saved adds one, so only the rows scrolled to are laid out and have their screenshots loaded
+    let spacing = ui.spacing().item_spacing;
+    let columns =
+        (((ui.available_width() + spacing.x) / (THUMBNAIL_SIZE.x + spacing.x)) as usize).max(1);
+    let row_height = THUMBNAIL_SIZE.y + spacing.y + ui.spacing().interact_size.y;
+
+    egui::ScrollArea::vertical().show_rows(
+        ui,
+        row_height,
+        screenshots.len().div_ceil(columns),
+        |ui, rows| {
+            for row in rows {
+                ui.horizontal(|ui| {
+                    for path in screenshots.iter().skip(row * columns).take(columns) {
+                        ui.vertical(|ui| {
+                            ui.set_width(THUMBNAIL_SIZE.x);
+
+                            match state.thumbnail(ui, path) {
+                                Some(texture) => {
+                                    let size = texture.size_vec2();
+                                    let scale =
+                                        (THUMBNAIL_SIZE.x / size.x).min(THUMBNAIL_SIZE.y / size.y);
+                                    ui.add_sized(
+                                        THUMBNAIL_SIZE,
+                                        Image::new((texture.id(), size * scale)),
+                                    );
+                                }
+                                None => {
+                                    ui.add_sized(THUMBNAIL_SIZE, Label::new("Unreadable"));
+                                }
+                            }
+
+                            let name = path.file_name().unwrap().to_string_lossy();
+                            if ui.button(name).clicked() {
+                                output = Some(UiOutput::OpenInFileManager { path: path.clone() });
+                            }
+                        });
+                    }
+                });
+            }
+        },
+    );
+
+    output
+}
@@ -0,0 +1,173 @@
+use crate::{component::memory::MemoryTranslationTable, symbols::SymbolTable};
+use egui::{Key, RichText, TextEdit, Ui};
+
+const BYTES_PER_ROW: usize = 16;
+const ROWS: usize = 16;
+const PAGE_SIZE: usize = BYTES_PER_ROW * ROWS;
+
+/// Where the hex viewer is looking and what is typed into it
+#[derive(Debug, Default)]
+pub struct HexViewerState {
+    /// First address shown, always the start of a row
+    address: usize,
+    goto: String,
+    /// The byte being edited and what was typed so far
+    editing: Option<(usize, String)>,
+    error: Option<String>,
+}
+
+impl HexViewerState {
+    /// Snaps to the row the address is in, keeping the whole page inside the address space
+    fn go_to(&mut self, address: usize) {
+        let address = address.min(usize::MAX - PAGE_SIZE);
+        self.address = address - address % BYTES_PER_ROW;
+    }
+}
+
+/// Shows a page of memory as the processors see it, refreshed every frame. Bytes are read with previews so looking
+/// has no side effects, edits are real writes
+pub fn hex_viewer_page(
+    ui: &mut Ui,
+    state: &mut HexViewerState,
+    memory_translation_table: &MemoryTranslationTable,
+    symbol_table: &SymbolTable,
+) {
+    ui.horizontal(|ui| {
+        let response = ui.add(
+            TextEdit::singleline(&mut state.goto)
+                .hint_text("Address or label")
+                .desired_width(160.0),
+        );
+
+        if ui.button("Go").clicked()
+            || (response.lost_focus() && ui.input(|input| input.key_pressed(Key::Enter)))
+        {
+            let goto = state.goto.trim();
+
+            match usize::from_str_radix(goto.trim_start_matches("0x"), 16)
+                .ok()
+                .or_else(|| symbol_table.find(goto))
+            {
+                Some(address) => {
+                    state.go_to(address);
+                    state.error = None;
+                }
+                None => state.error = Some(format!("{} is not a address or label", goto)),
+            }
+        }
+
+        if ui.button("Previous Page").clicked() {
+            state.go_to(state.address.saturating_sub(PAGE_SIZE));
+        }
+        if ui.button("Next Page").clicked() {
+            state.go_to(state.address.saturating_add(PAGE_SIZE));
+        }
+    });
+
+    if let Some(error) = &state.error {
+        ui.colored_label(ui.visuals().error_fg_color, error);
+    }
+
+    for row in 0..ROWS {
+        let row_address = state.address + row * BYTES_PER_ROW;
+        let bytes: Vec<_> = (row_address..row_address + BYTES_PER_ROW)
+            .map(|address| preview_byte(memory_translation_table, address))
+            .collect();
+
+        ui.horizontal(|ui| {
+            ui.monospace(format!("{:08x}", row_address));
+
+            for (address, byte) in (row_address..).zip(&bytes) {
+                if let Some((editing_address, text)) = &mut state.editing {
+                    if *editing_address == address {
+                        let response = ui.add(
+                            TextEdit::singleline(text)
+                                .char_limit(2)
+                                .desired_width(16.0)
+                                .font(egui::TextStyle::Monospace),
+                        );
+                        response.request_focus();
+
+                        if ui.input(|input| input.key_pressed(Key::Enter)) {
+                            let text = std::mem::take(text);
+                            state.editing = None;
+                            write_byte(state, memory_translation_table, address, &text);
+                        } else if ui.input(|input| input.key_pressed(Key::Escape)) {
+                            state.editing = None;
+                        }
+
+                        continue;
+                    }
+                }
+
+                let text = match byte {
+                    Some(byte) => format!("{:02x}", byte),
+                    None => "??".to_string(),
+                };
+                let mut hover_text = format!("0x{:x}", address);
+                if let Some(name) = symbol_table.name(address) {
+                    hover_text = format!("{} ({})", name, hover_text);
+                }
+
+                if ui
+                    .selectable_label(false, RichText::new(text).monospace())
+                    .on_hover_text(hover_text)
+                    .clicked()
+                {
+                    state.editing = Some((address, String::new()));
+                }
+            }
+
+            let ascii: String = bytes
+                .iter()
+                .map(|byte| match byte {
+                    Some(byte) if byte.is_ascii_graphic() || *byte == b' ' => *byte as char,
+                    _ => '.',
+                })
+                .collect();
+            ui.monospace(ascii);
+
+            let names: Vec<_> = (row_address..row_address + BYTES_PER_ROW)
+                .filter_map(|address| symbol_table.label_starting_at(address))
+                .collect();
+            if !names.is_empty() {
+                ui.weak(names.join(", "));
+            }
+        });
+    }
+}
+
+/// None for anything unmapped or that can't be looked at without side effects
+fn preview_byte(memory_translation_table: &MemoryTranslationTable, address: usize) -> Option<u8> {
+    let mut byte = 0;
+
+    memory_translation_table
+        .preview(address, std::slice::from_mut(&mut byte))
+        .ok()?;
+
+    Some(byte)
+}
+
+fn write_byte(
+    state: &mut HexViewerState,
+    memory_translation_table: &MemoryTranslationTable,
+    address: usize,
+    text: &str,
+) {
+    let Ok(byte) = u8::from_str_radix(text.trim(), 16) else {
+        state.error = Some(format!("{} is not a hex byte", text));
+        return;
+    };
+
+    match memory_translation_table.write(address, &[byte]) {
+        Ok(_) => {
+            tracing::info!(
+                "Wrote 0x{:02x} to 0x{:x} from the hex viewer",
+                byte,
+                address
+            );
+            state.error = None;
+        }
+        Err(error) => state.error = Some(format!("Writing 0x{:x} failed: {}", address, error)),
+    }
+}
@@ -0,0 +1,144 @@
+use crate::symbols::{load_symbol_file, AddressLabel};
+use egui::{Grid, ScrollArea, TextEdit, Ui};
+use std::path::Path;
+
+/// What is typed into the label editor but not added yet
+#[derive(Debug, Default)]
+pub struct LabelEditorState {
+    start: String,
+    /// Empty for a label on a single address
+    last: String,
+    name: String,
+    symbol_file: String,
+    error: Option<String>,
+}
+
+/// Lists the labels of the running game with ways to add more. Returns the new labels if they were changed
+pub fn labels_page(
+    ui: &mut Ui,
+    state: &mut LabelEditorState,
+    labels: &[AddressLabel],
+) -> Option<Vec<AddressLabel>> {
+    let mut new_labels = None;
+
+    ui.horizontal(|ui| {
+        ui.add(
+            TextEdit::singleline(&mut state.symbol_file)
+                .hint_text("Symbol file path")
+                .desired_width(240.0),
+        );
+
+        if ui.button("Import").clicked() {
+            match load_symbol_file(Path::new(state.symbol_file.trim())) {
+                Ok(imported) => {
+                    tracing::info!(
+                        "Imported {} labels from {}",
+                        imported.len(),
+                        state.symbol_file
+                    );
+                    new_labels = Some(labels.iter().cloned().chain(imported).collect());
+                    state.error = None;
+                }
+                Err(error) => state.error = Some(error.to_string()),
+            }
+        }
+    });
+
+    ui.horizontal(|ui| {
+        ui.add(
+            TextEdit::singleline(&mut state.start)
+                .hint_text("Start")
+                .desired_width(80.0),
+        );
+        ui.add(
+            TextEdit::singleline(&mut state.last)
+                .hint_text("Last Address")
+                .desired_width(80.0),
+        );
+        ui.add(
+            TextEdit::singleline(&mut state.name)
+                .hint_text("Name")
+                .desired_width(160.0),
+        );
+
+        if ui.button("Add").clicked() {
+            match parse_label(state) {
+                Ok(label) => {
+                    new_labels = Some(labels.iter().cloned().chain([label]).collect());
+                    state.start.clear();
+                    state.last.clear();
+                    state.name.clear();
+                    state.error = None;
+                }
+                Err(error) => state.error = Some(error.to_string()),
+            }
+        }
+    });
+
+    if let Some(error) = &state.error {
+        ui.colored_label(ui.visuals().error_fg_color, error);
+    }
+
+    if ui
+        .add_enabled(!labels.is_empty(), egui::Button::new("Remove All"))
+        .clicked()
+    {
+        new_labels = Some(Vec::new());
+    }
+
+    // Symbol files can have thousands of entries
+    let row_height = ui.text_style_height(&egui::TextStyle::Body);
+    ScrollArea::vertical()
+        .max_height(200.0)
+        .show_rows(ui, row_height, labels.len(), |ui, rows| {
+            Grid::new("labels").num_columns(3).show(ui, |ui| {
+                for index in rows {
+                    let label = &labels[index];
+
+                    if label.range.len() == 1 {
+                        ui.monospace(format!("{:08x}", label.range.start));
+                    } else {
+                        ui.monospace(format!(
+                            "{:08x}-{:08x}",
+                            label.range.start,
+                            label.range.end - 1
+                        ));
+                    }
+                    ui.label(&label.name);
+
+                    if ui.small_button("Remove").clicked() {
+                        let mut labels = labels.to_vec();
+                        labels.remove(index);
+                        new_labels = Some(labels);
+                    }
+                    ui.end_row();
+                }
+            });
+        });
+
+    new_labels
+}
+
+fn parse_label(state: &LabelEditorState) -> Result<AddressLabel, &'static str> {
+    let parse = |text: &str| usize::from_str_radix(text.trim().trim_start_matches("0x"), 16);
+
+    let start = parse(&state.start).map_err(|_| "The start is not a hex address")?;
+    let last = if state.last.trim().is_empty() {
+        start
+    } else {
+        parse(&state.last).map_err(|_| "The last address is not a hex address")?
+    };
+
+    if last < start {
+        return Err("The last address can't be before the start");
+    }
+
+    if state.name.trim().is_empty() {
+        return Err("The label needs a name");
+    }
+
+    Ok(AddressLabel {
+        range: start..last + 1,
+        name: state.name.trim().to_string(),
+    })
+}
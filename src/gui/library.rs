@@ -0,0 +1,310 @@
+use super::{
+    library_query::{LibraryQueries, LibraryQuery, LibraryResults},
+    snapshot_preview::SnapshotPreviews,
+    UiOutput,
+};
+use crate::{
+    config::GlobalConfig,
+    rom::{analysis::RomAnalysis, GameSystem, RomId, RomManager, RomRegion},
+};
+use egui::{vec2, Context, Grid, Image, RichText, ScrollArea, SelectableLabel, TextEdit, Ui};
+use itertools::Itertools;
+use std::{
+    collections::HashSet,
+    sync::{Arc, RwLock},
+};
+use strum::IntoEnumIterator;
+
+/// A line of the list, games are shown under the system they are for
+#[derive(Clone, Copy, Debug)]
+enum LibraryRow {
+    System {
+        system: GameSystem,
+        count: usize,
+    },
+    /// Index into the entries
+    Rom(usize),
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct LibraryState {
+    rom_manager: Arc<RomManager>,
+    /// Only dumps for this region are shown
+    region_filter: Option<RomRegion>,
+    one_game_one_rom: bool,
+    search: String,
+    imported_only: bool,
+    /// Systems whose games are hidden under their header
+    collapsed: HashSet<GameSystem>,
+    /// Building the list sorts the whole database, so it is only asked for when the filters or the database change
+    queries: LibraryQueries,
+    requested: Option<LibraryQuery>,
+    /// Kept on screen until the results of a newer query come in
+    results: Option<Arc<LibraryResults>>,
+    rows: Vec<LibraryRow>,
+    previews: SnapshotPreviews,
+}
+
+impl LibraryState {
+    pub fn set_rom_manager(&mut self, rom_manager: Arc<RomManager>) {
+        self.queries.set_rom_manager(rom_manager.clone());
+        self.rom_manager = rom_manager;
+        self.requested = None;
+    }
+
+    pub fn rom_manager(&self) -> &RomManager {
+        &self.rom_manager
+    }
+
+    /// The thumbnail of the game is stale once it is saved again
+    pub fn snapshot_saved(&mut self, game: RomId) {
+        self.previews.invalidate(game);
+    }
+
+    fn build(&mut self, context: &Context, preferred_region: Option<RomRegion>) {
+        let query = LibraryQuery {
+            region_filter: self.region_filter,
+            one_game_one_rom: self.one_game_one_rom,
+            preferred_region,
+            search: self.search.clone(),
+            imported_only: self.imported_only,
+        };
+        if self.requested.as_ref() != Some(&query) {
+            self.queries.request(context, query.clone());
+            self.requested = Some(query);
+        }
+
+        if let Some(results) = self.queries.poll() {
+            self.results = Some(results);
+            self.build_rows();
+        }
+    }
+
+    /// Empty until the first query is done. Shared so the state can change while going through them
+    fn results(&self) -> Arc<LibraryResults> {
+        self.results.clone().unwrap_or_default()
+    }
+
+    /// If the list shown isn't for the filters set right now
+    fn searching(&self) -> bool {
+        self.results.as_ref().map(|results| &results.query) != self.requested.as_ref()
+    }
+
+    fn build_rows(&mut self) {
+        self.rows.clear();
+
+        let results = self.results();
+
+        for (system, group) in &results
+            .entries
+            .iter()
+            .enumerate()
+            .chunk_by(|(_, rom)| rom.system)
+        {
+            let indices: Vec<_> = group.map(|(index, _)| index).collect();
+
+            self.rows.push(LibraryRow::System {
+                system,
+                count: indices.len(),
+            });
+            if !self.collapsed.contains(&system) {
+                self.rows.extend(indices.into_iter().map(LibraryRow::Rom));
+            }
+        }
+    }
+}
+
+/// Every ROM the database knows about by system, with regional duplicates optionally collapsed to the preferred one.
+/// Clicking a imported one starts it
+pub fn library_page(
+    ui: &mut Ui,
+    state: &mut LibraryState,
+    global_config: &RwLock<GlobalConfig>,
+) -> Option<UiOutput> {
+    let mut output = None;
+
+    ui.horizontal(|ui| {
+        ui.add(
+            TextEdit::singleline(&mut state.search)
+                .hint_text("Search")
+                .desired_width(200.0),
+        );
+
+        egui::ComboBox::from_label("Region")
+            .selected_text(region_text(state.region_filter, "All"))
+            .show_ui(ui, |ui| {
+                ui.selectable_value(&mut state.region_filter, None, "All");
+                for region in RomRegion::iter() {
+                    ui.selectable_value(&mut state.region_filter, Some(region), region.to_string());
+                }
+            });
+
+        ui.checkbox(&mut state.imported_only, "Imported Only");
+
+        ui.checkbox(&mut state.one_game_one_rom, "One Game One ROM")
+            .on_hover_text("Shows a single dump of every game, picked by the preferred region");
+
+        let mut global_config = global_config.write().unwrap();
+        egui::ComboBox::from_label("Preferred Region")
+            .selected_text(region_text(global_config.preferred_region, "None"))
+            .show_ui(ui, |ui| {
+                ui.selectable_value(&mut global_config.preferred_region, None, "None");
+                for region in RomRegion::iter() {
+                    ui.selectable_value(
+                        &mut global_config.preferred_region,
+                        Some(region),
+                        region.to_string(),
+                    );
+                }
+            });
+    });
+
+    let preferred_region = global_config.read().unwrap().preferred_region;
+    state.build(ui.ctx(), preferred_region);
+    let results = state.results();
+
+    ui.horizontal(|ui| {
+        ui.label(format!(
+            "{} ROMs, {} imported",
+            results.entries.len(),
+            results.imported
+        ));
+
+        if state.searching() {
+            ui.spinner();
+        }
+    });
+    ui.separator();
+
+    let mut toggled = None;
+
+    // Databases have thousands of entries
+    let row_height = ui.spacing().interact_size.y;
+    ScrollArea::vertical().show_rows(ui, row_height, state.rows.len(), |ui, rows| {
+        Grid::new("library").num_columns(6).show(ui, |ui| {
+            for row in &state.rows[rows] {
+                let rom = match *row {
+                    LibraryRow::System { system, count } => {
+                        let arrow = if state.collapsed.contains(&system) {
+                            "⏵"
+                        } else {
+                            "⏷"
+                        };
+
+                        if ui
+                            .selectable_label(
+                                false,
+                                RichText::new(format!("{} {} ({})", arrow, system, count)).strong(),
+                            )
+                            .clicked()
+                        {
+                            toggled = Some(system);
+                        }
+                        ui.end_row();
+                        continue;
+                    }
+                    LibraryRow::Rom(index) => &results.entries[index],
+                };
+
+                let path = state.rom_manager.rom_paths.get(&rom.hash);
+                let name = rom.name.clone().unwrap_or_else(|| rom.hash.to_string());
+
+                let name_clicked = ui
+                    .add_enabled(path.is_some(), SelectableLabel::new(false, name))
+                    .on_disabled_hover_text("Not imported")
+                    .clicked();
+                ui.label(rom.system.to_string());
+                ui.label(region_text(rom.region, "Unknown"));
+                analysis_label(ui, rom.analysis.as_ref());
+
+                if ui
+                    .add_enabled(path.is_some(), egui::Button::new("Play"))
+                    .on_disabled_hover_text("Not imported")
+                    .clicked()
+                    || name_clicked
+                {
+                    output = Some(UiOutput::OpenGame {
+                        path: path.unwrap().clone(),
+                    });
+                }
+
+                // Only imported games can have been played, and only rows on screen are looked up
+                match path.and_then(|_| state.previews.get(ui.ctx(), rom.hash)) {
+                    Some(preview) => {
+                        let description = preview.describe();
+
+                        match &preview.thumbnail {
+                            Some(thumbnail) => {
+                                let size = thumbnail.size_vec2();
+                                ui.add(Image::new(thumbnail).fit_to_exact_size(vec2(
+                                    row_height * size.x / size.y,
+                                    row_height,
+                                )))
+                                .on_hover_ui(|ui| {
+                                    ui.image(thumbnail);
+                                    ui.label(description);
+                                });
+                            }
+                            None => {
+                                ui.label("💾").on_hover_text(description);
+                            }
+                        }
+                    }
+                    None => {
+                        ui.label("");
+                    }
+                }
+                ui.end_row();
+            }
+        });
+    });
+
+    if let Some(system) = toggled {
+        if !state.collapsed.remove(&system) {
+            state.collapsed.insert(system);
+        }
+        state.build_rows();
+    }
+
+    output
+}
+
+/// Problems stand out so broken files are noticed before booting them, the rest of the analysis is on hover
+fn analysis_label(ui: &mut Ui, analysis: Option<&RomAnalysis>) {
+    let Some(analysis) = analysis else {
+        ui.weak("Not analyzed");
+        return;
+    };
+
+    let mut details: Vec<_> = analysis
+        .vectors
+        .iter()
+        .map(|vector| format!("{} vector: {:#06x}", vector.kind, vector.address))
+        .collect();
+    if let Some(bank_count) = analysis.bank_count {
+        details.push(format!("Banks: {}", bank_count));
+    }
+
+    if analysis.problems.is_empty() {
+        ui.weak("OK").on_hover_text(details.join("\n"));
+    } else {
+        let problems: Vec<_> = analysis
+            .problems
+            .iter()
+            .map(ToString::to_string)
+            .chain(details)
+            .collect();
+
+        ui.colored_label(
+            ui.visuals().warn_fg_color,
+            format!("⚠ {} problems", analysis.problems.len()),
+        )
+        .on_hover_text(problems.join("\n"));
+    }
+}
+
+fn region_text(region: Option<RomRegion>, none: &str) -> String {
+    region
+        .map(|region| region.to_string())
+        .unwrap_or_else(|| none.to_string())
+}
@@ -0,0 +1,244 @@
+use crate::rom::{
+    library::{matches_search, normalize_title, one_game_one_rom},
+    RomInfo, RomManager, RomRegion,
+};
+use egui::Context;
+use std::{
+    collections::VecDeque,
+    sync::{
+        mpsc::{channel, Receiver, Sender},
+        Arc,
+    },
+};
+
+/// How many result lists the worker keeps, enough to type a search and delete it again without redoing any
+const CACHED_RESULTS: usize = 16;
+
+/// What the library list is filtered by
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct LibraryQuery {
+    /// Only dumps for this region are shown
+    pub region_filter: Option<RomRegion>,
+    pub one_game_one_rom: bool,
+    pub preferred_region: Option<RomRegion>,
+    pub search: String,
+    pub imported_only: bool,
+}
+
+/// The ROMs a query matched
+#[derive(Debug, Default)]
+pub struct LibraryResults {
+    pub query: LibraryQuery,
+    /// By system, then by title
+    pub entries: Vec<RomInfo>,
+    /// How many of the entries are imported
+    pub imported: usize,
+}
+
+enum QueryJob {
+    Database(Arc<RomManager>),
+    Run(LibraryQuery),
+}
+
+/// Filters and sorts the database on a thread of its own, so typing in the search box never waits on it
+///
+/// The thread is only started once something is asked for, and stops when this is dropped
+#[derive(Default)]
+pub struct LibraryQueries {
+    worker: Option<(Sender<QueryJob>, Receiver<Arc<LibraryResults>>)>,
+    rom_manager: Arc<RomManager>,
+}
+
+// A clone starts with nothing cached and its own thread
+impl Clone for LibraryQueries {
+    fn clone(&self) -> Self {
+        Self {
+            worker: None,
+            rom_manager: self.rom_manager.clone(),
+        }
+    }
+}
+
+impl std::fmt::Debug for LibraryQueries {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LibraryQueries")
+            .field("running", &self.worker.is_some())
+            .finish_non_exhaustive()
+    }
+}
+
+impl LibraryQueries {
+    /// Cached results are for the old database, so they are thrown away
+    pub fn set_rom_manager(&mut self, rom_manager: Arc<RomManager>) {
+        self.rom_manager = rom_manager;
+
+        if let Some((jobs, _)) = &self.worker {
+            // The worker only goes away with us
+            jobs.send(QueryJob::Database(self.rom_manager.clone()))
+                .unwrap();
+        }
+    }
+
+    /// The results come back through [Self::poll], queries sent faster than they can be run are skipped
+    pub fn request(&mut self, context: &Context, query: LibraryQuery) {
+        let rom_manager = &self.rom_manager;
+        let (jobs, _) = self
+            .worker
+            .get_or_insert_with(|| spawn_worker(context.clone(), rom_manager.clone()));
+
+        jobs.send(QueryJob::Run(query)).unwrap();
+    }
+
+    /// The newest results that came in since the last call
+    pub fn poll(&self) -> Option<Arc<LibraryResults>> {
+        let (_, results) = self.worker.as_ref()?;
+
+        results.try_iter().last()
+    }
+}
+
+fn spawn_worker(
+    context: Context,
+    rom_manager: Arc<RomManager>,
+) -> (Sender<QueryJob>, Receiver<Arc<LibraryResults>>) {
+    let (job_sender, job_receiver) = channel();
+    let (result_sender, result_receiver) = channel();
+
+    std::thread::Builder::new()
+        .name("library_queries".to_string())
+        .spawn(move || {
+            let mut rom_manager = rom_manager;
+            let mut cache: VecDeque<Arc<LibraryResults>> = VecDeque::new();
+
+            while let Ok(job) = job_receiver.recv() {
+                // Only the newest query matters, the ones typed before it are stale already
+                let mut query = None;
+                for job in std::iter::once(job).chain(job_receiver.try_iter()) {
+                    match job {
+                        QueryJob::Database(new_rom_manager) => {
+                            rom_manager = new_rom_manager;
+                            cache.clear();
+                        }
+                        QueryJob::Run(new_query) => query = Some(new_query),
+                    }
+                }
+                let Some(query) = query else {
+                    continue;
+                };
+
+                let results = match cache.iter().position(|results| results.query == query) {
+                    Some(index) => cache.remove(index).unwrap(),
+                    None => Arc::new(run_query(&rom_manager, query)),
+                };
+                cache.push_front(results.clone());
+                cache.truncate(CACHED_RESULTS);
+
+                if result_sender.send(results).is_err() {
+                    break;
+                }
+                context.request_repaint();
+            }
+        })
+        .unwrap();
+
+    (job_sender, result_receiver)
+}
+
+fn run_query(rom_manager: &RomManager, query: LibraryQuery) -> LibraryResults {
+    let roms = rom_manager.rom_information.values().filter(|rom| {
+        (query.region_filter.is_none() || rom.region == query.region_filter)
+            && (!query.imported_only || rom_manager.rom_paths.contains_key(&rom.hash))
+            && rom
+                .name
+                .as_deref()
+                .map_or(query.search.trim().is_empty(), |name| {
+                    matches_search(name, &query.search)
+                })
+    });
+
+    let mut entries: Vec<RomInfo> = if query.one_game_one_rom {
+        one_game_one_rom(roms, query.preferred_region)
+            .into_iter()
+            .cloned()
+            .collect()
+    } else {
+        roms.cloned().collect()
+    };
+    entries.sort_by_cached_key(|rom| {
+        (
+            rom.system,
+            rom.name.as_deref().map(normalize_title),
+            rom.name.clone(),
+        )
+    });
+
+    LibraryResults {
+        imported: entries
+            .iter()
+            .filter(|rom| rom_manager.rom_paths.contains_key(&rom.hash))
+            .count(),
+        entries,
+        query,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rom::{GameSystem, NintendoSystem, RomId};
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn queries_run_off_thread_and_count_imports() {
+        let mut rom_manager = RomManager::default();
+        for (index, name) in ["Tetris (World)", "Tetris DX (World)", "Metroid II (USA)"]
+            .into_iter()
+            .enumerate()
+        {
+            let hash = RomId::new([index as u8; 20]);
+            rom_manager.rom_information.insert(
+                hash,
+                RomInfo {
+                    name: Some(name.to_string()),
+                    hash,
+                    system: GameSystem::Nintendo(NintendoSystem::GameBoy),
+                    region: RomRegion::from_nointro_name(name),
+                    analysis: None,
+                },
+            );
+        }
+        rom_manager
+            .rom_paths
+            .insert(RomId::new([1; 20]), "tetris_dx.gbc".into());
+
+        let mut queries = LibraryQueries::default();
+        queries.set_rom_manager(Arc::new(rom_manager));
+        queries.request(
+            &Context::default(),
+            LibraryQuery {
+                region_filter: None,
+                one_game_one_rom: false,
+                preferred_region: None,
+                search: "tetris".to_string(),
+                imported_only: false,
+            },
+        );
+
+        let started = Instant::now();
+        let results = loop {
+            if let Some(results) = queries.poll() {
+                break results;
+            }
+            assert!(started.elapsed() < Duration::from_secs(10));
+            std::thread::sleep(Duration::from_millis(1));
+        };
+
+        let names: Vec<_> = results
+            .entries
+            .iter()
+            .map(|rom| rom.name.as_deref().unwrap())
+            .collect();
+        assert_eq!(names, ["Tetris (World)", "Tetris DX (World)"]);
+        assert_eq!(results.imported, 1);
+    }
+}
@@ -0,0 +1,94 @@
+use crate::component::definitions::misc::memory_card::{MemoryCardLibrary, MEMORY_CARD_FORMATS};
+use egui::{ComboBox, Grid, TextEdit, Ui};
+
+#[derive(Debug, Clone, Default)]
+pub struct MemoryCardsState {
+    library: MemoryCardLibrary,
+    /// Index into [MEMORY_CARD_FORMATS]
+    format: usize,
+    new_name: String,
+    /// The image being copied and the name typed for the copy
+    copying: Option<(String, String)>,
+    error: Option<String>,
+}
+
+/// Creates, copies and deletes card images. The one named after a slot is what is inserted into it, so copying an
+/// image over it is how cards are swapped
+pub fn memory_cards_page(ui: &mut Ui, state: &mut MemoryCardsState) {
+    let format = MEMORY_CARD_FORMATS[state.format];
+
+    ComboBox::from_label("Format")
+        .selected_text(format.name)
+        .show_ui(ui, |ui| {
+            for (index, format) in MEMORY_CARD_FORMATS.iter().enumerate() {
+                ui.selectable_value(&mut state.format, index, format.name);
+            }
+        });
+
+    ui.horizontal(|ui| {
+        ui.add(TextEdit::singleline(&mut state.new_name).hint_text("Name"));
+
+        if ui.button("Create").clicked() && !state.new_name.trim().is_empty() {
+            state.error = state
+                .library
+                .create(format, state.new_name.trim())
+                .err()
+                .map(|error| format!("Could not create {}: {}", state.new_name.trim(), error));
+            state.new_name.clear();
+        }
+    });
+
+    if let Some(error) = &state.error {
+        ui.colored_label(ui.visuals().error_fg_color, error);
+    }
+
+    ui.separator();
+
+    let images = state.library.images(format);
+    if images.is_empty() {
+        ui.label(format!(
+            "No {} images yet, one is made for each slot a card is first plugged into",
+            format.name
+        ));
+        return;
+    }
+
+    Grid::new("memory_card_images")
+        .num_columns(2)
+        .show(ui, |ui| {
+            for image in images {
+                ui.label(&image);
+
+                ui.horizontal(|ui| match &mut state.copying {
+                    Some((from, to)) if *from == image => {
+                        ui.add(TextEdit::singleline(to).hint_text("Copy to"));
+
+                        if ui.button("Copy").clicked() && !to.trim().is_empty() {
+                            state.error = state
+                                .library
+                                .copy(format, from, to.trim())
+                                .err()
+                                .map(|error| format!("Could not copy {}: {}", from, error));
+                            state.copying = None;
+                        } else if ui.button("Cancel").clicked() {
+                            state.copying = None;
+                        }
+                    }
+                    _ => {
+                        if ui.button("Copy").clicked() {
+                            state.copying = Some((image.clone(), String::new()));
+                        }
+
+                        if ui.button("Delete").clicked() {
+                            state.error = state
+                                .library
+                                .delete(format, &image)
+                                .err()
+                                .map(|error| format!("Could not delete {}: {}", image, error));
+                        }
+                    }
+                });
+                ui.end_row();
+            }
+        });
+}
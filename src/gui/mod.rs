@@ -1,15 +1,106 @@
-use crate::config::GlobalConfig;
-use egui::{CentralPanel, Context, ScrollArea, SidePanel};
+use crate::{
+    cheats::Cheat,
+    component::audio::AudioComponent,
+    config::{
+        AspectMode, Buffering, ConfigApplyScope, DisplayLayout, FrameSkip, GameConfig,
+        GlobalConfig, RenderingBackendKind, ScaleFilter, SurfaceFormatPreference,
+    },
+    event_log::EVENT_LOG,
+    machine::{
+        executor::ScheduleReport, peripheral::PeripheralPort, watchdog::StallReport,
+        MachineGuiPage, QueryableComponents,
+    },
+    netplay::MAX_INPUT_DELAY,
+    rom::{import::ImportPolicy, GameSystem, RomId, RomManager},
+    runtime::color_filter::{ColorBlindness, ColorFilter, ColorFilterMode},
+};
+use cheats::CheatsState;
+use command_palette::{CommandPaletteState, PaletteAction, PaletteEntry};
+use egui::{
+    CentralPanel, Color32, Context, Key, KeyboardShortcut, Modifiers, ScrollArea, SidePanel,
+    Spinner, Stroke, Style, Visuals,
+};
 use file_browser::{FileBrowserSortingMethod, FileBrowserState};
+use gallery::GalleryState;
+use library::LibraryState;
+use memory_cards::MemoryCardsState;
+use ringbuffer::RingBuffer;
+use scripts::ScriptsState;
 use std::{
+    collections::VecDeque,
     path::PathBuf,
-    sync::{Arc, RwLock},
+    sync::{Arc, Mutex, RwLock},
+    time::{Duration, Instant},
 };
+use strum::IntoEnumIterator;
+use tracing::Level;
 
+mod cheats;
+mod command_palette;
+pub mod disassembler;
 mod file_browser;
+mod gallery;
+pub mod hex_viewer;
+pub mod labels;
+mod library;
+mod library_query;
+mod memory_cards;
+mod peripherals;
+mod scheduler;
+mod scripts;
+mod snapshot_preview;
+
+/// How long a on screen notification stays up
+const NOTIFICATION_DURATION: Duration = Duration::from_secs(3);
 
+const COLOR_FILTER_UNSUPPORTED: &str = "The rendering backend in use can't apply the color filter";
+
+#[derive(Clone, Debug)]
 pub enum UiOutput {
-    OpenGame { path: PathBuf },
+    OpenGame {
+        path: PathBuf,
+    },
+    /// Resets every component of the running machine
+    ResetMachine,
+    /// Tears down the running machine and goes back to the menu
+    StopMachine,
+    ImportRoms {
+        path: PathBuf,
+        policy: ImportPolicy,
+    },
+    ToggleAudioCapture,
+    /// Extension decides the format
+    DumpFramebuffer {
+        extension: &'static str,
+    },
+    LoadFramebuffer {
+        path: PathBuf,
+    },
+    /// Machine pages in their own window
+    OpenDebuggerWindow,
+    OpenDisplayWindow {
+        index: usize,
+    },
+    SaveSnapshot {
+        slot: u8,
+    },
+    LoadSnapshot {
+        slot: u8,
+    },
+    /// Index into the ports of the machine and the kind of device, None to leave it empty
+    PlugPeripheral {
+        port: usize,
+        kind: Option<usize>,
+    },
+    /// Replaces the cheats of the running game
+    SetCheats {
+        cheats: Vec<Cheat>,
+    },
+    /// Shows a file or directory with whatever the host uses to browse files
+    OpenInFileManager {
+        path: PathBuf,
+    },
+    Quit,
 }
 
 #[derive(PartialEq, Eq, Clone, Copy, Debug, Default)]
@@ -19,30 +110,221 @@ pub enum MenuItem {
     FileBrowser,
     Options,
     Database,
+    Audio,
+    EventLog,
+    Debug,
+    Scheduler,
+    Gallery,
+    MemoryCards,
+    Scripts,
+    Cheats,
+    Peripherals,
+    /// Index into the pages the running machine provided
+    MachinePage(usize),
+}
+
+/// Parts of the running machine the menu is allowed to poke at
+#[derive(Clone, Copy)]
+pub struct MenuMachineContext<'a> {
+    pub audio_components: &'a [Arc<Mutex<dyn AudioComponent>>],
+    pub audio_capturing: bool,
+    pub display_component_count: usize,
+    pub queryable_components: &'a QueryableComponents,
+    pub gui_pages: &'a [(&'static str, MachineGuiPage)],
+    pub peripheral_ports: &'a [PeripheralPort],
+    pub schedule_report: &'a ScheduleReport,
+    /// What the per game settings are stored under
+    pub game: RomId,
+    pub system: GameSystem,
 }
 
 #[derive(Clone, Debug)]
 pub struct GuiRuntime {
     pub active: bool,
+    /// Shows the per game quick settings instead of the full menu
+    pub quick_menu_active: bool,
     open_menu_item: MenuItem,
     file_browser_state: FileBrowserState,
     global_config: Arc<RwLock<GlobalConfig>>,
+    /// The config as it was on startup, for settings that can't be applied live
+    startup_config: GlobalConfig,
+    /// Least severe level shown in the event log
+    event_log_level: Level,
+    event_log_component: Option<String>,
+    import_policy: ImportPolicy,
+    framebuffer_import_path: String,
+    snapshot_slot: u8,
+    gallery_state: GalleryState,
+    memory_cards_state: MemoryCardsState,
+    scripts_state: ScriptsState,
+    cheats_state: CheatsState,
+    library_state: LibraryState,
+    command_palette_state: CommandPaletteState,
+    /// Names of the gamepads the host has, as told by the runtime
+    connected_gamepads: Vec<String>,
+    /// On screen notifications and when they were posted
+    notifications: VecDeque<(Instant, String)>,
+    /// The machine froze for a while, the user gets asked what to do about it
+    stall_report: Option<StallReport>,
+    /// The rendering backend in use draws machine displays through the color filter
+    color_filter_supported: bool,
+    /// Why the selected rendering backend is not the one in use
+    rendering_fallback: Option<String>,
 }
 
 impl GuiRuntime {
     pub fn new(global_config: Arc<RwLock<GlobalConfig>>) -> Self {
+        let import_policy = global_config.read().unwrap().import_policy;
+        let startup_config = global_config.read().unwrap().clone();
+
         Self {
             active: false,
+            quick_menu_active: false,
             open_menu_item: MenuItem::default(),
             file_browser_state: FileBrowserState::new(),
             global_config,
+            startup_config,
+            event_log_level: Level::WARN,
+            event_log_component: None,
+            import_policy,
+            framebuffer_import_path: String::new(),
+            snapshot_slot: 0,
+            gallery_state: GalleryState::default(),
+            memory_cards_state: MemoryCardsState::default(),
+            scripts_state: ScriptsState::default(),
+            cheats_state: CheatsState::default(),
+            library_state: LibraryState::default(),
+            command_palette_state: CommandPaletteState::default(),
+            connected_gamepads: Vec::new(),
+            notifications: VecDeque::new(),
+            stall_report: None,
+            color_filter_supported: true,
+            rendering_fallback: None,
         }
     }
 
+    /// Shows a message on screen for a few seconds
+    ///
+    /// TODO: These only show up while the menu is open, as the machine view can't draw egui on top of itself yet
+    pub fn notify(&mut self, message: impl Into<String>) {
+        self.notifications
+            .push_back((Instant::now(), message.into()));
+    }
+
+    /// Explains that the selected rendering backend could not start, once as a notification and for good in the options
+    pub fn report_rendering_fallback(&mut self, message: String) {
+        self.notify(message.clone());
+        self.rendering_fallback = Some(message);
+    }
+
+    /// Opens the menu with a dialog offering ways out of a machine that froze
+    pub fn report_stall(&mut self, report: StallReport) {
+        self.stall_report = Some(report);
+        self.active = true;
+        self.quick_menu_active = false;
+    }
+
+    /// The library lists what this knows about, it needs to be told again whenever the runtime imports more
+    pub fn set_rom_manager(&mut self, rom_manager: Arc<RomManager>) {
+        self.library_state.set_rom_manager(rom_manager);
+    }
+
+    pub fn snapshot_saved(&mut self, game: RomId) {
+        self.library_state.snapshot_saved(game);
+    }
+
+    /// Brings up the menu with the palette over it
+    pub fn open_command_palette(&mut self) {
+        self.active = true;
+        self.quick_menu_active = false;
+        self.command_palette_state.open();
+    }
+
+    pub fn set_connected_gamepads(&mut self, connected_gamepads: Vec<String>) {
+        self.connected_gamepads = connected_gamepads;
+    }
+
+    pub fn set_color_filter_supported(&mut self, color_filter_supported: bool) {
+        self.color_filter_supported = color_filter_supported;
+    }
+
     /// TODO: barely does anything
-    pub fn run_menu(&mut self, ctx: &Context) -> Option<UiOutput> {
+    /// Shown instead of the menu while a machine is built in the background
+    pub fn run_loading_screen(&mut self, ctx: &Context, game: &str, started: Instant) {
+        self.apply_accessibility_style(ctx);
+        self.show_notifications(ctx);
+
+        CentralPanel::default().show(ctx, |ui| {
+            ui.vertical_centered(|ui| {
+                ui.add_space(ui.available_height() / 3.0);
+                ui.heading(format!("Loading {}", game));
+                ui.add(Spinner::new().size(32.0));
+                ui.label(format!("{}s", started.elapsed().as_secs()));
+            });
+        });
+    }
+
+    pub fn run_menu(
+        &mut self,
+        ctx: &Context,
+        machine: Option<MenuMachineContext>,
+    ) -> Option<UiOutput> {
         let mut output = None;
 
+        self.apply_accessibility_style(ctx);
+        self.show_notifications(ctx);
+
+        if ctx.input_mut(|input| {
+            input.consume_shortcut(&KeyboardShortcut::new(Modifiers::COMMAND, Key::P))
+        }) {
+            self.open_command_palette();
+        }
+
+        if let Some(report) = self.stall_report.clone() {
+            egui::Window::new("Machine Stalled")
+                .collapsible(false)
+                .resizable(false)
+                .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+                .show(ctx, |ui| {
+                    ui.label(format!(
+                        "{} stopped responding for {:.1} seconds. It is running again, but may have been deadlocked \
+                         and the machine may not be in a good state. Details are in the event log",
+                        report.task,
+                        report.duration.as_secs_f32()
+                    ));
+
+                    ui.horizontal(|ui| {
+                        if ui.button("Keep Running").clicked() {
+                            self.stall_report = None;
+                            self.active = false;
+                        }
+
+                        if machine.is_some() && ui.button("Load Snapshot").clicked() {
+                            self.stall_report = None;
+                            self.active = false;
+                            output = Some(UiOutput::LoadSnapshot {
+                                slot: self.snapshot_slot,
+                            });
+                        }
+
+                        if ui.button("Quit").clicked() {
+                            output = Some(UiOutput::Quit);
+                        }
+                    });
+                });
+        }
+
+        if self.quick_menu_active {
+            match machine {
+                Some(machine) => {
+                    self.run_quick_menu(ctx, machine);
+                    return None;
+                }
+                // Nothing to configure without a game
+                None => self.quick_menu_active = false,
+            }
+        }
+
         SidePanel::left("options_panel")
             .resizable(true)
             .show(ctx, |ui| {
@@ -63,6 +345,54 @@ impl GuiRuntime {
                         if ui.button("Database").clicked() {
                             self.open_menu_item = MenuItem::Database;
                         }
+
+                        if ui.button("Audio").clicked() {
+                            self.open_menu_item = MenuItem::Audio;
+                        }
+
+                        if ui.button("Event Log").clicked() {
+                            self.open_menu_item = MenuItem::EventLog;
+                        }
+
+                        if ui.button("Debug").clicked() {
+                            self.open_menu_item = MenuItem::Debug;
+                        }
+
+                        if ui.button("Scheduler").clicked() {
+                            self.open_menu_item = MenuItem::Scheduler;
+                        }
+
+                        if ui.button("Gallery").clicked() {
+                            self.open_menu_item = MenuItem::Gallery;
+                        }
+
+                        if ui.button("Memory Cards").clicked() {
+                            self.open_menu_item = MenuItem::MemoryCards;
+                        }
+
+                        if ui.button("Scripts").clicked() {
+                            self.open_menu_item = MenuItem::Scripts;
+                        }
+
+                        if let Some(machine) = machine {
+                            ui.separator();
+
+                            if !machine.peripheral_ports.is_empty()
+                                && ui.button("Peripherals").clicked()
+                            {
+                                self.open_menu_item = MenuItem::Peripherals;
+                            }
+
+                            if ui.button("Cheats").clicked() {
+                                self.open_menu_item = MenuItem::Cheats;
+                            }
+
+                            for (index, (name, _)) in machine.gui_pages.iter().enumerate() {
+                                if ui.button(*name).clicked() {
+                                    self.open_menu_item = MenuItem::MachinePage(index);
+                                }
+                            }
+                        }
                     })
                 })
             });
@@ -71,7 +401,43 @@ impl GuiRuntime {
             ui.with_layout(
                 egui::Layout::top_down_justified(egui::Align::LEFT),
                 |ui| match self.open_menu_item {
-                    MenuItem::Main => if ui.button("Resume").clicked() {},
+                    MenuItem::Main => {
+                        if machine.is_some() {
+                            if ui.button("Resume").clicked() {
+                                self.active = false;
+                            }
+
+                            if ui.button("Reset").clicked() {
+                                self.active = false;
+                                output = Some(UiOutput::ResetMachine);
+                            }
+
+                            if ui.button("Quit to Menu").clicked() {
+                                output = Some(UiOutput::StopMachine);
+                            }
+
+                            ui.separator();
+                            ui.heading("Save States");
+
+                            ui.add(egui::Slider::new(&mut self.snapshot_slot, 0..=9).text("Slot"));
+
+                            ui.horizontal(|ui| {
+                                if ui.button("Save State").clicked() {
+                                    output = Some(UiOutput::SaveSnapshot {
+                                        slot: self.snapshot_slot,
+                                    });
+                                }
+
+                                if ui.button("Load State").clicked() {
+                                    output = Some(UiOutput::LoadSnapshot {
+                                        slot: self.snapshot_slot,
+                                    });
+                                }
+                            });
+                        } else {
+                            ui.label("Nothing is running, pick a game from the File Browser or the Database");
+                        }
+                    }
                     MenuItem::FileBrowser => {
                         let mut new_dir = None;
 
@@ -113,10 +479,34 @@ impl GuiRuntime {
                                     );
                                 });
                             self.file_browser_state.set_sorting_method(selected_sorting);
+
+                            ui.separator();
+
+                            egui::ComboBox::from_label("Import Policy")
+                                .selected_text(self.import_policy.to_string())
+                                .show_ui(ui, |ui| {
+                                    for policy in ImportPolicy::iter() {
+                                        ui.selectable_value(
+                                            &mut self.import_policy,
+                                            policy,
+                                            policy.to_string(),
+                                        );
+                                    }
+                                });
+
+                            if ui.button("Import Known ROMs").clicked() {
+                                output = Some(UiOutput::ImportRoms {
+                                    path: self.file_browser_state.directory().to_path_buf(),
+                                    policy: self.import_policy,
+                                });
+                            }
                         });
 
-                        egui::ScrollArea::vertical().show(ui, |ui| {
-                            for file_entry in self.file_browser_state.directory_contents() {
+                        // Only the entries scrolled to are laid out, ROM folders can hold thousands of files
+                        let entries = self.file_browser_state.directory_contents();
+                        let row_height = ui.spacing().interact_size.y;
+                        ScrollArea::vertical().show_rows(ui, row_height, entries.len(), |ui, rows| {
+                            for file_entry in &entries[rows] {
                                 let file_name = file_entry.file_name().unwrap().to_str().unwrap();
 
                                 if ui.button(file_name).clicked() {
@@ -147,18 +537,1005 @@ impl GuiRuntime {
                             }
                         });
 
+                        let restart_required = global_config
+                            .changed_scopes(&self.startup_config)
+                            .contains(&ConfigApplyScope::Restart);
+
+                        ui.horizontal(|ui| {
+                            egui::ComboBox::from_label("Rendering Backend")
+                                .selected_text(global_config.rendering_backend.to_string())
+                                .show_ui(ui, |ui| {
+                                    for rendering_backend in RenderingBackendKind::iter() {
+                                        ui.selectable_value(
+                                            &mut global_config.rendering_backend,
+                                            rendering_backend,
+                                            rendering_backend.to_string(),
+                                        );
+                                    }
+                                });
+
+                            if restart_required {
+                                ui.colored_label(ui.visuals().warn_fg_color, "Restart Required");
+                            }
+                        });
+
+                        if let Some(rendering_fallback) = &self.rendering_fallback {
+                            ui.colored_label(ui.visuals().warn_fg_color, rendering_fallback);
+                        }
+
+                        ui.checkbox(&mut global_config.vsync, "VSync");
+
+                        egui::ComboBox::from_label("Buffering")
+                            .selected_text(global_config.buffering.to_string())
+                            .show_ui(ui, |ui| {
+                                for buffering in Buffering::iter() {
+                                    ui.selectable_value(
+                                        &mut global_config.buffering,
+                                        buffering,
+                                        buffering.to_string(),
+                                    );
+                                }
+                            });
+
+                        egui::ComboBox::from_label("Output Format")
+                            .selected_text(global_config.surface_format.to_string())
+                            .show_ui(ui, |ui| {
+                                for surface_format in SurfaceFormatPreference::iter() {
+                                    ui.selectable_value(
+                                        &mut global_config.surface_format,
+                                        surface_format,
+                                        surface_format.to_string(),
+                                    );
+                                }
+                            });
+
+                        egui::ComboBox::from_label("Aspect")
+                            .selected_text(global_config.aspect_mode.to_string())
+                            .show_ui(ui, |ui| {
+                                for aspect_mode in AspectMode::iter() {
+                                    ui.selectable_value(
+                                        &mut global_config.aspect_mode,
+                                        aspect_mode,
+                                        aspect_mode.to_string(),
+                                    );
+                                }
+                            });
+
+                        egui::ComboBox::from_label("Scaling Filter")
+                            .selected_text(global_config.scale_filter.to_string())
+                            .show_ui(ui, |ui| {
+                                for scale_filter in ScaleFilter::iter() {
+                                    ui.selectable_value(
+                                        &mut global_config.scale_filter,
+                                        scale_filter,
+                                        scale_filter.to_string(),
+                                    );
+                                }
+                            });
+
+                        egui::ComboBox::from_label("Display Layout")
+                            .selected_text(global_config.display_layout.to_string())
+                            .show_ui(ui, |ui| {
+                                for display_layout in DisplayLayout::iter() {
+                                    ui.selectable_value(
+                                        &mut global_config.display_layout,
+                                        display_layout,
+                                        display_layout.to_string(),
+                                    );
+                                }
+                            })
+                            .response
+                            .on_hover_text("For machines with more than one display");
+
+                        ui.checkbox(&mut global_config.frame_blend, "Frame Blending")
+                            .on_hover_text(
+                                "Averages the last two frames, like the slow LCDs of handhelds",
+                            );
+
+                        egui::ComboBox::from_label("Frame Skip")
+                            .selected_text(global_config.frame_skip.to_string())
+                            .show_ui(ui, |ui| {
+                                for frame_skip in FrameSkip::iter() {
+                                    ui.selectable_value(
+                                        &mut global_config.frame_skip,
+                                        frame_skip,
+                                        frame_skip.to_string(),
+                                    );
+                                }
+                            });
+
+                        ui.add_enabled(
+                            global_config.frame_skip != FrameSkip::Disabled,
+                            egui::Slider::new(&mut global_config.max_frame_skip, 1..=10)
+                                .text("Max Frames Skipped"),
+                        );
+
+                        ui.add(
+                            egui::Slider::new(&mut global_config.fast_forward_speed, 150..=1000)
+                                .step_by(50.0)
+                                .suffix("%")
+                                .text("Fast Forward Speed"),
+                        );
+
                         ui.checkbox(
-                            &mut global_config.hardware_acceleration,
-                            "Hardware Acceleration",
+                            &mut global_config.emulate_in_menu,
+                            "Keep Running While The Menu Is Open",
                         );
 
-                        ui.checkbox(&mut global_config.vsync, "VSync");
+                        ui.add_enabled(
+                            global_config.emulate_in_menu,
+                            egui::Slider::new(&mut global_config.menu_emulation_speed, 10..=100)
+                                .step_by(10.0)
+                                .suffix("%")
+                                .text("Speed While The Menu Is Open"),
+                        );
+
+                        ui.checkbox(&mut global_config.battery_saver, "Battery Saver");
+
+                        ui.add(
+                            egui::Slider::new(&mut global_config.rewind_depth, 0..=600)
+                                .text("Rewind Points (0 Disables)"),
+                        );
+
+                        ui.add_enabled(
+                            global_config.rewind_depth != 0,
+                            egui::Slider::new(&mut global_config.rewind_interval, 50..=2000)
+                                .step_by(50.0)
+                                .suffix(" ms")
+                                .text("Rewind Interval"),
+                        );
+
+                        ui.checkbox(
+                            &mut global_config.restore_session,
+                            "Restore Last Session On Launch",
+                        );
+
+                        ui.checkbox(
+                            &mut global_config.pause_on_focus_loss,
+                            "Pause When Unfocused",
+                        );
+
+                        ui.add_enabled(
+                            global_config.pause_on_focus_loss,
+                            egui::Checkbox::new(
+                                &mut global_config.pause_on_focus_loss_in_netplay,
+                                "Pause When Unfocused During Netplay",
+                            ),
+                        );
+
+                        ui.add(
+                            egui::Slider::new(
+                                &mut global_config.netplay_input_delay,
+                                0..=MAX_INPUT_DELAY,
+                            )
+                            .suffix(" frames")
+                            .text("Netplay Input Delay"),
+                        );
+
+                        ui.add(
+                            egui::Slider::new(&mut global_config.idle_pause_minutes, 0..=120)
+                                .suffix(" min")
+                                .text("Pause When Idle (0 Disables)"),
+                        );
+
+                        ui.add_enabled(
+                            global_config.idle_pause_minutes != 0,
+                            egui::Checkbox::new(
+                                &mut global_config.idle_pause_autosave,
+                                "Save State When Pausing From Idle",
+                            ),
+                        );
+
+                        ui.checkbox(
+                            &mut global_config.desktop_notifications,
+                            "Notify When Imports Finish In The Background",
+                        );
+
+                        ui.add(
+                            egui::Slider::new(&mut global_config.watchdog_timeout, 0..=60)
+                                .suffix(" s")
+                                .text("Stall Detection (0 Disables, Next Game)"),
+                        );
+
+                        egui::ComboBox::from_label("Default Import Policy")
+                            .selected_text(global_config.import_policy.to_string())
+                            .show_ui(ui, |ui| {
+                                for policy in ImportPolicy::iter() {
+                                    ui.selectable_value(
+                                        &mut global_config.import_policy,
+                                        policy,
+                                        policy.to_string(),
+                                    );
+                                }
+                            });
+
+                        ui.separator();
+                        ui.heading("Watch Folders");
+
+                        global_config.watch_folders.retain(|watch_folder| {
+                            ui.horizontal(|ui| {
+                                ui.label(watch_folder.display().to_string());
+                                !ui.button("Remove").clicked()
+                            })
+                            .inner
+                        });
+
+                        let current_directory = self.file_browser_state.directory();
+                        if !global_config
+                            .watch_folders
+                            .iter()
+                            .any(|watch_folder| watch_folder == current_directory)
+                            && ui.button("Watch File Browser Directory").clicked()
+                        {
+                            global_config
+                                .watch_folders
+                                .push(current_directory.to_path_buf());
+                        }
+
+                        ui.separator();
+                        ui.heading("Accessibility");
+
+                        ui.checkbox(&mut global_config.high_contrast_gui, "High Contrast Menu");
+
+                        ui.add(
+                            egui::Slider::new(&mut global_config.osd_text_scale, 50..=300)
+                                .text("Text Size")
+                                .suffix("%"),
+                        );
+
+                        ui.add_enabled_ui(self.color_filter_supported, |ui| {
+                            let mut color_filter_enabled = global_config.color_filter.is_some();
+                            ui.checkbox(&mut color_filter_enabled, "Color Filter");
+
+                            if color_filter_enabled {
+                                let color_filter = global_config
+                                    .color_filter
+                                    .get_or_insert_with(ColorFilter::default);
+
+                                egui::ComboBox::from_label("Color Blindness")
+                                    .selected_text(color_filter.color_blindness.to_string())
+                                    .show_ui(ui, |ui| {
+                                        for color_blindness in ColorBlindness::iter() {
+                                            ui.selectable_value(
+                                                &mut color_filter.color_blindness,
+                                                color_blindness,
+                                                color_blindness.to_string(),
+                                            );
+                                        }
+                                    });
+
+                                egui::ComboBox::from_label("Filter Mode")
+                                    .selected_text(color_filter.mode.to_string())
+                                    .show_ui(ui, |ui| {
+                                        for mode in ColorFilterMode::iter() {
+                                            ui.selectable_value(
+                                                &mut color_filter.mode,
+                                                mode,
+                                                mode.to_string(),
+                                            );
+                                        }
+                                    });
+                            } else {
+                                global_config.color_filter = None;
+                            }
+                        });
+                        if !self.color_filter_supported {
+                            ui.colored_label(ui.visuals().warn_fg_color, COLOR_FILTER_UNSUPPORTED);
+                        }
+
+                        ui.separator();
+                        ui.heading("Controls");
+
+                        ui.label("Detected controllers:");
+                        if self.connected_gamepads.is_empty() {
+                            ui.label("None");
+                        }
+                        for gamepad in &self.connected_gamepads {
+                            ui.label(gamepad);
+                        }
+
+                        let mut reset_system = None;
+                        let binding_conflicts = global_config.binding_conflicts();
+
+                        if !binding_conflicts.is_empty() {
+                            for conflict in &binding_conflicts {
+                                ui.colored_label(ui.visuals().warn_fg_color, conflict.to_string());
+                            }
+
+                            if ui.button("Resolve Conflicts (Hotkeys Win)").clicked() {
+                                global_config.resolve_binding_conflicts();
+                            }
+                        }
+
+                        ui.checkbox(
+                            &mut global_config.auto_resolve_binding_conflicts,
+                            "Resolve Conflicts on Load",
+                        );
+
+                        for (system, controller_config) in &global_config.controller_configs {
+                            ui.collapsing(system.to_string(), |ui| {
+                                if ui.button("Reset to defaults").clicked() {
+                                    reset_system = Some(*system);
+                                }
+
+                                for (player, mapping) in controller_config.iter().enumerate() {
+                                    ui.strong(format!("Player {}", player + 1));
+
+                                    for (host_input, emulated_input) in mapping {
+                                        let text =
+                                            format!("{:?} → {:?}", host_input, emulated_input);
+
+                                        if binding_conflicts.iter().any(|conflict| {
+                                            conflict.system == *system
+                                                && conflict.player == player
+                                                && conflict.host_input == *host_input
+                                        }) {
+                                            ui.colored_label(ui.visuals().warn_fg_color, text);
+                                        } else {
+                                            ui.label(text);
+                                        }
+                                    }
+                                }
+                            });
+                        }
+
+                        if let Some(system) = reset_system {
+                            global_config.reset_controller_config(system);
+                        }
+                    }
+                    MenuItem::Database => {
+                        output =
+                            library::library_page(ui, &mut self.library_state, &self.global_config);
+                    }
+                    MenuItem::Audio => {
+                        ui.checkbox(&mut self.global_config.write().unwrap().audio_muted, "Mute");
+                        ui.checkbox(
+                            &mut self
+                                .global_config
+                                .write()
+                                .unwrap()
+                                .audio_capture_per_component,
+                            "Capture Each Component Separately",
+                        );
+
+                        let Some(machine) = machine else {
+                            ui.label("No machine is running");
+                            return;
+                        };
+
+                        let capture_label = if machine.audio_capturing {
+                            "Stop WAV Capture"
+                        } else {
+                            "Start WAV Capture"
+                        };
+
+                        if ui.button(capture_label).clicked() {
+                            output = Some(UiOutput::ToggleAudioCapture);
+                        }
+
+                        ui.separator();
+
+                        for (index, audio_component) in machine.audio_components.iter().enumerate()
+                        {
+                            let mut audio_component = audio_component.lock().unwrap();
+
+                            ui.label(format!("Audio component {}", index));
+
+                            for (channel, channel_name) in
+                                audio_component.audio_channels().iter().enumerate()
+                            {
+                                let mut enabled = audio_component.is_audio_channel_enabled(channel);
+
+                                if ui.checkbox(&mut enabled, *channel_name).changed() {
+                                    audio_component.set_audio_channel_enabled(channel, enabled);
+                                }
+                            }
+                        }
+                    }
+                    MenuItem::MachinePage(index) => {
+                        let Some((machine, (_, page))) = machine
+                            .and_then(|machine| Some((machine, machine.gui_pages.get(index)?)))
+                        else {
+                            // The machine that provided this page is gone
+                            self.open_menu_item = MenuItem::Main;
+                            return;
+                        };
+
+                        page(ui, machine.queryable_components);
+                    }
+                    MenuItem::EventLog => {
+                        // Copied out so logging while drawing can't deadlock
+                        let event_log: Vec<_> = EVENT_LOG.lock().unwrap().iter().cloned().collect();
+
+                        ui.horizontal(|ui| {
+                            egui::ComboBox::from_label("Severity")
+                                .selected_text(self.event_log_level.as_str())
+                                .show_ui(ui, |ui| {
+                                    for level in [Level::ERROR, Level::WARN, Level::INFO] {
+                                        ui.selectable_value(
+                                            &mut self.event_log_level,
+                                            level,
+                                            level.as_str(),
+                                        );
+                                    }
+                                });
+
+                            let mut components: Vec<_> =
+                                event_log.iter().map(|entry| &entry.component).collect();
+                            components.sort();
+                            components.dedup();
+
+                            egui::ComboBox::from_label("Component")
+                                .selected_text(self.event_log_component.as_deref().unwrap_or("All"))
+                                .show_ui(ui, |ui| {
+                                    ui.selectable_value(&mut self.event_log_component, None, "All");
+
+                                    for component in components {
+                                        ui.selectable_value(
+                                            &mut self.event_log_component,
+                                            Some(component.clone()),
+                                            component,
+                                        );
+                                    }
+                                });
+                        });
+
+                        ui.separator();
+
+                        let entries: Vec<_> = event_log
+                            .iter()
+                            .filter(|entry| {
+                                entry.level <= self.event_log_level
+                                    && self
+                                        .event_log_component
+                                        .as_ref()
+                                        .is_none_or(|component| *component == entry.component)
+                            })
+                            .collect();
+
+                        // The log holds thousands of entries, so only the ones scrolled to are laid out. That needs
+                        // every entry on a single line, long ones show in full on hover
+                        let row_height = ui.text_style_height(&egui::TextStyle::Body);
+                        egui::ScrollArea::vertical()
+                            .stick_to_bottom(true)
+                            .show_rows(ui, row_height, entries.len(), |ui, rows| {
+                                for entry in &entries[rows] {
+                                    ui.add(
+                                        egui::Label::new(format!(
+                                            "[{}] {}s ago {}: {}",
+                                            entry.level,
+                                            entry.time.elapsed().unwrap_or_default().as_secs(),
+                                            entry.component,
+                                            entry.message
+                                        ))
+                                        .truncate(),
+                                    );
+                                }
+                            });
+                    }
+                    MenuItem::Debug => {
+                        let Some(machine) = machine else {
+                            ui.label("No machine is running");
+                            return;
+                        };
+
+                        ui.heading("Windows");
+
+                        if ui.button("Detach Debugger").clicked() {
+                            output = Some(UiOutput::OpenDebuggerWindow);
+                        }
+
+                        for index in 0..machine.display_component_count {
+                            if ui
+                                .button(format!("Open Display {} in New Window", index))
+                                .clicked()
+                            {
+                                output = Some(UiOutput::OpenDisplayWindow { index });
+                            }
+                        }
+
+                        ui.separator();
+                        ui.heading("Framebuffer");
+
+                        ui.horizontal(|ui| {
+                            if ui.button("Dump as PNG").clicked() {
+                                output = Some(UiOutput::DumpFramebuffer { extension: "png" });
+                            }
+
+                            if ui.button("Dump as PPM").clicked() {
+                                output = Some(UiOutput::DumpFramebuffer { extension: "ppm" });
+                            }
+                        });
+
+                        ui.horizontal(|ui| {
+                            ui.text_edit_singleline(&mut self.framebuffer_import_path);
+
+                            if ui.button("Load Image").clicked() {
+                                output = Some(UiOutput::LoadFramebuffer {
+                                    path: PathBuf::from(&self.framebuffer_import_path),
+                                });
+                            }
+                        });
+                    }
+                    MenuItem::Scheduler => {
+                        let Some(machine) = machine else {
+                            ui.label("No machine is running");
+                            return;
+                        };
+
+                        ScrollArea::vertical().show(ui, |ui| {
+                            scheduler::scheduler_page(ui, machine.schedule_report);
+                        });
+                    }
+                    MenuItem::Peripherals => {
+                        let Some(machine) = machine else {
+                            ui.label("No machine is running");
+                            return;
+                        };
+
+                        output = peripherals::peripherals_page(ui, machine.peripheral_ports);
+                    }
+                    MenuItem::MemoryCards => {
+                        ScrollArea::vertical().show(ui, |ui| {
+                            memory_cards::memory_cards_page(ui, &mut self.memory_cards_state);
+                        });
+                    }
+                    MenuItem::Scripts => {
+                        output = scripts::scripts_page(
+                            ui,
+                            &mut self.scripts_state,
+                            &self.global_config,
+                            machine.map(|machine| machine.game),
+                        );
+                    }
+                    MenuItem::Cheats => {
+                        let Some(machine) = machine else {
+                            ui.label("No machine is running");
+                            return;
+                        };
+
+                        ScrollArea::vertical().show(ui, |ui| {
+                            output = cheats::cheats_page(
+                                ui,
+                                &mut self.cheats_state,
+                                machine.game,
+                                machine.system,
+                            );
+                        });
+                    }
+                    MenuItem::Gallery => {
+                        output = gallery::gallery_page(
+                            ui,
+                            &mut self.gallery_state,
+                            machine.map(|machine| machine.game),
+                        );
                     }
-                    MenuItem::Database => {}
                 },
             );
         });
 
+        if self.command_palette_state.is_open() {
+            let entries = self.command_palette_entries(machine);
+
+            match command_palette::command_palette(ctx, &mut self.command_palette_state, entries) {
+                Some(PaletteAction::Output(palette_output)) => {
+                    // Running something from the palette is expected to get back to the game
+                    if matches!(
+                        palette_output,
+                        UiOutput::OpenGame { .. }
+                            | UiOutput::ResetMachine
+                            | UiOutput::LoadSnapshot { .. }
+                    ) {
+                        self.active = false;
+                    }
+                    output = Some(palette_output);
+                }
+                Some(PaletteAction::OpenPage(menu_item)) => self.open_menu_item = menu_item,
+                Some(PaletteAction::Resume) => self.active = false,
+                Some(PaletteAction::ToggleMute) => {
+                    let mut global_config = self.global_config.write().unwrap();
+                    global_config.audio_muted = !global_config.audio_muted;
+                }
+                Some(PaletteAction::ToggleColorFilter) => {
+                    let mut global_config = self.global_config.write().unwrap();
+                    global_config.color_filter = match global_config.color_filter {
+                        Some(_) => None,
+                        None => Some(ColorFilter::default()),
+                    };
+                    drop(global_config);
+
+                    if !self.color_filter_supported {
+                        self.notify(COLOR_FILTER_UNSUPPORTED);
+                    }
+                }
+                None => {}
+            }
+        }
+
         output
     }
+
+    /// Everything the palette can search, games first being imported ones only as the rest can't be started
+    fn command_palette_entries(&self, machine: Option<MenuMachineContext>) -> Vec<PaletteEntry> {
+        let rom_manager = self.library_state.rom_manager();
+        let mut entries: Vec<_> = rom_manager
+            .rom_paths
+            .iter()
+            .map(|(rom_id, path)| {
+                let name = rom_manager
+                    .rom_information
+                    .get(rom_id)
+                    .and_then(|rom| rom.name.clone())
+                    .unwrap_or_else(|| path.file_name().unwrap().to_string_lossy().into_owned());
+
+                PaletteEntry::new(
+                    "Game",
+                    name,
+                    PaletteAction::Output(UiOutput::OpenGame { path: path.clone() }),
+                )
+            })
+            .collect();
+
+        if let Some(machine) = machine {
+            let slot = self.snapshot_slot;
+
+            entries.extend([
+                PaletteEntry::new("Action", "Resume", PaletteAction::Resume),
+                PaletteEntry::new(
+                    "Action",
+                    "Reset",
+                    PaletteAction::Output(UiOutput::ResetMachine),
+                ),
+                PaletteEntry::new(
+                    "Action",
+                    "Quit to Menu",
+                    PaletteAction::Output(UiOutput::StopMachine),
+                ),
+                PaletteEntry::new(
+                    "Action",
+                    format!("Save State to Slot {}", slot),
+                    PaletteAction::Output(UiOutput::SaveSnapshot { slot }),
+                ),
+                PaletteEntry::new(
+                    "Action",
+                    format!("Load State from Slot {}", slot),
+                    PaletteAction::Output(UiOutput::LoadSnapshot { slot }),
+                ),
+                PaletteEntry::new(
+                    "Action",
+                    "Open Debugger",
+                    PaletteAction::Output(UiOutput::OpenDebuggerWindow),
+                ),
+                PaletteEntry::new(
+                    "Action",
+                    "Take Screenshot",
+                    PaletteAction::Output(UiOutput::DumpFramebuffer { extension: "png" }),
+                ),
+                PaletteEntry::new(
+                    "Action",
+                    if machine.audio_capturing {
+                        "Stop Audio Capture"
+                    } else {
+                        "Start Audio Capture"
+                    },
+                    PaletteAction::Output(UiOutput::ToggleAudioCapture),
+                ),
+            ]);
+
+            if !machine.peripheral_ports.is_empty() {
+                entries.push(PaletteEntry::new(
+                    "Page",
+                    "Peripherals",
+                    PaletteAction::OpenPage(MenuItem::Peripherals),
+                ));
+            }
+            entries.push(PaletteEntry::new(
+                "Page",
+                "Cheats",
+                PaletteAction::OpenPage(MenuItem::Cheats),
+            ));
+            entries.extend(
+                machine
+                    .gui_pages
+                    .iter()
+                    .enumerate()
+                    .map(|(index, (name, _))| {
+                        PaletteEntry::new(
+                            "Page",
+                            *name,
+                            PaletteAction::OpenPage(MenuItem::MachinePage(index)),
+                        )
+                    }),
+            );
+        }
+
+        entries.extend([
+            PaletteEntry::new("Settings", "Toggle Mute", PaletteAction::ToggleMute),
+            PaletteEntry::new(
+                "Settings",
+                "Toggle Color Filter",
+                PaletteAction::ToggleColorFilter,
+            ),
+            PaletteEntry::new("Action", "Quit", PaletteAction::Output(UiOutput::Quit)),
+        ]);
+        entries.extend(
+            [
+                ("Main", MenuItem::Main),
+                ("File Browser", MenuItem::FileBrowser),
+                ("Options", MenuItem::Options),
+                ("Database", MenuItem::Database),
+                ("Audio", MenuItem::Audio),
+                ("Event Log", MenuItem::EventLog),
+                ("Debug", MenuItem::Debug),
+                ("Scheduler", MenuItem::Scheduler),
+                ("Gallery", MenuItem::Gallery),
+                ("Memory Cards", MenuItem::MemoryCards),
+                ("Scripts", MenuItem::Scripts),
+            ]
+            .map(|(name, menu_item)| {
+                PaletteEntry::new("Page", name, PaletteAction::OpenPage(menu_item))
+            }),
+        );
+
+        entries
+    }
+
+    /// The handful of settings people change mid game, written to the layer of the running game and applied live
+    fn run_quick_menu(&mut self, ctx: &Context, machine: MenuMachineContext) {
+        let mut global_config = self.global_config.write().unwrap();
+        let original_game_config = global_config
+            .game_configs
+            .get(&machine.game)
+            .cloned()
+            .unwrap_or_default();
+        let mut game_config = original_game_config.clone();
+        let mut save = false;
+
+        let mut speed = global_config.effective_speed();
+        let mut color_filter = global_config.effective_color_filter();
+        let mut aspect_mode = global_config.effective_aspect_mode();
+        let mut scale_filter = global_config.effective_scale_filter();
+        let mut frame_blend = global_config.effective_frame_blend();
+        let mut rewind = global_config.effective_rewind();
+        let profiles: Vec<_> = global_config
+            .controller_profiles
+            .get(&machine.system)
+            .map(|profiles| profiles.keys().cloned().collect())
+            .unwrap_or_default();
+
+        egui::Window::new("Quick Settings")
+            .anchor(egui::Align2::CENTER_CENTER, egui::vec2(0.0, 0.0))
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                if ui
+                    .add(
+                        egui::Slider::new(&mut speed, 25..=400)
+                            .step_by(25.0)
+                            .suffix("%")
+                            .text("Speed"),
+                    )
+                    .changed()
+                {
+                    game_config.speed = Some(speed);
+                }
+
+                ui.add_enabled_ui(self.color_filter_supported, |ui| {
+                    let mut color_filter_enabled = color_filter.is_some();
+                    if ui
+                        .checkbox(&mut color_filter_enabled, "Color Filter")
+                        .changed()
+                    {
+                        color_filter = color_filter_enabled
+                            .then(|| global_config.color_filter.unwrap_or_default());
+                        game_config.color_filter = Some(color_filter);
+                    }
+
+                    if let Some(mut filter) = color_filter {
+                        egui::ComboBox::from_label("Color Blindness")
+                            .selected_text(filter.color_blindness.to_string())
+                            .show_ui(ui, |ui| {
+                                for color_blindness in ColorBlindness::iter() {
+                                    if ui
+                                        .selectable_value(
+                                            &mut filter.color_blindness,
+                                            color_blindness,
+                                            color_blindness.to_string(),
+                                        )
+                                        .changed()
+                                    {
+                                        game_config.color_filter = Some(Some(filter));
+                                    }
+                                }
+                            });
+                    }
+                })
+                .response
+                .on_disabled_hover_text(COLOR_FILTER_UNSUPPORTED);
+
+                egui::ComboBox::from_label("Aspect")
+                    .selected_text(aspect_mode.to_string())
+                    .show_ui(ui, |ui| {
+                        for mode in AspectMode::iter() {
+                            if ui
+                                .selectable_value(&mut aspect_mode, mode, mode.to_string())
+                                .changed()
+                            {
+                                game_config.aspect_mode = Some(aspect_mode);
+                            }
+                        }
+                    });
+
+                egui::ComboBox::from_label("Scaling Filter")
+                    .selected_text(scale_filter.to_string())
+                    .show_ui(ui, |ui| {
+                        for filter in ScaleFilter::iter() {
+                            if ui
+                                .selectable_value(&mut scale_filter, filter, filter.to_string())
+                                .changed()
+                            {
+                                game_config.scale_filter = Some(scale_filter);
+                            }
+                        }
+                    });
+
+                if ui.checkbox(&mut frame_blend, "Frame Blending").changed() {
+                    game_config.frame_blend = Some(frame_blend);
+                }
+
+                // The depth is global and only picked up when a game starts
+                if ui
+                    .add_enabled(
+                        global_config.rewind_depth != 0,
+                        egui::Checkbox::new(&mut rewind, "Rewind"),
+                    )
+                    .on_disabled_hover_text("Set the rewind points in the options first")
+                    .changed()
+                {
+                    game_config.rewind = Some(rewind);
+                }
+
+                egui::ComboBox::from_label("Controller Profile")
+                    .selected_text(
+                        game_config
+                            .controller_profile
+                            .as_deref()
+                            .unwrap_or("System Default"),
+                    )
+                    .show_ui(ui, |ui| {
+                        ui.selectable_value(
+                            &mut game_config.controller_profile,
+                            None,
+                            "System Default",
+                        );
+
+                        for profile in &profiles {
+                            ui.selectable_value(
+                                &mut game_config.controller_profile,
+                                Some(profile.clone()),
+                                profile,
+                            );
+                        }
+                    });
+
+                if ui.button("Save Mapping as New Profile").clicked() {
+                    let controller_config = global_config
+                        .effective_controller_config(machine.system)
+                        .cloned()
+                        .unwrap_or_default();
+                    let profile = format!("Profile {}", profiles.len() + 1);
+
+                    global_config
+                        .controller_profiles
+                        .entry(machine.system)
+                        .or_default()
+                        .insert(profile.clone(), controller_config);
+                    game_config.controller_profile = Some(profile);
+                }
+
+                ui.separator();
+
+                ui.horizontal(|ui| {
+                    if ui.button("Resume").clicked() {
+                        self.quick_menu_active = false;
+                    }
+
+                    if ui.button("Full Menu").clicked() {
+                        self.quick_menu_active = false;
+                        self.active = true;
+                    }
+
+                    if ui.button("Use Global Settings").clicked() {
+                        game_config = GameConfig {
+                            labels: std::mem::take(&mut game_config.labels),
+                            scripts: std::mem::take(&mut game_config.scripts),
+                            ..Default::default()
+                        };
+                    }
+
+                    if ui.button("Save Config").clicked() {
+                        save = true;
+                    }
+                });
+            });
+
+        // Untouched games don't get an empty entry
+        if game_config != original_game_config {
+            if game_config == Default::default() {
+                global_config.game_configs.shift_remove(&machine.game);
+            } else {
+                global_config.game_configs.insert(machine.game, game_config);
+            }
+        }
+
+        if save {
+            global_config.save().unwrap();
+        }
+    }
+
+    fn show_notifications(&mut self, ctx: &Context) {
+        self.notifications
+            .retain(|(posted, _)| posted.elapsed() < NOTIFICATION_DURATION);
+
+        if self.notifications.is_empty() {
+            return;
+        }
+
+        egui::Area::new(egui::Id::new("notifications"))
+            .anchor(egui::Align2::RIGHT_BOTTOM, egui::vec2(-8.0, -8.0))
+            .show(ctx, |ui| {
+                for (_, message) in &self.notifications {
+                    egui::Frame::popup(ui.style()).show(ui, |ui| {
+                        ui.label(message);
+                    });
+                }
+            });
+
+        // Make sure they go away even if nothing else happens
+        ctx.request_repaint_after(NOTIFICATION_DURATION);
+    }
+
+    fn apply_accessibility_style(&self, ctx: &Context) {
+        let global_config = self.global_config.read().unwrap();
+        let mut style = Style::default();
+
+        if global_config.high_contrast_gui {
+            style.visuals = high_contrast_visuals();
+        }
+
+        let text_scale = global_config.osd_text_scale as f32 / 100.0;
+        for font_id in style.text_styles.values_mut() {
+            font_id.size *= text_scale;
+        }
+
+        ctx.set_style(style);
+    }
+}
+
+fn high_contrast_visuals() -> Visuals {
+    let mut visuals = Visuals::dark();
+
+    visuals.override_text_color = Some(Color32::WHITE);
+    visuals.panel_fill = Color32::BLACK;
+    visuals.window_fill = Color32::BLACK;
+    visuals.extreme_bg_color = Color32::BLACK;
+    visuals.selection.bg_fill = Color32::from_rgb(0, 0x60, 0xff);
+    visuals.selection.stroke = Stroke::new(2.0, Color32::WHITE);
+
+    for widget_visuals in [
+        &mut visuals.widgets.noninteractive,
+        &mut visuals.widgets.inactive,
+        &mut visuals.widgets.hovered,
+        &mut visuals.widgets.active,
+        &mut visuals.widgets.open,
+    ] {
+        widget_visuals.bg_stroke = Stroke::new(2.0, Color32::WHITE);
+        widget_visuals.fg_stroke = Stroke::new(2.0, Color32::WHITE);
+    }
+
+    visuals.widgets.inactive.weak_bg_fill = Color32::BLACK;
+    visuals.widgets.hovered.weak_bg_fill = Color32::from_rgb(0x40, 0x40, 0x40);
+    visuals.widgets.active.weak_bg_fill = Color32::from_rgb(0, 0x60, 0xff);
+
+    visuals
 }
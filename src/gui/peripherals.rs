@@ -0,0 +1,42 @@
+use super::UiOutput;
+use crate::machine::peripheral::PeripheralPort;
+use egui::{ComboBox, Grid, Ui};
+
+/// Lists the ports of the running machine with what is plugged into them. Swapping is done by the runtime, as only it
+/// can reach the executor
+pub fn peripherals_page(ui: &mut Ui, peripheral_ports: &[PeripheralPort]) -> Option<UiOutput> {
+    let mut output = None;
+
+    if peripheral_ports.is_empty() {
+        ui.label("This machine has no ports to plug devices into");
+        return None;
+    }
+
+    Grid::new("peripheral_ports").num_columns(2).show(ui, |ui| {
+        for (port, peripheral_port) in peripheral_ports.iter().enumerate() {
+            let accepts: Vec<_> = peripheral_port.accepts().collect();
+            let mut plugged = peripheral_port.plugged();
+
+            ui.label(peripheral_port.name);
+            ComboBox::from_id_salt(("peripheral_port", port))
+                .selected_text(plugged.map_or("Nothing", |kind| accepts[kind]))
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(&mut plugged, None, "Nothing");
+
+                    for (kind, name) in accepts.iter().enumerate() {
+                        ui.selectable_value(&mut plugged, Some(kind), *name);
+                    }
+                });
+            ui.end_row();
+
+            if plugged != peripheral_port.plugged() {
+                output = Some(UiOutput::PlugPeripheral {
+                    port,
+                    kind: plugged,
+                });
+            }
+        }
+    });
+
+    output
+}
@@ -0,0 +1,101 @@
+use crate::machine::executor::ScheduleReport;
+use egui::{Color32, Grid, Rect, Sense, Stroke, Ui, Vec2};
+use num::ToPrimitive;
+use std::time::Duration;
+
+/// How much time the timeline spans, matching what executors keep around
+const TIMELINE_WINDOW: Duration = Duration::from_secs(1);
+const TIMELINE_ROW_HEIGHT: f32 = 18.0;
+
+/// Shows how the executor resolved the machine timings and a timeline of what the tasks got recently
+pub fn scheduler_page(ui: &mut Ui, report: &ScheduleReport) {
+    ui.heading("Timings");
+
+    ui.label(format!(
+        "A tick on this machine is a real world {:?}",
+        report.tick_real_time
+    ));
+    ui.label(format!(
+        "Tasks line up again every {} ticks",
+        report.rollover_tick
+    ));
+
+    ui.separator();
+    ui.heading("Tasks");
+
+    Grid::new("scheduler_tasks")
+        .striped(true)
+        .num_columns(7)
+        .show(ui, |ui| {
+            ui.label("Task");
+            ui.label("Requested Rate");
+            ui.label("Effective Rate");
+            ui.label("Batches");
+            ui.label("Average Batch Size");
+            ui.label("Real Time");
+            ui.label("Share");
+            ui.end_row();
+
+            let total_real_time: Duration =
+                report.batches.iter().map(|batch| batch.real_time).sum();
+
+            for (index, task) in report.tasks.iter().enumerate() {
+                let batches = report.batches.iter().filter(|batch| batch.task == index);
+                let batch_count = batches.clone().count();
+                let ticks: u64 = batches.clone().map(|batch| batch.batch_size as u64).sum();
+                let real_time: Duration = batches.map(|batch| batch.real_time).sum();
+                // What the task actually runs at after being fit to the executor tick
+                let effective_rate =
+                    1.0 / (task.period as f64 * report.tick_real_time.as_secs_f64());
+
+                ui.colored_label(task_color(index), task.name);
+                ui.label(format!("{:.2} Hz", task.tick_rate.to_f64().unwrap()));
+                ui.label(format!("{:.2} Hz", effective_rate));
+                ui.label(batch_count.to_string());
+                ui.label(format!("{:.1}", ticks as f64 / batch_count.max(1) as f64));
+                ui.label(format!("{:?}", real_time));
+                ui.label(format!(
+                    "{:.1}%",
+                    real_time.as_secs_f64() / total_real_time.as_secs_f64().max(f64::EPSILON)
+                        * 100.0
+                ));
+                ui.end_row();
+            }
+        });
+
+    ui.separator();
+    ui.heading("Timeline");
+    ui.label("The last second of batches, newest on the right");
+
+    let (response, painter) = ui.allocate_painter(
+        Vec2::new(
+            ui.available_width(),
+            TIMELINE_ROW_HEIGHT * report.tasks.len().max(1) as f32,
+        ),
+        Sense::hover(),
+    );
+    let area = response.rect;
+    painter.rect_stroke(area, 0.0, Stroke::new(1.0, ui.visuals().weak_text_color()));
+
+    let window = TIMELINE_WINDOW.as_secs_f32();
+    for batch in &report.batches {
+        let start = 1.0 - batch.age.as_secs_f32() / window;
+        let width = batch.real_time.as_secs_f32() / window;
+        let top = area.top() + batch.task as f32 * TIMELINE_ROW_HEIGHT;
+
+        // Keep even the tiniest batches visible
+        let rect = Rect::from_min_size(
+            egui::pos2(area.left() + start * area.width(), top + 2.0),
+            Vec2::new((width * area.width()).max(1.0), TIMELINE_ROW_HEIGHT - 4.0),
+        )
+        .intersect(area);
+
+        painter.rect_filled(rect, 0.0, task_color(batch.task));
+    }
+}
+
+/// Spreads the task colors evenly around the hue circle
+fn task_color(index: usize) -> Color32 {
+    let hue = (index as f32 * 0.618_034).fract();
+    egui::ecolor::Hsva::new(hue, 0.7, 0.9, 1.0).into()
+}
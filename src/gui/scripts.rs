@@ -0,0 +1,80 @@
+use super::UiOutput;
+use crate::{
+    config::GlobalConfig,
+    env::SCRIPT_DIRECTORY,
+    rom::RomId,
+    scripting::{available_scripts, SCRIPT_EXTENSION},
+};
+use egui::Ui;
+use std::{fs::create_dir_all, sync::RwLock};
+
+#[derive(Clone, Debug, Default)]
+pub struct ScriptsState {
+    /// Looked up the first time the page is shown
+    scripts: Option<Vec<String>>,
+}
+
+/// Lists the scripts there are, with the ones the running game should run ticked
+pub fn scripts_page(
+    ui: &mut Ui,
+    state: &mut ScriptsState,
+    global_config: &RwLock<GlobalConfig>,
+    game: Option<RomId>,
+) -> Option<UiOutput> {
+    let mut output = None;
+
+    ui.horizontal(|ui| {
+        if ui.button("Refresh").clicked() {
+            state.scripts = None;
+        }
+
+        if ui.button("Open Scripts Folder").clicked() {
+            let _ = create_dir_all(SCRIPT_DIRECTORY.as_path());
+            output = Some(UiOutput::OpenInFileManager {
+                path: SCRIPT_DIRECTORY.clone(),
+            });
+        }
+    });
+
+    ui.separator();
+
+    let scripts = state.scripts.get_or_insert_with(available_scripts);
+    if scripts.is_empty() {
+        ui.label(format!(
+            "No scripts yet, .{} files in the scripts folder show up here",
+            SCRIPT_EXTENSION
+        ));
+        return output;
+    }
+
+    let Some(game) = game else {
+        ui.label("Start a game to pick the scripts it runs");
+
+        for script in scripts.iter() {
+            ui.label(script);
+        }
+
+        return output;
+    };
+
+    let mut global_config = global_config.write().unwrap();
+    let game_config = global_config.game_configs.entry(game).or_default();
+
+    for script in scripts.iter() {
+        let mut enabled = game_config.scripts.contains(script);
+
+        if ui.checkbox(&mut enabled, script).changed() {
+            if enabled {
+                game_config.scripts.push(script.clone());
+            } else {
+                game_config.scripts.retain(|enabled| enabled != script);
+            }
+        }
+    }
+
+    if *game_config == Default::default() {
+        global_config.game_configs.shift_remove(&game);
+    }
+
+    output
+}
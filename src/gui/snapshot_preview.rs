@@ -0,0 +1,168 @@
+use crate::{
+    rom::RomId,
+    snapshot::{latest_snapshot, SnapshotMetadata},
+};
+use egui::{ColorImage, Context, TextureHandle, TextureOptions};
+use std::{
+    collections::HashMap,
+    sync::mpsc::{channel, Receiver, Sender},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// The newest snapshot of a game, as shown in the library
+#[derive(Clone)]
+pub struct SnapshotPreview {
+    pub slot: u8,
+    /// Seconds since the unix epoch
+    pub saved_at: u64,
+    pub thumbnail: Option<TextureHandle>,
+}
+
+impl SnapshotPreview {
+    pub fn describe(&self) -> String {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+
+        format!(
+            "Last played {} (slot {})",
+            describe_age(now.saturating_sub(self.saved_at)),
+            self.slot
+        )
+    }
+}
+
+enum PreviewState {
+    Loading,
+    Loaded(Option<SnapshotPreview>),
+}
+
+type PreviewResult = (RomId, Option<(u8, SnapshotMetadata)>);
+
+/// Reads snapshot metadata on a thread of its own, so scrolling the library never waits on the disk
+///
+/// The thread is only started once a preview is first asked for, and stops when this is dropped
+#[derive(Default)]
+pub struct SnapshotPreviews {
+    worker: Option<(Sender<RomId>, Receiver<PreviewResult>)>,
+    previews: HashMap<RomId, PreviewState>,
+}
+
+// A clone starts with nothing cached and its own thread, previews are cheap to load again
+impl Clone for SnapshotPreviews {
+    fn clone(&self) -> Self {
+        Self::default()
+    }
+}
+
+impl std::fmt::Debug for SnapshotPreviews {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SnapshotPreviews")
+            .field("cached", &self.previews.len())
+            .finish_non_exhaustive()
+    }
+}
+
+impl SnapshotPreviews {
+    /// None until the worker has gotten to it, and also when the game has no snapshots
+    pub fn get(&mut self, context: &Context, game: RomId) -> Option<&SnapshotPreview> {
+        self.receive(context);
+
+        if !self.previews.contains_key(&game) {
+            let (requests, _) = self
+                .worker
+                .get_or_insert_with(|| spawn_worker(context.clone()));
+            // The worker only goes away with us
+            requests.send(game).unwrap();
+            self.previews.insert(game, PreviewState::Loading);
+        }
+
+        match self.previews.get(&game) {
+            Some(PreviewState::Loaded(preview)) => preview.as_ref(),
+            _ => None,
+        }
+    }
+
+    /// Has the preview read again the next time it is shown
+    pub fn invalidate(&mut self, game: RomId) {
+        self.previews.remove(&game);
+    }
+
+    fn receive(&mut self, context: &Context) {
+        let Some((_, results)) = &self.worker else {
+            return;
+        };
+
+        for (game, result) in results.try_iter() {
+            // Results for previews invalidated while they were being read are stale
+            let Some(state @ PreviewState::Loading) = self.previews.get_mut(&game) else {
+                continue;
+            };
+
+            *state = PreviewState::Loaded(result.map(|(slot, metadata)| SnapshotPreview {
+                slot,
+                saved_at: metadata.saved_at,
+                thumbnail: metadata.thumbnail.map(|thumbnail| {
+                    context.load_texture(
+                        format!("snapshot_preview_{}", game),
+                        ColorImage::from_rgba_unmultiplied(
+                            [thumbnail.width, thumbnail.height],
+                            &thumbnail.pixels,
+                        ),
+                        // Pixel art should stay sharp when scaled
+                        TextureOptions::NEAREST,
+                    )
+                }),
+            }));
+        }
+    }
+}
+
+fn spawn_worker(context: Context) -> (Sender<RomId>, Receiver<PreviewResult>) {
+    let (request_sender, request_receiver) = channel::<RomId>();
+    let (result_sender, result_receiver) = channel();
+
+    std::thread::Builder::new()
+        .name("snapshot_previews".to_string())
+        .spawn(move || {
+            for game in request_receiver {
+                if result_sender.send((game, latest_snapshot(game))).is_err() {
+                    break;
+                }
+                context.request_repaint();
+            }
+        })
+        .unwrap();
+
+    (request_sender, result_receiver)
+}
+
+fn describe_age(seconds: u64) -> String {
+    let (amount, unit) = match seconds {
+        0..60 => return "just now".to_string(),
+        60..3600 => (seconds / 60, "minute"),
+        3600..86400 => (seconds / 3600, "hour"),
+        _ => (seconds / 86400, "day"),
+    };
+
+    format!(
+        "{} {}{} ago",
+        amount,
+        unit,
+        if amount == 1 { "" } else { "s" }
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ages_are_described_in_their_largest_unit() {
+        assert_eq!(describe_age(5), "just now");
+        assert_eq!(describe_age(60), "1 minute ago");
+        assert_eq!(describe_age(3 * 3600 + 59), "3 hours ago");
+        assert_eq!(describe_age(2 * 86400), "2 days ago");
+    }
+}
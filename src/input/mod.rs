@@ -1,5 +1,6 @@
 use gamepad::GamepadInput;
 use keyboard::KeyboardInput;
+use pointer::PointerInput;
 use serde::{Deserialize, Serialize};
 use std::{
     collections::HashMap,
@@ -8,12 +9,14 @@ use std::{
 
 pub mod gamepad;
 pub mod keyboard;
+pub mod pointer;
 
 #[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum Input {
     Gamepad(GamepadInput),
     // In game uses key codes
     Keyboard(KeyboardInput),
+    Pointer(PointerInput),
 }
 
 #[derive(Debug, Copy, Clone, PartialEq)]
@@ -21,6 +24,7 @@ pub enum InputState {
     /// 0 or 1
     Digital(bool),
     /// Clamped from 0.0 to 1.0
+    #[cfg(feature = "frontend")]
     Analog(f32),
 }
 
@@ -34,10 +38,12 @@ impl InputState {
     pub fn as_digital(&self) -> bool {
         match self {
             InputState::Digital(value) => *value,
+            #[cfg(feature = "frontend")]
             InputState::Analog(value) => *value >= 0.5,
         }
     }
 
+    #[allow(dead_code)]
     pub fn as_analog(&self) -> f32 {
         match self {
             InputState::Digital(value) => {
@@ -47,40 +53,130 @@ impl InputState {
                     0.0
                 }
             }
+            #[cfg(feature = "frontend")]
             InputState::Analog(value) => *value,
         }
     }
 }
 
+#[derive(Debug, Default)]
+struct TrackedInputState {
+    state: InputState,
+    /// Generation of the gamepad when this last changed
+    changed: u64,
+    /// Relative motion not taken yet, for inputs like mouse axes that have no resting position
+    motion: f32,
+}
+
+#[derive(Debug, Default)]
+struct EmulatedGamepadState {
+    inputs: HashMap<Input, TrackedInputState>,
+    /// Increments every time a input changes
+    generation: u64,
+}
+
 #[derive(Debug)]
-pub struct EmulatedGamepad(Mutex<HashMap<Input, InputState>>);
+pub struct EmulatedGamepad(Mutex<EmulatedGamepadState>);
 
 impl EmulatedGamepad {
     pub fn new(inputs: &[Input]) -> Arc<Self> {
-        let mut map = HashMap::new();
+        let mut state = EmulatedGamepadState::default();
         for input in inputs {
-            map.insert(*input, InputState::Digital(false));
+            state.inputs.insert(*input, TrackedInputState::default());
         }
-        Arc::new(Self(Mutex::new(map)))
+        Arc::new(Self(Mutex::new(state)))
     }
 
     pub fn set_input_state(&self, input: Input, input_state: InputState) {
-        if let Some(value) = self.0.lock().unwrap().get_mut(&input) {
-            *value = input_state;
+        let mut state = self.0.lock().unwrap();
+        let generation = state.generation + 1;
+
+        if let Some(value) = state.inputs.get_mut(&input) {
+            if value.state == input_state {
+                return;
+            }
+
+            value.state = input_state;
+            value.changed = generation;
+            state.generation = generation;
         }
     }
 
+    /// Puts every input back at rest, so nothing stays held after a remap
+    pub fn release_all(&self) {
+        let inputs: Vec<_> = self.0.lock().unwrap().inputs.keys().copied().collect();
+
+        for input in inputs {
+            self.set_input_state(input, InputState::default());
+        }
+
+        for value in self.0.lock().unwrap().inputs.values_mut() {
+            value.motion = 0.0;
+        }
+    }
+
+    /// Adds up motion on the input until it is taken
+    #[cfg(any(feature = "frontend", test))]
+    pub fn add_motion(&self, input: Input, amount: f32) {
+        if let Some(value) = self.0.lock().unwrap().inputs.get_mut(&input) {
+            value.motion += amount;
+        }
+    }
+
+    /// How far the input moved since it was last taken
+    #[allow(dead_code)]
+    pub fn take_motion(&self, input: Input) -> f32 {
+        self.0
+            .lock()
+            .unwrap()
+            .inputs
+            .get_mut(&input)
+            .map_or(0.0, |value| std::mem::take(&mut value.motion))
+    }
+
     pub fn get_input_state(&self, input: Input) -> Option<InputState> {
-        self.0.lock().unwrap().get(&input).copied()
+        self.0
+            .lock()
+            .unwrap()
+            .inputs
+            .get(&input)
+            .map(|value| value.state)
     }
 
-    pub fn iter_pressed(&self) -> impl Iterator<Item = Input> + '_ {
+    /// Changes every time any input changes. Components save this every tick and hand it to the just_* methods
+    pub fn generation(&self) -> u64 {
+        self.0.lock().unwrap().generation
+    }
+
+    /// If the input went down after the given generation and is still down
+    #[allow(dead_code)]
+    pub fn just_pressed(&self, input: Input, since: u64) -> bool {
+        self.0
+            .lock()
+            .unwrap()
+            .inputs
+            .get(&input)
+            .is_some_and(|value| value.changed > since && value.state.as_digital())
+    }
+
+    /// If the input went up after the given generation and is still up
+    pub fn just_released(&self, input: Input, since: u64) -> bool {
+        self.0
+            .lock()
+            .unwrap()
+            .inputs
+            .get(&input)
+            .is_some_and(|value| value.changed > since && !value.state.as_digital())
+    }
+
+    pub fn iter_just_pressed(&self, since: u64) -> impl Iterator<Item = Input> + '_ {
         self.0
             .lock()
             .unwrap()
+            .inputs
             .iter()
-            .filter_map(|(input, state)| {
-                if state.as_digital() {
+            .filter_map(|(input, value)| {
+                if value.changed > since && value.state.as_digital() {
                     Some(*input)
                 } else {
                     None
@@ -94,9 +190,10 @@ impl EmulatedGamepad {
         self.0
             .lock()
             .unwrap()
+            .inputs
             .iter()
-            .filter_map(|(input, state)| {
-                if !state.as_digital() {
+            .filter_map(|(input, value)| {
+                if !value.state.as_digital() {
                     Some(*input)
                 } else {
                     None
@@ -110,4 +207,70 @@ impl EmulatedGamepad {
 #[derive(Serialize, Deserialize, Debug, Copy, Clone, PartialEq, Eq, Hash)]
 pub enum Hotkey {
     OpenMenu,
+    /// Common settings of the running game without leaving it
+    OpenQuickMenu,
+    ToggleMute,
+    /// Into the quick slot
+    SaveSnapshot,
+    LoadSnapshot,
+    /// One rewind point back per press
+    Rewind,
+    /// Confines and hides the cursor so mouse motion goes to the game
+    TogglePointerCapture,
+    /// Searches games and actions from anywhere
+    OpenCommandPalette,
+    /// Runs at the fast forward speed while held
+    FastForward,
+    /// Saves every display of the running game to the gallery
+    Screenshot,
+    ToggleFullscreen,
+    /// Stops the machine, leaving it to be moved along by frame advance
+    TogglePause,
+    /// Pauses if running, then runs exactly one frame of the fastest display
+    FrameAdvance,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn emulated_gamepad_edges() {
+        let input = Input::Keyboard(KeyboardInput::KeyA);
+        let gamepad = EmulatedGamepad::new(&[input]);
+
+        let last_tick = gamepad.generation();
+        assert!(!gamepad.just_pressed(input, last_tick));
+
+        gamepad.set_input_state(input, InputState::Digital(true));
+        assert!(gamepad.just_pressed(input, last_tick));
+        assert!(!gamepad.just_released(input, last_tick));
+
+        // Setting the same state again is not a edge
+        let last_tick = gamepad.generation();
+        gamepad.set_input_state(input, InputState::Digital(true));
+        assert!(!gamepad.just_pressed(input, last_tick));
+
+        gamepad.set_input_state(input, InputState::Digital(false));
+        assert!(gamepad.just_released(input, last_tick));
+    }
+
+    #[test]
+    fn motion_is_taken_once() {
+        let input = Input::Pointer(PointerInput::XAxis);
+        let gamepad = EmulatedGamepad::new(&[input]);
+
+        gamepad.add_motion(input, 3.0);
+        gamepad.add_motion(input, -1.0);
+        // Motion is not a state change
+        assert_eq!(gamepad.generation(), 0);
+        assert_eq!(gamepad.take_motion(input), 2.0);
+        assert_eq!(gamepad.take_motion(input), 0.0);
+
+        gamepad.add_motion(Input::Pointer(PointerInput::YAxis), 1.0);
+        assert_eq!(
+            gamepad.take_motion(Input::Pointer(PointerInput::YAxis)),
+            0.0
+        );
+    }
 }
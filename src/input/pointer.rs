@@ -0,0 +1,33 @@
+use serde::{Deserialize, Serialize};
+use strum::EnumIter;
+
+/// Mice, trackballs and light guns, only delivered while the pointer is captured
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, PartialEq, Eq, Hash, EnumIter)]
+pub enum PointerInput {
+    LeftButton,
+    MiddleButton,
+    RightButton,
+    /// Relative motion, read with [crate::input::EmulatedGamepad::take_motion]
+    XAxis,
+    YAxis,
+}
+
+#[cfg(desktop)]
+mod desktop {
+    use super::PointerInput;
+    use crate::input::Input;
+    use winit::event::MouseButton;
+
+    impl TryFrom<MouseButton> for Input {
+        type Error = ();
+
+        fn try_from(value: MouseButton) -> Result<Self, Self::Error> {
+            Ok(match value {
+                MouseButton::Left => Input::Pointer(PointerInput::LeftButton),
+                MouseButton::Middle => Input::Pointer(PointerInput::MiddleButton),
+                MouseButton::Right => Input::Pointer(PointerInput::RightButton),
+                _ => return Err(()),
+            })
+        }
+    }
+}
@@ -1,10 +1,12 @@
-use crate::machine::Machine;
+use crate::machine::{peripheral::PeripheralKind, Machine};
 use crate::rom::RomId;
 use crate::rom::RomManager;
 use crate::runtime::RenderingBackend;
-use crate::task::processor::ProcessorTask;
 use crate::{
-    component::definitions::misc::processor::m6502::{M6502Config, M6502},
+    component::definitions::atari2600::controller::{Atari2600Controller, JOYSTICK_INPUTS},
+    component::definitions::misc::processor::m6502::{M6502Config, M6502Kind, M6502},
+    component::interrupt::InterruptLine,
+    component::memory::OpenBusBehavior,
     task::processor::ProcessorTaskConfig,
 };
 use num::rational::Ratio;
@@ -12,19 +14,37 @@ use std::sync::Arc;
 
 pub fn atari_atari2600<R: RenderingBackend>(
     rom_manager: Arc<RomManager>,
-    user_specified_roms: Vec<RomId>,
-    rendering_state: &mut <R as RenderingBackend>::RuntimeState,
+    _user_specified_roms: Vec<RomId>,
 ) -> Machine<R> {
-    Machine::build(rom_manager, rendering_state)
+    Machine::build(rom_manager)
+        // Same as the NES, nothing pulls the data bus so it holds the last value
+        .open_bus(OpenBusBehavior::LastValue)
         .component::<M6502>(
             "processor",
             M6502Config {
                 frequency: Ratio::new(1193182, 1),
+                kind: M6502Kind::M6507,
+                // The 6507 has no interrupt pins, these are never raised
+                irq: InterruptLine::default(),
+                nmi: InterruptLine::default(),
+                // Racing the beam needs every instruction timed on its own
+                dynarec: None,
             },
         )
-        .insert_schedule::<ProcessorTask<_>>(ProcessorTaskConfig {
+        .insert_processor_schedule(ProcessorTaskConfig {
             initial_program_pointer: 0x0000,
         })
         .finalize_component()
+        // Read through the RIOT and TIA, so the ports have no memory of their own
+        .peripheral_port("Left Controller", None, controller_port_kinds(), Some(0))
+        .peripheral_port("Right Controller", None, controller_port_kinds(), None)
         .finalize_machine()
 }
+
+fn controller_port_kinds() -> Vec<PeripheralKind> {
+    vec![PeripheralKind {
+        name: "Joystick",
+        inputs: JOYSTICK_INPUTS,
+        construct: Atari2600Controller::peripheral,
+    }]
+}
@@ -1,51 +1,127 @@
 use super::Machine;
+#[cfg(feature = "nes")]
+use crate::component::definitions::nes::cartridge::CartridgeError;
 use crate::{
     component::{definitions::chip8::display::Chip8Display, display::DisplayComponent},
-    rom::{
-        AtariSystem, GameSystem, NintendoSystem, OtherSystem, RomId, RomManager, SegaSystem,
-        SonySystem,
-    },
+    rom::{AtariSystem, GameSystem, NintendoSystem, OtherSystem, RomId, RomManager},
     runtime::RenderingBackend,
 };
+#[cfg(feature = "atari2600")]
 use atari_atari2600::atari_atari2600;
+#[cfg(feature = "nes")]
+use nintendo_nes::nintendo_nes;
+#[cfg(feature = "chip8")]
 use other_chip8::other_chip8;
+#[cfg(feature = "chip8")]
+use other_superchip8::other_superchip8;
 use std::sync::Arc;
+use thiserror::Error;
 
+#[cfg(feature = "atari2600")]
 mod atari_atari2600;
+#[cfg(feature = "nes")]
+mod nintendo_nes;
+#[cfg(feature = "chip8")]
 mod other_chip8;
+#[cfg(feature = "chip8")]
 mod other_superchip8;
 mod sega_gamegear;
 mod sony_playstation;
 
+#[derive(Debug, Error)]
+pub enum UnsupportedSystem {
+    // Some systems have no name to show yet
+    #[error("There is no machine for {0:?}")]
+    NotEmulated(GameSystem),
+    #[error("{0} was left out of this build")]
+    #[cfg_attr(
+        all(feature = "chip8", feature = "nes", feature = "atari2600"),
+        allow(dead_code)
+    )]
+    LeftOut(GameSystem),
+    #[cfg(feature = "nes")]
+    #[error("The cartridge uses mapper {0}, which is not emulated yet")]
+    NesMapper(u16),
+    #[cfg(feature = "nes")]
+    #[error(transparent)]
+    NesCartridge(#[from] CartridgeError),
+}
+
+// Builds with no systems at all never get past the match
+#[cfg_attr(
+    not(any(feature = "chip8", feature = "nes", feature = "atari2600")),
+    allow(unused_variables, unreachable_code)
+)]
 pub fn construct_machine<R: RenderingBackend>(
     game_system: GameSystem,
     rom_manager: Arc<RomManager>,
     user_specified_roms: Vec<RomId>,
-    rendering_state: &mut <R as RenderingBackend>::RuntimeState,
-) -> Machine<R>
+) -> Result<Machine<R>, UnsupportedSystem>
 where
     Chip8Display: DisplayComponent<R>,
 {
-    match game_system {
-        GameSystem::Nintendo(NintendoSystem::GameBoy) => todo!(),
-        GameSystem::Nintendo(NintendoSystem::GameBoyColor) => todo!(),
-        GameSystem::Nintendo(NintendoSystem::GameBoyAdvance) => todo!(),
-        GameSystem::Nintendo(NintendoSystem::SuperNintendoEntertainmentSystem) => todo!(),
-        GameSystem::Nintendo(NintendoSystem::NintendoEntertainmentSystem) => todo!(),
-        GameSystem::Nintendo(NintendoSystem::Nintendo64) => todo!(),
-        GameSystem::Sega(SegaSystem::GameGear) => todo!(),
-        GameSystem::Sega(SegaSystem::Genesis) => todo!(),
-        GameSystem::Sega(SegaSystem::MasterSystem) => todo!(),
-        GameSystem::Sony(SonySystem::Playstation) => todo!(),
+    let roms = user_specified_roms.clone();
+
+    let mut machine: Machine<R> = match game_system {
+        #[cfg(feature = "nes")]
+        GameSystem::Nintendo(NintendoSystem::NintendoEntertainmentSystem) => {
+            nintendo_nes::<R>(rom_manager, user_specified_roms)?
+        }
+        #[cfg(feature = "atari2600")]
         GameSystem::Atari(AtariSystem::Atari2600) => {
-            atari_atari2600::<R>(rom_manager, user_specified_roms, rendering_state)
+            atari_atari2600::<R>(rom_manager, user_specified_roms)
         }
-        GameSystem::Other(OtherSystem::Chip8) => {
-            other_chip8::<R>(rom_manager, user_specified_roms, rendering_state)
+        #[cfg(feature = "chip8")]
+        GameSystem::Other(OtherSystem::Chip8) => other_chip8::<R>(rom_manager, user_specified_roms),
+        #[cfg(feature = "chip8")]
+        GameSystem::Other(OtherSystem::SuperChip8) => {
+            other_superchip8::<R>(rom_manager, user_specified_roms)
         }
-        GameSystem::Other(OtherSystem::SuperChip8) => todo!(),
-        _ => {
-            unimplemented!("This system is unlikely to ever be supported by this emulator")
+        #[cfg(not(feature = "nes"))]
+        GameSystem::Nintendo(NintendoSystem::NintendoEntertainmentSystem) => {
+            return Err(UnsupportedSystem::LeftOut(game_system));
         }
+        #[cfg(not(feature = "atari2600"))]
+        GameSystem::Atari(AtariSystem::Atari2600) => {
+            return Err(UnsupportedSystem::LeftOut(game_system));
+        }
+        #[cfg(not(feature = "chip8"))]
+        GameSystem::Other(OtherSystem::Chip8 | OtherSystem::SuperChip8) => {
+            return Err(UnsupportedSystem::LeftOut(game_system));
+        }
+        _ => return Err(UnsupportedSystem::NotEmulated(game_system)),
+    };
+
+    machine.fingerprint.system = game_system;
+    machine.fingerprint.roms = roms;
+
+    Ok(machine)
+}
+
+#[cfg(all(test, feature = "nes"))]
+mod tests {
+    use super::*;
+    use crate::runtime::SoftwareRendering;
+
+    #[test]
+    fn unknown_mappers_fail_to_build() {
+        let path = std::env::temp_dir().join(format!("multiemu_mapper_{}.nes", std::process::id()));
+        // Mapper 4, only the header is read before the board is picked
+        let mut rom = vec![b'N', b'E', b'S', 0x1a, 1, 1, 0x40, 0x00];
+        rom.resize(16, 0);
+        std::fs::write(&path, rom).unwrap();
+
+        let rom_id = RomId::new([0; 20]);
+        let mut rom_manager = RomManager::default();
+        rom_manager.rom_paths.insert(rom_id, path.clone());
+
+        let result = construct_machine::<SoftwareRendering>(
+            GameSystem::Nintendo(NintendoSystem::NintendoEntertainmentSystem),
+            Arc::new(rom_manager),
+            vec![rom_id],
+        );
+        assert!(matches!(result, Err(UnsupportedSystem::NesMapper(4))));
+
+        std::fs::remove_file(path).unwrap();
     }
 }
@@ -0,0 +1,175 @@
+use super::UnsupportedSystem;
+use crate::machine::Machine;
+use crate::rom::RomId;
+use crate::rom::RomManager;
+use crate::runtime::RenderingBackend;
+use crate::{
+    component::{
+        definitions::{
+            misc::{
+                mirror_memory::{MirrorMemory, MirrorMemoryConfig, MirrorMemoryOverflowMode},
+                plain_memory::{PlainMemory, PlainMemoryConfig, PlainMemoryInitialContents},
+                processor::m6502::{M6502Config, M6502Kind, M6502},
+            },
+            nes::cartridge::{mmc1::Mmc1, nrom::Nrom, uxrom::Uxrom, NesCartridgeConfig, NesHeader},
+        },
+        interrupt::InterruptLine,
+        memory::OpenBusBehavior,
+        processor::dynarec::DynarecConfig,
+    },
+    env::DYNAREC_CACHE_DIRECTORY,
+    task::processor::ProcessorTaskConfig,
+};
+use num::rational::Ratio;
+use std::sync::Arc;
+
+pub fn nintendo_nes<R: RenderingBackend>(
+    rom_manager: Arc<RomManager>,
+    user_specified_roms: Vec<RomId>,
+) -> Result<Machine<R>, UnsupportedSystem> {
+    // The board is picked from the header, so only that much is read here and the mapper loads the rest
+    let header = NesHeader::load(&rom_manager, user_specified_roms[0])?;
+    let cartridge_config = NesCartridgeConfig {
+        rom_id: user_specified_roms[0],
+    };
+
+    let builder = Machine::build(rom_manager)
+        // Unmapped reads see whatever the last access left on the data bus
+        .open_bus(OpenBusBehavior::LastValue)
+        .component::<M6502>(
+            "processor",
+            M6502Config {
+                // The NTSC master clock divided by 12
+                frequency: Ratio::new(21477272, 12),
+                kind: M6502Kind::R2A03,
+                irq: InterruptLine::default(),
+                nmi: InterruptLine::default(),
+                dynarec: Some(DynarecConfig {
+                    block_cache: Some(
+                        DYNAREC_CACHE_DIRECTORY.join(cartridge_config.rom_id.to_string()),
+                    ),
+                }),
+            },
+        )
+        .insert_processor_schedule(ProcessorTaskConfig {
+            initial_program_pointer: 0x0000,
+        })
+        .finalize_component()
+        .component::<PlainMemory>(
+            "work_memory",
+            PlainMemoryConfig {
+                readable: true,
+                writable: true,
+                max_word_size: 2,
+                read_cycle_penalty_calculator: |_, _| 0,
+                write_cycle_penalty_calculator: |_, _| 0,
+                assigned_range: 0x0000..0x0800,
+                initial_contents: PlainMemoryInitialContents::Value { value: 0 },
+            },
+        )
+        .with_memory_map()
+        .with_snapshot()
+        .finalize_component()
+        // Only 11 address lines go to the RAM, so it repeats up to the PPU registers
+        .component::<MirrorMemory>(
+            "work_memory_mirror",
+            MirrorMemoryConfig {
+                readable: true,
+                writable: true,
+                assigned_range: 0x0800..0x2000,
+                read_cycle_penalty_calculator: |_, _| 0,
+                write_cycle_penalty_calculator: |_, _| 0,
+                target: 0x0000..0x0800,
+                overflow_mode: MirrorMemoryOverflowMode::Wrap(3),
+            },
+        )
+        .with_memory_map()
+        .finalize_component();
+
+    let builder = match header.mapper {
+        0 => builder
+            .component::<Nrom>("cartridge", cartridge_config)
+            .with_memory_map()
+            .with_snapshot()
+            .finalize_component(),
+        1 => builder
+            .component::<Mmc1>("cartridge", cartridge_config)
+            .with_memory_map()
+            .with_snapshot()
+            .finalize_component(),
+        2 => builder
+            .component::<Uxrom>("cartridge", cartridge_config)
+            .with_memory_map()
+            .with_snapshot()
+            .finalize_component(),
+        mapper => return Err(UnsupportedSystem::NesMapper(mapper)),
+    };
+
+    Ok(builder.finalize_machine())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::machine::executor::{single::SingleThreadedExecutor, Executor};
+    use crate::runtime::SoftwareRendering;
+    use std::time::Duration;
+
+    #[test]
+    fn boots_nrom() {
+        let path = std::env::temp_dir().join(format!("multiemu_nrom_{}.nes", std::process::id()));
+        // 16KiB of PRG, 8KiB of CHR, mapper 0
+        let mut rom = vec![b'N', b'E', b'S', 0x1a, 1, 1, 0x00, 0x00];
+        rom.resize(16, 0);
+        let mut prg = vec![0; 0x4000];
+        // LDA #$42, STA $0200, then INC $10 and JMP back to it forever
+        let program = [0xa9, 0x42, 0x8d, 0x00, 0x02, 0xe6, 0x10, 0x4c, 0x05, 0x80];
+        prg[..program.len()].copy_from_slice(&program);
+        // The 16KiB shows up twice, so the reset vector at the top lands back at the start
+        prg[0x3ffc..0x3ffe].copy_from_slice(&0x8000u16.to_le_bytes());
+        rom.extend(prg);
+        rom.resize(rom.len() + 0x2000, 0);
+        std::fs::write(&path, rom).unwrap();
+
+        let rom_id = RomId::new([0xfe; 20]);
+        let mut rom_manager = RomManager::default();
+        rom_manager.rom_paths.insert(rom_id, path.clone());
+
+        let mut machine =
+            nintendo_nes::<SoftwareRendering>(Arc::new(rom_manager), vec![rom_id]).unwrap();
+        let (_, processor) = machine.debug_targets[0].clone();
+        let memory_translation_table = machine.memory_translation_table.clone();
+        let mut executor = SingleThreadedExecutor::new(
+            std::mem::take(&mut machine.tasks),
+            memory_translation_table.clone(),
+        );
+
+        let mut counter = [0];
+        for _ in 0..3 {
+            executor.run_unthrottled(Duration::from_secs(1) / 60);
+
+            let debug_state = processor.debug_state().lock().unwrap();
+            assert!(!debug_state.is_stopped());
+            assert!((0x8005..0x800a).contains(&debug_state.program_pointer));
+            drop(debug_state);
+
+            // Still counting
+            let last_counter = counter;
+            memory_translation_table.read(0x0010, &mut counter).unwrap();
+            assert_ne!(counter, last_counter);
+        }
+
+        let mut marker = [0];
+        memory_translation_table.read(0x0200, &mut marker).unwrap();
+        assert_eq!(marker, [0x42]);
+        // Through the mirror too
+        memory_translation_table.read(0x0a00, &mut marker).unwrap();
+        assert_eq!(marker, [0x42]);
+
+        drop(processor);
+        machine.shutdown(executor);
+        std::fs::remove_file(path).unwrap();
+        // Whatever the dynarec remembered about the ROM
+        let _ = std::fs::remove_file(DYNAREC_CACHE_DIRECTORY.join(rom_id.to_string()));
+    }
+}
@@ -1,10 +1,16 @@
+#[cfg(feature = "frontend")]
+use crate::component::definitions::chip8::{
+    audio::SOUND_TIMER_TOPIC,
+    timer::{Chip8TimerHandle, DELAY_TIMER_TOPIC},
+};
+#[cfg(feature = "frontend")]
+use crate::machine::QueryableComponents;
 use crate::rom::RomId;
 use crate::rom::RomManager;
 use crate::{component::definitions::chip8::display::Chip8DisplayConfig, machine::Machine};
 use crate::{
     component::definitions::chip8::processor::Chip8Processor,
     component::definitions::chip8::processor::Chip8ProcessorConfig, task::generic::GenericTask,
-    task::processor::ProcessorTask,
 };
 use crate::{
     component::definitions::{chip8::CHIP8_FONT, misc::plain_memory::PlainMemoryInitialContents},
@@ -26,12 +32,11 @@ use std::sync::Arc;
 pub fn other_chip8<R: RenderingBackend>(
     rom_manager: Arc<RomManager>,
     user_specified_roms: Vec<RomId>,
-    rendering_state: &mut <R as RenderingBackend>::RuntimeState,
 ) -> Machine<R>
 where
     Chip8Display: DisplayComponent<R>,
 {
-    Machine::build(rom_manager, rendering_state)
+    let builder = Machine::build(rom_manager)
         .component::<Chip8Processor>(
             "processor",
             Chip8ProcessorConfig {
@@ -39,7 +44,7 @@ where
                 kind: Chip8Kind::Chip8,
             },
         )
-        .insert_schedule::<ProcessorTask<_>>(ProcessorTaskConfig {
+        .insert_processor_schedule(ProcessorTaskConfig {
             initial_program_pointer: 0x200,
         })
         .with_gamepad()
@@ -60,6 +65,7 @@ where
             },
         )
         .with_memory_map()
+        .with_snapshot()
         .finalize_component()
         .component::<PlainMemory>(
             "work_memory",
@@ -77,21 +83,64 @@ where
             },
         )
         .with_memory_map()
+        .with_snapshot()
         .finalize_component()
         .component::<Chip8Display>(
             "display",
             Chip8DisplayConfig {
                 kind: Chip8Kind::Chip8,
+                quirk_sprite_wrapping: false,
             },
         )
         .with_displayable()
         .insert_schedule_default::<GenericTask<_>>()
+        .with_snapshot()
         .finalize_component()
         .component_default::<Chip8Timer>("timer")
         .insert_schedule_default::<GenericTask<_>>()
+        .with_snapshot()
         .finalize_component()
         .component_default::<Chip8Audio>("audio")
+        .with_audio()
         .insert_schedule_default::<GenericTask<_>>()
-        .finalize_component()
-        .finalize_machine()
+        .with_snapshot()
+        .finalize_component();
+
+    #[cfg(feature = "frontend")]
+    let builder = builder.gui_page("Chip8", chip8_gui_page);
+
+    builder.finalize_machine()
+}
+
+#[cfg(feature = "frontend")]
+pub(super) fn chip8_gui_page(ui: &mut egui::Ui, components: &QueryableComponents) {
+    if let Some(processor) = components.query_component::<Chip8Processor>("processor") {
+        let processor = processor.lock().unwrap();
+
+        ui.label(format!(
+            "Execution state: {:?}",
+            processor.execution_state()
+        ));
+        ui.label(format!("{:x?}", processor.registers()));
+    }
+
+    if let Some(timer) = components.event_bus().subscribe(DELAY_TIMER_TOPIC) {
+        timer_editor(ui, "Delay timer", &timer);
+    }
+
+    if let Some(sound_timer) = components.event_bus().subscribe(SOUND_TIMER_TOPIC) {
+        timer_editor(ui, "Sound timer", &sound_timer);
+    }
+}
+
+#[cfg(feature = "frontend")]
+fn timer_editor(ui: &mut egui::Ui, name: &str, timer: &Chip8TimerHandle) {
+    ui.horizontal(|ui| {
+        ui.label(name);
+
+        let mut value = timer.get();
+        if ui.add(egui::DragValue::new(&mut value)).changed() {
+            timer.set(value);
+        }
+    });
 }
@@ -1 +1,140 @@
+#[cfg(feature = "frontend")]
+use super::other_chip8::chip8_gui_page;
+use crate::machine::Machine;
+use crate::rom::RomId;
+use crate::rom::RomManager;
+use crate::{
+    component::definitions::chip8::processor::Chip8Processor,
+    component::definitions::chip8::processor::Chip8ProcessorConfig, task::generic::GenericTask,
+};
+use crate::{
+    component::definitions::{
+        chip8::{CHIP8_FONT, SUPERCHIP8_FONT, SUPERCHIP8_FONT_ADDRESS},
+        misc::plain_memory::PlainMemoryInitialContents,
+    },
+    runtime::RenderingBackend,
+};
+use crate::{
+    component::{
+        definitions::{
+            chip8::{
+                audio::Chip8Audio,
+                display::{Chip8Display, Chip8DisplayConfig},
+                rpl::Chip8RplFlags,
+                timer::Chip8Timer,
+                Chip8Kind,
+            },
+            misc::plain_memory::{PlainMemory, PlainMemoryConfig},
+        },
+        display::DisplayComponent,
+    },
+    task::processor::ProcessorTaskConfig,
+};
+use num::rational::Ratio;
+use std::sync::Arc;
 
+pub fn other_superchip8<R: RenderingBackend>(
+    rom_manager: Arc<RomManager>,
+    user_specified_roms: Vec<RomId>,
+) -> Machine<R>
+where
+    Chip8Display: DisplayComponent<R>,
+{
+    let builder = Machine::build(rom_manager)
+        .component::<Chip8Processor>(
+            "processor",
+            Chip8ProcessorConfig {
+                // The HP48 is a lot faster than the COSMAC VIP and programs were written for it
+                frequency: Ratio::new(1000, 1),
+                kind: Chip8Kind::SuperChip8,
+            },
+        )
+        .insert_processor_schedule(ProcessorTaskConfig {
+            initial_program_pointer: 0x200,
+        })
+        .with_gamepad()
+        .finalize_component()
+        .component::<PlainMemory>(
+            "system_memory",
+            PlainMemoryConfig {
+                readable: true,
+                writable: true,
+                max_word_size: 2,
+                read_cycle_penalty_calculator: |_, _| 0,
+                write_cycle_penalty_calculator: |_, _| 0,
+                assigned_range: 0x000..SUPERCHIP8_FONT_ADDRESS,
+                initial_contents: PlainMemoryInitialContents::Array {
+                    value: bytemuck::cast_slice(&CHIP8_FONT),
+                    offset: 0x000,
+                },
+            },
+        )
+        .with_memory_map()
+        .with_snapshot()
+        .finalize_component()
+        .component::<PlainMemory>(
+            "large_font_memory",
+            PlainMemoryConfig {
+                readable: true,
+                writable: true,
+                max_word_size: 2,
+                read_cycle_penalty_calculator: |_, _| 0,
+                write_cycle_penalty_calculator: |_, _| 0,
+                assigned_range: SUPERCHIP8_FONT_ADDRESS..0x200,
+                initial_contents: PlainMemoryInitialContents::Array {
+                    value: bytemuck::cast_slice(&SUPERCHIP8_FONT),
+                    offset: SUPERCHIP8_FONT_ADDRESS,
+                },
+            },
+        )
+        .with_memory_map()
+        .with_snapshot()
+        .finalize_component()
+        // The HP48 gives programs the same 4KiB as the original
+        .component::<PlainMemory>(
+            "work_memory",
+            PlainMemoryConfig {
+                readable: true,
+                writable: true,
+                max_word_size: 2,
+                read_cycle_penalty_calculator: |_, _| 0,
+                write_cycle_penalty_calculator: |_, _| 0,
+                assigned_range: 0x200..0x1000,
+                initial_contents: PlainMemoryInitialContents::Rom {
+                    rom_id: user_specified_roms[0],
+                    offset: 0x200,
+                },
+            },
+        )
+        .with_memory_map()
+        .with_snapshot()
+        .finalize_component()
+        .component::<Chip8Display>(
+            "display",
+            Chip8DisplayConfig {
+                kind: Chip8Kind::SuperChip8,
+                quirk_sprite_wrapping: false,
+            },
+        )
+        .with_displayable()
+        .insert_schedule_default::<GenericTask<_>>()
+        .with_snapshot()
+        .finalize_component()
+        .component_default::<Chip8Timer>("timer")
+        .insert_schedule_default::<GenericTask<_>>()
+        .with_snapshot()
+        .finalize_component()
+        .component_default::<Chip8Audio>("audio")
+        .with_audio()
+        .insert_schedule_default::<GenericTask<_>>()
+        .with_snapshot()
+        .finalize_component()
+        .component_default::<Chip8RplFlags>("rpl_flags")
+        .with_snapshot()
+        .finalize_component();
+
+    #[cfg(feature = "frontend")]
+    let builder = builder.gui_page("SuperChip8", chip8_gui_page);
+
+    builder.finalize_machine()
+}
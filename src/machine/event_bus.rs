@@ -0,0 +1,44 @@
+use std::{
+    any::{Any, TypeId},
+    collections::HashMap,
+};
+
+/// Typed handles components publish for each other, so they can talk without locking one another
+///
+/// A handle is looked up by its type and the topic it was published under. Handles are expected to be cheap to clone
+/// and to share their state between clones, like [crate::component::interrupt::InterruptLine]
+#[derive(Default)]
+pub struct EventBus(HashMap<(TypeId, &'static str), Box<dyn Any + Send + Sync>>);
+
+impl EventBus {
+    /// Publishing the same type under the same topic again replaces the old handle
+    pub fn publish<H: Clone + Send + Sync + 'static>(&mut self, topic: &'static str, handle: H) {
+        self.0.insert((TypeId::of::<H>(), topic), Box::new(handle));
+    }
+
+    pub fn subscribe<H: Clone + Send + Sync + 'static>(&self, topic: &'static str) -> Option<H> {
+        self.0
+            .get(&(TypeId::of::<H>(), topic))
+            .and_then(|handle| handle.downcast_ref::<H>())
+            .cloned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::component::interrupt::InterruptLine;
+
+    #[test]
+    fn subscribers_share_published_handle() {
+        let mut event_bus = EventBus::default();
+        event_bus.publish("irq", InterruptLine::default());
+
+        let line = event_bus.subscribe::<InterruptLine>("irq").unwrap();
+        event_bus.subscribe::<InterruptLine>("irq").unwrap().raise();
+        assert!(line.is_raised());
+
+        assert!(event_bus.subscribe::<InterruptLine>("nmi").is_none());
+        assert!(event_bus.subscribe::<u8>("irq").is_none());
+    }
+}
@@ -1,13 +1,96 @@
-use crate::{component::memory::MemoryTranslationTable, task::Task};
+#[cfg(feature = "frontend")]
+use super::watchdog::ExecutorHeartbeat;
+use crate::{
+    component::memory::MemoryTranslationTable, rewind::RewindBuffer,
+    snapshot::SnapshotTaskInformation, task::Task,
+};
 use num::rational::Ratio;
-use std::{sync::Arc, time::Duration};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
 
 pub mod single;
 
+/// How the executor resolved the machine timings and what it did with them recently
+#[derive(Debug, Clone)]
+pub struct ScheduleReport {
+    /// Real time a single executor tick stands for
+    pub tick_real_time: Duration,
+    /// Executor ticks until every task lines up again
+    pub rollover_tick: u32,
+    pub tasks: Vec<TaskScheduleReport>,
+    /// Oldest first, covering about the last second
+    pub batches: Vec<BatchRecord>,
+}
+
+#[derive(Debug, Clone)]
+pub struct TaskScheduleReport {
+    pub name: &'static str,
+    /// What the component asked for
+    pub tick_rate: Ratio<u32>,
+    /// Executor ticks between two ticks of this task
+    pub period: u32,
+}
+
+#[derive(Debug, Clone)]
+pub struct BatchRecord {
+    /// Index into [ScheduleReport::tasks]
+    pub task: usize,
+    /// How long before the report was made the batch started
+    pub age: Duration,
+    pub batch_size: u32,
+    pub real_time: Duration,
+}
+
 pub trait Executor {
     fn new(
-        tasks: Vec<(Ratio<u32>, Box<dyn Task>)>,
+        tasks: Vec<(&'static str, Ratio<u32>, Box<dyn Task>)>,
         memory_translation_table: Arc<MemoryTranslationTable>,
     ) -> Self;
+    #[cfg(feature = "frontend")]
     fn run(&mut self, period: Duration);
+    /// Runs exactly this much machine time as fast as the host allows, ignoring real time
+    #[cfg(any(feature = "frontend", test))]
+    fn run_unthrottled(&mut self, machine_time: Duration);
+    /// If disabled the executor drops time it fell behind on instead of running faster to make it up
+    #[cfg(feature = "frontend")]
+    fn set_catch_up(&mut self, catch_up: bool);
+    /// Multiplier on real time, so two runs the machine twice as fast
+    #[cfg(feature = "frontend")]
+    fn set_speed(&mut self, speed: Ratio<u32>);
+    /// How far the machine is behind real time
+    #[cfg(feature = "frontend")]
+    fn lag(&self) -> Duration;
+    /// Only called between runs
+    fn save_tasks(&mut self) -> SnapshotTaskInformation;
+    fn load_tasks(&mut self, task_info: SnapshotTaskInformation);
+    fn schedule_report(&self) -> ScheduleReport;
+    /// Rewind points captured into this buffer get the task state attached
+    fn set_rewind_buffer(&mut self, rewind_buffer: Arc<Mutex<RewindBuffer>>);
+    /// Returns false if there is nothing left to rewind to
+    fn rewind(&mut self) -> bool;
+    /// Runs until the next batch is done, which for a processor is a single instruction
+    #[cfg(feature = "frontend")]
+    fn step_instruction(&mut self);
+    /// Runs until the named task has ticked exactly once, for advancing a display a frame at a time. Returns false if
+    /// there is no task of that name
+    fn run_single_frame(&mut self, display_task: &str) -> bool;
+    /// Goes back to where the last batch started by replaying from the rewind point before it. Returns false if there
+    /// is no rewind point to replay from
+    #[cfg(feature = "frontend")]
+    fn step_back_instruction(&mut self) -> bool;
+    /// For watching the executor from another thread
+    #[cfg(feature = "frontend")]
+    fn heartbeat(&self) -> Arc<ExecutorHeartbeat>;
+    /// Adds a task to the running machine, for components that come and go like peripherals. A task of the same name
+    /// is replaced. Only called between runs
+    #[cfg(any(feature = "frontend", test))]
+    fn insert_task(&mut self, name: &'static str, tick_rate: Ratio<u32>, task: Box<dyn Task>);
+    /// Returns the task so its component can be kept around, None if there is no task of that name. Only called
+    /// between runs
+    #[cfg(any(feature = "frontend", test))]
+    fn remove_task(&mut self, name: &str) -> Option<Box<dyn Task>>;
+    /// Tasks whose components changed their tick rate since the last call with the rate they run at now, so the
+    /// runtime can follow along with whatever depends on the machine timing
+    #[cfg(any(feature = "frontend", test))]
+    fn take_tick_rate_changes(&mut self) -> Vec<(&'static str, Ratio<u32>)>;
 }
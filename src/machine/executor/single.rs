@@ -1,23 +1,65 @@
 use super::Executor;
-use crate::{component::memory::MemoryTranslationTable, task::Task};
+use super::{BatchRecord, ScheduleReport, TaskScheduleReport};
+use crate::{
+    component::memory::MemoryTranslationTable,
+    machine::watchdog::ExecutorHeartbeat,
+    rewind::{RewindBuffer, RewindTaskState},
+    snapshot::SnapshotTaskInformation,
+    task::Task,
+};
 use itertools::Itertools;
 use num::{integer::lcm, ToPrimitive};
 use num::{rational::Ratio, Integer};
 use std::{
-    sync::Arc,
+    collections::VecDeque,
+    sync::{Arc, Mutex},
     time::{Duration, Instant},
 };
 
+/// How far back batch timings are kept for [ScheduleReport]
+const SCHEDULE_HISTORY_WINDOW: Duration = Duration::from_secs(1);
+/// Upper bound on kept batch timings so machines with tiny batches don't grow this without limit
+const SCHEDULE_HISTORY_LIMIT: usize = 4096;
+
 pub struct SingleThreadedExecutor {
     tasks: Vec<(u32, Box<dyn Task>)>,
+    /// Names and requested tick rates of the tasks, for reporting
+    task_descriptions: Vec<(&'static str, Ratio<u32>)>,
+    /// Tick rates that changed while running and the runtime wasn't told about yet
+    tick_rate_changes: Vec<(&'static str, Ratio<u32>)>,
+    /// Start, task index, batch size and real time taken of recent batches
+    batch_history: VecDeque<(Instant, usize, u32, Duration)>,
     memory_translation_table: Arc<MemoryTranslationTable>,
     timestamp: Instant,
     current_tick: u32,
+    /// Like current_tick but never rolls over, for ordering rewind points
+    elapsed_ticks: u64,
     rollover_tick: u32,
     tick_real_time: Ratio<u32>,
+    #[cfg(feature = "frontend")]
+    catch_up: bool,
+    speed: Ratio<u32>,
+    rewind_buffer: Option<Arc<Mutex<RewindBuffer>>>,
+    heartbeat: Arc<ExecutorHeartbeat>,
 }
 
 impl SingleThreadedExecutor {
+    fn simulated_time(&self) -> Duration {
+        Duration::from_secs_f32(self.current_tick as f32 * self.tick_real_time.to_f32().unwrap())
+    }
+
+    /// Real time scaled by the speed, which is what the machine has to keep up with
+    #[cfg(feature = "frontend")]
+    fn scaled_time(&self, real_time: Duration) -> Duration {
+        real_time.mul_f32(self.speed.to_f32().unwrap())
+    }
+
+    /// Moves the timestamp so the current tick lines up with now, forgetting any lag
+    fn resynchronize(&mut self, now: Instant) {
+        let real_time = self.simulated_time().div_f32(self.speed.to_f32().unwrap());
+        self.timestamp = now.checked_sub(real_time).unwrap_or(now);
+    }
+
     fn increment_tick(&mut self, amount: u32) {
         let new_tick = (self.current_tick + amount) % self.rollover_tick;
 
@@ -26,16 +68,243 @@ impl SingleThreadedExecutor {
         }
 
         self.current_tick = new_tick;
+        self.elapsed_ticks += amount as u64;
+        // Only now the tasks of the ticks are done, so replaying from the point won't run them again
+        self.complete_rewind_capture();
+    }
+
+    /// Attaches the task state to a point the rewind task captured
+    fn complete_rewind_capture(&mut self) {
+        if let Some(rewind_buffer) = self.rewind_buffer.clone() {
+            let mut rewind_buffer = rewind_buffer.lock().unwrap();
+
+            if rewind_buffer.awaiting_task_info() {
+                rewind_buffer.complete_capture(RewindTaskState {
+                    elapsed_ticks: self.elapsed_ticks,
+                    task_info: self.save_tasks(),
+                });
+            }
+        }
+    }
+
+    fn load_rewind_state(&mut self, task_state: RewindTaskState) {
+        self.load_tasks(task_state.task_info);
+        self.elapsed_ticks = task_state.elapsed_ticks;
+    }
+
+    /// Ticks until the next tick a task runs at, which is a instruction boundary
+    #[cfg(feature = "frontend")]
+    fn ticks_until_boundary(&self) -> Option<u64> {
+        self.tasks
+            .iter()
+            .map(|(tick_rate, _)| {
+                ((*tick_rate - self.current_tick % *tick_rate) % *tick_rate) as u64
+            })
+            .min()
+    }
+
+    /// Ticks since the last tick before this one a task ran at
+    #[cfg(feature = "frontend")]
+    fn ticks_since_boundary(&self) -> Option<u64> {
+        self.tasks
+            .iter()
+            .map(|(tick_rate, _)| ((self.current_tick + *tick_rate - 1) % *tick_rate + 1) as u64)
+            .min()
+    }
+
+    #[cfg(feature = "frontend")]
+    fn set_breakpoints_enabled(&mut self, enabled: bool) {
+        for (_, task) in &mut self.tasks {
+            task.set_breakpoints_enabled(enabled);
+        }
+    }
+
+    /// Works out the tick plan again after the tasks changed
+    ///
+    /// The length of a tick depends on every task, so the machine continues from the start of the new plan. Rewind
+    /// points and batch timings from before refer to the old plan and are dropped
+    fn reschedule(&mut self) {
+        self.heartbeat.set_task_names(
+            self.task_descriptions
+                .iter()
+                .map(|(name, _)| *name)
+                .collect(),
+        );
+        self.batch_history.clear();
+        if let Some(rewind_buffer) = &self.rewind_buffer {
+            rewind_buffer.lock().unwrap().clear();
+        }
+        self.current_tick = 0;
+
+        if self.task_descriptions.is_empty() {
+            self.rollover_tick = 1;
+            return;
+        }
+
+        let (rollover_tick, task_tick_rates, tick_real_time) = find_component_timings(
+            &self
+                .task_descriptions
+                .iter()
+                .map(|(_, ratio)| *ratio)
+                .collect::<Vec<_>>(),
+        );
+
+        for ((period, _), task_tick_rate) in self.tasks.iter_mut().zip(task_tick_rates) {
+            *period = task_tick_rate;
+        }
+
+        // Kept in step with the machine time so it still orders whatever gets captured next
+        self.elapsed_ticks = (self.elapsed_ticks as u128
+            * *self.tick_real_time.numer() as u128
+            * *tick_real_time.denom() as u128
+            / (*self.tick_real_time.denom() as u128 * *tick_real_time.numer() as u128))
+            as u64;
+        self.rollover_tick = rollover_tick;
+        self.tick_real_time = tick_real_time;
+
+        tracing::info!(
+            "A tick on this machine is now a real world {:?}",
+            Duration::from_secs_f32(tick_real_time.to_f32().unwrap())
+        );
+
+        self.resynchronize(Instant::now());
+    }
+
+    /// Plans the machine timing again if any component asked for a different tick rate since the last check
+    fn refresh_tick_rates(&mut self) {
+        let mut changed = false;
+
+        for ((name, tick_rate), (_, task)) in self.task_descriptions.iter_mut().zip(&self.tasks) {
+            // A rate of zero would never run and can't be planned around, so it is ignored
+            let Some(new_tick_rate) = task.tick_rate().filter(|new_tick_rate| {
+                new_tick_rate != tick_rate && *new_tick_rate != Ratio::from_integer(0)
+            }) else {
+                continue;
+            };
+
+            tracing::info!(
+                "The task {} now runs at {:.2} Hz",
+                name,
+                new_tick_rate.to_f64().unwrap()
+            );
+            *tick_rate = new_tick_rate;
+            self.tick_rate_changes
+                .retain(|(changed_name, _)| changed_name != name);
+            self.tick_rate_changes.push((name, new_tick_rate));
+            changed = true;
+        }
+
+        if changed {
+            self.reschedule();
+        }
+    }
+
+    /// Runs as fast as possible until the executor is at elapsed_ticks
+    fn run_until(&mut self, elapsed_ticks: u64) {
+        while self.elapsed_ticks < elapsed_ticks {
+            let max_batch_size = (elapsed_ticks - self.elapsed_ticks)
+                .min((self.rollover_tick - self.current_tick) as u64)
+                as u32;
+
+            self.step(max_batch_size);
+        }
+    }
+
+    fn tick_task(&mut self, index: usize, batch_size: u32) {
+        let start = Instant::now();
+        self.heartbeat.begin_batch(index);
+        self.tasks[index]
+            .1
+            .tick(batch_size, &self.memory_translation_table);
+        self.heartbeat.end_batch();
+        let end = Instant::now();
+
+        while self.batch_history.len() >= SCHEDULE_HISTORY_LIMIT
+            || self
+                .batch_history
+                .front()
+                .is_some_and(|(batch_start, ..)| end - *batch_start > SCHEDULE_HISTORY_WINDOW)
+        {
+            self.batch_history.pop_front();
+        }
+
+        self.batch_history
+            .push_back((start, index, batch_size, end - start));
+    }
+
+    /// Runs whichever tasks are due, advancing at most max_batch_size ticks. Returns how many ticks it advanced
+    fn step(&mut self, max_batch_size: u32) -> u32 {
+        // Sort all the components by how many ticks until they run next
+        let to_run: Vec<_> = self
+            .tasks
+            .iter()
+            .enumerate()
+            .map(|(index, (tick_rate, _))| {
+                (
+                    *tick_rate,
+                    (*tick_rate - self.current_tick % *tick_rate) % *tick_rate,
+                    index,
+                )
+            })
+            .sorted_by_key(|(_, run_indication, _)| *run_indication)
+            .collect();
+
+        if to_run.is_empty() || to_run[0].1 != 0 {
+            self.increment_tick(1);
+            return 1;
+        }
+
+        // We can do a special case here projecting this to infinity
+        if to_run.len() == 1 {
+            let (tick_rate, _, index) = to_run[0];
+            let batch_size = max_batch_size.div_ceil(tick_rate);
+            self.tick_task(index, batch_size);
+            self.increment_tick(max_batch_size);
+            return max_batch_size;
+        }
+
+        // time slicing not possible
+        if to_run[1..]
+            .iter()
+            .any(|(_, run_indication, _)| *run_indication == 0)
+        {
+            for (_, _, index) in to_run
+                .into_iter()
+                .filter(|(_, run_indication, _)| *run_indication == 0)
+            {
+                self.tick_task(index, 1);
+            }
+
+            self.increment_tick(1);
+            return 1;
+        }
+
+        // We can batch normally here, up until the next component wants to run
+        let batch_size = to_run[1].1.min(max_batch_size);
+        let (tick_rate, _, index) = to_run[0];
+        // Rounded up as the batch starts on one of this components ticks
+        let normalized_batch_size = batch_size.div_ceil(tick_rate);
+        self.tick_task(index, normalized_batch_size);
+        self.increment_tick(batch_size);
+
+        batch_size
     }
 }
 
 impl Executor for SingleThreadedExecutor {
     fn new(
-        tasks: Vec<(Ratio<u32>, Box<dyn Task>)>,
+        tasks: Vec<(&'static str, Ratio<u32>, Box<dyn Task>)>,
         memory_translation_table: Arc<MemoryTranslationTable>,
     ) -> Self {
         let (rollover_tick, task_tick_rates, tick_real_time) =
-            find_component_timings(&tasks.iter().map(|(ratio, _)| *ratio).collect::<Vec<_>>());
+            find_component_timings(&tasks.iter().map(|(_, ratio, _)| *ratio).collect::<Vec<_>>());
+        let task_descriptions: Vec<_> = tasks
+            .iter()
+            .map(|(name, ratio, _)| (*name, *ratio))
+            .collect();
+        let heartbeat = Arc::new(ExecutorHeartbeat::new(
+            task_descriptions.iter().map(|(name, _)| *name).collect(),
+        ));
 
         tracing::info!(
             "A tick on this machine is a real world {:?}",
@@ -46,17 +315,28 @@ impl Executor for SingleThreadedExecutor {
             tasks: tasks
                 .into_iter()
                 .zip(task_tick_rates)
-                .map(|((_, task), tick_rate)| (tick_rate, task))
+                .map(|((_, _, task), tick_rate)| (tick_rate, task))
                 .collect(),
+            task_descriptions,
+            tick_rate_changes: Vec::new(),
+            batch_history: VecDeque::new(),
             memory_translation_table,
             timestamp: Instant::now(),
             current_tick: 0,
+            elapsed_ticks: 0,
             rollover_tick,
             tick_real_time,
+            #[cfg(feature = "frontend")]
+            catch_up: true,
+            speed: Ratio::from_integer(1),
+            rewind_buffer: None,
+            heartbeat,
         }
     }
 
+    #[cfg(feature = "frontend")]
     fn run(&mut self, period: Duration) {
+        self.refresh_tick_rates();
         let start_time = Instant::now();
 
         loop {
@@ -68,63 +348,238 @@ impl Executor for SingleThreadedExecutor {
             }
 
             // Exit if we are ahead of time
-            let simulated_time = Duration::from_secs_f32(
-                self.current_tick as f32 * self.tick_real_time.to_f32().unwrap(),
-            );
-            let real_time = now - self.timestamp;
+            let simulated_time = self.simulated_time();
+            let real_time = self.scaled_time(now - self.timestamp);
             if simulated_time > real_time {
                 break;
             }
 
-            let max_batch_size = ((runtime_assigned_time_left.as_secs_f32()
+            // Pretend we were never behind, so we never run faster than real time
+            if !self.catch_up && real_time - simulated_time > self.scaled_time(period) {
+                self.resynchronize(now);
+            }
+
+            let max_batch_size = ((self.scaled_time(runtime_assigned_time_left).as_secs_f32()
                 / self.tick_real_time.to_f32().unwrap())
             .floor() as u32)
                 .clamp(1, (self.rollover_tick - self.current_tick).max(1));
 
-            // Sort all the components
-            let mut to_run: Vec<_> = self
-                .tasks
-                .iter_mut()
-                .map(|(tick_rate, task)| (*tick_rate, self.current_tick % *tick_rate, task))
-                .sorted_by_key(|(_, run_indication, _)| *run_indication)
-                .collect();
+            self.step(max_batch_size);
+        }
+    }
 
-            if to_run.is_empty() || to_run[0].1 != 0 {
-                self.increment_tick(1);
-                continue;
-            }
+    #[cfg(any(feature = "frontend", test))]
+    fn run_unthrottled(&mut self, machine_time: Duration) {
+        self.refresh_tick_rates();
+        let mut ticks_left =
+            (machine_time.as_secs_f64() / self.tick_real_time.to_f64().unwrap()).round() as u64;
 
-            // We can do a special case here projecting this to infinity
-            if to_run.len() == 1 {
-                let (tick_rate, _, task) = &mut to_run[0];
-                let batch_size = max_batch_size / *tick_rate;
-                task.tick(batch_size, &self.memory_translation_table);
-                self.increment_tick(max_batch_size);
-                continue;
-            }
+        while ticks_left != 0 {
+            let max_batch_size = ticks_left
+                .min((self.rollover_tick - self.current_tick) as u64)
+                .max(1) as u32;
+
+            ticks_left = ticks_left.saturating_sub(self.step(max_batch_size) as u64);
+        }
+
+        // Whatever runs next shouldn't count the time spent here as lag
+        self.resynchronize(Instant::now());
+    }
+
+    #[cfg(feature = "frontend")]
+    fn set_catch_up(&mut self, catch_up: bool) {
+        self.catch_up = catch_up;
+    }
+
+    #[cfg(feature = "frontend")]
+    fn set_speed(&mut self, speed: Ratio<u32>) {
+        if speed == self.speed || speed == Ratio::from_integer(0) {
+            return;
+        }
 
-            // time slicing not possible
-            if to_run[1..]
+        // Lag built up at the old speed would otherwise be made up at the new one
+        self.speed = speed;
+        self.resynchronize(Instant::now());
+    }
+
+    #[cfg(feature = "frontend")]
+    fn lag(&self) -> Duration {
+        self.scaled_time(self.timestamp.elapsed())
+            .saturating_sub(self.simulated_time())
+    }
+
+    fn save_tasks(&mut self) -> SnapshotTaskInformation {
+        SnapshotTaskInformation {
+            current_cycle: self.current_tick,
+            tasks: self
+                .task_descriptions
                 .iter()
-                .any(|(_, run_indication, _)| *run_indication == 0)
-            {
-                for (_, _, task) in to_run
-                    .into_iter()
-                    .filter(|(_, run_indication, _)| *run_indication == 0)
-                {
-                    task.tick(1, &self.memory_translation_table);
-                }
-
-                self.increment_tick(1);
-                continue;
+                .zip(self.tasks.iter_mut())
+                .map(|((name, _), (_, task))| (name.to_string(), task.save()))
+                .collect(),
+        }
+    }
+
+    fn load_tasks(&mut self, mut task_info: SnapshotTaskInformation) {
+        for ((name, _), (_, task)) in self.task_descriptions.iter().zip(self.tasks.iter_mut()) {
+            match task_info.tasks.remove(*name) {
+                Some(state) => task.load(state),
+                // Tasks like rewind come and go with settings
+                None => tracing::warn!("Snapshot has no state for the task {}", name),
             }
+        }
+
+        self.current_tick = task_info.current_cycle % self.rollover_tick;
+        // The state may be from before the machine changed its timing
+        self.refresh_tick_rates();
+        // Don't try to catch up on the time spent not running this state
+        self.resynchronize(Instant::now());
+    }
+
+    fn set_rewind_buffer(&mut self, rewind_buffer: Arc<Mutex<RewindBuffer>>) {
+        self.rewind_buffer = Some(rewind_buffer);
+    }
+
+    fn rewind(&mut self) -> bool {
+        let Some(rewind_buffer) = self.rewind_buffer.clone() else {
+            return false;
+        };
+
+        let Some(task_state) = rewind_buffer.lock().unwrap().step_back() else {
+            return false;
+        };
+        self.load_rewind_state(task_state);
+
+        true
+    }
+
+    #[cfg(feature = "frontend")]
+    fn step_instruction(&mut self) {
+        self.refresh_tick_rates();
+        let Some(ticks_until_boundary) = self.ticks_until_boundary() else {
+            return;
+        };
+
+        self.set_breakpoints_enabled(false);
+        self.run_until(self.elapsed_ticks + ticks_until_boundary);
+        self.step(1);
+        self.set_breakpoints_enabled(true);
+
+        self.resynchronize(Instant::now());
+    }
+
+    fn run_single_frame(&mut self, display_task: &str) -> bool {
+        self.refresh_tick_rates();
+        let Some(index) = self
+            .task_descriptions
+            .iter()
+            .position(|(name, _)| *name == display_task)
+        else {
+            return false;
+        };
+        let period = self.tasks[index].0;
+
+        // Everything up to the frame, then the tick the display runs on
+        self.run_until(
+            self.elapsed_ticks + ((period - self.current_tick % period) % period) as u64,
+        );
+        self.step(1);
+
+        self.resynchronize(Instant::now());
+
+        true
+    }
+
+    #[cfg(feature = "frontend")]
+    fn step_back_instruction(&mut self) -> bool {
+        let (Some(rewind_buffer), Some(ticks_since_boundary)) =
+            (self.rewind_buffer.clone(), self.ticks_since_boundary())
+        else {
+            return false;
+        };
+        let Some(boundary) = self.elapsed_ticks.checked_sub(ticks_since_boundary) else {
+            return false;
+        };
+
+        let Some(task_state) = rewind_buffer.lock().unwrap().restore_before(boundary + 1) else {
+            return false;
+        };
+        self.load_rewind_state(task_state);
+        // Stopping at a breakpoint on the way would make the replay diverge
+        self.set_breakpoints_enabled(false);
+        self.run_until(boundary);
+        self.set_breakpoints_enabled(true);
+
+        self.resynchronize(Instant::now());
+
+        true
+    }
+
+    #[cfg(feature = "frontend")]
+    fn heartbeat(&self) -> Arc<ExecutorHeartbeat> {
+        self.heartbeat.clone()
+    }
+
+    #[cfg(any(feature = "frontend", test))]
+    fn insert_task(&mut self, name: &'static str, tick_rate: Ratio<u32>, task: Box<dyn Task>) {
+        self.remove_task(name);
+
+        tracing::info!("Adding the task {}", name);
+        self.task_descriptions.push((name, tick_rate));
+        // The period is filled in by the reschedule
+        self.tasks.push((1, task));
+        self.reschedule();
+    }
+
+    #[cfg(any(feature = "frontend", test))]
+    fn remove_task(&mut self, name: &str) -> Option<Box<dyn Task>> {
+        let index = self
+            .task_descriptions
+            .iter()
+            .position(|(task_name, _)| *task_name == name)?;
+
+        tracing::info!("Removing the task {}", name);
+        self.task_descriptions.remove(index);
+        let (_, task) = self.tasks.remove(index);
+        self.reschedule();
+
+        Some(task)
+    }
+
+    #[cfg(any(feature = "frontend", test))]
+    fn take_tick_rate_changes(&mut self) -> Vec<(&'static str, Ratio<u32>)> {
+        std::mem::take(&mut self.tick_rate_changes)
+    }
+
+    fn schedule_report(&self) -> ScheduleReport {
+        let now = Instant::now();
 
-            // We can batch normally here
-            let batch_size = (to_run[1].0 - to_run[1].1).min(max_batch_size);
-            let (tick_rate, _, task) = &mut to_run[0];
-            let normalized_batch_size = batch_size / *tick_rate;
-            task.tick(normalized_batch_size, &self.memory_translation_table);
-            self.increment_tick(batch_size);
+        ScheduleReport {
+            tick_real_time: Duration::from_secs_f64(
+                *self.tick_real_time.numer() as f64 / *self.tick_real_time.denom() as f64,
+            ),
+            rollover_tick: self.rollover_tick,
+            tasks: self
+                .task_descriptions
+                .iter()
+                .zip(&self.tasks)
+                .map(|((name, tick_rate), (period, _))| TaskScheduleReport {
+                    name,
+                    tick_rate: *tick_rate,
+                    period: *period,
+                })
+                .collect(),
+            batches: self
+                .batch_history
+                .iter()
+                .filter(|(start, ..)| now - *start <= SCHEDULE_HISTORY_WINDOW)
+                .map(|(start, task, batch_size, real_time)| BatchRecord {
+                    task: *task,
+                    age: now - *start,
+                    batch_size: *batch_size,
+                    real_time: *real_time,
+                })
+                .collect(),
         }
     }
 }
@@ -157,3 +612,183 @@ fn find_component_timings(ratios: &[Ratio<u32>]) -> (u32, Vec<u32>, Ratio<u32>)
         Ratio::new(common_multiple, common_denominator).recip(),
     )
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Counts its ticks
+    struct CountingTask(Arc<Mutex<u32>>);
+
+    impl Task for CountingTask {
+        fn tick(&mut self, batch_size: u32, _memory_translation_table: &MemoryTranslationTable) {
+            *self.0.lock().unwrap() += batch_size;
+        }
+
+        fn save(&mut self) -> rmpv::Value {
+            rmpv::Value::Nil
+        }
+
+        fn load(&mut self, _state: rmpv::Value) {}
+    }
+
+    /// Counts its ticks at a rate that can be changed from outside, like a display switching modes
+    struct VariableRateTask(Arc<Mutex<u32>>, Arc<Mutex<Ratio<u32>>>);
+
+    impl Task for VariableRateTask {
+        fn tick(&mut self, batch_size: u32, _memory_translation_table: &MemoryTranslationTable) {
+            *self.0.lock().unwrap() += batch_size;
+        }
+
+        fn save(&mut self) -> rmpv::Value {
+            rmpv::Value::Nil
+        }
+
+        fn load(&mut self, _state: rmpv::Value) {}
+
+        fn tick_rate(&self) -> Option<Ratio<u32>> {
+            Some(*self.1.lock().unwrap())
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "frontend")]
+    fn tasks_with_uneven_periods_run_at_their_rate() {
+        let fast_ticks = Arc::new(Mutex::new(0));
+        let slow_ticks = Arc::new(Mutex::new(0));
+        // Every other and every third tick, the two never line up in a batch
+        let mut executor = SingleThreadedExecutor::new(
+            vec![
+                (
+                    "fast",
+                    Ratio::from_integer(300),
+                    Box::new(CountingTask(fast_ticks.clone())),
+                ),
+                (
+                    "slow",
+                    Ratio::from_integer(200),
+                    Box::new(CountingTask(slow_ticks.clone())),
+                ),
+            ],
+            Arc::new(MemoryTranslationTable::default()),
+        );
+
+        // Catches up on the time spent sleeping and then stops as it is ahead
+        std::thread::sleep(Duration::from_millis(100));
+        executor.run(Duration::from_secs(1));
+
+        let fast_ticks = *fast_ticks.lock().unwrap();
+        let slow_ticks = *slow_ticks.lock().unwrap();
+        assert!(fast_ticks >= 30, "fast task ran {} ticks", fast_ticks);
+        assert!(slow_ticks >= 20, "slow task ran {} ticks", slow_ticks);
+        assert!(
+            (fast_ticks * 2).abs_diff(slow_ticks * 3) <= 6,
+            "fast task ran {} ticks to the slow tasks {}",
+            fast_ticks,
+            slow_ticks
+        );
+    }
+
+    #[test]
+    fn inserted_tasks_run_at_their_rate() {
+        let base_ticks = Arc::new(Mutex::new(0));
+        let peripheral_ticks = Arc::new(Mutex::new(0));
+        let mut executor = SingleThreadedExecutor::new(
+            vec![(
+                "base",
+                Ratio::from_integer(60),
+                Box::new(CountingTask(base_ticks.clone())),
+            )],
+            Arc::new(MemoryTranslationTable::default()),
+        );
+
+        executor.insert_task(
+            "peripheral",
+            Ratio::from_integer(120),
+            Box::new(CountingTask(peripheral_ticks.clone())),
+        );
+        executor.run_unthrottled(Duration::from_secs(1));
+        assert_eq!(*base_ticks.lock().unwrap(), 60);
+        assert_eq!(*peripheral_ticks.lock().unwrap(), 120);
+        assert_eq!(executor.schedule_report().tasks.len(), 2);
+
+        assert!(executor.remove_task("peripheral").is_some());
+        assert!(executor.remove_task("peripheral").is_none());
+        executor.run_unthrottled(Duration::from_secs(1));
+        assert_eq!(*base_ticks.lock().unwrap(), 120);
+        assert_eq!(*peripheral_ticks.lock().unwrap(), 120);
+    }
+
+    #[test]
+    fn single_frames_tick_the_display_once() {
+        let processor_ticks = Arc::new(Mutex::new(0));
+        let display_ticks = Arc::new(Mutex::new(0));
+        let mut executor = SingleThreadedExecutor::new(
+            vec![
+                (
+                    "processor",
+                    Ratio::from_integer(600),
+                    Box::new(CountingTask(processor_ticks.clone())),
+                ),
+                (
+                    "display",
+                    Ratio::from_integer(60),
+                    Box::new(CountingTask(display_ticks.clone())),
+                ),
+            ],
+            Arc::new(MemoryTranslationTable::default()),
+        );
+
+        for _ in 0..3 {
+            assert!(executor.run_single_frame("display"));
+        }
+        assert_eq!(*display_ticks.lock().unwrap(), 3);
+        // The first frame is right at the start, every one after is a whole frame of processor time later
+        assert_eq!(*processor_ticks.lock().unwrap(), 21);
+
+        assert!(!executor.run_single_frame("missing"));
+    }
+
+    #[test]
+    fn changed_tick_rates_are_planned_around() {
+        let processor_ticks = Arc::new(Mutex::new(0));
+        let display_ticks = Arc::new(Mutex::new(0));
+        let display_rate = Arc::new(Mutex::new(Ratio::from_integer(60)));
+        let mut executor = SingleThreadedExecutor::new(
+            vec![
+                (
+                    "processor",
+                    Ratio::from_integer(600),
+                    Box::new(CountingTask(processor_ticks.clone())),
+                ),
+                (
+                    "display",
+                    Ratio::from_integer(60),
+                    Box::new(VariableRateTask(
+                        display_ticks.clone(),
+                        display_rate.clone(),
+                    )),
+                ),
+            ],
+            Arc::new(MemoryTranslationTable::default()),
+        );
+
+        executor.run_unthrottled(Duration::from_secs(1));
+        assert_eq!(*display_ticks.lock().unwrap(), 60);
+        assert!(executor.take_tick_rate_changes().is_empty());
+
+        *display_rate.lock().unwrap() = Ratio::new(100, 2);
+        executor.run_unthrottled(Duration::from_secs(1));
+        assert_eq!(*display_ticks.lock().unwrap(), 110);
+        assert_eq!(*processor_ticks.lock().unwrap(), 1200);
+        assert_eq!(
+            executor.take_tick_rate_changes(),
+            [("display", Ratio::from_integer(50))]
+        );
+        assert!(executor.take_tick_rate_changes().is_empty());
+        assert_eq!(
+            executor.schedule_report().tasks[1].tick_rate,
+            Ratio::from_integer(50)
+        );
+    }
+}
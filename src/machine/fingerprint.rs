@@ -0,0 +1,214 @@
+use crate::{
+    component::{ComponentVersion, SemanticVersion},
+    rom::{GameSystem, RomId},
+};
+use serde::{Deserialize, Serialize};
+use sha1::{Digest, Sha1};
+use thiserror::Error;
+
+/// Identifies a machine configuration so state from a different one is never loaded into it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub struct MachineFingerprint {
+    pub system: GameSystem,
+    /// [ComponentVersion::NAME] and instance name of each component, in insertion order
+    pub components: Vec<String>,
+    /// Revision of each of the components, in the same order
+    pub versions: Vec<SemanticVersion>,
+    /// Sha-1 over every component config as MessagePack
+    pub config_hash: [u8; 20],
+    pub roms: Vec<RomId>,
+}
+
+#[derive(Debug, Error)]
+pub enum FingerprintMismatch {
+    #[error("The state was made for a {expected} but this is a {found}")]
+    System {
+        expected: GameSystem,
+        found: GameSystem,
+    },
+    #[error("The state was made with a different set of components: {missing:?} are missing and {unexpected:?} are new")]
+    Components {
+        missing: Vec<String>,
+        unexpected: Vec<String>,
+    },
+    #[error("The other side has version {expected} of {component} but this is version {found}")]
+    Version {
+        component: String,
+        expected: SemanticVersion,
+        found: SemanticVersion,
+    },
+    #[error("The state was made with different component settings (such as quirks)")]
+    Config,
+    #[error("The state was made with a different set of ROMs")]
+    Roms,
+}
+
+#[derive(Default)]
+pub(super) struct MachineFingerprintBuilder {
+    components: Vec<String>,
+    versions: Vec<SemanticVersion>,
+    hasher: Sha1,
+}
+
+impl MachineFingerprintBuilder {
+    pub fn insert_component<C: ComponentVersion>(&mut self, name: &str, config: &impl Serialize) {
+        let component = format!("{}({})", C::NAME, name);
+        self.hasher.update(component.as_bytes());
+        self.hasher.update(rmp_serde::to_vec(config).unwrap());
+        self.components.push(component);
+        self.versions.push(C::VERSION);
+    }
+
+    pub fn finalize(self) -> MachineFingerprint {
+        MachineFingerprint {
+            system: GameSystem::default(),
+            components: self.components,
+            versions: self.versions,
+            config_hash: self.hasher.finalize().into(),
+            roms: Vec::new(),
+        }
+    }
+}
+
+impl MachineFingerprint {
+    /// Checks if state from a machine with the fingerprint `other` can be loaded into this one
+    pub fn verify(&self, other: &MachineFingerprint) -> Result<(), FingerprintMismatch> {
+        self.compare(other, SemanticVersion::can_load_snapshot_of)
+    }
+
+    /// Checks if a netplay peer running a machine with the fingerprint `other` would stay in sync with this one, which
+    /// is stricter on component versions than loading state
+    #[allow(dead_code)]
+    pub fn verify_peer(&self, other: &MachineFingerprint) -> Result<(), FingerprintMismatch> {
+        self.compare(other, SemanticVersion::can_play_with)
+    }
+
+    fn compare(
+        &self,
+        other: &MachineFingerprint,
+        compatible: impl Fn(&SemanticVersion, &SemanticVersion) -> bool,
+    ) -> Result<(), FingerprintMismatch> {
+        if self.system != other.system {
+            return Err(FingerprintMismatch::System {
+                expected: other.system,
+                found: self.system,
+            });
+        }
+
+        if self.components != other.components {
+            return Err(FingerprintMismatch::Components {
+                missing: other
+                    .components
+                    .iter()
+                    .filter(|component| !self.components.contains(component))
+                    .cloned()
+                    .collect(),
+                unexpected: self
+                    .components
+                    .iter()
+                    .filter(|component| !other.components.contains(component))
+                    .cloned()
+                    .collect(),
+            });
+        }
+
+        for ((component, found), expected) in self
+            .components
+            .iter()
+            .zip(&self.versions)
+            .zip(&other.versions)
+        {
+            if !compatible(found, expected) {
+                return Err(FingerprintMismatch::Version {
+                    component: component.clone(),
+                    expected: *expected,
+                    found: *found,
+                });
+            }
+        }
+
+        if self.config_hash != other.config_hash {
+            return Err(FingerprintMismatch::Config);
+        }
+
+        if self.roms != other.roms {
+            return Err(FingerprintMismatch::Roms);
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::component::definitions::misc::plain_memory::{
+        PlainMemory, PlainMemoryConfig, PlainMemoryInitialContents,
+    };
+    use std::ops::Range;
+
+    #[test]
+    fn peers_are_stricter_on_versions_than_snapshots() {
+        let fingerprint = |version| MachineFingerprint {
+            components: vec!["processor".to_string()],
+            versions: vec![version],
+            ..Default::default()
+        };
+        let ours = fingerprint(SemanticVersion::new(1, 2, 0));
+
+        // A fix to emulation keeps the state layout but changes what the machine does
+        let fixed = fingerprint(SemanticVersion::new(1, 1, 3));
+        assert!(ours.verify(&fixed).is_ok());
+        assert!(matches!(
+            ours.verify_peer(&fixed),
+            Err(FingerprintMismatch::Version { .. })
+        ));
+
+        assert!(ours
+            .verify_peer(&fingerprint(SemanticVersion::new(1, 2, 7)))
+            .is_ok());
+        assert!(matches!(
+            ours.verify(&fingerprint(SemanticVersion::new(2, 0, 0))),
+            Err(FingerprintMismatch::Version { .. })
+        ));
+    }
+
+    #[test]
+    fn config_hash_leaves_out_function_pointers() {
+        let fingerprint = |config| {
+            let mut builder = MachineFingerprintBuilder::default();
+            builder.insert_component::<PlainMemory>("memory", &config);
+            builder.finalize()
+        };
+        let config = |value, penalty: fn(Range<usize>, bool) -> u64| PlainMemoryConfig {
+            assigned_range: 0x000..0x100,
+            initial_contents: PlainMemoryInitialContents::Value { value },
+            read_cycle_penalty_calculator: penalty,
+            ..Default::default()
+        };
+
+        let ours = fingerprint(config(0, |_, _| 0));
+        assert!(ours.verify(&fingerprint(config(0, |_, _| 1))).is_ok());
+        assert!(matches!(
+            ours.verify(&fingerprint(config(1, |_, _| 0))),
+            Err(FingerprintMismatch::Config)
+        ));
+    }
+
+    #[test]
+    fn components_are_named_independently_of_their_types() {
+        let mut builder = MachineFingerprintBuilder::default();
+        builder.insert_component::<PlainMemory>("memory", &PlainMemoryConfig::default());
+        let fingerprint = builder.finalize();
+
+        // Moving the type to another module can't turn old state away
+        assert_eq!(fingerprint.components, ["plain_memory(memory)"]);
+        assert_eq!(
+            fingerprint.config_hash,
+            [
+                0xb2, 0xea, 0xa5, 0xf6, 0xa7, 0x5c, 0x19, 0xdf, 0xaa, 0x73, 0x4d, 0xf6, 0x99, 0x22,
+                0xcb, 0x4e, 0xe4, 0x3f, 0xac, 0x65
+            ]
+        );
+    }
+}
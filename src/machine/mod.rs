@@ -1,97 +1,255 @@
+#[cfg(feature = "frontend")]
+use crate::gui::disassembler::{Disassembler, DisassemblerPage};
 use crate::{
     component::{
+        audio::AudioComponent,
         display::DisplayComponent,
         input::InputComponent,
-        memory::{MemoryComponent, MemoryTranslationTable},
+        memory::{MemoryComponent, MemoryTranslationTable, OpenBusBehavior},
+        port::{PortMappedComponent, PortTranslationTable, PORT_TRANSLATION_TABLE_TOPIC},
+        processor::ProcessorComponent,
         schedulable::SchedulableComponent,
+        snapshot::SnapshotableComponent,
         Component, FromConfig,
     },
+    debugger::{DebugTarget, ProcessorDebugTarget},
     input::EmulatedGamepad,
+    rewind::RewindBuffer,
     rom::RomManager,
     runtime::{RenderingBackend, RenderingBackendState},
-    task::{InitializeableTask, Task},
+    task::{
+        processor::{ProcessorTask, ProcessorTaskConfig},
+        rewind::RewindTask,
+        InitializeableTask, Task,
+    },
 };
 use downcast_rs::DowncastSync;
+use event_bus::EventBus;
+use executor::Executor;
+use fingerprint::{MachineFingerprint, MachineFingerprintBuilder};
 use num::rational::Ratio;
+use peripheral::{PeripheralKind, PeripheralPort};
 use sealed::sealed;
 use std::{
     any::TypeId,
     collections::HashMap,
+    ops::Range,
     sync::{Arc, Mutex},
 };
 
 pub mod definitions;
+pub mod event_bus;
 pub mod executor;
-pub mod initializer;
+pub mod fingerprint;
+pub mod peripheral;
+pub mod watchdog;
 
 #[sealed]
-trait MutexedComponent: DowncastSync {}
+trait MutexedComponent: DowncastSync {
+    fn reset(&self);
+    fn shutdown(&self);
+}
 #[sealed]
-impl<C: Component> MutexedComponent for Mutex<C> {}
+impl<C: Component> MutexedComponent for Mutex<C> {
+    fn reset(&self) {
+        self.lock().unwrap().reset();
+    }
+
+    fn shutdown(&self) {
+        self.lock().unwrap().shutdown();
+    }
+}
+
+/// A page in the in game menu provided by a machine definition
+#[cfg(feature = "frontend")]
+pub type MachineGuiPage = Box<dyn Fn(&mut egui::Ui, &QueryableComponents) + Send>;
 
 #[derive(Default)]
-pub struct QueryableComponents(HashMap<(TypeId, &'static str), Arc<dyn MutexedComponent>>);
+pub struct QueryableComponents {
+    components: HashMap<(TypeId, &'static str), Arc<dyn MutexedComponent>>,
+    /// Handles published by the components on insertion
+    event_bus: EventBus,
+}
 
 impl QueryableComponents {
     pub fn query_component<C: Component>(&self, name: &'static str) -> Option<Arc<Mutex<C>>> {
-        self.0
+        self.components
             .get(&(TypeId::of::<C>(), name))
             .cloned()
             .and_then(|component| component.into_any_arc().downcast::<Mutex<C>>().ok())
     }
+
+    pub fn insert<C: Component>(&mut self, name: &'static str, component: Arc<Mutex<C>>) {
+        component
+            .lock()
+            .unwrap()
+            .publish_handles(&mut self.event_bus);
+        self.components.insert((TypeId::of::<C>(), name), component);
+    }
+
+    pub fn event_bus(&self) -> &EventBus {
+        &self.event_bus
+    }
+
+    /// Puts every component back in its power on state, like pressing the reset button of the console
+    pub fn reset_all(&self) {
+        for component in self.components.values() {
+            component.reset();
+        }
+    }
+
+    fn shutdown_all(&self) {
+        for component in self.components.values() {
+            component.shutdown();
+        }
+    }
 }
 
 // Intermediate state for the runtime to construct a emulation context out of it
 pub struct Machine<R: RenderingBackend> {
-    pub tasks: Vec<(Ratio<u32>, Box<dyn Task>)>,
+    pub tasks: Vec<(&'static str, Ratio<u32>, Box<dyn Task>)>,
     pub memory_translation_table: Arc<MemoryTranslationTable>,
     pub controllers: Vec<Arc<EmulatedGamepad>>,
     pub display_components: Vec<Arc<Mutex<dyn DisplayComponent<R>>>>,
+    pub audio_components: Vec<Arc<Mutex<dyn AudioComponent>>>,
+    pub fingerprint: MachineFingerprint,
+    pub queryable_components: QueryableComponents,
+    #[cfg(feature = "frontend")]
+    pub gui_pages: Vec<(&'static str, MachineGuiPage)>,
+    pub snapshotable_components: Vec<(&'static str, Arc<Mutex<dyn SnapshotableComponent>>)>,
+    /// By the name of the processor
+    #[cfg(feature = "frontend")]
+    pub disassemblers: Vec<(&'static str, Box<dyn DisassemblerPage>)>,
+    /// The same processors as the disassemblers, in the same order, for debuggers outside the GUI
+    pub debug_targets: Vec<(&'static str, Arc<dyn DebugTarget>)>,
+    pub peripheral_ports: Vec<PeripheralPort>,
+    /// Task of the display with the highest rate, one tick of it is a frame
+    pub frame_task: Option<&'static str>,
 }
 
 impl<R: RenderingBackend> Machine<R> {
-    pub fn build(
-        rom_manager: Arc<RomManager>,
-        rendering_state: &mut <R as RenderingBackend>::RuntimeState,
-    ) -> MachineBuilder<R> {
+    /// Building doesn't touch the rendering backend, so it can happen off the thread that owns it. The displays are
+    /// set up with [Machine::initialize_displays] afterwards
+    pub fn build(rom_manager: Arc<RomManager>) -> MachineBuilder<R> {
         MachineBuilder {
             components: HashMap::new(),
             tasks: Vec::new(),
             rom_manager,
             memory_translation_table: MemoryTranslationTable::default(),
+            port_translation_table: PortTranslationTable::default(),
             queryable_components: QueryableComponents::default(),
             display_components: Vec::new(),
+            audio_components: Vec::new(),
             controllers: Vec::new(),
-            rendering_state,
+            fingerprint: MachineFingerprintBuilder::default(),
+            #[cfg(feature = "frontend")]
+            gui_pages: Vec::new(),
+            snapshotable_components: Vec::new(),
+            #[cfg(feature = "frontend")]
+            disassemblers: Vec::new(),
+            debug_targets: Vec::new(),
+            peripheral_ports: Vec::new(),
+            display_names: Vec::new(),
         }
     }
+
+    /// Hands the displays what they need from the rendering backend, before the machine first runs
+    pub fn initialize_displays(&self, rendering_state: &mut R::RuntimeState) {
+        rendering_state.initialize_components(&self.display_components);
+    }
+
+    /// Tears the machine down in a fixed order rather than whatever order things happen to be dropped in
+    ///
+    /// The executor goes first so nothing runs against components that are shutting down, then components and
+    /// plugged devices write out anything that outlives the session like battery backed RAM, then the displays let
+    /// go of what they hold on the rendering backend. The backend itself belongs to the runtime, which has to keep it
+    /// around until this returns
+    pub fn shutdown(mut self, executor: impl Executor) {
+        drop(executor);
+
+        self.queryable_components.shutdown_all();
+        for peripheral_port in &mut self.peripheral_ports {
+            peripheral_port.shutdown();
+        }
+
+        for display_component in &self.display_components {
+            display_component.lock().unwrap().release_display();
+        }
+    }
+
+    /// Schedules capturing of rewind points every interval (in milliseconds), keeping the latest depth of them
+    ///
+    /// Returns None if there is nothing to capture, which is the case for machines without snapshot support
+    pub fn insert_rewind(
+        &mut self,
+        depth: usize,
+        interval: u32,
+    ) -> Option<Arc<Mutex<RewindBuffer>>> {
+        if depth == 0 || self.snapshotable_components.is_empty() {
+            return None;
+        }
+
+        let rewind_buffer = Arc::new(Mutex::new(RewindBuffer::new(
+            self.snapshotable_components
+                .iter()
+                .map(|(_, component)| component.clone())
+                .collect(),
+            depth,
+        )));
+
+        self.tasks.push((
+            "rewind",
+            Ratio::new(1000, interval.max(1)),
+            Box::new(RewindTask::new(rewind_buffer.clone())),
+        ));
+
+        Some(rewind_buffer)
+    }
 }
 
-pub struct MachineBuilder<'a, R: RenderingBackend> {
+pub struct MachineBuilder<R: RenderingBackend> {
     /// Components
     components: HashMap<(TypeId, &'static str), Arc<Mutex<dyn Component>>>,
-    /// Tasks wrapping scheduable components
-    tasks: Vec<(Ratio<u32>, Box<dyn Task>)>,
+    /// Tasks wrapping scheduable components, with the name of the component
+    tasks: Vec<(&'static str, Ratio<u32>, Box<dyn Task>)>,
     /// Memory translation table
     memory_translation_table: MemoryTranslationTable,
+    /// The I/O port space, for the processors that have one
+    port_translation_table: PortTranslationTable,
     /// Display components to be hooked with the runtime graphics backends
     display_components: Vec<Arc<Mutex<dyn DisplayComponent<R>>>>,
+    /// Audio components to be hooked with the runtime audio backend
+    audio_components: Vec<Arc<Mutex<dyn AudioComponent>>>,
     /// Controllers
     controllers: Vec<Arc<EmulatedGamepad>>,
     /// Components stored in a downcastable way
     queryable_components: QueryableComponents,
     /// ROM manager
     rom_manager: Arc<RomManager>,
-    /// Rendering runtime component for initializing display components
-    rendering_state: &'a mut <R as RenderingBackend>::RuntimeState,
+    /// Accumulates the identity of the machine for snapshot compatibility checks
+    fingerprint: MachineFingerprintBuilder,
+    /// Custom menu pages
+    #[cfg(feature = "frontend")]
+    gui_pages: Vec<(&'static str, MachineGuiPage)>,
+    /// Components that make up a save state, by name
+    snapshotable_components: Vec<(&'static str, Arc<Mutex<dyn SnapshotableComponent>>)>,
+    /// Processors the debugger can disassemble and stop
+    #[cfg(feature = "frontend")]
+    disassemblers: Vec<(&'static str, Box<dyn DisassemblerPage>)>,
+    debug_targets: Vec<(&'static str, Arc<dyn DebugTarget>)>,
+    /// Places devices can be plugged into while the machine runs
+    peripheral_ports: Vec<PeripheralPort>,
+    /// Names of the display components, to find their tasks
+    display_names: Vec<&'static str>,
 }
 
-impl<'a, R: RenderingBackend> MachineBuilder<'a, R> {
+impl<R: RenderingBackend> MachineBuilder<R> {
     pub fn component<C: FromConfig>(
-        self,
+        mut self,
         name: &'static str,
         config: C::Config,
-    ) -> ComponentBuilder<'a, R, C> {
+    ) -> ComponentBuilder<R, C> {
+        self.fingerprint.insert_component::<C>(name, &config);
         let component = C::from_config(self.rom_manager.clone(), config);
 
         ComponentBuilder {
@@ -101,14 +259,75 @@ impl<'a, R: RenderingBackend> MachineBuilder<'a, R> {
         }
     }
 
-    pub fn component_default<C: FromConfig>(self, name: &'static str) -> ComponentBuilder<'a, R, C>
+    pub fn component_default<C: FromConfig>(self, name: &'static str) -> ComponentBuilder<R, C>
     where
         C::Config: Default,
     {
         self.component(name, C::Config::default())
     }
 
-    pub fn finalize_machine(self) -> Machine<R> {
+    /// What the processors see when they access memory nothing is mapped to
+    pub fn open_bus(mut self, open_bus: OpenBusBehavior) -> Self {
+        self.memory_translation_table
+            .set_open_bus_behavior(open_bus);
+        self
+    }
+
+    /// Which address lines the components on the I/O port space see
+    #[allow(dead_code)]
+    pub fn port_address_mask(mut self, address_mask: u16) -> Self {
+        self.port_translation_table.set_address_mask(address_mask);
+        self
+    }
+
+    /// Adds a port devices of the kinds can be plugged into from the menu, starting out with the plugged kind
+    ///
+    /// Devices with memory answer accesses to the range, the range reads as open bus while the port is empty
+    pub fn peripheral_port(
+        mut self,
+        name: &'static str,
+        memory_range: Option<Range<usize>>,
+        accepts: Vec<PeripheralKind>,
+        plugged: Option<usize>,
+    ) -> Self {
+        let mut port = PeripheralPort::new(
+            name,
+            memory_range.clone(),
+            accepts,
+            self.rom_manager.clone(),
+        );
+
+        if let (Some(memory_range), Some(slot)) = (memory_range, port.slot()) {
+            self.memory_translation_table.insert(memory_range, slot);
+        }
+        self.controllers.push(port.controller());
+
+        if let Some((tick_rate, task)) = plugged.and_then(|kind| port.connect(kind)) {
+            self.tasks.push((name, tick_rate, task));
+        }
+        self.peripheral_ports.push(port);
+
+        self
+    }
+
+    /// Adds a page to the in game menu that is shown while this machine runs
+    #[cfg(feature = "frontend")]
+    pub fn gui_page(
+        mut self,
+        name: &'static str,
+        page: impl Fn(&mut egui::Ui, &QueryableComponents) + Send + 'static,
+    ) -> Self {
+        self.gui_pages.push((name, Box::new(page)));
+        self
+    }
+
+    pub fn finalize_machine(mut self) -> Machine<R> {
+        // Finished before anything queries for it
+        self.queryable_components.event_bus.publish(
+            PORT_TRANSLATION_TABLE_TOPIC,
+            Arc::new(self.port_translation_table),
+        );
+
         for component in self.components.values() {
             component
                 .lock()
@@ -116,31 +335,45 @@ impl<'a, R: RenderingBackend> MachineBuilder<'a, R> {
                 .query_components(&self.queryable_components);
         }
 
-        self.rendering_state
-            .initialize_components(&self.display_components);
+        let frame_task = self
+            .tasks
+            .iter()
+            .filter(|(name, _, _)| self.display_names.contains(name))
+            .max_by_key(|(_, tick_rate, _)| *tick_rate)
+            .map(|(name, _, _)| *name);
 
         Machine {
             tasks: self.tasks,
             memory_translation_table: Arc::new(self.memory_translation_table),
             controllers: self.controllers,
             display_components: self.display_components,
+            audio_components: self.audio_components,
+            fingerprint: self.fingerprint.finalize(),
+            queryable_components: self.queryable_components,
+            #[cfg(feature = "frontend")]
+            gui_pages: self.gui_pages,
+            snapshotable_components: self.snapshotable_components,
+            #[cfg(feature = "frontend")]
+            disassemblers: self.disassemblers,
+            debug_targets: self.debug_targets,
+            peripheral_ports: self.peripheral_ports,
+            frame_task,
         }
     }
 }
 
-pub struct ComponentBuilder<'a, R: RenderingBackend, C: Component> {
+pub struct ComponentBuilder<R: RenderingBackend, C: Component> {
     name: &'static str,
     component: Arc<Mutex<C>>,
-    machine_builder: MachineBuilder<'a, R>,
+    machine_builder: MachineBuilder<R>,
 }
 
-impl<'a, R: RenderingBackend, C: Component> ComponentBuilder<'a, R, C> {
-    pub fn finalize_component(self) -> MachineBuilder<'a, R> {
+impl<R: RenderingBackend, C: Component> ComponentBuilder<R, C> {
+    pub fn finalize_component(self) -> MachineBuilder<R> {
         let mut machine_builder = self.machine_builder;
         machine_builder
             .queryable_components
-            .0
-            .insert((TypeId::of::<C>(), self.name), self.component.clone());
+            .insert(self.name, self.component.clone());
         machine_builder
             .components
             .insert((TypeId::of::<C>(), self.name), self.component);
@@ -148,21 +381,23 @@ impl<'a, R: RenderingBackend, C: Component> ComponentBuilder<'a, R, C> {
     }
 }
 
-impl<'a, R: RenderingBackend, C: SchedulableComponent> ComponentBuilder<'a, R, C> {
+impl<R: RenderingBackend, C: SchedulableComponent> ComponentBuilder<R, C> {
     pub fn insert_schedule<T: InitializeableTask<C>>(
         mut self,
         config: T::Config,
-    ) -> ComponentBuilder<'a, R, C> {
+    ) -> ComponentBuilder<R, C> {
         let task = T::new(self.component.clone(), config);
 
-        self.machine_builder
-            .tasks
-            .push((self.component.lock().unwrap().tick_rate(), Box::new(task)));
+        self.machine_builder.tasks.push((
+            self.name,
+            self.component.lock().unwrap().tick_rate(),
+            Box::new(task),
+        ));
 
         self
     }
 
-    pub fn insert_schedule_default<T: InitializeableTask<C>>(self) -> ComponentBuilder<'a, R, C>
+    pub fn insert_schedule_default<T: InitializeableTask<C>>(self) -> ComponentBuilder<R, C>
     where
         T::Config: Default,
     {
@@ -170,8 +405,42 @@ impl<'a, R: RenderingBackend, C: SchedulableComponent> ComponentBuilder<'a, R, C
     }
 }
 
-impl<'a, R: RenderingBackend, C: MemoryComponent> ComponentBuilder<'a, R, C> {
-    pub fn with_memory_map(mut self) -> ComponentBuilder<'a, R, C> {
+impl<R: RenderingBackend, C: ProcessorComponent> ComponentBuilder<R, C> {
+    /// Like [ComponentBuilder::insert_schedule] with a [ProcessorTask], but also shows the processor in the debugger and
+    /// saves it in snapshots
+    pub fn insert_processor_schedule(
+        mut self,
+        config: ProcessorTaskConfig,
+    ) -> ComponentBuilder<R, C> {
+        let task = ProcessorTask::new(self.component.clone(), config);
+
+        #[cfg(feature = "frontend")]
+        self.machine_builder.disassemblers.push((
+            self.name,
+            Box::new(Disassembler::new(
+                self.component.clone(),
+                task.debug_state(),
+            )),
+        ));
+        self.machine_builder.debug_targets.push((
+            self.name,
+            Arc::new(ProcessorDebugTarget::new(
+                self.component.clone(),
+                task.debug_state(),
+            )),
+        ));
+        self.machine_builder.tasks.push((
+            self.name,
+            self.component.lock().unwrap().tick_rate(),
+            Box::new(task),
+        ));
+
+        self.with_snapshot()
+    }
+}
+
+impl<R: RenderingBackend, C: MemoryComponent> ComponentBuilder<R, C> {
+    pub fn with_memory_map(mut self) -> ComponentBuilder<R, C> {
         self.machine_builder.memory_translation_table.insert(
             self.component.lock().unwrap().assigned_memory_range(),
             self.component.clone(),
@@ -181,18 +450,52 @@ impl<'a, R: RenderingBackend, C: MemoryComponent> ComponentBuilder<'a, R, C> {
     }
 }
 
-impl<'a, R: RenderingBackend, C: DisplayComponent<R>> ComponentBuilder<'a, R, C> {
-    pub fn with_displayable(mut self) -> ComponentBuilder<'a, R, C> {
+impl<R: RenderingBackend, C: PortMappedComponent> ComponentBuilder<R, C> {
+    // For the Z80 and 8080 machines, which don't exist yet
+    #[allow(dead_code)]
+    pub fn with_port_map(mut self) -> ComponentBuilder<R, C> {
+        self.machine_builder.port_translation_table.insert(
+            self.component.lock().unwrap().assigned_ports(),
+            self.component.clone(),
+        );
+
+        self
+    }
+}
+
+impl<R: RenderingBackend, C: DisplayComponent<R>> ComponentBuilder<R, C> {
+    pub fn with_displayable(mut self) -> ComponentBuilder<R, C> {
         self.machine_builder
             .display_components
             .push(self.component.clone());
+        self.machine_builder.display_names.push(self.name);
+
+        self
+    }
+}
+
+impl<R: RenderingBackend, C: AudioComponent> ComponentBuilder<R, C> {
+    pub fn with_audio(mut self) -> ComponentBuilder<R, C> {
+        self.machine_builder
+            .audio_components
+            .push(self.component.clone());
+
+        self
+    }
+}
+
+impl<R: RenderingBackend, C: SnapshotableComponent> ComponentBuilder<R, C> {
+    pub fn with_snapshot(mut self) -> ComponentBuilder<R, C> {
+        self.machine_builder
+            .snapshotable_components
+            .push((self.name, self.component.clone()));
 
         self
     }
 }
 
-impl<'a, R: RenderingBackend, C: InputComponent> ComponentBuilder<'a, R, C> {
-    pub fn with_gamepad(mut self) -> ComponentBuilder<'a, R, C> {
+impl<R: RenderingBackend, C: InputComponent> ComponentBuilder<R, C> {
+    pub fn with_gamepad(mut self) -> ComponentBuilder<R, C> {
         let assigned_inputs = self.component.lock().unwrap().registered_inputs();
         let controller = EmulatedGamepad::new(assigned_inputs);
         self.machine_builder.controllers.push(controller.clone());
@@ -0,0 +1,351 @@
+#[cfg(feature = "frontend")]
+use super::executor::Executor;
+use crate::{
+    component::{
+        input::InputComponent,
+        memory::{MemoryComponent, PreviewMemoryRecord, ReadMemoryRecord, WriteMemoryRecord},
+        Component,
+    },
+    input::{EmulatedGamepad, Input},
+    rom::RomManager,
+    task::Task,
+};
+use arrayvec::ArrayVec;
+use num::rational::Ratio;
+use std::{
+    ops::Range,
+    sync::{Arc, Mutex},
+};
+
+/// What a device brings into the machine while it is plugged in
+///
+/// Devices are not part of snapshots, they keep their state across loading one
+#[derive(Default)]
+pub struct PeripheralDevice {
+    /// Answers the accesses to the memory range of the port
+    pub memory: Option<Arc<Mutex<dyn MemoryComponent>>>,
+    /// Gets the controller of the port
+    pub input: Option<Arc<Mutex<dyn InputComponent>>>,
+    /// Scheduled under the name of the port while plugged in
+    pub task: Option<(Ratio<u32>, Box<dyn Task>)>,
+}
+
+/// A device a port accepts
+pub struct PeripheralKind {
+    pub name: &'static str,
+    /// What the device reads from the controller of the port
+    pub inputs: &'static [Input],
+    /// Gets the name of the port, for devices that keep something per port like the image of a memory card
+    pub construct: fn(Arc<RomManager>, &'static str) -> PeripheralDevice,
+}
+
+/// Stands in for the device in the memory translation table, which can't change once the machine is built
+struct PeripheralSlot {
+    range: Range<usize>,
+    device: Option<Arc<Mutex<dyn MemoryComponent>>>,
+}
+
+impl Component for PeripheralSlot {}
+
+impl MemoryComponent for PeripheralSlot {
+    fn assigned_memory_range(&self) -> Range<usize> {
+        self.range.clone()
+    }
+
+    fn read_memory(
+        &mut self,
+        address: usize,
+        buffer: &mut [u8],
+        records: &mut ArrayVec<(Range<usize>, ReadMemoryRecord), 8>,
+    ) -> u64 {
+        match &self.device {
+            Some(device) => device.lock().unwrap().read_memory(address, buffer, records),
+            // Nothing plugged in answers, so the machine sees open bus
+            None => {
+                records.push((address..address + buffer.len(), ReadMemoryRecord::Denied));
+                0
+            }
+        }
+    }
+
+    fn write_memory(
+        &mut self,
+        address: usize,
+        buffer: &[u8],
+        records: &mut ArrayVec<(Range<usize>, WriteMemoryRecord), 8>,
+    ) -> u64 {
+        match &self.device {
+            Some(device) => device
+                .lock()
+                .unwrap()
+                .write_memory(address, buffer, records),
+            None => {
+                records.push((address..address + buffer.len(), WriteMemoryRecord::Denied));
+                0
+            }
+        }
+    }
+
+    fn preview_memory(
+        &mut self,
+        address: usize,
+        buffer: &mut [u8],
+        records: &mut ArrayVec<(Range<usize>, PreviewMemoryRecord), 8>,
+    ) {
+        match &self.device {
+            Some(device) => device
+                .lock()
+                .unwrap()
+                .preview_memory(address, buffer, records),
+            None => records.push((address..address + buffer.len(), PreviewMemoryRecord::Denied)),
+        }
+    }
+}
+
+/// A place on the machine devices can be plugged into and pulled out of while it runs, like a controller port
+pub struct PeripheralPort {
+    pub name: &'static str,
+    accepts: Vec<PeripheralKind>,
+    slot: Option<Arc<Mutex<PeripheralSlot>>>,
+    /// Covers the inputs of every kind the port accepts, so the player mapping survives swapping devices
+    controller: Arc<EmulatedGamepad>,
+    /// Index into accepts
+    plugged: Option<usize>,
+    /// Held here as devices without a task have nothing else keeping their input alive
+    input: Option<Arc<Mutex<dyn InputComponent>>>,
+    rom_manager: Arc<RomManager>,
+}
+
+impl PeripheralPort {
+    /// The memory slot, if the port has a range, has to be mapped by whoever makes the port
+    pub(super) fn new(
+        name: &'static str,
+        memory_range: Option<Range<usize>>,
+        accepts: Vec<PeripheralKind>,
+        rom_manager: Arc<RomManager>,
+    ) -> Self {
+        let inputs: Vec<_> = accepts
+            .iter()
+            .flat_map(|kind| kind.inputs.iter().copied())
+            .collect();
+
+        Self {
+            name,
+            accepts,
+            slot: memory_range.map(|range| {
+                Arc::new(Mutex::new(PeripheralSlot {
+                    range,
+                    device: None,
+                }))
+            }),
+            controller: EmulatedGamepad::new(&inputs),
+            plugged: None,
+            input: None,
+            rom_manager,
+        }
+    }
+
+    pub(super) fn slot(&self) -> Option<Arc<Mutex<dyn MemoryComponent>>> {
+        self.slot
+            .clone()
+            .map(|slot| slot as Arc<Mutex<dyn MemoryComponent>>)
+    }
+
+    pub(super) fn controller(&self) -> Arc<EmulatedGamepad> {
+        self.controller.clone()
+    }
+
+    /// Names of the kinds of devices that fit
+    #[cfg(feature = "frontend")]
+    pub fn accepts(&self) -> impl Iterator<Item = &'static str> + '_ {
+        self.accepts.iter().map(|kind| kind.name)
+    }
+
+    /// Index of the kind plugged in
+    #[cfg(any(feature = "frontend", test))]
+    pub fn plugged(&self) -> Option<usize> {
+        self.plugged
+    }
+
+    /// Wires up a new device of the kind, returning its task for whoever runs the machine to schedule
+    pub(super) fn connect(&mut self, kind: usize) -> Option<(Ratio<u32>, Box<dyn Task>)> {
+        self.disconnect();
+
+        let device = (self.accepts[kind].construct)(self.rom_manager.clone(), self.name);
+        tracing::info!("Plugged {} into {}", self.accepts[kind].name, self.name);
+
+        if let Some(slot) = &self.slot {
+            slot.lock().unwrap().device = device.memory;
+        }
+        if let Some(input) = &device.input {
+            input
+                .lock()
+                .unwrap()
+                .assign_controller(self.controller.clone());
+        }
+        self.input = device.input;
+        self.plugged = Some(kind);
+
+        device.task
+    }
+
+    /// Pulling a device out is the end of its session like shutting the machine down is
+    fn disconnect(&mut self) {
+        if let Some(kind) = self.plugged.take() {
+            tracing::info!("Pulled {} out of {}", self.accepts[kind].name, self.name);
+        }
+
+        if let Some(device) = self
+            .slot
+            .as_ref()
+            .and_then(|slot| slot.lock().unwrap().device.take())
+        {
+            device.lock().unwrap().shutdown();
+        }
+        if let Some(input) = self.input.take() {
+            input.lock().unwrap().shutdown();
+        }
+    }
+
+    /// Shuts the plugged device down with the machine, leaving the port empty
+    pub(super) fn shutdown(&mut self) {
+        self.disconnect();
+    }
+
+    /// Swaps the device of a running machine, None leaves the port empty. Only called between runs of the executor
+    #[cfg(feature = "frontend")]
+    pub fn plug(&mut self, kind: Option<usize>, executor: &mut impl Executor) {
+        executor.remove_task(self.name);
+
+        match kind {
+            Some(kind) => {
+                if let Some((tick_rate, task)) = self.connect(kind) {
+                    executor.insert_task(self.name, tick_rate, task);
+                }
+            }
+            None => self.disconnect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::component::{
+        definitions::misc::plain_memory::{
+            PlainMemory, PlainMemoryConfig, PlainMemoryInitialContents,
+        },
+        memory::MemoryTranslationTable,
+        FromConfig,
+    };
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    fn cartridge(rom_manager: Arc<RomManager>, _port: &'static str) -> PeripheralDevice {
+        PeripheralDevice {
+            memory: Some(Arc::new(Mutex::new(PlainMemory::from_config(
+                rom_manager,
+                PlainMemoryConfig {
+                    assigned_range: 0x100..0x200,
+                    initial_contents: PlainMemoryInitialContents::Value { value: 0x42 },
+                    ..Default::default()
+                },
+            )))),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn port_memory_follows_plugged_device() {
+        let mut port = PeripheralPort::new(
+            "Expansion",
+            Some(0x100..0x200),
+            vec![PeripheralKind {
+                name: "Cartridge",
+                inputs: &[],
+                construct: cartridge,
+            }],
+            Arc::new(RomManager::default()),
+        );
+        let mut memory_translation_table = MemoryTranslationTable::default();
+        memory_translation_table.insert(0x100..0x200, port.slot().unwrap());
+
+        let mut buffer = [0];
+        assert!(memory_translation_table.read(0x150, &mut buffer).is_err());
+
+        port.connect(0);
+        assert_eq!(port.plugged(), Some(0));
+        memory_translation_table.read(0x150, &mut buffer).unwrap();
+        assert_eq!(buffer, [0x42]);
+
+        port.disconnect();
+        assert_eq!(port.plugged(), None);
+        assert!(memory_translation_table.read(0x150, &mut buffer).is_err());
+    }
+
+    static SHUT_DOWN: AtomicBool = AtomicBool::new(false);
+
+    /// Stands in for a memory card, which saves itself on shutdown
+    struct SavingDevice;
+
+    impl Component for SavingDevice {
+        fn shutdown(&mut self) {
+            SHUT_DOWN.store(true, Ordering::Relaxed);
+        }
+    }
+
+    impl MemoryComponent for SavingDevice {
+        fn assigned_memory_range(&self) -> Range<usize> {
+            0x100..0x200
+        }
+
+        fn read_memory(
+            &mut self,
+            _address: usize,
+            _buffer: &mut [u8],
+            _records: &mut ArrayVec<(Range<usize>, ReadMemoryRecord), 8>,
+        ) -> u64 {
+            0
+        }
+
+        fn write_memory(
+            &mut self,
+            _address: usize,
+            _buffer: &[u8],
+            _records: &mut ArrayVec<(Range<usize>, WriteMemoryRecord), 8>,
+        ) -> u64 {
+            0
+        }
+
+        fn preview_memory(
+            &mut self,
+            _address: usize,
+            _buffer: &mut [u8],
+            _records: &mut ArrayVec<(Range<usize>, PreviewMemoryRecord), 8>,
+        ) {
+        }
+    }
+
+    #[test]
+    fn devices_shut_down_with_the_port() {
+        let mut port = PeripheralPort::new(
+            "Memory Card",
+            Some(0x100..0x200),
+            vec![PeripheralKind {
+                name: "Memory Card",
+                inputs: &[],
+                construct: |_, _| PeripheralDevice {
+                    memory: Some(Arc::new(Mutex::new(SavingDevice))),
+                    ..Default::default()
+                },
+            }],
+            Arc::new(RomManager::default()),
+        );
+
+        port.connect(0);
+        assert!(!SHUT_DOWN.load(Ordering::Relaxed));
+
+        port.shutdown();
+        assert!(SHUT_DOWN.load(Ordering::Relaxed));
+        assert_eq!(port.plugged(), None);
+    }
+}
@@ -0,0 +1,228 @@
+#[cfg(any(feature = "frontend", test))]
+use std::{
+    sync::{atomic::AtomicBool, Arc},
+    thread::JoinHandle,
+    time::Duration,
+};
+use std::{
+    sync::{
+        atomic::{AtomicU64, AtomicUsize, Ordering},
+        Mutex,
+    },
+    thread::ThreadId,
+    time::Instant,
+};
+
+/// How often the watchdog looks at the heartbeat
+#[cfg(any(feature = "frontend", test))]
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// What the executor is doing right now, updated around every batch so another thread can tell when it stops moving
+#[derive(Debug)]
+pub struct ExecutorHeartbeat {
+    epoch: Instant,
+    /// Milliseconds after the epoch the running batch started, 0 if no batch is running
+    batch_start: AtomicU64,
+    /// Index of the task of the running batch
+    task: AtomicUsize,
+    /// Changes when the executor adds or removes tasks
+    task_names: Mutex<Vec<&'static str>>,
+    /// Filled in by the first batch, the executor may not live on the thread that made it
+    thread: Mutex<Option<(ThreadId, Option<String>)>>,
+}
+
+impl ExecutorHeartbeat {
+    pub fn new(task_names: Vec<&'static str>) -> Self {
+        Self {
+            epoch: Instant::now(),
+            batch_start: AtomicU64::new(0),
+            task: AtomicUsize::new(0),
+            task_names: Mutex::new(task_names),
+            thread: Mutex::new(None),
+        }
+    }
+
+    pub fn begin_batch(&self, task: usize) {
+        self.task.store(task, Ordering::Relaxed);
+        // Zero means idle, so a batch in the first millisecond is rounded up
+        self.batch_start.store(
+            (self.epoch.elapsed().as_millis() as u64).max(1),
+            Ordering::Release,
+        );
+
+        let mut thread = self.thread.lock().unwrap();
+        if thread.is_none() {
+            let current = std::thread::current();
+            *thread = Some((current.id(), current.name().map(ToString::to_string)));
+        }
+    }
+
+    pub fn end_batch(&self) {
+        self.batch_start.store(0, Ordering::Release);
+    }
+
+    pub fn set_task_names(&self, task_names: Vec<&'static str>) {
+        *self.task_names.lock().unwrap() = task_names;
+    }
+
+    /// The running batch, its task name and how long it has run for. Time the executor spends not running a batch,
+    /// like while paused, is never a stall
+    #[cfg(any(feature = "frontend", test))]
+    fn running_batch(&self) -> Option<(u64, &'static str, Duration)> {
+        let batch_start = self.batch_start.load(Ordering::Acquire);
+        if batch_start == 0 {
+            return None;
+        }
+
+        let elapsed = (self.epoch.elapsed().as_millis() as u64).saturating_sub(batch_start);
+
+        Some((
+            batch_start,
+            self.task_names.lock().unwrap()[self.task.load(Ordering::Relaxed)],
+            Duration::from_millis(elapsed),
+        ))
+    }
+}
+
+/// A batch that ran for longer than the watchdog allows but finished in the end
+#[cfg(any(feature = "frontend", test))]
+#[derive(Debug, Clone)]
+pub struct StallReport {
+    pub task: &'static str,
+    pub duration: Duration,
+}
+
+/// Watches a executor from its own thread and reports batches that take too long, which is almost always a lock
+/// cycle between components or a component stuck in a loop
+#[cfg(any(feature = "frontend", test))]
+pub struct Watchdog {
+    stop: Arc<AtomicBool>,
+    /// Stalls that ended, for the runtime to show once it is responsive again. Ones that never end are only logged
+    reports: Arc<Mutex<Vec<StallReport>>>,
+    thread: Option<JoinHandle<()>>,
+}
+
+#[cfg(any(feature = "frontend", test))]
+impl Watchdog {
+    pub fn spawn(heartbeat: Arc<ExecutorHeartbeat>, timeout: Duration) -> Self {
+        let stop = Arc::new(AtomicBool::new(false));
+        let reports = Arc::new(Mutex::new(Vec::new()));
+
+        let thread = std::thread::Builder::new()
+            .name("watchdog".to_string())
+            .spawn({
+                let stop = stop.clone();
+                let reports = reports.clone();
+
+                move || watch(&heartbeat, timeout, &stop, &reports)
+            })
+            .unwrap();
+
+        Self {
+            stop,
+            reports,
+            thread: Some(thread),
+        }
+    }
+
+    pub fn take_reports(&self) -> Vec<StallReport> {
+        std::mem::take(&mut self.reports.lock().unwrap())
+    }
+}
+
+#[cfg(any(feature = "frontend", test))]
+impl Drop for Watchdog {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+
+        if let Some(thread) = self.thread.take() {
+            thread.thread().unpark();
+            let _ = thread.join();
+        }
+    }
+}
+
+#[cfg(any(feature = "frontend", test))]
+fn watch(
+    heartbeat: &ExecutorHeartbeat,
+    timeout: Duration,
+    stop: &AtomicBool,
+    reports: &Mutex<Vec<StallReport>>,
+) {
+    // Start of the batch that was reported, so each stall is only reported once
+    let mut stalled_batch: Option<(u64, &'static str, Duration)> = None;
+
+    while !stop.load(Ordering::Relaxed) {
+        std::thread::park_timeout(POLL_INTERVAL);
+
+        let running_batch = heartbeat.running_batch();
+
+        match (stalled_batch, running_batch) {
+            // Still the same stall
+            (Some((stalled_start, task, _)), Some((batch_start, _, duration)))
+                if stalled_start == batch_start =>
+            {
+                stalled_batch = Some((batch_start, task, duration));
+            }
+            (Some((_, task, duration)), _) => {
+                tracing::warn!(
+                    component = task,
+                    "Task {} recovered after stalling for {:?}",
+                    task,
+                    duration
+                );
+                reports.lock().unwrap().push(StallReport { task, duration });
+                stalled_batch = None;
+            }
+            (None, Some((batch_start, task, duration))) if duration >= timeout => {
+                report_stall(heartbeat, task, duration);
+                stalled_batch = Some((batch_start, task, duration));
+            }
+            (None, _) => {}
+        }
+    }
+}
+
+#[cfg(any(feature = "frontend", test))]
+fn report_stall(heartbeat: &ExecutorHeartbeat, task: &'static str, duration: Duration) {
+    let thread = heartbeat.thread.lock().unwrap().clone();
+
+    tracing::error!(
+        component = task,
+        "Task {} has been running a single batch for {:?}, the machine is likely deadlocked",
+        task,
+        duration
+    );
+    tracing::error!(
+        "Executor thread: {:?}, tasks on it: {}",
+        thread,
+        heartbeat.task_names.lock().unwrap().join(", ")
+    );
+    // std can only capture the stack of the calling thread, and the one that matters is stuck
+    tracing::error!("Attach a debugger to the executor thread to see where it is stuck");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reports_stalled_batch() {
+        let heartbeat = Arc::new(ExecutorHeartbeat::new(vec!["fast", "stuck"]));
+        let watchdog = Watchdog::spawn(heartbeat.clone(), Duration::from_millis(100));
+
+        heartbeat.begin_batch(0);
+        heartbeat.end_batch();
+        heartbeat.begin_batch(1);
+        std::thread::sleep(POLL_INTERVAL * 3);
+        assert!(watchdog.take_reports().is_empty());
+
+        heartbeat.end_batch();
+        std::thread::sleep(POLL_INTERVAL * 2);
+
+        let reports = watchdog.take_reports();
+        assert_eq!(reports.len(), 1);
+        assert_eq!(reports[0].task, "stuck");
+        assert!(reports[0].duration >= Duration::from_millis(100));
+    }
+}
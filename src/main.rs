@@ -1,49 +1,93 @@
 // Required for audio support
 #![cfg_attr(nintendo_3ds, feature(allocator_api))]
+// The core build is what miri checks, anything unsafe there belongs behind a platform backend
+#![cfg_attr(feature = "core-only", forbid(unsafe_code))]
+
+#[cfg(all(feature = "core-only", feature = "jit"))]
+compile_error!("The jit runs generated host code, which can't be done without unsafe code");
+#[cfg(all(feature = "core-only", feature = "frontend"))]
+compile_error!("The core build has no frontend, build it with `--no-default-features`");
+#[cfg(not(any(feature = "core-only", feature = "frontend")))]
+compile_error!(
+    "Machines need either the frontend or the headless runtime of `core-only` to run on"
+);
 
 use config::GlobalConfig;
-use env::{IMPORTED_ROM_DIRECTORY, LOG_LOCATION, ROM_DATABASE_PATH, STORAGE_DIRECTORY};
+use env::{
+    CACHE_DIRECTORY, IMPORTED_ROM_DIRECTORY, LOG_LOCATION, ROM_DATABASE_PATH, STORAGE_DIRECTORY,
+};
+#[cfg(feature = "frontend")]
+use event_log::EventLogLayer;
 use rom::RomManager;
-use runtime::{launch_gui, InitialGuiState};
+#[cfg(not(feature = "core-only"))]
+use runtime::InitialGuiState;
+#[cfg(nintendo_3ds)]
+use runtime::{launch_gui, SoftwareRendering};
 use std::{
     error::Error,
     fs::{create_dir_all, File},
     ops::Deref,
+    process::ExitCode,
     sync::{Arc, RwLock},
 };
 use tracing::Level;
-use tracing_subscriber::EnvFilter;
-
-use runtime::SoftwareRendering;
+#[cfg(feature = "frontend")]
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::{util::SubscriberInitExt, EnvFilter};
 
+#[cfg(any(desktop, feature = "core-only"))]
+mod atomic_write;
+#[cfg(any(feature = "frontend", test))]
+mod cheats;
 #[cfg(desktop)]
 mod cli;
 mod component;
 mod config;
+mod debugger;
 mod env;
+#[cfg(feature = "frontend")]
+mod event_log;
+#[cfg(feature = "frontend")]
 mod gui;
 mod input;
 mod machine;
+// Only the delay setting is wired up until there is a session to drive the rest
+#[cfg(any(feature = "frontend", test))]
+#[allow(dead_code)]
+mod netplay;
+mod rewind;
 mod rom;
 mod runtime;
+#[cfg(any(feature = "frontend", test))]
+mod scripting;
 mod snapshot;
+mod symbols;
 mod task;
 
-fn main() -> Result<(), Box<dyn Error>> {
+fn main() -> Result<ExitCode, Box<dyn Error>> {
     #[cfg(nintendo_3ds)]
     ctru::applets::error::set_panic_hook(true);
 
     let _ = create_dir_all(STORAGE_DIRECTORY.deref());
+    let _ = create_dir_all(CACHE_DIRECTORY.deref());
     let log_file = File::create(LOG_LOCATION.deref())?;
     let (log_writer, _log_writer_guard) = tracing_appender::non_blocking(log_file);
-    tracing_subscriber::fmt()
+    let subscriber = tracing_subscriber::fmt()
         .with_max_level(Level::INFO)
         .with_env_filter(EnvFilter::from_default_env().add_directive(Level::INFO.into()))
         .with_writer(log_writer)
         .with_ansi(false)
-        .init();
+        .finish();
+    // Feeds the event log of the GUI
+    #[cfg(feature = "frontend")]
+    let subscriber = subscriber.with(EventLogLayer);
+    subscriber.init();
 
     tracing::info!("MultiEMU v{}", env!("CARGO_PKG_VERSION"));
+    tracing::info!("Using {}", runtime::simd::describe());
+
+    #[cfg(desktop)]
+    env::migrate_legacy_layout();
 
     let mut global_config = GlobalConfig::default();
     let _ = global_config.load();
@@ -58,11 +102,11 @@ fn main() -> Result<(), Box<dyn Error>> {
         let cli_arguments = Cli::parse();
 
         if let Some(action) = cli_arguments.action {
-            handle_cli(action, global_config.clone());
+            let status = handle_cli(action, global_config.clone());
 
             global_config.read().unwrap().save()?;
 
-            return Ok(());
+            return Ok(status.into());
         }
     }
 
@@ -71,38 +115,229 @@ fn main() -> Result<(), Box<dyn Error>> {
     create_dir_all(IMPORTED_ROM_DIRECTORY.deref())?;
     let _ = rom_manager.load_rom_paths(IMPORTED_ROM_DIRECTORY.deref());
     let _ = rom_manager.load_rom_info(ROM_DATABASE_PATH.deref());
+    {
+        let global_config = global_config.read().unwrap();
+        rom::import::scan_watch_folders(
+            &mut rom_manager,
+            global_config.import_policy,
+            &global_config.watch_folders,
+        );
+    }
     let rom_manager = Arc::new(rom_manager);
 
-    if global_config.read().unwrap().hardware_acceleration {
-        #[cfg(desktop)]
-        {
-            use runtime::desktop::display::vulkan::VulkanRendering;
+    launch(rom_manager, &global_config);
 
-            launch_gui::<VulkanRendering>(
-                rom_manager,
-                InitialGuiState::MainMenu,
-                global_config.clone(),
-            );
+    global_config.read().unwrap().save()?;
+
+    Ok(ExitCode::SUCCESS)
+}
+
+#[cfg(not(feature = "core-only"))]
+fn launch(rom_manager: Arc<RomManager>, global_config: &Arc<RwLock<GlobalConfig>>) {
+    #[cfg(desktop)]
+    if global_config.read().unwrap().backend_benchmark.is_none() {
+        runtime::backend_benchmark::run_backend_benchmark(global_config);
+    }
+
+    #[cfg(desktop)]
+    runtime::desktop::launch_gui_with_selected_backend(
+        rom_manager,
+        InitialGuiState::MainMenu,
+        global_config.clone(),
+    );
+
+    // FIXME: Implement the hardware accelerated backends with the gpu rendering plugins once that is ready
+    #[cfg(nintendo_3ds)]
+    launch_gui::<SoftwareRendering>(
+        rom_manager,
+        InitialGuiState::MainMenu,
+        global_config.clone(),
+    );
+}
+
+/// Runs the game with the ROMs given on the command line for a while without a window, saving a snapshot halfway and
+/// loading it back at the end. Running it under miri with `cargo miri run` checks a whole machine rather than what the
+/// tests reach, snapshots are written to disk so that needs `-Zmiri-disable-isolation`
+#[cfg(feature = "core-only")]
+fn launch(rom_manager: Arc<RomManager>, global_config: &Arc<RwLock<GlobalConfig>>) {
+    use input::InputState;
+    use machine::{
+        definitions::construct_machine,
+        executor::{single::SingleThreadedExecutor, Executor},
+    };
+    use rom::RomId;
+    use runtime::{headless::SoftwareState, SoftwareRendering};
+    use snapshot::SnapshotManager;
+    use std::time::Duration;
+
+    /// Enough for a game to get past its boot code
+    const FRAMES: usize = 60;
+
+    let user_specified_roms: Vec<RomId> = match std::env::args()
+        .skip(1)
+        .map(|rom_id| rom_id.parse())
+        .collect()
+    {
+        Ok(user_specified_roms) => user_specified_roms,
+        Err(error) => {
+            tracing::error!("ROMs are given by their hash: {}", error);
+            return;
         }
+    };
 
-        #[cfg(nintendo_3ds)]
-        {
-            // FIXME: Implement this with the gpu rendering plugins once that is ready
-            launch_gui::<SoftwareRendering>(
-                rom_manager,
-                InitialGuiState::MainMenu,
-                global_config.clone(),
-            );
+    let Some(rom_information) = user_specified_roms
+        .first()
+        .and_then(|rom_id| rom_manager.rom_information.get(rom_id))
+    else {
+        tracing::info!(
+            "Built without platform backends, {} ROMs are known but none was given to run",
+            rom_manager.rom_information.len()
+        );
+        return;
+    };
+
+    let mut machine = match construct_machine::<SoftwareRendering>(
+        rom_information.system,
+        rom_manager.clone(),
+        user_specified_roms,
+    ) {
+        Ok(machine) => machine,
+        Err(error) => {
+            tracing::error!("{}", error);
+            return;
+        }
+    };
+    let Some(frame_task) = machine.frame_task else {
+        tracing::error!("{} has no display to run frames of", rom_information.system);
+        return;
+    };
+
+    machine.initialize_displays(&mut SoftwareState::headless(global_config.clone()));
+    let snapshot_manager = SnapshotManager::new(
+        machine.fingerprint.clone(),
+        machine.snapshotable_components.clone(),
+    );
+    let snapshot_path = snapshot_manager.slot_path(0);
+    let rewind_buffer = {
+        let global_config = global_config.read().unwrap();
+        machine.insert_rewind(global_config.rewind_depth, global_config.rewind_interval)
+    };
+    let audio_buffers: Vec<_> = machine
+        .audio_components
+        .iter()
+        .map(|component| component.lock().unwrap().audio_buffer())
+        .collect();
+
+    let mut executor = SingleThreadedExecutor::new(
+        std::mem::take(&mut machine.tasks),
+        machine.memory_translation_table.clone(),
+    );
+    if let Some(rewind_buffer) = rewind_buffer {
+        executor.set_rewind_buffer(rewind_buffer);
+    }
+
+    for frame in 0..FRAMES {
+        // Every input goes down for a frame and back up, so the input handling of the components runs too
+        if frame == FRAMES / 4 {
+            for controller in &machine.controllers {
+                let inputs: Vec<_> = controller.iter_released().collect();
+
+                for input in inputs {
+                    controller.set_input_state(input, InputState::Digital(true));
+                }
+            }
+        } else if frame == FRAMES / 4 + 1 {
+            for controller in &machine.controllers {
+                controller.release_all();
+            }
         }
-    } else {
-        launch_gui::<SoftwareRendering>(
-            rom_manager,
-            InitialGuiState::MainMenu,
-            global_config.clone(),
+
+        executor.run_single_frame(frame_task);
+        // Nothing plays it, but the buffers would fill up otherwise
+        for audio_buffer in &audio_buffers {
+            while audio_buffer.pop().is_some() {}
+        }
+
+        if frame == FRAMES / 2 {
+            let thumbnail = machine.display_components.first().map(|display| {
+                display
+                    .lock()
+                    .unwrap()
+                    .display_data()
+                    .to_pixels()
+                    .into_owned()
+            });
+
+            if let Err(error) =
+                snapshot_manager.save(&mut executor, &snapshot_path, thumbnail.as_ref())
+            {
+                tracing::error!("Could not save the snapshot: {}", error);
+                return;
+            }
+        }
+    }
+
+    let report = executor.schedule_report();
+    let span = report
+        .batches
+        .first()
+        .map(|batch| batch.age)
+        .unwrap_or_default();
+    tracing::info!(
+        "Executor ticks are {:?} long and line up again every {} ticks",
+        report.tick_real_time,
+        report.rollover_tick
+    );
+    for (index, task) in report.tasks.iter().enumerate() {
+        let (batch_count, ticks, real_time) = report
+            .batches
+            .iter()
+            .filter(|batch| batch.task == index)
+            .fold(
+                (0, 0, Duration::ZERO),
+                |(batch_count, ticks, real_time), batch| {
+                    (
+                        batch_count + 1,
+                        ticks + batch.batch_size,
+                        real_time + batch.real_time,
+                    )
+                },
+            );
+
+        tracing::info!(
+            "{} at {} Hz ticks every {} executor ticks, {} ticks in {} batches took {:?} of the last {:?}",
+            task.name,
+            task.tick_rate,
+            task.period,
+            ticks,
+            batch_count,
+            real_time,
+            span
         );
     }
 
-    global_config.read().unwrap().save()?;
+    executor.rewind();
+    if let Err(error) = snapshot_manager.load(&mut executor, &snapshot_path) {
+        tracing::error!("Could not load the snapshot back: {}", error);
+        return;
+    }
+    executor.run_single_frame(frame_task);
+    // Like pressing the reset button, which has to leave the machine runnable too
+    machine.queryable_components.reset_all();
+    executor.run_single_frame(frame_task);
+
+    for (name, debug_target) in &machine.debug_targets {
+        match &debug_target.debug_state().lock().unwrap().fault {
+            Some(fault) => tracing::error!(
+                "{} faulted at {:#x}: {}",
+                name,
+                fault.address,
+                fault.message
+            ),
+            None => tracing::info!("{} stopped with {:x?}", name, debug_target.registers()),
+        }
+    }
+    machine.shutdown(executor);
 
-    Ok(())
+    tracing::info!("Ran {} frames of {}", FRAMES, rom_information.system);
 }
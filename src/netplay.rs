@@ -0,0 +1,218 @@
+//! Parts of lockstep netplay that don't need a connection
+//!
+//! Every peer runs each frame with the inputs of all of them, so local inputs are held back a few frames to give them
+//! time to reach the others. How many is the input delay, which the peers agree on when the session starts and can
+//! change while it runs
+
+use serde::{Deserialize, Serialize};
+use std::{collections::VecDeque, time::Duration};
+
+/// More than this feels worse than not playing together at all
+pub const MAX_INPUT_DELAY: u8 = 15;
+/// Round trips the latency is measured over
+const LATENCY_SAMPLES: usize = 32;
+
+/// What the peers agree on, once before the first frame and again every time one of them changes it
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SessionParameters {
+    pub input_delay: u8,
+    /// The first frame these apply to, far enough ahead that every peer switches on the same one
+    pub starting_frame: u64,
+}
+
+impl SessionParameters {
+    /// Every peer proposes the delay it wants, the session takes the largest so nobody stalls waiting on inputs
+    pub fn negotiate(proposals: impl IntoIterator<Item = u8>, starting_frame: u64) -> Self {
+        Self {
+            input_delay: proposals
+                .into_iter()
+                .max()
+                .unwrap_or_default()
+                .min(MAX_INPUT_DELAY),
+            starting_frame,
+        }
+    }
+}
+
+/// Recent round trips to a peer
+#[derive(Debug, Default)]
+pub struct LatencyTracker {
+    samples: VecDeque<Duration>,
+}
+
+impl LatencyTracker {
+    pub fn record(&mut self, round_trip: Duration) {
+        if self.samples.len() == LATENCY_SAMPLES {
+            self.samples.pop_front();
+        }
+
+        self.samples.push_back(round_trip);
+    }
+
+    /// The median, so a single late packet doesn't throw it off
+    pub fn round_trip_time(&self) -> Option<Duration> {
+        let mut samples: Vec<_> = self.samples.iter().copied().collect();
+        samples.sort();
+
+        samples.get(samples.len() / 2).copied()
+    }
+
+    /// Frames it takes inputs to reach the peer, which is half a round trip
+    pub fn suggested_input_delay(&self, frame_time: Duration) -> Option<u8> {
+        let one_way = self.round_trip_time()? / 2;
+        let frames = one_way.as_nanos().div_ceil(frame_time.as_nanos().max(1));
+
+        Some(frames.min(MAX_INPUT_DELAY as u128) as u8)
+    }
+}
+
+/// Picks the frames local inputs apply to, and holds them until then
+///
+/// Inputs are states like a held button rather than presses, so when the delay shrinks the inputs of frames that
+/// already have one are dropped, and when it grows the last one is repeated over the gap. Either way every frame gets
+/// exactly one input from every peer
+#[derive(Debug)]
+pub struct InputDelayQueue<T> {
+    parameters: SessionParameters,
+    /// Waiting for their frame to come around
+    changes: VecDeque<SessionParameters>,
+    pending: VecDeque<(u64, T)>,
+    /// The last frame a input was picked for, along with that input
+    last_scheduled: Option<(u64, T)>,
+}
+
+impl<T: Clone> InputDelayQueue<T> {
+    pub fn new(parameters: SessionParameters) -> Self {
+        Self {
+            parameters,
+            changes: VecDeque::new(),
+            pending: VecDeque::new(),
+            last_scheduled: None,
+        }
+    }
+
+    /// The delay in effect as of the last input that was pushed
+    pub fn input_delay(&self) -> u8 {
+        self.parameters.input_delay
+    }
+
+    /// Switches to new parameters once their starting frame is reached
+    pub fn change(&mut self, parameters: SessionParameters) {
+        self.changes
+            .retain(|change| change.starting_frame < parameters.starting_frame);
+        self.changes.push_back(parameters);
+    }
+
+    /// Takes the input made on `frame`, returning the frames it applies to so they can be sent to the peers
+    pub fn push(&mut self, frame: u64, input: T) -> Vec<(u64, T)> {
+        while self
+            .changes
+            .front()
+            .is_some_and(|change| change.starting_frame <= frame)
+        {
+            self.parameters = self.changes.pop_front().unwrap();
+        }
+
+        let target = frame + self.parameters.input_delay as u64;
+        let mut scheduled = Vec::new();
+
+        match self.last_scheduled.take() {
+            // Already has an input, which the peers may have by now
+            Some((last_frame, last_input)) if target <= last_frame => {
+                self.last_scheduled = Some((last_frame, last_input));
+                return scheduled;
+            }
+            Some((last_frame, last_input)) => {
+                scheduled.extend((last_frame + 1..target).map(|frame| (frame, last_input.clone())));
+            }
+            None => {}
+        }
+
+        scheduled.push((target, input.clone()));
+        self.last_scheduled = Some((target, input));
+        self.pending.extend(scheduled.iter().cloned());
+
+        scheduled
+    }
+
+    /// The local input for `frame`, None until one was pushed for it. Inputs of earlier frames are discarded
+    pub fn take(&mut self, frame: u64) -> Option<T> {
+        while self
+            .pending
+            .front()
+            .is_some_and(|(scheduled_frame, _)| *scheduled_frame < frame)
+        {
+            self.pending.pop_front();
+        }
+
+        if self
+            .pending
+            .front()
+            .is_some_and(|(scheduled_frame, _)| *scheduled_frame == frame)
+        {
+            return self.pending.pop_front().map(|(_, input)| input);
+        }
+
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parameters(input_delay: u8, starting_frame: u64) -> SessionParameters {
+        SessionParameters {
+            input_delay,
+            starting_frame,
+        }
+    }
+
+    #[test]
+    fn negotiation_takes_the_largest_delay() {
+        assert_eq!(SessionParameters::negotiate([2, 4, 3], 0), parameters(4, 0));
+        assert_eq!(
+            SessionParameters::negotiate([40], 0).input_delay,
+            MAX_INPUT_DELAY
+        );
+    }
+
+    #[test]
+    fn suggested_delay_covers_half_a_round_trip() {
+        let mut tracker = LatencyTracker::default();
+        assert_eq!(
+            tracker.suggested_input_delay(Duration::from_millis(16)),
+            None
+        );
+
+        for milliseconds in [70, 70, 500] {
+            tracker.record(Duration::from_millis(milliseconds));
+        }
+
+        assert_eq!(tracker.round_trip_time(), Some(Duration::from_millis(70)));
+        assert_eq!(
+            tracker.suggested_input_delay(Duration::from_millis(16)),
+            Some(3)
+        );
+    }
+
+    #[test]
+    fn every_frame_gets_one_input_across_changes() {
+        let mut queue = InputDelayQueue::new(parameters(2, 0));
+        queue.change(parameters(4, 2));
+        queue.change(parameters(1, 4));
+
+        let scheduled: Vec<_> = (0..8).flat_map(|frame| queue.push(frame, frame)).collect();
+
+        // Growing repeats the input of frame 1 over the gap, shrinking drops the inputs of frames 4 to 6
+        assert_eq!(
+            scheduled,
+            [(2, 0), (3, 1), (4, 1), (5, 1), (6, 2), (7, 3), (8, 7)]
+        );
+        assert_eq!(queue.input_delay(), 1);
+
+        assert_eq!(queue.take(3), Some(1));
+        assert_eq!(queue.take(6), Some(2));
+        assert_eq!(queue.take(9), None);
+    }
+}
@@ -0,0 +1,230 @@
+use crate::{component::snapshot::SnapshotableComponent, snapshot::SnapshotTaskInformation};
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Mutex},
+};
+
+/// A moment the machine can be rewound to
+struct RewindPoint {
+    /// In the same order as [RewindBuffer::components]
+    components: Vec<rmpv::Value>,
+    /// Filled in by the executor right after the components were captured, as only it can reach the tasks
+    task_state: Option<RewindTaskState>,
+}
+
+/// What the executor needs to continue from a rewind point
+#[derive(Debug, Clone)]
+pub struct RewindTaskState {
+    /// Executor ticks since the machine started. Unlike [SnapshotTaskInformation::current_cycle] this never rolls
+    /// over, so points can be ordered by it
+    pub elapsed_ticks: u64,
+    pub task_info: SnapshotTaskInformation,
+}
+
+/// Ring buffer of recent in memory snapshots of a running machine
+///
+/// Unlike [crate::snapshot::SnapshotManager] nothing here touches the disk or checks fingerprints, as the points
+/// never outlive the machine that made them
+pub struct RewindBuffer {
+    components: Vec<Arc<Mutex<dyn SnapshotableComponent>>>,
+    points: VecDeque<RewindPoint>,
+    depth: usize,
+    /// Games can turn rewinding off without the machine losing the task
+    enabled: bool,
+}
+
+impl RewindBuffer {
+    pub fn new(components: Vec<Arc<Mutex<dyn SnapshotableComponent>>>, depth: usize) -> Self {
+        Self {
+            components,
+            points: VecDeque::with_capacity(depth),
+            depth,
+            enabled: true,
+        }
+    }
+
+    /// Disabling drops every point, so turning it back on doesn't rewind across the gap
+    #[cfg(feature = "frontend")]
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+
+        if !enabled {
+            self.points.clear();
+        }
+    }
+
+    /// Captures the components, dropping the oldest point if the buffer is full
+    pub fn capture(&mut self) {
+        if self.depth == 0 || !self.enabled {
+            return;
+        }
+
+        if self.points.len() == self.depth {
+            self.points.pop_front();
+        }
+
+        let components = self
+            .components
+            .iter()
+            .map(|component| component.lock().unwrap().save_snapshot())
+            .collect();
+
+        self.points.push_back(RewindPoint {
+            components,
+            task_state: None,
+        });
+    }
+
+    /// If the latest point is waiting on the executor for the task state
+    pub fn awaiting_task_info(&self) -> bool {
+        self.points
+            .back()
+            .is_some_and(|point| point.task_state.is_none())
+    }
+
+    pub fn complete_capture(&mut self, task_state: RewindTaskState) {
+        if let Some(point) = self.points.back_mut() {
+            point.task_state = Some(task_state);
+        }
+    }
+
+    /// Loads the latest complete point into the components and returns the task state for the executor to load
+    ///
+    /// The point is consumed so repeated calls go further back
+    pub fn step_back(&mut self) -> Option<RewindTaskState> {
+        // A point the executor never completed can't be restored
+        while self.awaiting_task_info() {
+            self.points.pop_back();
+        }
+
+        let point = self.points.pop_back()?;
+
+        for (component, state) in self.components.iter().zip(point.components) {
+            component.lock().unwrap().load_snapshot(state);
+        }
+
+        point.task_state
+    }
+
+    /// Loads the latest complete point from before the executor reached elapsed_ticks, for replaying from it
+    ///
+    /// Points from elapsed_ticks onwards are dropped as replaying captures them again, the loaded one is kept
+    #[cfg(any(feature = "frontend", test))]
+    pub fn restore_before(&mut self, elapsed_ticks: u64) -> Option<RewindTaskState> {
+        while self.points.back().is_some_and(|point| {
+            point
+                .task_state
+                .as_ref()
+                .is_none_or(|task_state| task_state.elapsed_ticks >= elapsed_ticks)
+        }) {
+            self.points.pop_back();
+        }
+
+        let point = self.points.back()?;
+
+        for (component, state) in self.components.iter().zip(&point.components) {
+            component.lock().unwrap().load_snapshot(state.clone());
+        }
+
+        point.task_state.clone()
+    }
+
+    /// For when the points can't be restored anymore, like after the machine changed shape
+    pub fn clear(&mut self) {
+        self.points.clear();
+    }
+
+    #[cfg(test)]
+    pub fn len(&self) -> usize {
+        self.points.len()
+    }
+
+    #[cfg(test)]
+    pub fn is_empty(&self) -> bool {
+        self.points.is_empty()
+    }
+}
+
+#[cfg(all(test, feature = "chip8"))]
+mod tests {
+    use super::*;
+    use crate::{
+        component::{definitions::chip8::timer::Chip8Timer, FromConfig},
+        rom::RomManager,
+    };
+    use std::collections::HashMap;
+
+    #[test]
+    fn rewind_steps_back_in_order() {
+        let timer = Arc::new(Mutex::new(Chip8Timer::from_config(
+            Arc::new(RomManager::default()),
+            (),
+        )));
+        let mut rewind_buffer = RewindBuffer::new(vec![timer.clone()], 3);
+
+        for value in [1, 2, 3, 4] {
+            timer.lock().unwrap().handle().set(value);
+            rewind_buffer.capture();
+            rewind_buffer.complete_capture(RewindTaskState {
+                elapsed_ticks: value as u64,
+                task_info: SnapshotTaskInformation {
+                    current_cycle: value as u32,
+                    tasks: HashMap::new(),
+                },
+            });
+        }
+        // The oldest point fell out
+        assert_eq!(rewind_buffer.len(), 3);
+
+        // Never completed, so skipped
+        timer.lock().unwrap().handle().set(5);
+        rewind_buffer.capture();
+
+        assert_eq!(
+            rewind_buffer.step_back().unwrap().task_info.current_cycle,
+            4
+        );
+        assert_eq!(timer.lock().unwrap().handle().get(), 4);
+        assert_eq!(
+            rewind_buffer.step_back().unwrap().task_info.current_cycle,
+            3
+        );
+        assert_eq!(timer.lock().unwrap().handle().get(), 3);
+        assert!(rewind_buffer.step_back().is_none());
+    }
+
+    #[test]
+    fn restore_before_keeps_the_point() {
+        let timer = Arc::new(Mutex::new(Chip8Timer::from_config(
+            Arc::new(RomManager::default()),
+            (),
+        )));
+        let mut rewind_buffer = RewindBuffer::new(vec![timer.clone()], 3);
+
+        for value in [10, 20, 30] {
+            timer.lock().unwrap().handle().set(value);
+            rewind_buffer.capture();
+            rewind_buffer.complete_capture(RewindTaskState {
+                elapsed_ticks: value as u64,
+                task_info: SnapshotTaskInformation {
+                    current_cycle: value as u32,
+                    tasks: HashMap::new(),
+                },
+            });
+        }
+
+        // A point at exactly the tick is not before it
+        assert_eq!(rewind_buffer.restore_before(30).unwrap().elapsed_ticks, 20);
+        assert_eq!(timer.lock().unwrap().handle().get(), 20);
+        assert_eq!(rewind_buffer.len(), 2);
+
+        // Stepping back again replays from the same point
+        timer.lock().unwrap().handle().set(25);
+        assert_eq!(rewind_buffer.restore_before(25).unwrap().elapsed_ticks, 20);
+        assert_eq!(timer.lock().unwrap().handle().get(), 20);
+        assert_eq!(rewind_buffer.len(), 2);
+
+        assert!(rewind_buffer.restore_before(10).is_none());
+        assert!(rewind_buffer.is_empty());
+    }
+}
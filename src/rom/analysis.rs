@@ -0,0 +1,396 @@
+use super::{AtariSystem, GameSystem, NintendoSystem, SegaSystem};
+#[cfg(feature = "nes")]
+use crate::component::definitions::nes::cartridge::{
+    NesHeader, HEADER_SIZE, PRG_ROM_START, PRG_ROM_UNIT, TRAINER_SIZE,
+};
+use serde::{Deserialize, Serialize};
+use std::{fmt::Display, path::Path};
+use strum::Display;
+
+#[derive(
+    Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Display,
+)]
+pub enum VectorKind {
+    /// Where execution starts when there is no reset vector
+    Entry,
+    Reset,
+    #[strum(serialize = "NMI")]
+    Nmi,
+    #[strum(serialize = "IRQ")]
+    Irq,
+}
+
+/// Where the processor starts or jumps to on a interrupt
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct RomVector {
+    pub kind: VectorKind,
+    pub address: u16,
+}
+
+/// Something about the file that means it probably won't boot, or won't boot right
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum RomProblem {
+    BadHeaderChecksum {
+        expected: u16,
+        actual: u16,
+    },
+    BadGlobalChecksum {
+        expected: u16,
+        actual: u16,
+    },
+    /// The header the system needs is missing or unreadable
+    BadHeader,
+    /// Bigger than the header says, usually junk a dumper read past the end of the chip
+    OverdumpSuspected {
+        expected: usize,
+        actual: usize,
+    },
+    Truncated {
+        expected: usize,
+        actual: usize,
+    },
+    /// A vector points somewhere the ROM isn't mapped
+    VectorOutsideRom(RomVector),
+}
+
+impl Display for RomProblem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RomProblem::BadHeaderChecksum { expected, actual } => write!(
+                f,
+                "Bad header checksum, the header says {:#x} but it is {:#x}",
+                expected, actual
+            ),
+            RomProblem::BadGlobalChecksum { expected, actual } => write!(
+                f,
+                "Bad global checksum, the header says {:#x} but it is {:#x}",
+                expected, actual
+            ),
+            RomProblem::BadHeader => write!(f, "Missing or unreadable header"),
+            RomProblem::OverdumpSuspected { expected, actual } => write!(
+                f,
+                "Overdump suspected, the file is {} bytes but should be {}",
+                actual, expected
+            ),
+            RomProblem::Truncated { expected, actual } => write!(
+                f,
+                "Truncated, the file is {} bytes but should be {}",
+                actual, expected
+            ),
+            RomProblem::VectorOutsideRom(vector) => write!(
+                f,
+                "The {} vector points outside the ROM to {:#06x}",
+                vector.kind, vector.address
+            ),
+        }
+    }
+}
+
+/// What could be learned from a ROM without running it, done once on import and kept in the database
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct RomAnalysis {
+    pub vectors: Vec<RomVector>,
+    /// Switchable banks of the size the system banks in, if the system has any
+    pub bank_count: Option<usize>,
+    pub problems: Vec<RomProblem>,
+}
+
+/// None if the file can't be read
+pub fn analyze_rom_file(path: &Path, system: GameSystem) -> Option<RomAnalysis> {
+    match std::fs::read(path) {
+        Ok(rom) => Some(analyze_rom(system, &rom)),
+        Err(error) => {
+            tracing::warn!("Could not analyze {}: {}", path.display(), error);
+            None
+        }
+    }
+}
+
+/// Systems without a analysis get a empty one, as do the ones left out of the build
+pub fn analyze_rom(system: GameSystem, rom: &[u8]) -> RomAnalysis {
+    match system {
+        #[cfg(feature = "nes")]
+        GameSystem::Nintendo(NintendoSystem::NintendoEntertainmentSystem) => analyze_nes(rom),
+        GameSystem::Nintendo(NintendoSystem::GameBoy | NintendoSystem::GameBoyColor) => {
+            analyze_game_boy(rom)
+        }
+        GameSystem::Sega(SegaSystem::MasterSystem | SegaSystem::GameGear) => {
+            analyze_master_system(rom)
+        }
+        GameSystem::Atari(AtariSystem::Atari2600) => analyze_atari_2600(rom),
+        _ => RomAnalysis::default(),
+    }
+}
+
+fn read_vector(rom: &[u8], offset: usize, kind: VectorKind) -> Option<RomVector> {
+    let bytes = rom.get(offset..offset + 2)?;
+
+    Some(RomVector {
+        kind,
+        address: u16::from_le_bytes([bytes[0], bytes[1]]),
+    })
+}
+
+fn size_problem(expected: usize, actual: usize) -> Option<RomProblem> {
+    match actual.cmp(&expected) {
+        std::cmp::Ordering::Less => Some(RomProblem::Truncated { expected, actual }),
+        std::cmp::Ordering::Equal => None,
+        std::cmp::Ordering::Greater => Some(RomProblem::OverdumpSuspected { expected, actual }),
+    }
+}
+
+// https://www.nesdev.org/wiki/CPU_memory_map
+#[cfg(feature = "nes")]
+fn analyze_nes(rom: &[u8]) -> RomAnalysis {
+    let mut analysis = RomAnalysis::default();
+
+    let Some(header) = rom
+        .get(..HEADER_SIZE)
+        .and_then(|header| NesHeader::parse(header.try_into().unwrap()).ok())
+    else {
+        analysis.problems.push(RomProblem::BadHeader);
+        return analysis;
+    };
+
+    let prg_rom_start = HEADER_SIZE + if header.trainer { TRAINER_SIZE } else { 0 };
+    let expected = prg_rom_start + header.prg_rom_size + header.chr_rom_size;
+    analysis.problems.extend(size_problem(expected, rom.len()));
+    analysis.bank_count = Some(header.prg_rom_size / PRG_ROM_UNIT);
+
+    if header.prg_rom_size < 6 {
+        return analysis;
+    }
+
+    // The last PRG ROM bank is the one fixed at the top of the address space on power on for nearly every mapper
+    let vector_offset = prg_rom_start + header.prg_rom_size - 6;
+    analysis.vectors.extend(
+        [
+            (0, VectorKind::Nmi),
+            (2, VectorKind::Reset),
+            (4, VectorKind::Irq),
+        ]
+        .into_iter()
+        .filter_map(|(offset, kind)| read_vector(rom, vector_offset + offset, kind)),
+    );
+
+    for vector in &analysis.vectors {
+        // IRQ is commonly left pointing anywhere by games that never enable it
+        if vector.kind != VectorKind::Irq && (vector.address as usize) < PRG_ROM_START {
+            analysis
+                .problems
+                .push(RomProblem::VectorOutsideRom(*vector));
+        }
+    }
+
+    analysis
+}
+
+// https://gbdev.io/pandocs/The_Cartridge_Header.html
+fn analyze_game_boy(rom: &[u8]) -> RomAnalysis {
+    let mut analysis = RomAnalysis::default();
+
+    if rom.len() < 0x150 {
+        analysis.problems.push(RomProblem::BadHeader);
+        return analysis;
+    }
+
+    analysis.vectors.push(RomVector {
+        kind: VectorKind::Entry,
+        address: 0x100,
+    });
+
+    let actual = rom[0x134..=0x14c].iter().fold(0u8, |checksum, byte| {
+        checksum.wrapping_sub(*byte).wrapping_sub(1)
+    });
+    if actual != rom[0x14d] {
+        analysis.problems.push(RomProblem::BadHeaderChecksum {
+            expected: rom[0x14d] as u16,
+            actual: actual as u16,
+        });
+    }
+
+    // The global checksum covers everything but itself
+    let expected = u16::from_be_bytes([rom[0x14e], rom[0x14f]]);
+    let actual = rom
+        .iter()
+        .enumerate()
+        .filter(|(index, _)| !matches!(index, 0x14e | 0x14f))
+        .fold(0u16, |checksum, (_, byte)| {
+            checksum.wrapping_add(*byte as u16)
+        });
+    if actual != expected {
+        analysis
+            .problems
+            .push(RomProblem::BadGlobalChecksum { expected, actual });
+    }
+
+    // 32KiB shifted left by the code, the few odd sizes aren't used by any known cartridge
+    if let Some(size) = 0x8000usize
+        .checked_shl(rom[0x148] as u32)
+        .filter(|_| rom[0x148] <= 8)
+    {
+        analysis.bank_count = Some(size / 0x4000);
+        analysis.problems.extend(size_problem(size, rom.len()));
+    } else {
+        analysis.problems.push(RomProblem::BadHeader);
+    }
+
+    analysis
+}
+
+// https://www.smspower.org/Development/ROMHeader
+fn analyze_master_system(rom: &[u8]) -> RomAnalysis {
+    let mut analysis = RomAnalysis::default();
+
+    analysis.vectors.push(RomVector {
+        kind: VectorKind::Reset,
+        address: 0x0000,
+    });
+    analysis.bank_count = Some(rom.len().div_ceil(0x4000));
+
+    let Some(header) = [0x7ff0, 0x3ff0, 0x1ff0]
+        .into_iter()
+        .find(|offset| rom.get(*offset..offset + 8) == Some(b"TMR SEGA".as_slice()))
+    else {
+        // Japanese and Game Gear releases often have no header at all and still boot
+        return analysis;
+    };
+
+    let size = match rom[header + 0xf] & 0x0f {
+        0xa => 0x2000,
+        0xb => 0x4000,
+        0xc => 0x8000,
+        0xd => 0xc000,
+        0xe => 0x10000,
+        0xf => 0x20000,
+        0x0 => 0x40000,
+        0x1 => 0x80000,
+        0x2 => 0x100000,
+        _ => {
+            analysis.problems.push(RomProblem::BadHeader);
+            return analysis;
+        }
+    };
+
+    // Everything the size covers except the header itself
+    let expected = u16::from_le_bytes([rom[header + 0xa], rom[header + 0xb]]);
+    let actual = rom
+        .iter()
+        .take(size)
+        .enumerate()
+        .filter(|(index, _)| !(0x7ff0..0x8000).contains(index))
+        .fold(0u16, |checksum, (_, byte)| {
+            checksum.wrapping_add(*byte as u16)
+        });
+    if actual != expected {
+        analysis
+            .problems
+            .push(RomProblem::BadHeaderChecksum { expected, actual });
+    }
+
+    // The checksummed size is allowed to be less than the ROM, but not more
+    if rom.len() < size {
+        analysis.problems.push(RomProblem::Truncated {
+            expected: size,
+            actual: rom.len(),
+        });
+    }
+
+    analysis
+}
+
+// https://problemkaputt.de/2k6specs.htm#memoryandiomap
+fn analyze_atari_2600(rom: &[u8]) -> RomAnalysis {
+    // Anything over 4KiB is bank switched in 4KiB banks, and every size is a power of two or a whole number of banks
+    let mut analysis = RomAnalysis {
+        bank_count: Some(rom.len().div_ceil(0x1000)),
+        ..Default::default()
+    };
+
+    if !rom.len().is_power_of_two() && !rom.len().is_multiple_of(0x1000) {
+        let expected = if rom.len() > 0x800 {
+            rom.len() - rom.len() % 0x800
+        } else {
+            0x800
+        };
+        analysis.problems.extend(size_problem(expected, rom.len()));
+    }
+
+    // The last bank is the one most bank switching schemes start in
+    let vector_offset = rom.len().saturating_sub(4);
+    analysis.vectors.extend(
+        [(0, VectorKind::Reset), (2, VectorKind::Irq)]
+            .into_iter()
+            .filter_map(|(offset, kind)| read_vector(rom, vector_offset + offset, kind)),
+    );
+
+    // Only 13 address lines go to the cartridge, and it is selected by the highest one
+    if let Some(reset) = analysis.vectors.first().copied() {
+        if reset.address & 0x1000 == 0 {
+            analysis.problems.push(RomProblem::VectorOutsideRom(reset));
+        }
+    }
+
+    analysis
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[cfg(feature = "nes")]
+    fn finds_nes_vectors_and_overdumps() {
+        let mut rom = vec![0; HEADER_SIZE + 0x4000 + 0x2000];
+        rom[..8].copy_from_slice(&[b'N', b'E', b'S', 0x1a, 1, 1, 0, 0]);
+        let vectors = HEADER_SIZE + 0x4000 - 6;
+        rom[vectors..vectors + 6].copy_from_slice(&[0x00, 0x90, 0x00, 0xc0, 0x00, 0x00]);
+
+        let analysis = analyze_nes(&rom);
+        assert_eq!(analysis.bank_count, Some(1));
+        assert_eq!(
+            analysis.vectors[..2],
+            [
+                RomVector {
+                    kind: VectorKind::Nmi,
+                    address: 0x9000
+                },
+                RomVector {
+                    kind: VectorKind::Reset,
+                    address: 0xc000
+                }
+            ]
+        );
+        assert!(analysis.problems.is_empty());
+
+        rom.extend([0xff; 0x100]);
+        assert_eq!(
+            analyze_nes(&rom).problems,
+            [RomProblem::OverdumpSuspected {
+                expected: HEADER_SIZE + 0x6000,
+                actual: HEADER_SIZE + 0x6100
+            }]
+        );
+    }
+
+    #[test]
+    fn checks_game_boy_header_checksum() {
+        let mut rom = vec![0; 0x8000];
+        rom[0x134..0x13a].copy_from_slice(b"TETRIS");
+        rom[0x14d] = rom[0x134..=0x14c].iter().fold(0u8, |checksum, byte| {
+            checksum.wrapping_sub(*byte).wrapping_sub(1)
+        });
+        let global: u16 = rom.iter().map(|byte| *byte as u16).sum();
+        rom[0x14e..0x150].copy_from_slice(&global.to_be_bytes());
+
+        let analysis = analyze_game_boy(&rom);
+        assert_eq!(analysis.bank_count, Some(2));
+        assert!(analysis.problems.is_empty());
+
+        rom[0x134] = b'X';
+        assert!(matches!(
+            analyze_game_boy(&rom).problems[0],
+            RomProblem::BadHeaderChecksum { .. }
+        ));
+    }
+}
@@ -135,6 +135,7 @@ fn guess_by_extension(rom: &Path) -> Option<GameSystem> {
             "md" => Some(GameSystem::Sega(SegaSystem::MasterSystem)),
             "gg" => Some(GameSystem::Sega(SegaSystem::GameGear)),
             "ch8" | "c8" => Some(GameSystem::Other(OtherSystem::Chip8)),
+            "sc8" => Some(GameSystem::Other(OtherSystem::SuperChip8)),
             "a26" => Some(GameSystem::Atari(AtariSystem::Atari2600)),
             _ => None,
         } {
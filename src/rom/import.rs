@@ -0,0 +1,207 @@
+use super::{analysis::analyze_rom_file, RomId, RomManager};
+use crate::env::{IMPORTED_ROM_DIRECTORY, ROM_DATABASE_PATH};
+use serde::{Deserialize, Serialize};
+use sha1::{Digest, Sha1};
+use std::{
+    fs::{self, copy, create_dir_all, File},
+    ops::Deref,
+    path::{Path, PathBuf},
+};
+use strum::{Display, EnumIter};
+use walkdir::WalkDir;
+
+pub fn hash_rom(path: &Path) -> Option<RomId> {
+    if path.is_dir() {
+        return None;
+    }
+
+    let mut file = File::open(path).ok()?;
+    let mut hasher = Sha1::new();
+    std::io::copy(&mut file, &mut hasher).ok()?;
+
+    Some(RomId::new(hasher.finalize().into()))
+}
+
+/// How a file gets into the managed store
+#[cfg_attr(desktop, derive(clap::ValueEnum))]
+#[derive(
+    Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash, Default, EnumIter, Display,
+)]
+pub enum ImportPolicy {
+    #[default]
+    Copy,
+    Symlink,
+    Hardlink,
+    /// Copies and deletes the original if a rename is impossible
+    Move,
+}
+
+/// Places a file in the managed store under its hash
+pub fn store_rom(path: &Path, rom_id: RomId, policy: ImportPolicy) -> std::io::Result<()> {
+    create_dir_all(IMPORTED_ROM_DIRECTORY.deref())?;
+
+    let internal_store_path = IMPORTED_ROM_DIRECTORY.join(rom_id.to_string());
+    let _ = fs::remove_file(&internal_store_path);
+
+    match policy {
+        ImportPolicy::Copy => {
+            copy(path, internal_store_path)?;
+        }
+        ImportPolicy::Symlink => {
+            #[cfg(unix)]
+            std::os::unix::fs::symlink(path, internal_store_path)?;
+
+            #[cfg(windows)]
+            std::os::windows::fs::symlink_file(path, internal_store_path)?;
+
+            #[cfg(not(any(unix, windows)))]
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::Unsupported,
+                "Symlinking is not supported on this platform",
+            ));
+        }
+        ImportPolicy::Hardlink => {
+            fs::hard_link(path, internal_store_path)?;
+        }
+        ImportPolicy::Move => {
+            // Renaming fails across filesystems
+            if fs::rename(path, &internal_store_path).is_err() {
+                copy(path, internal_store_path)?;
+                fs::remove_file(path)?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Imports a file into the managed store if the database knows about it
+pub fn import_known_rom(
+    rom_manager: &RomManager,
+    policy: ImportPolicy,
+    path: &Path,
+) -> Option<RomId> {
+    let hash = hash_rom(path)?;
+    let rom = rom_manager.rom_information.get(&hash)?;
+
+    tracing::info!(
+        "Identified ROM at {} as \"{:?}\" for the system {} with hash {}",
+        path.display(),
+        rom.name,
+        rom.system,
+        hash
+    );
+
+    if let Err(error) = store_rom(path, hash, policy) {
+        tracing::error!("Failed to import {}: {}", path.display(), error);
+        return None;
+    }
+
+    Some(hash)
+}
+
+/// Points the database at the stored copy and analyzes it, so problems with the file show up before it is booted
+fn record_import(rom_manager: &mut RomManager, rom_id: RomId) {
+    let path = IMPORTED_ROM_DIRECTORY.join(rom_id.to_string());
+    let info = rom_manager.rom_information.get_mut(&rom_id).unwrap();
+    info.analysis = analyze_rom_file(&path, info.system);
+
+    for problem in info.analysis.iter().flat_map(|analysis| &analysis.problems) {
+        tracing::warn!("ROM {:?} ({}): {}", info.name, rom_id, problem);
+    }
+
+    rom_manager.rom_paths.insert(rom_id, path);
+}
+
+/// Keeps the analyses of newly imported ROMs
+fn store_analyses(rom_manager: &RomManager) {
+    if let Err(error) = rom_manager.store_rom_info(ROM_DATABASE_PATH.deref()) {
+        tracing::error!("Could not store the ROM database: {}", error);
+    }
+}
+
+/// What a batch import did with each file it came across
+#[cfg(feature = "frontend")]
+#[derive(Default, Debug)]
+pub struct ImportReport {
+    pub imported: Vec<(PathBuf, RomId)>,
+    /// Files the database doesn't know, or that could not be stored
+    pub skipped: Vec<PathBuf>,
+}
+
+/// Imports every known ROM at the paths, descending into directories
+///
+/// The database is left for the caller to store, as it is only worth writing once per batch
+#[cfg(feature = "frontend")]
+pub fn import_known_roms(
+    rom_manager: &mut RomManager,
+    policy: ImportPolicy,
+    paths: &[PathBuf],
+) -> ImportReport {
+    let mut report = ImportReport::default();
+
+    for path in paths {
+        for entry in WalkDir::new(path).into_iter().flatten() {
+            if entry.file_type().is_dir() {
+                continue;
+            }
+
+            match import_known_rom(rom_manager, policy, entry.path()) {
+                Some(rom_id) => {
+                    record_import(rom_manager, rom_id);
+                    report.imported.push((entry.into_path(), rom_id));
+                }
+                None => report.skipped.push(entry.into_path()),
+            }
+        }
+    }
+
+    report
+}
+
+/// Imports any known ROMs in the watch folders that are not in the managed store yet
+pub fn scan_watch_folders(
+    rom_manager: &mut RomManager,
+    policy: ImportPolicy,
+    watch_folders: &[PathBuf],
+) {
+    if watch_folders.is_empty() {
+        return;
+    }
+
+    create_dir_all(IMPORTED_ROM_DIRECTORY.deref()).unwrap();
+
+    let mut imported = 0;
+
+    for watch_folder in watch_folders {
+        if !watch_folder.is_dir() {
+            tracing::warn!("Watch folder {} does not exist", watch_folder.display());
+            continue;
+        }
+
+        for entry in WalkDir::new(watch_folder).into_iter().flatten() {
+            let Some(rom_id) = hash_rom(entry.path()) else {
+                continue;
+            };
+
+            if rom_manager.rom_paths.contains_key(&rom_id) {
+                continue;
+            }
+
+            if let Some(rom_id) = import_known_rom(rom_manager, policy, entry.path()) {
+                record_import(rom_manager, rom_id);
+                imported += 1;
+            }
+        }
+    }
+
+    if imported != 0 {
+        store_analyses(rom_manager);
+    }
+
+    tracing::info!(
+        "Scanned {} watch folders, {} new ROMs were imported",
+        watch_folders.len(),
+        imported
+    );
+}
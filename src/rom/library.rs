@@ -0,0 +1,202 @@
+use super::{GameSystem, RomId, RomInfo, RomRegion};
+use std::collections::HashMap;
+
+/// Articles databases move behind the title for sorting, like "Legend of Zelda, The"
+const INVERTED_ARTICLES: [&str; 15] = [
+    "the", "a", "an", "der", "die", "das", "le", "la", "les", "el", "los", "las", "il", "lo", "gli",
+];
+
+/// The title of a game without the tags No-Intro puts after it, so regional dumps and revisions of the same game
+/// compare equal
+///
+/// Case and diacritics are folded and inverted articles are put back in front. This is only for comparing, the
+/// original name is what gets shown
+pub fn normalize_title(name: &str) -> String {
+    let mut title = String::with_capacity(name.len());
+    let mut depth = 0usize;
+
+    for character in name.chars() {
+        match character {
+            '(' | '[' => depth += 1,
+            ')' | ']' => depth = depth.saturating_sub(1),
+            _ if depth == 0 => title.push(character),
+            _ => {}
+        }
+    }
+
+    let title = fold(&title)
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    // Only the main title, before any subtitle, has the article moved
+    let (main, subtitle) = title.split_at(title.find(" - ").unwrap_or(title.len()));
+    match main.rsplit_once(", ") {
+        Some((main, article)) if INVERTED_ARTICLES.contains(&article) => {
+            format!("{} {}{}", article, main, subtitle)
+        }
+        _ => title,
+    }
+}
+
+/// Every word searched for has to be in the title, in any order, case and with or without diacritics. Tags count so
+/// regions can be searched for, and inverted articles are found either way
+pub fn matches_search(name: &str, search: &str) -> bool {
+    let searched = format!("{} {}", fold(name), normalize_title(name));
+
+    fold(search)
+        .split_whitespace()
+        .all(|word| searched.contains(word))
+}
+
+/// Lowercase with the accents taken off letters, "Pokémon" and "pokemon" fold the same
+fn fold(text: &str) -> String {
+    let mut folded = String::with_capacity(text.len());
+
+    for character in text.chars().flat_map(char::to_lowercase) {
+        match character {
+            'ß' => folded.push_str("ss"),
+            'æ' => folded.push_str("ae"),
+            'œ' => folded.push_str("oe"),
+            _ => folded.push(strip_diacritic(character)),
+        }
+    }
+
+    folded
+}
+
+/// Covers the Latin letters the databases use, anything else is left alone
+fn strip_diacritic(character: char) -> char {
+    match character {
+        'à'..='å' | 'ā' | 'ă' | 'ą' => 'a',
+        'ç' | 'ć' | 'ĉ' | 'ċ' | 'č' => 'c',
+        'ď' | 'đ' => 'd',
+        'è'..='ë' | 'ē' | 'ĕ' | 'ė' | 'ę' | 'ě' => 'e',
+        'ĝ' | 'ğ' | 'ġ' | 'ģ' => 'g',
+        'ĥ' | 'ħ' => 'h',
+        'ì'..='ï' | 'ĩ' | 'ī' | 'ĭ' | 'į' | 'ı' => 'i',
+        'ĵ' => 'j',
+        'ķ' => 'k',
+        'ĺ' | 'ļ' | 'ľ' | 'ŀ' | 'ł' => 'l',
+        'ñ' | 'ń' | 'ņ' | 'ň' => 'n',
+        'ò'..='ö' | 'ø' | 'ō' | 'ŏ' | 'ő' => 'o',
+        'ŕ' | 'ŗ' | 'ř' => 'r',
+        'ś' | 'ŝ' | 'ş' | 'š' => 's',
+        'ţ' | 'ť' | 'ŧ' => 't',
+        'ù'..='ü' | 'ũ' | 'ū' | 'ŭ' | 'ů' | 'ű' | 'ų' => 'u',
+        'ŵ' => 'w',
+        'ý' | 'ÿ' | 'ŷ' => 'y',
+        'ź' | 'ż' | 'ž' => 'z',
+        _ => character,
+    }
+}
+
+/// Lower is better. The preferred region first, then dumps that work everywhere, then the rest
+fn region_rank(region: Option<RomRegion>, preferred_region: Option<RomRegion>) -> u8 {
+    match region {
+        Some(region) if Some(region) == preferred_region => 0,
+        Some(RomRegion::World) => 1,
+        Some(_) => 2,
+        None => 3,
+    }
+}
+
+/// Collapses every game to its single best dump, "one game one ROM". Dumps are the same game if they are for the same
+/// system and their names normalize to the same title, unnamed dumps are never collapsed
+pub fn one_game_one_rom<'a>(
+    roms: impl IntoIterator<Item = &'a RomInfo>,
+    preferred_region: Option<RomRegion>,
+) -> Vec<&'a RomInfo> {
+    let mut best: HashMap<(GameSystem, Result<String, RomId>), &'a RomInfo> = HashMap::new();
+
+    for rom in roms {
+        let title = rom.name.as_deref().map(normalize_title).ok_or(rom.hash);
+
+        best.entry((rom.system, title))
+            .and_modify(|current| {
+                // Names break ties so the pick doesn't depend on hash map order
+                let rank =
+                    |rom: &RomInfo| (region_rank(rom.region, preferred_region), rom.name.clone());
+
+                if rank(rom) < rank(current) {
+                    *current = rom;
+                }
+            })
+            .or_insert(rom);
+    }
+
+    best.into_values().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rom::NintendoSystem;
+
+    #[test]
+    fn picks_preferred_region() {
+        let system = GameSystem::Nintendo(NintendoSystem::GameBoy);
+        let roms: Vec<_> = [
+            "Tetris (Japan)",
+            "Tetris (World) (Rev 1)",
+            "Tetris (Europe)",
+            "Dr. Mario (USA, Europe)",
+        ]
+        .into_iter()
+        .enumerate()
+        .map(|(index, name)| RomInfo {
+            name: Some(name.to_string()),
+            hash: RomId::new([index as u8; 20]),
+            system,
+            region: RomRegion::from_nointro_name(name),
+            analysis: None,
+        })
+        .collect();
+
+        assert_eq!(normalize_title("Tetris  (World) [b]"), "tetris");
+        assert_eq!(roms[3].region, Some(RomRegion::World));
+
+        let mut picked: Vec<_> = one_game_one_rom(&roms, Some(RomRegion::Europe))
+            .into_iter()
+            .map(|rom| rom.name.as_deref().unwrap())
+            .collect();
+        picked.sort();
+        assert_eq!(picked, ["Dr. Mario (USA, Europe)", "Tetris (Europe)"]);
+
+        let picked = one_game_one_rom(&roms[..3], Some(RomRegion::NorthAmerica));
+        assert_eq!(picked[0].name.as_deref(), Some("Tetris (World) (Rev 1)"));
+    }
+
+    #[test]
+    fn search_matches_every_word() {
+        assert!(matches_search("Super Mario Bros. 3 (USA)", "mario 3"));
+        assert!(matches_search("Super Mario Bros. 3 (USA)", "usa SUPER"));
+        assert!(matches_search("Super Mario Bros. 3 (USA)", ""));
+        assert!(!matches_search("Super Mario Bros. 3 (USA)", "mario japan"));
+    }
+
+    #[test]
+    fn titles_fold_articles_and_diacritics() {
+        assert_eq!(
+            normalize_title("Legend of Zelda, The - A Link to the Past (USA)"),
+            "the legend of zelda - a link to the past"
+        );
+        assert_eq!(
+            normalize_title("Pokémon - Édition Rouge (France) (SGB Enhanced)"),
+            normalize_title("Pokemon - Edition Rouge (France)")
+        );
+        // Only articles are moved
+        assert_eq!(normalize_title("Mario, Luigi (USA)"), "mario, luigi");
+
+        assert!(matches_search(
+            "Legend of Zelda, The (Europe)",
+            "the legend"
+        ));
+        assert!(matches_search(
+            "Legend of Zelda, The (Europe)",
+            "zelda europe"
+        ));
+        assert!(matches_search("Pokémon Stadium (Europe)", "POKEMON"));
+        assert!(matches_search("Pokemon Stadium (Europe)", "pokémon"));
+    }
+}
@@ -1,20 +1,30 @@
+use crate::atomic_write::{read_with_backup, write_atomically_with_backup};
+use analysis::RomAnalysis;
 use data_encoding::HEXLOWER_PERMISSIVE;
 use serde::{Deserialize, Serialize};
 use serde_with::serde_as;
 use serde_with::DisplayFromStr;
+#[cfg(feature = "frontend")]
 use sha1::{Digest, Sha1};
 use std::{
     collections::HashMap,
     error::Error,
     fs::{self, File},
-    io::{BufReader, BufWriter},
+    io::BufReader,
     path::PathBuf,
     str::FromStr,
 };
 use std::{fmt::Display, path::Path};
 use strum::{EnumIter, IntoEnumIterator};
 
+pub mod analysis;
+#[cfg(feature = "frontend")]
 pub mod guess_rom;
+pub mod import;
+#[cfg(any(feature = "frontend", test))]
+pub mod library;
+#[cfg(any(feature = "frontend", test))]
+pub mod sidecar;
 
 #[derive(
     Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, EnumIter,
@@ -83,6 +93,7 @@ pub enum GameSystem {
 }
 
 impl GameSystem {
+    #[allow(dead_code)]
     pub fn iter() -> impl Iterator<Item = GameSystem> {
         NintendoSystem::iter()
             .map(GameSystem::Nintendo)
@@ -190,9 +201,14 @@ pub struct RomInfo {
     pub hash: RomId,
     pub system: GameSystem,
     pub region: Option<RomRegion>,
+    /// Filled in when the ROM is imported
+    #[serde(default)]
+    pub analysis: Option<RomAnalysis>,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[derive(
+    Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, EnumIter,
+)]
 pub enum RomRegion {
     World,
     Japan,
@@ -200,6 +216,52 @@ pub enum RomRegion {
     NorthAmerica,
 }
 
+#[cfg(any(feature = "frontend", test))]
+impl RomRegion {
+    /// Reads a No-Intro style region, like `USA` or `USA, Europe`. Countries count as the market they belong to, and
+    /// dumps made for several markets count as World
+    pub fn from_nointro(regions: &str) -> Option<Self> {
+        let mut found = None;
+
+        for region in regions.split(',').map(str::trim) {
+            let region = match region {
+                "World" => RomRegion::World,
+                "Japan" | "Asia" | "Korea" => RomRegion::Japan,
+                "USA" | "Canada" | "Brazil" => RomRegion::NorthAmerica,
+                "Europe" | "Australia" | "France" | "Germany" | "Italy" | "Netherlands"
+                | "Spain" | "Sweden" | "UK" => RomRegion::Europe,
+                _ => return None,
+            };
+
+            found = match found {
+                Some(found) if found != region => Some(RomRegion::World),
+                _ => Some(region),
+            };
+        }
+
+        found
+    }
+
+    /// The region from the first parenthesized tag of a No-Intro name that is one
+    pub fn from_nointro_name(name: &str) -> Option<Self> {
+        name.split('(')
+            .skip(1)
+            .filter_map(|tag| tag.split_once(')'))
+            .find_map(|(tag, _)| Self::from_nointro(tag))
+    }
+}
+
+impl Display for RomRegion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RomRegion::World => write!(f, "World"),
+            RomRegion::Japan => write!(f, "Japan"),
+            RomRegion::Europe => write!(f, "Europe"),
+            RomRegion::NorthAmerica => write!(f, "North America"),
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 /// Sha-1 of rom
 pub struct RomId([u8; 20]);
@@ -236,6 +298,8 @@ impl FromStr for RomId {
     }
 }
 
+// No machine has optional ROMs yet
+#[allow(dead_code)]
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum RomRequirement {
     /// Ok to boot machine without this ROM but runtime failure can occur without it
@@ -246,7 +310,7 @@ pub enum RomRequirement {
     Required,
 }
 
-#[derive(Default, Clone, PartialEq, Eq)]
+#[derive(Default, Clone, Debug, PartialEq, Eq)]
 pub struct RomManager {
     pub rom_information: HashMap<RomId, RomInfo>,
     pub rom_paths: HashMap<RomId, PathBuf>,
@@ -254,14 +318,14 @@ pub struct RomManager {
 
 impl RomManager {
     pub fn load_rom_info(&mut self, path: impl AsRef<Path>) -> Result<(), Box<dyn Error>> {
-        let path = path.as_ref();
-
-        if !path.is_file() {
-            return Err("Path is not a file".into());
-        }
+        // A database cut short falls back to the one it was saved over
+        let datasheet: Vec<RomInfo> = read_with_backup(path.as_ref(), |path| {
+            if !path.is_file() {
+                return Err::<_, Box<dyn Error>>("Path is not a file".into());
+            }
 
-        let file = BufReader::new(File::open(path)?);
-        let datasheet: Vec<RomInfo> = rmp_serde::from_read(file)?;
+            Ok(rmp_serde::from_read(BufReader::new(File::open(path)?))?)
+        })?;
         self.rom_information
             .extend(datasheet.into_iter().map(|info| (info.hash, info)));
 
@@ -271,10 +335,11 @@ impl RomManager {
     pub fn store_rom_info(&self, path: impl AsRef<Path>) -> Result<(), Box<dyn Error>> {
         let rom_info = self.rom_information.values().cloned().collect::<Vec<_>>();
 
-        let mut file = BufWriter::new(File::create(path)?);
-        rmp_serde::encode::write_named(&mut file, &rom_info)?;
+        write_atomically_with_backup(path.as_ref(), |file| {
+            rmp_serde::encode::write_named(file, &rom_info)?;
 
-        Ok(())
+            Ok(())
+        })
     }
 
     pub fn load_rom_paths(&mut self, path: impl AsRef<Path>) -> Result<(), Box<dyn Error>> {
@@ -298,6 +363,7 @@ impl RomManager {
         Ok(())
     }
 
+    #[cfg(feature = "frontend")]
     pub fn load_rom_paths_verified(
         &mut self,
         path: impl AsRef<Path>,
@@ -364,21 +430,3 @@ impl RomManager {
         }
     }
 }
-
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
-pub enum RomSpecification {
-    Path(PathBuf),
-    Hash(RomId),
-}
-
-impl From<PathBuf> for RomSpecification {
-    fn from(path: PathBuf) -> Self {
-        Self::Path(path)
-    }
-}
-
-impl From<RomId> for RomSpecification {
-    fn from(hash: RomId) -> Self {
-        Self::Hash(hash)
-    }
-}
@@ -0,0 +1,129 @@
+#[cfg(feature = "frontend")]
+use super::{analysis::analyze_rom_file, guess_rom::guess_rom, RomManager, RomRegion};
+use super::{RomId, RomInfo};
+use ron::ser::PrettyConfig;
+#[cfg(feature = "frontend")]
+use sha1::{Digest, Sha1};
+use std::{
+    error::Error,
+    fs::File,
+    path::{Path, PathBuf},
+};
+
+/// Appended to the full file name of the ROM, so `game.ch8` gets `game.ch8.multiemu.ron`
+pub const SIDECAR_EXTENSION: &str = "multiemu.ron";
+
+/// Where the metadata of a ROM that lives outside the managed store is kept
+pub fn sidecar_path(rom_path: &Path) -> PathBuf {
+    let mut file_name = rom_path.file_name().unwrap_or_default().to_os_string();
+    file_name.push(".");
+    file_name.push(SIDECAR_EXTENSION);
+
+    rom_path.with_file_name(file_name)
+}
+
+/// Reads the sidecar of a ROM, ignoring it if it describes different contents than what is there now
+pub fn load_sidecar(rom_path: &Path, rom_id: RomId) -> Option<RomInfo> {
+    let path = sidecar_path(rom_path);
+    let file = File::open(&path).ok()?;
+
+    let info: RomInfo = match ron::de::from_reader(file) {
+        Ok(info) => info,
+        Err(error) => {
+            tracing::warn!("Ignoring unreadable sidecar {}: {}", path.display(), error);
+            return None;
+        }
+    };
+
+    if info.hash != rom_id {
+        tracing::info!(
+            "Sidecar {} is for a different version of the ROM, it will be replaced",
+            path.display()
+        );
+        return None;
+    }
+
+    Some(info)
+}
+
+pub fn store_sidecar(rom_path: &Path, info: &RomInfo) -> Result<(), Box<dyn Error>> {
+    let file = File::create(sidecar_path(rom_path))?;
+    ron::ser::to_writer_pretty(file, info, PrettyConfig::default())?;
+
+    Ok(())
+}
+
+/// Looks a ROM up in the database, guessing what it is from the file if it isn't there
+#[cfg(feature = "frontend")]
+pub fn identify_rom(rom_path: &Path, rom_id: RomId, rom_manager: &RomManager) -> Option<RomInfo> {
+    if let Some(info) = rom_manager.rom_information.get(&rom_id) {
+        return Some(info.clone());
+    }
+
+    let (system, rom_id) = guess_rom(rom_path, rom_manager)?;
+    let name = rom_path
+        .file_stem()
+        .map(|name| name.to_string_lossy().into_owned());
+
+    Some(RomInfo {
+        // Files are often named after their No-Intro entry
+        region: name.as_deref().and_then(RomRegion::from_nointro_name),
+        name,
+        hash: rom_id,
+        system,
+        analysis: analyze_rom_file(rom_path, system),
+    })
+}
+
+/// Identifies a ROM outside the managed store, preferring its sidecar and writing one after the first identification
+///
+/// The database is consulted before guessing so known ROMs keep their proper name and region
+#[cfg(feature = "frontend")]
+pub fn identify_external_rom(rom_path: &Path, rom_manager: &RomManager) -> Option<RomInfo> {
+    let mut file = File::open(rom_path).ok()?;
+    let mut hasher = Sha1::new();
+    std::io::copy(&mut file, &mut hasher).ok()?;
+    let rom_id = RomId::new(hasher.finalize().into());
+
+    if let Some(info) = load_sidecar(rom_path, rom_id) {
+        return Some(info);
+    }
+
+    let info = identify_rom(rom_path, rom_id, rom_manager)?;
+
+    // Not being able to write next to the ROM only costs us the cache
+    if let Err(error) = store_sidecar(rom_path, &info) {
+        tracing::warn!(
+            "Could not write the sidecar for {}: {}",
+            rom_path.display(),
+            error
+        );
+    }
+
+    Some(info)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rom::{GameSystem, OtherSystem};
+
+    #[test]
+    fn sidecar_roundtrip() {
+        let rom_path = std::env::temp_dir().join("multiemu_sidecar_test.ch8");
+        let info = RomInfo {
+            name: Some("Sidecar Test".to_string()),
+            hash: RomId::new([1; 20]),
+            system: GameSystem::Other(OtherSystem::Chip8),
+            region: None,
+            analysis: None,
+        };
+
+        store_sidecar(&rom_path, &info).unwrap();
+        assert_eq!(load_sidecar(&rom_path, info.hash), Some(info));
+        // The ROM changed since
+        assert_eq!(load_sidecar(&rom_path, RomId::new([2; 20])), None);
+
+        let _ = std::fs::remove_file(sidecar_path(&rom_path));
+    }
+}
@@ -0,0 +1,496 @@
+use crate::component::audio::SampleFrame;
+use crossbeam::queue::ArrayQueue;
+use std::{
+    fs::File,
+    io::{BufWriter, ErrorKind, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc,
+    },
+    thread::JoinHandle,
+    time::Duration,
+};
+
+const WAV_HEADER_SIZE: u32 = 44;
+/// The RIFF size counts the header after itself as well, and it has to fit in 32 bits too
+const MAX_DATA_SIZE: u32 = u32::MAX - (WAV_HEADER_SIZE - 8);
+/// How long the writer thread sleeps between draining the queues
+const WRITER_INTERVAL: Duration = Duration::from_millis(20);
+
+/// Writes interleaved 16 bit PCM to a WAV file, patching the sizes in the header on finalization
+pub struct WavWriter<W: Write + Seek> {
+    writer: W,
+    data_size: u32,
+    /// The most data that fits, in whole frames
+    data_limit: u32,
+    block_align: u16,
+}
+
+impl<W: Write + Seek> WavWriter<W> {
+    pub fn new(mut writer: W, channels: u16, sample_rate: u32) -> std::io::Result<Self> {
+        let block_align = channels
+            .checked_mul(size_of::<i16>() as u16)
+            .filter(|block_align| *block_align != 0)
+            .ok_or_else(|| {
+                std::io::Error::new(ErrorKind::InvalidInput, "Unsupported channel count")
+            })?;
+        let byte_rate = sample_rate.checked_mul(block_align as u32).ok_or_else(|| {
+            std::io::Error::new(ErrorKind::InvalidInput, "Unsupported sample rate")
+        })?;
+        writer.write_all(b"RIFF")?;
+        // Patched on finalization
+        writer.write_all(&0u32.to_le_bytes())?;
+        writer.write_all(b"WAVE")?;
+        writer.write_all(b"fmt ")?;
+        writer.write_all(&16u32.to_le_bytes())?;
+        // PCM
+        writer.write_all(&1u16.to_le_bytes())?;
+        writer.write_all(&channels.to_le_bytes())?;
+        writer.write_all(&sample_rate.to_le_bytes())?;
+        writer.write_all(&byte_rate.to_le_bytes())?;
+        writer.write_all(&block_align.to_le_bytes())?;
+        writer.write_all(&16u16.to_le_bytes())?;
+        writer.write_all(b"data")?;
+        // Patched on finalization
+        writer.write_all(&0u32.to_le_bytes())?;
+
+        Ok(Self {
+            writer,
+            data_size: 0,
+            data_limit: MAX_DATA_SIZE,
+            block_align,
+        }
+        .with_data_limit(MAX_DATA_SIZE))
+    }
+
+    /// Makes the file count as full before the format limit, rounded down to whole frames
+    fn with_data_limit(mut self, data_limit: u32) -> Self {
+        let data_limit = data_limit.min(MAX_DATA_SIZE);
+        debug_assert!(data_limit >= self.block_align as u32);
+        self.data_limit = data_limit - data_limit % self.block_align as u32;
+
+        self
+    }
+
+    /// Whole frames that can still be written before the file is full
+    pub fn remaining_frames(&self) -> u32 {
+        (self.data_limit - self.data_size) / self.block_align as u32
+    }
+
+    /// Writes nothing and fails if the samples don't fit in what is left of the file
+    pub fn write_samples<I>(&mut self, samples: I) -> std::io::Result<()>
+    where
+        I: IntoIterator<Item = i16>,
+        I::IntoIter: ExactSizeIterator,
+    {
+        let samples = samples.into_iter();
+        let data_size = samples
+            .len()
+            .checked_mul(size_of::<i16>())
+            .and_then(|size| u32::try_from(size).ok())
+            .and_then(|size| self.data_size.checked_add(size))
+            .filter(|data_size| *data_size <= self.data_limit)
+            .ok_or_else(|| {
+                std::io::Error::new(ErrorKind::FileTooLarge, "WAV files are limited to 4GiB")
+            })?;
+
+        for sample in samples {
+            self.writer.write_all(&sample.to_le_bytes())?;
+        }
+        self.data_size = data_size;
+
+        Ok(())
+    }
+
+    pub fn finalize(mut self) -> std::io::Result<W> {
+        self.writer.seek(SeekFrom::Start(4))?;
+        self.writer
+            .write_all(&(WAV_HEADER_SIZE - 8 + self.data_size).to_le_bytes())?;
+        self.writer.seek(SeekFrom::Start(40))?;
+        self.writer.write_all(&self.data_size.to_le_bytes())?;
+        self.writer.seek(SeekFrom::End(0))?;
+        self.writer.flush()?;
+
+        Ok(self.writer)
+    }
+}
+
+/// Where a capture continues once the file before it is full, `capture.wav` goes on in `capture_1.wav` and so on
+pub fn part_path(path: &Path, part: u32) -> PathBuf {
+    if part == 0 {
+        return path.to_path_buf();
+    }
+
+    let mut file_name = path.file_stem().unwrap_or_default().to_os_string();
+    file_name.push(format!("_{}", part));
+    if let Some(extension) = path.extension() {
+        file_name.push(".");
+        file_name.push(extension);
+    }
+
+    path.with_file_name(file_name)
+}
+
+/// A stereo WAV file being captured to, rolling over to the next part whenever one fills up
+struct CaptureTrack {
+    path: PathBuf,
+    sample_rate: u32,
+    data_limit: u32,
+    part: u32,
+    wav_writer: WavWriter<BufWriter<File>>,
+    frames: Arc<ArrayQueue<SampleFrame>>,
+    /// Kept between drains so they don't allocate
+    samples: Vec<i16>,
+}
+
+impl CaptureTrack {
+    fn new(
+        path: PathBuf,
+        sample_rate: u32,
+        data_limit: u32,
+        queue_capacity: usize,
+    ) -> std::io::Result<Self> {
+        let wav_writer = Self::create_part(&path, 0, sample_rate, data_limit)?;
+
+        Ok(Self {
+            path,
+            sample_rate,
+            data_limit,
+            part: 0,
+            wav_writer,
+            frames: Arc::new(ArrayQueue::new(queue_capacity)),
+            samples: Vec::new(),
+        })
+    }
+
+    fn create_part(
+        path: &Path,
+        part: u32,
+        sample_rate: u32,
+        data_limit: u32,
+    ) -> std::io::Result<WavWriter<BufWriter<File>>> {
+        Ok(WavWriter::new(
+            BufWriter::new(File::create(part_path(path, part))?),
+            2,
+            sample_rate,
+        )?
+        .with_data_limit(data_limit))
+    }
+
+    /// Writes out whatever has been queued so far
+    fn drain(&mut self) -> std::io::Result<()> {
+        let mut samples = std::mem::take(&mut self.samples);
+        samples.clear();
+        while let Some(frame) = self.frames.pop() {
+            samples.extend(frame);
+        }
+
+        let mut remaining = samples.as_slice();
+        let result = loop {
+            if remaining.is_empty() {
+                break Ok(());
+            }
+
+            let fitting = remaining
+                .len()
+                .min(self.wav_writer.remaining_frames() as usize * 2);
+            if fitting == 0 {
+                if let Err(error) = self.roll_over() {
+                    break Err(error);
+                }
+                continue;
+            }
+
+            let (now, later) = remaining.split_at(fitting);
+            if let Err(error) = self.wav_writer.write_samples(now.iter().copied()) {
+                break Err(error);
+            }
+            remaining = later;
+        };
+        self.samples = samples;
+
+        result
+    }
+
+    fn roll_over(&mut self) -> std::io::Result<()> {
+        self.part += 1;
+        let wav_writer =
+            Self::create_part(&self.path, self.part, self.sample_rate, self.data_limit)?;
+        std::mem::replace(&mut self.wav_writer, wav_writer).finalize()?;
+
+        tracing::info!(
+            "Audio capture continues in {}",
+            part_path(&self.path, self.part).display()
+        );
+
+        Ok(())
+    }
+
+    fn finalize(self) -> std::io::Result<()> {
+        self.wav_writer.finalize()?;
+
+        Ok(())
+    }
+}
+
+/// Captures stereo frames to WAV files, one per track
+///
+/// The files are written on a thread of its own so pushing frames, which the audio callback does, never touches the
+/// disk or blocks. Frames pushed while the writer is too far behind are dropped
+pub struct AudioCapture {
+    tracks: Vec<Arc<ArrayQueue<SampleFrame>>>,
+    dropped_frames: Arc<AtomicUsize>,
+    finishing: Arc<AtomicBool>,
+    writer: JoinHandle<std::io::Result<()>>,
+}
+
+impl AudioCapture {
+    /// Every file is created before this returns
+    pub fn start(paths: &[PathBuf], sample_rate: u32) -> std::io::Result<Self> {
+        Self::with_data_limit(paths, sample_rate, MAX_DATA_SIZE)
+    }
+
+    fn with_data_limit(
+        paths: &[PathBuf],
+        sample_rate: u32,
+        data_limit: u32,
+    ) -> std::io::Result<Self> {
+        // About a second of audio per track
+        let queue_capacity = (sample_rate as usize).max(1);
+        let tracks = paths
+            .iter()
+            .map(|path| CaptureTrack::new(path.clone(), sample_rate, data_limit, queue_capacity))
+            .collect::<std::io::Result<Vec<_>>>()?;
+        let queues = tracks.iter().map(|track| track.frames.clone()).collect();
+        let dropped_frames = Arc::new(AtomicUsize::new(0));
+        let finishing = Arc::new(AtomicBool::new(false));
+
+        let writer = std::thread::Builder::new()
+            .name("audio capture".to_string())
+            .spawn({
+                let dropped_frames = dropped_frames.clone();
+                let finishing = finishing.clone();
+
+                move || {
+                    let result = write_tracks(tracks, &dropped_frames, &finishing);
+
+                    if let Err(error) = &result {
+                        tracing::error!("Audio capture stopped: {}", error);
+                    }
+
+                    result
+                }
+            })?;
+
+        Ok(Self {
+            tracks: queues,
+            dropped_frames,
+            finishing,
+            writer,
+        })
+    }
+
+    pub fn track_count(&self) -> usize {
+        self.tracks.len()
+    }
+
+    pub fn push(&self, track: usize, frames: impl IntoIterator<Item = SampleFrame>) {
+        let queue = &self.tracks[track];
+
+        for frame in frames {
+            if queue.push(frame).is_err() {
+                self.dropped_frames.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+    }
+
+    /// Waits for everything pushed so far to be written and the files to be finalized
+    pub fn finish(self) -> std::io::Result<()> {
+        self.finishing.store(true, Ordering::Release);
+        self.writer.thread().unpark();
+
+        self.writer
+            .join()
+            .map_err(|_| std::io::Error::other("The audio capture thread panicked"))?
+    }
+}
+
+fn write_tracks(
+    mut tracks: Vec<CaptureTrack>,
+    dropped_frames: &AtomicUsize,
+    finishing: &AtomicBool,
+) -> std::io::Result<()> {
+    loop {
+        // Checked before draining so whatever was pushed before finishing still makes it in
+        let finished = finishing.load(Ordering::Acquire);
+
+        for track in &mut tracks {
+            track.drain()?;
+        }
+
+        let dropped = dropped_frames.swap(0, Ordering::Relaxed);
+        if dropped != 0 {
+            tracing::warn!("Audio capture fell behind and dropped {} frames", dropped);
+        }
+
+        if finished {
+            break;
+        }
+
+        std::thread::park_timeout(WRITER_INTERVAL);
+    }
+
+    tracks.into_iter().try_for_each(CaptureTrack::finalize)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn u16_at(bytes: &[u8], offset: usize) -> u16 {
+        u16::from_le_bytes(bytes[offset..offset + 2].try_into().unwrap())
+    }
+
+    fn u32_at(bytes: &[u8], offset: usize) -> u32 {
+        u32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap())
+    }
+
+    #[test]
+    fn wav_sizes() {
+        let samples = [0, 1, -1, i16::MAX, i16::MIN, 0x1234];
+
+        let mut writer = WavWriter::new(Cursor::new(Vec::new()), 2, 44100).unwrap();
+        writer.write_samples(samples).unwrap();
+        let bytes = writer.finalize().unwrap().into_inner();
+
+        let data_size = (samples.len() * size_of::<i16>()) as u32;
+        assert_eq!(bytes.len() as u32, WAV_HEADER_SIZE + data_size);
+
+        assert_eq!(&bytes[0..4], b"RIFF");
+        assert_eq!(u32_at(&bytes, 4), bytes.len() as u32 - 8);
+        assert_eq!(&bytes[8..12], b"WAVE");
+
+        assert_eq!(&bytes[12..16], b"fmt ");
+        assert_eq!(u32_at(&bytes, 16), 16);
+        assert_eq!(u16_at(&bytes, 20), 1);
+        assert_eq!(u16_at(&bytes, 22), 2);
+        assert_eq!(u32_at(&bytes, 24), 44100);
+        assert_eq!(u32_at(&bytes, 28), 44100 * 4);
+        assert_eq!(u16_at(&bytes, 32), 4);
+        assert_eq!(u16_at(&bytes, 34), 16);
+
+        assert_eq!(&bytes[36..40], b"data");
+        assert_eq!(u32_at(&bytes, 40), data_size);
+        assert_eq!(u16_at(&bytes, 44 + 3 * 2), i16::MAX as u16);
+    }
+
+    #[test]
+    fn empty_wav() {
+        let writer = WavWriter::new(Cursor::new(Vec::new()), 1, 8000).unwrap();
+        let bytes = writer.finalize().unwrap().into_inner();
+
+        assert_eq!(bytes.len() as u32, WAV_HEADER_SIZE);
+        assert_eq!(u32_at(&bytes, 4), WAV_HEADER_SIZE - 8);
+        assert_eq!(u32_at(&bytes, 40), 0);
+    }
+
+    #[test]
+    fn full_wav_refuses_samples() {
+        let mut writer = WavWriter::new(Cursor::new(Vec::new()), 2, 8000)
+            .unwrap()
+            .with_data_limit(10);
+        // Rounded down to whole frames
+        assert_eq!(writer.remaining_frames(), 2);
+
+        writer.write_samples([1, 2]).unwrap();
+        assert_eq!(
+            writer.write_samples([3, 4, 5, 6]).unwrap_err().kind(),
+            ErrorKind::FileTooLarge
+        );
+        writer.write_samples([3, 4]).unwrap();
+        assert_eq!(writer.remaining_frames(), 0);
+
+        let bytes = writer.finalize().unwrap().into_inner();
+        assert_eq!(bytes.len() as u32, WAV_HEADER_SIZE + 8);
+        assert_eq!(u32_at(&bytes, 40), 8);
+
+        let writer = WavWriter::new(Cursor::new(Vec::new()), 2, 8000).unwrap();
+        assert_eq!(writer.remaining_frames(), MAX_DATA_SIZE / 4);
+        assert_eq!(WAV_HEADER_SIZE - 8 + writer.data_limit, u32::MAX - 3);
+    }
+
+    #[test]
+    fn capture_rolls_over() {
+        let path =
+            std::env::temp_dir().join(format!("multiemu_audio_capture_{}.wav", std::process::id()));
+        let parts: Vec<_> = (0..3).map(|part| part_path(&path, part)).collect();
+        assert_eq!(
+            parts[2].file_name().unwrap().to_str().unwrap(),
+            format!("multiemu_audio_capture_{}_2.wav", std::process::id())
+        );
+
+        // Room for three frames a file
+        let capture = AudioCapture::with_data_limit(std::slice::from_ref(&path), 100, 12).unwrap();
+        assert_eq!(capture.track_count(), 1);
+        capture.push(0, (0..7).map(|frame| [frame, -frame]));
+        capture.finish().unwrap();
+
+        let files: Vec<_> = parts
+            .iter()
+            .map(|part| std::fs::read(part).unwrap())
+            .collect();
+        for part in &parts {
+            std::fs::remove_file(part).unwrap();
+        }
+
+        for (bytes, frames) in files.iter().zip([3, 3, 1]) {
+            assert_eq!(u32_at(bytes, 4), WAV_HEADER_SIZE - 8 + frames * 4);
+            assert_eq!(u32_at(bytes, 40), frames * 4);
+            assert_eq!(bytes.len() as u32, WAV_HEADER_SIZE + frames * 4);
+        }
+        let samples: Vec<_> = files
+            .iter()
+            .flat_map(|bytes| bytes[WAV_HEADER_SIZE as usize..].chunks(2))
+            .map(|sample| i16::from_le_bytes(sample.try_into().unwrap()))
+            .collect();
+        assert_eq!(
+            samples,
+            (0..7).flat_map(|frame| [frame, -frame]).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn capture_tracks() {
+        let paths: Vec<_> = (0..2)
+            .map(|track| {
+                std::env::temp_dir().join(format!(
+                    "multiemu_audio_capture_{}_track{}.wav",
+                    std::process::id(),
+                    track
+                ))
+            })
+            .collect();
+
+        let capture = AudioCapture::start(&paths, 48000).unwrap();
+        capture.push(0, [[1, 2], [3, 4]]);
+        capture.push(1, [[5, 6]]);
+        capture.finish().unwrap();
+
+        let files: Vec<_> = paths
+            .iter()
+            .map(|path| std::fs::read(path).unwrap())
+            .collect();
+        for path in &paths {
+            std::fs::remove_file(path).unwrap();
+        }
+
+        assert_eq!(u32_at(&files[0], 24), 48000);
+        assert_eq!(u16_at(&files[0], 22), 2);
+        assert_eq!(u32_at(&files[0], 40), 8);
+        assert_eq!(&files[0][44..], [1, 0, 2, 0, 3, 0, 4, 0]);
+        assert_eq!(u32_at(&files[1], 40), 4);
+        assert_eq!(&files[1][44..], [5, 0, 6, 0]);
+    }
+}
@@ -0,0 +1,143 @@
+use crate::{config::GlobalConfig, env::ROM_DATABASE_PATH, rom::RomManager};
+use std::{
+    sync::{
+        mpsc::{channel, Sender},
+        Arc, RwLock,
+    },
+    thread::JoinHandle,
+    time::{Duration, Instant},
+};
+
+/// How long things have to stay unchanged before they are saved, so dragging a slider doesn't write the config for
+/// every frame
+pub const AUTOSAVE_DELAY: Duration = Duration::from_secs(3);
+
+enum AutosaveJob {
+    Config(Box<GlobalConfig>),
+    Database(Arc<RomManager>),
+}
+
+/// Saves the config and the ROM database on a thread of its own a little while after they change, so a crash loses
+/// seconds of changes instead of the whole session
+///
+/// Whatever is still waiting is saved when this is dropped
+pub struct Autosaver {
+    global_config: Arc<RwLock<GlobalConfig>>,
+    saved_config: GlobalConfig,
+    /// The config as last seen and when it was first seen like that
+    pending_config: Option<(GlobalConfig, Instant)>,
+    pending_database: Option<(Arc<RomManager>, Instant)>,
+    jobs: Option<Sender<AutosaveJob>>,
+    worker: Option<JoinHandle<()>>,
+}
+
+impl Autosaver {
+    pub fn new(global_config: Arc<RwLock<GlobalConfig>>) -> Self {
+        let saved_config = global_config.read().unwrap().clone();
+        let (jobs, job_receiver) = channel();
+
+        let worker = std::thread::Builder::new()
+            .name("autosave".to_string())
+            .spawn(move || {
+                for job in job_receiver {
+                    match job {
+                        AutosaveJob::Config(global_config) => {
+                            if let Err(error) = global_config.save() {
+                                tracing::error!("Could not save the config: {}", error);
+                            }
+                        }
+                        AutosaveJob::Database(rom_manager) => {
+                            if let Err(error) = rom_manager.store_rom_info(&*ROM_DATABASE_PATH) {
+                                tracing::error!("Could not store the ROM database: {}", error);
+                            }
+                        }
+                    }
+                }
+            })
+            .unwrap();
+
+        Self {
+            global_config,
+            saved_config,
+            pending_config: None,
+            pending_database: None,
+            jobs: Some(jobs),
+            worker: Some(worker),
+        }
+    }
+
+    pub fn database_changed(&mut self, rom_manager: Arc<RomManager>) {
+        self.pending_database = Some((rom_manager, Instant::now()));
+    }
+
+    /// Meant to be called every iteration of the event loop, saves whatever has settled
+    pub fn poll(&mut self) {
+        let now = Instant::now();
+
+        {
+            let global_config = self.global_config.read().unwrap();
+
+            if *global_config == self.saved_config {
+                self.pending_config = None;
+            } else if self
+                .pending_config
+                .as_ref()
+                .is_none_or(|(pending, _)| *pending != *global_config)
+            {
+                self.pending_config = Some((global_config.clone(), now));
+            }
+        }
+
+        if self
+            .pending_config
+            .as_ref()
+            .is_some_and(|(_, since)| now - *since >= AUTOSAVE_DELAY)
+        {
+            self.save_config();
+        }
+
+        if self
+            .pending_database
+            .as_ref()
+            .is_some_and(|(_, since)| now - *since >= AUTOSAVE_DELAY)
+        {
+            self.save_database();
+        }
+    }
+
+    /// Saves everything waiting right away, for moments a crash is more likely like starting a machine
+    pub fn flush(&mut self) {
+        if *self.global_config.read().unwrap() != self.saved_config {
+            self.save_config();
+        }
+        self.save_database();
+    }
+
+    fn save_config(&mut self) {
+        self.pending_config = None;
+        self.saved_config = self.global_config.read().unwrap().clone();
+        self.send(AutosaveJob::Config(Box::new(self.saved_config.clone())));
+    }
+
+    fn save_database(&mut self) {
+        if let Some((rom_manager, _)) = self.pending_database.take() {
+            self.send(AutosaveJob::Database(rom_manager));
+        }
+    }
+
+    fn send(&self, job: AutosaveJob) {
+        // The worker only stops once the sender is dropped
+        self.jobs.as_ref().unwrap().send(job).unwrap();
+    }
+}
+
+impl Drop for Autosaver {
+    fn drop(&mut self) {
+        self.flush();
+
+        self.jobs = None;
+        if let Some(worker) = self.worker.take() {
+            let _ = worker.join();
+        }
+    }
+}
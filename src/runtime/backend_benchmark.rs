@@ -0,0 +1,143 @@
+#[cfg(desktop)]
+use super::software_egui_render::SoftwareEguiRenderer;
+#[cfg(feature = "frontend")]
+use crate::config::RenderingBackendKind;
+#[cfg(desktop)]
+use crate::{config::GlobalConfig, gui::GuiRuntime};
+#[cfg(desktop)]
+use egui::RawInput;
+#[cfg(desktop)]
+use nalgebra::{DMatrix, DMatrixViewMut};
+#[cfg(desktop)]
+use palette::Srgba;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+#[cfg(desktop)]
+use std::{
+    sync::{Arc, RwLock},
+    time::Instant,
+};
+
+// Only desktops have more than one backend to pick from
+#[cfg(desktop)]
+const BENCHMARK_FRAMES: u32 = 60;
+#[cfg(desktop)]
+const BENCHMARK_RESOLUTION: [usize; 2] = [640, 480];
+/// Size of the dummy machine display, same as a chip8
+#[cfg(desktop)]
+const DUMMY_DISPLAY_RESOLUTION: [usize; 2] = [64, 32];
+
+/// Average frame times of each rendering backend, recorded so the benchmark only runs once
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BackendBenchmarkResults {
+    pub software_frame_time: Duration,
+    /// None if vulkan is unavailable
+    pub vulkan_frame_time: Option<Duration>,
+    /// None if wgpu found no adapter, or the results are from before there was a wgpu backend
+    #[serde(default)]
+    pub wgpu_frame_time: Option<Duration>,
+}
+
+impl BackendBenchmarkResults {
+    /// The fastest backend, vulkan before wgpu before software if they are equally fast
+    #[cfg(feature = "frontend")]
+    pub fn recommended_backend(&self) -> RenderingBackendKind {
+        [
+            (RenderingBackendKind::Vulkan, self.vulkan_frame_time),
+            (RenderingBackendKind::Wgpu, self.wgpu_frame_time),
+            (
+                RenderingBackendKind::Software,
+                Some(self.software_frame_time),
+            ),
+        ]
+        .into_iter()
+        .filter_map(|(backend, frame_time)| Some((backend, frame_time?)))
+        .min_by_key(|(_, frame_time)| *frame_time)
+        .map_or(RenderingBackendKind::Software, |(backend, _)| backend)
+    }
+}
+
+/// Renders the menu and a dummy machine offscreen with each backend, and picks the fastest for the config
+#[cfg(desktop)]
+pub fn run_backend_benchmark(global_config: &Arc<RwLock<GlobalConfig>>) -> BackendBenchmarkResults {
+    tracing::info!("Benchmarking rendering backends");
+
+    let results = BackendBenchmarkResults {
+        software_frame_time: benchmark_software(global_config),
+        #[cfg(vulkan)]
+        vulkan_frame_time: super::desktop::display::vulkan::benchmark::benchmark(BENCHMARK_FRAMES),
+        #[cfg(not(vulkan))]
+        vulkan_frame_time: None,
+        #[cfg(wgpu)]
+        wgpu_frame_time: super::desktop::display::wgpu::benchmark::benchmark(BENCHMARK_FRAMES),
+        #[cfg(not(wgpu))]
+        wgpu_frame_time: None,
+    };
+
+    tracing::info!(
+        "Software rendering took {:?} per frame, vulkan took {:?} per frame, wgpu took {:?} per frame",
+        results.software_frame_time,
+        results.vulkan_frame_time,
+        results.wgpu_frame_time
+    );
+
+    let mut global_config = global_config.write().unwrap();
+    global_config.backend_benchmark = Some(results);
+
+    tracing::info!(
+        "The automatic rendering backend is now {}",
+        global_config.selected_rendering_backend()
+    );
+
+    results
+}
+
+#[cfg(desktop)]
+fn benchmark_software(global_config: &Arc<RwLock<GlobalConfig>>) -> Duration {
+    let [width, height] = BENCHMARK_RESOLUTION;
+    let [display_width, display_height] = DUMMY_DISPLAY_RESOLUTION;
+
+    let mut gui_state = GuiRuntime::new(global_config.clone());
+    let egui_context = egui::Context::default();
+    let mut egui_renderer = SoftwareEguiRenderer::default();
+    let mut render_buffer = vec![Srgba::new(0, 0, 0, 0xff); width * height];
+
+    let dummy_display = DMatrix::from_fn(display_width, display_height, |x, y| {
+        if (x + y) % 2 == 0 {
+            Srgba::new(0xff, 0xff, 0xff, 0xff)
+        } else {
+            Srgba::new(0, 0, 0, 0xff)
+        }
+    });
+
+    let start = Instant::now();
+
+    for _ in 0..BENCHMARK_FRAMES {
+        let mut render_buffer_view = DMatrixViewMut::from_slice(&mut render_buffer, width, height);
+
+        // Same nearest neighbor scaling the software backend does
+        for x in 0..width {
+            for y in 0..height {
+                render_buffer_view[(x, y)] =
+                    dummy_display[(x * display_width / width, y * display_height / height)];
+            }
+        }
+
+        let full_output = egui_context.run(
+            RawInput {
+                screen_rect: Some(egui::Rect::from_min_max(
+                    (0.0, 0.0).into(),
+                    (width as f32, height as f32).into(),
+                )),
+                ..Default::default()
+            },
+            |context| {
+                gui_state.run_menu(context, None);
+            },
+        );
+
+        egui_renderer.render(&egui_context, render_buffer_view, full_output);
+    }
+
+    start.elapsed() / BENCHMARK_FRAMES
+}
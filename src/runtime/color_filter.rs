@@ -0,0 +1,92 @@
+#[cfg(feature = "frontend")]
+use nalgebra::{Matrix3, Vector3};
+#[cfg(feature = "frontend")]
+use palette::{LinSrgba, Srgba};
+use serde::{Deserialize, Serialize};
+use strum::{Display, EnumIter};
+
+#[derive(
+    Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash, Default, EnumIter, Display,
+)]
+pub enum ColorBlindness {
+    #[default]
+    Protanopia,
+    Deuteranopia,
+    Tritanopia,
+}
+
+#[derive(
+    Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash, Default, EnumIter, Display,
+)]
+pub enum ColorFilterMode {
+    /// Show what the image looks like to someone with the color blindness
+    #[default]
+    Simulate,
+    /// Shift the colors that would be lost into ones that can still be told apart
+    Compensate,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct ColorFilter {
+    pub color_blindness: ColorBlindness,
+    pub mode: ColorFilterMode,
+}
+
+#[cfg(feature = "frontend")]
+impl ColorFilter {
+    /// The whole filter as one matrix over linear RGB, for backends that run it in a shader
+    pub fn matrix(&self) -> Matrix3<f32> {
+        let simulation = self.simulation_matrix();
+
+        match self.mode {
+            ColorFilterMode::Simulate => simulation,
+            ColorFilterMode::Compensate => {
+                Matrix3::identity()
+                    + self.compensation_matrix() * (Matrix3::identity() - simulation)
+            }
+        }
+    }
+
+    pub fn apply(&self, pixel: Srgba<u8>) -> Srgba<u8> {
+        let linear: LinSrgba<f32> = pixel.into_format::<f32, f32>().into_linear();
+        let filtered = (self.matrix() * Vector3::new(linear.red, linear.green, linear.blue))
+            .map(|channel| channel.clamp(0.0, 1.0));
+
+        let filtered: Srgba<f32> = Srgba::from_linear(LinSrgba::new(
+            filtered.x,
+            filtered.y,
+            filtered.z,
+            linear.alpha,
+        ));
+
+        filtered.into_format()
+    }
+
+    // Machado et al. 2009, full severity
+    fn simulation_matrix(&self) -> Matrix3<f32> {
+        match self.color_blindness {
+            ColorBlindness::Protanopia => Matrix3::new(
+                0.152286, 1.052583, -0.204868, 0.114503, 0.786281, 0.099216, -0.003882, -0.048116,
+                1.051998,
+            ),
+            ColorBlindness::Deuteranopia => Matrix3::new(
+                0.367322, 0.860646, -0.227968, 0.280085, 0.672501, 0.047413, -0.011820, 0.042940,
+                0.968881,
+            ),
+            ColorBlindness::Tritanopia => Matrix3::new(
+                1.255528, -0.076749, -0.178779, -0.078411, 0.930809, 0.147602, 0.004733, 0.691367,
+                0.303900,
+            ),
+        }
+    }
+
+    // Redistributes the lost information into the remaining channels
+    fn compensation_matrix(&self) -> Matrix3<f32> {
+        match self.color_blindness {
+            ColorBlindness::Protanopia | ColorBlindness::Deuteranopia => {
+                Matrix3::new(0.0, 0.0, 0.0, 0.7, 1.0, 0.0, 0.7, 0.0, 1.0)
+            }
+            ColorBlindness::Tritanopia => Matrix3::new(1.0, 0.0, 0.7, 0.0, 1.0, 0.7, 0.0, 0.0, 0.0),
+        }
+    }
+}
@@ -1,23 +1,164 @@
 use cpal::{
-    traits::{DeviceTrait, HostTrait},
-    Device, OutputCallbackInfo, SampleFormat, SizedSample, Stream, StreamConfig, StreamError,
-    SupportedStreamConfig,
+    traits::{DeviceTrait, HostTrait, StreamTrait},
+    Device, FromSample, OutputCallbackInfo, SampleFormat, SizedSample, Stream, StreamConfig,
+    StreamError, SupportedStreamConfig,
 };
-use std::sync::Arc;
+use std::{
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+
+use crate::{
+    component::audio::{AudioBuffer, AudioComponent},
+    runtime::{audio_capture::AudioCapture, simd::simd_dispatch},
+};
+use num::ToPrimitive;
+
+type CaptureSlot = Arc<Mutex<Option<ActiveCapture>>>;
+type AudioSources = Arc<Mutex<Vec<AudioSource>>>;
+
+/// How much audio is kept queued per component, enough to ride out a late frame
+const TARGET_LATENCY: Duration = Duration::from_millis(50);
+/// How far the playback rate is bent to keep the queue near the target, too little to hear
+const MAX_RATE_ADJUSTMENT: f64 = 0.005;
+/// Past this many times the target the machine ran ahead, like after fast forwarding, and the excess is dropped
+const MAX_QUEUED_TARGETS: usize = 4;
+/// How quickly the last frame fades out when a component stops producing them, so underruns don't click
+const UNDERRUN_DECAY: f32 = 0.995;
+
+/// A audio component as the stream sees it, resampled to the device rate with linear interpolation
+struct AudioSource {
+    buffer: AudioBuffer,
+    /// Component frames per device frame
+    step: f64,
+    /// Frames queued at the component rate that playback aims for
+    target_fill: usize,
+    /// Between previous and next, in component frames
+    position: f64,
+    previous: [f32; 2],
+    next: [f32; 2],
+}
+
+impl AudioSource {
+    fn new(component: &dyn AudioComponent, device_sample_rate: u32) -> Self {
+        let mut source = Self {
+            buffer: component.audio_buffer(),
+            step: 0.0,
+            target_fill: 0,
+            position: 1.0,
+            previous: [0.0; 2],
+            next: [0.0; 2],
+        };
+        source.set_sample_rate(component, device_sample_rate);
+
+        source
+    }
+
+    /// Follows the component to a new rate, whatever is queued keeps playing
+    fn set_sample_rate(&mut self, component: &dyn AudioComponent, device_sample_rate: u32) {
+        let sample_rate = component.sample_rate().to_f64().unwrap();
+
+        self.step = sample_rate / device_sample_rate as f64;
+        self.target_fill = (sample_rate * TARGET_LATENCY.as_secs_f64()).ceil() as usize;
+    }
+
+    /// The machine and the audio device run off different clocks, so the step is bent slightly to keep the queue
+    /// near the target rather than having audio slowly drift ahead of or behind the picture
+    fn synchronized_step(&self) -> f64 {
+        let fill = self.buffer.len();
+
+        if fill > self.target_fill * MAX_QUEUED_TARGETS {
+            self.buffer.truncate_front(self.target_fill);
+            return self.step;
+        }
+
+        let error = ((fill as f64 - self.target_fill as f64) / self.target_fill.max(1) as f64)
+            .clamp(-1.0, 1.0);
+
+        self.step * (1.0 + error * MAX_RATE_ADJUSTMENT)
+    }
+
+    /// Resamples as many frames as there are in `mixed` and adds them onto it
+    fn mix_into(&mut self, step: f64, mixed: &mut [[f32; 2]], block: &mut ResampleBlock) {
+        block.clear();
+
+        // Pulling frames from the queue can't be done in bulk, but the interpolation after can
+        for _ in 0..mixed.len() {
+            while self.position >= 1.0 {
+                self.previous = self.next;
+                self.next = match self.buffer.pop() {
+                    Some(frame) => frame.map(|sample| sample as f32),
+                    None => self.next.map(|sample| sample * UNDERRUN_DECAY),
+                };
+                self.position -= 1.0;
+            }
 
-use crate::component::audio::AudioComponent;
+            block.previous.push(self.previous);
+            block.next.push(self.next);
+            block.positions.push(self.position as f32);
+            self.position += step;
+        }
 
-// TODO: Audio basically does nothing right now
+        interpolate_into(mixed, &block.previous, &block.next, &block.positions);
+    }
+}
+
+/// A capture in progress, either of the mix or of every component on a track of its own
+struct ActiveCapture {
+    capture: AudioCapture,
+    per_component: bool,
+}
+
+/// The component frames either side of each device frame and how far between them it is, kept between callbacks
+#[derive(Default)]
+struct ResampleBlock {
+    previous: Vec<[f32; 2]>,
+    next: Vec<[f32; 2]>,
+    positions: Vec<f32>,
+}
+
+impl ResampleBlock {
+    fn clear(&mut self) {
+        self.previous.clear();
+        self.next.clear();
+        self.positions.clear();
+    }
+}
+
+simd_dispatch! {
+    fn interpolate_into(
+        mixed: &mut [[f32; 2]],
+        previous: &[[f32; 2]],
+        next: &[[f32; 2]],
+        positions: &[f32],
+    ) {
+        for (((mixed, previous), next), position) in
+            mixed.iter_mut().zip(previous).zip(next).zip(positions)
+        {
+            for channel in 0..2 {
+                mixed[channel] += previous[channel] + (next[channel] - previous[channel]) * position;
+            }
+        }
+    }
+}
 
 pub struct CpalContext {
     device: Device,
     stream: Stream,
+    output_config: StreamConfig,
+    muted: Arc<AtomicBool>,
+    capture: CaptureSlot,
+    sources: AudioSources,
 }
 
 impl CpalContext {
-    pub fn new() -> Self {
+    pub fn new() -> Option<Self> {
         let host = cpal::default_host();
-        let device = host.default_output_device().unwrap();
+        let device = host.default_output_device()?;
 
         let config = device
             .supported_output_configs()
@@ -38,12 +179,20 @@ impl CpalContext {
 
         let sample_format = config.sample_format();
         let output_config: StreamConfig = config.into();
+        let muted = Arc::new(AtomicBool::new(false));
+        let capture = CaptureSlot::default();
+        let sources = AudioSources::default();
 
         let stream = match sample_format {
             SampleFormat::I8 => device
                 .build_output_stream(
                     &output_config,
-                    audio_callback::<i8>(output_config.clone()),
+                    audio_callback::<i8>(
+                        output_config.clone(),
+                        muted.clone(),
+                        capture.clone(),
+                        sources.clone(),
+                    ),
                     audio_error,
                     None,
                 )
@@ -51,7 +200,12 @@ impl CpalContext {
             SampleFormat::I16 => device
                 .build_output_stream(
                     &output_config,
-                    audio_callback::<i16>(output_config.clone()),
+                    audio_callback::<i16>(
+                        output_config.clone(),
+                        muted.clone(),
+                        capture.clone(),
+                        sources.clone(),
+                    ),
                     audio_error,
                     None,
                 )
@@ -59,7 +213,12 @@ impl CpalContext {
             SampleFormat::I32 => device
                 .build_output_stream(
                     &output_config,
-                    audio_callback::<i32>(output_config.clone()),
+                    audio_callback::<i32>(
+                        output_config.clone(),
+                        muted.clone(),
+                        capture.clone(),
+                        sources.clone(),
+                    ),
                     audio_error,
                     None,
                 )
@@ -67,7 +226,12 @@ impl CpalContext {
             SampleFormat::I64 => device
                 .build_output_stream(
                     &output_config,
-                    audio_callback::<i64>(output_config.clone()),
+                    audio_callback::<i64>(
+                        output_config.clone(),
+                        muted.clone(),
+                        capture.clone(),
+                        sources.clone(),
+                    ),
                     audio_error,
                     None,
                 )
@@ -75,7 +239,12 @@ impl CpalContext {
             SampleFormat::U8 => device
                 .build_output_stream(
                     &output_config,
-                    audio_callback::<u8>(output_config.clone()),
+                    audio_callback::<u8>(
+                        output_config.clone(),
+                        muted.clone(),
+                        capture.clone(),
+                        sources.clone(),
+                    ),
                     audio_error,
                     None,
                 )
@@ -83,7 +252,12 @@ impl CpalContext {
             SampleFormat::U16 => device
                 .build_output_stream(
                     &output_config,
-                    audio_callback::<u16>(output_config.clone()),
+                    audio_callback::<u16>(
+                        output_config.clone(),
+                        muted.clone(),
+                        capture.clone(),
+                        sources.clone(),
+                    ),
                     audio_error,
                     None,
                 )
@@ -91,7 +265,12 @@ impl CpalContext {
             SampleFormat::U32 => device
                 .build_output_stream(
                     &output_config,
-                    audio_callback::<u32>(output_config.clone()),
+                    audio_callback::<u32>(
+                        output_config.clone(),
+                        muted.clone(),
+                        capture.clone(),
+                        sources.clone(),
+                    ),
                     audio_error,
                     None,
                 )
@@ -99,7 +278,12 @@ impl CpalContext {
             SampleFormat::U64 => device
                 .build_output_stream(
                     &output_config,
-                    audio_callback::<u64>(output_config.clone()),
+                    audio_callback::<u64>(
+                        output_config.clone(),
+                        muted.clone(),
+                        capture.clone(),
+                        sources.clone(),
+                    ),
                     audio_error,
                     None,
                 )
@@ -107,7 +291,12 @@ impl CpalContext {
             SampleFormat::F32 => device
                 .build_output_stream(
                     &output_config,
-                    audio_callback::<f32>(output_config.clone()),
+                    audio_callback::<f32>(
+                        output_config.clone(),
+                        muted.clone(),
+                        capture.clone(),
+                        sources.clone(),
+                    ),
                     audio_error,
                     None,
                 )
@@ -115,7 +304,12 @@ impl CpalContext {
             SampleFormat::F64 => device
                 .build_output_stream(
                     &output_config,
-                    audio_callback::<f64>(output_config.clone()),
+                    audio_callback::<f64>(
+                        output_config.clone(),
+                        muted.clone(),
+                        capture.clone(),
+                        sources.clone(),
+                    ),
                     audio_error,
                     None,
                 )
@@ -123,20 +317,246 @@ impl CpalContext {
             _ => panic!("Unsupported sample format"),
         };
 
-        Self { device, stream }
+        Some(Self {
+            device,
+            stream,
+            output_config,
+            muted,
+            capture,
+            sources,
+        })
+    }
+
+    pub fn set_muted(&self, muted: bool) {
+        self.muted.store(muted, Ordering::Relaxed);
+    }
+
+    /// Records the output in stereo at the device rate, before muting is applied
+    ///
+    /// Per component, every audio component is captured to a file of its own next to `path`
+    pub fn start_capture(&self, path: &Path, per_component: bool) -> std::io::Result<()> {
+        let paths = if per_component {
+            (0..self.sources.lock().unwrap().len())
+                .map(|index| component_capture_path(path, index))
+                .collect()
+        } else {
+            vec![path.to_path_buf()]
+        };
+
+        let capture = AudioCapture::start(&paths, self.output_config.sample_rate.0)?;
+        let old_capture = self.capture.lock().unwrap().replace(ActiveCapture {
+            capture,
+            per_component,
+        });
+
+        // Finishing waits on the writer thread, so it's done outside the lock the audio callback takes
+        if let Some(old_capture) = old_capture {
+            old_capture.capture.finish()?;
+        }
+
+        Ok(())
     }
 
-    pub fn startup_stream(&mut self, audio_components: Vec<Arc<dyn AudioComponent>>) {}
+    pub fn stop_capture(&self) -> std::io::Result<()> {
+        let capture = self.capture.lock().unwrap().take();
 
-    pub fn terminate_stream(&mut self) {}
+        if let Some(capture) = capture {
+            capture.capture.finish()?;
+        }
+
+        Ok(())
+    }
+
+    pub fn is_capturing(&self) -> bool {
+        self.capture.lock().unwrap().is_some()
+    }
+
+    /// Starts playing whatever these components produce, mixed together
+    pub fn startup_stream(&mut self, audio_components: &[Arc<Mutex<dyn AudioComponent>>]) {
+        *self.sources.lock().unwrap() = audio_components
+            .iter()
+            .map(|audio_component| {
+                AudioSource::new(
+                    &*audio_component.lock().unwrap(),
+                    self.output_config.sample_rate.0,
+                )
+            })
+            .collect();
+
+        if let Err(error) = self.stream.play() {
+            tracing::error!("Failed to start the audio stream: {}", error);
+        }
+    }
+
+    /// For when the machine changed its timing, the components are the same ones the stream was started with
+    pub fn update_sample_rates(&self, audio_components: &[Arc<Mutex<dyn AudioComponent>>]) {
+        for (source, audio_component) in self
+            .sources
+            .lock()
+            .unwrap()
+            .iter_mut()
+            .zip(audio_components)
+        {
+            source.set_sample_rate(
+                &*audio_component.lock().unwrap(),
+                self.output_config.sample_rate.0,
+            );
+        }
+    }
+
+    pub fn terminate_stream(&mut self) {
+        if let Err(error) = self.stream.pause() {
+            tracing::error!("Failed to stop the audio stream: {}", error);
+        }
+
+        self.sources.lock().unwrap().clear();
+    }
+}
+
+/// `capture.wav` becomes `capture_component0.wav` for the first component
+fn component_capture_path(path: &Path, index: usize) -> PathBuf {
+    let mut file_name = path.file_stem().unwrap_or_default().to_os_string();
+    file_name.push(format!("_component{}", index));
+    if let Some(extension) = path.extension() {
+        file_name.push(".");
+        file_name.push(extension);
+    }
+
+    path.with_file_name(file_name)
+}
+
+fn to_sample_frame(frame: [f32; 2]) -> [i16; 2] {
+    frame.map(|sample| sample.clamp(i16::MIN as f32, i16::MAX as f32) as i16)
 }
 
-pub fn audio_callback<S: SizedSample>(
+fn audio_callback<S: SizedSample + FromSample<i16>>(
     output_config: StreamConfig,
+    muted: Arc<AtomicBool>,
+    capture: CaptureSlot,
+    sources: AudioSources,
 ) -> impl FnMut(&mut [S], &OutputCallbackInfo) {
+    let mut mixed = Vec::new();
+    let mut component = Vec::new();
+    let mut block = ResampleBlock::default();
+
     move |output, _| {
-        for channel_buffer in output.chunks_mut(output_config.channels as usize) {}
+        let frame_count = output.len() / output_config.channels as usize;
+        mixed.clear();
+        mixed.resize(frame_count, [0.0; 2]);
+
+        let capture = capture.lock().unwrap();
+        let mut sources = sources.lock().unwrap();
+        for (index, source) in sources.iter_mut().enumerate() {
+            let step = source.synchronized_step();
+
+            match capture.as_ref() {
+                Some(ActiveCapture {
+                    capture,
+                    per_component: true,
+                }) if index < capture.track_count() => {
+                    // Resampled on its own first so it can be captured before joining the mix
+                    component.clear();
+                    component.resize(frame_count, [0.0; 2]);
+                    source.mix_into(step, &mut component, &mut block);
+                    capture.push(index, component.iter().copied().map(to_sample_frame));
+
+                    for (mixed, component) in mixed.iter_mut().zip(&component) {
+                        mixed[0] += component[0];
+                        mixed[1] += component[1];
+                    }
+                }
+                _ => source.mix_into(step, &mut mixed, &mut block),
+            }
+        }
+        drop(sources);
+
+        if let Some(ActiveCapture {
+            capture,
+            per_component: false,
+        }) = capture.as_ref()
+        {
+            capture.push(0, mixed.iter().copied().map(to_sample_frame));
+        }
+        drop(capture);
+
+        for (channel_buffer, mixed) in output
+            .chunks_mut(output_config.channels as usize)
+            .zip(&mixed)
+        {
+            let [left, right] = to_sample_frame(*mixed);
+
+            match channel_buffer {
+                [mono] => *mono = S::from_sample(((left as i32 + right as i32) / 2) as i16),
+                [left_channel, right_channel, rest @ ..] => {
+                    *left_channel = S::from_sample(left);
+                    *right_channel = S::from_sample(right);
+                    rest.fill(S::EQUILIBRIUM);
+                }
+                [] => {}
+            }
+        }
+
+        if muted.load(Ordering::Relaxed) {
+            output.fill(S::EQUILIBRIUM);
+        }
     }
 }
 
-pub fn audio_error(error: StreamError) {}
+pub fn audio_error(error: StreamError) {
+    tracing::error!("Audio stream error: {}", error);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use num::rational::Ratio;
+
+    fn test_source(sample_rate: u32, device_sample_rate: u32) -> AudioSource {
+        let buffer = AudioBuffer::new(Ratio::from_integer(sample_rate));
+
+        AudioSource {
+            buffer,
+            step: sample_rate as f64 / device_sample_rate as f64,
+            target_fill: 0,
+            position: 1.0,
+            previous: [0.0; 2],
+            next: [0.0; 2],
+        }
+    }
+
+    fn resample(source: &mut AudioSource, frames: usize) -> Vec<[f32; 2]> {
+        let mut mixed = vec![[0.0; 2]; frames];
+        source.mix_into(source.step, &mut mixed, &mut ResampleBlock::default());
+
+        mixed
+    }
+
+    #[test]
+    fn linear_resampling() {
+        // Upsampling by two puts a frame halfway between every pair
+        let mut source = test_source(100, 200);
+        source.buffer.extend([[0, 0], [100, -100], [200, -200]]);
+        let frames = resample(&mut source, 5);
+        assert_eq!(
+            frames,
+            [
+                [0.0, 0.0],
+                [0.0, 0.0],
+                [0.0, 0.0],
+                [50.0, -50.0],
+                [100.0, -100.0]
+            ]
+        );
+
+        // Running dry fades out instead of cutting off
+        let mut source = test_source(100, 100);
+        source.buffer.push([1000, 1000]);
+        let frames: Vec<_> = resample(&mut source, 3)
+            .into_iter()
+            .map(|frame| frame[0])
+            .collect();
+        assert_eq!(frames[0], 0.0);
+        assert_eq!(frames[1], 1000.0);
+        assert!(frames[2] < 1000.0 && frames[2] > 0.0);
+    }
+}
@@ -0,0 +1,45 @@
+use crate::rom::{
+    import::{import_known_roms, ImportPolicy, ImportReport},
+    RomManager,
+};
+use std::{
+    path::PathBuf,
+    sync::mpsc::{channel, Receiver, TryRecvError},
+};
+
+pub enum ImportProgress {
+    Running,
+    /// The manager with the new ROMs added, for replacing the one the import started from
+    Finished(RomManager, ImportReport),
+    /// The import thread panicked
+    Failed,
+}
+
+/// Imports ROMs on a thread of its own, so big directories don't freeze the window
+pub struct BackgroundImport {
+    result: Receiver<(RomManager, ImportReport)>,
+}
+
+impl BackgroundImport {
+    pub fn start(mut rom_manager: RomManager, policy: ImportPolicy, path: PathBuf) -> Self {
+        let (result_sender, result) = channel();
+
+        std::thread::Builder::new()
+            .name("import".to_string())
+            .spawn(move || {
+                let report = import_known_roms(&mut rom_manager, policy, &[path]);
+                let _ = result_sender.send((rom_manager, report));
+            })
+            .unwrap();
+
+        Self { result }
+    }
+
+    pub fn poll(&self) -> ImportProgress {
+        match self.result.try_recv() {
+            Ok((rom_manager, report)) => ImportProgress::Finished(rom_manager, report),
+            Err(TryRecvError::Empty) => ImportProgress::Running,
+            Err(TryRecvError::Disconnected) => ImportProgress::Failed,
+        }
+    }
+}
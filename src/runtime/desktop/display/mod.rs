@@ -1,10 +1,22 @@
 use crate::{config::GlobalConfig, runtime::RenderingBackendState};
-use std::sync::{Arc, RwLock};
+use std::{
+    error::Error,
+    sync::{Arc, RwLock},
+};
 use winit::window::Window;
 
 pub mod software;
+#[cfg(vulkan)]
 pub mod vulkan;
+#[cfg(wgpu)]
+pub mod wgpu;
 
-pub trait WinitRenderBackendState: RenderingBackendState {
-    fn new(window: Arc<Window>, global_config: Arc<RwLock<GlobalConfig>>) -> Self;
+pub trait WinitRenderBackendState: RenderingBackendState + Sized {
+    /// Fails if the backend can't run on this host, so another one can be tried
+    fn new(
+        window: Arc<Window>,
+        global_config: Arc<RwLock<GlobalConfig>>,
+    ) -> Result<Self, Box<dyn Error>>;
+    /// Creates state for another window that can draw the same display components as this one
+    fn new_secondary(&self, window: Arc<Window>) -> Self;
 }
@@ -1,35 +1,52 @@
 use super::WinitRenderBackendState;
 use crate::{
-    component::display::DisplayComponent,
-    config::GlobalConfig,
+    component::display::{
+        color::{to_xrgb8888, DisplayBuffer, Pixel, BLACK},
+        DisplayComponent,
+    },
+    config::{GlobalConfig, ScaleFilter},
     runtime::{
-        software_egui_render::SoftwareEguiRenderer, RedrawKind, RenderingBackend,
-        RenderingBackendState,
+        frame_blend::FrameBlender,
+        scale::{scale_bilinear, NearestScaler},
+        software_egui_render::SoftwareEguiRenderer,
+        RedrawKind, RenderingBackend, RenderingBackendState,
     },
 };
-use nalgebra::{DMatrix, DMatrixViewMut, Vector2};
-use palette::Srgba;
+use nalgebra::{DMatrixViewMut, Vector2};
 use softbuffer::{Context, Surface};
 use std::{
+    borrow::Cow,
+    error::Error,
     num::NonZero,
     sync::{Arc, Mutex, RwLock},
 };
 use winit::window::Window;
 
-pub struct SoftwareState {
+/// Where frames are presented to
+struct SoftwareOutput {
     surface: Surface<Arc<Window>, Arc<Window>>,
     window: Arc<Window>,
+}
+
+pub struct SoftwareState {
+    /// None when running headless, everything is still rendered to the component buffers
+    output: Option<SoftwareOutput>,
     global_config: Arc<RwLock<GlobalConfig>>,
     egui_renderer: SoftwareEguiRenderer,
+    /// One for each display
+    frame_blenders: Vec<FrameBlender>,
 }
 
 impl RenderingBackendState for SoftwareState {
     type RenderingBackend = SoftwareRendering;
 
     fn surface_resized(&mut self) {
-        let [window_width, window_height]: [u32; 2] = self.window.inner_size().into();
+        let Some(SoftwareOutput { surface, window }) = self.output.as_mut() else {
+            return;
+        };
+        let [window_width, window_height]: [u32; 2] = window.inner_size().into();
 
-        self.surface
+        surface
             .resize(
                 window_width.try_into().unwrap(),
                 window_height.try_into().unwrap(),
@@ -38,7 +55,10 @@ impl RenderingBackendState for SoftwareState {
     }
 
     fn redraw(&mut self, kind: RedrawKind<SoftwareRendering>) {
-        let window_dimensions = self.window.inner_size();
+        let Some(SoftwareOutput { surface, window }) = self.output.as_mut() else {
+            return;
+        };
+        let window_dimensions = window.inner_size();
         let window_dimensions = Vector2::new(window_dimensions.width, window_dimensions.height);
 
         // Skip rendering if impossible window size
@@ -46,62 +66,106 @@ impl RenderingBackendState for SoftwareState {
             return;
         }
 
-        let mut surface_buffer = self.surface.buffer_mut().unwrap();
-        let mut surface_buffer_view = DMatrixViewMut::from_slice(
-            bytemuck::cast_slice_mut(surface_buffer.as_mut()),
-            window_dimensions.x as usize,
-            window_dimensions.y as usize,
-        );
+        let mut surface_buffer = surface.buffer_mut().unwrap();
+        let surface_pixels: &mut [Pixel] = bytemuck::cast_slice_mut(surface_buffer.as_mut());
 
         // Clear the surface buffer
-        surface_buffer_view.fill(Srgba::<u8>::new(0, 0, 0, 0xff));
+        surface_pixels.fill(BLACK);
 
         match kind {
             RedrawKind::Machine(display_components) => {
-                let display_component_guard = display_components[0].lock().unwrap();
-                let display_component_buffer = display_component_guard.display_data();
-                let display_component_buffer_size = Vector2::new(
-                    display_component_buffer.nrows(),
-                    display_component_buffer.ncols(),
+                let global_config = self.global_config.read().unwrap();
+                // Post processing is skipped to save power
+                let color_filter = global_config
+                    .effective_color_filter()
+                    .filter(|_| !global_config.battery_saver);
+                let aspect_mode = global_config.effective_aspect_mode();
+                let scale_filter = global_config.effective_scale_filter();
+                let frame_blend = global_config.effective_frame_blend();
+                let display_layout = global_config.display_layout;
+                drop(global_config);
+                let display_component_guards: Vec<_> = display_components
+                    .iter()
+                    .map(|display_component| display_component.lock().unwrap())
+                    .collect();
+                let areas = display_layout.arrange(
+                    aspect_mode,
+                    window_dimensions.into(),
+                    &display_component_guards
+                        .iter()
+                        .map(|guard| {
+                            let (width, height) = guard.display_data().dimensions();
+                            [width as u32, height as u32]
+                        })
+                        .collect::<Vec<_>>(),
                 );
-
-                let scaling = window_dimensions
-                    .cast::<f32>()
-                    .component_div(&display_component_buffer_size.cast::<f32>());
-
-                // Iterate over each pixel in the display component buffer
-                for x in 0..display_component_buffer.nrows() {
-                    for y in 0..display_component_buffer.ncols() {
-                        let source_pixel = display_component_buffer[(x, y)];
-
-                        let dest_start = Vector2::new(x, y)
-                            .cast::<f32>()
-                            .component_mul(&scaling)
-                            .map(f32::round)
-                            .try_cast::<usize>()
-                            .unwrap()
-                            .zip_map(&window_dimensions, |dest_dim, window_dim| {
-                                dest_dim.min(window_dim as usize)
-                            });
-
-                        let dest_end = Vector2::new(x, y)
-                            .cast::<f32>()
-                            .add_scalar(1.0)
-                            .component_mul(&scaling)
-                            .map(f32::round)
-                            .try_cast::<usize>()
-                            .unwrap()
-                            .zip_map(&window_dimensions, |dest_dim, window_dim| {
-                                dest_dim.min(window_dim as usize)
-                            });
-
-                        // Fill the destination pixels with the source pixel
-                        let mut destination_pixels = surface_buffer_view.view_mut(
-                            (dest_start.x, dest_start.y),
-                            (dest_end.x - dest_start.x, dest_end.y - dest_start.y),
+                self.frame_blenders
+                    .resize_with(display_components.len(), FrameBlender::default);
+
+                for ((display_component_guard, (offset, size)), frame_blender) in
+                    display_component_guards
+                        .iter()
+                        .zip(areas)
+                        .zip(&mut self.frame_blenders)
+                {
+                    let display_component_buffer = display_component_guard.display_data();
+                    // Indexed buffers are looked up through their palette pixel by pixel, unless they need blending
+                    let blended_buffer = if frame_blend {
+                        Some(frame_blender.blend(&display_component_buffer.to_pixels()))
+                    } else {
+                        frame_blender.reset();
+                        None
+                    };
+                    let (width, height) = display_component_buffer.dimensions();
+
+                    // Filtering needs neighbouring rows, so the whole frame is resolved first
+                    if scale_filter == ScaleFilter::Linear {
+                        let mut frame = match blended_buffer {
+                            Some(blended_buffer) => Cow::Borrowed(blended_buffer),
+                            None => display_component_buffer.to_pixels(),
+                        };
+                        if let Some(color_filter) = &color_filter {
+                            for pixel in frame.to_mut().iter_mut() {
+                                *pixel = color_filter.apply(*pixel);
+                            }
+                        }
+
+                        scale_bilinear(
+                            &frame,
+                            surface_pixels,
+                            window_dimensions.cast::<usize>().into(),
+                            offset,
+                            size,
+                        );
+                    } else {
+                        let scaler = NearestScaler::new(
+                            [width, height],
+                            window_dimensions.cast::<usize>().into(),
+                            offset,
+                            size,
                         );
 
-                        destination_pixels.fill(source_pixel);
+                        let mut row = vec![BLACK; width];
+                        for y in 0..height {
+                            match blended_buffer {
+                                Some(blended_buffer) => row.copy_from_slice(
+                                    &blended_buffer.as_slice()[y * width..(y + 1) * width],
+                                ),
+                                None => {
+                                    for (x, pixel) in row.iter_mut().enumerate() {
+                                        *pixel = display_component_buffer.pixel(x, y);
+                                    }
+                                }
+                            }
+
+                            if let Some(color_filter) = &color_filter {
+                                for pixel in row.iter_mut() {
+                                    *pixel = color_filter.apply(*pixel);
+                                }
+                            }
+
+                            scaler.draw_row(y, &row, surface_pixels);
+                        }
                     }
                 }
             }
@@ -109,11 +173,22 @@ impl RenderingBackendState for SoftwareState {
                 context,
                 full_output,
             } => {
+                let surface_buffer_view = DMatrixViewMut::from_slice(
+                    surface_pixels,
+                    window_dimensions.x as usize,
+                    window_dimensions.y as usize,
+                );
+
                 self.egui_renderer
                     .render(context, surface_buffer_view, full_output);
             }
         }
 
+        // Everything above draws pixels in our own format, softbuffer wants them packed differently
+        for value in surface_buffer.iter_mut() {
+            *value = to_xrgb8888(bytemuck::cast(*value));
+        }
+
         surface_buffer.present().unwrap();
     }
 
@@ -128,24 +203,41 @@ impl RenderingBackendState for SoftwareState {
 }
 
 impl WinitRenderBackendState for SoftwareState {
-    fn new(window: Arc<Window>, global_config: Arc<RwLock<GlobalConfig>>) -> Self {
+    fn new(
+        window: Arc<Window>,
+        global_config: Arc<RwLock<GlobalConfig>>,
+    ) -> Result<Self, Box<dyn Error>> {
         let window_dimensions = window.inner_size();
         let window_dimensions = Vector2::new(
             NonZero::new(window_dimensions.width).unwrap(),
             NonZero::new(window_dimensions.height).unwrap(),
         );
 
-        let context = Context::new(window.clone()).unwrap();
-        let mut surface = Surface::new(&context, window.clone()).unwrap();
+        let context = Context::new(window.clone())?;
+        let mut surface = Surface::new(&context, window.clone())?;
 
-        surface
-            .resize(window_dimensions.x, window_dimensions.y)
-            .unwrap();
+        surface.resize(window_dimensions.x, window_dimensions.y)?;
 
+        Ok(Self {
+            output: Some(SoftwareOutput { surface, window }),
+            egui_renderer: SoftwareEguiRenderer::default(),
+            frame_blenders: Vec::new(),
+            global_config,
+        })
+    }
+
+    fn new_secondary(&self, window: Arc<Window>) -> Self {
+        Self::new(window, self.global_config.clone()).unwrap()
+    }
+}
+
+impl SoftwareState {
+    /// A state with no window, for running machines without showing them
+    pub fn headless(global_config: Arc<RwLock<GlobalConfig>>) -> Self {
         Self {
-            surface,
-            window,
+            output: None,
             egui_renderer: SoftwareEguiRenderer::default(),
+            frame_blenders: Vec::new(),
             global_config,
         }
     }
@@ -156,6 +248,8 @@ pub struct SoftwareRendering;
 impl RenderingBackend for SoftwareRendering {
     // Software rendering doesn't require any initialization data
     type ComponentInitializationData = ();
-    type ComponentDisplayBuffer = DMatrix<Srgba<u8>>;
+    type ComponentDisplayBuffer = DisplayBuffer;
     type RuntimeState = SoftwareState;
+
+    const COLOR_FILTER: bool = true;
 }
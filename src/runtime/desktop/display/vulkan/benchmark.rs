@@ -0,0 +1,122 @@
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use vulkano::{
+    command_buffer::{
+        allocator::StandardCommandBufferAllocator, AutoCommandBufferBuilder, BlitImageInfo,
+        CommandBufferUsage,
+    },
+    device::{physical::PhysicalDeviceType, Device, DeviceCreateInfo, QueueCreateInfo, QueueFlags},
+    format::Format,
+    image::{sampler::Filter, Image, ImageCreateInfo, ImageType, ImageUsage},
+    instance::{Instance, InstanceCreateFlags, InstanceCreateInfo},
+    memory::allocator::{AllocationCreateInfo, StandardMemoryAllocator},
+    sync::GpuFuture,
+    VulkanLibrary,
+};
+
+/// Blits a dummy machine display to a offscreen image, returning the average time per frame or None if vulkan is
+/// unusable
+///
+/// TODO: Include the menu once the vulkan egui renderer actually draws
+pub fn benchmark(frames: u32) -> Option<Duration> {
+    let library = VulkanLibrary::new().ok()?;
+    // No surface so no extensions needed
+    let instance = Instance::new(
+        library,
+        InstanceCreateInfo {
+            flags: InstanceCreateFlags::ENUMERATE_PORTABILITY,
+            ..Default::default()
+        },
+    )
+    .ok()?;
+
+    let (physical_device, queue_family_index) = instance
+        .enumerate_physical_devices()
+        .ok()?
+        .filter_map(|p| {
+            p.queue_family_properties()
+                .iter()
+                .position(|q| q.queue_flags.intersects(QueueFlags::GRAPHICS))
+                .map(|i| (p, i as u32))
+        })
+        .min_by_key(|(p, _)| match p.properties().device_type {
+            PhysicalDeviceType::DiscreteGpu => 0,
+            PhysicalDeviceType::IntegratedGpu => 1,
+            PhysicalDeviceType::VirtualGpu => 2,
+            PhysicalDeviceType::Cpu => 3,
+            PhysicalDeviceType::Other => 4,
+            _ => 5,
+        })?;
+
+    let (device, mut queues) = Device::new(
+        physical_device,
+        DeviceCreateInfo {
+            queue_create_infos: vec![QueueCreateInfo {
+                queue_family_index,
+                ..Default::default()
+            }],
+            ..Default::default()
+        },
+    )
+    .ok()?;
+    let queue = queues.next()?;
+
+    let memory_allocator = Arc::new(StandardMemoryAllocator::new_default(device.clone()));
+    let command_buffer_allocator =
+        StandardCommandBufferAllocator::new(device.clone(), Default::default());
+
+    let source_image = Image::new(
+        memory_allocator.clone(),
+        ImageCreateInfo {
+            image_type: ImageType::Dim2d,
+            format: Format::R8G8B8A8_SRGB,
+            extent: [64, 32, 1],
+            usage: ImageUsage::TRANSFER_SRC,
+            ..Default::default()
+        },
+        AllocationCreateInfo::default(),
+    )
+    .ok()?;
+    let destination_image = Image::new(
+        memory_allocator,
+        ImageCreateInfo {
+            image_type: ImageType::Dim2d,
+            format: Format::B8G8R8A8_SRGB,
+            extent: [640, 480, 1],
+            usage: ImageUsage::TRANSFER_DST,
+            ..Default::default()
+        },
+        AllocationCreateInfo::default(),
+    )
+    .ok()?;
+
+    let start = Instant::now();
+
+    for _ in 0..frames {
+        let mut command_buffer = AutoCommandBufferBuilder::primary(
+            &command_buffer_allocator,
+            queue.queue_family_index(),
+            CommandBufferUsage::OneTimeSubmit,
+        )
+        .ok()?;
+
+        command_buffer
+            .blit_image(BlitImageInfo {
+                filter: Filter::Nearest,
+                ..BlitImageInfo::images(source_image.clone(), destination_image.clone())
+            })
+            .ok()?;
+
+        vulkano::sync::now(device.clone())
+            .then_execute(queue.clone(), command_buffer.build().ok()?)
+            .ok()?
+            .then_signal_fence_and_flush()
+            .ok()?
+            .wait(None)
+            .ok()?;
+    }
+
+    Some(start.elapsed() / frames)
+}
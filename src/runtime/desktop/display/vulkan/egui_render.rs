@@ -17,8 +17,11 @@ use vulkano::{
         Image, ImageCreateFlags, ImageCreateInfo, ImageType, ImageUsage,
     },
     memory::allocator::{AllocationCreateInfo, MemoryTypeFilter, StandardMemoryAllocator},
-    pipeline::graphics::color_blend::{
-        AttachmentBlend, BlendFactor, ColorBlendAttachmentState, ColorBlendState,
+    pipeline::{
+        cache::PipelineCache,
+        graphics::color_blend::{
+            AttachmentBlend, BlendFactor, ColorBlendAttachmentState, ColorBlendState,
+        },
     },
     render_pass::RenderPass,
     swapchain::Swapchain,
@@ -48,6 +51,8 @@ pub struct EguiRenderer {
     texture_descriptors: HashMap<TextureId, Arc<PersistentDescriptorSet>>,
     texture_images: HashMap<TextureId, Arc<ImageView>>,
     queue: Arc<Queue>,
+    /// Shared with every window so pipelines compiled once are reused
+    pipeline_cache: Arc<PipelineCache>,
 }
 
 impl EguiRenderer {
@@ -56,6 +61,7 @@ impl EguiRenderer {
         device: Arc<Device>,
         queue: Arc<Queue>,
         memory_allocator: Arc<StandardMemoryAllocator>,
+        pipeline_cache: Arc<PipelineCache>,
     ) -> Self {
         let [window_width, window_height]: [u32; 2] = window.inner_size().into();
 
@@ -159,6 +165,7 @@ impl EguiRenderer {
             texture_descriptors: HashMap::new(),
             texture_images: HashMap::new(),
             queue,
+            pipeline_cache,
         }
     }
 
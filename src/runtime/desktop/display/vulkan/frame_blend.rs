@@ -0,0 +1,108 @@
+use std::sync::Arc;
+use vulkano::{
+    command_buffer::{
+        allocator::CommandBufferAllocator, AutoCommandBufferBuilder, BlitImageInfo, ImageBlit,
+    },
+    image::{sampler::Filter, Image, ImageCreateInfo, ImageType, ImageUsage},
+    memory::allocator::{AllocationCreateInfo, StandardMemoryAllocator},
+};
+
+/// The last two machine frames as the two slices of a 3D image, so a linear blit down to a single slice averages
+/// them without needing a pipeline
+pub struct FrameBlendHistory {
+    frames: Arc<Image>,
+    blended: Arc<Image>,
+    /// Slice the next frame goes into, the other one has the frame before. Which is which doesn't matter to a average
+    next_slice: u32,
+    /// Both slices start out as the first frame
+    filled: bool,
+}
+
+impl FrameBlendHistory {
+    pub fn new(memory_allocator: Arc<StandardMemoryAllocator>, frame: &Image) -> Self {
+        let [width, height, _] = frame.extent();
+
+        let create_image = |image_type, depth| {
+            Image::new(
+                memory_allocator.clone(),
+                ImageCreateInfo {
+                    image_type,
+                    format: frame.format(),
+                    extent: [width, height, depth],
+                    usage: ImageUsage::TRANSFER_SRC | ImageUsage::TRANSFER_DST,
+                    ..Default::default()
+                },
+                AllocationCreateInfo::default(),
+            )
+            .unwrap()
+        };
+
+        Self {
+            frames: create_image(ImageType::Dim3d, 2),
+            blended: create_image(ImageType::Dim2d, 1),
+            next_slice: 0,
+            filled: false,
+        }
+    }
+
+    /// If this was made for frames like this one
+    pub fn fits(&self, frame: &Image) -> bool {
+        let [width, height, _] = frame.extent();
+        let [history_width, history_height, _] = self.frames.extent();
+
+        [width, height] == [history_width, history_height] && frame.format() == self.frames.format()
+    }
+
+    /// Records the frame and returns the image with it blended with the one before
+    pub fn blend<L, A: CommandBufferAllocator>(
+        &mut self,
+        command_buffer: &mut AutoCommandBufferBuilder<L, A>,
+        frame: Arc<Image>,
+    ) -> Arc<Image> {
+        let [width, height, _] = frame.extent();
+
+        let slices = if self.filled {
+            self.next_slice..self.next_slice + 1
+        } else {
+            0..2
+        };
+
+        // Blits rather than copies, as copying between 2D and 3D images needs Vulkan 1.1
+        for slice in slices {
+            command_buffer
+                .blit_image(BlitImageInfo {
+                    regions: [ImageBlit {
+                        src_subresource: frame.subresource_layers(),
+                        src_offsets: [[0, 0, 0], [width, height, 1]],
+                        dst_subresource: self.frames.subresource_layers(),
+                        dst_offsets: [[0, 0, slice], [width, height, slice + 1]],
+                        ..Default::default()
+                    }]
+                    .into(),
+                    filter: Filter::Nearest,
+                    ..BlitImageInfo::images(frame.clone(), self.frames.clone())
+                })
+                .unwrap();
+        }
+        self.filled = true;
+        self.next_slice ^= 1;
+
+        // Sampling in the middle of the two slices, the filter decodes sRGB so this mixes in linear light
+        command_buffer
+            .blit_image(BlitImageInfo {
+                regions: [ImageBlit {
+                    src_subresource: self.frames.subresource_layers(),
+                    src_offsets: [[0, 0, 0], [width, height, 2]],
+                    dst_subresource: self.blended.subresource_layers(),
+                    dst_offsets: [[0, 0, 0], [width, height, 1]],
+                    ..Default::default()
+                }]
+                .into(),
+                filter: Filter::Linear,
+                ..BlitImageInfo::images(self.frames.clone(), self.blended.clone())
+            })
+            .unwrap();
+
+        self.blended.clone()
+    }
+}
@@ -1,38 +1,59 @@
 use super::WinitRenderBackendState;
 use crate::{
     component::display::DisplayComponent,
-    config::GlobalConfig,
+    config::{Buffering, GlobalConfig, ScaleFilter, SurfaceFormatPreference},
     machine::executor::Executor,
     runtime::{RedrawKind, RenderingBackend, RenderingBackendState},
 };
 use egui_render::EguiRenderer;
+use frame_blend::FrameBlendHistory;
 use nalgebra::Vector2;
-use std::sync::{Arc, Mutex, RwLock};
+use pipeline_cache::PersistentPipelineCache;
+use std::{
+    error::Error,
+    sync::{Arc, Mutex, RwLock},
+};
 use vulkano::{
     command_buffer::{
         allocator::StandardCommandBufferAllocator, AutoCommandBufferBuilder, BlitImageInfo,
-        CommandBufferUsage,
+        ClearColorImageInfo, CommandBufferExecFuture, CommandBufferUsage, ImageBlit,
     },
     device::{
         physical::PhysicalDeviceType, Device, DeviceCreateInfo, DeviceExtensions, Queue,
         QueueCreateInfo, QueueFlags,
     },
+    format::{ClearColorValue, Format},
     image::{sampler::Filter, view::ImageView, Image, ImageLayout, ImageUsage},
-    instance::{Instance, InstanceCreateFlags, InstanceCreateInfo},
+    instance::{Instance, InstanceCreateFlags, InstanceCreateInfo, InstanceExtensions},
     memory::allocator::StandardMemoryAllocator,
     render_pass::{Framebuffer, FramebufferCreateInfo, RenderPass},
     single_pass_renderpass,
     swapchain::{
-        acquire_next_image, PresentMode, Surface, Swapchain, SwapchainCreateInfo,
-        SwapchainPresentInfo,
+        acquire_next_image, ColorSpace, PresentFuture, PresentMode, Surface, Swapchain,
+        SwapchainAcquireFuture, SwapchainCreateInfo, SwapchainPresentInfo,
+    },
+    sync::{
+        future::{FenceSignalFuture, JoinFuture},
+        GpuFuture,
     },
-    sync::GpuFuture,
     Validated, VulkanError, VulkanLibrary,
 };
 use winit::window::Window;
 
+pub mod benchmark;
 mod shader;
 mod egui_render;
+mod frame_blend;
+mod pipeline_cache;
+
+/// Signaled when a frame is done presenting
+type FrameFence = Arc<
+    FenceSignalFuture<
+        PresentFuture<
+            CommandBufferExecFuture<JoinFuture<Box<dyn GpuFuture>, SwapchainAcquireFuture>>,
+        >,
+    >,
+>;
 
 pub struct VulkanState {
     instance: Arc<Instance>,
@@ -44,12 +65,18 @@ pub struct VulkanState {
     memory_allocator: Arc<StandardMemoryAllocator>,
     command_buffer_allocator: Arc<StandardCommandBufferAllocator>,
     render_pass: Arc<RenderPass>,
-    previous_frame_future: Option<Box<dyn GpuFuture>>,
+    /// Saved once the last window sharing the device is gone
+    pipeline_cache: Arc<PersistentPipelineCache>,
+    /// One slot per swapchain image, so we only wait on a frame when its image is about to be reused
+    frame_fences: Vec<Option<FrameFence>>,
+    previous_frame_index: usize,
     framebuffers: Vec<Arc<Framebuffer>>,
     swapchain_images: Vec<Arc<Image>>,
     recreate_swapchain: bool,
     window: Arc<Window>,
     egui_renderer_state: EguiRenderer,
+    /// One for each display, only kept while frame blending is on
+    frame_blend_histories: Vec<Option<FrameBlendHistory>>,
     global_config: Arc<RwLock<GlobalConfig>>,
 }
 
@@ -60,17 +87,16 @@ impl RenderingBackendState for VulkanState {
         self.recreate_swapchain = true;
     }
 
+    fn surface_config_changed(&mut self) {
+        self.recreate_swapchain = true;
+    }
+
     fn redraw(&mut self, kind: RedrawKind<VulkanRendering>) {
         let window_size = Vector2::new(
             self.window.inner_size().width,
             self.window.inner_size().height,
         );
 
-        self.previous_frame_future
-            .as_mut()
-            .unwrap()
-            .cleanup_finished();
-
         // Skip rendering if impossible window size
         if window_size.as_slice().contains(&0) {
             return;
@@ -79,29 +105,43 @@ impl RenderingBackendState for VulkanState {
         if self.recreate_swapchain {
             tracing::trace!("Recreating swapchain");
 
+            let surface_capabilities = self
+                .device
+                .physical_device()
+                .surface_capabilities(&self.surface, Default::default())
+                .unwrap();
+
+            let global_config = self.global_config.read().unwrap();
+            let (image_format, image_color_space) =
+                select_surface_format(&self.device, &self.surface, global_config.surface_format);
+
             let (new_swapchain, new_images) = self
                 .swapchain
                 .recreate(SwapchainCreateInfo {
                     image_extent: window_size.into(),
+                    min_image_count: min_image_count(
+                        global_config.buffering,
+                        surface_capabilities.min_image_count,
+                        surface_capabilities.max_image_count,
+                    ),
+                    image_format,
+                    image_color_space,
+                    present_mode: present_mode(global_config.vsync),
                     ..self.swapchain.create_info()
                 })
                 .expect("Failed to recreate swapchain");
+            drop(global_config);
 
-            let new_framebuffers = new_images
-                .iter()
-                .map(|image| {
-                    let view = ImageView::new_default(image.clone()).unwrap();
-                    Framebuffer::new(
-                        self.render_pass.clone(),
-                        FramebufferCreateInfo {
-                            attachments: vec![view],
-                            ..Default::default()
-                        },
-                    )
-                    .unwrap()
-                })
-                .collect::<Vec<_>>();
+            // The attachment format has to match the swapchain
+            if new_swapchain.image_format() != self.swapchain.image_format() {
+                self.render_pass = create_render_pass(&self.device, &new_swapchain);
+            }
 
+            let new_framebuffers = create_framebuffers(&self.render_pass, &new_images);
+
+            // The old frames will finish on their own, we just can't index them anymore
+            self.frame_fences = vec![None; new_images.len()];
+            self.previous_frame_index = 0;
             self.swapchain = new_swapchain;
             self.swapchain_images = new_images;
             self.framebuffers = new_framebuffers;
@@ -113,6 +153,11 @@ impl RenderingBackendState for VulkanState {
         };
         self.recreate_swapchain |= recreate_swapchain;
 
+        // Wait for the frame that last used this image to finish
+        if let Some(frame_fence) = &self.frame_fences[image_index as usize] {
+            frame_fence.wait(None).unwrap();
+        }
+
         let swapchain_image = self.swapchain_images[image_index as usize].clone();
 
         let mut command_buffer = AutoCommandBufferBuilder::primary(
@@ -124,20 +169,119 @@ impl RenderingBackendState for VulkanState {
 
         match kind {
             RedrawKind::Machine(display_components) => {
-                let display_component_guard = display_components[0].lock().unwrap();
-                let display_component_buffer = display_component_guard.display_data();
+                let global_config = self.global_config.read().unwrap();
+                let aspect_mode = global_config.effective_aspect_mode();
+                let scale_filter = global_config.effective_scale_filter();
+                let frame_blend = global_config.effective_frame_blend();
+                let display_layout = global_config.display_layout;
+                drop(global_config);
+                let mut display_component_buffers: Vec<_> = display_components
+                    .iter()
+                    .map(|display_component| {
+                        display_component.lock().unwrap().display_data().clone()
+                    })
+                    .collect();
+
+                if frame_blend {
+                    self.frame_blend_histories
+                        .resize_with(display_component_buffers.len(), || None);
+
+                    for (display_component_buffer, history) in display_component_buffers
+                        .iter_mut()
+                        .zip(&mut self.frame_blend_histories)
+                    {
+                        if !history
+                            .as_ref()
+                            .is_some_and(|history| history.fits(display_component_buffer))
+                        {
+                            *history = Some(FrameBlendHistory::new(
+                                self.memory_allocator.clone(),
+                                display_component_buffer,
+                            ));
+                        }
+
+                        *display_component_buffer = history
+                            .as_mut()
+                            .unwrap()
+                            .blend(&mut command_buffer, display_component_buffer.clone());
+                    }
+                } else {
+                    self.frame_blend_histories.clear();
+                }
 
+                // Bars around the displays when their aspect ratio is kept
                 command_buffer
-                    .blit_image(BlitImageInfo {
-                        src_image_layout: ImageLayout::TransferSrcOptimal,
-                        dst_image_layout: ImageLayout::TransferDstOptimal,
-                        filter: Filter::Nearest,
-                        ..BlitImageInfo::images(
-                            display_component_buffer.clone(),
-                            swapchain_image.clone(),
-                        )
+                    .clear_color_image(ClearColorImageInfo {
+                        clear_value: ClearColorValue::Float([0.0, 0.0, 0.0, 1.0]),
+                        ..ClearColorImageInfo::image(swapchain_image.clone())
                     })
                     .unwrap();
+
+                let [window_width, window_height, _] = swapchain_image.extent();
+                let areas = display_layout.arrange(
+                    aspect_mode,
+                    [window_width, window_height],
+                    &display_component_buffers
+                        .iter()
+                        .map(|display_component_buffer| {
+                            let [width, height, _] = display_component_buffer.extent();
+                            [width, height]
+                        })
+                        .collect::<Vec<_>>(),
+                );
+
+                for (display_component_buffer, (offset, size)) in
+                    display_component_buffers.iter().zip(areas)
+                {
+                    // Too small a window to show anything in
+                    if size.contains(&0) {
+                        continue;
+                    }
+
+                    let [width, height, _] = display_component_buffer.extent();
+                    // Integer scaling can overflow the window, blits must stay inside it so the source is cut to what fits
+                    let visible = |axis: usize, source: u32, window: u32| {
+                        let source_end = (source as u64 * (window - offset[axis]) as u64
+                            / size[axis] as u64)
+                            .min(source as u64) as u32;
+
+                        (
+                            source_end,
+                            offset[axis]
+                                + (source_end as u64 * size[axis] as u64 / source as u64) as u32,
+                        )
+                    };
+                    let (source_right, destination_right) = visible(0, width, window_width);
+                    let (source_bottom, destination_bottom) = visible(1, height, window_height);
+
+                    // The blit decodes the sRGB component image to linear and encodes it for the swapchain format, so
+                    // only sRGB and extended linear swapchains come out right
+                    command_buffer
+                        .blit_image(BlitImageInfo {
+                            src_image_layout: ImageLayout::TransferSrcOptimal,
+                            dst_image_layout: ImageLayout::TransferDstOptimal,
+                            regions: [ImageBlit {
+                                src_subresource: display_component_buffer.subresource_layers(),
+                                src_offsets: [[0, 0, 0], [source_right, source_bottom, 1]],
+                                dst_subresource: swapchain_image.subresource_layers(),
+                                dst_offsets: [
+                                    [offset[0], offset[1], 0],
+                                    [destination_right, destination_bottom, 1],
+                                ],
+                                ..Default::default()
+                            }]
+                            .into(),
+                            filter: match scale_filter {
+                                ScaleFilter::Nearest => Filter::Nearest,
+                                ScaleFilter::Linear => Filter::Linear,
+                            },
+                            ..BlitImageInfo::images(
+                                display_component_buffer.clone(),
+                                swapchain_image.clone(),
+                            )
+                        })
+                        .unwrap();
+                }
             }
             RedrawKind::Egui {
                 context,
@@ -147,10 +291,16 @@ impl RenderingBackendState for VulkanState {
 
         let command_buffer = command_buffer.build().unwrap();
 
-        match self
-            .previous_frame_future
-            .take()
-            .unwrap()
+        let previous_frame_future = match self.frame_fences[self.previous_frame_index].clone() {
+            Some(frame_fence) => frame_fence.boxed(),
+            None => {
+                let mut now = vulkano::sync::now(self.device.clone());
+                now.cleanup_finished();
+                now.boxed()
+            }
+        };
+
+        self.frame_fences[image_index as usize] = match previous_frame_future
             .join(acquire_future)
             .then_execute(self.gui_queue.clone(), command_buffer)
             .unwrap()
@@ -161,15 +311,14 @@ impl RenderingBackendState for VulkanState {
             .then_signal_fence_and_flush()
             .map_err(Validated::unwrap)
         {
-            Ok(previous_frame_future) => {
-                self.previous_frame_future = Some(Box::new(previous_frame_future));
-            }
+            Ok(frame_fence) => Some(Arc::new(frame_fence)),
             Err(VulkanError::OutOfDate) => {
                 self.recreate_swapchain = true;
-                self.previous_frame_future = Some(vulkano::sync::now(self.device.clone()).boxed());
+                None
             }
             Err(_) => panic!("Failed to present swapchain image"),
-        }
+        };
+        self.previous_frame_index = image_index as usize;
     }
 
     fn initialize_components(
@@ -194,14 +343,21 @@ impl RenderingBackendState for VulkanState {
 }
 
 impl WinitRenderBackendState for VulkanState {
-    fn new(window: Arc<Window>, global_config: Arc<RwLock<GlobalConfig>>) -> Self {
+    fn new(
+        window: Arc<Window>,
+        global_config: Arc<RwLock<GlobalConfig>>,
+    ) -> Result<Self, Box<dyn Error>> {
         let window_size = window.inner_size().into();
 
-        let library = VulkanLibrary::new().unwrap();
+        let library = VulkanLibrary::new()?;
 
         tracing::info!("Found vulkan {} implementation", library.api_version());
 
-        let required_extensions = Surface::required_extensions(&window);
+        let required_extensions = InstanceExtensions {
+            // Needed for the HDR color spaces to show up
+            ext_swapchain_colorspace: library.supported_extensions().ext_swapchain_colorspace,
+            ..Surface::required_extensions(&window)
+        };
         let instance = Instance::new(
             library,
             InstanceCreateInfo {
@@ -209,16 +365,14 @@ impl WinitRenderBackendState for VulkanState {
                 enabled_extensions: required_extensions,
                 ..Default::default()
             },
-        )
-        .unwrap();
-        let surface = Surface::from_window(instance.clone(), window.clone()).unwrap();
+        )?;
+        let surface = Surface::from_window(instance.clone(), window.clone())?;
         let device_extensions = DeviceExtensions {
             khr_swapchain: true,
             ..DeviceExtensions::empty()
         };
         let (physical_device, queue_family_index) = instance
-            .enumerate_physical_devices()
-            .unwrap()
+            .enumerate_physical_devices()?
             .filter(|p| p.supported_extensions().contains(&device_extensions))
             .filter_map(|p| {
                 p.queue_family_properties()
@@ -238,7 +392,7 @@ impl WinitRenderBackendState for VulkanState {
                 PhysicalDeviceType::Other => 4,
                 _ => 5,
             })
-            .unwrap();
+            .ok_or("No vulkan device can present to the window")?;
 
         tracing::info!(
             "Using device: {} (type: {:?})",
@@ -256,8 +410,7 @@ impl WinitRenderBackendState for VulkanState {
                 }],
                 ..Default::default()
             },
-        )
-        .unwrap();
+        )?;
         let queues: Vec<_> = queues.collect();
 
         tracing::info!("Using {} queue(s)", queues.len());
@@ -269,87 +422,29 @@ impl WinitRenderBackendState for VulkanState {
             (gui_queue.clone(), queues.to_vec())
         };
 
-        let (swapchain, swapchain_images) = {
-            let surface_capabilities = device
-                .physical_device()
-                .surface_capabilities(&surface, Default::default())
-                .unwrap();
-            let image_format = device
-                .physical_device()
-                .surface_formats(&surface, Default::default())
-                .unwrap()[0]
-                .0;
-
-            Swapchain::new(
-                device.clone(),
-                surface.clone(),
-                SwapchainCreateInfo {
-                    min_image_count: surface_capabilities.min_image_count.max(2),
-                    image_format,
-                    image_extent: window_size,
-                    image_usage: ImageUsage::COLOR_ATTACHMENT | ImageUsage::TRANSFER_DST,
-                    composite_alpha: surface_capabilities
-                        .supported_composite_alpha
-                        .into_iter()
-                        .next()
-                        .unwrap(),
-                    present_mode: if global_config.read().unwrap().vsync {
-                        PresentMode::Fifo
-                    } else {
-                        PresentMode::Immediate
-                    },
-                    ..Default::default()
-                },
-            )
-            .unwrap()
-        };
+        let (swapchain, swapchain_images) =
+            create_swapchain(&device, &surface, window_size, &global_config);
         let memory_allocator = Arc::new(StandardMemoryAllocator::new_default(device.clone()));
         let command_buffer_allocator = Arc::new(StandardCommandBufferAllocator::new(
             device.clone(),
             Default::default(),
         ));
 
-        let render_pass = single_pass_renderpass!(
-            device.clone(),
-            attachments: {
-                color: {
-                    format: swapchain.image_format(),
-                    samples: 1,
-                    load_op: Clear,
-                    store_op: Store,
-                }
-            },
-            pass: {
-                color: [color],
-                depth_stencil: {}
-            }
-        )
-        .unwrap();
+        let render_pass = create_render_pass(&device, &swapchain);
+        let framebuffers = create_framebuffers(&render_pass, &swapchain_images);
+        let pipeline_cache = Arc::new(PersistentPipelineCache::load(&device));
 
-        let framebuffers: Vec<Arc<Framebuffer>> = swapchain_images
-            .iter()
-            .map(|image| {
-                let view = ImageView::new_default(image.clone()).unwrap();
-
-                Framebuffer::new(
-                    render_pass.clone(),
-                    FramebufferCreateInfo {
-                        attachments: vec![view.clone()],
-                        ..Default::default()
-                    },
-                )
-                .unwrap()
-            })
-            .collect();
-
-        Self {
+        Ok(Self {
             egui_renderer_state: EguiRenderer::new(
                 window.clone(),
                 device.clone(),
                 gui_queue.clone(),
                 memory_allocator.clone(),
+                pipeline_cache.cache(),
             ),
-            previous_frame_future: Some(vulkano::sync::now(device.clone()).boxed()),
+            pipeline_cache,
+            frame_fences: vec![None; swapchain_images.len()],
+            previous_frame_index: 0,
             instance,
             surface,
             device,
@@ -363,11 +458,223 @@ impl WinitRenderBackendState for VulkanState {
             swapchain_images,
             recreate_swapchain: false,
             window,
+            frame_blend_histories: Vec::new(),
             global_config,
+        })
+    }
+
+    fn new_secondary(&self, window: Arc<Window>) -> Self {
+        // Reuse the device so the display components images are usable here
+        let surface = Surface::from_window(self.instance.clone(), window.clone()).unwrap();
+
+        if !self
+            .device
+            .physical_device()
+            .surface_support(self.gui_queue.queue_family_index(), &surface)
+            .unwrap_or(false)
+        {
+            tracing::warn!("Device may not be able to present to the new window");
         }
+
+        let (swapchain, swapchain_images) = create_swapchain(
+            &self.device,
+            &surface,
+            window.inner_size().into(),
+            &self.global_config,
+        );
+        let render_pass = create_render_pass(&self.device, &swapchain);
+        let framebuffers = create_framebuffers(&render_pass, &swapchain_images);
+
+        Self {
+            egui_renderer_state: EguiRenderer::new(
+                window.clone(),
+                self.device.clone(),
+                self.gui_queue.clone(),
+                self.memory_allocator.clone(),
+                self.pipeline_cache.cache(),
+            ),
+            pipeline_cache: self.pipeline_cache.clone(),
+            frame_fences: vec![None; swapchain_images.len()],
+            previous_frame_index: 0,
+            instance: self.instance.clone(),
+            surface,
+            device: self.device.clone(),
+            gui_queue: self.gui_queue.clone(),
+            queues_for_components: self.queues_for_components.clone(),
+            swapchain,
+            memory_allocator: self.memory_allocator.clone(),
+            command_buffer_allocator: self.command_buffer_allocator.clone(),
+            render_pass,
+            framebuffers,
+            swapchain_images,
+            recreate_swapchain: false,
+            window,
+            frame_blend_histories: Vec::new(),
+            global_config: self.global_config.clone(),
+        }
+    }
+}
+
+fn create_swapchain(
+    device: &Arc<Device>,
+    surface: &Arc<Surface>,
+    window_size: [u32; 2],
+    global_config: &RwLock<GlobalConfig>,
+) -> (Arc<Swapchain>, Vec<Arc<Image>>) {
+    let surface_capabilities = device
+        .physical_device()
+        .surface_capabilities(surface, Default::default())
+        .unwrap();
+    let global_config = global_config.read().unwrap();
+    let (image_format, image_color_space) =
+        select_surface_format(device, surface, global_config.surface_format);
+
+    Swapchain::new(
+        device.clone(),
+        surface.clone(),
+        SwapchainCreateInfo {
+            min_image_count: min_image_count(
+                global_config.buffering,
+                surface_capabilities.min_image_count,
+                surface_capabilities.max_image_count,
+            ),
+            image_format,
+            image_color_space,
+            image_extent: window_size,
+            image_usage: ImageUsage::COLOR_ATTACHMENT | ImageUsage::TRANSFER_DST,
+            composite_alpha: surface_capabilities
+                .supported_composite_alpha
+                .into_iter()
+                .next()
+                .unwrap(),
+            present_mode: present_mode(global_config.vsync),
+            ..Default::default()
+        },
+    )
+    .unwrap()
+}
+
+fn present_mode(vsync: bool) -> PresentMode {
+    if vsync {
+        PresentMode::Fifo
+    } else {
+        PresentMode::Immediate
+    }
+}
+
+/// Candidate surface formats, in order of preference
+const SRGB_SURFACE_FORMATS: &[(Format, ColorSpace)] = &[
+    (Format::B8G8R8A8_SRGB, ColorSpace::SrgbNonLinear),
+    (Format::R8G8B8A8_SRGB, ColorSpace::SrgbNonLinear),
+    (Format::A8B8G8R8_SRGB_PACK32, ColorSpace::SrgbNonLinear),
+];
+const TEN_BIT_SURFACE_FORMATS: &[(Format, ColorSpace)] = &[
+    (Format::A2B10G10R10_UNORM_PACK32, ColorSpace::SrgbNonLinear),
+    (Format::A2R10G10B10_UNORM_PACK32, ColorSpace::SrgbNonLinear),
+];
+const HDR_SURFACE_FORMATS: &[(Format, ColorSpace)] =
+    &[(Format::R16G16B16A16_SFLOAT, ColorSpace::ExtendedSrgbLinear)];
+
+fn select_surface_format(
+    device: &Arc<Device>,
+    surface: &Arc<Surface>,
+    preference: SurfaceFormatPreference,
+) -> (Format, ColorSpace) {
+    let supported_formats = device
+        .physical_device()
+        .surface_formats(surface, Default::default())
+        .unwrap();
+
+    let candidates: &[&[(Format, ColorSpace)]] = match preference {
+        SurfaceFormatPreference::Srgb => &[SRGB_SURFACE_FORMATS],
+        SurfaceFormatPreference::TenBit => &[TEN_BIT_SURFACE_FORMATS, SRGB_SURFACE_FORMATS],
+        SurfaceFormatPreference::Hdr => &[
+            HDR_SURFACE_FORMATS,
+            TEN_BIT_SURFACE_FORMATS,
+            SRGB_SURFACE_FORMATS,
+        ],
+    };
+
+    let selected = candidates
+        .iter()
+        .flat_map(|candidates| candidates.iter())
+        .find(|candidate| supported_formats.contains(candidate))
+        .copied()
+        .unwrap_or_else(|| {
+            tracing::warn!(
+                "Surface supports none of the preferred formats, colors will probably be wrong"
+            );
+
+            supported_formats[0]
+        });
+
+    // 10 bit unorm needs sRGB encoding the blit doesn't do
+    if TEN_BIT_SURFACE_FORMATS.contains(&selected) {
+        tracing::warn!("10 bit output will look too dark until there is a post processing pass");
     }
+
+    tracing::info!(
+        "Using surface format {:?} with color space {:?}",
+        selected.0,
+        selected.1
+    );
+
+    selected
 }
 
+/// Clamps the requested image count to what the surface supports
+fn min_image_count(buffering: Buffering, surface_min: u32, surface_max: Option<u32>) -> u32 {
+    let image_count = buffering.frame_count().max(surface_min);
+
+    match surface_max {
+        Some(surface_max) => image_count.min(surface_max),
+        None => image_count,
+    }
+}
+
+fn create_render_pass(device: &Arc<Device>, swapchain: &Swapchain) -> Arc<RenderPass> {
+    single_pass_renderpass!(
+        device.clone(),
+        attachments: {
+            color: {
+                format: swapchain.image_format(),
+                samples: 1,
+                load_op: Clear,
+                store_op: Store,
+            }
+        },
+        pass: {
+            color: [color],
+            depth_stencil: {}
+        }
+    )
+    .unwrap()
+}
+
+fn create_framebuffers(
+    render_pass: &Arc<RenderPass>,
+    swapchain_images: &[Arc<Image>],
+) -> Vec<Arc<Framebuffer>> {
+    swapchain_images
+        .iter()
+        .map(|image| {
+            let view = ImageView::new_default(image.clone()).unwrap();
+
+            Framebuffer::new(
+                render_pass.clone(),
+                FramebufferCreateInfo {
+                    attachments: vec![view],
+                    ..Default::default()
+                },
+            )
+            .unwrap()
+        })
+        .collect()
+}
+
+/// What component images hold, the layout of [crate::component::display::color::Pixel] so uploads are plain copies
+pub const PIXEL_FORMAT: Format = Format::R8G8B8A8_SRGB;
+
 pub struct VulkanRendering;
 
 pub struct VulkanComponentInitializationData {
@@ -380,6 +687,12 @@ pub struct VulkanComponentInitializationData {
 impl RenderingBackend for VulkanRendering {
     type ComponentInitializationData = VulkanComponentInitializationData;
     /// This MUST have TRANSFER_SRC set
+    /// Always full color, as machine frames are only blitted to the swapchain. Palette based components resolve their
+    /// [crate::component::display::color::IndexedFramebuffer] while filling their staging buffer until there is a
+    /// post processing pass to look the palette up in
     type ComponentDisplayBuffer = Arc<Image>;
     type RuntimeState = VulkanState;
+
+    // Needs a post processing pass, blits can't do it
+    const COLOR_FILTER: bool = false;
 }
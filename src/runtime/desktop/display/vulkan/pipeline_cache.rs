@@ -0,0 +1,111 @@
+use crate::{atomic_write::write_atomically, env::PIPELINE_CACHE_DIRECTORY};
+use data_encoding::HEXLOWER;
+use std::{fs, io::Write, path::PathBuf, sync::Arc};
+use vulkano::{
+    device::{physical::PhysicalDevice, Device},
+    pipeline::cache::{PipelineCache, PipelineCacheCreateInfo},
+};
+
+/// Size of VkPipelineCacheHeaderVersionOne, which every driver starts its cache data with
+const HEADER_SIZE: usize = 32;
+const HEADER_VERSION_ONE: u32 = 1;
+
+/// Pipelines compiled in earlier runs, so starting up doesn't have to compile them again
+///
+/// Kept per device and driver version, as the data is only good for the exact driver that made it. Written back
+/// once every state sharing it is gone
+pub struct PersistentPipelineCache {
+    cache: Arc<PipelineCache>,
+    path: PathBuf,
+}
+
+impl PersistentPipelineCache {
+    pub fn load(device: &Arc<Device>) -> Self {
+        let physical_device = device.physical_device();
+        let properties = physical_device.properties();
+        let path = PIPELINE_CACHE_DIRECTORY.join(format!(
+            "{}-{:x}",
+            HEXLOWER.encode(
+                &properties
+                    .device_uuid
+                    .unwrap_or(properties.pipeline_cache_uuid)
+            ),
+            properties.driver_version
+        ));
+
+        let initial_data = fs::read(&path)
+            .ok()
+            .filter(|data| {
+                let fits = header_fits(data, physical_device);
+                if !fits {
+                    tracing::warn!("Ignoring the pipeline cache made for another device or driver");
+                }
+
+                fits
+            })
+            .unwrap_or_default();
+
+        tracing::info!("Loaded {} bytes of pipeline cache", initial_data.len());
+
+        // SAFETY: The header was checked to come from this device and driver, which checks the rest itself
+        let cache = unsafe {
+            PipelineCache::new(
+                device.clone(),
+                PipelineCacheCreateInfo {
+                    initial_data,
+                    ..Default::default()
+                },
+            )
+        }
+        .or_else(|error| {
+            tracing::warn!("Could not use the stored pipeline cache: {}", error);
+
+            // SAFETY: There is no initial data to be wrong
+            unsafe { PipelineCache::new(device.clone(), Default::default()) }
+        })
+        .unwrap();
+
+        Self { cache, path }
+    }
+
+    /// For passing to pipeline creation
+    pub fn cache(&self) -> Arc<PipelineCache> {
+        self.cache.clone()
+    }
+}
+
+impl Drop for PersistentPipelineCache {
+    fn drop(&mut self) {
+        let data = match self.cache.get_data() {
+            Ok(data) => data,
+            Err(error) => {
+                tracing::warn!("Could not read back the pipeline cache: {}", error);
+                return;
+            }
+        };
+
+        if let Err(error) = write_atomically(&self.path, |file| file.write_all(&data)) {
+            tracing::warn!(
+                "Could not store the pipeline cache at {}: {}",
+                self.path.display(),
+                error
+            );
+        }
+    }
+}
+
+/// Checks the header drivers put in front of their cache data names this device, so data from a swapped GPU or an
+/// older driver never reaches it
+fn header_fits(data: &[u8], physical_device: &PhysicalDevice) -> bool {
+    let Some(header) = data.get(..HEADER_SIZE) else {
+        return false;
+    };
+    let field = |offset: usize| u32::from_ne_bytes(header[offset..offset + 4].try_into().unwrap());
+    let properties = physical_device.properties();
+
+    field(0) as usize >= HEADER_SIZE
+        && field(4) == HEADER_VERSION_ONE
+        && field(8) == properties.vendor_id
+        && field(12) == properties.device_id
+        && header[16..32] == properties.pipeline_cache_uuid
+}
@@ -0,0 +1,84 @@
+use super::{display_pipeline::DisplayPipeline, PIXEL_FORMAT};
+use crate::config::ScaleFilter;
+use std::time::{Duration, Instant};
+use wgpu::{
+    Color, DeviceDescriptor, Extent3d, Instance, LoadOp, Maintain, Operations, PowerPreference,
+    RenderPassColorAttachment, RenderPassDescriptor, RequestAdapterOptions, StoreOp,
+    TextureDescriptor, TextureDimension, TextureFormat, TextureUsages,
+};
+
+/// Draws a dummy machine display to a offscreen texture, returning the average time per frame or None if no adapter
+/// is usable
+pub fn benchmark(frames: u32) -> Option<Duration> {
+    // No surface so any adapter will do
+    let instance = Instance::new(Default::default());
+    let adapter = pollster::block_on(instance.request_adapter(&RequestAdapterOptions {
+        power_preference: PowerPreference::HighPerformance,
+        ..Default::default()
+    }))?;
+    let (device, queue) =
+        pollster::block_on(adapter.request_device(&DeviceDescriptor::default(), None)).ok()?;
+
+    let texture = |format, size: [u32; 2], usage| {
+        device.create_texture(&TextureDescriptor {
+            label: Some("benchmark"),
+            size: Extent3d {
+                width: size[0],
+                height: size[1],
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: TextureDimension::D2,
+            format,
+            usage,
+            view_formats: &[],
+        })
+    };
+    let source_texture = texture(PIXEL_FORMAT, [64, 32], TextureUsages::TEXTURE_BINDING);
+    let destination_texture = texture(
+        TextureFormat::Bgra8UnormSrgb,
+        [640, 480],
+        TextureUsages::RENDER_ATTACHMENT,
+    );
+    let source_view = source_texture.create_view(&Default::default());
+    let destination_view = destination_texture.create_view(&Default::default());
+
+    let display_pipeline = DisplayPipeline::new(&device, destination_texture.format());
+
+    let start = Instant::now();
+
+    for _ in 0..frames {
+        let mut encoder = device.create_command_encoder(&Default::default());
+        let mut render_pass = encoder.begin_render_pass(&RenderPassDescriptor {
+            label: Some("benchmark"),
+            color_attachments: &[Some(RenderPassColorAttachment {
+                view: &destination_view,
+                resolve_target: None,
+                ops: Operations {
+                    load: LoadOp::Clear(Color::BLACK),
+                    store: StoreOp::Store,
+                },
+            })],
+            ..Default::default()
+        });
+
+        display_pipeline.draw(
+            &device,
+            &mut render_pass,
+            &source_view,
+            None,
+            ScaleFilter::Nearest,
+            None,
+            [640, 480],
+            [0, 0],
+            [640, 480],
+        );
+        drop(render_pass);
+
+        queue.submit([encoder.finish()]);
+        device.poll(Maintain::Wait);
+    }
+
+    Some(start.elapsed() / frames)
+}
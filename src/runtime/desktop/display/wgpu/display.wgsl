@@ -0,0 +1,55 @@
+// Draws a machine display onto part of the window
+
+struct Placement {
+    // Top left then bottom right corner of the display, in clip space
+    corners: vec4<f32>,
+    // The color filter over linear RGB, identity when there is none
+    color_filter: mat3x3<f32>,
+    // How much of the previous frame is mixed in
+    blend: f32,
+    // Set if the target format doesn't encode sRGB on its own
+    encode_srgb: u32,
+}
+
+@group(0) @binding(0) var<uniform> placement: Placement;
+@group(0) @binding(1) var frame: texture_2d<f32>;
+@group(0) @binding(2) var previous_frame: texture_2d<f32>;
+@group(0) @binding(3) var frame_sampler: sampler;
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+}
+
+// A quad as a triangle strip, parts outside the window are clipped away
+@vertex
+fn vertex_main(@builtin(vertex_index) index: u32) -> VertexOutput {
+    let uv = vec2<f32>(f32(index & 1u), f32(index >> 1u));
+
+    var output: VertexOutput;
+    output.position = vec4<f32>(mix(placement.corners.xy, placement.corners.zw, uv), 0.0, 1.0);
+    output.uv = uv;
+    return output;
+}
+
+fn encode_srgb(linear: vec3<f32>) -> vec3<f32> {
+    let low = linear * 12.92;
+    let high = 1.055 * pow(linear, vec3<f32>(1.0 / 2.4)) - 0.055;
+    return select(high, low, linear <= vec3<f32>(0.0031308));
+}
+
+@fragment
+fn fragment_main(input: VertexOutput) -> @location(0) vec4<f32> {
+    // The frames are sRGB textures which are decoded when sampled, so this mixes in linear light
+    let color = mix(
+        textureSample(frame, frame_sampler, input.uv),
+        textureSample(previous_frame, frame_sampler, input.uv),
+        placement.blend
+    );
+    let rgb = clamp(placement.color_filter * color.rgb, vec3<f32>(0.0), vec3<f32>(1.0));
+
+    if placement.encode_srgb != 0u {
+        return vec4<f32>(encode_srgb(rgb), color.a);
+    }
+    return vec4<f32>(rgb, color.a);
+}
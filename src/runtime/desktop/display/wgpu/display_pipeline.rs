@@ -0,0 +1,212 @@
+use crate::{config::ScaleFilter, runtime::color_filter::ColorFilter};
+use bytemuck::{Pod, Zeroable};
+use nalgebra::Matrix3;
+use wgpu::{
+    util::{BufferInitDescriptor, DeviceExt},
+    AddressMode, BindGroupDescriptor, BindGroupEntry, BindGroupLayout, BindGroupLayoutDescriptor,
+    BindGroupLayoutEntry, BindingResource, BindingType, BufferBindingType, BufferUsages,
+    ColorTargetState, ColorWrites, Device, FilterMode, FragmentState, MultisampleState,
+    PipelineLayoutDescriptor, PrimitiveState, PrimitiveTopology, RenderPass, RenderPipeline,
+    RenderPipelineDescriptor, Sampler, SamplerBindingType, SamplerDescriptor,
+    ShaderModuleDescriptor, ShaderSource, ShaderStages, TextureFormat, TextureSampleType,
+    TextureView, TextureViewDimension, VertexState,
+};
+
+/// Where a display goes and how it is mixed, laid out like the uniform of the shader
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Zeroable, Pod)]
+struct Placement {
+    corners: [f32; 4],
+    /// Columns of the matrix, each padded out to 4 like a uniform mat3x3 is
+    color_filter: [[f32; 4]; 3],
+    blend: f32,
+    encode_srgb: u32,
+    padding: [u32; 2],
+}
+
+/// Draws machine displays scaled onto the window, with the previous frame mixed in for frame blending
+pub struct DisplayPipeline {
+    pipeline: RenderPipeline,
+    bind_group_layout: BindGroupLayout,
+    nearest_sampler: Sampler,
+    linear_sampler: Sampler,
+    /// Set for plain unorm targets, sRGB targets encode on their own and float targets are extended linear
+    encode_srgb: bool,
+}
+
+impl DisplayPipeline {
+    pub fn new(device: &Device, format: TextureFormat) -> Self {
+        let shader = device.create_shader_module(ShaderModuleDescriptor {
+            label: Some("display"),
+            source: ShaderSource::Wgsl(include_str!("display.wgsl").into()),
+        });
+
+        let texture_entry = |binding| BindGroupLayoutEntry {
+            binding,
+            visibility: ShaderStages::FRAGMENT,
+            ty: BindingType::Texture {
+                sample_type: TextureSampleType::Float { filterable: true },
+                view_dimension: TextureViewDimension::D2,
+                multisampled: false,
+            },
+            count: None,
+        };
+        let bind_group_layout = device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+            label: Some("display"),
+            entries: &[
+                BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: ShaderStages::VERTEX_FRAGMENT,
+                    ty: BindingType::Buffer {
+                        ty: BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                texture_entry(1),
+                texture_entry(2),
+                BindGroupLayoutEntry {
+                    binding: 3,
+                    visibility: ShaderStages::FRAGMENT,
+                    ty: BindingType::Sampler(SamplerBindingType::Filtering),
+                    count: None,
+                },
+            ],
+        });
+        let layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
+            label: Some("display"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        let pipeline = device.create_render_pipeline(&RenderPipelineDescriptor {
+            label: Some("display"),
+            layout: Some(&layout),
+            vertex: VertexState {
+                module: &shader,
+                entry_point: "vertex_main",
+                compilation_options: Default::default(),
+                buffers: &[],
+            },
+            fragment: Some(FragmentState {
+                module: &shader,
+                entry_point: "fragment_main",
+                compilation_options: Default::default(),
+                targets: &[Some(ColorTargetState {
+                    format,
+                    blend: None,
+                    write_mask: ColorWrites::ALL,
+                })],
+            }),
+            primitive: PrimitiveState {
+                topology: PrimitiveTopology::TriangleStrip,
+                ..Default::default()
+            },
+            depth_stencil: None,
+            multisample: MultisampleState::default(),
+            multiview: None,
+            cache: None,
+        });
+
+        let sampler = |filter| {
+            device.create_sampler(&SamplerDescriptor {
+                label: Some("display"),
+                address_mode_u: AddressMode::ClampToEdge,
+                address_mode_v: AddressMode::ClampToEdge,
+                mag_filter: filter,
+                min_filter: filter,
+                ..Default::default()
+            })
+        };
+
+        Self {
+            pipeline,
+            bind_group_layout,
+            nearest_sampler: sampler(FilterMode::Nearest),
+            linear_sampler: sampler(FilterMode::Linear),
+            encode_srgb: !format.is_srgb() && format != TextureFormat::Rgba16Float,
+        }
+    }
+
+    /// Draws the frame into the area of the window, offset and size are like [crate::config::AspectMode::fit] gives.
+    /// The previous frame is mixed in half and half if blending, otherwise it isn't looked at, and the color filter goes
+    /// over the result
+    #[allow(clippy::too_many_arguments)]
+    pub fn draw(
+        &self,
+        device: &Device,
+        render_pass: &mut RenderPass,
+        frame: &TextureView,
+        previous_frame: Option<&TextureView>,
+        scale_filter: ScaleFilter,
+        color_filter: Option<ColorFilter>,
+        window: [u32; 2],
+        offset: [u32; 2],
+        size: [u32; 2],
+    ) {
+        let clip_space = |axis: usize, position: u32| {
+            let position = position as f32 / window[axis] as f32 * 2.0 - 1.0;
+
+            // Clip space points up
+            if axis == 1 {
+                -position
+            } else {
+                position
+            }
+        };
+
+        let color_filter = color_filter.map_or_else(Matrix3::identity, |filter| filter.matrix());
+
+        let placement = Placement {
+            corners: [
+                clip_space(0, offset[0]),
+                clip_space(1, offset[1]),
+                clip_space(0, offset[0] + size[0]),
+                clip_space(1, offset[1] + size[1]),
+            ],
+            color_filter: std::array::from_fn(|column| {
+                let column = color_filter.column(column);
+                [column.x, column.y, column.z, 0.0]
+            }),
+            blend: if previous_frame.is_some() { 0.5 } else { 0.0 },
+            encode_srgb: self.encode_srgb as u32,
+            padding: [0; 2],
+        };
+        let placement = device.create_buffer_init(&BufferInitDescriptor {
+            label: Some("display placement"),
+            contents: bytemuck::bytes_of(&placement),
+            usage: BufferUsages::UNIFORM,
+        });
+
+        let bind_group = device.create_bind_group(&BindGroupDescriptor {
+            label: Some("display"),
+            layout: &self.bind_group_layout,
+            entries: &[
+                BindGroupEntry {
+                    binding: 0,
+                    resource: placement.as_entire_binding(),
+                },
+                BindGroupEntry {
+                    binding: 1,
+                    resource: BindingResource::TextureView(frame),
+                },
+                BindGroupEntry {
+                    binding: 2,
+                    resource: BindingResource::TextureView(previous_frame.unwrap_or(frame)),
+                },
+                BindGroupEntry {
+                    binding: 3,
+                    resource: BindingResource::Sampler(match scale_filter {
+                        ScaleFilter::Nearest => &self.nearest_sampler,
+                        ScaleFilter::Linear => &self.linear_sampler,
+                    }),
+                },
+            ],
+        });
+
+        render_pass.set_pipeline(&self.pipeline);
+        render_pass.set_bind_group(0, &bind_group, &[]);
+        render_pass.draw(0..4, 0..1);
+    }
+}
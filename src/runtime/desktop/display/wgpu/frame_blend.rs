@@ -0,0 +1,60 @@
+use wgpu::{
+    CommandEncoder, Device, Extent3d, Texture, TextureDescriptor, TextureDimension, TextureUsages,
+    TextureView,
+};
+
+/// The last machine frame, which the display pipeline mixes with the current one
+pub struct FrameBlendHistory {
+    previous: Texture,
+    view: TextureView,
+    /// It starts out as the first frame
+    filled: bool,
+}
+
+impl FrameBlendHistory {
+    pub fn new(device: &Device, frame: &Texture) -> Self {
+        let previous = device.create_texture(&TextureDescriptor {
+            label: Some("frame blend history"),
+            size: frame.size(),
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: TextureDimension::D2,
+            format: frame.format(),
+            usage: TextureUsages::TEXTURE_BINDING | TextureUsages::COPY_DST,
+            view_formats: &[],
+        });
+
+        Self {
+            view: previous.create_view(&Default::default()),
+            previous,
+            filled: false,
+        }
+    }
+
+    /// If this was made for frames like this one
+    pub fn fits(&self, frame: &Texture) -> bool {
+        frame.size() == self.previous.size() && frame.format() == self.previous.format()
+    }
+
+    /// The frame before this one, to be drawn before [FrameBlendHistory::record]
+    pub fn previous(&mut self, encoder: &mut CommandEncoder, frame: &Texture) -> &TextureView {
+        if !self.filled {
+            self.record(encoder, frame);
+            self.filled = true;
+        }
+
+        &self.view
+    }
+
+    /// Keeps the frame for blending with the next one, once it was drawn
+    pub fn record(&self, encoder: &mut CommandEncoder, frame: &Texture) {
+        encoder.copy_texture_to_texture(
+            frame.as_image_copy(),
+            self.previous.as_image_copy(),
+            Extent3d {
+                depth_or_array_layers: 1,
+                ..frame.size()
+            },
+        );
+    }
+}
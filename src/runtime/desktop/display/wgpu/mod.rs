@@ -0,0 +1,432 @@
+use super::WinitRenderBackendState;
+use crate::{
+    component::display::DisplayComponent,
+    config::{GlobalConfig, SurfaceFormatPreference},
+    runtime::{RedrawKind, RenderingBackend, RenderingBackendState},
+};
+use display_pipeline::DisplayPipeline;
+use egui_wgpu::{Renderer as EguiRenderer, ScreenDescriptor};
+use frame_blend::FrameBlendHistory;
+use std::{
+    error::Error,
+    sync::{Arc, Mutex, RwLock},
+};
+use wgpu::{
+    Adapter, Color, Device, DeviceDescriptor, Instance, InstanceDescriptor, Limits, LoadOp,
+    Operations, PowerPreference, PresentMode, Queue, RenderPassColorAttachment,
+    RenderPassDescriptor, RequestAdapterOptions, StoreOp, Surface, SurfaceConfiguration,
+    SurfaceError, Texture, TextureFormat, TextureUsages,
+};
+use winit::window::Window;
+
+pub mod benchmark;
+mod display_pipeline;
+mod frame_blend;
+
+/// What every window of the runtime draws with, so display components can be shown in any of them
+struct WgpuDevice {
+    instance: Instance,
+    adapter: Adapter,
+    device: Arc<Device>,
+    queue: Arc<Queue>,
+}
+
+pub struct WgpuState {
+    shared: Arc<WgpuDevice>,
+    surface: Surface<'static>,
+    surface_config: SurfaceConfiguration,
+    reconfigure_surface: bool,
+    display_pipeline: DisplayPipeline,
+    egui_renderer: EguiRenderer,
+    /// One for each display, only kept while frame blending is on
+    frame_blend_histories: Vec<Option<FrameBlendHistory>>,
+    window: Arc<Window>,
+    global_config: Arc<RwLock<GlobalConfig>>,
+}
+
+impl RenderingBackendState for WgpuState {
+    type RenderingBackend = WgpuRendering;
+
+    fn surface_resized(&mut self) {
+        self.reconfigure_surface = true;
+    }
+
+    fn surface_config_changed(&mut self) {
+        self.reconfigure_surface = true;
+    }
+
+    fn redraw(&mut self, kind: RedrawKind<WgpuRendering>) {
+        let window_size: [u32; 2] = self.window.inner_size().into();
+
+        // Skip rendering if impossible window size
+        if window_size.contains(&0) {
+            return;
+        }
+
+        if self.reconfigure_surface {
+            self.configure_surface(window_size);
+        }
+
+        let surface_texture = match self.surface.get_current_texture() {
+            Ok(surface_texture) => surface_texture,
+            Err(SurfaceError::Outdated | SurfaceError::Lost) => {
+                self.reconfigure_surface = true;
+                return;
+            }
+            Err(SurfaceError::Timeout) => {
+                tracing::warn!("Timed out waiting for the next surface image");
+                return;
+            }
+            Err(SurfaceError::OutOfMemory) => {
+                panic!("Out of memory acquiring the next surface image")
+            }
+        };
+        self.reconfigure_surface |= surface_texture.suboptimal;
+
+        let shared = self.shared.clone();
+        let target = surface_texture.texture.create_view(&Default::default());
+        let mut encoder = shared.device.create_command_encoder(&Default::default());
+        let mut command_buffers = Vec::new();
+        let mut textures_to_free = Vec::new();
+        let color_attachments = [Some(RenderPassColorAttachment {
+            view: &target,
+            resolve_target: None,
+            ops: Operations {
+                // Bars around the displays when their aspect ratio is kept
+                load: LoadOp::Clear(Color::BLACK),
+                store: StoreOp::Store,
+            },
+        })];
+
+        match kind {
+            RedrawKind::Machine(display_components) => {
+                let global_config = self.global_config.read().unwrap();
+                // Post processing is skipped to save power
+                let color_filter = global_config
+                    .effective_color_filter()
+                    .filter(|_| !global_config.battery_saver);
+                let aspect_mode = global_config.effective_aspect_mode();
+                let scale_filter = global_config.effective_scale_filter();
+                let frame_blend = global_config.effective_frame_blend();
+                let display_layout = global_config.display_layout;
+                drop(global_config);
+                let frames: Vec<_> = display_components
+                    .iter()
+                    .map(|display_component| {
+                        display_component.lock().unwrap().display_data().clone()
+                    })
+                    .collect();
+                let views: Vec<_> = frames
+                    .iter()
+                    .map(|frame| frame.create_view(&Default::default()))
+                    .collect();
+
+                if frame_blend {
+                    self.frame_blend_histories
+                        .resize_with(frames.len(), || None);
+
+                    for (frame, history) in frames.iter().zip(&mut self.frame_blend_histories) {
+                        if !history.as_ref().is_some_and(|history| history.fits(frame)) {
+                            *history = Some(FrameBlendHistory::new(&shared.device, frame));
+                        }
+                    }
+                } else {
+                    self.frame_blend_histories.clear();
+                }
+
+                let areas = display_layout.arrange(
+                    aspect_mode,
+                    window_size,
+                    &frames
+                        .iter()
+                        .map(|frame| [frame.width(), frame.height()])
+                        .collect::<Vec<_>>(),
+                );
+                let previous_frames: Vec<_> = if frame_blend {
+                    self.frame_blend_histories
+                        .iter_mut()
+                        .zip(&frames)
+                        .map(|(history, frame)| {
+                            Some(history.as_mut().unwrap().previous(&mut encoder, frame))
+                        })
+                        .collect()
+                } else {
+                    vec![None; frames.len()]
+                };
+
+                let mut render_pass = encoder.begin_render_pass(&RenderPassDescriptor {
+                    label: Some("machine"),
+                    color_attachments: &color_attachments,
+                    ..Default::default()
+                });
+
+                for ((view, previous_frame), (offset, size)) in
+                    views.iter().zip(previous_frames).zip(areas)
+                {
+                    // Too small a window to show anything in
+                    if size.contains(&0) {
+                        continue;
+                    }
+
+                    self.display_pipeline.draw(
+                        &shared.device,
+                        &mut render_pass,
+                        view,
+                        previous_frame,
+                        scale_filter,
+                        color_filter,
+                        window_size,
+                        offset,
+                        size,
+                    );
+                }
+                drop(render_pass);
+
+                for (history, frame) in self.frame_blend_histories.iter().zip(&frames) {
+                    history.as_ref().unwrap().record(&mut encoder, frame);
+                }
+            }
+            RedrawKind::Egui {
+                context,
+                full_output,
+            } => {
+                let screen_descriptor = ScreenDescriptor {
+                    size_in_pixels: window_size,
+                    pixels_per_point: full_output.pixels_per_point,
+                };
+
+                for (id, image_delta) in &full_output.textures_delta.set {
+                    self.egui_renderer.update_texture(
+                        &shared.device,
+                        &shared.queue,
+                        *id,
+                        image_delta,
+                    );
+                }
+                let paint_jobs =
+                    context.tessellate(full_output.shapes, full_output.pixels_per_point);
+                command_buffers = self.egui_renderer.update_buffers(
+                    &shared.device,
+                    &shared.queue,
+                    &mut encoder,
+                    &paint_jobs,
+                    &screen_descriptor,
+                );
+
+                let mut render_pass = encoder
+                    .begin_render_pass(&RenderPassDescriptor {
+                        label: Some("egui"),
+                        color_attachments: &color_attachments,
+                        ..Default::default()
+                    })
+                    .forget_lifetime();
+                self.egui_renderer
+                    .render(&mut render_pass, &paint_jobs, &screen_descriptor);
+                drop(render_pass);
+
+                // They may still be drawn with in this frame
+                textures_to_free = full_output.textures_delta.free;
+            }
+        }
+
+        command_buffers.push(encoder.finish());
+        shared.queue.submit(command_buffers);
+        surface_texture.present();
+
+        for id in &textures_to_free {
+            self.egui_renderer.free_texture(id);
+        }
+    }
+
+    fn initialize_components(
+        &mut self,
+        components: &[Arc<Mutex<dyn DisplayComponent<WgpuRendering>>>],
+    ) {
+        for component in components {
+            component
+                .lock()
+                .unwrap()
+                .initialize_display(WgpuComponentInitializationData {
+                    device: self.shared.device.clone(),
+                    queue: self.shared.queue.clone(),
+                });
+        }
+    }
+}
+
+impl WinitRenderBackendState for WgpuState {
+    fn new(
+        window: Arc<Window>,
+        global_config: Arc<RwLock<GlobalConfig>>,
+    ) -> Result<Self, Box<dyn Error>> {
+        let instance = Instance::new(InstanceDescriptor::default());
+        let surface = instance.create_surface(window.clone())?;
+
+        let adapter = pollster::block_on(instance.request_adapter(&RequestAdapterOptions {
+            power_preference: PowerPreference::HighPerformance,
+            force_fallback_adapter: false,
+            compatible_surface: Some(&surface),
+        }))
+        .ok_or("No graphics adapter can present to the window")?;
+        let adapter_info = adapter.get_info();
+
+        tracing::info!(
+            "Using adapter: {} (type: {:?}, backend: {:?})",
+            adapter_info.name,
+            adapter_info.device_type,
+            adapter_info.backend
+        );
+
+        let (device, queue) = pollster::block_on(adapter.request_device(
+            &DeviceDescriptor {
+                label: Some("multiemu"),
+                // Nothing here needs more than OpenGL ES 3 can do, so old hardware works too
+                required_limits:
+                    Limits::downlevel_webgl2_defaults().using_resolution(adapter.limits()),
+                ..Default::default()
+            },
+            None,
+        ))?;
+
+        let shared = Arc::new(WgpuDevice {
+            instance,
+            adapter,
+            device: Arc::new(device),
+            queue: Arc::new(queue),
+        });
+
+        Ok(Self::with_surface(shared, surface, window, global_config))
+    }
+
+    fn new_secondary(&self, window: Arc<Window>) -> Self {
+        // Reuse the device so the display components textures are usable here
+        let surface = self.shared.instance.create_surface(window.clone()).unwrap();
+
+        if !self.shared.adapter.is_surface_supported(&surface) {
+            tracing::warn!("Adapter may not be able to present to the new window");
+        }
+
+        Self::with_surface(
+            self.shared.clone(),
+            surface,
+            window,
+            self.global_config.clone(),
+        )
+    }
+}
+
+impl WgpuState {
+    fn with_surface(
+        shared: Arc<WgpuDevice>,
+        surface: Surface<'static>,
+        window: Arc<Window>,
+        global_config: Arc<RwLock<GlobalConfig>>,
+    ) -> Self {
+        let [width, height]: [u32; 2] = window.inner_size().into();
+        let capabilities = surface.get_capabilities(&shared.adapter);
+        // Chosen once, as egui only uploads its textures once and a renderer for another format would be missing them
+        let format = select_surface_format(
+            &capabilities.formats,
+            global_config.read().unwrap().surface_format,
+        );
+
+        Self {
+            display_pipeline: DisplayPipeline::new(&shared.device, format),
+            egui_renderer: EguiRenderer::new(&shared.device, format, None, 1, false),
+            surface_config: SurfaceConfiguration {
+                usage: TextureUsages::RENDER_ATTACHMENT,
+                format,
+                width: width.max(1),
+                height: height.max(1),
+                // Filled in from the config when configuring
+                present_mode: PresentMode::Fifo,
+                desired_maximum_frame_latency: 2,
+                alpha_mode: capabilities.alpha_modes[0],
+                view_formats: Vec::new(),
+            },
+            reconfigure_surface: true,
+            frame_blend_histories: Vec::new(),
+            shared,
+            surface,
+            window,
+            global_config,
+        }
+    }
+
+    fn configure_surface(&mut self, window_size: [u32; 2]) {
+        tracing::trace!("Configuring surface");
+
+        let global_config = self.global_config.read().unwrap();
+        [self.surface_config.width, self.surface_config.height] = window_size;
+        self.surface_config.present_mode = if global_config.vsync {
+            PresentMode::AutoVsync
+        } else {
+            PresentMode::AutoNoVsync
+        };
+        // The frame being drawn counts as one of the buffers
+        self.surface_config.desired_maximum_frame_latency =
+            global_config.buffering.frame_count() - 1;
+        drop(global_config);
+
+        self.surface
+            .configure(&self.shared.device, &self.surface_config);
+        self.reconfigure_surface = false;
+    }
+}
+
+/// Candidate surface formats, in order of preference
+const SRGB_SURFACE_FORMATS: &[TextureFormat] =
+    &[TextureFormat::Bgra8UnormSrgb, TextureFormat::Rgba8UnormSrgb];
+const TEN_BIT_SURFACE_FORMATS: &[TextureFormat] = &[TextureFormat::Rgb10a2Unorm];
+const HDR_SURFACE_FORMATS: &[TextureFormat] = &[TextureFormat::Rgba16Float];
+
+fn select_surface_format(
+    supported_formats: &[TextureFormat],
+    preference: SurfaceFormatPreference,
+) -> TextureFormat {
+    let candidates: &[&[TextureFormat]] = match preference {
+        SurfaceFormatPreference::Srgb => &[SRGB_SURFACE_FORMATS],
+        SurfaceFormatPreference::TenBit => &[TEN_BIT_SURFACE_FORMATS, SRGB_SURFACE_FORMATS],
+        SurfaceFormatPreference::Hdr => &[
+            HDR_SURFACE_FORMATS,
+            TEN_BIT_SURFACE_FORMATS,
+            SRGB_SURFACE_FORMATS,
+        ],
+    };
+
+    let selected = candidates
+        .iter()
+        .flat_map(|candidates| candidates.iter())
+        .find(|candidate| supported_formats.contains(candidate))
+        .copied()
+        .unwrap_or_else(|| {
+            tracing::warn!(
+                "Surface supports none of the preferred formats, colors will probably be wrong"
+            );
+
+            supported_formats[0]
+        });
+
+    tracing::info!("Using surface format {:?}", selected);
+
+    selected
+}
+
+/// What component textures hold, the layout of [crate::component::display::color::Pixel] so uploads are plain copies
+pub const PIXEL_FORMAT: TextureFormat = TextureFormat::Rgba8UnormSrgb;
+
+pub struct WgpuRendering;
+
+pub struct WgpuComponentInitializationData {
+    pub device: Arc<Device>,
+    pub queue: Arc<Queue>,
+}
+
+impl RenderingBackend for WgpuRendering {
+    type ComponentInitializationData = WgpuComponentInitializationData;
+    /// This MUST have TEXTURE_BINDING and COPY_SRC set and be in [PIXEL_FORMAT]
+    type ComponentDisplayBuffer = Arc<Texture>;
+    type RuntimeState = WgpuState;
+
+    const COLOR_FILTER: bool = true;
+}
@@ -1,6 +1,6 @@
 use crate::{
     config::GlobalConfig,
-    input::{gamepad::GamepadInput, EmulatedGamepad, Input, InputState},
+    input::{gamepad::GamepadInput, pointer::PointerInput, EmulatedGamepad, Input, InputState},
     rom::GameSystem,
 };
 use arrayvec::ArrayVec;
@@ -8,46 +8,122 @@ use gilrs::{Axis, Button, EventType, Gilrs};
 use std::{
     cmp::Ordering,
     sync::{Arc, RwLock},
+    time::{Duration, Instant},
 };
 
+pub enum GamepadHotplugEvent {
+    Connected { name: String },
+    Disconnected { name: String },
+}
+
 pub struct GilrsGamepadManager {
     context: Gilrs,
+    /// Emulated gamepads of the running machine
     gamepads: Vec<Arc<EmulatedGamepad>>,
-    system: GameSystem,
+    system: Option<GameSystem>,
     global_config: Arc<RwLock<GlobalConfig>>,
+    /// When any input last came in, for idle detection
+    last_input: Instant,
 }
 
 impl GilrsGamepadManager {
-    pub fn new(
-        gamepads: Vec<Arc<EmulatedGamepad>>,
-        system: GameSystem,
-        global_config: Arc<RwLock<GlobalConfig>>,
-    ) -> Self {
+    pub fn new(global_config: Arc<RwLock<GlobalConfig>>) -> Self {
+        let context = match Gilrs::new() {
+            Ok(context) => context,
+            // Still usable, it just won't ever see a gamepad
+            Err(gilrs::Error::NotImplemented(context)) => {
+                tracing::warn!("Gamepads are not supported on this platform");
+                context
+            }
+            Err(error) => panic!("Failed to initialize gamepad support: {}", error),
+        };
+
         Self {
-            context: Gilrs::new().unwrap(),
-            gamepads,
-            system,
+            context,
+            gamepads: Vec::new(),
+            system: None,
             global_config,
+            last_input: Instant::now(),
         }
     }
 
-    pub fn insert_input(&mut self, input: Input, input_state: InputState) {
-        if let Some(translated_input) = self
-            .global_config
-            .read()
+    /// Routes inputs to the gamepads of a newly started machine
+    pub fn attach_machine(&mut self, gamepads: Vec<Arc<EmulatedGamepad>>, system: GameSystem) {
+        self.global_config
+            .write()
             .unwrap()
-            .controller_configs
-            .get(&self.system)
-            .and_then(|config| config.get(&input))
-            .copied()
-        {
-            self.gamepads[0].set_input_state(translated_input, input_state);
+            .ensure_controller_config(system);
+
+        self.gamepads = gamepads;
+        self.system = Some(system);
+    }
+
+    /// Inputs go nowhere until the next machine is attached
+    pub fn detach_machine(&mut self) {
+        self.gamepads.clear();
+        self.system = None;
+    }
+
+    pub fn insert_input(&mut self, input: Input, input_state: InputState) {
+        self.last_input = Instant::now();
+
+        let Some(system) = self.system else {
+            return;
+        };
+
+        let global_config = self.global_config.read().unwrap();
+        let Some(players) = global_config.effective_controller_config(system) else {
+            return;
+        };
+
+        // Players can share a input, like one key starting the game for everyone
+        for (gamepad, mapping) in self.gamepads.iter().zip(players) {
+            if let Some(translated_input) = mapping.get(&input) {
+                gamepad.set_input_state(*translated_input, input_state);
+            }
+        }
+    }
+
+    /// Relative motion of the captured pointer, the machine takes it whenever it next polls
+    pub fn insert_pointer_motion(&mut self, delta: (f64, f64)) {
+        self.last_input = Instant::now();
+
+        let Some(system) = self.system else {
+            return;
+        };
+
+        let global_config = self.global_config.read().unwrap();
+        let Some(players) = global_config.effective_controller_config(system) else {
+            return;
+        };
+
+        for (gamepad, mapping) in self.gamepads.iter().zip(players) {
+            for (axis, amount) in [
+                (PointerInput::XAxis, delta.0),
+                (PointerInput::YAxis, delta.1),
+            ] {
+                if let Some(translated_input) = mapping.get(&Input::Pointer(axis)) {
+                    gamepad.add_motion(*translated_input, amount as f32);
+                }
+            }
         }
     }
 
-    pub fn refresh_gamepad_inputs(&mut self) {
+    pub fn refresh_gamepad_inputs(&mut self) -> Vec<GamepadHotplugEvent> {
+        let mut hotplug_events = Vec::new();
+
         while let Some(event) = self.context.next_event() {
             match event.event {
+                EventType::Connected => {
+                    hotplug_events.push(GamepadHotplugEvent::Connected {
+                        name: self.context.gamepad(event.id).name().to_string(),
+                    });
+                }
+                EventType::Disconnected => {
+                    hotplug_events.push(GamepadHotplugEvent::Disconnected {
+                        name: self.context.gamepad(event.id).name().to_string(),
+                    });
+                }
                 EventType::AxisChanged(axis, value, _) => {
                     for (axis, value) in gilrs_axis_translator(axis, value) {
                         self.insert_input(axis, value);
@@ -61,6 +137,32 @@ impl GilrsGamepadManager {
                 _ => {}
             }
         }
+
+        hotplug_events
+    }
+
+    /// Mappings changed under us, so whatever is held may not be held anymore
+    pub fn release_all_inputs(&self) {
+        for gamepad in &self.gamepads {
+            gamepad.release_all();
+        }
+    }
+
+    /// How long it has been since the user last touched anything
+    pub fn idle_time(&self) -> Duration {
+        self.last_input.elapsed()
+    }
+
+    /// Counts as input, for activity that never reaches the gamepads
+    pub fn reset_idle_time(&mut self) {
+        self.last_input = Instant::now();
+    }
+
+    pub fn connected_gamepads(&self) -> Vec<String> {
+        self.context
+            .gamepads()
+            .map(|(_, gamepad)| gamepad.name().to_string())
+            .collect()
     }
 }
 
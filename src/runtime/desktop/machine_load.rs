@@ -0,0 +1,85 @@
+use crate::{
+    component::{definitions::chip8::display::Chip8Display, display::DisplayComponent},
+    machine::{
+        definitions::{construct_machine, UnsupportedSystem},
+        Machine,
+    },
+    rom::{GameSystem, RomId, RomManager},
+    runtime::RenderingBackend,
+};
+use std::{
+    any::Any,
+    sync::{
+        mpsc::{channel, Receiver, TryRecvError},
+        Arc,
+    },
+    thread::JoinHandle,
+};
+
+#[allow(clippy::large_enum_variant)]
+pub enum LoadProgress<R: RenderingBackend> {
+    Running,
+    /// Built but with the displays not yet initialized, that has to happen on the thread owning the rendering backend
+    Finished(Machine<R>),
+    /// The system can't be built or building panicked, with the message if it had one
+    Failed(String),
+}
+
+/// Builds a machine on a thread of its own, so reading the ROMs doesn't freeze the window
+pub struct MachineLoad<R: RenderingBackend> {
+    result: Receiver<Result<Machine<R>, UnsupportedSystem>>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl<R: RenderingBackend> MachineLoad<R>
+where
+    Chip8Display: DisplayComponent<R>,
+{
+    pub fn start(
+        game_system: GameSystem,
+        rom_manager: Arc<RomManager>,
+        user_specified_roms: Vec<RomId>,
+    ) -> Self {
+        let (result_sender, result) = channel();
+
+        let thread = std::thread::Builder::new()
+            .name("machine load".to_string())
+            .spawn(move || {
+                let machine = construct_machine::<R>(game_system, rom_manager, user_specified_roms);
+                let _ = result_sender.send(machine);
+            })
+            .unwrap();
+
+        Self {
+            result,
+            thread: Some(thread),
+        }
+    }
+}
+
+impl<R: RenderingBackend> MachineLoad<R> {
+    pub fn poll(&mut self) -> LoadProgress<R> {
+        match self.result.try_recv() {
+            Ok(Ok(machine)) => LoadProgress::Finished(machine),
+            Ok(Err(error)) => LoadProgress::Failed(error.to_string()),
+            Err(TryRecvError::Empty) => LoadProgress::Running,
+            Err(TryRecvError::Disconnected) => {
+                let message = self
+                    .thread
+                    .take()
+                    .and_then(|thread| thread.join().err())
+                    .map_or_else(|| "Unknown error".to_string(), panic_message);
+
+                LoadProgress::Failed(message)
+            }
+        }
+    }
+}
+
+fn panic_message(payload: Box<dyn Any + Send>) -> String {
+    payload
+        .downcast_ref::<&str>()
+        .map(|message| message.to_string())
+        .or_else(|| payload.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "Unknown error".to_string())
+}
@@ -2,40 +2,97 @@ use super::{
     timing::FramerateTracker, InitialGuiState, RedrawKind, RenderingBackend, RenderingBackendState,
 };
 use crate::{
+    cheats::{read_patches, Cheat, CheatDatabase},
     component::{definitions::chip8::display::Chip8Display, display::DisplayComponent},
-    config::GlobalConfig,
-    gui::{GuiRuntime, UiOutput},
-    input::InputState,
+    config::{ConfigApplyScope, GlobalConfig, RenderingBackendKind},
+    debugger::gdb::{GdbRequest, GdbServer, GdbStop},
+    env::{AUDIO_CAPTURE_DIRECTORY, CHEAT_DATABASE_PATH, FRAMEBUFFER_DUMP_DIRECTORY, SESSION_PATH},
+    gui::{
+        hex_viewer::{hex_viewer_page, HexViewerState},
+        labels::{labels_page, LabelEditorState},
+        GuiRuntime, MenuMachineContext, UiOutput,
+    },
+    input::{Hotkey, Input, InputState},
     machine::{
-        definitions::construct_machine,
         executor::{single::SingleThreadedExecutor, Executor},
+        watchdog::Watchdog,
+        Machine,
+    },
+    rewind::RewindBuffer,
+    rom::{sidecar::identify_external_rom, GameSystem, RomId, RomManager},
+    runtime::{
+        autosave::Autosaver,
+        framebuffer_dump::{load_framebuffer, save_framebuffer},
+        screenshot::capture_screenshots,
     },
-    rom::{GameSystem, RomId, RomManager},
+    scripting::{engine::ScriptHost, ScriptRequest},
+    snapshot::SnapshotManager,
+    symbols::SymbolTable,
+    task::processor::ProcessorStop,
 };
+use audio::CpalContext;
+use background_import::{BackgroundImport, ImportProgress};
 use display::WinitRenderBackendState;
-use egui::ViewportId;
+use egui::{CentralPanel, CollapsingHeader, ScrollArea, TopBottomPanel, ViewportId};
 use egui_winit::EventResponse;
-use gamepad::GilrsGamepadManager;
-use std::sync::{Arc, Mutex, RwLock};
+use gamepad::{GamepadHotplugEvent, GilrsGamepadManager};
+use machine_load::{LoadProgress, MachineLoad};
+use num::{rational::Ratio, ToPrimitive};
+use serde::{Deserialize, Serialize};
+use session::{Session, SessionGame, WindowGeometry};
+use std::{
+    collections::HashMap,
+    error::Error,
+    fs::create_dir_all,
+    ops::Deref,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex, RwLock},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
 use winit::{
     application::ApplicationHandler,
     dpi::PhysicalSize,
-    event::{ElementState, WindowEvent},
-    event_loop::{ActiveEventLoop, EventLoop},
+    event::{DeviceEvent, DeviceId, ElementState, WindowEvent},
+    event_loop::{ActiveEventLoop, ControlFlow, EventLoop},
     keyboard::PhysicalKey,
-    window::{Window, WindowId},
+    platform::run_on_demand::EventLoopExtRunOnDemand,
+    window::{CursorGrabMode, Fullscreen, Window, WindowId},
 };
 
 pub mod audio;
+mod background_import;
 pub mod display;
 pub mod gamepad;
+mod machine_load;
+mod notification;
+mod session;
+
+/// How often the menu is redrawn in battery saver mode if nothing happens
+const BATTERY_SAVER_GUI_REPAINT_INTERVAL: Duration = Duration::from_millis(100);
+/// Slot the snapshot hotkeys use
+const QUICK_SNAPSHOT_SLOT: u8 = 0;
+/// Slot saved to when pausing for being idle, past the ones the menu offers so it never overwrites the user's
+const IDLE_SNAPSHOT_SLOT: u8 = 10;
+/// Slot the running game is saved to on shutdown, to be resumed from on the next launch
+const SESSION_SNAPSHOT_SLOT: u8 = 11;
 
 /// Tracks if we are running or should be running a game
+#[allow(clippy::large_enum_variant)]
 enum MachineContextState<E: Executor, R: RenderingBackend> {
     /// Machine is waiting for graphics context to be ready
     Pending {
         user_specified_roms: Vec<RomId>,
         forced_system: Option<GameSystem>,
+        audio_capture: Option<PathBuf>,
+    },
+    /// Machine is being built on another thread
+    Loading {
+        load: MachineLoad<R>,
+        game: RomId,
+        system: GameSystem,
+        audio_capture: Option<PathBuf>,
+        /// For the loading screen
+        started: Instant,
     },
     /// Machine is currently running
     Running {
@@ -52,13 +109,126 @@ struct WindowingContext<R: RenderingBackend> {
     egui_winit_context: egui_winit::State,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+enum AuxiliaryWindowKind {
+    /// Shows every page the machine provided at once
+    Debugger,
+    Display {
+        index: usize,
+    },
+}
+
+/// What the user asked for from the toolbar of the debugger
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum DebuggerAction {
+    TogglePause,
+    StepBack,
+    Step,
+}
+
+/// A window waiting to be opened, with where it was and what it showed if it is coming back from the last session
+struct PendingAuxiliaryWindow {
+    kind: AuxiliaryWindowKind,
+    geometry: Option<WindowGeometry>,
+    egui_memory: Option<egui::Memory>,
+}
+
+impl From<AuxiliaryWindowKind> for PendingAuxiliaryWindow {
+    fn from(kind: AuxiliaryWindowKind) -> Self {
+        Self {
+            kind,
+            geometry: None,
+            egui_memory: None,
+        }
+    }
+}
+
+/// A window other than the main one, sharing the running machine
+struct AuxiliaryWindowContext<R: RenderingBackend> {
+    kind: AuxiliaryWindowKind,
+    windowing_context: WindowingContext<R>,
+    /// Separate from the main one so the menu and this don't fight over input
+    egui_context: egui::Context,
+}
+
 /// Stuff needed for a running emulation
 struct MachineContext<E: Executor, R: RenderingBackend> {
     executor: E,
-    /// Intermediate buffer components render to
-    display_components: Vec<Arc<Mutex<dyn DisplayComponent<R>>>>,
-    /// gamepad translation table
-    gamepad_manager: GilrsGamepadManager,
+    /// First of the ROMs the user picked, the per game settings are stored under it
+    game: RomId,
+    system: GameSystem,
+    /// Kept so rewinding can be toggled while running
+    rewind_buffer: Option<Arc<Mutex<RewindBuffer>>>,
+    /// Everything of the machine but the tasks, which the executor owns
+    machine: Machine<R>,
+    /// Built from the labels of the game
+    symbol_table: SymbolTable,
+    /// Audio output, if the host has any
+    audio_context: Option<CpalContext>,
+    snapshot_manager: SnapshotManager,
+    /// If this machine is synchronized with remote players
+    netplay: bool,
+    /// None if disabled in the config
+    watchdog: Option<Watchdog>,
+    /// Tick rate of [Machine::frame_task], which the machine may change while running
+    frame_rate: Option<Ratio<u32>>,
+    /// Only there if a port was given for it
+    gdb_server: Option<GdbServer>,
+    /// Enabled for the game in the config
+    scripts: ScriptHost,
+}
+
+impl<E: Executor, R: RenderingBackend> MachineContext<E, R> {
+    /// Stops everything watching or listening to the machine, then shuts the machine itself down. The rendering
+    /// backend has to still be around
+    fn shutdown(self) {
+        let MachineContext {
+            executor,
+            machine,
+            audio_context,
+            watchdog,
+            gdb_server,
+            ..
+        } = self;
+
+        // Or it would report the executor going away as a stall
+        drop(watchdog);
+        drop(gdb_server);
+
+        // Make sure any in progress capture gets a valid header
+        if let Some(mut audio_context) = audio_context {
+            audio_context.terminate_stream();
+            let _ = audio_context.stop_capture();
+        }
+
+        machine.shutdown(executor);
+    }
+
+    /// Catches up with tick rates the machine changed, like a display switching video modes
+    fn follow_tick_rate_changes(&mut self) {
+        let changes = self.executor.take_tick_rate_changes();
+        if changes.is_empty() {
+            return;
+        }
+
+        for (name, tick_rate) in changes {
+            if self.machine.frame_task == Some(name) {
+                self.frame_rate = Some(tick_rate);
+            }
+        }
+
+        // Audio components tend to produce their samples per tick, so their rate moves along
+        if let Some(audio_context) = &self.audio_context {
+            audio_context.update_sample_rates(&self.machine.audio_components);
+        }
+    }
+
+    /// How long a frame of the machine is, or of the host if the machine has no display
+    fn frame_time(&self, host_frame_time: Duration) -> Duration {
+        self.frame_rate.map_or(host_frame_time, |frame_rate| {
+            Duration::from_secs_f64(frame_rate.recip().to_f64().unwrap())
+        })
+    }
 }
 
 pub struct DesktopRuntime<E: Executor, R: RenderingBackend> {
@@ -76,18 +246,84 @@ pub struct DesktopRuntime<E: Executor, R: RenderingBackend> {
     rom_manager: Arc<RomManager>,
     /// The global config
     global_config: Arc<RwLock<GlobalConfig>>,
+    /// What the runtime was last set up for, to find out what changed
+    applied_config: GlobalConfig,
+    /// gamepad translation table, kept around without a machine for hotplug notifications
+    gamepad_manager: GilrsGamepadManager,
+    /// Emulation is paused because the window lost focus
+    focus_paused: bool,
+    /// Decides if finished jobs are told about on screen or through the desktop
+    window_focused: bool,
+    /// Emulation is paused because nothing was pressed for a while
+    idle_paused: bool,
+    /// Emulation is paused from the debugger, only ever advancing by the instructions the user steps through
+    debugger_paused: bool,
+    /// The cursor is hidden and held in the window, with mouse motion going to the machine
+    pointer_captured: bool,
+    /// The fast forward hotkey is held
+    fast_forwarding: bool,
+    /// Paused by the user, only frame advance moves the machine
+    user_paused: bool,
+    /// Frame advance was pressed, the frame is run right before the next redraw so it shows up immediately
+    frame_advance_pending: bool,
+    label_editor: LabelEditorState,
+    hex_viewer: HexViewerState,
+    /// What the frame skip indicator currently shows
+    frames_skipped: u32,
+    last_gui_repaint: Instant,
+    auxiliary_windows: HashMap<WindowId, AuxiliaryWindowContext<R>>,
+    /// Windows can only be created from inside the event loop callbacks
+    pending_auxiliary_windows: Vec<PendingAuxiliaryWindow>,
+    autosaver: Autosaver,
+    /// Only one at a time, as each starts from the ROMs the last one left
+    import: Option<BackgroundImport>,
+    /// Why the rendering backend could not start, the event loop exits with this so another backend can be tried
+    rendering_failure: Option<Box<dyn Error>>,
+    /// Every machine started gets a GDB stub on this port
+    gdb_port: Option<u16>,
+    /// Being brought back from the last launch, until its machine runs
+    restored_session: Option<Session>,
 }
 
-impl<E: Executor, R: RenderingBackend> DesktopRuntime<E, R> {
+impl<E: Executor, R: RenderingBackend + 'static> DesktopRuntime<E, R> {
     pub fn new(rom_manager: Arc<RomManager>, global_config: Arc<RwLock<GlobalConfig>>) -> Self {
+        let gamepad_manager = GilrsGamepadManager::new(global_config.clone());
+        let mut gui_state = GuiRuntime::new(global_config.clone());
+        gui_state.set_connected_gamepads(gamepad_manager.connected_gamepads());
+        gui_state.set_rom_manager(rom_manager.clone());
+        let applied_config = global_config.read().unwrap().clone();
+        let autosaver = Autosaver::new(global_config.clone());
+        gui_state.set_color_filter_supported(R::COLOR_FILTER);
+
         Self {
             framerate_tracker: FramerateTracker::default(),
             egui_context: egui::Context::default(),
-            gui_state: GuiRuntime::new(global_config.clone()),
+            gui_state,
             windowing_context: None,
             machine_context_state: None,
             rom_manager,
             global_config,
+            applied_config,
+            gamepad_manager,
+            focus_paused: false,
+            window_focused: true,
+            idle_paused: false,
+            debugger_paused: false,
+            pointer_captured: false,
+            fast_forwarding: false,
+            user_paused: false,
+            frame_advance_pending: false,
+            label_editor: LabelEditorState::default(),
+            hex_viewer: HexViewerState::default(),
+            frames_skipped: 0,
+            last_gui_repaint: Instant::now(),
+            auxiliary_windows: HashMap::new(),
+            pending_auxiliary_windows: Vec::new(),
+            autosaver,
+            import: None,
+            rendering_failure: None,
+            gdb_port: None,
+            restored_session: None,
         }
     }
 
@@ -95,38 +331,1067 @@ impl<E: Executor, R: RenderingBackend> DesktopRuntime<E, R> {
         rom_manager: Arc<RomManager>,
         user_specified_roms: Vec<RomId>,
         forced_system: Option<GameSystem>,
+        audio_capture: Option<PathBuf>,
+        gdb_port: Option<u16>,
         global_config: Arc<RwLock<GlobalConfig>>,
     ) -> Self {
         let mut me = Self::new(rom_manager, global_config);
+        me.gdb_port = gdb_port;
+
+        me.machine_context_state = Some(MachineContextState::Pending {
+            user_specified_roms,
+            forced_system,
+            audio_capture,
+        });
+
+        me
+    }
+
+    /// Picks up what was open when the GUI last shut down, the game starts as soon as the window is there
+    pub fn restore_session(&mut self) {
+        let session = match Session::take(&SESSION_PATH) {
+            Ok(Some(session)) => session,
+            Ok(None) => return,
+            Err(error) => {
+                tracing::error!("Could not read the last session: {}", error);
+                return;
+            }
+        };
+
+        if let Some(game) = &session.game {
+            self.machine_context_state = Some(MachineContextState::Pending {
+                user_specified_roms: game.roms.clone(),
+                forced_system: Some(game.system),
+                audio_capture: None,
+            });
+        }
+
+        self.restored_session = Some(session);
+    }
+
+    /// Writes down what is open for [Self::restore_session], if the user wants it back
+    fn store_session(&mut self) {
+        if !self.global_config.read().unwrap().restore_session {
+            return;
+        }
+
+        let mut session = Session {
+            window: self
+                .windowing_context
+                .as_ref()
+                .map(|windowing_context| WindowGeometry::of(&windowing_context.window)),
+            ..Default::default()
+        };
+
+        if let Some(MachineContextState::Running { machine_context }) =
+            self.machine_context_state.as_mut()
+        {
+            let path = machine_context
+                .snapshot_manager
+                .slot_path(SESSION_SNAPSHOT_SLOT);
+
+            match machine_context
+                .snapshot_manager
+                .save(&mut machine_context.executor, &path, None)
+            {
+                Ok(()) => {
+                    session.game = Some(SessionGame {
+                        roms: machine_context.machine.fingerprint.roms.clone(),
+                        system: machine_context.system,
+                    });
+                    session.auxiliary_windows = self
+                        .auxiliary_windows
+                        .values()
+                        .map(|auxiliary_window| {
+                            (
+                                auxiliary_window.kind,
+                                WindowGeometry::of(&auxiliary_window.windowing_context.window),
+                            )
+                        })
+                        .collect();
+                    session.debugger_memory = self
+                        .auxiliary_windows
+                        .values()
+                        .find(|auxiliary_window| {
+                            auxiliary_window.kind == AuxiliaryWindowKind::Debugger
+                        })
+                        .map(|auxiliary_window| {
+                            auxiliary_window
+                                .egui_context
+                                .memory(|memory| memory.clone())
+                        });
+                }
+                Err(error) => {
+                    tracing::error!(
+                        "Could not save the game for the next session to {}: {}",
+                        path.display(),
+                        error
+                    );
+                }
+            }
+        }
+
+        if let Err(error) = session.store(&SESSION_PATH) {
+            tracing::error!("Could not store the session: {}", error);
+        }
+    }
+
+    pub fn setup_window(&mut self, event_loop: &ActiveEventLoop) -> Arc<Window> {
+        let mut window_attributes = Window::default_attributes()
+            .with_title("MultiEMU")
+            .with_resizable(true)
+            // TODO: Add a fullscreen knob on the global config
+            .with_inner_size(PhysicalSize::new(640, 480));
+
+        if let Some(geometry) = self
+            .restored_session
+            .as_ref()
+            .and_then(|session| session.window)
+        {
+            window_attributes = geometry.apply(window_attributes);
+        }
+
+        Arc::new(event_loop.create_window(window_attributes).unwrap())
+    }
+
+    pub fn is_gui_active(&self) -> bool {
+        // This helps the user not stare at a black screen
+        self.gui_state.active
+            || self.gui_state.quick_menu_active
+            || !matches!(
+                self.machine_context_state,
+                Some(MachineContextState::Running { .. })
+            )
+    }
+
+    /// Starts building the machine in the background, the loading screen shows until [Self::poll_machine_load] finds
+    /// it done
+    fn start_machine(
+        &mut self,
+        user_specified_roms: Vec<RomId>,
+        forced_system: Option<GameSystem>,
+        audio_capture: Option<PathBuf>,
+    ) where
+        Chip8Display: DisplayComponent<R>,
+    {
+        // Machines are where crashes happen, nothing the user changed should go down with one
+        self.autosaver.flush();
+
+        // FIXME: In no way is this sound. Roms can very much have disagreeing systems
+        let game_system = forced_system
+            .unwrap_or_else(|| self.rom_manager.rom_information[&user_specified_roms[0]].system);
+
+        self.machine_context_state = Some(MachineContextState::Loading {
+            game: user_specified_roms[0],
+            system: game_system,
+            audio_capture,
+            started: Instant::now(),
+            load: MachineLoad::start(game_system, self.rom_manager.clone(), user_specified_roms),
+        });
+    }
+
+    /// Swaps in the machine once it is built, or goes back to the menu if building it failed
+    fn poll_machine_load(&mut self) {
+        let Some(MachineContextState::Loading { load, .. }) = self.machine_context_state.as_mut()
+        else {
+            return;
+        };
+
+        let machine = match load.poll() {
+            LoadProgress::Running => return,
+            LoadProgress::Finished(machine) => machine,
+            LoadProgress::Failed(message) => {
+                tracing::error!("Building the machine failed: {}", message);
+                self.machine_context_state = None;
+                self.restored_session = None;
+                self.gui_state
+                    .notify(format!("The game could not be started: {}", message));
+                return;
+            }
+        };
+
+        let Some(MachineContextState::Loading {
+            game,
+            system,
+            audio_capture,
+            ..
+        }) = self.machine_context_state.take()
+        else {
+            unreachable!()
+        };
+
+        // Loading only starts with a window around
+        let mut windowing_context = self
+            .windowing_context
+            .take()
+            .expect("Window was not initialized");
+        machine.initialize_displays(&mut windowing_context.display_backend_state);
+        self.run_machine(machine, game, system, audio_capture);
+        self.windowing_context = Some(windowing_context);
+
+        if let Some(session) = self.restored_session.take() {
+            self.resume_session(session);
+        }
+
+        if let Some(windowing_context) = &self.windowing_context {
+            windowing_context.window.request_redraw();
+        }
+    }
+
+    /// Continues the game of the last session where it was left, with the windows it had open
+    fn resume_session(&mut self, session: Session) {
+        let Some(MachineContextState::Running { machine_context }) =
+            self.machine_context_state.as_mut()
+        else {
+            return;
+        };
+
+        load_snapshot(machine_context, &mut self.gui_state, SESSION_SNAPSHOT_SLOT);
+
+        let mut debugger_memory = session.debugger_memory;
+        self.pending_auxiliary_windows
+            .extend(
+                session
+                    .auxiliary_windows
+                    .into_iter()
+                    .map(|(kind, geometry)| PendingAuxiliaryWindow {
+                        kind,
+                        geometry: Some(geometry),
+                        egui_memory: if kind == AuxiliaryWindowKind::Debugger {
+                            debugger_memory.take()
+                        } else {
+                            None
+                        },
+                    }),
+            );
+    }
+
+    /// Sets up everything around a built machine and lets it run
+    fn run_machine(
+        &mut self,
+        mut machine: Machine<R>,
+        game: RomId,
+        game_system: GameSystem,
+        audio_capture: Option<PathBuf>,
+    ) {
+        let (rewind_depth, rewind_interval, rewind_enabled, symbol_table, scripts) = {
+            let mut global_config = self.global_config.write().unwrap();
+            global_config.active_game = Some(game);
+
+            (
+                global_config.rewind_depth,
+                global_config.rewind_interval,
+                global_config.effective_rewind(),
+                SymbolTable::new(global_config.effective_labels()),
+                global_config.effective_scripts().to_vec(),
+            )
+        };
+        let rewind_buffer = machine.insert_rewind(rewind_depth, rewind_interval);
+
+        let mut executor = E::new(
+            std::mem::take(&mut machine.tasks),
+            machine.memory_translation_table.clone(),
+        );
+        if let Some(rewind_buffer) = &rewind_buffer {
+            rewind_buffer.lock().unwrap().set_enabled(rewind_enabled);
+            executor.set_rewind_buffer(rewind_buffer.clone());
+        }
+
+        let watchdog_timeout = self.global_config.read().unwrap().watchdog_timeout;
+        let watchdog = (watchdog_timeout != 0).then(|| {
+            Watchdog::spawn(
+                executor.heartbeat(),
+                Duration::from_secs(watchdog_timeout as u64),
+            )
+        });
+
+        let frame_rate = machine.frame_task.and_then(|frame_task| {
+            executor
+                .schedule_report()
+                .tasks
+                .into_iter()
+                .find(|task| task.name == frame_task)
+                .map(|task| task.tick_rate)
+        });
+
+        let mut audio_context = CpalContext::new();
+
+        match audio_context.as_mut() {
+            Some(audio_context) => audio_context.startup_stream(&machine.audio_components),
+            None => tracing::warn!("No audio output device found, running without audio"),
+        }
+
+        if let (Some(audio_context), Some(path)) = (&audio_context, audio_capture) {
+            let per_component = self
+                .global_config
+                .read()
+                .unwrap()
+                .audio_capture_per_component;
+
+            if let Err(error) = audio_context.start_capture(&path, per_component) {
+                tracing::error!(
+                    "Failed to start audio capture to {}: {}",
+                    path.display(),
+                    error
+                );
+            }
+        }
+
+        let gdb_server = self.gdb_port.and_then(|port| {
+            GdbServer::start(
+                port,
+                machine.debug_targets.clone(),
+                machine.memory_translation_table.clone(),
+            )
+            .inspect_err(|error| {
+                tracing::error!("Failed to start the GDB stub on port {}: {}", port, error)
+            })
+            .ok()
+        });
+
+        let scripts = load_scripts(&machine, &scripts, &mut self.gui_state);
+
+        match CheatDatabase::load(&CHEAT_DATABASE_PATH) {
+            Ok(cheat_database) => {
+                if let Some(cheats) = cheat_database.cheats.get(&game) {
+                    apply_cheats(&machine, game_system, cheats, &mut self.gui_state);
+                }
+            }
+            Err(error) => tracing::error!("Failed to load the cheat database: {}", error),
+        }
+
+        self.gamepad_manager
+            .attach_machine(machine.controllers.clone(), game_system);
+
+        self.gui_state.active = false;
+        self.machine_context_state = Some(MachineContextState::Running {
+            machine_context: MachineContext {
+                executor,
+                game,
+                system: game_system,
+                rewind_buffer,
+                audio_context,
+                symbol_table,
+                snapshot_manager: SnapshotManager::new(
+                    machine.fingerprint.clone(),
+                    machine.snapshotable_components.clone(),
+                ),
+                // TODO: Set this once netplay exists
+                netplay: false,
+                watchdog,
+                frame_rate,
+                gdb_server,
+                scripts,
+                machine,
+            },
+        });
+    }
+
+    /// Starts a ROM picked in the menu, whether or not it was imported
+    fn open_game(&mut self, path: &Path)
+    where
+        Chip8Display: DisplayComponent<R>,
+    {
+        // Imported ROMs are already known, and their store is no place for sidecars
+        let imported = self
+            .rom_manager
+            .rom_paths
+            .iter()
+            .find(|(_, rom_path)| rom_path.as_path() == path)
+            .map(|(rom_id, _)| *rom_id);
+
+        let rom_id = match imported {
+            Some(rom_id) => rom_id,
+            None => {
+                let Some(rom_info) = identify_external_rom(path, &self.rom_manager) else {
+                    self.gui_state.notify(format!(
+                        "Could not tell what system {} is for",
+                        path.display()
+                    ));
+                    return;
+                };
+
+                let mut rom_manager = RomManager::clone(&self.rom_manager);
+                let rom_id = rom_info.hash;
+                rom_manager.rom_paths.insert(rom_id, path.to_path_buf());
+                rom_manager.rom_information.insert(rom_id, rom_info);
+                self.rom_manager = Arc::new(rom_manager);
+                self.gui_state.set_rom_manager(self.rom_manager.clone());
+
+                rom_id
+            }
+        };
+
+        // The old machine has to be gone before the new one grabs the audio device and the gamepads
+        self.stop_machine();
+        self.start_machine(vec![rom_id], None, None);
+    }
+
+    /// Tears down the running machine, leaving the menu up
+    fn stop_machine(&mut self) {
+        self.set_pointer_capture(false);
+        if let Some(MachineContextState::Running { machine_context }) =
+            self.machine_context_state.take()
+        {
+            machine_context.shutdown();
+        }
+        self.auxiliary_windows.clear();
+        self.gamepad_manager.detach_machine();
+        self.global_config.write().unwrap().active_game = None;
+        self.focus_paused = false;
+        self.debugger_paused = false;
+        self.idle_paused = false;
+        self.user_paused = false;
+        self.frame_advance_pending = false;
+        self.gui_state.active = true;
+    }
+
+    /// Keeps the machine going muted behind the menu, if the config asks for that
+    fn run_machine_behind_menu(&mut self) {
+        let Some(MachineContextState::Running { machine_context }) =
+            self.machine_context_state.as_mut()
+        else {
+            return;
+        };
+
+        if let Some(audio_context) = &machine_context.audio_context {
+            audio_context.set_muted(true);
+        }
+
+        let paused =
+            self.focus_paused || self.idle_paused || self.debugger_paused || self.user_paused;
+        let (battery_saver, speed) = {
+            let global_config = self.global_config.read().unwrap();
+
+            (global_config.battery_saver, global_config.speed_in_menu())
+        };
+        let Some(speed) = speed.filter(|_| !paused) else {
+            return;
+        };
+
+        self.framerate_tracker.record_frame();
+        machine_context.executor.set_catch_up(!battery_saver);
+        machine_context
+            .executor
+            .set_speed(Ratio::new(speed as u32, 100));
+        machine_context
+            .executor
+            .run(self.framerate_tracker.average_framerate());
+        machine_context.follow_tick_rate_changes();
+        run_scripts(machine_context, &mut self.gui_state);
+    }
+
+    /// Released whenever the menu comes up, as it needs the cursor
+    fn set_pointer_capture(&mut self, captured: bool) {
+        let Some(windowing_context) = self.windowing_context.as_ref() else {
+            return;
+        };
+        let window = &windowing_context.window;
+
+        if captured {
+            // Not every platform can lock the cursor in place, confining it still keeps it from leaving
+            if let Err(error) = window
+                .set_cursor_grab(CursorGrabMode::Locked)
+                .or_else(|_| window.set_cursor_grab(CursorGrabMode::Confined))
+            {
+                tracing::warn!("Could not capture the pointer: {}", error);
+                self.gui_state
+                    .notify("Pointer capture is not supported here");
+                return;
+            }
+        } else if let Err(error) = window.set_cursor_grab(CursorGrabMode::None) {
+            tracing::warn!("Could not release the pointer: {}", error);
+        }
+
+        window.set_cursor_visible(!captured);
+        self.pointer_captured = captured;
+    }
+
+    /// Tells about a finished long job, on screen if the window has focus and through the desktop if it doesn't
+    fn report_job_finished(&mut self, message: String) {
+        tracing::info!("{}", message);
+
+        if !self.window_focused && self.global_config.read().unwrap().desktop_notifications {
+            notification::send(message);
+        } else {
+            self.gui_state.notify(message);
+        }
+    }
+
+    /// Takes over the ROMs of the import once it is done
+    fn poll_import(&mut self) {
+        let Some(import) = self.import.as_ref() else {
+            return;
+        };
+
+        match import.poll() {
+            ImportProgress::Running => {}
+            ImportProgress::Finished(rom_manager, report) => {
+                self.import = None;
+                self.rom_manager = Arc::new(rom_manager);
+                self.gui_state.set_rom_manager(self.rom_manager.clone());
+                if !report.imported.is_empty() {
+                    self.autosaver.database_changed(self.rom_manager.clone());
+                }
+
+                self.report_job_finished(format!(
+                    "Imported {} ROMs, skipped {} files",
+                    report.imported.len(),
+                    report.skipped.len()
+                ));
+            }
+            ImportProgress::Failed => {
+                self.import = None;
+                self.report_job_finished("The import failed, see the log".to_string());
+            }
+        }
+    }
+
+    /// Pauses once no input has come in for the configured time, and resumes on the next one
+    fn update_idle_pause(&mut self) {
+        let (idle_timeout, autosave) = {
+            let global_config = self.global_config.read().unwrap();
+            (
+                Duration::from_secs(global_config.idle_pause_minutes as u64 * 60),
+                global_config.idle_pause_autosave,
+            )
+        };
+        let is_gui_active = self.is_gui_active();
+
+        let Some(MachineContextState::Running { machine_context }) =
+            self.machine_context_state.as_mut()
+        else {
+            self.idle_paused = false;
+            return;
+        };
+
+        // Time spent in the menu doesn't count, and netplay can't stop for one player
+        if idle_timeout.is_zero() || is_gui_active || machine_context.netplay {
+            self.gamepad_manager.reset_idle_time();
+            self.idle_paused = false;
+            return;
+        }
+
+        let idle = self.gamepad_manager.idle_time() >= idle_timeout;
+
+        if idle && !self.idle_paused {
+            tracing::info!(
+                "Pausing emulation as there was no input for {:?}",
+                idle_timeout
+            );
+            self.gui_state.notify("Paused for inactivity");
+
+            if autosave {
+                save_snapshot(machine_context, &mut self.gui_state, IDLE_SNAPSHOT_SLOT);
+            }
+        } else if !idle && self.idle_paused {
+            tracing::info!("Resuming emulation after inactivity");
+        }
+
+        self.idle_paused = idle;
+    }
+
+    /// Applies settings the menu changed to whatever they affect
+    fn apply_config_changes(&mut self) {
+        let global_config = self.global_config.read().unwrap();
+        if *global_config == self.applied_config {
+            return;
+        }
+        let global_config = global_config.clone();
+
+        for scope in global_config.changed_scopes(&self.applied_config) {
+            tracing::info!("Applying config changes to {}", scope);
+
+            match scope {
+                ConfigApplyScope::Swapchain => {
+                    if let Some(windowing_context) = self.windowing_context.as_mut() {
+                        windowing_context
+                            .display_backend_state
+                            .surface_config_changed();
+                    }
+
+                    for auxiliary_window in self.auxiliary_windows.values_mut() {
+                        auxiliary_window
+                            .windowing_context
+                            .display_backend_state
+                            .surface_config_changed();
+                    }
+                }
+                ConfigApplyScope::AudioStream => {
+                    if let Some(MachineContextState::Running { machine_context }) =
+                        self.machine_context_state.as_ref()
+                    {
+                        if let Some(audio_context) = &machine_context.audio_context {
+                            audio_context.set_muted(global_config.audio_muted);
+                        }
+                    }
+                }
+                ConfigApplyScope::InputMapping => {
+                    self.gamepad_manager.release_all_inputs();
+                }
+                ConfigApplyScope::Rewind => {
+                    if let Some(MachineContextState::Running { machine_context }) =
+                        self.machine_context_state.as_ref()
+                    {
+                        if let Some(rewind_buffer) = &machine_context.rewind_buffer {
+                            rewind_buffer
+                                .lock()
+                                .unwrap()
+                                .set_enabled(global_config.effective_rewind());
+                        }
+                    }
+                }
+                ConfigApplyScope::Labels => {
+                    if let Some(MachineContextState::Running { machine_context }) =
+                        self.machine_context_state.as_mut()
+                    {
+                        machine_context.symbol_table =
+                            SymbolTable::new(global_config.effective_labels());
+                    }
+                }
+                // Scripts start over, as there is no telling which ones kept state
+                ConfigApplyScope::Scripts => {
+                    if let Some(MachineContextState::Running { machine_context }) =
+                        self.machine_context_state.as_mut()
+                    {
+                        machine_context.scripts = load_scripts(
+                            &machine_context.machine,
+                            global_config.effective_scripts(),
+                            &mut self.gui_state,
+                        );
+                    }
+                }
+                // The menu shows a badge for these
+                ConfigApplyScope::Restart => {}
+            }
+        }
+
+        self.applied_config = global_config;
+    }
+
+    /// Returns true if the input was consumed by a hotkey
+    ///
+    /// Releases of hotkeys are consumed too, only the ones held down like fast forward act on them
+    fn handle_hotkey(&mut self, input: Input, pressed: bool) -> bool {
+        let Some(hotkey) = self
+            .global_config
+            .read()
+            .unwrap()
+            .hotkeys
+            .get(&input)
+            .copied()
+        else {
+            return false;
+        };
+
+        if !pressed && hotkey != Hotkey::FastForward {
+            return true;
+        }
+
+        match hotkey {
+            Hotkey::OpenMenu => {
+                self.gui_state.active = !self.gui_state.active;
+                self.gui_state.quick_menu_active = false;
+            }
+            Hotkey::OpenQuickMenu => {
+                if !matches!(
+                    self.machine_context_state,
+                    Some(MachineContextState::Running { .. })
+                ) {
+                    return false;
+                }
+
+                self.gui_state.quick_menu_active = !self.gui_state.quick_menu_active;
+                self.gui_state.active = false;
+            }
+            Hotkey::OpenCommandPalette => self.gui_state.open_command_palette(),
+            Hotkey::ToggleMute => {
+                let mut global_config = self.global_config.write().unwrap();
+                global_config.audio_muted = !global_config.audio_muted;
+                tracing::info!("Audio muted: {}", global_config.audio_muted);
+            }
+            Hotkey::SaveSnapshot | Hotkey::LoadSnapshot => {
+                let Some(MachineContextState::Running { machine_context }) =
+                    self.machine_context_state.as_mut()
+                else {
+                    return false;
+                };
+
+                if hotkey == Hotkey::SaveSnapshot {
+                    save_snapshot(machine_context, &mut self.gui_state, QUICK_SNAPSHOT_SLOT);
+                } else {
+                    load_snapshot(machine_context, &mut self.gui_state, QUICK_SNAPSHOT_SLOT);
+                }
+            }
+            Hotkey::TogglePointerCapture => {
+                if self.is_gui_active() {
+                    return false;
+                }
+
+                self.set_pointer_capture(!self.pointer_captured);
+            }
+            Hotkey::Rewind => {
+                let Some(MachineContextState::Running { machine_context }) =
+                    self.machine_context_state.as_mut()
+                else {
+                    return false;
+                };
+
+                // Going back in time alone would desync the other players
+                if machine_context.netplay {
+                    return true;
+                }
+
+                if !machine_context.executor.rewind() {
+                    self.gui_state.notify("Nothing left to rewind");
+                }
+            }
+            Hotkey::Screenshot => {
+                let Some(MachineContextState::Running { machine_context }) =
+                    self.machine_context_state.as_ref()
+                else {
+                    return false;
+                };
+
+                let paths = capture_screenshots(
+                    machine_context
+                        .machine
+                        .display_components
+                        .iter()
+                        .filter_map(|display_component| {
+                            display_component.lock().unwrap().dump_display_data()
+                        }),
+                    machine_context.game,
+                    "screenshot",
+                );
+
+                if paths.is_empty() {
+                    self.gui_state
+                        .notify("Nothing to screenshot, the displays can't be read back");
+                } else {
+                    self.gui_state.notify("Saved screenshot to the gallery");
+                }
+            }
+            Hotkey::ToggleFullscreen => {
+                let Some(windowing_context) = self.windowing_context.as_ref() else {
+                    return false;
+                };
+                let window = &windowing_context.window;
+
+                window.set_fullscreen(match window.fullscreen() {
+                    Some(_) => None,
+                    None => Some(Fullscreen::Borderless(None)),
+                });
+            }
+            Hotkey::TogglePause | Hotkey::FrameAdvance => {
+                let Some(MachineContextState::Running { machine_context }) =
+                    self.machine_context_state.as_ref()
+                else {
+                    return false;
+                };
+
+                // Stopping alone would desync the other players
+                if machine_context.netplay {
+                    return true;
+                }
+
+                if hotkey == Hotkey::TogglePause {
+                    self.user_paused = !self.user_paused;
+                } else if machine_context.machine.frame_task.is_none() {
+                    self.gui_state
+                        .notify("This machine has no display to advance a frame of");
+                } else {
+                    self.user_paused = true;
+                    self.frame_advance_pending = true;
+                }
+            }
+            Hotkey::FastForward => {
+                // Going faster than the other players would desync them
+                self.fast_forwarding = pressed
+                    && !matches!(
+                        &self.machine_context_state,
+                        Some(MachineContextState::Running { machine_context }) if machine_context.netplay
+                    );
+            }
+        }
+
+        true
+    }
+
+    fn apply_debugger_action(&mut self, action: DebuggerAction) {
+        let Some(MachineContextState::Running { machine_context }) =
+            self.machine_context_state.as_mut()
+        else {
+            return;
+        };
+
+        match action {
+            DebuggerAction::TogglePause => {
+                self.debugger_paused = !self.debugger_paused;
+            }
+            DebuggerAction::StepBack => {
+                self.debugger_paused = true;
+
+                if !machine_context.executor.step_back_instruction() {
+                    self.gui_state
+                        .notify("Nothing to step back to, rewind points have to be enabled");
+                }
+            }
+            DebuggerAction::Step => {
+                self.debugger_paused = true;
+                machine_context.executor.step_instruction();
+            }
+        }
+    }
+
+    /// Runs and stops the machine for a attached remote debugger, the same way the debugger window does
+    fn apply_gdb_requests(&mut self) {
+        let Some(MachineContextState::Running { machine_context }) =
+            self.machine_context_state.as_mut()
+        else {
+            return;
+        };
+        let Some(gdb_server) = machine_context.gdb_server.as_ref() else {
+            return;
+        };
+
+        for request in gdb_server.take_requests() {
+            match request {
+                GdbRequest::Continue => self.debugger_paused = false,
+                GdbRequest::Step => {
+                    self.debugger_paused = true;
+                    machine_context.executor.step_instruction();
+                    gdb_server.report_stop(GdbStop::Stepped);
+                }
+                GdbRequest::Halt => {
+                    self.debugger_paused = true;
+                    gdb_server.report_stop(GdbStop::Halted);
+                }
+            }
+        }
+    }
+
+    fn open_pending_auxiliary_windows(&mut self, event_loop: &ActiveEventLoop)
+    where
+        R::RuntimeState: WinitRenderBackendState,
+    {
+        let Some(main_window_context) = self.windowing_context.as_ref() else {
+            return;
+        };
+
+        for PendingAuxiliaryWindow {
+            kind,
+            geometry,
+            egui_memory,
+        } in self.pending_auxiliary_windows.drain(..)
+        {
+            let title = match kind {
+                AuxiliaryWindowKind::Debugger => "MultiEMU Debugger".to_string(),
+                AuxiliaryWindowKind::Display { index } => format!("MultiEMU Display {}", index),
+            };
+            let mut window_attributes = Window::default_attributes()
+                .with_title(title)
+                .with_resizable(true)
+                .with_inner_size(PhysicalSize::new(640, 480));
+
+            if let Some(geometry) = geometry {
+                window_attributes = geometry.apply(window_attributes);
+            }
+
+            let window = Arc::new(event_loop.create_window(window_attributes).unwrap());
+
+            let display_backend_state = main_window_context
+                .display_backend_state
+                .new_secondary(window.clone());
+            let egui_context = egui::Context::default();
+            if let Some(egui_memory) = egui_memory {
+                egui_context.memory_mut(|memory| *memory = egui_memory);
+            }
+            // Every window has its own egui context so they can all be the root viewport
+            let egui_winit_context = egui_winit::State::new(
+                egui_context.clone(),
+                ViewportId::ROOT,
+                &window,
+                None,
+                None,
+                None,
+            );
+
+            tracing::info!("Opened auxiliary window {:?}", kind);
+
+            self.auxiliary_windows.insert(
+                window.id(),
+                AuxiliaryWindowContext {
+                    kind,
+                    windowing_context: WindowingContext {
+                        window,
+                        display_backend_state,
+                        egui_winit_context,
+                    },
+                    egui_context,
+                },
+            );
+        }
+    }
+
+    fn auxiliary_window_event(&mut self, window_id: WindowId, event: WindowEvent) {
+        let Some(auxiliary_window) = self.auxiliary_windows.get_mut(&window_id) else {
+            return;
+        };
+        let windowing_context = &mut auxiliary_window.windowing_context;
+
+        match event {
+            WindowEvent::CloseRequested => {
+                self.auxiliary_windows.remove(&window_id);
+            }
+            WindowEvent::Resized(_) => {
+                windowing_context.display_backend_state.surface_resized();
+            }
+            WindowEvent::RedrawRequested => {
+                let Some(MachineContextState::Running { machine_context }) =
+                    self.machine_context_state.as_ref()
+                else {
+                    return;
+                };
+
+                match auxiliary_window.kind {
+                    AuxiliaryWindowKind::Debugger => {
+                        let mut action = None;
+                        let mut new_labels = None;
+                        let full_output = auxiliary_window.egui_context.run(
+                            windowing_context
+                                .egui_winit_context
+                                .take_egui_input(&windowing_context.window),
+                            |context| {
+                                TopBottomPanel::top("debugger_toolbar").show(context, |ui| {
+                                    // Netplay can't stop for one player
+                                    ui.add_enabled_ui(!machine_context.netplay, |ui| {
+                                        ui.horizontal(|ui| {
+                                            let pause_text = if self.debugger_paused {
+                                                "Resume"
+                                            } else {
+                                                "Pause"
+                                            };
+
+                                            if ui.button(pause_text).clicked() {
+                                                action = Some(DebuggerAction::TogglePause);
+                                            }
+                                            if ui
+                                                .button("Step Back")
+                                                .on_hover_text(
+                                                    "Replays from the last rewind point to the previous instruction",
+                                                )
+                                                .clicked()
+                                            {
+                                                action = Some(DebuggerAction::StepBack);
+                                            }
+                                            if ui.button("Step").clicked() {
+                                                action = Some(DebuggerAction::Step);
+                                            }
+                                        });
+                                    });
+                                });
+
+                                CentralPanel::default().show(context, |ui| {
+                                    ScrollArea::vertical().show(ui, |ui| {
+                                        for (name, disassembler) in &machine_context.machine.disassemblers
+                                        {
+                                            CollapsingHeader::new(format!("Disassembly: {}", name))
+                                                .default_open(true)
+                                                .show(ui, |ui| {
+                                                    disassembler.show(
+                                                        ui,
+                                                        &machine_context.machine.memory_translation_table,
+                                                        &machine_context.symbol_table,
+                                                    )
+                                                });
+                                        }
+
+                                        CollapsingHeader::new("Memory").show(ui, |ui| {
+                                            hex_viewer_page(
+                                                ui,
+                                                &mut self.hex_viewer,
+                                                &machine_context.machine.memory_translation_table,
+                                                &machine_context.symbol_table,
+                                            );
+                                        });
+
+                                        CollapsingHeader::new("Labels").show(ui, |ui| {
+                                            let global_config =
+                                                self.global_config.read().unwrap();
+                                            let labels = global_config.effective_labels();
 
-        me.machine_context_state = Some(MachineContextState::Pending {
-            user_specified_roms,
-            forced_system,
-        });
+                                            new_labels =
+                                                labels_page(ui, &mut self.label_editor, labels);
+                                        });
 
-        me
-    }
+                                        for (name, page) in &machine_context.machine.gui_pages {
+                                            CollapsingHeader::new(*name).default_open(true).show(
+                                                ui,
+                                                |ui| {
+                                                    page(ui, &machine_context.machine.queryable_components)
+                                                },
+                                            );
+                                        }
+                                    });
+                                });
+                            },
+                        );
 
-    pub fn setup_window(&mut self, event_loop: &ActiveEventLoop) -> Arc<Window> {
-        let window_attributes = Window::default_attributes()
-            .with_title("MultiEMU")
-            .with_resizable(true)
-            // TODO: Add a fullscreen knob on the global config
-            .with_inner_size(PhysicalSize::new(640, 480));
-        Arc::new(event_loop.create_window(window_attributes).unwrap())
-    }
+                        windowing_context
+                            .display_backend_state
+                            .redraw(RedrawKind::Egui {
+                                context: &auxiliary_window.egui_context,
+                                full_output,
+                            });
 
-    pub fn is_gui_active(&self) -> bool {
-        // This helps the user not stare at a black screen
-        self.gui_state.active
-            || !matches!(
-                self.machine_context_state,
-                Some(MachineContextState::Running { .. })
-            )
+                        // Picked up by the next config change check like any other setting
+                        if let Some(labels) = new_labels {
+                            let game = machine_context.game;
+                            let mut global_config = self.global_config.write().unwrap();
+                            let game_config = global_config.game_configs.entry(game).or_default();
+                            game_config.labels = labels;
+
+                            if *game_config == Default::default() {
+                                global_config.game_configs.shift_remove(&game);
+                            }
+                        }
+
+                        if let Some(action) = action {
+                            self.apply_debugger_action(action);
+                        }
+                    }
+                    AuxiliaryWindowKind::Display { index } => {
+                        let Some(display_component) = machine_context
+                            .machine
+                            .display_components
+                            .get(index..=index)
+                        else {
+                            return;
+                        };
+
+                        windowing_context
+                            .display_backend_state
+                            .redraw(RedrawKind::Machine(display_component));
+                    }
+                }
+            }
+            event => {
+                if auxiliary_window.kind == AuxiliaryWindowKind::Debugger {
+                    let EventResponse { repaint, .. } = windowing_context
+                        .egui_winit_context
+                        .on_window_event(&windowing_context.window, &event);
+
+                    if repaint {
+                        windowing_context.window.request_redraw();
+                    }
+                }
+            }
+        }
     }
 }
 
-impl<E: Executor, R: RenderingBackend> ApplicationHandler for DesktopRuntime<E, R>
+impl<E: Executor, R: RenderingBackend + 'static> ApplicationHandler for DesktopRuntime<E, R>
 where
     R::RuntimeState: WinitRenderBackendState,
     Chip8Display: DisplayComponent<R>,
@@ -138,8 +1403,17 @@ where
         }
 
         let window = self.setup_window(event_loop);
-        let mut rendering_state = R::RuntimeState::new(window.clone(), self.global_config.clone());
-        // A hack that only works because this application only uses one window
+        let rendering_state = match R::RuntimeState::new(window.clone(), self.global_config.clone())
+        {
+            Ok(rendering_state) => rendering_state,
+            Err(error) => {
+                tracing::error!("The rendering backend could not start: {}", error);
+                self.rendering_failure = Some(error);
+                event_loop.exit();
+                return;
+            }
+        };
+        // Every window has its own egui context, so each can be the root viewport
         let viewport_id = ViewportId::ROOT;
         let egui_winit_context = egui_winit::State::new(
             self.egui_context.clone(),
@@ -149,65 +1423,49 @@ where
             None,
             None,
         );
+        self.windowing_context = Some(WindowingContext {
+            window,
+            display_backend_state: rendering_state,
+            egui_winit_context,
+        });
 
         match self.machine_context_state.take() {
             Some(MachineContextState::Pending {
                 user_specified_roms,
                 forced_system,
+                audio_capture,
             }) => {
-                // FIXME: In no way is this sound. Roms can very much have disagreeing systems
-                let game_system = forced_system.unwrap_or_else(|| {
-                    self.rom_manager.rom_information[&user_specified_roms[0]].system
-                });
-
-                let machine = construct_machine::<R>(
-                    game_system,
-                    self.rom_manager.clone(),
-                    user_specified_roms,
-                    &mut rendering_state,
-                );
-
-                let executor = E::new(machine.tasks, machine.memory_translation_table.clone());
-
-                self.gui_state.active = false;
-                self.machine_context_state = Some(MachineContextState::Running {
-                    machine_context: MachineContext {
-                        executor,
-                        display_components: machine.display_components,
-                        gamepad_manager: GilrsGamepadManager::new(
-                            machine.controllers,
-                            game_system,
-                            self.global_config.clone(),
-                        ),
-                    },
-                });
+                self.start_machine(user_specified_roms, forced_system, audio_capture);
             }
-            Some(MachineContextState::Running { .. }) => {
+            Some(MachineContextState::Loading { .. } | MachineContextState::Running { .. }) => {
                 panic!("Windowing was initialized while a machine was active somehow");
             }
             None => {}
         }
-
-        self.windowing_context = Some(WindowingContext {
-            window,
-            display_backend_state: rendering_state,
-            egui_winit_context,
-        })
     }
 
     fn window_event(
         &mut self,
         event_loop: &ActiveEventLoop,
-        _window_id: WindowId,
+        window_id: WindowId,
         event: WindowEvent,
     ) {
+        if self
+            .windowing_context
+            .as_ref()
+            .is_some_and(|windowing_context| windowing_context.window.id() != window_id)
+        {
+            self.auxiliary_window_event(window_id, event);
+            return;
+        }
+
         // This helps the user not stare at a black screen
         let is_gui_active = self.is_gui_active();
 
-        let window_context = self
-            .windowing_context
-            .as_mut()
-            .expect("Window was not initialized");
+        // The rendering backend failed to start and the event loop is on its way out
+        let Some(window_context) = self.windowing_context.as_mut() else {
+            return;
+        };
 
         // Ensure a resize happens before drawing occurs
         if matches!(event, WindowEvent::Resized(_)) {
@@ -216,13 +1474,15 @@ where
         }
 
         if is_gui_active || matches!(event, WindowEvent::ScaleFactorChanged { .. }) {
-            let EventResponse {
-                consumed,
-                repaint: _,
-            } = window_context
+            let EventResponse { consumed, repaint } = window_context
                 .egui_winit_context
                 .on_window_event(&window_context.window, &event);
 
+            // Don't wait for the throttled repaint when the user is interacting
+            if repaint {
+                window_context.window.request_redraw();
+            }
+
             if consumed {
                 return;
             }
@@ -233,6 +1493,43 @@ where
                 tracing::info!("Window close requested");
                 event_loop.exit();
             }
+            WindowEvent::Focused(focused) => {
+                self.window_focused = focused;
+                let global_config = self.global_config.read().unwrap();
+                let netplay = matches!(
+                    &self.machine_context_state,
+                    Some(MachineContextState::Running { machine_context }) if machine_context.netplay
+                );
+
+                self.focus_paused = !focused
+                    && global_config.pause_on_focus_loss
+                    && (!netplay || global_config.pause_on_focus_loss_in_netplay);
+
+                if self.focus_paused {
+                    tracing::info!("Pausing emulation as the window lost focus");
+                }
+
+                // The release of a key held while focus moved away never arrives
+                if !focused {
+                    self.fast_forwarding = false;
+                }
+
+                if !focused && self.pointer_captured {
+                    drop(global_config);
+                    self.set_pointer_capture(false);
+                }
+            }
+            WindowEvent::MouseInput { state, button, .. } => {
+                let Ok(input) = Input::try_from(button) else {
+                    return;
+                };
+
+                // Clicks only count as the pointer of the machine while captured
+                if self.pointer_captured && !is_gui_active {
+                    self.gamepad_manager
+                        .insert_input(input, InputState::Digital(state == ElementState::Pressed));
+                }
+            }
             WindowEvent::KeyboardInput {
                 device_id: _,
                 event,
@@ -242,40 +1539,212 @@ where
                     return;
                 }
 
-                if !is_gui_active {
-                    let Some(MachineContextState::Running { machine_context }) =
-                        self.machine_context_state.as_mut()
-                    else {
-                        tracing::error!("Menu is not active yet no machine is running, the user is probably sitting at a black screen right now");
-                        return;
-                    };
+                // Hotkeys never reach the gamepads but are still activity
+                self.gamepad_manager.reset_idle_time();
 
-                    let PhysicalKey::Code(key) = event.physical_key else {
-                        return;
-                    };
+                let PhysicalKey::Code(key) = event.physical_key else {
+                    return;
+                };
+
+                let Ok(input) = Input::try_from(key) else {
+                    return;
+                };
+
+                if !event.repeat && self.handle_hotkey(input, event.state == ElementState::Pressed)
+                {
+                    return;
+                }
 
-                    machine_context.gamepad_manager.insert_input(
-                        key.try_into().unwrap(),
+                // The menu being inactive means a machine is running
+                if !is_gui_active {
+                    self.gamepad_manager.insert_input(
+                        input,
                         InputState::Digital(event.state == ElementState::Pressed),
                     );
                 }
             }
             WindowEvent::RedrawRequested => {
                 if is_gui_active {
+                    if self.frames_skipped != 0 {
+                        self.frames_skipped = 0;
+                        show_frame_skip_indicator(&window_context.window, 0);
+                    }
+
                     // Grabbing the ui output is a little unpleasant here
                     let mut ui_output = None;
+                    let mut game_to_open = None;
+                    let mut stop_machine = false;
+                    let schedule_report = match self.machine_context_state.as_ref() {
+                        Some(MachineContextState::Running { machine_context }) => {
+                            Some(machine_context.executor.schedule_report())
+                        }
+                        _ => None,
+                    };
+                    let menu_machine_context = match (
+                        self.machine_context_state.as_ref(),
+                        schedule_report.as_ref(),
+                    ) {
+                        (
+                            Some(MachineContextState::Running { machine_context }),
+                            Some(schedule_report),
+                        ) => Some(MenuMachineContext {
+                            audio_components: &machine_context.machine.audio_components,
+                            audio_capturing: machine_context
+                                .audio_context
+                                .as_ref()
+                                .is_some_and(CpalContext::is_capturing),
+                            display_component_count: machine_context
+                                .machine
+                                .display_components
+                                .len(),
+                            queryable_components: &machine_context.machine.queryable_components,
+                            gui_pages: &machine_context.machine.gui_pages,
+                            peripheral_ports: &machine_context.machine.peripheral_ports,
+                            schedule_report,
+                            game: machine_context.game,
+                            system: machine_context.system,
+                        }),
+                        _ => None,
+                    };
+                    let loading = match self.machine_context_state.as_ref() {
+                        Some(MachineContextState::Loading { game, started, .. }) => Some((
+                            self.rom_manager
+                                .rom_information
+                                .get(game)
+                                .and_then(|rom_info| rom_info.name.clone())
+                                .unwrap_or_else(|| game.to_string()),
+                            *started,
+                        )),
+                        _ => None,
+                    };
                     let full_output = self.egui_context.run(
                         window_context
                             .egui_winit_context
                             .take_egui_input(&window_context.window),
-                        |context| {
-                            ui_output = ui_output.take().or(self.gui_state.run_menu(context));
+                        |context| match &loading {
+                            Some((game, started)) => {
+                                self.gui_state.run_loading_screen(context, game, *started)
+                            }
+                            None => {
+                                ui_output = ui_output
+                                    .take()
+                                    .or(self.gui_state.run_menu(context, menu_machine_context));
+                            }
                         },
                     );
 
                     match ui_output {
                         Some(UiOutput::OpenGame { path }) => {
                             tracing::info!("Opening {} by order of the gui", path.display());
+                            // The machine needs the rendering state the menu is drawn with right now
+                            game_to_open = Some(path);
+                        }
+                        Some(UiOutput::ResetMachine) => {
+                            if let Some(MachineContextState::Running { machine_context }) =
+                                self.machine_context_state.as_ref()
+                            {
+                                tracing::info!("Resetting the machine by order of the gui");
+                                machine_context.machine.queryable_components.reset_all();
+                            }
+                        }
+                        Some(UiOutput::StopMachine) => {
+                            tracing::info!("Stopping the machine by order of the gui");
+                            stop_machine = true;
+                        }
+                        Some(UiOutput::ImportRoms { path, policy }) => {
+                            if self.import.is_some() {
+                                self.gui_state
+                                    .notify("Wait for the running import to finish");
+                            } else {
+                                tracing::info!("Importing ROMs by order of the gui");
+                                self.import = Some(BackgroundImport::start(
+                                    RomManager::clone(&self.rom_manager),
+                                    policy,
+                                    path,
+                                ));
+                            }
+                        }
+                        Some(UiOutput::ToggleAudioCapture) => {
+                            if let Some(MachineContextState::Running { machine_context }) =
+                                self.machine_context_state.as_ref()
+                            {
+                                toggle_audio_capture(
+                                    machine_context.audio_context.as_ref(),
+                                    self.global_config
+                                        .read()
+                                        .unwrap()
+                                        .audio_capture_per_component,
+                                );
+                            }
+                        }
+                        Some(UiOutput::DumpFramebuffer { extension }) => {
+                            if let Some(MachineContextState::Running { machine_context }) =
+                                self.machine_context_state.as_ref()
+                            {
+                                dump_framebuffers(
+                                    &machine_context.machine.display_components,
+                                    extension,
+                                );
+                            }
+                        }
+                        Some(UiOutput::LoadFramebuffer { path }) => {
+                            if let Some(MachineContextState::Running { machine_context }) =
+                                self.machine_context_state.as_ref()
+                            {
+                                load_framebuffers(
+                                    &machine_context.machine.display_components,
+                                    &path,
+                                );
+                            }
+                        }
+                        Some(UiOutput::OpenDebuggerWindow) => {
+                            self.pending_auxiliary_windows
+                                .push(AuxiliaryWindowKind::Debugger.into());
+                        }
+                        Some(UiOutput::OpenDisplayWindow { index }) => {
+                            self.pending_auxiliary_windows
+                                .push(AuxiliaryWindowKind::Display { index }.into());
+                        }
+                        Some(UiOutput::SaveSnapshot { slot }) => {
+                            if let Some(MachineContextState::Running { machine_context }) =
+                                self.machine_context_state.as_mut()
+                            {
+                                save_snapshot(machine_context, &mut self.gui_state, slot);
+                            }
+                        }
+                        Some(UiOutput::LoadSnapshot { slot }) => {
+                            if let Some(MachineContextState::Running { machine_context }) =
+                                self.machine_context_state.as_mut()
+                            {
+                                load_snapshot(machine_context, &mut self.gui_state, slot);
+                            }
+                        }
+                        Some(UiOutput::PlugPeripheral { port, kind }) => {
+                            if let Some(MachineContextState::Running { machine_context }) =
+                                self.machine_context_state.as_mut()
+                            {
+                                machine_context.machine.peripheral_ports[port]
+                                    .plug(kind, &mut machine_context.executor);
+                            }
+                        }
+                        Some(UiOutput::SetCheats { cheats }) => {
+                            if let Some(MachineContextState::Running { machine_context }) =
+                                self.machine_context_state.as_ref()
+                            {
+                                apply_cheats(
+                                    &machine_context.machine,
+                                    machine_context.system,
+                                    &cheats,
+                                    &mut self.gui_state,
+                                );
+                            }
+                        }
+                        Some(UiOutput::OpenInFileManager { path }) => {
+                            open_in_file_manager(&path);
+                        }
+                        Some(UiOutput::Quit) => {
+                            tracing::info!("Quitting by order of the gui");
+                            event_loop.exit();
                         }
                         None => {}
                     }
@@ -286,6 +1755,16 @@ where
                             context: &self.egui_context,
                             full_output,
                         });
+                    self.last_gui_repaint = Instant::now();
+                    self.run_machine_behind_menu();
+
+                    if stop_machine {
+                        self.stop_machine();
+                    }
+
+                    if let Some(path) = game_to_open {
+                        self.open_game(&path);
+                    }
                 } else {
                     let Some(MachineContextState::Running { machine_context }) =
                         self.machine_context_state.as_mut()
@@ -294,58 +1773,542 @@ where
                         return;
                     };
                     self.framerate_tracker.record_frame();
+                    let paused = self.focus_paused
+                        || self.idle_paused
+                        || self.debugger_paused
+                        || self.user_paused;
+                    if let Some(audio_context) = &machine_context.audio_context {
+                        audio_context
+                            .set_muted(self.global_config.read().unwrap().audio_muted || paused);
+                    }
+                    if std::mem::take(&mut self.frame_advance_pending) {
+                        if let Some(frame_task) = machine_context.machine.frame_task {
+                            machine_context.executor.run_single_frame(frame_task);
+                            machine_context.follow_tick_rate_changes();
+                            run_scripts(machine_context, &mut self.gui_state);
+                        }
+                    }
                     window_context
                         .display_backend_state
-                        .redraw(RedrawKind::Machine(&machine_context.display_components));
-                    machine_context
-                        .executor
-                        .run(self.framerate_tracker.average_framerate());
+                        .redraw(RedrawKind::Machine(
+                            &machine_context.machine.display_components,
+                        ));
+                    if !paused {
+                        let (battery_saver, speed) = {
+                            let global_config = self.global_config.read().unwrap();
+                            let speed = if self.fast_forwarding {
+                                global_config.fast_forward_speed
+                            } else {
+                                global_config.effective_speed()
+                            };
+
+                            (global_config.battery_saver, speed)
+                        };
+                        machine_context.executor.set_catch_up(!battery_saver);
+                        machine_context
+                            .executor
+                            .set_speed(Ratio::new(speed as u32, 100));
+                        machine_context
+                            .executor
+                            .run(self.framerate_tracker.average_framerate());
+                        machine_context.follow_tick_rate_changes();
+                        run_scripts(machine_context, &mut self.gui_state);
+
+                        // Lag is in machine time, so it is counted in frames of the machine
+                        let frames_to_skip = {
+                            let global_config = self.global_config.read().unwrap();
+                            global_config.frame_skip.frames_to_skip(
+                                global_config.max_frame_skip,
+                                machine_context.executor.lag(),
+                                machine_context
+                                    .frame_time(self.framerate_tracker.average_framerate()),
+                            )
+                        };
+
+                        if frames_to_skip != 0 {
+                            for display_component in &machine_context.machine.display_components {
+                                display_component
+                                    .lock()
+                                    .unwrap()
+                                    .skip_frames(frames_to_skip);
+                            }
+                        }
+
+                        if frames_to_skip != self.frames_skipped {
+                            self.frames_skipped = frames_to_skip;
+                            show_frame_skip_indicator(&window_context.window, frames_to_skip);
+                        }
+                    }
                 }
             }
             _ => {}
         }
     }
 
-    fn about_to_wait(&mut self, _event_loop: &ActiveEventLoop) {
+    fn device_event(
+        &mut self,
+        _event_loop: &ActiveEventLoop,
+        _device_id: DeviceId,
+        event: DeviceEvent,
+    ) {
+        // Raw motion keeps coming while the cursor is locked in place, unlike cursor positions
+        if let DeviceEvent::MouseMotion { delta } = event {
+            if self.pointer_captured {
+                self.gamepad_manager.insert_pointer_motion(delta);
+            }
+        }
+    }
+
+    fn about_to_wait(&mut self, event_loop: &ActiveEventLoop) {
+        if self.rendering_failure.is_some() {
+            return;
+        }
+
+        self.open_pending_auxiliary_windows(event_loop);
+        self.apply_config_changes();
+        self.autosaver.poll();
+        self.poll_import();
+
+        if self.pointer_captured && self.is_gui_active() {
+            self.set_pointer_capture(false);
+        }
+
+        let hotplug_events = self.gamepad_manager.refresh_gamepad_inputs();
+        if !hotplug_events.is_empty() {
+            for hotplug_event in hotplug_events {
+                let message = match hotplug_event {
+                    GamepadHotplugEvent::Connected { name } => format!("{} connected", name),
+                    GamepadHotplugEvent::Disconnected { name } => format!("{} disconnected", name),
+                };
+
+                tracing::info!("{}", message);
+                self.gui_state.notify(message);
+            }
+
+            self.gui_state
+                .set_connected_gamepads(self.gamepad_manager.connected_gamepads());
+        }
+
+        self.update_idle_pause();
+        self.apply_gdb_requests();
+        self.poll_machine_load();
+
+        if let Some(MachineContextState::Running { machine_context }) =
+            self.machine_context_state.as_ref()
+        {
+            for (index, (name, disassembler)) in
+                machine_context.machine.disassemblers.iter().enumerate()
+            {
+                let Some(stop) = disassembler.take_stop() else {
+                    continue;
+                };
+
+                self.debugger_paused = true;
+                if let Some(gdb_server) = &machine_context.gdb_server {
+                    gdb_server.report_stop(match stop {
+                        ProcessorStop::Breakpoint(_) => GdbStop::Breakpoint(index),
+                        ProcessorStop::Fault(_) => GdbStop::Fault(index),
+                    });
+                }
+                self.gui_state.notify(match stop {
+                    ProcessorStop::Breakpoint(address) => format!(
+                        "{} hit a breakpoint at {}",
+                        name,
+                        machine_context.symbol_table.format_address(address)
+                    ),
+                    ProcessorStop::Fault(fault) => format!(
+                        "{} faulted at {}: {}",
+                        name,
+                        machine_context.symbol_table.format_address(fault.address),
+                        fault.message
+                    ),
+                });
+            }
+
+            if let Some(report) = machine_context
+                .watchdog
+                .as_ref()
+                .and_then(|watchdog| watchdog.take_reports().pop())
+            {
+                self.gui_state.report_stall(report);
+            }
+        }
+
+        for auxiliary_window in self.auxiliary_windows.values() {
+            auxiliary_window.windowing_context.window.request_redraw();
+        }
+
+        if self.global_config.read().unwrap().battery_saver && self.is_gui_active() {
+            let next_repaint = self.last_gui_repaint + BATTERY_SAVER_GUI_REPAINT_INTERVAL;
+
+            if Instant::now() < next_repaint {
+                event_loop.set_control_flow(ControlFlow::WaitUntil(next_repaint));
+                return;
+            }
+        }
+
+        event_loop.set_control_flow(ControlFlow::Poll);
         self.windowing_context
             .as_mut()
             .unwrap()
             .window
             .request_redraw();
     }
+
+    // Closing the window and quitting from the menu both end up here, while the windows are all still open
+    fn exiting(&mut self, _event_loop: &ActiveEventLoop) {
+        self.store_session();
+        self.stop_machine();
+    }
 }
 
 impl<E: Executor, R: RenderingBackend> Drop for DesktopRuntime<E, R> {
+    /// Only has something to do if the event loop never got to exit, like when unwinding
     fn drop(&mut self) {
-        // Prevents a segfault
+        // The displays of the machine hold resources of the rendering backend
+        if let Some(MachineContextState::Running { machine_context }) =
+            self.machine_context_state.take()
+        {
+            machine_context.shutdown();
+        }
+
+        // The rendering backends go before the windows they draw to, rather than in field order
+        self.auxiliary_windows.clear();
         self.windowing_context = None;
     }
 }
 
-pub fn launch_gui<R: RenderingBackend>(
+fn toggle_audio_capture(audio_context: Option<&CpalContext>, per_component: bool) {
+    let Some(audio_context) = audio_context else {
+        tracing::warn!("Cannot capture audio without a audio output device");
+        return;
+    };
+
+    if audio_context.is_capturing() {
+        if let Err(error) = audio_context.stop_capture() {
+            tracing::error!("Failed to finish audio capture: {}", error);
+        }
+
+        return;
+    }
+
+    let _ = create_dir_all(AUDIO_CAPTURE_DIRECTORY.deref());
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    let path = AUDIO_CAPTURE_DIRECTORY.join(format!("{}.wav", timestamp));
+
+    match audio_context.start_capture(&path, per_component) {
+        Ok(()) => tracing::info!("Capturing audio to {}", path.display()),
+        Err(error) => tracing::error!("Failed to start audio capture: {}", error),
+    }
+}
+
+fn dump_framebuffers<R: RenderingBackend + 'static>(
+    display_components: &[Arc<Mutex<dyn DisplayComponent<R>>>],
+    extension: &str,
+) {
+    let _ = create_dir_all(FRAMEBUFFER_DUMP_DIRECTORY.deref());
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+
+    for (index, display_component) in display_components.iter().enumerate() {
+        let Some(buffer) = display_component.lock().unwrap().dump_display_data() else {
+            tracing::warn!(
+                "Display component {} cannot dump its framebuffer with this rendering backend",
+                index
+            );
+            continue;
+        };
+
+        let path =
+            FRAMEBUFFER_DUMP_DIRECTORY.join(format!("{}-{}.{}", timestamp, index, extension));
+
+        match save_framebuffer(&buffer, &path) {
+            Ok(()) => tracing::info!("Dumped framebuffer to {}", path.display()),
+            Err(error) => tracing::error!("Failed to dump framebuffer: {}", error),
+        }
+    }
+}
+
+/// Loads a image into the first display component that will take it
+fn load_framebuffers<R: RenderingBackend + 'static>(
+    display_components: &[Arc<Mutex<dyn DisplayComponent<R>>>],
+    path: &Path,
+) {
+    let buffer = match load_framebuffer(path) {
+        Ok(buffer) => buffer,
+        Err(error) => {
+            tracing::error!("Failed to load {}: {}", path.display(), error);
+            return;
+        }
+    };
+
+    for display_component in display_components {
+        if display_component
+            .lock()
+            .unwrap()
+            .import_display_data(buffer.clone())
+        {
+            tracing::info!("Loaded framebuffer from {}", path.display());
+            return;
+        }
+    }
+
+    tracing::warn!("No display component accepted {}", path.display());
+}
+
+/// TODO: This lives in the title bar until the machine view can draw egui on top of itself
+fn show_frame_skip_indicator(window: &Window, frames_skipped: u32) {
+    if frames_skipped == 0 {
+        window.set_title("MultiEMU");
+    } else {
+        window.set_title(&format!("MultiEMU (Skipping {} Frames)", frames_skipped));
+    }
+}
+
+fn open_in_file_manager(path: &Path) {
+    #[cfg(target_os = "windows")]
+    let program = "explorer";
+    #[cfg(target_os = "macos")]
+    let program = "open";
+    #[cfg(not(any(target_os = "windows", target_os = "macos")))]
+    let program = "xdg-open";
+
+    if let Err(error) = std::process::Command::new(program).arg(path).spawn() {
+        tracing::error!(
+            "Could not open {} with {}: {}",
+            path.display(),
+            program,
+            error
+        );
+    }
+}
+
+fn save_snapshot<E: Executor, R: RenderingBackend + 'static>(
+    machine_context: &mut MachineContext<E, R>,
+    gui_state: &mut GuiRuntime,
+    slot: u8,
+) {
+    let path = machine_context.snapshot_manager.slot_path(slot);
+    // Displays that can't be read back with this rendering backend are left out
+    let buffers: Vec<_> = machine_context
+        .machine
+        .display_components
+        .iter()
+        .filter_map(|display_component| display_component.lock().unwrap().dump_display_data())
+        .collect();
+
+    match machine_context.snapshot_manager.save(
+        &mut machine_context.executor,
+        &path,
+        buffers.first(),
+    ) {
+        Ok(()) => {
+            capture_screenshots(buffers, machine_context.game, &format!("slot{}", slot));
+            gui_state.snapshot_saved(machine_context.game);
+            gui_state.notify(format!("Saved state to slot {}", slot));
+        }
+        Err(error) => {
+            tracing::error!("Failed to save state to {}: {}", path.display(), error);
+            gui_state.notify(format!("Failed to save state: {}", error));
+        }
+    }
+}
+
+fn load_snapshot<E: Executor, R: RenderingBackend>(
+    machine_context: &mut MachineContext<E, R>,
+    gui_state: &mut GuiRuntime,
+    slot: u8,
+) {
+    let path = machine_context.snapshot_manager.slot_path(slot);
+
+    match machine_context
+        .snapshot_manager
+        .load(&mut machine_context.executor, &path)
+    {
+        Ok(()) => gui_state.notify(format!("Loaded state from slot {}", slot)),
+        Err(error) => {
+            tracing::error!("Failed to load state from {}: {}", path.display(), error);
+            gui_state.notify(format!("Failed to load state: {}", error));
+        }
+    }
+}
+
+/// Loads the scripts by name, leaving out and telling about the ones that don't load
+fn load_scripts<R: RenderingBackend>(
+    machine: &Machine<R>,
+    names: &[String],
+    gui_state: &mut GuiRuntime,
+) -> ScriptHost {
+    let mut scripts = ScriptHost::new(
+        machine.memory_translation_table.clone(),
+        machine.controllers.clone(),
+    );
+
+    for name in names {
+        if let Err(error) = scripts.load(name) {
+            tracing::error!("Failed to load script {}: {}", name, error);
+            gui_state.notify(format!("Failed to load script {}: {}", name, error));
+        }
+    }
+
+    scripts
+}
+
+/// Patches memory with the enabled cheats, telling about the codes that don't fit the system
+fn apply_cheats<R: RenderingBackend>(
+    machine: &Machine<R>,
+    system: GameSystem,
+    cheats: &[Cheat],
+    gui_state: &mut GuiRuntime,
+) {
+    let (read_patches, errors) = read_patches(cheats, system);
+
+    for error in errors {
+        tracing::warn!("Skipping cheat: {}", error);
+        gui_state.notify(format!("Skipping cheat: {}", error));
+    }
+
+    machine
+        .memory_translation_table
+        .set_read_patches(read_patches);
+}
+
+/// Lets the scripts see the frame that just ran and does what they asked for
+fn run_scripts<E: Executor, R: RenderingBackend + 'static>(
+    machine_context: &mut MachineContext<E, R>,
+    gui_state: &mut GuiRuntime,
+) {
+    for request in machine_context.scripts.run_frame() {
+        match request {
+            ScriptRequest::SaveSnapshot(slot) => save_snapshot(machine_context, gui_state, slot),
+            ScriptRequest::LoadSnapshot(slot) => load_snapshot(machine_context, gui_state, slot),
+            ScriptRequest::Notify(message) => gui_state.notify(message),
+        }
+    }
+}
+
+/// Runs the GUI on the event loop until it exits, giving back why the rendering backend could not start if it couldn't
+///
+/// The warning shows up in the menu from the start
+fn run_gui<R: RenderingBackend + 'static>(
+    event_loop: &mut EventLoop<()>,
     rom_manager: Arc<RomManager>,
     initial_gui_state: InitialGuiState,
     global_config: Arc<RwLock<GlobalConfig>>,
-) where
+    warning: Option<String>,
+) -> Result<(), Box<dyn Error>>
+where
     DesktopRuntime<SingleThreadedExecutor, R>: ApplicationHandler,
     // TODO: find some better way to express these bounds
     Chip8Display: DisplayComponent<R>,
 {
     let mut winit_state = match initial_gui_state {
         InitialGuiState::MainMenu => {
-            DesktopRuntime::<SingleThreadedExecutor, R>::new(rom_manager, global_config)
+            let restore_session = global_config.read().unwrap().restore_session;
+            let mut runtime =
+                DesktopRuntime::<SingleThreadedExecutor, R>::new(rom_manager, global_config);
+
+            if restore_session {
+                runtime.restore_session();
+            }
+
+            runtime
         }
         InitialGuiState::OpenGame {
             user_specified_roms,
             game_system,
+            audio_capture,
+            gdb_port,
         } => DesktopRuntime::<SingleThreadedExecutor, R>::new_with_game(
             rom_manager,
             user_specified_roms,
             Some(game_system),
+            audio_capture,
+            gdb_port,
             global_config,
         ),
     };
 
-    let event_loop = EventLoop::new().unwrap();
-    event_loop.run_app(&mut winit_state).unwrap();
+    if let Some(warning) = warning {
+        winit_state.gui_state.report_rendering_fallback(warning);
+    }
+
+    // On demand so the event loop, which can only be made once, is still around for a fallback
+    event_loop.run_app_on_demand(&mut winit_state).unwrap();
+
+    winit_state.rendering_failure.take().map_or(Ok(()), Err)
+}
+
+/// Launches with whichever backend the config selects, see [GlobalConfig::selected_rendering_backend]
+///
+/// Software rendering takes over if a hardware accelerated backend can't start, like without any vulkan driver
+pub fn launch_gui_with_selected_backend(
+    rom_manager: Arc<RomManager>,
+    initial_gui_state: InitialGuiState,
+    global_config: Arc<RwLock<GlobalConfig>>,
+) {
+    let rendering_backend = global_config.read().unwrap().selected_rendering_backend();
+    tracing::info!("Using the {} rendering backend", rendering_backend);
+
+    let mut event_loop = EventLoop::new().unwrap();
+
+    // Nothing when software rendering runs from the start
+    let result: Option<Result<(), Box<dyn Error>>> = match rendering_backend {
+        #[cfg(vulkan)]
+        RenderingBackendKind::Vulkan => Some(run_gui::<display::vulkan::VulkanRendering>(
+            &mut event_loop,
+            rom_manager.clone(),
+            initial_gui_state.clone(),
+            global_config.clone(),
+            None,
+        )),
+        #[cfg(wgpu)]
+        RenderingBackendKind::Wgpu => Some(run_gui::<display::wgpu::WgpuRendering>(
+            &mut event_loop,
+            rom_manager.clone(),
+            initial_gui_state.clone(),
+            global_config.clone(),
+            None,
+        )),
+        _ => {
+            if !matches!(
+                rendering_backend,
+                RenderingBackendKind::Software | RenderingBackendKind::Auto
+            ) {
+                tracing::warn!(
+                    "This build has no {} rendering backend, using software rendering",
+                    rendering_backend
+                );
+            }
+
+            None
+        }
+    };
+
+    let notice = match result {
+        Some(Ok(())) => return,
+        Some(Err(error)) => {
+            tracing::warn!("Falling back to software rendering");
+
+            Some(format!(
+                "{} rendering could not start, software rendering is used instead: {}",
+                rendering_backend, error
+            ))
+        }
+        None => None,
+    };
+
+    run_gui::<display::software::SoftwareRendering>(
+        &mut event_loop,
+        rom_manager,
+        initial_gui_state,
+        global_config,
+        notice,
+    )
+    .expect("Software rendering could not start");
 }
@@ -0,0 +1,15 @@
+use notify_rust::Notification;
+
+/// Shows a notification through the desktop, from a thread of its own as the notification daemon can be slow to answer
+pub fn send(message: String) {
+    std::thread::spawn(move || {
+        if let Err(error) = Notification::new()
+            .appname("MultiEMU")
+            .summary("MultiEMU")
+            .body(&message)
+            .show()
+        {
+            tracing::warn!("Could not show a desktop notification: {}", error);
+        }
+    });
+}
@@ -0,0 +1,77 @@
+use super::AuxiliaryWindowKind;
+use crate::{
+    atomic_write::write_atomically,
+    rom::{GameSystem, RomId},
+};
+use serde::{Deserialize, Serialize};
+use std::{error::Error, fs::File, path::Path};
+use winit::{
+    dpi::{PhysicalPosition, PhysicalSize},
+    window::{Window, WindowAttributes},
+};
+
+/// Where a window was and how big, in physical pixels
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct WindowGeometry {
+    pub size: [u32; 2],
+    /// Not every platform tells windows where they are
+    pub position: Option<[i32; 2]>,
+}
+
+impl WindowGeometry {
+    pub fn of(window: &Window) -> Self {
+        Self {
+            size: window.inner_size().into(),
+            position: window.outer_position().ok().map(Into::into),
+        }
+    }
+
+    pub fn apply(self, window_attributes: WindowAttributes) -> WindowAttributes {
+        let window_attributes =
+            window_attributes.with_inner_size(PhysicalSize::new(self.size[0], self.size[1]));
+
+        match self.position {
+            Some([x, y]) => window_attributes.with_position(PhysicalPosition::new(x, y)),
+            None => window_attributes,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct SessionGame {
+    pub roms: Vec<RomId>,
+    pub system: GameSystem,
+}
+
+/// What was open when the GUI last shut down cleanly
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct Session {
+    pub window: Option<WindowGeometry>,
+    /// Resumed from the session snapshot slot
+    pub game: Option<SessionGame>,
+    pub auxiliary_windows: Vec<(AuxiliaryWindowKind, WindowGeometry)>,
+    /// What egui remembers of the debugger window, like which of its sections were open
+    pub debugger_memory: Option<egui::Memory>,
+}
+
+impl Session {
+    /// Reads the session and removes it, so a game that crashes on the way back in can't do so on every launch
+    pub fn take(path: &Path) -> Result<Option<Self>, Box<dyn Error>> {
+        if !path.exists() {
+            return Ok(None);
+        }
+
+        let session = ron::de::from_reader(File::open(path)?);
+        std::fs::remove_file(path)?;
+
+        Ok(Some(session?))
+    }
+
+    pub fn store(&self, path: &Path) -> Result<(), Box<dyn Error>> {
+        write_atomically(path, |file| {
+            ron::ser::to_writer(file, self)?;
+
+            Ok(())
+        })
+    }
+}
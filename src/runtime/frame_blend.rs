@@ -0,0 +1,121 @@
+use crate::runtime::simd::simd_dispatch;
+use nalgebra::DMatrix;
+use palette::{LinSrgba, Srgba};
+use std::sync::LazyLock;
+
+/// [average] of every pair of channel values, as color then alpha, indexed by the pair as a big endian u16
+///
+/// Going through linear light per pixel is far too slow for every frame
+static AVERAGES: LazyLock<Box<[[u8; 2]]>> = LazyLock::new(|| {
+    (0..=u16::MAX)
+        .map(|pair| {
+            let [first, second] = pair.to_be_bytes();
+            let average = average(
+                Srgba::new(first, first, first, first),
+                Srgba::new(second, second, second, second),
+            );
+
+            [average.red, average.alpha]
+        })
+        .collect()
+});
+
+/// Averages every frame with the one before it on the CPU, for the software backends
+///
+/// Machines usually only redraw part of the screen each frame, so pixels that didn't change since the last frame are
+/// carried over instead of being blended again
+#[derive(Debug, Default)]
+pub struct FrameBlender {
+    previous: DMatrix<Srgba<u8>>,
+    blended: DMatrix<Srgba<u8>>,
+}
+
+impl FrameBlender {
+    /// Forgets the last frame, so turning blending back on later doesn't blend with something long gone
+    #[cfg(feature = "frontend")]
+    pub fn reset(&mut self) {
+        self.previous = DMatrix::default();
+        self.blended = DMatrix::default();
+    }
+
+    pub fn blend(&mut self, frame: &DMatrix<Srgba<u8>>) -> &DMatrix<Srgba<u8>> {
+        // Nothing to blend with yet
+        if self.previous.shape() != frame.shape() {
+            self.previous = frame.clone();
+            self.blended = frame.clone();
+
+            return &self.blended;
+        }
+
+        blend_changed(
+            frame.as_slice(),
+            self.previous.as_mut_slice(),
+            self.blended.as_mut_slice(),
+            &AVERAGES,
+        );
+
+        &self.blended
+    }
+}
+
+simd_dispatch! {
+    fn blend_changed(
+        frame: &[Srgba<u8>],
+        previous: &mut [Srgba<u8>],
+        blended: &mut [Srgba<u8>],
+        averages: &[[u8; 2]],
+    ) {
+        for ((current, previous), blended) in frame.iter().zip(previous).zip(blended) {
+            let lookup = |first: u8, second: u8| averages[u16::from_be_bytes([first, second]) as usize];
+
+            // Settles on the still image once the pixel stops changing
+            *blended = if current == previous {
+                *current
+            } else {
+                Srgba::new(
+                    lookup(current.red, previous.red)[0],
+                    lookup(current.green, previous.green)[0],
+                    lookup(current.blue, previous.blue)[0],
+                    lookup(current.alpha, previous.alpha)[1],
+                )
+            };
+            *previous = *current;
+        }
+    }
+}
+
+/// Mixed in linear light like the light of the two frames would be, matching the hardware backend
+fn average(first: Srgba<u8>, second: Srgba<u8>) -> Srgba<u8> {
+    let first: LinSrgba<f32> = first.into_format::<f32, f32>().into_linear();
+    let second: LinSrgba<f32> = second.into_format::<f32, f32>().into_linear();
+    let average: Srgba<f32> = Srgba::from_linear((first + second) * 0.5);
+
+    average.into_format()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn blends_changed_pixels_once() {
+        let black = Srgba::new(0, 0, 0, 0xff);
+        let white = Srgba::new(0xff, 0xff, 0xff, 0xff);
+        let mut frame_blender = FrameBlender::default();
+
+        assert_eq!(
+            frame_blender.blend(&DMatrix::from_element(2, 1, black))[(0, 0)],
+            black
+        );
+
+        let blended = frame_blender.blend(&DMatrix::from_row_slice(2, 1, &[white, black]));
+        // Half the light, which is brighter than half the sRGB value
+        assert!(blended[(0, 0)].red > 0x80 && blended[(0, 0)].red < 0xff);
+        assert_eq!(blended[(0, 0)], average(white, black));
+        assert_eq!(blended[(1, 0)], black);
+
+        // Holding still shows the frame as it is
+        let blended = frame_blender.blend(&DMatrix::from_row_slice(2, 1, &[white, black]));
+        assert_eq!(blended[(0, 0)], white);
+    }
+}
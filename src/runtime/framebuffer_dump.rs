@@ -0,0 +1,58 @@
+use crate::component::display::color::Pixel;
+use image::{ImageResult, RgbaImage};
+use nalgebra::DMatrix;
+use std::path::Path;
+
+/// Writes a display buffer to a image, with the format guessed from the extension (png and ppm are supported)
+pub fn save_framebuffer(buffer: &DMatrix<Pixel>, path: &Path) -> ImageResult<()> {
+    let image = RgbaImage::from_fn(buffer.nrows() as u32, buffer.ncols() as u32, |x, y| {
+        let pixel = buffer[(x as usize, y as usize)];
+        image::Rgba([pixel.red, pixel.green, pixel.blue, pixel.alpha])
+    });
+
+    // ppm has no alpha channel
+    if path.extension().is_some_and(|extension| extension == "ppm") {
+        return image::DynamicImage::ImageRgba8(image).to_rgb8().save(path);
+    }
+
+    image.save(path)
+}
+
+pub fn load_framebuffer(path: &Path) -> ImageResult<DMatrix<Pixel>> {
+    let image = image::open(path)?.into_rgba8();
+
+    Ok(DMatrix::from_fn(
+        image.width() as usize,
+        image.height() as usize,
+        |x, y| {
+            let [red, green, blue, alpha] = image.get_pixel(x as u32, y as u32).0;
+            Pixel::new(red, green, blue, alpha)
+        },
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn framebuffer_roundtrip() {
+        let buffer = DMatrix::from_fn(64, 32, |x, y| {
+            if (x + y) % 2 == 0 {
+                Pixel::new(255, 255, 255, 255)
+            } else {
+                Pixel::new(0, 0, 0, 255)
+            }
+        });
+
+        for extension in ["png", "ppm"] {
+            let path =
+                std::env::temp_dir().join(format!("multiemu_framebuffer_test.{}", extension));
+
+            save_framebuffer(&buffer, &path).unwrap();
+            assert_eq!(load_framebuffer(&path).unwrap(), buffer);
+
+            let _ = std::fs::remove_file(path);
+        }
+    }
+}
@@ -0,0 +1,40 @@
+//! The rendering backend of the core build, which has no window to draw to. Components still render to their buffers,
+//! so tests can look at what they drew
+
+use super::{RenderingBackend, RenderingBackendState};
+use crate::{
+    component::display::{color::DisplayBuffer, DisplayComponent},
+    config::GlobalConfig,
+};
+use std::sync::{Arc, Mutex, RwLock};
+
+#[derive(Debug, Default)]
+pub struct SoftwareState;
+
+impl SoftwareState {
+    /// Like the software state of the desktop, which has the same constructor for running machines without a window
+    pub fn headless(_global_config: Arc<RwLock<GlobalConfig>>) -> Self {
+        Self
+    }
+}
+
+impl RenderingBackendState for SoftwareState {
+    type RenderingBackend = SoftwareRendering;
+
+    fn initialize_components(
+        &mut self,
+        components: &[Arc<Mutex<dyn DisplayComponent<Self::RenderingBackend>>>],
+    ) {
+        for component in components.iter() {
+            component.lock().unwrap().initialize_display(());
+        }
+    }
+}
+
+pub struct SoftwareRendering;
+
+impl RenderingBackend for SoftwareRendering {
+    type ComponentInitializationData = ();
+    type ComponentDisplayBuffer = DisplayBuffer;
+    type RuntimeState = SoftwareState;
+}
@@ -1,37 +1,65 @@
+#[cfg(any(feature = "frontend", test))]
+pub mod audio_capture;
+#[cfg(feature = "frontend")]
+pub mod autosave;
+pub mod backend_benchmark;
+pub mod color_filter;
 #[cfg(desktop)]
 pub mod desktop;
+#[cfg(any(feature = "frontend", test))]
+pub mod frame_blend;
+#[cfg(any(feature = "frontend", test))]
+pub mod framebuffer_dump;
+#[cfg(feature = "core-only")]
+pub mod headless;
 #[cfg(nintendo_3ds)]
 pub mod nintendo_3ds;
+#[cfg(any(feature = "frontend", test))]
+pub mod scale;
+#[cfg(feature = "frontend")]
+pub mod screenshot;
+pub mod simd;
+#[cfg(feature = "frontend")]
 pub mod timing;
 
+#[cfg(feature = "frontend")]
 mod software_egui_render;
 
-use crate::{
-    component::display::DisplayComponent,
-    rom::{GameSystem, RomId},
-};
+use crate::component::display::DisplayComponent;
+#[cfg(feature = "frontend")]
+use crate::rom::{GameSystem, RomId};
+#[cfg(feature = "frontend")]
 use egui::FullOutput;
+#[cfg(feature = "frontend")]
+use std::path::PathBuf;
 use std::sync::{Arc, Mutex};
 
 #[cfg(desktop)]
 pub use desktop::display::software::SoftwareRendering;
-#[cfg(desktop)]
-pub use desktop::launch_gui;
 
 #[cfg(nintendo_3ds)]
 pub use nintendo_3ds::display::software::SoftwareRendering;
 #[cfg(nintendo_3ds)]
 pub use nintendo_3ds::launch_gui;
 
-pub trait RenderingBackend {
+#[cfg(feature = "core-only")]
+pub use headless::SoftwareRendering;
+
+pub trait RenderingBackend: 'static {
     /// Data needed for a component to initialize itself for rendering
     type ComponentInitializationData: 'static;
     /// Intermediate image buffer to be shared with the runtime, typically arc wrapped
     type ComponentDisplayBuffer;
 
     type RuntimeState: RenderingBackendState<RenderingBackend = Self>;
+
+    /// If machine displays are drawn through the configured [color_filter::ColorFilter], the option is greyed out
+    /// otherwise
+    #[cfg(feature = "frontend")]
+    const COLOR_FILTER: bool;
 }
 
+#[cfg(feature = "frontend")]
 #[allow(clippy::large_enum_variant)]
 pub enum RedrawKind<'a, R: RenderingBackend> {
     Machine(&'a [Arc<Mutex<dyn DisplayComponent<R>>>]),
@@ -44,8 +72,14 @@ pub enum RedrawKind<'a, R: RenderingBackend> {
 pub trait RenderingBackendState: Sized {
     type RenderingBackend: RenderingBackend;
 
+    #[cfg(feature = "frontend")]
     fn surface_resized(&mut self);
 
+    /// Vsync or another setting baked into the presentation surface changed
+    #[cfg(feature = "frontend")]
+    fn surface_config_changed(&mut self) {}
+
+    #[cfg(feature = "frontend")]
     fn redraw(&mut self, kind: RedrawKind<Self::RenderingBackend>);
 
     fn initialize_components(
@@ -54,16 +88,16 @@ pub trait RenderingBackendState: Sized {
     );
 }
 
+#[cfg(feature = "frontend")]
+#[derive(Clone, Debug)]
 pub enum InitialGuiState {
     MainMenu,
     OpenGame {
         user_specified_roms: Vec<RomId>,
         game_system: GameSystem,
+        /// Record the audio output to this WAV file from the start
+        audio_capture: Option<PathBuf>,
+        /// Serve the GDB remote protocol on this localhost port while the game runs
+        gdb_port: Option<u16>,
     },
 }
-
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
-enum RuntimeState {
-    Menu,
-    PlayingGame,
-}
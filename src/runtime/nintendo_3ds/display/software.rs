@@ -1,7 +1,8 @@
 use super::Nintendo3dsRenderBackendState;
 use crate::runtime::{RenderingBackend, RenderingBackendState};
 use crate::{
-    component::display::DisplayComponent, runtime::software_egui_render::SoftwareEguiRenderer,
+    component::display::{color::DisplayBuffer, DisplayComponent},
+    runtime::software_egui_render::SoftwareEguiRenderer,
 };
 use ctru::{
     prelude::Gfx,
@@ -105,6 +106,8 @@ pub struct SoftwareRendering;
 
 impl RenderingBackend for SoftwareRendering {
     type ComponentInitializationData = ();
-    type ComponentDisplayBuffer = DMatrix<Srgba<u8>>;
+    type ComponentDisplayBuffer = DisplayBuffer;
     type RuntimeState = SoftwareState;
+
+    const COLOR_FILTER: bool = false;
 }
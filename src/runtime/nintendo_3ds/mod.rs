@@ -13,13 +13,18 @@ use ctru::{
 use display::Nintendo3dsRenderBackendState;
 use egui::{FullOutput, RawInput};
 use nalgebra::Vector2;
+use num::rational::Ratio;
 use std::{
     rc::Rc,
     sync::{Arc, Mutex, RwLock},
+    time::Duration,
 };
 
 pub mod display;
 
+/// Time between vblanks of the top screen, which runs at about 59.83hz
+const VBLANK_PERIOD: Duration = Duration::from_micros(16_714);
+
 /// Stuff needed for a running emulation
 struct MachineContext<E: Executor, R: RenderingBackend> {
     executor: E,
@@ -34,6 +39,7 @@ pub struct Nintendo3dsRuntime<E: Executor, R: RenderingBackend> {
     egui_context: egui::Context,
     gui_state: GuiRuntime,
     display_runtime_state: R::RuntimeState,
+    global_config: Arc<RwLock<GlobalConfig>>,
 }
 
 impl<E: Executor, R: RenderingBackend> Nintendo3dsRuntime<E, R>
@@ -46,14 +52,17 @@ where
         let (display_runtime_state, gfx) = R::RuntimeState::new();
 
         let egui_context = egui::Context::default();
+        let mut gui_state = GuiRuntime::new(global_config.clone());
+        gui_state.set_color_filter_supported(R::COLOR_FILTER);
 
         Self {
             applet_service: apt,
             graphics_service: gfx,
             machine_context: None,
-            gui_state: GuiRuntime::new(global_config.clone()),
+            gui_state,
             egui_context,
             display_runtime_state,
+            global_config,
         }
     }
 
@@ -74,13 +83,37 @@ where
             };
 
             let full_output = self.egui_context.run(input, |context| {
-                self.gui_state.run_menu(context);
+                self.gui_state.run_menu(context, None);
             });
 
             //console.flush_buffers();
             self.display_runtime_state
                 .redraw_egui(&self.egui_context, full_output);
             self.graphics_service.wait_for_vblank();
+
+            let (battery_saver, speed_in_menu) = {
+                let global_config = self.global_config.read().unwrap();
+
+                (global_config.battery_saver, global_config.speed_in_menu())
+            };
+
+            // Halves the menu refresh rate
+            if battery_saver {
+                self.graphics_service.wait_for_vblank();
+            }
+
+            // The menu is all there is to see, the machine keeps going behind it if the config asks for that
+            if let (Some(machine_context), Some(speed)) = (&mut self.machine_context, speed_in_menu)
+            {
+                machine_context
+                    .executor
+                    .set_speed(Ratio::new(speed as u32, 100));
+                machine_context.executor.run(if battery_saver {
+                    VBLANK_PERIOD * 2
+                } else {
+                    VBLANK_PERIOD
+                });
+            }
         }
     }
 }
@@ -95,5 +128,6 @@ pub fn launch_gui<R: RenderingBackend>(
     R::RuntimeState: Nintendo3dsRenderBackendState,
 {
     let mut runtime = Nintendo3dsRuntime::<SingleThreadedExecutor, R>::new(global_config);
+    runtime.gui_state.set_rom_manager(rom_manager);
     runtime.run();
 }
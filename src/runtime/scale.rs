@@ -0,0 +1,247 @@
+use crate::{component::display::color::Pixel, runtime::simd::simd_dispatch};
+use nalgebra::DMatrix;
+use palette::{LinSrgb, Srgb};
+use std::{ops::Range, sync::LazyLock};
+
+/// Precision linear light is rounded to before it is encoded back to sRGB, finer than a sRGB step everywhere but the
+/// very darkest shades
+const ENCODE_STEPS: usize = 4096;
+
+static DECODE: LazyLock<[f32; 256]> = LazyLock::new(|| {
+    std::array::from_fn(|value| {
+        Srgb::new(value as u8, 0, 0)
+            .into_format::<f32>()
+            .into_linear::<f32>()
+            .red
+    })
+});
+
+static ENCODE: LazyLock<Box<[u8]>> = LazyLock::new(|| {
+    (0..ENCODE_STEPS)
+        .map(|step| {
+            let value = step as f32 / (ENCODE_STEPS - 1) as f32;
+            Srgb::<f32>::from_linear(LinSrgb::new(value, 0.0, 0.0))
+                .into_format::<u8>()
+                .red
+        })
+        .collect()
+});
+
+/// Nearest neighbour scaling of a display onto part of the window
+///
+/// Works a row at a time, so palette based displays only ever need one row expanded into pixels
+pub struct NearestScaler {
+    /// The source column of each window pixel in a row, starting at the first column
+    source_columns: Vec<u32>,
+    first_column: usize,
+    /// The window rows each source row covers
+    rows: Vec<Range<usize>>,
+    window_width: usize,
+}
+
+impl NearestScaler {
+    /// Source and window are width then height, offset and size are the area to draw to like [crate::config::AspectMode::fit] gives
+    pub fn new(source: [usize; 2], window: [usize; 2], offset: [u32; 2], size: [u32; 2]) -> Self {
+        let covered = |axis: usize| -> Vec<Range<usize>> {
+            let scaling = size[axis] as f32 / source[axis] as f32;
+            let edge = |position: usize| {
+                ((position as f32 * scaling + offset[axis] as f32).round() as usize)
+                    .min(window[axis])
+            };
+
+            (0..source[axis])
+                .map(|position| edge(position)..edge(position + 1))
+                .collect()
+        };
+
+        let columns = covered(0);
+
+        Self {
+            first_column: columns.first().map_or(0, |columns| columns.start),
+            source_columns: columns
+                .iter()
+                .enumerate()
+                .flat_map(|(x, columns)| columns.clone().map(move |_| x as u32))
+                .collect(),
+            rows: covered(1),
+            window_width: window[0],
+        }
+    }
+
+    /// Draws the row `y` of the source, which is exactly as wide as the source, to every window row it covers
+    pub fn draw_row(&self, y: usize, source_row: &[Pixel], window: &mut [Pixel]) {
+        let mut rows = self.rows[y].clone();
+        let Some(first_row) = rows.next() else {
+            return;
+        };
+
+        let start = first_row * self.window_width + self.first_column;
+        let drawn = start..start + self.source_columns.len();
+        gather(&mut window[drawn.clone()], source_row, &self.source_columns);
+
+        // The rest are the same, so they are copied rather than scaled again
+        for row in rows {
+            window.copy_within(drawn.clone(), row * self.window_width + self.first_column);
+        }
+    }
+}
+
+/// Bilinear scaling of a whole frame onto part of the window, mixing in linear light like the blit of the vulkan
+/// backend does. Offset and size are like for [NearestScaler::new]
+pub fn scale_bilinear(
+    source: &DMatrix<Pixel>,
+    window: &mut [Pixel],
+    window_size: [usize; 2],
+    offset: [u32; 2],
+    size: [u32; 2],
+) {
+    let (width, height) = source.shape();
+    if width == 0 || height == 0 {
+        return;
+    }
+
+    let linear: Vec<[f32; 4]> = source
+        .iter()
+        .map(|pixel| {
+            [
+                DECODE[pixel.red as usize],
+                DECODE[pixel.green as usize],
+                DECODE[pixel.blue as usize],
+                pixel.alpha as f32 / 255.0,
+            ]
+        })
+        .collect();
+
+    let (columns, column_samples) = samples(width, offset[0], size[0], window_size[0]);
+    let (rows, row_samples) = samples(height, offset[1], size[1], window_size[1]);
+
+    for (y, (top, bottom, vertical)) in rows.zip(row_samples) {
+        let row = |index: u32| &linear[index as usize * width..(index as usize + 1) * width];
+        let start = y * window_size[0] + columns.start;
+
+        blend_row(
+            &mut window[start..start + columns.len()],
+            row(top),
+            row(bottom),
+            &column_samples,
+            vertical,
+            &ENCODE,
+        );
+    }
+}
+
+/// The window positions the source covers on one axis, and for each of them the two source pixels to mix and how
+/// much of the second to take
+fn samples(
+    source_length: usize,
+    offset: u32,
+    size: u32,
+    window_length: usize,
+) -> (Range<usize>, Vec<(u32, u32, f32)>) {
+    let covered =
+        (offset as usize).min(window_length)..((offset + size) as usize).min(window_length);
+    let scaling = source_length as f32 / size as f32;
+    let last = (source_length - 1) as f32;
+
+    let samples = covered
+        .clone()
+        .map(|position| {
+            // Pixel centers line up, and the edges repeat the outermost pixels
+            let source = ((position as f32 + 0.5 - offset as f32) * scaling - 0.5).clamp(0.0, last);
+            let first = source.floor();
+
+            (first as u32, (first + 1.0).min(last) as u32, source - first)
+        })
+        .collect();
+
+    (covered, samples)
+}
+
+simd_dispatch! {
+    fn blend_row(
+        destination: &mut [Pixel],
+        top: &[[f32; 4]],
+        bottom: &[[f32; 4]],
+        columns: &[(u32, u32, f32)],
+        vertical: f32,
+        encode: &[u8],
+    ) {
+        for (destination, (left, right, horizontal)) in destination.iter_mut().zip(columns) {
+            let (left, right) = (*left as usize, *right as usize);
+            let mix = |row: &[[f32; 4]], channel: usize| {
+                row[left][channel] + (row[right][channel] - row[left][channel]) * horizontal
+            };
+            let channel = |channel: usize| {
+                let upper = mix(top, channel);
+                upper + (mix(bottom, channel) - upper) * vertical
+            };
+            let color = |index: usize| {
+                encode[(channel(index) * (ENCODE_STEPS - 1) as f32).round() as usize]
+            };
+
+            *destination = Pixel::new(
+                color(0),
+                color(1),
+                color(2),
+                (channel(3) * 255.0).round() as u8,
+            );
+        }
+    }
+}
+
+simd_dispatch! {
+    fn gather(destination: &mut [Pixel], source: &[Pixel], indices: &[u32]) {
+        for (destination, index) in destination.iter_mut().zip(indices) {
+            *destination = source[*index as usize];
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::component::display::color::BLACK;
+
+    #[test]
+    fn rows_are_scaled_into_the_offset_area() {
+        let red = Pixel::new(0xff, 0, 0, 0xff);
+        let blue = Pixel::new(0, 0, 0xff, 0xff);
+        let mut window = vec![BLACK; 6 * 4];
+
+        // Two by two pixels into a 4x4 area one pixel from the left
+        let scaler = NearestScaler::new([2, 2], [6, 4], [1, 0], [4, 4]);
+        scaler.draw_row(0, &[red, blue], &mut window);
+        scaler.draw_row(1, &[blue, red], &mut window);
+
+        let (b, r, k) = (blue, red, BLACK);
+        #[rustfmt::skip]
+        assert_eq!(window, [
+            k, r, r, b, b, k,
+            k, r, r, b, b, k,
+            k, b, b, r, r, k,
+            k, b, b, r, r, k,
+        ]);
+    }
+
+    #[test]
+    fn bilinear_mixes_in_linear_light() {
+        let white = Pixel::new(0xff, 0xff, 0xff, 0xff);
+        let mut window = vec![BLACK; 4];
+
+        scale_bilinear(
+            &DMatrix::from_row_slice(2, 1, &[BLACK, white]),
+            &mut window,
+            [4, 1],
+            [0, 0],
+            [4, 1],
+        );
+
+        // The edges repeat the outer pixels, the middle ones are a quarter and three quarters of the light of white,
+        // which is brighter than a quarter of the sRGB value
+        assert_eq!(window[0], BLACK);
+        assert_eq!(window[3], white);
+        assert_eq!(window[1].red, 137);
+        assert_eq!(window[2].red, 225);
+        assert!(window.iter().all(|pixel| pixel.alpha == 0xff));
+    }
+}
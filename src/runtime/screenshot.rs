@@ -0,0 +1,91 @@
+use super::framebuffer_dump::save_framebuffer;
+use crate::{component::display::color::Pixel, env::MEDIA_DIRECTORY, rom::RomId};
+use nalgebra::DMatrix;
+use std::{
+    fs::{create_dir_all, read_dir},
+    path::PathBuf,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// Where the screenshots for a game are kept
+pub fn screenshot_directory(game: RomId) -> PathBuf {
+    MEDIA_DIRECTORY.join(game.to_string()).join("screenshots")
+}
+
+/// Saves what every display is showing at its native resolution, named after the time and what caused it
+pub fn capture_screenshots(
+    buffers: impl IntoIterator<Item = DMatrix<Pixel>>,
+    game: RomId,
+    label: &str,
+) -> Vec<PathBuf> {
+    let directory = screenshot_directory(game);
+    if let Err(error) = create_dir_all(&directory) {
+        tracing::error!(
+            "Could not create screenshot directory {}: {}",
+            directory.display(),
+            error
+        );
+        return Vec::new();
+    }
+
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+
+    buffers
+        .into_iter()
+        .enumerate()
+        .filter_map(|(index, buffer)| {
+            let path = directory.join(format!("{}-{}-{}.png", timestamp, label, index));
+
+            match save_framebuffer(&buffer, &path) {
+                Ok(()) => Some(path),
+                Err(error) => {
+                    tracing::error!("Failed to save screenshot {}: {}", path.display(), error);
+                    None
+                }
+            }
+        })
+        .collect()
+}
+
+/// Newest first
+pub fn list_screenshots(game: RomId) -> Vec<PathBuf> {
+    let Ok(entries) = read_dir(screenshot_directory(game)) else {
+        return Vec::new();
+    };
+
+    let mut screenshots: Vec<_> = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().is_some_and(|extension| extension == "png"))
+        .collect();
+    // The timestamp leads the name, so sorting by name sorts by age
+    screenshots.sort_by(|a, b| b.file_name().cmp(&a.file_name()));
+
+    screenshots
+}
+
+/// Every game that has a media directory
+pub fn games_with_media() -> Vec<RomId> {
+    let Ok(entries) = read_dir(MEDIA_DIRECTORY.as_path()) else {
+        return Vec::new();
+    };
+
+    let mut games: Vec<RomId> = entries
+        .filter_map(|entry| {
+            let name = entry.ok()?.file_name();
+            let name = name.to_str()?;
+
+            // Parsing panics on hex of the wrong length
+            if name.len() != 40 {
+                return None;
+            }
+
+            name.parse().ok()
+        })
+        .collect();
+    games.sort();
+
+    games
+}
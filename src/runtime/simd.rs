@@ -0,0 +1,117 @@
+//! Picks the fastest version of the hot loops the host CPU can run, so one build is quick on old and new machines alike
+//!
+//! The loops are written once as plain code and compiled again for each instruction set extension, which the compiler
+//! then vectorizes for. Which version runs is decided once at startup
+
+use std::{fmt::Display, sync::LazyLock};
+
+/// The loops that are dispatched through [simd_dispatch], for diagnostics
+pub const DISPATCHED_PATHS: [&str; 3] = ["software scaler", "audio resampler", "frame blending"];
+
+static ACTIVE: LazyLock<SimdLevel> = LazyLock::new(SimdLevel::detect);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum SimdLevel {
+    /// Plain code, which may still be vectorized for what the target always has (like SSE2 on x86_64)
+    Scalar,
+    #[cfg(simd_x86)]
+    Sse41,
+    #[cfg(simd_x86)]
+    Avx2,
+    #[cfg(simd_aarch64)]
+    Neon,
+}
+
+impl SimdLevel {
+    pub fn detect() -> Self {
+        #[cfg(simd_x86)]
+        {
+            if std::is_x86_feature_detected!("avx2") {
+                return SimdLevel::Avx2;
+            }
+            if std::is_x86_feature_detected!("sse4.1") {
+                return SimdLevel::Sse41;
+            }
+        }
+
+        #[cfg(simd_aarch64)]
+        if std::arch::is_aarch64_feature_detected!("neon") {
+            return SimdLevel::Neon;
+        }
+
+        SimdLevel::Scalar
+    }
+}
+
+impl Display for SimdLevel {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            SimdLevel::Scalar => "scalar code",
+            #[cfg(simd_x86)]
+            SimdLevel::Sse41 => "SSE4.1",
+            #[cfg(simd_x86)]
+            SimdLevel::Avx2 => "AVX2",
+            #[cfg(simd_aarch64)]
+            SimdLevel::Neon => "NEON",
+        })
+    }
+}
+
+/// The level the dispatched loops run at, detected the first time this is asked
+pub fn active() -> SimdLevel {
+    *ACTIVE
+}
+
+/// Which paths are active, for the log and the self test
+pub fn describe() -> String {
+    format!("{} for the {}", active(), DISPATCHED_PATHS.join(", "))
+}
+
+/// Wraps a function in versions compiled for each supported extension, calls go to the one [active] picks
+///
+/// Generic functions aren't supported, only plain arguments
+#[cfg(any(feature = "frontend", test))]
+macro_rules! simd_dispatch {
+    (
+        $(#[$attribute:meta])*
+        $visibility:vis fn $name:ident($($argument:ident: $type:ty),* $(,)?) $(-> $output:ty)? $body:block
+    ) => {
+        $(#[$attribute])*
+        $visibility fn $name($($argument: $type),*) $(-> $output)? {
+            #[inline(always)]
+            fn scalar($($argument: $type),*) $(-> $output)? $body
+
+            #[cfg(simd_x86)]
+            #[target_feature(enable = "sse4.1")]
+            fn sse41($($argument: $type),*) $(-> $output)? {
+                scalar($($argument),*)
+            }
+
+            #[cfg(simd_x86)]
+            #[target_feature(enable = "avx2")]
+            fn avx2($($argument: $type),*) $(-> $output)? {
+                scalar($($argument),*)
+            }
+
+            #[cfg(simd_aarch64)]
+            #[target_feature(enable = "neon")]
+            fn neon($($argument: $type),*) $(-> $output)? {
+                scalar($($argument),*)
+            }
+
+            // SAFETY: Levels are only active once the host was found to support them
+            match $crate::runtime::simd::active() {
+                #[cfg(simd_x86)]
+                $crate::runtime::simd::SimdLevel::Sse41 => unsafe { sse41($($argument),*) },
+                #[cfg(simd_x86)]
+                $crate::runtime::simd::SimdLevel::Avx2 => unsafe { avx2($($argument),*) },
+                #[cfg(simd_aarch64)]
+                $crate::runtime::simd::SimdLevel::Neon => unsafe { neon($($argument),*) },
+                $crate::runtime::simd::SimdLevel::Scalar => scalar($($argument),*),
+            }
+        }
+    };
+}
+
+#[cfg(any(feature = "frontend", test))]
+pub(crate) use simd_dispatch;
@@ -83,6 +83,9 @@ impl SoftwareEguiRenderer {
         render_buffer.fill(Srgba::new(0, 0, 0, 0xff));
 
         for shape in context.tessellate(full_output.shapes, full_output.pixels_per_point) {
+            // Scroll areas and windows cut off what doesn't fit in them
+            let clip_rect = shape.clip_rect;
+
             match shape.primitive {
                 egui::epaint::Primitive::Mesh(mesh) => {
                     let texture = self.textures.get(&mesh.texture_id).unwrap();
@@ -102,15 +105,28 @@ impl SoftwareEguiRenderer {
                             .collect();
 
                         if let [v0, v1, v2] = vertexes.as_slice() {
-                            let min_x =
-                                v0.pos.x.min(v1.pos.x).min(v2.pos.x).max(0.0).floor() as usize;
-                            let min_y =
-                                v0.pos.y.min(v1.pos.y).min(v2.pos.y).max(0.0).floor() as usize;
+                            let min_x = v0
+                                .pos
+                                .x
+                                .min(v1.pos.x)
+                                .min(v2.pos.x)
+                                .max(clip_rect.min.x)
+                                .max(0.0)
+                                .floor() as usize;
+                            let min_y = v0
+                                .pos
+                                .y
+                                .min(v1.pos.y)
+                                .min(v2.pos.y)
+                                .max(clip_rect.min.y)
+                                .max(0.0)
+                                .floor() as usize;
                             let max_x = v0
                                 .pos
                                 .x
                                 .max(v1.pos.x)
                                 .max(v2.pos.x)
+                                .min(clip_rect.max.x - 1.0)
                                 .min(render_buffer.nrows() as f32 - 1.0)
                                 .ceil() as usize;
                             let max_y = v0
@@ -118,6 +134,7 @@ impl SoftwareEguiRenderer {
                                 .y
                                 .max(v1.pos.y)
                                 .max(v2.pos.y)
+                                .min(clip_rect.max.y - 1.0)
                                 .min(render_buffer.ncols() as f32 - 1.0)
                                 .ceil() as usize;
 
@@ -200,3 +217,115 @@ fn is_point_in_triangle(point: Point2<f32>, v: [Point2<f32>; 3]) -> bool {
 
     b.iter().all(|&p| p >= 0.0) || b.iter().all(|&p| p <= 0.0)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::runtime::framebuffer_dump::{load_framebuffer, save_framebuffer};
+    use egui::{CentralPanel, Pos2, RawInput, Rect, ScrollArea};
+    use std::path::PathBuf;
+
+    /// Channel differences up to this are rounding, not a change
+    const TOLERANCE: u8 = 16;
+    /// Share of pixels allowed past the tolerance, for glyph edges that land a little differently
+    const MISMATCH_ALLOWANCE: f32 = 0.002;
+
+    fn golden_path(name: &str) -> PathBuf {
+        PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/golden")
+            .join(format!("{}.png", name))
+    }
+
+    /// Draws a frame of the UI twice, as egui lays some things out on the first frame they are shown, and keeps the
+    /// second
+    fn render_scene(size: [usize; 2], ui: impl Fn(&egui::Context)) -> DMatrix<Srgba<u8>> {
+        let context = egui::Context::default();
+        let mut renderer = SoftwareEguiRenderer::default();
+        let mut buffer = DMatrix::from_element(size[0], size[1], Srgba::new(0, 0, 0, 0xff));
+
+        for _ in 0..2 {
+            let input = RawInput {
+                screen_rect: Some(Rect::from_min_max(
+                    Pos2::ZERO,
+                    Pos2::new(size[0] as f32, size[1] as f32),
+                )),
+                ..Default::default()
+            };
+            let full_output = context.run(input, |context| ui(context));
+            renderer.render(&context, buffer.view_range_mut(.., ..), full_output);
+        }
+
+        buffer
+    }
+
+    /// Compares against the stored reference, or stores it when `UPDATE_GOLDEN_IMAGES` is set
+    fn assert_matches_golden(name: &str, rendered: &DMatrix<Srgba<u8>>) {
+        let path = golden_path(name);
+
+        if std::env::var_os("UPDATE_GOLDEN_IMAGES").is_some() {
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            save_framebuffer(rendered, &path).unwrap();
+            return;
+        }
+
+        let golden = load_framebuffer(&path).unwrap_or_else(|error| {
+            panic!(
+                "No reference at {} ({}), set UPDATE_GOLDEN_IMAGES to make one",
+                path.display(),
+                error
+            )
+        });
+        assert_eq!(golden.shape(), rendered.shape());
+
+        let mismatched = golden
+            .iter()
+            .zip(rendered.iter())
+            .filter(|(golden, rendered)| {
+                let difference = |a: u8, b: u8| a.abs_diff(b);
+
+                difference(golden.red, rendered.red)
+                    .max(difference(golden.green, rendered.green))
+                    .max(difference(golden.blue, rendered.blue))
+                    .max(difference(golden.alpha, rendered.alpha))
+                    > TOLERANCE
+            })
+            .count();
+
+        if mismatched as f32 > golden.len() as f32 * MISMATCH_ALLOWANCE {
+            let actual = std::env::temp_dir().join(format!("multiemu_golden_{}.png", name));
+            save_framebuffer(rendered, &actual).unwrap();
+
+            panic!(
+                "{} pixels differ from {}, the render is at {}",
+                mismatched,
+                path.display(),
+                actual.display()
+            );
+        }
+    }
+
+    #[test]
+    fn menu_scene_matches_golden() {
+        let rendered = render_scene([320, 240], |context| {
+            CentralPanel::default().show(context, |ui| {
+                ui.heading("MultiEMU");
+                ui.horizontal(|ui| {
+                    let _ = ui.button("Resume");
+                    let _ = ui.button("Quit to Menu");
+                });
+                ui.checkbox(&mut true, "Frame Blending");
+                ui.separator();
+
+                // Most of the list is scrolled out of view and has to be cut off
+                ScrollArea::vertical().max_height(80.0).show(ui, |ui| {
+                    for index in 0..20 {
+                        ui.label(format!("Game {}", index));
+                    }
+                });
+                ui.label("Below the list");
+            });
+        });
+
+        assert_matches_golden("menu_scene", &rendered);
+    }
+}
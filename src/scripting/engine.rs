@@ -0,0 +1,257 @@
+use super::{parse_input, ScriptRequest};
+use crate::{
+    component::memory::MemoryTranslationTable,
+    env::SCRIPT_DIRECTORY,
+    input::{EmulatedGamepad, InputState},
+};
+use rhai::{CallFnOptions, Dynamic, Engine, EvalAltResult, Map, Scope, AST};
+use std::{
+    fs,
+    sync::{Arc, Mutex},
+};
+use thiserror::Error;
+
+/// Operations a single call into a script may take, so a endless loop can't hang the emulator
+const MAX_OPERATIONS: u64 = 10_000_000;
+
+#[derive(Debug, Error)]
+pub enum ScriptError {
+    #[error("{0}")]
+    Io(#[from] std::io::Error),
+    #[error("{0}")]
+    Parse(#[from] rhai::ParseError),
+    #[error("{0}")]
+    Runtime(#[from] Box<EvalAltResult>),
+}
+
+/// What the functions given to scripts share with the host
+#[derive(Debug, Default)]
+struct HostState {
+    requests: Vec<ScriptRequest>,
+    frame_count: u64,
+}
+
+struct LoadedScript {
+    name: String,
+    ast: AST,
+    /// Variables of the top level
+    scope: Scope<'static>,
+    /// What `on_frame` sees as `this`
+    this: Dynamic,
+    /// Scripts without a frame callback are done once their top level ran
+    on_frame: bool,
+}
+
+/// The scripts enabled for the running game
+pub struct ScriptHost {
+    engine: Engine,
+    scripts: Vec<LoadedScript>,
+    state: Arc<Mutex<HostState>>,
+}
+
+impl ScriptHost {
+    pub fn new(
+        memory_translation_table: Arc<MemoryTranslationTable>,
+        controllers: Vec<Arc<EmulatedGamepad>>,
+    ) -> Self {
+        let state = Arc::new(Mutex::new(HostState::default()));
+        let mut engine = Engine::new();
+        engine.set_max_operations(MAX_OPERATIONS);
+        engine.on_print(|text| tracing::info!("Script printed: {}", text));
+
+        for (suffix, size) in [("u8", 1), ("u16", 2), ("u32", 4)] {
+            let table = memory_translation_table.clone();
+            engine.register_fn(
+                format!("read_{}", suffix),
+                move |address: i64| -> Result<i64, Box<EvalAltResult>> {
+                    let mut buffer = [0; 8];
+                    table
+                        .preview(to_address(address)?, &mut buffer[..size])
+                        .map_err(|error| error.to_string())?;
+
+                    Ok(u64::from_le_bytes(buffer) as i64)
+                },
+            );
+
+            let table = memory_translation_table.clone();
+            engine.register_fn(
+                format!("write_{}", suffix),
+                move |address: i64, value: i64| -> Result<(), Box<EvalAltResult>> {
+                    table
+                        .write(to_address(address)?, &value.to_le_bytes()[..size])
+                        .map_err(|error| error.to_string())?;
+
+                    Ok(())
+                },
+            );
+        }
+
+        let press_controllers = controllers.clone();
+        engine.register_fn(
+            "press",
+            move |player: i64, input: &str| -> Result<(), Box<EvalAltResult>> {
+                set_input(&press_controllers, player, input, InputState::Digital(true))
+            },
+        );
+        let release_controllers = controllers.clone();
+        engine.register_fn(
+            "release",
+            move |player: i64, input: &str| -> Result<(), Box<EvalAltResult>> {
+                set_input(
+                    &release_controllers,
+                    player,
+                    input,
+                    InputState::Digital(false),
+                )
+            },
+        );
+        engine.register_fn(
+            "set_analog",
+            move |player: i64, input: &str, value: f64| -> Result<(), Box<EvalAltResult>> {
+                set_input(
+                    &controllers,
+                    player,
+                    input,
+                    InputState::Analog(value.clamp(0.0, 1.0) as f32),
+                )
+            },
+        );
+
+        let save_state = state.clone();
+        engine.register_fn(
+            "save_state",
+            move |slot: i64| -> Result<(), Box<EvalAltResult>> {
+                let slot = to_slot(slot)?;
+                save_state
+                    .lock()
+                    .unwrap()
+                    .requests
+                    .push(ScriptRequest::SaveSnapshot(slot));
+
+                Ok(())
+            },
+        );
+        let load_state = state.clone();
+        engine.register_fn(
+            "load_state",
+            move |slot: i64| -> Result<(), Box<EvalAltResult>> {
+                let slot = to_slot(slot)?;
+                load_state
+                    .lock()
+                    .unwrap()
+                    .requests
+                    .push(ScriptRequest::LoadSnapshot(slot));
+
+                Ok(())
+            },
+        );
+
+        let frame_count_state = state.clone();
+        engine.register_fn("frame_count", move || {
+            frame_count_state.lock().unwrap().frame_count as i64
+        });
+        let notify_state = state.clone();
+        engine.register_fn("notify", move |message: &str| {
+            notify_state
+                .lock()
+                .unwrap()
+                .requests
+                .push(ScriptRequest::Notify(message.to_string()));
+        });
+
+        Self {
+            engine,
+            scripts: Vec::new(),
+            state,
+        }
+    }
+
+    /// Compiles a script from [SCRIPT_DIRECTORY] and runs its top level
+    pub fn load(&mut self, name: &str) -> Result<(), ScriptError> {
+        let source = fs::read_to_string(SCRIPT_DIRECTORY.join(name))?;
+        let ast = self.engine.compile(source)?;
+        let mut scope = Scope::new();
+        self.engine.run_ast_with_scope(&mut scope, &ast)?;
+
+        let on_frame = ast
+            .iter_functions()
+            .any(|function| function.name == "on_frame" && function.params.is_empty());
+
+        self.scripts.push(LoadedScript {
+            name: name.to_string(),
+            ast,
+            scope,
+            this: Map::new().into(),
+            on_frame,
+        });
+
+        Ok(())
+    }
+
+    /// Calls every frame callback, giving back what the scripts asked of the runtime. Scripts that fail are dropped
+    pub fn run_frame(&mut self) -> Vec<ScriptRequest> {
+        self.state.lock().unwrap().frame_count += 1;
+
+        let engine = &self.engine;
+        let mut failures = Vec::new();
+        self.scripts.retain_mut(|script| {
+            if !script.on_frame {
+                return true;
+            }
+
+            // The top level already ran when the script was loaded
+            let options = CallFnOptions::new()
+                .eval_ast(false)
+                .bind_this_ptr(&mut script.this);
+
+            match engine.call_fn_with_options::<Dynamic>(
+                options,
+                &mut script.scope,
+                &script.ast,
+                "on_frame",
+                (),
+            ) {
+                Ok(_) => true,
+                Err(error) => {
+                    tracing::error!("Script {} failed: {}", script.name, error);
+                    failures.push(ScriptRequest::Notify(format!(
+                        "Script {} stopped: {}",
+                        script.name, error
+                    )));
+
+                    false
+                }
+            }
+        });
+
+        let mut requests = std::mem::take(&mut self.state.lock().unwrap().requests);
+        requests.extend(failures);
+
+        requests
+    }
+}
+
+fn to_address(address: i64) -> Result<usize, Box<EvalAltResult>> {
+    usize::try_from(address).map_err(|_| format!("{} is not a address", address).into())
+}
+
+fn to_slot(slot: i64) -> Result<u8, Box<EvalAltResult>> {
+    u8::try_from(slot).map_err(|_| format!("There is no snapshot slot {}", slot).into())
+}
+
+fn set_input(
+    controllers: &[Arc<EmulatedGamepad>],
+    player: i64,
+    input: &str,
+    input_state: InputState,
+) -> Result<(), Box<EvalAltResult>> {
+    let controller = usize::try_from(player)
+        .ok()
+        .and_then(|player| controllers.get(player))
+        .ok_or_else(|| format!("There is no player {}", player))?;
+    let input = parse_input(input).ok_or_else(|| format!("There is no input named {}", input))?;
+
+    controller.set_input_state(input, input_state);
+
+    Ok(())
+}
@@ -0,0 +1,92 @@
+//! User scripts that drive a running game, for bots, cheat trainers and autosplitters
+//!
+//! Scripts are [rhai](https://rhai.rs) files in [SCRIPT_DIRECTORY], each game runs the ones enabled for it in the
+//! menu. The top level of a script runs once when the game starts, after that `on_frame()` is called every frame the
+//! machine ran with `this` bound to a object map that is kept between calls. They get these functions:
+//!
+//! - `read_u8(address)`, `read_u16(address)` and `read_u32(address)`, little endian and without side effects
+//! - `write_u8(address, value)`, `write_u16(address, value)` and `write_u32(address, value)`, like the processor would
+//! - `press(player, input)`, `release(player, input)` and `set_analog(player, input, value)`, inputs are named like
+//!   in the config, so `"FPadDown"` or `"KeyA"`, and players count from 0
+//! - `save_state(slot)` and `load_state(slot)`, done once the frame callbacks are through
+//! - `frame_count()`, frames since the script was loaded
+//! - `notify(message)`, shown on screen, while `print` goes to the log
+
+#[cfg(feature = "frontend")]
+use crate::env::SCRIPT_DIRECTORY;
+use crate::input::{gamepad::GamepadInput, keyboard::KeyboardInput, pointer::PointerInput, Input};
+#[cfg(feature = "frontend")]
+use std::fs;
+use strum::IntoEnumIterator;
+
+#[cfg(desktop)]
+pub mod engine;
+
+#[cfg(feature = "frontend")]
+pub const SCRIPT_EXTENSION: &str = "rhai";
+
+/// What a script asked for that only the runtime can do
+#[cfg(feature = "frontend")]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ScriptRequest {
+    SaveSnapshot(u8),
+    LoadSnapshot(u8),
+    Notify(String),
+}
+
+/// File names of every script there is, sorted
+#[cfg(feature = "frontend")]
+pub fn available_scripts() -> Vec<String> {
+    let Ok(entries) = fs::read_dir(SCRIPT_DIRECTORY.as_path()) else {
+        return Vec::new();
+    };
+
+    let mut scripts: Vec<_> = entries
+        .filter_map(|entry| {
+            let path = entry.ok()?.path();
+
+            (path.extension()? == SCRIPT_EXTENSION)
+                .then(|| path.file_name()?.to_str().map(str::to_string))
+                .flatten()
+        })
+        .collect();
+    scripts.sort();
+
+    scripts
+}
+
+/// Looks up a input by the name of its variant, which is what the config files show
+pub fn parse_input(name: &str) -> Option<Input> {
+    GamepadInput::iter()
+        .map(Input::Gamepad)
+        .chain(KeyboardInput::iter().map(Input::Keyboard))
+        .chain(PointerInput::iter().map(Input::Pointer))
+        .find(|input| {
+            let variant_name = match input {
+                Input::Gamepad(input) => format!("{:?}", input),
+                Input::Keyboard(input) => format!("{:?}", input),
+                Input::Pointer(input) => format!("{:?}", input),
+            };
+
+            variant_name == name
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_input;
+    use crate::input::{gamepad::GamepadInput, keyboard::KeyboardInput, Input};
+
+    #[test]
+    fn input_names() {
+        assert_eq!(
+            parse_input("FPadDown"),
+            Some(Input::Gamepad(GamepadInput::FPadDown))
+        );
+        assert_eq!(
+            parse_input("KeyA"),
+            Some(Input::Keyboard(KeyboardInput::KeyA))
+        );
+        assert_eq!(parse_input("Gamepad(FPadDown)"), None);
+    }
+}
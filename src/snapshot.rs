@@ -1,16 +1,320 @@
+#[cfg(feature = "frontend")]
+use crate::rom::RomId;
+use crate::{
+    atomic_write::write_atomically,
+    component::{display::color::Pixel, snapshot::SnapshotableComponent},
+    env::SNAPSHOT_DIRECTORY,
+    machine::{
+        executor::Executor,
+        fingerprint::{FingerprintMismatch, MachineFingerprint},
+    },
+};
+use nalgebra::DMatrix;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use serde_with::{serde_as, Bytes};
+#[cfg(feature = "frontend")]
+use std::fs::read_dir;
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::BufReader,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    time::{SystemTime, UNIX_EPOCH},
+};
+use thiserror::Error;
 
-/// TODO: Actually implement this
+/// Bumped whenever the layout of [SnapshotMetadata] or [Snapshot] changes
+pub const SNAPSHOT_VERSION: u32 = 4;
 
-#[derive(Debug, Serialize, Deserialize)]
+/// Thumbnails are shrunk until they fit in this many pixels on both sides
+pub const THUMBNAIL_SIZE: usize = 160;
+
+/// A small copy of the screen as it was when the snapshot was taken
+#[serde_as]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SnapshotThumbnail {
+    pub width: usize,
+    pub height: usize,
+    /// RGBA, row by row
+    #[serde_as(as = "Bytes")]
+    pub pixels: Vec<u8>,
+}
+
+impl SnapshotThumbnail {
+    /// Picks the nearest pixel rather than blending, so pixel art stays sharp
+    pub fn new(buffer: &DMatrix<Pixel>) -> Self {
+        let (source_width, source_height) = buffer.shape();
+        let scale =
+            (THUMBNAIL_SIZE as f32 / source_width.max(source_height).max(1) as f32).min(1.0);
+        let width = ((source_width as f32 * scale) as usize).max(1);
+        let height = ((source_height as f32 * scale) as usize).max(1);
+
+        let mut pixels = Vec::with_capacity(width * height * 4);
+        for y in 0..height {
+            for x in 0..width {
+                let pixel = buffer[(
+                    (x * source_width / width).min(source_width.saturating_sub(1)),
+                    (y * source_height / height).min(source_height.saturating_sub(1)),
+                )];
+                pixels.extend([pixel.red, pixel.green, pixel.blue, pixel.alpha]);
+            }
+        }
+
+        Self {
+            width,
+            height,
+            pixels,
+        }
+    }
+}
+
+/// Comes before the snapshot itself, so the library can show it without decoding the whole machine
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SnapshotMetadata {
+    /// Seconds since the unix epoch
+    pub saved_at: u64,
+    pub thumbnail: Option<SnapshotThumbnail>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SnapshotTaskInformation {
     pub current_cycle: u32,
+    /// By the name of the component they drive
     pub tasks: HashMap<String, rmpv::Value>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Snapshot {
+    /// Checked against the running machine before anything is loaded
+    pub fingerprint: MachineFingerprint,
     pub components: HashMap<String, rmpv::Value>,
     pub task_info: SnapshotTaskInformation,
 }
+
+#[derive(Debug, Error)]
+pub enum SnapshotError {
+    #[error("Could not access the snapshot file: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Could not encode the snapshot: {0}")]
+    Encode(#[from] rmp_serde::encode::Error),
+    #[error("Snapshot is corrupted: {0}")]
+    Decode(#[from] rmp_serde::decode::Error),
+    #[error("Snapshot is version {0} but only version {SNAPSHOT_VERSION} is supported")]
+    Version(u32),
+    #[error("Snapshot does not belong to this game: {0}")]
+    Fingerprint(#[from] FingerprintMismatch),
+    #[error("Snapshot has no state for the component {0}")]
+    MissingComponent(String),
+}
+
+/// Saves and restores the whole state of a running machine
+pub struct SnapshotManager {
+    fingerprint: MachineFingerprint,
+    components: Vec<(&'static str, Arc<Mutex<dyn SnapshotableComponent>>)>,
+}
+
+impl SnapshotManager {
+    pub fn new(
+        fingerprint: MachineFingerprint,
+        components: Vec<(&'static str, Arc<Mutex<dyn SnapshotableComponent>>)>,
+    ) -> Self {
+        Self {
+            fingerprint,
+            components,
+        }
+    }
+
+    /// Where a numbered slot for this game lives
+    pub fn slot_path(&self, slot: u8) -> PathBuf {
+        let game = self
+            .fingerprint
+            .roms
+            .first()
+            .map(ToString::to_string)
+            .unwrap_or_else(|| self.fingerprint.system.to_string());
+
+        game_slot_path(&game, slot)
+    }
+
+    /// The thumbnail is usually the first display as it is right now
+    pub fn save(
+        &self,
+        executor: &mut impl Executor,
+        path: &Path,
+        thumbnail: Option<&DMatrix<Pixel>>,
+    ) -> Result<(), SnapshotError> {
+        let metadata = SnapshotMetadata {
+            saved_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs(),
+            thumbnail: thumbnail.map(SnapshotThumbnail::new),
+        };
+        let snapshot = Snapshot {
+            fingerprint: self.fingerprint.clone(),
+            components: self
+                .components
+                .iter()
+                .map(|(name, component)| {
+                    (name.to_string(), component.lock().unwrap().save_snapshot())
+                })
+                .collect(),
+            task_info: executor.save_tasks(),
+        };
+
+        // Saving over a slot that then fails halfway would lose both states
+        write_atomically(path, |file| {
+            // The version goes first so it can be read no matter what the rest looks like
+            rmp_serde::encode::write(file, &SNAPSHOT_VERSION)?;
+            rmp_serde::encode::write_named(file, &metadata)?;
+            rmp_serde::encode::write_named(file, &snapshot)?;
+
+            Ok::<_, SnapshotError>(())
+        })?;
+
+        tracing::info!("Saved snapshot to {}", path.display());
+
+        Ok(())
+    }
+
+    /// Nothing is touched unless the whole snapshot is usable
+    pub fn load(&self, executor: &mut impl Executor, path: &Path) -> Result<(), SnapshotError> {
+        let mut file = BufReader::new(File::open(path)?);
+
+        read_header(&mut file)?;
+        let mut snapshot: Snapshot = rmp_serde::decode::from_read(&mut file)?;
+        self.fingerprint.verify(&snapshot.fingerprint)?;
+
+        let states = self
+            .components
+            .iter()
+            .map(|(name, _)| {
+                snapshot
+                    .components
+                    .remove(*name)
+                    .ok_or_else(|| SnapshotError::MissingComponent(name.to_string()))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        for ((_, component), state) in self.components.iter().zip(states) {
+            component.lock().unwrap().load_snapshot(state);
+        }
+        executor.load_tasks(snapshot.task_info);
+
+        tracing::info!("Loaded snapshot from {}", path.display());
+
+        Ok(())
+    }
+}
+
+fn game_slot_path(game: &str, slot: u8) -> PathBuf {
+    SNAPSHOT_DIRECTORY.join(format!("{}-{}.snapshot", game, slot))
+}
+
+/// Checks the version and reads the metadata, leaving the file at the snapshot
+fn read_header(file: &mut impl std::io::Read) -> Result<SnapshotMetadata, SnapshotError> {
+    let version: u32 = rmp_serde::decode::from_read(&mut *file)?;
+    if version != SNAPSHOT_VERSION {
+        return Err(SnapshotError::Version(version));
+    }
+
+    Ok(rmp_serde::decode::from_read(file)?)
+}
+
+#[cfg(any(feature = "frontend", test))]
+pub fn read_snapshot_metadata(path: &Path) -> Result<SnapshotMetadata, SnapshotError> {
+    read_header(&mut BufReader::new(File::open(path)?))
+}
+
+/// The most recently saved slot of a game and what it was saved with, snapshots that can't be read are skipped
+#[cfg(feature = "frontend")]
+pub fn latest_snapshot(game: RomId) -> Option<(u8, SnapshotMetadata)> {
+    let prefix = format!("{}-", game);
+
+    read_dir(SNAPSHOT_DIRECTORY.as_path())
+        .ok()?
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let path = entry.path();
+            if path.extension()? != "snapshot" {
+                return None;
+            }
+            let slot = path
+                .file_stem()?
+                .to_str()?
+                .strip_prefix(&prefix)?
+                .parse()
+                .ok()?;
+
+            Some((slot, read_snapshot_metadata(&path).ok()?))
+        })
+        .max_by_key(|(_, metadata)| metadata.saved_at)
+}
+
+#[cfg(all(test, feature = "chip8"))]
+mod tests {
+    use super::*;
+    use crate::{
+        component::{definitions::chip8::timer::Chip8Timer, FromConfig},
+        machine::executor::single::SingleThreadedExecutor,
+        rom::RomManager,
+        task::{generic::GenericTask, InitializeableTask},
+    };
+    use num::rational::Ratio;
+
+    #[test]
+    fn snapshot_roundtrip() {
+        let timer = Arc::new(Mutex::new(Chip8Timer::from_config(
+            Arc::new(RomManager::default()),
+            (),
+        )));
+        let mut executor = SingleThreadedExecutor::new(
+            vec![(
+                "timer",
+                Ratio::new(60, 1),
+                Box::new(GenericTask::new(timer.clone(), ())),
+            )],
+            Arc::default(),
+        );
+        let snapshot_manager = SnapshotManager::new(
+            MachineFingerprint::default(),
+            vec![("timer", timer.clone())],
+        );
+        let path = std::env::temp_dir().join("multiemu_snapshot_test.snapshot");
+
+        timer.lock().unwrap().handle().set(42);
+        snapshot_manager
+            .save(
+                &mut executor,
+                &path,
+                Some(&DMatrix::from_element(640, 320, Pixel::new(1, 2, 3, 4))),
+            )
+            .unwrap();
+        let thumbnail = read_snapshot_metadata(&path).unwrap().thumbnail.unwrap();
+        assert_eq!(
+            (thumbnail.width, thumbnail.height),
+            (THUMBNAIL_SIZE, THUMBNAIL_SIZE / 2)
+        );
+        assert_eq!(thumbnail.pixels[..4], [1, 2, 3, 4]);
+
+        timer.lock().unwrap().handle().set(0);
+        snapshot_manager.load(&mut executor, &path).unwrap();
+        assert_eq!(timer.lock().unwrap().handle().get(), 42);
+
+        // A different machine must not accept it
+        let other_snapshot_manager = SnapshotManager::new(
+            MachineFingerprint {
+                components: vec!["other".to_string()],
+                ..Default::default()
+            },
+            vec![("timer", timer.clone())],
+        );
+        assert!(matches!(
+            other_snapshot_manager.load(&mut executor, &path),
+            Err(SnapshotError::Fingerprint(_))
+        ));
+
+        let _ = std::fs::remove_file(path);
+    }
+}
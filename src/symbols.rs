@@ -0,0 +1,224 @@
+use serde::{Deserialize, Serialize};
+#[cfg(any(feature = "frontend", test))]
+use std::collections::BTreeMap;
+use std::ops::Range;
+#[cfg(feature = "frontend")]
+use std::{fs::read_to_string, path::Path};
+#[cfg(any(feature = "frontend", test))]
+use thiserror::Error;
+
+/// A name for a address, or for every address in a range
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AddressLabel {
+    pub range: Range<usize>,
+    pub name: String,
+}
+
+#[cfg(any(feature = "frontend", test))]
+#[derive(Debug, Error)]
+pub enum SymbolFileError {
+    #[error("Could not read the symbol file: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("The file has no symbols in a known format")]
+    NoSymbols,
+}
+
+/// Labels of a game looked up by address, for showing names where the debugger would show raw addresses
+#[cfg(any(feature = "frontend", test))]
+#[derive(Debug, Default)]
+pub struct SymbolTable {
+    /// By the start of their range
+    labels: BTreeMap<usize, AddressLabel>,
+}
+
+#[cfg(any(feature = "frontend", test))]
+impl SymbolTable {
+    /// Later labels win if two start at the same address, so hand written ones can follow imported ones
+    pub fn new(labels: &[AddressLabel]) -> Self {
+        Self {
+            labels: labels
+                .iter()
+                .map(|label| (label.range.start, label.clone()))
+                .collect(),
+        }
+    }
+
+    /// The name of the label starting at the address, or of the one it is inside with the offset into it
+    pub fn name(&self, address: usize) -> Option<String> {
+        let label = self
+            .labels
+            .range(..=address)
+            .rev()
+            .map(|(_, label)| label)
+            .find(|label| label.range.contains(&address))?;
+
+        if label.range.start == address {
+            Some(label.name.clone())
+        } else {
+            Some(format!(
+                "{}+0x{:x}",
+                label.name,
+                address - label.range.start
+            ))
+        }
+    }
+
+    /// Only the name of a label that starts exactly here
+    #[cfg(feature = "frontend")]
+    pub fn label_starting_at(&self, address: usize) -> Option<&str> {
+        self.labels.get(&address).map(|label| label.name.as_str())
+    }
+
+    /// Where the label with this name starts
+    #[cfg(feature = "frontend")]
+    pub fn find(&self, name: &str) -> Option<usize> {
+        self.labels
+            .values()
+            .find(|label| label.name == name)
+            .map(|label| label.range.start)
+    }
+
+    /// The name if there is one, the address otherwise
+    pub fn format_address(&self, address: usize) -> String {
+        self.name(address)
+            .unwrap_or_else(|| format!("0x{:x}", address))
+    }
+}
+
+#[cfg(feature = "frontend")]
+pub fn load_symbol_file(path: &Path) -> Result<Vec<AddressLabel>, SymbolFileError> {
+    parse_symbols(&read_to_string(path)?)
+}
+
+/// Reads the symbol formats common assemblers write, `[bank:]address name` (RGBDS, WLA-DX, no$ debuggers), VICE
+/// `al` commands and `name = address` assignments
+///
+/// Lines that match none of them, like section headers, are skipped. Banks are dropped as labels are in terms of the
+/// address space the processor sees
+#[cfg(any(feature = "frontend", test))]
+pub fn parse_symbols(text: &str) -> Result<Vec<AddressLabel>, SymbolFileError> {
+    let mut labels = Vec::new();
+    let mut skipped = 0;
+
+    for line in text.lines() {
+        // Comments
+        let line = line.split(';').next().unwrap().trim();
+
+        if line.is_empty() || line.starts_with('#') || line.starts_with('[') {
+            continue;
+        }
+
+        match parse_symbol_line(line) {
+            Some((address, name)) => labels.push(AddressLabel {
+                range: address..address + 1,
+                name: name.to_string(),
+            }),
+            None => skipped += 1,
+        }
+    }
+
+    if skipped != 0 {
+        tracing::warn!("Skipped {} lines of the symbol file", skipped);
+    }
+
+    if labels.is_empty() {
+        return Err(SymbolFileError::NoSymbols);
+    }
+
+    Ok(labels)
+}
+
+#[cfg(any(feature = "frontend", test))]
+fn parse_symbol_line(line: &str) -> Option<(usize, &str)> {
+    if let Some((name, address)) = line.split_once('=') {
+        return Some((parse_address(address.trim())?, name.trim()));
+    }
+
+    let mut parts = line.split_whitespace();
+    let mut address = parts.next()?;
+    // VICE
+    if address == "al" {
+        address = parts.next()?;
+    }
+    let name = parts.next()?.trim_start_matches('.');
+
+    if name.is_empty() || parts.next().is_some() {
+        return None;
+    }
+
+    Some((parse_address(address)?, name))
+}
+
+/// Hex with any of the usual prefixes, and a bank in front separated by a colon
+#[cfg(any(feature = "frontend", test))]
+fn parse_address(address: &str) -> Option<usize> {
+    let address = address.rsplit(':').next().unwrap();
+    let address = address
+        .strip_prefix('$')
+        .or_else(|| address.strip_prefix("0x"))
+        .or_else(|| address.strip_suffix(['h', 'H']))
+        .unwrap_or(address);
+
+    usize::from_str_radix(address, 16).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_common_symbol_formats() {
+        let labels = parse_symbols(
+            "; RGBDS\n\
+             [labels]\n\
+             00:0150 Start\n\
+             al C:0810 .irq_handler\n\
+             vblank = $0040\n\
+             not a symbol line\n",
+        )
+        .unwrap();
+
+        assert_eq!(
+            labels,
+            vec![
+                AddressLabel {
+                    range: 0x150..0x151,
+                    name: "Start".to_string()
+                },
+                AddressLabel {
+                    range: 0x810..0x811,
+                    name: "irq_handler".to_string()
+                },
+                AddressLabel {
+                    range: 0x40..0x41,
+                    name: "vblank".to_string()
+                },
+            ]
+        );
+        assert!(matches!(
+            parse_symbols("[labels]\n"),
+            Err(SymbolFileError::NoSymbols)
+        ));
+    }
+
+    #[test]
+    fn names_addresses_inside_ranges() {
+        let symbol_table = SymbolTable::new(&[
+            AddressLabel {
+                range: 0x200..0x300,
+                name: "sprites".to_string(),
+            },
+            AddressLabel {
+                range: 0x210..0x211,
+                name: "player".to_string(),
+            },
+        ]);
+
+        assert_eq!(symbol_table.format_address(0x200), "sprites");
+        assert_eq!(symbol_table.format_address(0x208), "sprites+0x8");
+        assert_eq!(symbol_table.format_address(0x210), "player");
+        // The inner label ended, so the outer one it is still in counts
+        assert_eq!(symbol_table.format_address(0x211), "sprites+0x11");
+        assert_eq!(symbol_table.format_address(0x300), "0x300");
+    }
+}
@@ -1,5 +1,6 @@
 use super::{InitializeableTask, Task};
 use crate::component::{memory::MemoryTranslationTable, schedulable::SchedulableComponent};
+use num::rational::Ratio;
 use std::sync::{Arc, Mutex};
 
 pub struct GenericTask<C: SchedulableComponent> {
@@ -20,6 +21,10 @@ impl<C: SchedulableComponent> Task for GenericTask<C> {
     fn save(&mut self) -> rmpv::Value {
         rmpv::Value::Nil
     }
+
+    fn tick_rate(&self) -> Option<Ratio<u32>> {
+        Some(self.component.lock().unwrap().tick_rate())
+    }
 }
 
 impl<C: SchedulableComponent> InitializeableTask<C> for GenericTask<C> {
@@ -1,16 +1,31 @@
 use crate::component::{memory::MemoryTranslationTable, schedulable::SchedulableComponent};
+use num::rational::Ratio;
 use std::fmt::Debug;
 use std::sync::{Arc, Mutex};
 
 pub mod generic;
 pub mod processor;
+pub mod rewind;
 
 /// Trait that wraps a [ScheduableComponent] to provide more functionality and handle batching
-pub trait Task: Send + Sync + 'static {
+///
+/// A task is owned by exactly one executor thread at a time and only ever accessed mutably, so it does not need to be
+/// Sync. It should lock its component once per batch rather than once per tick
+pub trait Task: Send + 'static {
     fn tick(&mut self, batch_size: u32, memory_translation_table: &MemoryTranslationTable);
 
     fn save(&mut self) -> rmpv::Value;
     fn load(&mut self, state: rmpv::Value);
+
+    /// Replaying and single stepping run through breakpoints, tasks without any can ignore this
+    #[cfg(any(feature = "frontend", test))]
+    fn set_breakpoints_enabled(&mut self, _enabled: bool) {}
+
+    /// The rate the component wants to run at right now, checked between runs. None keeps the rate the task was
+    /// scheduled with
+    fn tick_rate(&self) -> Option<Ratio<u32>> {
+        None
+    }
 }
 
 pub trait InitializeableTask<C: SchedulableComponent>: Task + Sized {
@@ -18,9 +33,3 @@ pub trait InitializeableTask<C: SchedulableComponent>: Task + Sized {
 
     fn new(component: Arc<Mutex<C>>, config: Self::Config) -> Self;
 }
-
-#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
-pub enum TaskOrdering {
-    Before,
-    After,
-}
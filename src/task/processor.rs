@@ -1,10 +1,11 @@
 use super::{InitializeableTask, Task};
-use crate::component::{
-    memory::MemoryTranslationTable, processor::ProcessorComponent,
-    schedulable::SchedulableComponent,
-};
+use crate::component::{memory::MemoryTranslationTable, processor::ProcessorComponent};
+use num::rational::Ratio;
 use serde::{Deserialize, Serialize};
-use std::sync::{Arc, Mutex};
+use std::{
+    collections::BTreeSet,
+    sync::{Arc, Mutex},
+};
 
 #[derive(Serialize, Deserialize)]
 struct TaskState {
@@ -16,45 +17,158 @@ pub struct ProcessorTaskConfig {
     pub initial_program_pointer: usize,
 }
 
+/// What the debugger sees of a processor task and can change about it
+#[derive(Debug, Default)]
+pub struct ProcessorDebugState {
+    /// As of the end of the last batch
+    pub program_pointer: usize,
+    pub breakpoints: BTreeSet<usize>,
+    /// Set when the processor stopped at a breakpoint, it doesn't run again until [ProcessorDebugState::resume]
+    pub breakpoint_hit: Option<usize>,
+    /// Set when a instruction failed in a way the processor couldn't handle, it stops the same way as a breakpoint
+    pub fault: Option<ProcessorFault>,
+    /// So resuming from a breakpoint doesn't stop at it again right away
+    step_over: bool,
+}
+
+/// A instruction that failed, by where it started
+#[derive(Debug, Clone)]
+pub struct ProcessorFault {
+    pub address: usize,
+    pub message: String,
+}
+
+/// Why a processor stopped for the debugger
+#[cfg(feature = "frontend")]
+#[derive(Debug, Clone)]
+pub enum ProcessorStop {
+    Breakpoint(usize),
+    Fault(ProcessorFault),
+}
+
+impl ProcessorDebugState {
+    #[cfg(any(feature = "frontend", test))]
+    pub fn resume(&mut self) {
+        if self.breakpoint_hit.take().is_some() {
+            self.step_over = true;
+        }
+        // The faulting instruction already ran, so there is nothing to step over
+        self.fault = None;
+    }
+
+    pub fn is_stopped(&self) -> bool {
+        self.breakpoint_hit.is_some() || self.fault.is_some()
+    }
+}
+
 pub struct ProcessorTask<C: ProcessorComponent> {
     program_pointer: usize,
     component: Arc<Mutex<C>>,
+    debug_state: Arc<Mutex<ProcessorDebugState>>,
+    breakpoints_enabled: bool,
+}
+
+impl<C: ProcessorComponent> ProcessorTask<C> {
+    pub fn debug_state(&self) -> Arc<Mutex<ProcessorDebugState>> {
+        self.debug_state.clone()
+    }
 }
 
 impl<C: ProcessorComponent> Task for ProcessorTask<C> {
     fn tick(&mut self, batch_size: u32, memory_translation_table: &MemoryTranslationTable) {
         let mut component = self.component.lock().unwrap();
+        let mut debug_state = self.debug_state.lock().unwrap();
+
+        if debug_state.is_stopped() {
+            return;
+        }
 
         for _ in 0..batch_size {
             // Tick
             component.tick(memory_translation_table);
+            component.service_interrupts(&mut self.program_pointer, memory_translation_table);
 
             if !component.should_execution_occur() {
                 continue;
             }
 
-            // Fetch / decode
-            let (instruction, size) = component
-                .decompile(self.program_pointer, memory_translation_table)
-                .unwrap();
-
-            tracing::debug!(
-                "Instruction: {:x?} decoded from address: 0x{:x}",
-                instruction,
-                self.program_pointer
-            );
-
-            self.program_pointer = self.program_pointer.wrapping_add(size as usize);
-
-            // Execute
-            component
-                .interpret(
-                    &mut self.program_pointer,
-                    instruction,
-                    memory_translation_table,
-                )
-                .unwrap();
+            if self.breakpoints_enabled
+                && !std::mem::take(&mut debug_state.step_over)
+                && debug_state.breakpoints.contains(&self.program_pointer)
+            {
+                tracing::info!("Breakpoint hit at 0x{:x}", self.program_pointer);
+                debug_state.breakpoint_hit = Some(self.program_pointer);
+                break;
+            }
+
+            let instruction_address = self.program_pointer;
+
+            // A block would run straight past any breakpoint inside of it
+            let recompiled = if !self.breakpoints_enabled || debug_state.breakpoints.is_empty() {
+                component.run_recompiled(&mut self.program_pointer, memory_translation_table)
+            } else {
+                None
+            };
+
+            let result = match recompiled {
+                Some(result) => result,
+                None => {
+                    // Fetch / decode
+                    let (instruction, size) =
+                        match component.decompile(self.program_pointer, memory_translation_table) {
+                            Ok(decompiled) => decompiled,
+                            Err(error) => {
+                                tracing::error!(
+                                    "Illegal instruction at 0x{:x}, stopping the processor: {}",
+                                    self.program_pointer,
+                                    error
+                                );
+                                debug_state.fault = Some(ProcessorFault {
+                                    address: instruction_address,
+                                    message: error.to_string(),
+                                });
+
+                                break;
+                            }
+                        };
+
+                    tracing::debug!(
+                        "Instruction: {:x?} decoded from address: 0x{:x}",
+                        instruction,
+                        self.program_pointer
+                    );
+
+                    self.program_pointer = self.program_pointer.wrapping_add(size as usize);
+
+                    // Execute
+                    component.interpret(
+                        &mut self.program_pointer,
+                        instruction,
+                        memory_translation_table,
+                    )
+                }
+            };
+
+            if let Err(error) = result {
+                tracing::error!(
+                    "Instruction at 0x{:x} faulted, stopping the processor: {}",
+                    instruction_address,
+                    error
+                );
+                debug_state.fault = Some(ProcessorFault {
+                    address: instruction_address,
+                    message: error.to_string(),
+                });
+                break;
+            }
         }
+
+        debug_state.program_pointer = self.program_pointer;
+    }
+
+    #[cfg(any(feature = "frontend", test))]
+    fn set_breakpoints_enabled(&mut self, enabled: bool) {
+        self.breakpoints_enabled = enabled;
     }
 
     fn save(&mut self) -> rmpv::Value {
@@ -69,6 +183,11 @@ impl<C: ProcessorComponent> Task for ProcessorTask<C> {
         let state = rmpv::ext::from_value::<TaskState>(state).unwrap();
 
         self.program_pointer = state.program_pointer;
+        self.debug_state.lock().unwrap().program_pointer = state.program_pointer;
+    }
+
+    fn tick_rate(&self) -> Option<Ratio<u32>> {
+        Some(self.component.lock().unwrap().tick_rate())
     }
 }
 
@@ -79,6 +198,163 @@ impl<C: ProcessorComponent> InitializeableTask<C> for ProcessorTask<C> {
         Self {
             program_pointer: config.initial_program_pointer,
             component,
+            debug_state: Arc::new(Mutex::new(ProcessorDebugState {
+                program_pointer: config.initial_program_pointer,
+                ..Default::default()
+            })),
+            breakpoints_enabled: true,
         }
     }
 }
+
+#[cfg(all(test, feature = "chip8"))]
+mod tests {
+    use super::*;
+    use crate::{
+        component::{
+            definitions::{
+                chip8::{
+                    audio::{Chip8Audio, Chip8AudioConfig},
+                    display::{Chip8Display, Chip8DisplayConfig},
+                    processor::{Chip8Processor, Chip8ProcessorConfig},
+                    timer::Chip8Timer,
+                    Chip8Kind,
+                },
+                misc::plain_memory::{PlainMemory, PlainMemoryConfig, PlainMemoryInitialContents},
+            },
+            Component, FromConfig,
+        },
+        machine::QueryableComponents,
+        rom::RomManager,
+    };
+    use num::rational::Ratio;
+
+    #[test]
+    fn stops_at_breakpoints() {
+        let rom_manager = Arc::new(RomManager::default());
+        let processor = Arc::new(Mutex::new(Chip8Processor::from_config(
+            rom_manager.clone(),
+            Chip8ProcessorConfig {
+                frequency: Ratio::from_integer(700),
+                kind: Chip8Kind::Chip8,
+            },
+        )));
+        // Only there so the processor can start, loads don't touch them
+        let mut queryable_components = QueryableComponents::default();
+        queryable_components.insert(
+            "display",
+            Arc::new(Mutex::new(Chip8Display::from_config(
+                rom_manager.clone(),
+                Chip8DisplayConfig {
+                    kind: Chip8Kind::Chip8,
+                    quirk_sprite_wrapping: false,
+                },
+            ))),
+        );
+        queryable_components.insert(
+            "timer",
+            Arc::new(Mutex::new(Chip8Timer::from_config(rom_manager.clone(), ()))),
+        );
+        queryable_components.insert(
+            "audio",
+            Arc::new(Mutex::new(Chip8Audio::from_config(
+                rom_manager.clone(),
+                Chip8AudioConfig::default(),
+            ))),
+        );
+        processor
+            .lock()
+            .unwrap()
+            .query_components(&queryable_components);
+
+        let mut memory_translation_table = MemoryTranslationTable::default();
+        memory_translation_table.insert(
+            0x200..0x1000,
+            Arc::new(Mutex::new(PlainMemory::from_config(
+                rom_manager,
+                PlainMemoryConfig {
+                    max_word_size: 2,
+                    assigned_range: 0x200..0x1000,
+                    initial_contents: PlainMemoryInitialContents::Value { value: 0x60 },
+                    ..Default::default()
+                },
+            ))),
+        );
+
+        let mut task = ProcessorTask::new(
+            processor,
+            ProcessorTaskConfig {
+                initial_program_pointer: 0x200,
+            },
+        );
+        let debug_state = task.debug_state();
+        debug_state.lock().unwrap().breakpoints.insert(0x204);
+
+        task.tick(10, &memory_translation_table);
+        assert_eq!(debug_state.lock().unwrap().breakpoint_hit, Some(0x204));
+        assert_eq!(debug_state.lock().unwrap().program_pointer, 0x204);
+
+        // Stays stopped until resumed
+        task.tick(10, &memory_translation_table);
+        assert_eq!(debug_state.lock().unwrap().program_pointer, 0x204);
+
+        debug_state.lock().unwrap().resume();
+        task.tick(10, &memory_translation_table);
+        assert_eq!(debug_state.lock().unwrap().breakpoint_hit, None);
+        assert_eq!(debug_state.lock().unwrap().program_pointer, 0x218);
+
+        // Replaying runs straight through
+        task.load(
+            rmpv::ext::to_value(TaskState {
+                program_pointer: 0x200,
+            })
+            .unwrap(),
+        );
+        task.set_breakpoints_enabled(false);
+        task.tick(10, &memory_translation_table);
+        assert_eq!(debug_state.lock().unwrap().program_pointer, 0x214);
+    }
+
+    #[test]
+    fn stops_at_illegal_instructions() {
+        let rom_manager = Arc::new(RomManager::default());
+        let processor = Arc::new(Mutex::new(Chip8Processor::from_config(
+            rom_manager.clone(),
+            Chip8ProcessorConfig {
+                frequency: Ratio::from_integer(700),
+                kind: Chip8Kind::Chip8,
+            },
+        )));
+
+        let mut memory_translation_table = MemoryTranslationTable::default();
+        memory_translation_table.insert(
+            0x200..0x1000,
+            Arc::new(Mutex::new(PlainMemory::from_config(
+                rom_manager,
+                PlainMemoryConfig {
+                    max_word_size: 2,
+                    assigned_range: 0x200..0x1000,
+                    // 0xffff isn't anything
+                    initial_contents: PlainMemoryInitialContents::Value { value: 0xff },
+                    ..Default::default()
+                },
+            ))),
+        );
+
+        let mut task = ProcessorTask::new(
+            processor,
+            ProcessorTaskConfig {
+                initial_program_pointer: 0x200,
+            },
+        );
+        let debug_state = task.debug_state();
+
+        task.tick(10, &memory_translation_table);
+        assert!(debug_state.lock().unwrap().is_stopped());
+        assert_eq!(
+            debug_state.lock().unwrap().fault.as_ref().unwrap().address,
+            0x200
+        );
+        assert_eq!(debug_state.lock().unwrap().program_pointer, 0x200);
+    }
+}
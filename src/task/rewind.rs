@@ -0,0 +1,29 @@
+use super::Task;
+use crate::{component::memory::MemoryTranslationTable, rewind::RewindBuffer};
+use std::sync::{Arc, Mutex};
+
+/// Captures a rewind point every time it is scheduled, the executor fills in the task state afterwards
+///
+/// This wraps no component, so the machine inserts it on its own rather than through a component builder
+pub struct RewindTask {
+    rewind_buffer: Arc<Mutex<RewindBuffer>>,
+}
+
+impl RewindTask {
+    pub fn new(rewind_buffer: Arc<Mutex<RewindBuffer>>) -> Self {
+        Self { rewind_buffer }
+    }
+}
+
+impl Task for RewindTask {
+    fn tick(&mut self, _batch_size: u32, _memory_translation_table: &MemoryTranslationTable) {
+        // Points inside a batch would all be the same moment, so one is enough
+        self.rewind_buffer.lock().unwrap().capture();
+    }
+
+    fn save(&mut self) -> rmpv::Value {
+        rmpv::Value::Nil
+    }
+
+    fn load(&mut self, _state: rmpv::Value) {}
+}